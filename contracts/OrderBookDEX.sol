@@ -2,14 +2,20 @@
 pragma solidity ^0.8.19;
 
 import "@openzeppelin/contracts/token/ERC20/IERC20.sol";
+import "@openzeppelin/contracts/token/ERC20/extensions/IERC20Permit.sol";
+import "@openzeppelin/contracts/token/ERC20/utils/SafeERC20.sol";
 import "@openzeppelin/contracts/utils/ReentrancyGuard.sol";
 import "@openzeppelin/contracts/access/Ownable.sol";
+import "@openzeppelin/contracts/utils/Pausable.sol";
+import "@openzeppelin/contracts/utils/cryptography/EIP712.sol";
+import "@openzeppelin/contracts/utils/cryptography/ECDSA.sol";
 /**
  * @title OrderBookDEX
  * @dev High-performance on-chain order book DEX for Monad
  * Features: Limit orders, market orders, order matching, liquidity pools
  */
-contract OrderBookDEX is ReentrancyGuard, Ownable {
+contract OrderBookDEX is ReentrancyGuard, Ownable, Pausable, EIP712 {
+    using SafeERC20 for IERC20;
     
     // Structs
     struct Order {
@@ -32,30 +38,81 @@ contract OrderBookDEX is ReentrancyGuard, Ownable {
         uint256 pricePrecision;
     }
     
+    struct SignedOrder {
+        address maker;
+        address baseToken;
+        address quoteToken;
+        uint256 amount;
+        uint256 price;
+        bool isBuy;
+        uint256 nonce;
+        uint256 expiry;
+        bytes signature;
+    }
+    
+    struct Settlement {
+        SignedOrder buy;
+        SignedOrder sell;
+        uint256 amount;
+        uint256 price;
+    }
+    
+    struct BookEntry {
+        uint256 id;
+        uint256 price;
+        uint256 amount;
+        bool isBuy;
+    }
+    
     // State variables
     uint256 private _orderIdCounter;
     mapping(uint256 => Order) public orders;
     mapping(address => mapping(address => TradingPair)) public tradingPairs;
     mapping(address => uint256[]) public userOrders;
     mapping(address => mapping(address => uint256)) public balances;
+    mapping(bytes32 => uint256) public signedOrderFilled;
+    bool private _initialized;
     
     // Events
     event OrderPlaced(uint256 indexed orderId, address indexed trader, address baseToken, address quoteToken, uint256 amount, uint256 price, bool isBuy);
     event OrderMatched(uint256 indexed buyOrderId, uint256 indexed sellOrderId, address baseToken, address quoteToken, uint256 amount, uint256 price);
     event OrderCancelled(uint256 indexed orderId, address indexed trader);
     event TradingPairAdded(address indexed baseToken, address indexed quoteToken, uint256 minOrderSize);
+    event TradingPairUpdated(address indexed baseToken, address indexed quoteToken, bool isActive, uint256 minOrderSize, uint256 pricePrecision);
+    event TradingFeeUpdated(uint256 oldFee, uint256 newFee);
+    event SignedOrderSettled(bytes32 indexed buyHash, bytes32 indexed sellHash, address baseToken, address quoteToken, uint256 amount, uint256 price);
+    event SignedOrderCancelled(bytes32 indexed orderHash, address indexed maker);
     event LiquidityAdded(address indexed provider, address baseToken, address quoteToken, uint256 baseAmount, uint256 quoteAmount);
     event LiquidityRemoved(address indexed provider, address baseToken, address quoteToken, uint256 baseAmount, uint256 quoteAmount);
     
     // Constants
     uint256 public constant FEE_DENOMINATOR = 10000;
-    uint256 public constant TRADING_FEE = 30; // 0.3%
+    uint256 public constant MAX_TRADING_FEE = 100; // 1%
+    uint256 public tradingFee = 30; // 0.3%
     uint256 public constant LIQUIDITY_FEE = 25; // 0.25%
     
     // Native token address
     address public constant NATIVE_TOKEN = address(0);
     
-    constructor() Ownable(msg.sender) {}
+    // EIP-712 type of orders signed off-chain for later settlement
+    bytes32 public constant ORDER_TYPEHASH = keccak256(
+        "Order(address maker,address baseToken,address quoteToken,uint256 amount,uint256 price,bool isBuy,uint256 nonce,uint256 expiry)"
+    );
+    
+    constructor() Ownable(msg.sender) EIP712("OrderBookDEX", "1") {
+        // The implementation behind a proxy is never initialized itself
+        _initialized = true;
+    }
+    
+    /**
+     * @dev Set up state the constructor sets, for deployments behind a proxy
+     */
+    function initialize(address initialOwner) external {
+        require(!_initialized, "Already initialized");
+        _initialized = true;
+        _transferOwnership(initialOwner);
+        tradingFee = 30;
+    }
     
     /**
      * @dev Add a new trading pair
@@ -80,6 +137,63 @@ contract OrderBookDEX is ReentrancyGuard, Ownable {
         emit TradingPairAdded(baseToken, quoteToken, minOrderSize);
     }
     
+    /**
+     * @dev Activate or deactivate a trading pair. Inactive pairs reject new orders;
+     * resting orders can still be cancelled.
+     */
+    function setTradingPairActive(address baseToken, address quoteToken, bool isActive) external onlyOwner {
+        TradingPair storage pair = tradingPairs[baseToken][quoteToken];
+        require(pair.pricePrecision != 0, "Trading pair does not exist");
+        pair.isActive = isActive;
+        
+        emit TradingPairUpdated(baseToken, quoteToken, isActive, pair.minOrderSize, pair.pricePrecision);
+    }
+    
+    /**
+     * @dev Update a trading pair's minimum order size and price precision
+     */
+    function updateTradingPair(
+        address baseToken,
+        address quoteToken,
+        uint256 minOrderSize,
+        uint256 pricePrecision
+    ) external onlyOwner {
+        TradingPair storage pair = tradingPairs[baseToken][quoteToken];
+        require(pair.pricePrecision != 0, "Trading pair does not exist");
+        require(pricePrecision > 0, "Invalid price precision");
+        pair.minOrderSize = minOrderSize;
+        pair.pricePrecision = pricePrecision;
+        
+        emit TradingPairUpdated(baseToken, quoteToken, pair.isActive, minOrderSize, pricePrecision);
+    }
+    
+    /**
+     * @dev Set the fee taken from the base amount of every limit match
+     */
+    function setTradingFee(uint256 newFee) external onlyOwner {
+        require(newFee <= MAX_TRADING_FEE, "Fee too high");
+        emit TradingFeeUpdated(tradingFee, newFee);
+        tradingFee = newFee;
+    }
+    
+    /**
+     * @dev Current trading fee; kept for clients built against the original constant
+     */
+    function TRADING_FEE() external view returns (uint256) {
+        return tradingFee;
+    }
+    
+    /**
+     * @dev Stop all order placement. Cancels and withdrawals keep working.
+     */
+    function pause() external onlyOwner {
+        _pause();
+    }
+    
+    function unpause() external onlyOwner {
+        _unpause();
+    }
+    
     /**
      * @dev Place a limit order
      */
@@ -89,7 +203,44 @@ contract OrderBookDEX is ReentrancyGuard, Ownable {
         uint256 amount,
         uint256 price,
         bool isBuy
-    ) external payable nonReentrant returns (uint256 orderId) {
+    ) external payable nonReentrant whenNotPaused returns (uint256 orderId) {
+        return _placeLimitOrder(baseToken, quoteToken, amount, price, isBuy);
+    }
+    
+    /**
+     * @dev Place a limit order, approving the DEX with an EIP-2612 permit for the
+     * token being deposited (quote for buys, base for sells) in the same transaction
+     */
+    function placeLimitOrderWithPermit(
+        address baseToken,
+        address quoteToken,
+        uint256 amount,
+        uint256 price,
+        bool isBuy,
+        uint256 permitValue,
+        uint256 deadline,
+        uint8 v,
+        bytes32 r,
+        bytes32 s
+    ) external nonReentrant whenNotPaused returns (uint256 orderId) {
+        address token = isBuy ? quoteToken : baseToken;
+        require(token != NATIVE_TOKEN, "Native token needs no permit");
+        
+        // A permit front-run by someone else still leaves the allowance in place
+        try IERC20Permit(token).permit(msg.sender, address(this), permitValue, deadline, v, r, s) {} catch {
+            require(IERC20(token).allowance(msg.sender, address(this)) >= permitValue, "Permit failed");
+        }
+        
+        return _placeLimitOrder(baseToken, quoteToken, amount, price, isBuy);
+    }
+    
+    function _placeLimitOrder(
+        address baseToken,
+        address quoteToken,
+        uint256 amount,
+        uint256 price,
+        bool isBuy
+    ) internal returns (uint256 orderId) {
         TradingPair memory pair = tradingPairs[baseToken][quoteToken];
         require(pair.isActive, "Trading pair not active");
         require(amount >= pair.minOrderSize, "Order size too small");
@@ -152,7 +303,7 @@ contract OrderBookDEX is ReentrancyGuard, Ownable {
         address quoteToken,
         uint256 amount,
         bool isBuy
-    ) external payable nonReentrant {
+    ) external payable nonReentrant whenNotPaused {
         TradingPair memory pair = tradingPairs[baseToken][quoteToken];
         require(pair.isActive, "Trading pair not active");
         require(amount >= pair.minOrderSize, "Order size too small");
@@ -205,6 +356,94 @@ contract OrderBookDEX is ReentrancyGuard, Ownable {
         emit OrderCancelled(orderId, msg.sender);
     }
     
+    /**
+     * @dev EIP-712 digest a maker signs for an off-chain order
+     */
+    function hashOrder(
+        address maker,
+        address baseToken,
+        address quoteToken,
+        uint256 amount,
+        uint256 price,
+        bool isBuy,
+        uint256 nonce,
+        uint256 expiry
+    ) public view returns (bytes32) {
+        return _hashTypedDataV4(keccak256(abi.encode(
+            ORDER_TYPEHASH, maker, baseToken, quoteToken, amount, price, isBuy, nonce, expiry
+        )));
+    }
+    
+    /**
+     * @dev Settle matches between orders signed off-chain, in one transaction. Both makers
+     * must have approved the DEX; proceeds are credited to their balances like on-chain matches.
+     */
+    function settleSignedOrders(Settlement[] calldata settlements) external nonReentrant whenNotPaused {
+        for (uint256 i = 0; i < settlements.length; i++) {
+            _settle(settlements[i]);
+        }
+    }
+    
+    /**
+     * @dev Stop a signed order from being settled any further
+     */
+    function cancelSignedOrder(SignedOrder calldata order) external {
+        require(order.maker == msg.sender, "Not order maker");
+        bytes32 orderHash = _hashSignedOrder(order);
+        signedOrderFilled[orderHash] = order.amount;
+        emit SignedOrderCancelled(orderHash, msg.sender);
+    }
+    
+    function _hashSignedOrder(SignedOrder calldata order) internal view returns (bytes32) {
+        return hashOrder(
+            order.maker,
+            order.baseToken,
+            order.quoteToken,
+            order.amount,
+            order.price,
+            order.isBuy,
+            order.nonce,
+            order.expiry
+        );
+    }
+    
+    function _verifySignedOrder(SignedOrder calldata order, uint256 amount) internal returns (bytes32 orderHash) {
+        require(block.timestamp < order.expiry, "Order expired");
+        orderHash = _hashSignedOrder(order);
+        require(ECDSA.recover(orderHash, order.signature) == order.maker, "Invalid signature");
+        require(signedOrderFilled[orderHash] + amount <= order.amount, "Order overfilled");
+        signedOrderFilled[orderHash] += amount;
+    }
+    
+    function _settle(Settlement calldata settlement) internal {
+        SignedOrder calldata buy = settlement.buy;
+        SignedOrder calldata sell = settlement.sell;
+        require(buy.isBuy && !sell.isBuy, "Invalid order types");
+        require(buy.baseToken == sell.baseToken && buy.quoteToken == sell.quoteToken, "Pair mismatch");
+        require(buy.baseToken != NATIVE_TOKEN && buy.quoteToken != NATIVE_TOKEN, "Native token not supported");
+        require(settlement.price <= buy.price && settlement.price >= sell.price, "Price outside limits");
+        require(settlement.amount > 0, "Amount must be greater than 0");
+        
+        TradingPair memory pair = tradingPairs[buy.baseToken][buy.quoteToken];
+        require(pair.isActive, "Trading pair not active");
+        
+        bytes32 buyHash = _verifySignedOrder(buy, settlement.amount);
+        bytes32 sellHash = _verifySignedOrder(sell, settlement.amount);
+        
+        uint256 quoteAmount = (settlement.amount * settlement.price) / pair.pricePrecision;
+        require(quoteAmount > 0, "Quote amount too small");
+        // Makers' tokens are pulled without them present, so a token that returns false
+        // instead of reverting must not credit balances for nothing
+        IERC20(buy.quoteToken).safeTransferFrom(buy.maker, address(this), quoteAmount);
+        IERC20(buy.baseToken).safeTransferFrom(sell.maker, address(this), settlement.amount);
+        
+        uint256 fee = (settlement.amount * tradingFee) / FEE_DENOMINATOR;
+        balances[buy.maker][buy.baseToken] += settlement.amount - fee;
+        balances[sell.maker][buy.quoteToken] += quoteAmount;
+        
+        emit SignedOrderSettled(buyHash, sellHash, buy.baseToken, buy.quoteToken, settlement.amount, settlement.price);
+    }
+    
     /**
      * @dev Get order book for a trading pair
      */
@@ -256,6 +495,47 @@ contract OrderBookDEX is ReentrancyGuard, Ownable {
         }
     }
     
+    /**
+     * @dev Get one page of the order book for a trading pair: active orders with IDs from
+     * `startId`, scanning at most `maxScan` IDs and returning at most `maxOrders` orders
+     * priced within [minPrice, maxPrice] (a `maxPrice` of 0 means no upper bound).
+     * `nextId` is where the next page starts, or 0 once every order has been scanned.
+     */
+    function getOrderBookPage(
+        address baseToken,
+        address quoteToken,
+        uint256 startId,
+        uint256 maxScan,
+        uint256 maxOrders,
+        uint256 minPrice,
+        uint256 maxPrice
+    ) external view returns (BookEntry[] memory entries, uint256 nextId) {
+        uint256 end = startId + maxScan;
+        if (end > _orderIdCounter || end < startId) end = _orderIdCounter;
+        
+        BookEntry[] memory found = new BookEntry[](maxOrders);
+        uint256 count = 0;
+        uint256 i = startId;
+        for (; i < end && count < maxOrders; i++) {
+            Order storage order = orders[i];
+            if (order.isActive && 
+                order.baseToken == baseToken && 
+                order.quoteToken == quoteToken &&
+                order.price >= minPrice &&
+                (maxPrice == 0 || order.price <= maxPrice)) {
+                found[count] = BookEntry(order.id, order.price, order.amount, order.isBuy);
+                count++;
+            }
+        }
+        
+        // Copy into an array of the exact length
+        entries = new BookEntry[](count);
+        for (uint256 j = 0; j < count; j++) {
+            entries[j] = found[j];
+        }
+        nextId = i < _orderIdCounter ? i : 0;
+    }
+    
     /**
      * @dev Get user's active orders
      */
@@ -329,8 +609,8 @@ contract OrderBookDEX is ReentrancyGuard, Ownable {
         uint256 matchPrice = (buyOrder.price + sellOrder.price) / 2; // Average price
         
         // Calculate fees
-        uint256 tradingFee = (matchAmount * TRADING_FEE) / FEE_DENOMINATOR;
-        uint256 baseAmount = matchAmount - tradingFee;
+        uint256 fee = (matchAmount * tradingFee) / FEE_DENOMINATOR;
+        uint256 baseAmount = matchAmount - fee;
         uint256 quoteAmount = (baseAmount * matchPrice) / tradingPairs[buyOrder.baseToken][buyOrder.quoteToken].pricePrecision;
         
         // Transfer tokens between traders
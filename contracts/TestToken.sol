@@ -2,9 +2,10 @@
 pragma solidity ^0.8.20;
 
 import "@openzeppelin/contracts/token/ERC20/ERC20.sol";
+import "@openzeppelin/contracts/token/ERC20/extensions/ERC20Permit.sol";
 import "@openzeppelin/contracts/access/Ownable.sol";
 
-contract TestToken is ERC20, Ownable {
+contract TestToken is ERC20, ERC20Permit, Ownable {
     uint8 private _decimals;
 
     constructor(
@@ -12,7 +13,7 @@ contract TestToken is ERC20, Ownable {
         string memory symbol,
         uint8 decimals_,
         uint256 initialSupply
-    ) ERC20(name, symbol) Ownable(msg.sender) {
+    ) ERC20(name, symbol) ERC20Permit(name) Ownable(msg.sender) {
         _decimals = decimals_;
         _mint(msg.sender, initialSupply * 10**decimals_);
     }
@@ -0,0 +1,224 @@
+use crate::bindings::{OrderBookDEX, ERC20};
+use crate::error::{DexError, Result};
+use crate::indexer::{EventStore, DEFAULT_CHUNK_SIZE};
+use crate::notify::{Alert, NotifyConfig};
+use crate::strategy::{to_f64, BookView};
+use anyhow::Context;
+use ethers::{
+    abi::Abi,
+    providers::{JsonRpcClient, Middleware, Provider},
+    types::{Address, U256},
+};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::sync::Arc;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Direction {
+    Above,
+    Below,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BalanceSource {
+    Wallet,
+    Dex,
+}
+
+/// An alert condition from the alerts config
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum Rule {
+    /// Mid price moves above or below a level
+    PriceCross {
+        base_token: Address,
+        quote_token: Address,
+        level: f64,
+        direction: Direction,
+    },
+    /// Best bid/ask spread wider than `max_bps` of mid, or one side empty
+    Spread {
+        base_token: Address,
+        quote_token: Address,
+        max_bps: f64,
+    },
+    /// Any order of `user` matched
+    OrderFilled { user: Address },
+    /// Token balance of `user` under `threshold`
+    BalanceBelow {
+        user: Address,
+        token: Address,
+        threshold: u64,
+        #[serde(default = "default_balance_source")]
+        source: BalanceSource,
+    },
+}
+
+fn default_balance_source() -> BalanceSource {
+    BalanceSource::Dex
+}
+
+impl Rule {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Rule::PriceCross { .. } => "price_cross",
+            Rule::Spread { .. } => "spread",
+            Rule::OrderFilled { .. } => "order_filled",
+            Rule::BalanceBelow { .. } => "balance_below",
+        }
+    }
+}
+
+/// Alerts config file (TOML)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AlertsConfig {
+    /// Seconds between checks
+    #[serde(default = "default_interval_secs")]
+    pub interval_secs: u64,
+    pub notify: NotifyConfig,
+    pub rules: Vec<Rule>,
+}
+
+fn default_interval_secs() -> u64 {
+    15
+}
+
+impl AlertsConfig {
+    pub fn load(path: &str) -> Result<Self> {
+        let content = fs::read_to_string(path).with_context(|| DexError::Config(format!("Failed to read {}", path)))?;
+        Ok(toml::from_str(&content).with_context(|| DexError::Config(format!("Invalid alerts config {}", path)))?)
+    }
+}
+
+/// Evaluates rules on every check. Level rules fire once when their condition becomes
+/// true and re-arm when it clears, so a wide spread doesn't alert on every poll.
+pub struct AlertEngine {
+    rules: Vec<Rule>,
+    active: Vec<bool>,
+    store: EventStore,
+    /// Trades already inspected for fill alerts
+    seen_trades: usize,
+}
+
+impl AlertEngine {
+    /// Start watching `dex`. A new event index starts at the current block, so fill
+    /// alerts for orders placed earlier need `dex index` to have run first.
+    pub async fn new<P: JsonRpcClient>(
+        provider: &Provider<P>,
+        dex: Address,
+        dex_abi: &Abi,
+        rules: Vec<Rule>,
+    ) -> Result<Self> {
+        let head = provider.get_block_number().await?.as_u64();
+        let mut store = EventStore::load(dex, head + 1)?;
+        store.sync(provider, dex_abi, DEFAULT_CHUNK_SIZE).await?;
+        // Only fills after startup are news
+        let seen_trades = store.trades.len();
+        Ok(Self {
+            active: vec![false; rules.len()],
+            rules,
+            store,
+            seen_trades,
+        })
+    }
+
+    pub async fn check<P: JsonRpcClient + 'static>(
+        &mut self,
+        provider: Arc<Provider<P>>,
+        dex_abi: &Abi,
+    ) -> Result<Vec<Alert>> {
+        self.store.sync(provider.as_ref(), dex_abi, DEFAULT_CHUNK_SIZE).await?;
+        let dex = OrderBookDEX::new(self.store.dex, provider.clone());
+
+        let mut alerts = Vec::new();
+        for index in 0..self.rules.len() {
+            let rule = self.rules[index].clone();
+            match &rule {
+                Rule::OrderFilled { user } => {
+                    for trade in &self.store.trades[self.seen_trades..] {
+                        let owner = |id| self.store.order(id).map(|order| order.trader);
+                        let is_buy = if owner(trade.buy_order_id) == Some(*user) {
+                            true
+                        } else if owner(trade.sell_order_id) == Some(*user) {
+                            false
+                        } else {
+                            continue;
+                        };
+                        let order_id = if is_buy { trade.buy_order_id } else { trade.sell_order_id };
+                        alerts.push(Alert::new(
+                            rule.name(),
+                            format!(
+                                "Order {} of {:?} filled: {} {} at price {} (tx {:?})",
+                                order_id, user, if is_buy { "BUY" } else { "SELL" }, trade.amount, trade.price, trade.tx_hash
+                            ),
+                        ));
+                    }
+                }
+                _ => {
+                    let message = self.evaluate(&rule, &dex, provider.as_ref()).await?;
+                    match message {
+                        Some(message) if !self.active[index] => {
+                            self.active[index] = true;
+                            alerts.push(Alert::new(rule.name(), message));
+                        }
+                        Some(_) => {}
+                        None => self.active[index] = false,
+                    }
+                }
+            }
+        }
+        self.seen_trades = self.store.trades.len();
+        Ok(alerts)
+    }
+
+    /// Message if a level rule's condition currently holds
+    async fn evaluate<M: Middleware + 'static>(
+        &self,
+        rule: &Rule,
+        dex: &OrderBookDEX<M>,
+        provider: &M,
+    ) -> Result<Option<String>> {
+        Ok(match rule {
+            Rule::PriceCross { base_token, quote_token, level, direction } => {
+                let book = fetch_book(dex, *base_token, *quote_token).await?;
+                book.mid().and_then(|mid| {
+                    let crossed = match direction {
+                        Direction::Above => mid > *level,
+                        Direction::Below => mid < *level,
+                    };
+                    crossed.then(|| format!("{:?} / {:?} mid {} is {:?} {}", base_token, quote_token, mid, direction, level))
+                })
+            }
+            Rule::Spread { base_token, quote_token, max_bps } => {
+                let book = fetch_book(dex, *base_token, *quote_token).await?;
+                match (book.best_bid(), book.best_ask(), book.mid()) {
+                    (Some(bid), Some(ask), Some(mid)) => {
+                        let spread_bps = (to_f64(ask) - to_f64(bid)) / mid * 10_000.0;
+                        (spread_bps > *max_bps).then(|| {
+                            format!("{:?} / {:?} spread {:.1} bps exceeds {} bps", base_token, quote_token, spread_bps, max_bps)
+                        })
+                    }
+                    _ => Some(format!("{:?} / {:?} has an empty side", base_token, quote_token)),
+                }
+            }
+            Rule::BalanceBelow { user, token, threshold, source } => {
+                let balance = match source {
+                    BalanceSource::Dex => dex.get_user_balance(*user, *token).call().await?,
+                    BalanceSource::Wallet if token.is_zero() => provider.get_balance(*user, None).await.map_err(|e| DexError::middleware(&e))?,
+                    BalanceSource::Wallet => ERC20::new(*token, dex.client()).balance_of(*user).call().await?,
+                };
+                (balance < U256::from(*threshold)).then(|| {
+                    format!("{:?} {:?} balance of {:?} is {} (below {})", user, source, token, balance, threshold)
+                })
+            }
+            Rule::OrderFilled { .. } => None,
+        })
+    }
+}
+
+async fn fetch_book<M: Middleware + 'static>(dex: &OrderBookDEX<M>, base_token: Address, quote_token: Address) -> Result<BookView> {
+    let (buy_prices, buy_amounts, sell_prices, sell_amounts) = dex.get_order_book(base_token, quote_token).call().await?;
+    Ok(BookView::from_levels(&buy_prices, &buy_amounts, &sell_prices, &sell_amounts))
+}
@@ -0,0 +1,100 @@
+use crate::deployments;
+use crate::error::{DexError, Result};
+use anyhow::Context;
+use ethers::types::Address;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+
+const ADDRESS_BOOK_PATH: &str = "config/aliases.json";
+
+/// Named addresses usable anywhere a command takes an address
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct AddressBook {
+    pub aliases: BTreeMap<String, Address>,
+}
+
+impl AddressBook {
+    pub fn load() -> Result<Self> {
+        if !Path::new(ADDRESS_BOOK_PATH).exists() {
+            return Ok(Self::default());
+        }
+        let content = fs::read_to_string(ADDRESS_BOOK_PATH)
+            .with_context(|| format!("Failed to read {}", ADDRESS_BOOK_PATH))?;
+        Ok(serde_json::from_str(&content)?)
+    }
+
+    pub fn save(&self) -> Result<()> {
+        fs::create_dir_all("config")?;
+        fs::write(ADDRESS_BOOK_PATH, serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+
+    /// Register `name` for `address`. Names are case-insensitive, must not look like an
+    /// address, and may only be reassigned with `overwrite`.
+    pub fn add(&mut self, name: &str, address: Address, overwrite: bool) -> Result<()> {
+        let name = name.to_lowercase();
+        if name.is_empty() || !name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-') {
+            return Err(DexError::Input(format!("Alias '{}' may only contain letters, digits, '_' and '-'", name)));
+        }
+        if name.starts_with("0x") || name.parse::<Address>().is_ok() {
+            return Err(DexError::Input(format!("Alias '{}' could be mistaken for an address", name)));
+        }
+        if let Some(existing) = self.aliases.get(&name) {
+            if *existing != address && !overwrite {
+                return Err(DexError::Input(format!("Alias '{}' already points to {:?} (use --force to replace it)", name, existing)));
+            }
+        }
+        if let Some(other) = self.name_of(address).filter(|other| *other != name) {
+            return Err(DexError::Input(format!("{:?} is already registered as '{}'", address, other)));
+        }
+        self.aliases.insert(name, address);
+        Ok(())
+    }
+
+    pub fn remove(&mut self, name: &str) -> Result<Address> {
+        self.aliases
+            .remove(&name.to_lowercase())
+            .ok_or_else(|| DexError::Input(format!("Unknown alias '{}'", name)))
+    }
+
+    /// Alias registered for `address`, if any
+    pub fn name_of(&self, address: Address) -> Option<&str> {
+        self.aliases
+            .iter()
+            .find(|(_, a)| **a == address)
+            .map(|(name, _)| name.as_str())
+    }
+
+    /// Parse a hex address or look up an alias
+    pub fn resolve(&self, value: &str) -> Result<Address> {
+        let value = value.trim();
+        if let Ok(address) = value.parse::<Address>() {
+            return Ok(address);
+        }
+        self.aliases
+            .get(&value.to_lowercase())
+            .copied()
+            .ok_or_else(|| DexError::Input(format!("'{}' is neither an address nor a known alias", value)))
+    }
+}
+
+/// Resolve an address argument against the saved address book, then against the names
+/// of recorded deployments (e.g. `OrderBookDEX` for the latest DEX deployment)
+pub fn resolve(value: &str) -> Result<Address> {
+    if let Ok(address) = value.trim().parse::<Address>() {
+        return Ok(address);
+    }
+    let book = AddressBook::load()?;
+    if let Ok(address) = book.resolve(value) {
+        return Ok(address);
+    }
+    deployments::resolve(value.trim())?.ok_or_else(|| {
+        DexError::Input(format!(
+            "'{}' is neither an address, a known alias nor a contract deployed on {}",
+            value,
+            crate::rpc::network_name()
+        ))
+    })
+}
@@ -0,0 +1,224 @@
+use crate::indexer::EventStore;
+use crate::matching::FEE_DENOMINATOR;
+use crate::strategy::to_f64;
+use ethers::types::{Address, U256};
+use serde::Serialize;
+use std::collections::BTreeMap;
+
+/// Volume-weighted average price of indexed trades over a block window
+#[derive(Debug, Clone, Serialize)]
+pub struct Vwap {
+    pub from_block: u64,
+    pub to_block: u64,
+    pub trades: usize,
+    /// Matched base amount, fees included
+    pub base_volume: f64,
+    pub quote_volume: f64,
+    pub vwap: f64,
+}
+
+/// Resting liquidity within `bps` of the mid price
+#[derive(Debug, Clone, Serialize)]
+pub struct DepthBand {
+    pub bps: u64,
+    pub bid_depth: f64,
+    pub ask_depth: f64,
+}
+
+/// A price level holding an outsized share of one side of the book
+#[derive(Debug, Clone, Serialize)]
+pub struct Wall {
+    pub is_buy: bool,
+    pub price: f64,
+    pub amount: f64,
+    pub orders: usize,
+    /// Distance from the mid price
+    pub distance_bps: Option<f64>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct LiquidityReport {
+    pub best_bid: Option<f64>,
+    pub best_ask: Option<f64>,
+    pub mid_price: Option<f64>,
+    pub spread_bps: Option<f64>,
+    pub depth: Vec<DepthBand>,
+    pub walls: Vec<Wall>,
+}
+
+/// Rolling ticker statistics of one pair, shaped like a typical exchange 24h ticker.
+/// Prices are in the contract's raw units.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Ticker {
+    pub base_token: Address,
+    pub quote_token: Address,
+    pub open_price: Option<f64>,
+    pub high_price: Option<f64>,
+    pub low_price: Option<f64>,
+    pub last_price: Option<f64>,
+    pub price_change: Option<f64>,
+    pub price_change_percent: Option<f64>,
+    /// Matched base amount, fees included
+    pub volume: f64,
+    pub quote_volume: f64,
+    pub count: usize,
+    pub first_block: u64,
+    pub last_block: u64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct AnalyticsReport {
+    pub base_token: Address,
+    pub quote_token: Address,
+    pub vwap: Option<Vwap>,
+    pub liquidity: LiquidityReport,
+}
+
+/// VWAP of the pair's trades in the last `window_blocks` indexed blocks (all history when `None`).
+/// Prices stay in the contract's raw units; quote volume is scaled by `price_precision`.
+pub fn vwap(
+    store: &EventStore,
+    base_token: Address,
+    quote_token: Address,
+    price_precision: U256,
+    window_blocks: Option<u64>,
+) -> Option<Vwap> {
+    let from_block = window_blocks.map_or(0, |window| store.last_block.saturating_sub(window.saturating_sub(1)));
+    let trades: Vec<_> = store
+        .trades
+        .iter()
+        .filter(|t| t.base_token == base_token && t.quote_token == quote_token && t.block >= from_block)
+        .collect();
+
+    let base_volume: f64 = trades.iter().map(|t| to_f64(t.matched())).sum();
+    if base_volume == 0.0 {
+        return None;
+    }
+    let notional: f64 = trades.iter().map(|t| to_f64(t.matched()) * to_f64(t.price)).sum();
+
+    Some(Vwap {
+        from_block,
+        to_block: store.last_block,
+        trades: trades.len(),
+        base_volume,
+        quote_volume: notional / to_f64(price_precision).max(1.0),
+        vwap: notional / base_volume,
+    })
+}
+
+/// Ticker statistics of the pair's trades from `from_block` to the end of the index
+pub fn ticker(
+    store: &EventStore,
+    base_token: Address,
+    quote_token: Address,
+    price_precision: U256,
+    from_block: u64,
+) -> Ticker {
+    let trades: Vec<_> = store
+        .trades
+        .iter()
+        .filter(|t| t.base_token == base_token && t.quote_token == quote_token && t.block >= from_block)
+        .collect();
+    let prices: Vec<f64> = trades.iter().map(|t| to_f64(t.price)).collect();
+
+    let open_price = prices.first().copied();
+    let last_price = prices.last().copied();
+    let price_change = open_price.zip(last_price).map(|(open, last)| last - open);
+    let notional: f64 = trades.iter().map(|t| to_f64(t.matched()) * to_f64(t.price)).sum();
+
+    Ticker {
+        base_token,
+        quote_token,
+        open_price,
+        high_price: prices.iter().copied().reduce(f64::max),
+        low_price: prices.iter().copied().reduce(f64::min),
+        last_price,
+        price_change,
+        price_change_percent: open_price
+            .zip(price_change)
+            .filter(|(open, _)| *open > 0.0)
+            .map(|(open, change)| change / open * 100.0),
+        volume: trades.iter().map(|t| to_f64(t.matched())).sum(),
+        quote_volume: notional / to_f64(price_precision).max(1.0),
+        count: trades.len(),
+        first_block: from_block,
+        last_block: store.last_block,
+    }
+}
+
+/// Depth bands and the `walls` largest price levels of a book snapshot, as returned by `getOrderBook`
+pub fn liquidity(
+    buy_prices: &[U256],
+    buy_amounts: &[U256],
+    sell_prices: &[U256],
+    sell_amounts: &[U256],
+    bands: &[u64],
+    walls: usize,
+) -> LiquidityReport {
+    let bids = levels(buy_prices, buy_amounts);
+    let asks = levels(sell_prices, sell_amounts);
+    let best_bid = bids.keys().next_back().map(|&p| to_f64(p));
+    let best_ask = asks.keys().next().map(|&p| to_f64(p));
+    let mid_price = match (best_bid, best_ask) {
+        (Some(bid), Some(ask)) => Some((bid + ask) / 2.0),
+        (bid, ask) => bid.or(ask),
+    };
+    let spread_bps = match (best_bid, best_ask, mid_price) {
+        (Some(bid), Some(ask), Some(mid)) if mid > 0.0 => Some((ask - bid) / mid * FEE_DENOMINATOR as f64),
+        _ => None,
+    };
+
+    let depth = bands
+        .iter()
+        .map(|&bps| {
+            let (bid_depth, ask_depth) = match mid_price {
+                Some(mid) => {
+                    let offset = mid * bps as f64 / FEE_DENOMINATOR as f64;
+                    (
+                        bids.iter().filter(|(p, _)| to_f64(**p) >= mid - offset).map(|(_, l)| l.0).sum(),
+                        asks.iter().filter(|(p, _)| to_f64(**p) <= mid + offset).map(|(_, l)| l.0).sum(),
+                    )
+                }
+                None => (0.0, 0.0),
+            };
+            DepthBand { bps, bid_depth, ask_depth }
+        })
+        .collect();
+
+    let mut all: Vec<Wall> = bids
+        .iter()
+        .map(|(p, l)| (true, p, l))
+        .chain(asks.iter().map(|(p, l)| (false, p, l)))
+        .map(|(is_buy, price, (amount, orders))| {
+            let price = to_f64(*price);
+            Wall {
+                is_buy,
+                price,
+                amount: *amount,
+                orders: *orders,
+                distance_bps: mid_price
+                    .filter(|mid| *mid > 0.0)
+                    .map(|mid| (price - mid).abs() / mid * FEE_DENOMINATOR as f64),
+            }
+        })
+        .collect();
+    all.sort_by(|a, b| b.amount.total_cmp(&a.amount));
+    all.truncate(walls);
+
+    LiquidityReport { best_bid, best_ask, mid_price, spread_bps, depth, walls: all }
+}
+
+/// Resting amount and order count per price
+fn levels(prices: &[U256], amounts: &[U256]) -> BTreeMap<U256, (f64, usize)> {
+    let mut levels = BTreeMap::new();
+    for (price, amount) in prices.iter().zip(amounts) {
+        if amount.is_zero() {
+            continue;
+        }
+        let level = levels.entry(*price).or_insert((0.0, 0));
+        level.0 += to_f64(*amount);
+        level.1 += 1;
+    }
+    levels
+}
@@ -0,0 +1,214 @@
+use crate::bindings::OrderBookDEX;
+use crate::error::Result;
+use crate::paper::PairMarket;
+use anyhow::Context;
+use ethers::{
+    providers::Middleware,
+    types::{Address, U256},
+};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+
+/// A pair the scanner watches
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PairConfig {
+    pub base_token: Address,
+    pub quote_token: Address,
+}
+
+/// Arbitrage scanner config file (TOML)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ArbConfig {
+    pub pairs: Vec<PairConfig>,
+    /// Extra token cycles to check, e.g. [MON, USDC, WETH] for MON -> USDC -> WETH -> MON.
+    /// Every pair is always checked as a two-token cycle starting in its quote token.
+    #[serde(default)]
+    pub routes: Vec<Vec<Address>>,
+    /// Amount of the route's first token to trade through each route
+    pub trade_size: u64,
+    /// Largest amount auto-execution may commit to a single route
+    #[serde(default)]
+    pub max_size: Option<u64>,
+    /// Minimum profit after gas, in basis points of the input
+    #[serde(default)]
+    pub min_profit_bps: f64,
+    /// Gas budgeted per transaction when netting out costs
+    #[serde(default = "default_gas_per_tx")]
+    pub gas_per_tx: u64,
+    /// Seconds between scans
+    #[serde(default = "default_interval_secs")]
+    pub interval_secs: u64,
+}
+
+fn default_gas_per_tx() -> u64 {
+    300_000
+}
+
+fn default_interval_secs() -> u64 {
+    10
+}
+
+impl ArbConfig {
+    pub fn load(path: &str) -> Result<Self> {
+        let content = fs::read_to_string(path).with_context(|| format!("Failed to read {}", path))?;
+        Ok(toml::from_str(&content).with_context(|| format!("Invalid arb config {}", path))?)
+    }
+
+    /// Two-token cycles for every pair plus the configured routes
+    pub fn all_routes(&self) -> Vec<Vec<Address>> {
+        let mut routes: Vec<Vec<Address>> = self
+            .pairs
+            .iter()
+            .map(|pair| vec![pair.quote_token, pair.base_token])
+            .collect();
+        routes.extend(self.routes.iter().cloned());
+        routes
+    }
+}
+
+/// Live books of the configured pairs, keyed by (base, quote)
+pub type Markets = BTreeMap<(Address, Address), PairMarket>;
+
+pub async fn fetch_markets<M: Middleware + 'static>(contract: &OrderBookDEX<M>, pairs: &[PairConfig]) -> Result<Markets> {
+    let mut markets = Markets::new();
+    for pair in pairs {
+        let market = PairMarket::fetch(contract, pair.base_token, pair.quote_token)
+            .await
+            .with_context(|| format!("Failed to fetch {:?} / {:?}", pair.base_token, pair.quote_token))?;
+        markets.insert((pair.base_token, pair.quote_token), market);
+    }
+    Ok(markets)
+}
+
+/// One market order in a route
+#[derive(Debug, Clone, Serialize)]
+pub struct Leg {
+    pub base_token: Address,
+    pub quote_token: Address,
+    pub is_buy: bool,
+    /// Base amount of the market order
+    pub amount: U256,
+    /// Token spent and received by this leg
+    pub input: U256,
+    pub output: U256,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Opportunity {
+    pub route: Vec<Address>,
+    pub input: U256,
+    pub output: U256,
+    pub legs: Vec<Leg>,
+    /// Gas for every leg plus the withdrawal that frees its proceeds, in wei
+    pub gas_cost: U256,
+    /// Profit in basis points of the input, after gas when the route starts in the native token
+    pub profit_bps: f64,
+    pub profitable: bool,
+}
+
+/// Simulate trading up to `input` of `route[0]` around the cycle with market orders.
+///
+/// Market orders pay no trading fee in the contract but fill in order ID order, so each
+/// leg is simulated against a local copy of the book rather than priced off the best level.
+pub fn evaluate(
+    markets: &Markets,
+    route: &[Address],
+    input: U256,
+    gas_price: U256,
+    gas_per_tx: u64,
+    min_profit_bps: f64,
+) -> Option<Opportunity> {
+    if route.len() < 2 || input.is_zero() {
+        return None;
+    }
+
+    let mut holding = input;
+    let mut legs = Vec::with_capacity(route.len());
+    for (i, from) in route.iter().enumerate() {
+        let to = route[(i + 1) % route.len()];
+        let leg = swap_leg(markets, *from, to, holding)?;
+        holding = leg.output;
+        legs.push(leg);
+    }
+
+    // Quote left over from an initial buy never leaves the wallet
+    let input = legs[0].input;
+    if input.is_zero() {
+        return None;
+    }
+    let gas_cost = gas_price * U256::from(gas_per_tx) * U256::from(legs.len() * 2);
+    let to_f64 = |value: U256| value.to_string().parse::<f64>().unwrap_or(f64::MAX);
+    let mut net = to_f64(holding) - to_f64(input);
+    if route[0] == Address::zero() {
+        net -= to_f64(gas_cost);
+    }
+    let profit_bps = net / to_f64(input) * 10_000.0;
+
+    Some(Opportunity {
+        route: route.to_vec(),
+        input,
+        output: holding,
+        legs,
+        gas_cost,
+        profit_bps,
+        profitable: net > 0.0 && profit_bps >= min_profit_bps,
+    })
+}
+
+/// Simulate a market order turning up to `input` of `from` into `to`: a buy on the
+/// `(to, from)` pair or a sell on the `(from, to)` pair, whichever is in `markets`
+pub fn swap_leg(markets: &Markets, from: Address, to: Address, input: U256) -> Option<Leg> {
+    if let Some(market) = markets.get(&(to, from)) {
+        buy_leg(market, input)
+    } else if let Some(market) = markets.get(&(from, to)) {
+        sell_leg(market, input)
+    } else {
+        None
+    }
+}
+
+/// Largest market buy whose cost fits in `budget` quote
+fn buy_leg(market: &PairMarket, budget: U256) -> Option<Leg> {
+    let cost = |amount: U256| -> Option<U256> {
+        let takes = market.book().place_market(amount, true).ok()?;
+        Some(takes.iter().fold(U256::zero(), |total, take| total + take.quote_amount))
+    };
+
+    let depth = market.sell_amounts.iter().fold(U256::zero(), |total, amount| total + *amount);
+    let (mut low, mut high) = (U256::zero(), depth);
+    while low < high {
+        let mid = low + (high - low + 1) / 2;
+        match cost(mid) {
+            Some(spent) if spent <= budget => low = mid,
+            _ => high = mid - 1,
+        }
+    }
+
+    if low.is_zero() || low < market.min_order_size {
+        return None;
+    }
+    Some(Leg {
+        base_token: market.base_token,
+        quote_token: market.quote_token,
+        is_buy: true,
+        amount: low,
+        input: cost(low)?,
+        output: low,
+    })
+}
+
+fn sell_leg(market: &PairMarket, amount: U256) -> Option<Leg> {
+    if amount.is_zero() || amount < market.min_order_size {
+        return None;
+    }
+    let takes = market.book().place_market(amount, false).ok()?;
+    Some(Leg {
+        base_token: market.base_token,
+        quote_token: market.quote_token,
+        is_buy: false,
+        amount,
+        input: amount,
+        output: takes.iter().fold(U256::zero(), |total, take| total + take.quote_amount),
+    })
+}
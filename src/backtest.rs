@@ -0,0 +1,356 @@
+use crate::indexer::EventStore;
+use crate::matching::{OrderBook, FEE_DENOMINATOR, TRADING_FEE};
+use crate::strategy::{to_f64, BookView, Fill, Intent, OpenOrder, Strategy};
+use ethers::types::{Address, U256};
+use serde::Serialize;
+use std::collections::{BTreeMap, BTreeSet};
+
+/// Equity and inventory after one replayed block
+#[derive(Debug, Clone, Serialize)]
+pub struct CurvePoint {
+    pub block: u64,
+    pub mid_price: Option<f64>,
+    /// Base inventory in raw units
+    pub position: f64,
+    /// Quote cash in raw units
+    pub cash: f64,
+    /// Cash plus inventory marked at mid, in quote units
+    pub pnl: f64,
+}
+
+/// Aggregate fill statistics
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct FillStats {
+    pub fills: usize,
+    pub buys: usize,
+    pub sells: usize,
+    pub maker_fills: usize,
+    pub taker_fills: usize,
+    pub base_volume: f64,
+    pub quote_volume: f64,
+    /// Trading fees paid, in base units
+    pub fees: f64,
+    /// Fill price versus mid when the order was placed; positive is worse than mid
+    pub average_slippage_bps: f64,
+    pub worst_slippage_bps: f64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct BacktestReport {
+    pub strategy: String,
+    pub from_block: u64,
+    pub to_block: u64,
+    pub orders_placed: usize,
+    pub orders_cancelled: usize,
+    pub final_pnl: f64,
+    pub max_drawdown: f64,
+    pub stats: FillStats,
+    pub curve: Vec<CurvePoint>,
+}
+
+#[derive(Debug, Clone)]
+struct SimOrder {
+    order: OpenOrder,
+    /// Mid when the order was placed, for slippage
+    mid_at_placement: Option<f64>,
+}
+
+/// Replays indexed DEX history for one pair through a strategy.
+///
+/// The book is rebuilt from `OrderPlaced`, `OrderMatched` and `OrderCancelled` events;
+/// market orders emit no events, so liquidity they consumed stays on the replayed book.
+/// Strategy orders that cross the book fill immediately under the contract's matching
+/// rules; resting strategy orders fill when a historical trade prints through their price.
+pub struct Backtest<'a> {
+    store: &'a EventStore,
+    base_token: Address,
+    quote_token: Address,
+    price_precision: U256,
+}
+
+impl<'a> Backtest<'a> {
+    pub fn new(store: &'a EventStore, base_token: Address, quote_token: Address, price_precision: U256) -> Self {
+        Self {
+            store,
+            base_token,
+            quote_token,
+            price_precision: if price_precision.is_zero() { U256::one() } else { price_precision },
+        }
+    }
+
+    pub fn run(&self, strategy: &mut dyn Strategy) -> BacktestReport {
+        let in_pair = |base: Address, quote: Address| base == self.base_token && quote == self.quote_token;
+        let pair_orders: BTreeSet<U256> = self
+            .store
+            .orders
+            .iter()
+            .filter(|o| in_pair(o.base_token, o.quote_token))
+            .map(|o| o.order_id)
+            .collect();
+
+        let mut blocks: BTreeSet<u64> = BTreeSet::new();
+        blocks.extend(self.store.orders.iter().filter(|o| pair_orders.contains(&o.order_id)).map(|o| o.block));
+        blocks.extend(self.store.trades.iter().filter(|t| in_pair(t.base_token, t.quote_token)).map(|t| t.block));
+        blocks.extend(self.store.cancellations.iter().filter(|c| pair_orders.contains(&c.order_id)).map(|c| c.block));
+
+        let mut state = SimState::new(self.price_precision);
+        // order ID -> (price, remaining amount, is_buy)
+        let mut live: BTreeMap<U256, (U256, U256, bool)> = BTreeMap::new();
+        // Reactions to immediate fills, handled with the next block
+        let mut deferred: Vec<Intent> = Vec::new();
+
+        for &block in &blocks {
+            for order in self.store.orders.iter().filter(|o| o.block == block && pair_orders.contains(&o.order_id)) {
+                live.insert(order.order_id, (order.price, order.amount, order.is_buy));
+            }
+
+            let trades: Vec<_> = self
+                .store
+                .trades
+                .iter()
+                .filter(|t| t.block == block && in_pair(t.base_token, t.quote_token))
+                .collect();
+            for trade in &trades {
+                // Events carry the post-fee amount; reduce orders by the matched amount
+                let matched = trade.matched();
+                for id in [trade.buy_order_id, trade.sell_order_id] {
+                    if let Some((_, amount, _)) = live.get_mut(&id) {
+                        *amount = amount.saturating_sub(matched);
+                        if amount.is_zero() {
+                            live.remove(&id);
+                        }
+                    }
+                }
+            }
+
+            for cancellation in self.store.cancellations.iter().filter(|c| c.block == block) {
+                live.remove(&cancellation.order_id);
+            }
+
+            let (buy_prices, buy_amounts, sell_prices, sell_amounts) = levels(&live);
+            let mut book = BookView::from_levels(&buy_prices, &buy_amounts, &sell_prices, &sell_amounts);
+            book.block = Some(block);
+            let mid = book.mid();
+
+            let mut intents = std::mem::take(&mut deferred);
+            for trade in &trades {
+                for fill in state.fill_resting(trade.price, trade.amount) {
+                    intents.extend(strategy.on_fill(&fill));
+                }
+            }
+            intents.extend(strategy.on_book_update(&book, &state.open_orders()));
+
+            let local = OrderBook::from_levels(self.price_precision, &buy_prices, &buy_amounts, &sell_prices, &sell_amounts);
+            for intent in intents {
+                match intent {
+                    Intent::Place { price, amount, is_buy } => {
+                        if let Some(fill) = state.place(&local, price, amount, is_buy, mid) {
+                            deferred.extend(strategy.on_fill(&fill));
+                        }
+                    }
+                    Intent::Cancel { order_id } => state.cancel(order_id),
+                }
+            }
+
+            state.record(block, mid);
+        }
+
+        state.report(strategy.name(), &blocks)
+    }
+}
+
+fn levels(live: &BTreeMap<U256, (U256, U256, bool)>) -> (Vec<U256>, Vec<U256>, Vec<U256>, Vec<U256>) {
+    let (mut buy_prices, mut buy_amounts, mut sell_prices, mut sell_amounts) = (Vec::new(), Vec::new(), Vec::new(), Vec::new());
+    for (price, amount, is_buy) in live.values() {
+        if *is_buy {
+            buy_prices.push(*price);
+            buy_amounts.push(*amount);
+        } else {
+            sell_prices.push(*price);
+            sell_amounts.push(*amount);
+        }
+    }
+    (buy_prices, buy_amounts, sell_prices, sell_amounts)
+}
+
+struct SimState {
+    price_precision: f64,
+    next_id: u64,
+    orders: Vec<SimOrder>,
+    position: f64,
+    cash: f64,
+    stats: FillStats,
+    slippage_total: f64,
+    placed: usize,
+    cancelled: usize,
+    curve: Vec<CurvePoint>,
+}
+
+impl SimState {
+    fn new(price_precision: U256) -> Self {
+        Self {
+            price_precision: to_f64(price_precision),
+            next_id: 1,
+            orders: Vec::new(),
+            position: 0.0,
+            cash: 0.0,
+            stats: FillStats::default(),
+            slippage_total: 0.0,
+            placed: 0,
+            cancelled: 0,
+            curve: Vec::new(),
+        }
+    }
+
+    fn open_orders(&self) -> Vec<OpenOrder> {
+        self.orders.iter().map(|sim| sim.order.clone()).collect()
+    }
+
+    /// Place a strategy order, taking liquidity first if it crosses the book
+    fn place(&mut self, book: &OrderBook, price: U256, amount: U256, is_buy: bool, mid: Option<f64>) -> Option<Fill> {
+        let id = self.next_id;
+        self.next_id += 1;
+        self.placed += 1;
+
+        let mut book = book.clone();
+        let (book_id, matched) = book.place_limit(None, amount, price, is_buy);
+        let mut remaining = amount;
+        let mut fill = None;
+        if let Some(matched) = matched.filter(|m| m.buy_order_id == book_id || m.sell_order_id == book_id) {
+            let filled = Fill {
+                order_id: id,
+                is_buy,
+                amount: matched.matched,
+                price: matched.price,
+            };
+            self.apply_fill(&filled, mid, false);
+            remaining -= matched.matched;
+            fill = Some(filled);
+        }
+
+        if !remaining.is_zero() {
+            self.orders.push(SimOrder {
+                order: OpenOrder { id, price, amount: remaining, is_buy },
+                mid_at_placement: mid,
+            });
+        }
+        fill
+    }
+
+    fn cancel(&mut self, order_id: u64) {
+        let before = self.orders.len();
+        self.orders.retain(|sim| sim.order.id != order_id);
+        if self.orders.len() < before {
+            self.cancelled += 1;
+        }
+    }
+
+    /// Fill resting orders a historical trade printed through, oldest first
+    fn fill_resting(&mut self, trade_price: U256, trade_amount: U256) -> Vec<Fill> {
+        let mut available = trade_amount;
+        let mut fills = Vec::new();
+        let mut index = 0;
+        while index < self.orders.len() && !available.is_zero() {
+            let sim = self.orders[index].clone();
+            let crosses = if sim.order.is_buy { trade_price <= sim.order.price } else { trade_price >= sim.order.price };
+            if !crosses {
+                index += 1;
+                continue;
+            }
+
+            let amount = sim.order.amount.min(available);
+            available -= amount;
+            let fill = Fill {
+                order_id: sim.order.id,
+                is_buy: sim.order.is_buy,
+                amount,
+                price: sim.order.price,
+            };
+            self.apply_fill(&fill, sim.mid_at_placement, true);
+            fills.push(fill);
+
+            self.orders[index].order.amount -= amount;
+            if self.orders[index].order.amount.is_zero() {
+                self.orders.remove(index);
+            } else {
+                index += 1;
+            }
+        }
+        fills
+    }
+
+    /// Book a fill at the contract's terms: the buyer pays the fee in base
+    fn apply_fill(&mut self, fill: &Fill, mid: Option<f64>, maker: bool) {
+        let matched = to_f64(fill.amount);
+        let price = to_f64(fill.price);
+        let fee = (matched * TRADING_FEE as f64 / FEE_DENOMINATOR as f64).floor();
+        let net = matched - fee;
+        let quote = net * price / self.price_precision;
+
+        if fill.is_buy {
+            self.position += net;
+            self.cash -= quote;
+            self.stats.buys += 1;
+        } else {
+            self.position -= matched;
+            self.cash += quote;
+            self.stats.sells += 1;
+        }
+
+        self.stats.fills += 1;
+        if maker {
+            self.stats.maker_fills += 1;
+        } else {
+            self.stats.taker_fills += 1;
+        }
+        self.stats.base_volume += matched;
+        self.stats.quote_volume += quote;
+        self.stats.fees += fee;
+
+        if let Some(mid) = mid.filter(|mid| *mid > 0.0) {
+            let slippage = (if fill.is_buy { price - mid } else { mid - price }) / mid * 10_000.0;
+            self.slippage_total += slippage;
+            if self.stats.fills == 1 || slippage > self.stats.worst_slippage_bps {
+                self.stats.worst_slippage_bps = slippage;
+            }
+        }
+    }
+
+    fn record(&mut self, block: u64, mid: Option<f64>) {
+        // Keep marking at the last known mid when one side of the book is empty
+        let mark = mid.or_else(|| self.curve.last().and_then(|point| point.mid_price));
+        let inventory = mark.map_or(0.0, |mark| self.position * mark / self.price_precision);
+        self.curve.push(CurvePoint {
+            block,
+            mid_price: mark,
+            position: self.position,
+            cash: self.cash,
+            pnl: self.cash + inventory,
+        });
+    }
+
+    fn report(mut self, strategy: &str, blocks: &BTreeSet<u64>) -> BacktestReport {
+        if self.stats.fills > 0 {
+            self.stats.average_slippage_bps = self.slippage_total / self.stats.fills as f64;
+        }
+
+        let mut peak = f64::MIN;
+        let mut max_drawdown: f64 = 0.0;
+        for point in &self.curve {
+            peak = peak.max(point.pnl);
+            max_drawdown = max_drawdown.max(peak - point.pnl);
+        }
+
+        BacktestReport {
+            strategy: strategy.to_string(),
+            from_block: blocks.first().copied().unwrap_or_default(),
+            to_block: blocks.last().copied().unwrap_or_default(),
+            orders_placed: self.placed,
+            orders_cancelled: self.cancelled,
+            final_pnl: self.curve.last().map_or(0.0, |point| point.pnl),
+            max_drawdown,
+            stats: self.stats,
+            curve: self.curve,
+        }
+    }
+}
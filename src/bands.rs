@@ -0,0 +1,184 @@
+use crate::bot::Executor;
+use crate::error::{DexError, Result};
+use crate::price::PriceSource;
+use crate::strategy::{to_f64, BookView, Fill, Intent, OpenOrder, PendingActivity};
+use anyhow::{anyhow, Context};
+use async_trait::async_trait;
+use ethers::types::U256;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::Path;
+use tracing::warn;
+
+const PRICE_BANDS_PATH: &str = "config/price_bands.json";
+
+/// Fat-finger limits on how far a limit price may sit from where the market is.
+/// Each band is off while its limit is unset.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct PriceBands {
+    /// Largest distance from the book's mid price, in basis points. Skipped while either
+    /// side of the book is empty.
+    #[serde(default)]
+    pub max_mid_deviation_bps: Option<u32>,
+    /// Reference price in DEX price units: a number, an http(s) JSON endpoint with a
+    /// JSON pointer after `#`, or `feed:<SYMBOL>`, as for `--usd-price-source`
+    #[serde(default)]
+    pub reference: Option<String>,
+    /// Largest distance from `reference`, in basis points
+    #[serde(default)]
+    pub max_reference_deviation_bps: Option<u32>,
+}
+
+impl PriceBands {
+    /// The bands applied to CLI orders, and to bots whose config sets none
+    pub fn load() -> Result<Self> {
+        if !Path::new(PRICE_BANDS_PATH).exists() {
+            return Ok(Self::default());
+        }
+        let content = fs::read_to_string(PRICE_BANDS_PATH)
+            .with_context(|| format!("Failed to read {}", PRICE_BANDS_PATH))?;
+        Ok(serde_json::from_str(&content)?)
+    }
+
+    pub fn save(&self) -> Result<()> {
+        self.reference_source()?;
+        fs::create_dir_all("config")?;
+        fs::write(PRICE_BANDS_PATH, serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.max_mid_deviation_bps.is_none() && self.max_reference_deviation_bps.is_none()
+    }
+
+    fn reference_source(&self) -> Result<Option<PriceSource>> {
+        match (&self.reference, self.max_reference_deviation_bps) {
+            (Some(reference), _) => reference
+                .parse::<PriceSource>()
+                .map(Some)
+                .map_err(|e| DexError::Config(format!("Invalid reference price: {}", e))),
+            (None, Some(_)) => Err(DexError::Config("max_reference_deviation_bps is set but no reference price is".to_string())),
+            (None, None) => Ok(None),
+        }
+    }
+
+    /// Current reference price, when the reference band is on
+    pub async fn reference_price(&self) -> Result<Option<f64>> {
+        if self.max_reference_deviation_bps.is_none() {
+            return Ok(None);
+        }
+        match self.reference_source()? {
+            Some(source) => Ok(Some(source.fetch().await?)),
+            None => Ok(None),
+        }
+    }
+
+    /// Reject `price` if it lies outside a band around `mid` or `reference`
+    pub fn check(&self, price: U256, mid: Option<f64>, reference: Option<f64>) -> Result<()> {
+        let price = to_f64(price);
+        let bands = [
+            ("book mid", mid, self.max_mid_deviation_bps),
+            ("reference price", reference, self.max_reference_deviation_bps),
+        ];
+        for (name, anchor, limit) in bands {
+            let (Some(anchor), Some(limit)) = (anchor, limit) else {
+                continue;
+            };
+            if anchor <= 0.0 {
+                continue;
+            }
+            let deviation_bps = (price - anchor).abs() / anchor * 10_000.0;
+            if deviation_bps > limit as f64 {
+                return Err(DexError::Config(format!(
+                    "Price {} is {:.0} bps from the {} of {:.2}, beyond the {} bps band (pass --force to send it anyway)",
+                    price,
+                    deviation_bps,
+                    name,
+                    anchor,
+                    limit
+                )));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Refuses a bot's quotes that fall outside `bands` before handing the rest to `inner`.
+/// The mid comes from the last book the strategy saw, the reference is fetched once per
+/// iteration.
+pub struct BandedExecutor<E> {
+    inner: E,
+    bands: PriceBands,
+    mid: Option<f64>,
+}
+
+impl<E: Executor> BandedExecutor<E> {
+    pub fn new(inner: E, bands: PriceBands) -> Self {
+        Self { inner, bands, mid: None }
+    }
+}
+
+#[async_trait]
+impl<E: Executor> Executor for BandedExecutor<E> {
+    async fn book(&mut self) -> Result<BookView> {
+        let book = self.inner.book().await?;
+        self.mid = book.mid();
+        Ok(book)
+    }
+
+    async fn poll_fills(&mut self) -> Result<Vec<Fill>> {
+        self.inner.poll_fills().await
+    }
+
+    async fn open_orders(&mut self) -> Result<Vec<OpenOrder>> {
+        self.inner.open_orders().await
+    }
+
+    async fn poll_pending(&mut self) -> Result<Vec<PendingActivity>> {
+        self.inner.poll_pending().await
+    }
+
+    async fn place(&mut self, price: U256, amount: U256, is_buy: bool) -> Result<u64> {
+        let reference = self.bands.reference_price().await?;
+        self.bands.check(price, self.mid, reference)?;
+        self.inner.place(price, amount, is_buy).await
+    }
+
+    async fn cancel(&mut self, order_id: u64) -> Result<()> {
+        self.inner.cancel(order_id).await
+    }
+
+    async fn execute(&mut self, intents: &[Intent]) -> Vec<Result<Option<u64>>> {
+        // Without the reference the band can't be enforced, so quotes are held while
+        // cancels still go through
+        let reference = self.bands.reference_price().await.map_err(|e| e.to_string());
+        if let Err(e) = &reference {
+            warn!("Failed to fetch the reference price: {}", e);
+        }
+        let checks: Vec<Result<()>> = intents
+            .iter()
+            .map(|intent| match (intent, &reference) {
+                (Intent::Cancel { .. }, _) => Ok(()),
+                (Intent::Place { .. }, Err(e)) => Err(anyhow!("Reference price unavailable: {}", e).into()),
+                (Intent::Place { price, .. }, Ok(reference)) => self.bands.check(*price, self.mid, *reference),
+            })
+            .collect();
+
+        // Pass the intents that clear the bands through in one batch so the inner
+        // executor can still pipeline them, then put the refusals back in place
+        let allowed: Vec<Intent> = intents
+            .iter()
+            .zip(&checks)
+            .filter(|(_, check)| check.is_ok())
+            .map(|(intent, _)| intent.clone())
+            .collect();
+        let mut results = self.inner.execute(&allowed).await.into_iter();
+        checks
+            .into_iter()
+            .map(|check| match check {
+                Ok(()) => results.next().unwrap_or_else(|| Err(anyhow!("No result from executor").into())),
+                Err(e) => Err(e),
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,163 @@
+use crate::bindings::OrderBookDEX;
+use crate::error::{DexError, Result};
+use crate::rpc;
+use anyhow::{anyhow, Context};
+use ethers::{
+    providers::{Http, HttpClientError, JsonRpcClient, Provider},
+    types::{Address, Bytes, U64},
+};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::fs;
+use std::path::Path;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+const RPC_PROFILE_PATH: &str = "config/rpc_profile.json";
+
+/// Blocks covered by each benchmarked `eth_getLogs` request
+const LOG_RANGE: u64 = 100;
+
+/// Truncated EIP-1559 raw transaction. Nodes reject it while decoding, so timing the
+/// rejection measures the submission path without spending gas.
+const INVALID_RAW_TX: &str = "0x02f0";
+
+/// Latency of one JSON-RPC method against one endpoint
+#[derive(Debug, Clone, Serialize)]
+pub struct MethodLatency {
+    pub method: String,
+    pub samples: usize,
+    pub errors: usize,
+    pub p50_ms: f64,
+    pub p95_ms: f64,
+    pub p99_ms: f64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct EndpointBench {
+    pub url: String,
+    pub methods: Vec<MethodLatency>,
+    /// Sum of the p95 latencies; lower is better, `None` if any method failed every sample
+    pub score_ms: Option<f64>,
+}
+
+/// Endpoint order recorded by `doctor bench`, fastest first
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct RpcProfile {
+    pub endpoints: Vec<String>,
+}
+
+impl RpcProfile {
+    pub fn load() -> Result<Self> {
+        if !Path::new(RPC_PROFILE_PATH).exists() {
+            return Ok(Self::default());
+        }
+        let content = fs::read_to_string(RPC_PROFILE_PATH)
+            .with_context(|| format!("Failed to read {}", RPC_PROFILE_PATH))?;
+        Ok(serde_json::from_str(&content)?)
+    }
+
+    pub fn save(&self) -> Result<()> {
+        fs::create_dir_all("config")?;
+        fs::write(RPC_PROFILE_PATH, serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+
+    /// `urls` sorted by their benchmarked rank; endpoints the profile doesn't know keep
+    /// their relative order after the ranked ones
+    pub fn order<'a>(&self, urls: &[&'a str]) -> Vec<&'a str> {
+        let mut ordered = urls.to_vec();
+        ordered.sort_by_key(|url| {
+            self.endpoints
+                .iter()
+                .position(|ranked| ranked == url.trim())
+                .unwrap_or(usize::MAX)
+        });
+        ordered
+    }
+}
+
+/// Time `samples` rounds of `eth_call` on `getOrderBook`, `eth_getLogs` over recent DEX
+/// events and `eth_sendRawTransaction` against a single endpoint, bypassing failover
+pub async fn bench_endpoint(
+    url: &str,
+    dex: Address,
+    base_token: Address,
+    quote_token: Address,
+    samples: usize,
+) -> Result<EndpointBench> {
+    let http = rpc::http(url)?;
+
+    // Only used to encode calldata; nothing is sent through this provider
+    let encoder = Arc::new(Provider::new(http.clone()));
+    let calldata: Bytes = OrderBookDEX::new(dex, encoder)
+        .get_order_book(base_token, quote_token)
+        .calldata()
+        .ok_or_else(|| anyhow!("Failed to encode getOrderBook"))?;
+    let latest = http
+        .request::<_, U64>("eth_blockNumber", ())
+        .await
+        .map_err(|e| DexError::Rpc(format!("{} did not answer eth_blockNumber: {}", url, e)))?
+        .as_u64();
+
+    let call_params = json!([{ "to": dex, "data": calldata }, "latest"]);
+    let logs_params = json!([{
+        "address": dex,
+        "fromBlock": format!("{:#x}", latest.saturating_sub(LOG_RANGE - 1)),
+        "toBlock": format!("{:#x}", latest),
+    }]);
+    let send_params = json!([INVALID_RAW_TX]);
+
+    let methods = vec![
+        time_method(&http, "eth_call", &call_params, samples, false).await,
+        time_method(&http, "eth_getLogs", &logs_params, samples, false).await,
+        time_method(&http, "eth_sendRawTransaction", &send_params, samples, true).await,
+    ];
+    let score_ms = methods
+        .iter()
+        .map(|m| (m.errors < m.samples).then_some(m.p95_ms))
+        .sum::<Option<f64>>();
+
+    Ok(EndpointBench { url: url.to_string(), methods, score_ms })
+}
+
+/// `rejection_ok` counts a JSON-RPC error reply as a completed round trip
+async fn time_method(http: &Http, method: &str, params: &Value, samples: usize, rejection_ok: bool) -> MethodLatency {
+    let mut latencies = Vec::with_capacity(samples);
+    let mut errors = 0;
+    for _ in 0..samples {
+        let start = Instant::now();
+        let result: Result<Value, HttpClientError> = http.request(method, params).await;
+        match result {
+            Ok(_) => latencies.push(start.elapsed()),
+            Err(HttpClientError::JsonRpcError(_)) if rejection_ok => latencies.push(start.elapsed()),
+            Err(_) => errors += 1,
+        }
+    }
+
+    latencies.sort();
+    let percentile = |p: usize| {
+        latencies
+            .get((latencies.len().saturating_sub(1)) * p / 100)
+            .map_or(0.0, |d: &Duration| d.as_secs_f64() * 1000.0)
+    };
+    MethodLatency {
+        method: method.to_string(),
+        samples,
+        errors,
+        p50_ms: percentile(50),
+        p95_ms: percentile(95),
+        p99_ms: percentile(99),
+    }
+}
+
+/// Benchmarks ordered fastest first; endpoints that failed a method entirely go last
+pub fn rank(mut benches: Vec<EndpointBench>) -> Vec<EndpointBench> {
+    benches.sort_by(|a, b| match (a.score_ms, b.score_ms) {
+        (Some(a), Some(b)) => a.total_cmp(&b),
+        (Some(_), None) => std::cmp::Ordering::Less,
+        (None, Some(_)) => std::cmp::Ordering::Greater,
+        (None, None) => std::cmp::Ordering::Equal,
+    });
+    benches
+}
@@ -1,43 +1,202 @@
 use clap::{Parser, Subcommand};
 use ethers::{
     middleware::{SignerMiddleware, Middleware},
-    providers::{Http, Provider},
-    signers::LocalWallet,
-    types::{U256},
-    contract::ContractFactory,
-    abi::Abi,
+    types::{Address, Bytes, U256},
+    contract::{Contract, ContractFactory},
+    abi::{Abi, Token},
 };
-use serde::{Deserialize, Serialize};
-use std::fs;
-use std::path::Path;
+use std::process::ExitCode;
 use std::sync::Arc;
 use anyhow::{Result, Context};
+use monad_app::aliases;
+use monad_app::build;
+use monad_app::create2;
+use monad_app::deployments::{self, Deployment, DeploymentRegistry};
+use monad_app::error;
+use monad_app::fees::Fees;
+use monad_app::logging::{self, LogArgs};
+use monad_app::pipeline;
+use monad_app::proxy::{self, ProxyRecord, ProxyRegistry};
+use monad_app::rpc::{self, RpcArgs};
+use monad_app::safe;
+use monad_app::tx::{self, TxArgs};
 use tracing::{info, error, warn};
 
 #[derive(Parser)]
-#[command(author, version, about, long_about = None)]
+#[command(author, version, about, long_about = None, after_help = error::EXIT_CODES)]
 struct Cli {
+    #[command(flatten)]
+    log: LogArgs,
+
+    #[command(flatten)]
+    rpc: RpcArgs,
+
+    #[command(flatten)]
+    tx: TxArgs,
+
     #[command(subcommand)]
     command: Commands,
 }
 
 #[derive(Subcommand)]
 enum Commands {
+    /// Compile the Solidity contracts into out/, with forge or, failing that, solc
+    Build {
+        /// Recompile everything, ignoring the cache (forge only)
+        #[arg(long)]
+        force: bool,
+    },
+    
     /// Deploy MonadToken contract to testnet
     Deploy {
-        /// Private key for deployment
+        /// Private key for deployment (or --account)
+        #[arg(short, long)]
+        private_key: Option<String>,
+        
+        /// Constructor arguments, space-separated and quoted where needed, e.g. `"My Token" MTK 18 1000000`
+        #[arg(long)]
+        constructor_args: Option<String>,
+        
+        /// Deploy with CREATE2 through the deterministic deployer, at the same address on every network
+        #[arg(long, requires = "salt")]
+        create2: bool,
+        
+        /// CREATE2 salt, up to 32 bytes of hex
+        #[arg(long)]
+        salt: Option<String>,
+        
+        /// RPC URL, comma-separated list for failover (defaults to the network's endpoints)
+        #[arg(short, long)]
+        rpc_url: Option<String>,
+        
+        /// Gas price in wei (legacy transactions only)
+        #[arg(short, long, default_value = "20000000000")] // 20 gwei
+        gas_price: u64,
+    },
+    
+    /// Estimate gas and cost for deploying MonadToken without sending it
+    EstimateGas {
+        /// Private key for deployment (or --account)
         #[arg(short, long)]
-        private_key: String,
+        private_key: Option<String>,
+        
+        /// Constructor arguments, space-separated and quoted where needed, e.g. `"My Token" MTK 18 1000000`
+        #[arg(long)]
+        constructor_args: Option<String>,
+        
+        /// Deploy with CREATE2 through the deterministic deployer, at the same address on every network
+        #[arg(long, requires = "salt")]
+        create2: bool,
+        
+        /// CREATE2 salt, up to 32 bytes of hex
+        #[arg(long)]
+        salt: Option<String>,
         
-        /// RPC URL (defaults to Monad testnet)
-        #[arg(short, long, default_value = "https://monad-testnet.g.alchemy.com/v2/hl5Gau0XVV37m-RDdhcRzqCh7ISwmOAe")]
-        rpc_url: String,
+        /// RPC URL, comma-separated list for failover (defaults to the network's endpoints)
+        #[arg(short, long)]
+        rpc_url: Option<String>,
         
-        /// Gas price in wei
+        /// Gas price in wei (legacy transactions only)
         #[arg(short, long, default_value = "20000000000")] // 20 gwei
         gas_price: u64,
     },
     
+    /// Compute the address a CREATE2 deployment will get, without deploying
+    Address {
+        /// Contract to take the bytecode from, as compiled to out/<contract>.sol (defaults to the one Deploy uses)
+        #[arg(long)]
+        contract: Option<String>,
+        
+        /// Constructor arguments, space-separated and quoted where needed
+        #[arg(long)]
+        constructor_args: Option<String>,
+        
+        /// CREATE2 salt, up to 32 bytes of hex
+        #[arg(long)]
+        salt: String,
+    },
+    
+    /// Deploy a contract (the DEX by default) behind a transparent upgradeable proxy
+    DeployProxy {
+        /// Implementation contract, as compiled to out/<contract>.sol
+        #[arg(long, default_value = "OrderBookDEX")]
+        contract: String,
+        
+        /// Owner of the contract and of its ProxyAdmin (defaults to the deployer)
+        #[arg(long)]
+        owner: Option<String>,
+        
+        /// Private key for deployment (or --account)
+        #[arg(short, long)]
+        private_key: Option<String>,
+        
+        /// RPC URL, comma-separated list for failover (defaults to the network's endpoints)
+        #[arg(short, long)]
+        rpc_url: Option<String>,
+    },
+    
+    /// Deploy a new implementation and point a proxy at it, after checking storage layout compatibility
+    Upgrade {
+        /// Proxy address
+        #[arg(long)]
+        proxy: String,
+        
+        /// New implementation contract, as compiled to out/<contract>.sol
+        #[arg(long, default_value = "OrderBookDEX")]
+        contract: String,
+        
+        /// Upgrade even if the storage layouts are incompatible or unknown
+        #[arg(long)]
+        force: bool,
+        
+        /// Propose and execute through this Gnosis Safe (the ProxyAdmin owner) instead of sending from the key
+        #[arg(long)]
+        safe: Option<String>,
+        
+        /// Upgrade to this already deployed implementation of `contract` instead of deploying
+        /// a new one (Safe owners co-signing an upgrade pass the address from the proposal)
+        #[arg(long)]
+        implementation: Option<String>,
+        
+        /// Private key of the ProxyAdmin owner, or of a Safe owner with --safe (or --account)
+        #[arg(short, long)]
+        private_key: Option<String>,
+        
+        /// RPC URL, comma-separated list for failover (defaults to the network's endpoints)
+        #[arg(short, long)]
+        rpc_url: Option<String>,
+    },
+    
+    /// Show a proxy's implementation and admin
+    GetImplementation {
+        /// Proxy address
+        #[arg(long)]
+        proxy: String,
+        
+        /// RPC URL, comma-separated list for failover (defaults to the network's endpoints)
+        #[arg(short, long)]
+        rpc_url: Option<String>,
+    },
+    
+    /// Transfer ownership of a proxy's ProxyAdmin, i.e. the right to upgrade it
+    TransferProxyAdmin {
+        /// Proxy address
+        #[arg(long)]
+        proxy: String,
+        
+        /// New ProxyAdmin owner
+        #[arg(long)]
+        new_owner: String,
+        
+        /// Private key of the current ProxyAdmin owner (or --account)
+        #[arg(short, long)]
+        private_key: Option<String>,
+        
+        /// RPC URL, comma-separated list for failover (defaults to the network's endpoints)
+        #[arg(short, long)]
+        rpc_url: Option<String>,
+    },
+    
     /// Verify contract on Monad testnet
     Verify {
         /// Contract address
@@ -49,32 +208,82 @@ enum Commands {
         constructor_args: Option<String>,
     },
     
+    /// Run a multi-step deployment manifest (tokens, DEX, pairs, mints, liquidity),
+    /// skipping the steps already completed
+    Pipeline {
+        /// Manifest TOML file
+        #[arg(short, long)]
+        manifest: String,
+        
+        /// Continue the interrupted run of this manifest, waiting for a step that was in flight
+        #[arg(long, conflicts_with = "restart")]
+        resume: bool,
+        
+        /// Abandon the interrupted run of this manifest and run every step again
+        #[arg(long)]
+        restart: bool,
+        
+        /// Private key for deployment (or --account)
+        #[arg(short, long)]
+        private_key: Option<String>,
+        
+        /// RPC URL, comma-separated list for failover (defaults to the network's endpoints)
+        #[arg(short, long)]
+        rpc_url: Option<String>,
+    },
+    
     /// Get deployment configuration
     Config,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
-struct DeploymentConfig {
-    contract_address: Option<String>,
-    deployer_address: Option<String>,
-    network: String,
-    deployment_tx: Option<String>,
+#[tokio::main]
+async fn main() -> ExitCode {
+    match run(Cli::parse()).await {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => error::report(&e),
+    }
 }
 
-#[tokio::main]
-async fn main() -> Result<()> {
-    // Initialize logging
-    tracing_subscriber::fmt::init();
+async fn run(cli: Cli) -> Result<()> {
     
-    let cli = Cli::parse();
+    // Initialize logging
+    let _log_guard = logging::init(&cli.log)?;
+    let _fork = rpc::configure(cli.rpc.clone());
     
     match cli.command {
-        Commands::Deploy { private_key, rpc_url, gas_price } => {
-            deploy_contract(private_key, rpc_url, gas_price).await?;
+        Commands::Build { force } => {
+            build::build(force)?;
+            info!("Contracts compiled successfully!");
+        }
+        Commands::Deploy { private_key, constructor_args, create2, salt, rpc_url, gas_price } => {
+            let salt = if create2 { salt } else { None };
+            deploy_contract(private_key, constructor_args, salt, rpc_url, gas_price, &cli.tx).await?;
+        }
+        Commands::EstimateGas { private_key, constructor_args, create2, salt, rpc_url, gas_price } => {
+            let salt = if create2 { salt } else { None };
+            deploy_contract(private_key, constructor_args, salt, rpc_url, gas_price, &cli.tx.estimate_only()).await?;
+        }
+        Commands::Address { contract, constructor_args, salt } => {
+            predict_address(contract, constructor_args, salt)?;
+        }
+        Commands::DeployProxy { contract, owner, private_key, rpc_url } => {
+            deploy_proxy(contract, owner, private_key, rpc_url, &cli.tx).await?;
+        }
+        Commands::Upgrade { proxy, contract, force, safe, implementation, private_key, rpc_url } => {
+            upgrade_proxy(proxy, contract, force, safe, implementation, private_key, rpc_url, &cli.tx).await?;
+        }
+        Commands::GetImplementation { proxy, rpc_url } => {
+            get_implementation(proxy, rpc_url).await?;
+        }
+        Commands::TransferProxyAdmin { proxy, new_owner, private_key, rpc_url } => {
+            transfer_proxy_admin(proxy, new_owner, private_key, rpc_url, &cli.tx).await?;
         }
         Commands::Verify { address, constructor_args } => {
             verify_contract(address, constructor_args).await?;
         }
+        Commands::Pipeline { manifest, resume, restart, private_key, rpc_url } => {
+            run_pipeline(manifest, resume, restart, private_key, rpc_url, &cli.tx).await?;
+        }
         Commands::Config => {
             show_config().await?;
         }
@@ -83,15 +292,43 @@ async fn main() -> Result<()> {
     Ok(())
 }
 
-async fn deploy_contract(private_key: String, rpc_url: String, _gas_price: u64) -> Result<()> {
+async fn deploy_contract(
+    private_key: Option<String>,
+    constructor_args: Option<String>,
+    salt: Option<String>,
+    rpc_url: Option<String>,
+    gas_price: u64,
+    tx_args: &TxArgs
+) -> Result<()> {
     info!("Starting contract deployment to Monad testnet...");
     
+    // Load contract bytecode and ABI
+    let contract_bytecode = load_contract_bytecode()?;
+    let contract_abi = load_contract_abi()?;
+    
+    // Check constructor arguments before touching the network
+    let constructor_args = match constructor_args {
+        Some(args) => split_args(&args)?,
+        None => Vec::new(),
+    };
+    let constructor_params = contract_abi.constructor().map(|c| c.inputs.as_slice()).unwrap_or_default();
+    let constructor_tokens = pipeline::tokenize_args("Constructor", constructor_params, &constructor_args)?;
+    let encoded_constructor_args = match contract_abi.constructor() {
+        Some(constructor) => hex::encode(constructor.encode_input(Vec::new(), &constructor_tokens)?),
+        None => String::new(),
+    };
+    let salt = salt.as_deref().map(create2::parse_salt).transpose()?;
+    
     // Setup provider and wallet
-    let provider = Provider::<Http>::try_from(rpc_url.clone())
+    let provider = rpc::connect(rpc_url.as_deref()).await
         .context("Failed to create provider")?;
     
-    let wallet = private_key.parse::<LocalWallet>()
-        .context("Failed to parse private key")?;
+    let fees = match tx_args.fees.resolve(&provider).await? {
+        Fees::Legacy { .. } => Fees::Legacy { gas_price: Some(U256::from(gas_price)) },
+        fees => fees,
+    };
+    
+    let wallet = rpc::wallet(&provider, private_key.as_deref()).await?;
     
     let client = SignerMiddleware::new(provider, wallet);
     let address = client.address();
@@ -107,38 +344,354 @@ async fn deploy_contract(private_key: String, rpc_url: String, _gas_price: u64)
         return Err(anyhow::anyhow!("Insufficient balance"));
     }
     
-    // Load contract bytecode and ABI
-    let contract_bytecode = load_contract_bytecode()?;
-    let contract_abi = load_contract_abi()?;
-    
     info!("Contract bytecode size: {} bytes", contract_bytecode.len());
     
-    // Create contract factory
-    let factory = ContractFactory::new(
-        contract_abi, 
-        contract_bytecode.into(), 
-        Arc::new(client)
-    );
-    
-    // Deploy contract
-    info!("Deploying contract...");
-    let deploy_tx = factory.deploy(())?;
-    
-    let deployed_contract = deploy_tx.send().await?;
-    let contract_address = deployed_contract.address();
+    let (contract_address, receipt) = match salt {
+        Some(salt) => {
+            // Deploy through the deterministic deployer
+            let init_code = create2::init_code(&contract_abi, &contract_bytecode.into(), constructor_tokens)?;
+            let (contract_address, receipt) = create2::deploy(&client, salt, init_code, &fees, tx_args).await?;
+            let Some(receipt) = receipt else {
+                info!("Expected contract address: {:?}", contract_address);
+                return Ok(());
+            };
+            (contract_address, receipt)
+        }
+        None => {
+            // Create contract factory
+            let client = Arc::new(client);
+            let factory = ContractFactory::new(
+                contract_abi, 
+                contract_bytecode.into(), 
+                client.clone()
+            );
+            
+            // Deploy contract
+            let mut deploy_tx = factory.deploy_tokens(constructor_tokens)?;
+            fees.apply(&mut deploy_tx.tx);
+            let estimated_gas = client.estimate_gas(&deploy_tx.tx, None).await?;
+            
+            if tx_args.estimate_only {
+                tx::print_estimate(estimated_gas, &fees, tx_args).await;
+                return Ok(());
+            }
+            
+            deploy_tx.tx.set_gas(estimated_gas);
+            // Prepared deployments are recorded once they are broadcast
+            if tx_args.prepare.is_some() {
+                tx::broadcast(client.as_ref(), deploy_tx.tx, tx_args).await?;
+                return Ok(());
+            }
+            
+            info!("Deploying contract...");
+            let (deployed_contract, receipt) = deploy_tx
+                .confirmations(tx_args.confirmations)
+                .send_with_receipt()
+                .await?;
+            tx::report(estimated_gas, &receipt, tx_args).await;
+            (deployed_contract.address(), receipt)
+        }
+    };
     
     info!("Contract deployed successfully!");
     info!("Contract address: {:?}", contract_address);
     
-    // Save deployment config
-    let config = DeploymentConfig {
-        contract_address: Some(format!("{:?}", contract_address)),
-        deployer_address: Some(format!("{:?}", address)),
-        network: "monad_testnet".to_string(),
-        deployment_tx: Some("Transaction hash not available in ethers v2".to_string()),
+    // Record the deployment
+    let deployment = Deployment {
+        address: contract_address,
+        deployer: Some(address),
+        tx: Some(receipt.transaction_hash),
+        constructor_args,
+        encoded_constructor_args: Some(encoded_constructor_args).filter(|args| !args.is_empty()),
+        create2_salt: salt.map(|salt| format!("{:?}", salt)),
+        ..Default::default()
+    };
+    
+    save_deployment("MonadToken", deployment)?;
+    
+    Ok(())
+}
+
+fn predict_address(contract: Option<String>, constructor_args: Option<String>, salt: String) -> Result<()> {
+    let (contract_abi, contract_bytecode) = match &contract {
+        Some(contract) => pipeline::load_artifact(contract)?,
+        None => (load_contract_abi()?, load_contract_bytecode()?.into()),
+    };
+    
+    let constructor_args = match constructor_args {
+        Some(args) => split_args(&args)?,
+        None => Vec::new(),
+    };
+    let constructor_params = contract_abi.constructor().map(|c| c.inputs.as_slice()).unwrap_or_default();
+    let constructor_tokens = pipeline::tokenize_args("Constructor", constructor_params, &constructor_args)?;
+    
+    let salt = create2::parse_salt(&salt)?;
+    let init_code = create2::init_code(&contract_abi, &contract_bytecode, constructor_tokens)?;
+    
+    println!("CREATE2 Address");
+    println!("===============");
+    println!("Deployer: {}", create2::DETERMINISTIC_DEPLOYER);
+    println!("Salt: {:?}", salt);
+    println!("Init code hash: 0x{}", hex::encode(ethers::utils::keccak256(&init_code)));
+    println!("Address: {:?}", create2::predict(salt, &init_code));
+    
+    Ok(())
+}
+
+async fn run_pipeline(manifest: String, resume: bool, restart: bool, private_key: Option<String>, rpc_url: Option<String>, tx_args: &TxArgs) -> Result<()> {
+    info!("Running deployment pipeline {}...", manifest);
+    
+    let provider = rpc::connect(rpc_url.as_deref()).await
+        .context("Failed to create provider")?;
+    let fees = tx_args.fees.resolve(&provider).await?;
+    let wallet = rpc::wallet(&provider, private_key.as_deref()).await?;
+    let client = Arc::new(SignerMiddleware::new(provider, wallet));
+    
+    pipeline::run(&manifest, client, &fees, tx_args, resume, restart).await?;
+    Ok(())
+}
+
+async fn deploy_proxy(
+    contract: String,
+    owner: Option<String>,
+    private_key: Option<String>,
+    rpc_url: Option<String>,
+    tx_args: &TxArgs
+) -> Result<()> {
+    // The proxy's constructor takes the implementation's address, known only once it is mined
+    if tx_args.prepare.is_some() {
+        return Err(anyhow::anyhow!("deploy-proxy can't be prepared offline: the proxy needs the implementation's address"));
+    }
+    info!("Deploying {} behind a transparent proxy...", contract);
+    
+    let (implementation_abi, implementation_bytecode) = pipeline::load_artifact(&contract)?;
+    let (proxy_abi, proxy_bytecode) = pipeline::load_artifact("TransparentUpgradeableProxy")?;
+    let storage_layout = proxy::storage_layout(&contract)?;
+    
+    let provider = rpc::connect(rpc_url.as_deref()).await
+        .context("Failed to create provider")?;
+    let fees = tx_args.fees.resolve(&provider).await?;
+    let wallet = rpc::wallet(&provider, private_key.as_deref()).await?;
+    let client = Arc::new(SignerMiddleware::new(provider, wallet));
+    let owner = match owner {
+        Some(owner) => aliases::resolve(&owner)?,
+        None => client.address(),
+    };
+    
+    // The proxy runs initialize() in its constructor, so nobody can front-run it
+    let init_data = implementation_abi
+        .function("initialize")
+        .context("Implementation has no initialize(address) function")?
+        .encode_input(&[Token::Address(owner)])?;
+    
+    info!("Deploying implementation...");
+    let Some((implementation, _)) = pipeline::deploy_contract(
+        client.clone(), implementation_abi, implementation_bytecode, Vec::new(), &fees, tx_args
+    ).await? else {
+        return Ok(());
+    };
+    info!("Implementation deployed at {:?}", implementation);
+    
+    info!("Deploying proxy...");
+    let proxy_args = vec![Token::Address(implementation), Token::Address(owner), Token::Bytes(init_data)];
+    let Some((proxy_address, receipt)) = pipeline::deploy_contract(
+        client.clone(), proxy_abi, proxy_bytecode, proxy_args, &fees, tx_args
+    ).await? else {
+        return Ok(());
+    };
+    let admin = proxy::admin(client.as_ref(), proxy_address).await?;
+    
+    let mut registry = ProxyRegistry::load()?;
+    registry.proxies.insert(proxy_address, ProxyRecord { contract: contract.clone(), implementation, admin, storage_layout });
+    registry.save()?;
+    save_deployment(&contract, Deployment {
+        address: proxy_address,
+        deployer: Some(client.address()),
+        tx: Some(receipt.transaction_hash),
+        implementation: Some(implementation),
+        ..Default::default()
+    })?;
+    
+    info!("Proxy deployed successfully!");
+    info!("Proxy address: {:?}", proxy_address);
+    info!("Implementation: {:?}", implementation);
+    info!("ProxyAdmin: {:?} (owned by {:?})", admin, owner);
+    info!("Transaction hash: {:?}", receipt.transaction_hash);
+    
+    Ok(())
+}
+
+#[allow(clippy::too_many_arguments)]
+async fn upgrade_proxy(
+    proxy_address: String,
+    contract: String,
+    force: bool,
+    safe: Option<String>,
+    implementation: Option<String>,
+    private_key: Option<String>,
+    rpc_url: Option<String>,
+    tx_args: &TxArgs
+) -> Result<()> {
+    let proxy_address = aliases::resolve(&proxy_address)?;
+    info!("Upgrading proxy {:?} to {}...", proxy_address, contract);
+    
+    // Check the new layout against the one recorded for the current implementation
+    let (implementation_abi, implementation_bytecode) = pipeline::load_artifact(&contract)?;
+    let storage_layout = proxy::storage_layout(&contract)?;
+    let mut registry = ProxyRegistry::load()?;
+    match registry.proxies.get(&proxy_address) {
+        Some(record) => {
+            let (errors, warnings) = proxy::check_layout(&record.storage_layout, &storage_layout);
+            for warning in &warnings {
+                warn!("Storage layout: {}", warning);
+            }
+            for problem in &errors {
+                error!("Storage layout: {}", problem);
+            }
+            if !errors.is_empty() && !force {
+                return Err(anyhow::anyhow!(
+                    "{} is not storage-compatible with {} (use --force to upgrade anyway)",
+                    contract, record.contract
+                ));
+            }
+        }
+        None if force => warn!("No recorded storage layout for {:?}; upgrading unchecked", proxy_address),
+        None => {
+            return Err(anyhow::anyhow!(
+                "No recorded storage layout for {:?}, so the upgrade can't be checked (use --force to upgrade anyway)",
+                proxy_address
+            ));
+        }
+    }
+    
+    let provider = rpc::connect(rpc_url.as_deref()).await
+        .context("Failed to create provider")?;
+    let fees = tx_args.fees.resolve(&provider).await?;
+    let wallet = rpc::wallet(&provider, private_key.as_deref()).await?;
+    let client = Arc::new(SignerMiddleware::new(provider, wallet));
+    
+    let admin = proxy::admin(client.as_ref(), proxy_address).await?;
+    let (admin_abi, _) = pipeline::load_artifact("ProxyAdmin")?;
+    let proxy_admin = Contract::new(admin, admin_abi, client.clone());
+    let safe = safe.map(|safe| aliases::resolve(&safe)).transpose()?;
+    let upgrader = safe.unwrap_or(client.address());
+    let admin_owner: Address = proxy_admin.method("owner", ())?.call().await?;
+    if admin_owner != upgrader {
+        return Err(anyhow::anyhow!("{:?} does not own the ProxyAdmin {:?} (owner is {:?})", upgrader, admin, admin_owner));
+    }
+    
+    let implementation = match implementation {
+        Some(implementation) => {
+            let implementation = aliases::resolve(&implementation)?;
+            if client.get_code(implementation, None).await?.is_empty() {
+                return Err(anyhow::anyhow!("No contract deployed at implementation {:?}", implementation));
+            }
+            implementation
+        }
+        None => {
+            info!("Deploying implementation...");
+            let Some((implementation, _)) = pipeline::deploy_contract(
+                client.clone(), implementation_abi, implementation_bytecode, Vec::new(), &fees, tx_args
+            ).await? else {
+                return Ok(());
+            };
+            info!("Implementation deployed at {:?}", implementation);
+            implementation
+        }
+    };
+    
+    let args = (proxy_address, implementation, Bytes::new());
+    let method = proxy_admin.method::<_, ()>("upgradeAndCall", args)?;
+    let receipt = match safe {
+        Some(safe) => {
+            let data = method.calldata().ok_or_else(|| anyhow::anyhow!("upgradeAndCall call has no calldata"))?;
+            let description = format!("Upgrade proxy {:?} to {} at {:?}", proxy_address, contract, implementation);
+            safe::submit(client.clone(), safe, admin, data, &description, &fees, tx_args).await?
+        }
+        None => tx::send(client.as_ref(), method, &fees, tx_args).await?,
     };
     
-    save_deployment_config(config)?;
+    if let Some(receipt) = receipt {
+        registry.proxies.insert(proxy_address, ProxyRecord { contract: contract.clone(), implementation, admin, storage_layout });
+        registry.save()?;
+        save_deployment(&contract, Deployment {
+            address: proxy_address,
+            deployer: Some(client.address()),
+            tx: Some(receipt.transaction_hash),
+            implementation: Some(implementation),
+            ..Default::default()
+        })?;
+        
+        info!("Proxy upgraded successfully!");
+        info!("Transaction hash: {:?}", receipt.transaction_hash);
+    }
+    
+    Ok(())
+}
+
+async fn get_implementation(proxy_address: String, rpc_url: Option<String>) -> Result<()> {
+    let proxy_address = aliases::resolve(&proxy_address)?;
+    let provider = Arc::new(rpc::connect(rpc_url.as_deref()).await?);
+    
+    let implementation = proxy::implementation(provider.as_ref(), proxy_address).await?;
+    let admin = proxy::admin(provider.as_ref(), proxy_address).await?;
+    let (admin_abi, _) = pipeline::load_artifact("ProxyAdmin")?;
+    let admin_owner: Option<Address> = Contract::new(admin, admin_abi, provider.clone())
+        .method("owner", ())?
+        .call()
+        .await
+        .ok();
+    let record = ProxyRegistry::load()?.proxies.remove(&proxy_address);
+    
+    println!("Proxy {:?}", proxy_address);
+    println!("================================");
+    match &record {
+        Some(record) if record.implementation == implementation => {
+            println!("Implementation: {:?} ({})", implementation, record.contract);
+        }
+        Some(record) => {
+            println!("Implementation: {:?}", implementation);
+            println!("  (registry expected {:?}; upgraded outside this tool?)", record.implementation);
+        }
+        None => println!("Implementation: {:?}", implementation),
+    }
+    println!("ProxyAdmin: {:?}", admin);
+    match admin_owner {
+        Some(owner) => println!("ProxyAdmin owner: {:?}", owner),
+        None => println!("ProxyAdmin owner: unknown"),
+    }
+    
+    Ok(())
+}
+
+async fn transfer_proxy_admin(
+    proxy_address: String,
+    new_owner: String,
+    private_key: Option<String>,
+    rpc_url: Option<String>,
+    tx_args: &TxArgs
+) -> Result<()> {
+    let proxy_address = aliases::resolve(&proxy_address)?;
+    let new_owner = aliases::resolve(&new_owner)?;
+    info!("Transferring the ProxyAdmin of {:?} to {:?}", proxy_address, new_owner);
+    
+    let provider = rpc::connect(rpc_url.as_deref()).await
+        .context("Failed to create provider")?;
+    let fees = tx_args.fees.resolve(&provider).await?;
+    let wallet = rpc::wallet(&provider, private_key.as_deref()).await?;
+    let client = Arc::new(SignerMiddleware::new(provider, wallet));
+    
+    let admin = proxy::admin(client.as_ref(), proxy_address).await?;
+    let (admin_abi, _) = pipeline::load_artifact("ProxyAdmin")?;
+    let proxy_admin = Contract::new(admin, admin_abi, client.clone());
+    
+    let method = proxy_admin.method::<_, ()>("transferOwnership", new_owner)?;
+    let receipt = tx::send(client.as_ref(), method, &fees, tx_args).await?;
+    
+    if let Some(receipt) = receipt {
+        info!("ProxyAdmin ownership transferred successfully!");
+        info!("Transaction hash: {:?}", receipt.transaction_hash);
+    }
     
     Ok(())
 }
@@ -151,6 +704,22 @@ async fn verify_contract(address: String, constructor_args: Option<String>) -> R
     warn!("Contract verification not implemented yet");
     warn!("Please verify manually on Monad block explorer");
     warn!("Contract address: {}", address);
+    if let Some(link) = rpc::network()?.explorer_link(&format!("address/{}", address)) {
+        warn!("Explorer: {}", link);
+    }
+    
+    // Fall back to the arguments recorded when this address was deployed
+    let constructor_args = match constructor_args {
+        Some(args) => Some(args),
+        None => {
+            let registry = DeploymentRegistry::load()?;
+            address
+                .parse()
+                .ok()
+                .and_then(|address| registry.find(address))
+                .and_then(|(_, _, deployment)| deployment.encoded_constructor_args.clone())
+        }
+    };
     
     if let Some(args) = constructor_args {
         info!("Constructor arguments: {}", args);
@@ -160,19 +729,42 @@ async fn verify_contract(address: String, constructor_args: Option<String>) -> R
 }
 
 async fn show_config() -> Result<()> {
-    let config_path = Path::new("config/deployment.json");
-    
-    if config_path.exists() {
-        let config_content = fs::read_to_string(config_path)?;
-        let config: DeploymentConfig = serde_json::from_str(&config_content)?;
-        
-        println!("Deployment Configuration:");
-        println!("Network: {}", config.network);
-        println!("Contract Address: {}", config.contract_address.unwrap_or_else(|| "Not deployed".to_string()));
-        println!("Deployer Address: {}", config.deployer_address.unwrap_or_else(|| "Unknown".to_string()));
-        println!("Deployment TX: {}", config.deployment_tx.unwrap_or_else(|| "Unknown".to_string()));
-    } else {
+    let registry = DeploymentRegistry::load()?;
+    
+    if registry.networks.is_empty() {
         println!("No deployment configuration found");
+        return Ok(());
+    }
+    
+    println!("Deployment Configuration:");
+    for (network, contracts) in &registry.networks {
+        println!();
+        println!("Network: {}", network);
+        for (contract, history) in contracts {
+            println!("  {}:", contract);
+            for deployment in history.iter().rev() {
+                let label = deployment.label.as_ref().map(|label| format!(" [{}]", label)).unwrap_or_default();
+                println!("    v{} {:?}{} (deployed at {})", deployment.version, deployment.address, label, deployment.timestamp);
+                if let Some(deployer) = deployment.deployer {
+                    println!("      Deployer: {:?}", deployer);
+                }
+                if let Some(tx) = deployment.tx {
+                    println!("      TX: {:?}", tx);
+                }
+                if let Some(implementation) = deployment.implementation {
+                    println!("      Implementation: {:?}", implementation);
+                }
+                if !deployment.constructor_args.is_empty() {
+                    println!("      Constructor Args: {}", deployment.constructor_args.join(" "));
+                }
+                if let Some(encoded) = &deployment.encoded_constructor_args {
+                    println!("      Encoded Constructor Args: {}", encoded);
+                }
+                if let Some(salt) = &deployment.create2_salt {
+                    println!("      CREATE2 Salt: {}", salt);
+                }
+            }
+        }
     }
     
     Ok(())
@@ -200,15 +792,53 @@ fn load_contract_abi() -> Result<Abi> {
     Ok(abi)
 }
 
-fn save_deployment_config(config: DeploymentConfig) -> Result<()> {
-    let config_dir = Path::new("config");
-    if !config_dir.exists() {
-        fs::create_dir_all(config_dir)?;
-    }
+/// Split a constructor argument string on whitespace, keeping quoted sections together
+fn split_args(input: &str) -> Result<Vec<String>> {
+    let mut args = Vec::new();
+    let mut current = String::new();
+    let mut quote = None;
+    let mut in_arg = false;
     
-    let config_path = config_dir.join("deployment.json");
-    let config_json = serde_json::to_string_pretty(&config)?;
-    fs::write(config_path, config_json)?;
+    for c in input.chars() {
+        match quote {
+            Some(q) if c == q => quote = None,
+            Some(_) => current.push(c),
+            None if c == '"' || c == '\'' => {
+                quote = Some(c);
+                in_arg = true;
+            }
+            None if c.is_whitespace() => {
+                if in_arg {
+                    args.push(std::mem::take(&mut current));
+                    in_arg = false;
+                }
+            }
+            None => {
+                current.push(c);
+                in_arg = true;
+            }
+        }
+    }
     
+    if quote.is_some() {
+        return Err(anyhow::anyhow!("Unterminated quote in constructor arguments: {}", input));
+    }
+    if in_arg {
+        args.push(current);
+    }
+    Ok(args)
+}
+
+/// Append a deployment to the registry's history for `contract` on the `--network` network
+fn save_deployment(contract: &str, deployment: Deployment) -> Result<()> {
+    if rpc::is_fork() {
+        info!("Not recording {} in the deployment registry: deployed on a --fork", contract);
+        return Ok(());
+    }
+    let mut registry = DeploymentRegistry::load()?;
+    let network = deployments::current_network();
+    let recorded = registry.record(&network, contract, deployment);
+    info!("Recorded {} v{} on {}", contract, recorded.version, network);
+    registry.save()?;
     Ok(())
-} 
\ No newline at end of file
+}
@@ -1,29 +1,179 @@
 use clap::{Parser, Subcommand};
 use ethers::{
     middleware::SignerMiddleware,
-    providers::{Http, Provider},
-    signers::LocalWallet,
-    types::{Address, U256},
-    contract::Contract,
-    abi::Abi,
+    providers::Middleware,
+    signers::Signer,
+    types::{transaction::eip2718::TypedTransaction, Address, Filter, TransactionRequest, H256, U256},
+    utils::{format_ether, parse_ether},
+    contract::{Contract, ContractCall},
+    abi::{Abi, Event, Function, StateMutability, Token},
 };
 use anyhow::Result;
+use monad_app::{analytics, arb, bench, compat, error, events, expiry, fanout, fills, loadtest, pipeline, qr, recorder, replace, route, safe, session, signing, trace};
+use monad_app::aliases::{self, AddressBook};
+use monad_app::alerts::{AlertEngine, AlertsConfig};
+use monad_app::analytics::AnalyticsReport;
+use monad_app::arb::{ArbConfig, Leg};
+use monad_app::backtest::Backtest;
+use monad_app::bands::PriceBands;
+use monad_app::bench::RpcProfile;
+use monad_app::bindings::{OrderBookDEX, ERC20, ERC20_ABI, ORDERBOOKDEX_ABI, WMON, WMON_ABI};
+use monad_app::bot::SignerClient;
+use monad_app::breaker::Halt;
+use monad_app::compat::DexReleases;
+use monad_app::daemon::{self, DaemonStatus, Request, Response};
+use monad_app::doctor::{self, DoctorOptions, Status};
+use monad_app::explorer::{self, AbiLookup};
+use monad_app::fees::Fees;
+use monad_app::export::{ExportFilter, ExportFormat, Table};
+use monad_app::indexer::{self, EventStore, DEFAULT_CHUNK_SIZE};
+use monad_app::journal::{self, JournalEntry, RunStatus, TxJournal, TxStatus};
+use monad_app::limits::{self, Cap, Spend, SpendingLedger, SpendingLimits};
+use monad_app::matching::MarketQuote;
+use monad_app::mempool::MempoolWatcher;
+use monad_app::metrics;
+use monad_app::multicall::Multicall;
+use monad_app::orderbook::{self, BookPages, Depth, PageQuery};
+use monad_app::networks::{BasicAuth, Network, NetworkRegistry, RpcAuth};
+use monad_app::offline::{self, PreparedBatch, SignedBatch};
+use monad_app::output::{self, OutputFormat};
+use monad_app::pairs::PairParams;
+use monad_app::paper::{PairMarket, PaperAccount, PaperFill};
+use monad_app::placement::{self, LimitOrder, LimitOrderOptions};
+use monad_app::portfolio::{self, PairPnl, PairPosition, TokenHolding};
+use monad_app::pricefeed::{Feed, PriceFeeds};
+use monad_app::relay::Relay;
+use monad_app::replay::{Replay, ReplayEvent, ReplayOrder};
+use monad_app::risk::{RiskEngine, RiskLimits};
+use monad_app::schedule::{self, ScheduleStore, ScheduledOrder};
+use monad_app::signing::{OffchainOrder, OrderDomain, SignedOrder};
+use monad_app::session::{SessionPolicy, SessionStore};
+use monad_app::snapshot::BookSnapshot;
+use monad_app::runner::{self, RunnerOptions};
+use monad_app::strategy::{BookView, BotConfig, PendingAction, PendingActivity, StrategyRegistry};
+use monad_app::tokens::{TokenInfo, TokenRegistry};
+use monad_app::wallets::{self, WalletStore};
+use monad_app::loadtest::{LatencyStats, LoadTestParams};
+use monad_app::logging::{self, LogArgs};
+use monad_app::rpc::{self, RpcArgs};
+use monad_app::tx::{self, TxArgs};
+use serde::{Deserialize, Serialize};
 use tracing::info;
+use std::collections::btree_map::Entry;
+use std::collections::{BTreeMap, BTreeSet};
+use std::io::Write;
+use std::net::SocketAddr;
+use std::process::ExitCode;
 use std::sync::Arc;
+use std::time::{Duration, SystemTime};
+
+/// Return value of the DEX `orders(uint256)` getter:
+/// (id, trader, baseToken, quoteToken, amount, price, isBuy, isActive, timestamp)
+type OrderInfo = (U256, Address, Address, Address, U256, U256, bool, bool, U256);
+
+/// One row of `logs` output: (block, transaction, event, decoded parameters)
+type ContractLogRow = (Option<u64>, Option<H256>, Option<String>, String);
+
+/// One entry of a PlaceOrders file
+#[derive(Debug, Deserialize)]
+struct OrderRequest {
+    base_token: String,
+    quote_token: String,
+    amount: u64,
+    price: u64,
+    is_buy: bool,
+}
+
+/// Trading fee settings for GetFees output
+#[derive(Serialize)]
+struct FeeReport {
+    trading_fee: u64,
+    max_trading_fee: u64,
+    fee_denominator: u64,
+    trading_fee_percent: f64,
+}
+
+/// One row of ListPairs output
+#[derive(Serialize)]
+struct PairRow {
+    base_token: Address,
+    base_symbol: String,
+    quote_token: Address,
+    quote_symbol: String,
+    is_active: bool,
+    #[serde(serialize_with = "output::decimal")]
+    min_order_size: U256,
+    #[serde(serialize_with = "output::decimal")]
+    price_precision: U256,
+}
+
+/// Result of checking a signed order for VerifyOrder output
+#[derive(Serialize)]
+struct OrderVerification {
+    digest: H256,
+    maker: Address,
+    signer: Option<Address>,
+    valid: bool,
+    /// Reasons the order can't be settled, separated by "; "
+    problems: String,
+}
+
+/// Portfolio report for JSON output
+#[derive(Serialize)]
+struct PortfolioReport {
+    user: Address,
+    holdings: Vec<TokenHolding>,
+    pairs: Vec<PairPnl>,
+}
 
 #[derive(Parser)]
-#[command(author, version, about, long_about = None)]
+#[command(author, version, about, long_about = None, after_help = error::EXIT_CODES)]
 struct Cli {
+    #[command(flatten)]
+    log: LogArgs,
+
+    #[command(flatten)]
+    rpc: RpcArgs,
+
+    #[command(flatten)]
+    tx: TxArgs,
+
+    /// Output format for report commands. With json, a failure is also written to stderr as
+    /// {"error": {"category", "message", "reason", "tx_hash", "exit_code"}}
+    #[arg(long, value_enum, default_value_t = OutputFormat::Text, global = true)]
+    output: OutputFormat,
+
+    /// Simulate order commands against the live book using a local paper account
+    #[arg(long, global = true)]
+    paper: bool,
+
+    /// Send to the DEX without first checking that it exposes the interface these tools call
+    #[arg(long, global = true)]
+    skip_compat_check: bool,
+
+    /// Serve Prometheus metrics on this address (e.g. 0.0.0.0:9090) while the command runs
+    #[arg(long, global = true)]
+    metrics_addr: Option<SocketAddr>,
+
     #[command(subcommand)]
     command: Commands,
 }
 
 #[derive(Subcommand)]
 enum Commands {
-    /// Add a new trading pair (owner only)
-    AddTradingPair {
-        /// DEX contract address
-        #[arg(short, long)]
+    #[command(flatten)]
+    Write(WriteCommands),
+    
+    /// Estimate gas and cost for a write command without sending it
+    EstimateGas {
+        #[command(subcommand)]
+        command: WriteCommands,
+    },
+    
+    /// Get order book for a trading pair
+    GetOrderBook {
+        /// DEX contract address (defaults to the latest recorded OrderBookDEX deployment)
+        #[arg(short, long, default_value = "OrderBookDEX")]
         address: String,
         
         /// Base token address
@@ -34,27 +184,140 @@ enum Commands {
         #[arg(short, long)]
         quote_token: String,
         
-        /// Minimum order size
+        /// Price levels to show per side (default: all)
+        #[arg(long)]
+        depth: Option<usize>,
+        
+        /// Best price levels to skip per side before showing `--depth` levels
+        #[arg(long, default_value_t = 0)]
+        offset: usize,
+        
+        /// Only orders priced at or above this (raw contract price)
+        #[arg(long)]
+        min_price: Option<String>,
+        
+        /// Only orders priced at or below this (raw contract price)
+        #[arg(long)]
+        max_price: Option<String>,
+        
+        /// Orders fetched per call
+        #[arg(long, default_value_t = orderbook::DEFAULT_PAGE_SIZE)]
+        page_size: u64,
+        
+        /// Print individual orders as pages arrive instead of aggregated price levels
+        #[arg(long)]
+        stream: bool,
+        
+        /// RPC URL (comma-separated list for failover)
         #[arg(short, long)]
-        min_order_size: u64,
+        rpc_url: Option<String>,
+    },
+    
+    /// Get user's active orders
+    GetUserOrders {
+        /// DEX contract address (defaults to the latest recorded OrderBookDEX deployment)
+        #[arg(short, long, default_value = "OrderBookDEX")]
+        address: String,
         
-        /// Price precision
+        /// User address
+        #[arg(short, long)]
+        user: String,
+        
+        /// Also fetch each order's pair, side, price and amount
         #[arg(long)]
-        price_precision: u64,
+        detailed: bool,
         
-        /// Private key
+        /// RPC URL (comma-separated list for failover)
         #[arg(short, long)]
-        private_key: String,
+        rpc_url: Option<String>,
+    },
+    
+    /// Get user balance
+    GetBalance {
+        /// DEX contract address (defaults to the latest recorded OrderBookDEX deployment)
+        #[arg(short, long, default_value = "OrderBookDEX")]
+        address: String,
         
-        /// RPC URL
-        #[arg(short, long, default_value = "https://monad-testnet.g.alchemy.com/v2/hl5Gau0XVV37m-RDdhcRzqCh7ISwmOAe")]
-        rpc_url: String,
+        /// User address
+        #[arg(short, long)]
+        user: String,
+        
+        /// Token address (repeat or comma-separate for several tokens)
+        #[arg(short, long, value_delimiter = ',', required = true)]
+        token: Vec<String>,
+        
+        /// RPC URL (comma-separated list for failover)
+        #[arg(short, long)]
+        rpc_url: Option<String>,
     },
     
-    /// Place a limit order
-    PlaceLimitOrder {
-        /// DEX contract address
+    /// Index DEX order, trade and cancellation events into config/index
+    Index {
+        /// DEX contract address (defaults to the latest recorded OrderBookDEX deployment)
+        #[arg(short, long, default_value = "OrderBookDEX")]
+        address: String,
+        
+        /// Block to start indexing from when no index exists yet
+        #[arg(long, default_value_t = 0)]
+        from_block: u64,
+        
+        /// Blocks requested per eth_getLogs call
+        #[arg(long, default_value_t = DEFAULT_CHUNK_SIZE)]
+        chunk_size: u64,
+        
+        /// RPC URL (comma-separated list for failover)
+        #[arg(short, long)]
+        rpc_url: Option<String>,
+    },
+    
+    /// Write indexed trades or orders to CSV or Parquet (with the `parquet` feature) for pandas, DuckDB and the like
+    Export {
+        #[command(subcommand)]
+        command: ExportCommands,
+    },
+    
+    /// List trading pairs registered on the DEX (from indexed TradingPairAdded events)
+    ListPairs {
+        /// DEX contract address (defaults to the latest recorded OrderBookDEX deployment)
+        #[arg(short, long, default_value = "OrderBookDEX")]
+        address: String,
+        
+        /// Block to start indexing from when no index exists yet
+        #[arg(long, default_value_t = 0)]
+        from_block: u64,
+        
+        /// Include deactivated pairs
+        #[arg(long)]
+        all: bool,
+        
+        /// RPC URL (comma-separated list for failover)
+        #[arg(short, long)]
+        rpc_url: Option<String>,
+    },
+    
+    /// Show a trading pair's status, minimum order size and price precision
+    PairInfo {
+        /// DEX contract address (defaults to the latest recorded OrderBookDEX deployment)
+        #[arg(short, long, default_value = "OrderBookDEX")]
+        address: String,
+        
+        /// Base token address
+        #[arg(short, long)]
+        base_token: String,
+        
+        /// Quote token address
+        #[arg(short, long)]
+        quote_token: String,
+        
+        /// RPC URL (comma-separated list for failover)
         #[arg(short, long)]
+        rpc_url: Option<String>,
+    },
+    
+    /// Estimate the average price, price impact and fees of a market order against the current book
+    Quote {
+        /// DEX contract address (defaults to the latest recorded OrderBookDEX deployment)
+        #[arg(short, long, default_value = "OrderBookDEX")]
         address: String,
         
         /// Base token address
@@ -69,27 +332,23 @@ enum Commands {
         #[arg(long)]
         amount: u64,
         
-        /// Order price
-        #[arg(long)]
-        price: u64,
-        
         /// Is buy order
         #[arg(long)]
         is_buy: bool,
         
-        /// Private key
-        #[arg(short, long)]
-        private_key: String,
+        /// Account to estimate gas for (needs its deposit approved); gas is not estimated without it
+        #[arg(long)]
+        from: Option<String>,
         
-        /// RPC URL
-        #[arg(short, long, default_value = "https://monad-testnet.g.alchemy.com/v2/hl5Gau0XVV37m-RDdhcRzqCh7ISwmOAe")]
-        rpc_url: String,
+        /// RPC URL (comma-separated list for failover)
+        #[arg(short, long)]
+        rpc_url: Option<String>,
     },
     
-    /// Place a market order
-    PlaceMarketOrder {
-        /// DEX contract address
-        #[arg(short, long)]
+    /// Indexed VWAP plus depth around mid and the largest resting walls of the live book
+    Analytics {
+        /// DEX contract address (defaults to the latest recorded OrderBookDEX deployment)
+        #[arg(short, long, default_value = "OrderBookDEX")]
         address: String,
         
         /// Base token address
@@ -100,478 +359,6765 @@ enum Commands {
         #[arg(short, long)]
         quote_token: String,
         
-        /// Order amount
+        /// VWAP over the last N indexed blocks (default: all indexed history)
         #[arg(long)]
-        amount: u64,
+        window_blocks: Option<u64>,
         
-        /// Is buy order
-        #[arg(long)]
-        is_buy: bool,
+        /// Depth bands around mid, in bps (comma-separated)
+        #[arg(long, value_delimiter = ',', default_value = "10,50,100")]
+        bands: Vec<u64>,
         
-        /// Private key
-        #[arg(short, long)]
-        private_key: String,
+        /// Number of largest price levels to report
+        #[arg(long, default_value_t = 5)]
+        walls: usize,
         
-        /// RPC URL
-        #[arg(short, long, default_value = "https://monad-testnet.g.alchemy.com/v2/hl5Gau0XVV37m-RDdhcRzqCh7ISwmOAe")]
-        rpc_url: String,
+        /// Block to start indexing from when no index exists yet
+        #[arg(long, default_value_t = 0)]
+        from_block: u64,
+        
+        /// RPC URL (comma-separated list for failover)
+        #[arg(short, long)]
+        rpc_url: Option<String>,
     },
     
-    /// Cancel an order
-    CancelOrder {
-        /// DEX contract address
-        #[arg(short, long)]
+    /// 24h ticker statistics per pair (volume, high, low, last, change) from indexed trades
+    Stats {
+        /// DEX contract address (defaults to the latest recorded OrderBookDEX deployment)
+        #[arg(short, long, default_value = "OrderBookDEX")]
         address: String,
         
-        /// Order ID
+        /// Base token address (default: every listed pair)
+        #[arg(short, long, requires = "quote_token")]
+        base_token: Option<String>,
+        
+        /// Quote token address
+        #[arg(short, long, requires = "base_token")]
+        quote_token: Option<String>,
+        
+        /// Rolling window (e.g. 24h, 1h, 7d)
+        #[arg(long, value_parser = humantime::parse_duration, default_value = "24h")]
+        window: Duration,
+        
+        /// Block to start indexing from when no index exists yet
+        #[arg(long, default_value_t = 0)]
+        from_block: u64,
+        
+        /// RPC URL (comma-separated list for failover)
         #[arg(short, long)]
-        order_id: u64,
+        rpc_url: Option<String>,
+    },
+    
+    /// Show balances, open orders, positions and PnL per pair (CSV output lists the pairs)
+    Portfolio {
+        /// DEX contract address (defaults to the latest recorded OrderBookDEX deployment)
+        #[arg(short, long, default_value = "OrderBookDEX")]
+        address: String,
         
-        /// Private key
+        /// User address
         #[arg(short, long)]
-        private_key: String,
+        user: String,
         
-        /// RPC URL
-        #[arg(short, long, default_value = "https://monad-testnet.g.alchemy.com/v2/hl5Gau0XVV37m-RDdhcRzqCh7ISwmOAe")]
-        rpc_url: String,
+        /// Block to start indexing from when no index exists yet
+        #[arg(long, default_value_t = 0)]
+        from_block: u64,
+        
+        /// RPC URL (comma-separated list for failover)
+        #[arg(short, long)]
+        rpc_url: Option<String>,
     },
     
-    /// Get order book for a trading pair
-    GetOrderBook {
-        /// DEX contract address
+    /// Net position per pair from indexed fills next to current balances, with average
+    /// cost and PnL marked to the current mid
+    Position {
+        /// DEX contract address (defaults to the latest recorded OrderBookDEX deployment)
+        #[arg(short, long, default_value = "OrderBookDEX")]
+        address: String,
+        
+        /// User address
+        #[arg(short, long)]
+        user: String,
+        
+        /// Only this base token's pair (needs --quote-token)
+        #[arg(short, long, requires = "quote_token")]
+        base_token: Option<String>,
+        
+        /// Only this quote token's pair (needs --base-token)
+        #[arg(short, long, requires = "base_token")]
+        quote_token: Option<String>,
+        
+        /// Block to start indexing from when no index exists yet
+        #[arg(long, default_value_t = 0)]
+        from_block: u64,
+        
+        /// Mark the pair to this price feed instead of the book mid (needs --base-token)
+        #[arg(long, requires = "base_token")]
+        mark_feed: Option<String>,
+        
+        /// RPC URL (comma-separated list for failover)
         #[arg(short, long)]
+        rpc_url: Option<String>,
+    },
+    
+    /// Run a trading strategy from a TOML config (combine with --paper to simulate)
+    Bot {
+        /// DEX contract address (defaults to the latest recorded OrderBookDEX deployment)
+        #[arg(short, long, default_value = "OrderBookDEX")]
         address: String,
         
-        /// Base token address
+        /// Bot config file
         #[arg(short, long)]
-        base_token: String,
+        config: String,
         
-        /// Quote token address
+        /// Quote even outside the configured price bands
+        #[arg(long)]
+        force: bool,
+        
+        /// Append every strategy input and decision to this event log, for replay-strategy
+        #[arg(long)]
+        record: Option<String>,
+        
+        /// Private key or --account (not needed with --paper)
         #[arg(short, long)]
-        quote_token: String,
+        private_key: Option<String>,
         
-        /// RPC URL
-        #[arg(short, long, default_value = "https://monad-testnet.g.alchemy.com/v2/hl5Gau0XVV37m-RDdhcRzqCh7ISwmOAe")]
-        rpc_url: String,
+        /// RPC URL (comma-separated list for failover)
+        #[arg(short, long)]
+        rpc_url: Option<String>,
     },
     
-    /// Get user's active orders
-    GetUserOrders {
-        /// DEX contract address
-        #[arg(short, long)]
+    /// Replay indexed history through a strategy and report PnL, fills and slippage
+    Backtest {
+        /// DEX contract address (defaults to the latest recorded OrderBookDEX deployment)
+        #[arg(short, long, default_value = "OrderBookDEX")]
         address: String,
         
-        /// User address
+        /// Bot config file
         #[arg(short, long)]
-        user: String,
+        config: String,
         
-        /// RPC URL
-        #[arg(short, long, default_value = "https://monad-testnet.g.alchemy.com/v2/hl5Gau0XVV37m-RDdhcRzqCh7ISwmOAe")]
-        rpc_url: String,
+        /// Block to start indexing from when no index exists yet
+        #[arg(long, default_value_t = 0)]
+        from_block: u64,
+        
+        /// RPC URL (comma-separated list for failover)
+        #[arg(short, long)]
+        rpc_url: Option<String>,
     },
     
-    /// Get user balance
-    GetBalance {
-        /// DEX contract address
+    /// Re-run a strategy against an event log recorded with `bot --record` and report
+    /// where its decisions differ from the recorded ones
+    ReplayStrategy {
+        /// Event log file
+        log: String,
+        
+        /// Bot config whose [strategy] replaces the recorded one, to test a change
         #[arg(short, long)]
+        config: Option<String>,
+    },
+    
+    /// Cross-pair and triangular arbitrage tools
+    Arb {
+        #[command(subcommand)]
+        command: ArbCommands,
+    },
+    
+    /// Watch alert rules from a TOML config and send notifications when they trigger
+    Alerts {
+        /// DEX contract address (defaults to the latest recorded OrderBookDEX deployment)
+        #[arg(short, long, default_value = "OrderBookDEX")]
         address: String,
         
-        /// User address
+        /// Alerts config file
         #[arg(short, long)]
-        user: String,
+        config: String,
         
-        /// Token address
+        /// RPC URL (comma-separated list for failover)
         #[arg(short, long)]
-        token: String,
+        rpc_url: Option<String>,
+    },
+    
+    /// Cancel orders placed with --expires-at/--ttl/--cancel-after once they lapse
+    ExpireOrders {
+        /// Seconds between checks
+        #[arg(long, default_value_t = 15)]
+        interval_secs: u64,
         
-        /// RPC URL
-        #[arg(short, long, default_value = "https://monad-testnet.g.alchemy.com/v2/hl5Gau0XVV37m-RDdhcRzqCh7ISwmOAe")]
-        rpc_url: String,
+        /// Check once and exit instead of running as a daemon
+        #[arg(long)]
+        once: bool,
+        
+        /// Private key (or --account); only orders placed by this key are cancelled
+        #[arg(short, long)]
+        private_key: Option<String>,
+        
+        /// RPC URL (comma-separated list for failover)
+        #[arg(short, long)]
+        rpc_url: Option<String>,
     },
     
-    /// Withdraw tokens from DEX
-    Withdraw {
-        /// DEX contract address
+    /// Manage the local paper-trading account used by --paper
+    Paper {
+        #[command(subcommand)]
+        command: PaperCommands,
+    },
+    
+    /// Show the DEX trading fee and its limits
+    GetFees {
+        /// DEX contract address (defaults to the latest recorded OrderBookDEX deployment)
+        #[arg(short, long, default_value = "OrderBookDEX")]
+        address: String,
+        
+        /// RPC URL (comma-separated list for failover)
         #[arg(short, long)]
+        rpc_url: Option<String>,
+    },
+    
+    /// Set the trading fee in basis points of the matched base amount (owner only)
+    SetFees {
+        /// DEX contract address (defaults to the latest recorded OrderBookDEX deployment)
+        #[arg(short, long, default_value = "OrderBookDEX")]
         address: String,
         
-        /// Token address
+        /// New fee (30 = 0.3%, at most MAX_TRADING_FEE)
         #[arg(short, long)]
-        token: String,
+        fee: u64,
         
-        /// Amount to withdraw
+        /// Propose and execute through this Gnosis Safe (the DEX owner) instead of sending from the key
+        #[arg(long)]
+        safe: Option<String>,
+        
+        /// Private key (or --account)
         #[arg(short, long)]
-        amount: u64,
+        private_key: Option<String>,
         
-        /// Private key
+        /// RPC URL (comma-separated list for failover)
         #[arg(short, long)]
-        private_key: String,
+        rpc_url: Option<String>,
+    },
+    
+    /// Owner-only DEX administration (asks for confirmation before sending)
+    Admin {
+        /// Propose and execute through this Gnosis Safe (the DEX owner) instead of sending from the key
+        #[arg(long, global = true)]
+        safe: Option<String>,
         
-        /// RPC URL
-        #[arg(short, long, default_value = "https://monad-testnet.g.alchemy.com/v2/hl5Gau0XVV37m-RDdhcRzqCh7ISwmOAe")]
-        rpc_url: String,
+        #[command(subcommand)]
+        command: AdminCommands,
     },
-}
-
-#[tokio::main]
-async fn main() -> Result<()> {
-    // Initialize logging
-    tracing_subscriber::fmt::init();
     
-    let cli = Cli::parse();
+    /// Manage named aliases usable in place of any address argument
+    Alias {
+        #[command(subcommand)]
+        command: AliasCommands,
+    },
+    
+    /// Manage the network presets selectable with --network
+    Network {
+        #[command(subcommand)]
+        command: NetworkCommands,
+    },
+    
+    /// Inspect the journal of transactions sent by the tools
+    Journal {
+        #[command(subcommand)]
+        command: JournalCommands,
+    },
+    
+    /// Sign transactions written by --prepare away from the network, and broadcast the
+    /// signed file from any online machine
+    Offline {
+        #[command(subcommand)]
+        command: OfflineCommands,
+    },
+    
+    /// Control a running monad-dexd over its socket
+    Ctl {
+        /// Control socket of the daemon
+        #[arg(long, default_value = daemon::DEFAULT_SOCKET_PATH, global = true)]
+        socket: String,
+        
+        #[command(subcommand)]
+        command: CtlCommands,
+    },
+    
+    /// Manage labeled, encrypted signing keys selectable with --account
+    Wallet {
+        #[command(subcommand)]
+        command: WalletCommands,
+    },
+    
+    /// Delegate trading to a hot key limited to placing and cancelling orders within set limits
+    Session {
+        #[command(subcommand)]
+        command: SessionCommands,
+    },
+    
+    /// Rebuild a pair's book at a historical block from indexed events, optionally stepping
+    /// forward block by block. Market orders emit no events; start from a snapshot taken after
+    /// them (`snapshot save --block`) to account for what they consumed.
+    Replay {
+        /// DEX contract address (defaults to the latest recorded OrderBookDEX deployment)
+        #[arg(short, long, default_value = "OrderBookDEX")]
+        address: String,
+        
+        /// Base token address (taken from the snapshot with --snapshot)
+        #[arg(short, long, required_unless_present = "snapshot")]
+        base_token: Option<String>,
+        
+        /// Quote token address (taken from the snapshot with --snapshot)
+        #[arg(short, long, required_unless_present = "snapshot")]
+        quote_token: Option<String>,
+        
+        /// Block to rebuild the book at (default: the snapshot's block)
+        #[arg(long, required_unless_present = "snapshot")]
+        block: Option<u64>,
+        
+        /// Snapshot file to start from instead of an empty book
+        #[arg(long)]
+        snapshot: Option<String>,
+        
+        /// After showing the book, step through later blocks with activity (Enter for next, q to quit)
+        #[arg(long)]
+        step: bool,
+        
+        /// Last block to step to (default: end of the index)
+        #[arg(long, requires = "step")]
+        to_block: Option<u64>,
+        
+        /// Block to start indexing from when no index exists yet
+        #[arg(long, default_value_t = 0)]
+        from_block: u64,
+        
+        /// RPC URL (comma-separated list for failover)
+        #[arg(short, long)]
+        rpc_url: Option<String>,
+    },
+    
+    /// Flood a (testnet) DEX with randomized limit orders and cancels from several funded
+    /// accounts, measuring throughput, confirmation latency and failure rates
+    Loadtest {
+        /// DEX contract address (defaults to the latest recorded OrderBookDEX deployment)
+        #[arg(short, long, default_value = "OrderBookDEX")]
+        address: String,
+        
+        /// Base token address
+        #[arg(short, long)]
+        base_token: String,
+        
+        /// Quote token address
+        #[arg(short, long)]
+        quote_token: String,
+        
+        /// File with one funded private key per line (default: the --private-key/--account signer)
+        #[arg(long)]
+        keys_file: Option<String>,
+        
+        /// Target transactions per second across all accounts
+        #[arg(long, default_value_t = 5.0)]
+        rate: f64,
+        
+        /// How long to keep sending (e.g. 60s, 10m)
+        #[arg(long, value_parser = humantime::parse_duration, default_value = "60s")]
+        duration: Duration,
+        
+        /// Share of transactions that cancel a resting order instead of placing one (0-1)
+        #[arg(long, default_value_t = 0.3)]
+        cancel_ratio: f64,
+        
+        /// Price orders around this (default: mid of the live book)
+        #[arg(long)]
+        mid_price: Option<u64>,
+        
+        /// Maximum distance from mid, in bps
+        #[arg(long, default_value_t = 100)]
+        spread_bps: u64,
+        
+        /// Smallest order amount (default: the pair's minimum order size)
+        #[arg(long)]
+        min_amount: Option<u64>,
+        
+        /// Largest order amount (default: 10x the smallest)
+        #[arg(long)]
+        max_amount: Option<u64>,
+        
+        /// Gas limit for every transaction, so sends skip gas estimation
+        #[arg(long, default_value_t = 500_000)]
+        gas_limit: u64,
+        
+        /// Count a transaction as timed out after this long without a receipt
+        #[arg(long, value_parser = humantime::parse_duration, default_value = "60s")]
+        receipt_timeout: Duration,
+        
+        /// How often to poll for receipts, in milliseconds
+        #[arg(long, default_value_t = 200)]
+        poll_ms: u64,
+        
+        /// Private key (or --account), used without --keys-file
+        #[arg(short, long)]
+        private_key: Option<String>,
+        
+        /// RPC URL (comma-separated list for failover)
+        #[arg(short, long)]
+        rpc_url: Option<String>,
+    },
+    
+    /// Save order book snapshots to files and compare them level by level
+    Snapshot {
+        #[command(subcommand)]
+        command: SnapshotCommands,
+    },
+    
+    /// Place orders at set times or recurring intervals (DCA)
+    Schedule {
+        #[command(subcommand)]
+        command: ScheduleCommands,
+    },
+    
+    /// Route a swap through an intermediate token when the direct book is thin
+    Route {
+        #[command(subcommand)]
+        command: RouteCommands,
+    },
+    
+    /// Watch pending DEX transactions
+    Mempool {
+        #[command(subcommand)]
+        command: MempoolCommands,
+    },
+    
+    /// Diagnose the RPC setup, the DEX contract and the wallet
+    Doctor {
+        #[command(subcommand)]
+        command: DoctorCommands,
+    },
+    
+    /// Call functions and read events of any contract, using its artifact or its verified
+    /// ABI from the block explorer
+    Contract {
+        #[command(subcommand)]
+        command: ContractCommands,
+    },
+    
+    /// Manage the local registry of token symbols and decimals
+    Tokens {
+        #[command(subcommand)]
+        command: TokenCommands,
+    },
+    
+    /// Fat-finger limits on how far limit prices may sit from the mid or a reference price
+    PriceBands {
+        #[command(subcommand)]
+        command: PriceBandCommands,
+    },
+    
+    /// Current price of a configured feed
+    Price {
+        /// Feed symbol, as given to `price-feed set`
+        symbol: String,
+    },
+    
+    /// External price feeds (Pyth, Chainlink, exchange tickers) used as references
+    PriceFeed {
+        #[command(subcommand)]
+        command: PriceFeedCommands,
+    },
+    
+    /// Halt every bot run from this directory until `resume`
+    Halt {
+        /// Recorded with the halt and sent to the bots' notification channels
+        #[arg(long, default_value = "Halted from the command line")]
+        reason: String,
+        
+        /// Have the bots cancel their resting orders
+        #[arg(long)]
+        cancel: bool,
+    },
+    
+    /// Lift a halt set by `halt` or a bot's circuit breaker
+    Resume,
+    
+    /// Emergency stop: halt the bots, cancel every open order and withdraw every DEX balance
+    Estop {
+        /// DEX contract address (defaults to the latest recorded OrderBookDEX deployment)
+        #[arg(short, long, default_value = "OrderBookDEX")]
+        address: String,
+        
+        /// Forward everything withdrawn to this address (e.g. a cold wallet)
+        #[arg(long)]
+        sweep_to: Option<String>,
+        
+        /// Block to start indexing from when no index exists yet
+        #[arg(long, default_value_t = 0)]
+        from_block: u64,
+        
+        /// Control socket of a running monad-dexd to halt
+        #[arg(long, default_value = daemon::DEFAULT_SOCKET_PATH)]
+        socket: String,
+        
+        /// Private key (or --account)
+        #[arg(short, long)]
+        private_key: Option<String>,
+        
+        /// RPC URL (comma-separated list for failover)
+        #[arg(short, long)]
+        rpc_url: Option<String>,
+    },
+    
+    /// Risk limits checked before every order from the CLI and bots
+    Risk {
+        #[command(subcommand)]
+        command: RiskCommands,
+    },
+    
+    /// Spending caps on withdrawals, order notional and mints from the CLI
+    Limits {
+        #[command(subcommand)]
+        command: LimitsCommands,
+    },
+    
+    /// Sign an order off-chain as EIP-712 typed data for a relayer to settle later
+    SignOrder {
+        /// DEX contract address (its EIP-712 domain is read from the contract)
+        #[arg(short, long, default_value = "OrderBookDEX")]
+        address: String,
+        
+        /// Base token address
+        #[arg(short, long)]
+        base_token: String,
+        
+        /// Quote token address
+        #[arg(short, long)]
+        quote_token: String,
+        
+        /// Order amount
+        #[arg(long)]
+        amount: u64,
+        
+        /// Order price
+        #[arg(long)]
+        price: u64,
+        
+        /// Is buy order
+        #[arg(long)]
+        is_buy: bool,
+        
+        /// Order nonce (defaults to the current time in milliseconds)
+        #[arg(long)]
+        nonce: Option<u64>,
+        
+        /// Seconds until the order expires
+        #[arg(long, default_value_t = 86400)]
+        expires_in: u64,
+        
+        /// Write the signed order to this file instead of stdout
+        #[arg(long)]
+        out: Option<String>,
+        
+        /// Private key of the maker (or --account)
+        #[arg(short, long)]
+        private_key: Option<String>,
+        
+        /// RPC URL (comma-separated list for failover)
+        #[arg(short, long)]
+        rpc_url: Option<String>,
+    },
+    
+    /// Check a signed order's signature, domain, expiry and pair parameters
+    VerifyOrder {
+        /// DEX contract address the order should settle on
+        #[arg(short, long, default_value = "OrderBookDEX")]
+        address: String,
+        
+        /// Signed order JSON produced by SignOrder
+        #[arg(short, long)]
+        file: String,
+        
+        /// RPC URL (comma-separated list for failover)
+        #[arg(short, long)]
+        rpc_url: Option<String>,
+    },
+    
+    /// Accept signed orders over HTTP, match them off-chain and settle matches on the DEX in batches
+    Relay {
+        /// DEX contract address (defaults to the latest recorded OrderBookDEX deployment)
+        #[arg(short, long, default_value = "OrderBookDEX")]
+        address: String,
+        
+        /// Address to serve the HTTP API on (POST /orders, GET /orders)
+        #[arg(long, default_value = "127.0.0.1:8547")]
+        listen: SocketAddr,
+        
+        /// Most matches settled in one transaction
+        #[arg(long, default_value_t = 20)]
+        batch_size: usize,
+        
+        /// Seconds between settlement rounds
+        #[arg(long, default_value_t = 10)]
+        batch_interval_secs: u64,
+        
+        /// Private key of the account paying for settlement
+        #[arg(short, long)]
+        private_key: Option<String>,
+        
+        /// RPC URL (comma-separated list for failover)
+        #[arg(short, long)]
+        rpc_url: Option<String>,
+    },
+    
+    /// Show the status, confirmations, gas used and decoded logs of a transaction
+    TxStatus {
+        /// Transaction hash
+        #[arg(long)]
+        tx_hash: String,
+        
+        /// RPC URL (comma-separated list for failover)
+        #[arg(short, long)]
+        rpc_url: Option<String>,
+    },
+    
+    /// Decode the DEX, token and WMON events of a transaction or a whole block; logs of
+    /// other contracts are decoded with their verified ABI from the explorer
+    DecodeLogs {
+        /// Transaction hash
+        #[arg(long, required_unless_present = "block", conflicts_with = "block")]
+        tx_hash: Option<String>,
+        
+        /// Block number
+        #[arg(long)]
+        block: Option<u64>,
+        
+        /// Only logs emitted by this contract
+        #[arg(long)]
+        address: Option<String>,
+        
+        /// RPC URL (comma-separated list for failover)
+        #[arg(short, long)]
+        rpc_url: Option<String>,
+    },
+    
+    /// Find where a failed transaction reverted with debug_traceTransaction: the chain of
+    /// failing calls and, given the contract's build artifact, the source line
+    Trace {
+        /// Transaction hash
+        #[arg(long)]
+        tx_hash: String,
+        
+        /// Artifact of the contract that reverted (defaults to the one the deployment
+        /// registry records at its address)
+        #[arg(long)]
+        artifact: Option<String>,
+        
+        /// RPC URL with the debug namespace (comma-separated list for failover)
+        #[arg(short, long)]
+        rpc_url: Option<String>,
+    },
+    
+    /// Rebroadcast a pending transaction with higher fees
+    BumpTx {
+        /// Hash of the pending transaction
+        #[arg(long)]
+        tx_hash: String,
+        
+        /// Private key (or --account)
+        #[arg(short, long)]
+        private_key: Option<String>,
+        
+        /// RPC URL (comma-separated list for failover)
+        #[arg(short, long)]
+        rpc_url: Option<String>,
+    },
+    
+    /// Cancel a pending transaction with a 0-value self-transfer at the same nonce
+    CancelTx {
+        /// Hash of the pending transaction
+        #[arg(long)]
+        tx_hash: String,
+        
+        /// Private key (or --account)
+        #[arg(short, long)]
+        private_key: Option<String>,
+        
+        /// RPC URL (comma-separated list for failover)
+        #[arg(short, long)]
+        rpc_url: Option<String>,
+    },
+}
+
+/// Arbitrage commands
+#[derive(Subcommand)]
+enum ArbCommands {
+    /// Watch the configured pairs and routes for price discrepancies beyond gas costs
+    Scan {
+        /// DEX contract address (defaults to the latest recorded OrderBookDEX deployment)
+        #[arg(short, long, default_value = "OrderBookDEX")]
+        address: String,
+        
+        /// Arbitrage config file
+        #[arg(short, long)]
+        config: String,
+        
+        /// Execute profitable routes with market orders (tokens must already be approved)
+        #[arg(long)]
+        execute: bool,
+        
+        /// Scan once and exit
+        #[arg(long)]
+        once: bool,
+        
+        /// Private key, or --account, required with --execute
+        #[arg(short, long)]
+        private_key: Option<String>,
+        
+        /// RPC URL (comma-separated list for failover)
+        #[arg(short, long)]
+        rpc_url: Option<String>,
+    },
+}
+
+/// Paper account management
+#[derive(Subcommand)]
+enum PaperCommands {
+    /// Credit simulated funds to the paper account
+    Fund {
+        /// Token address (0x0 for the native token)
+        #[arg(short, long)]
+        token: String,
+        
+        /// Amount to credit
+        #[arg(short, long)]
+        amount: u64,
+    },
+    
+    /// Show paper balances, open orders and recent fills
+    Status,
+    
+    /// Delete the paper account and start over
+    Reset,
+}
+
+#[derive(Subcommand)]
+enum AdminCommands {
+    /// Stop new orders on one trading pair
+    PausePair {
+        /// DEX contract address (defaults to the latest recorded OrderBookDEX deployment)
+        #[arg(short, long, default_value = "OrderBookDEX")]
+        address: String,
+        
+        /// Base token address
+        #[arg(short, long)]
+        base_token: String,
+        
+        /// Quote token address
+        #[arg(short, long)]
+        quote_token: String,
+        
+        /// Private key (or --account)
+        #[arg(short, long)]
+        private_key: Option<String>,
+        
+        /// RPC URL (comma-separated list for failover)
+        #[arg(short, long)]
+        rpc_url: Option<String>,
+    },
+    
+    /// Accept new orders on a paused trading pair again
+    UnpausePair {
+        /// DEX contract address (defaults to the latest recorded OrderBookDEX deployment)
+        #[arg(short, long, default_value = "OrderBookDEX")]
+        address: String,
+        
+        /// Base token address
+        #[arg(short, long)]
+        base_token: String,
+        
+        /// Quote token address
+        #[arg(short, long)]
+        quote_token: String,
+        
+        /// Private key (or --account)
+        #[arg(short, long)]
+        private_key: Option<String>,
+        
+        /// RPC URL (comma-separated list for failover)
+        #[arg(short, long)]
+        rpc_url: Option<String>,
+    },
+    
+    /// Stop order placement on every pair (cancels and withdrawals keep working)
+    Pause {
+        /// DEX contract address (defaults to the latest recorded OrderBookDEX deployment)
+        #[arg(short, long, default_value = "OrderBookDEX")]
+        address: String,
+        
+        /// Private key (or --account)
+        #[arg(short, long)]
+        private_key: Option<String>,
+        
+        /// RPC URL (comma-separated list for failover)
+        #[arg(short, long)]
+        rpc_url: Option<String>,
+    },
+    
+    /// Resume order placement after Pause
+    Unpause {
+        /// DEX contract address (defaults to the latest recorded OrderBookDEX deployment)
+        #[arg(short, long, default_value = "OrderBookDEX")]
+        address: String,
+        
+        /// Private key (or --account)
+        #[arg(short, long)]
+        private_key: Option<String>,
+        
+        /// RPC URL (comma-separated list for failover)
+        #[arg(short, long)]
+        rpc_url: Option<String>,
+    },
+    
+    /// Change a trading pair's minimum order size and price precision
+    UpdatePair {
+        /// DEX contract address (defaults to the latest recorded OrderBookDEX deployment)
+        #[arg(short, long, default_value = "OrderBookDEX")]
+        address: String,
+        
+        /// Base token address
+        #[arg(short, long)]
+        base_token: String,
+        
+        /// Quote token address
+        #[arg(short, long)]
+        quote_token: String,
+        
+        /// New minimum order size
+        #[arg(short, long)]
+        min_order_size: u64,
+        
+        /// New price precision
+        #[arg(long)]
+        price_precision: u64,
+        
+        /// Private key (or --account)
+        #[arg(short, long)]
+        private_key: Option<String>,
+        
+        /// RPC URL (comma-separated list for failover)
+        #[arg(short, long)]
+        rpc_url: Option<String>,
+    },
+    
+    /// Hand DEX ownership to another address
+    TransferOwnership {
+        /// DEX contract address (defaults to the latest recorded OrderBookDEX deployment)
+        #[arg(short, long, default_value = "OrderBookDEX")]
+        address: String,
+        
+        /// New owner address
+        #[arg(short, long)]
+        new_owner: String,
+        
+        /// Private key (or --account)
+        #[arg(short, long)]
+        private_key: Option<String>,
+        
+        /// RPC URL (comma-separated list for failover)
+        #[arg(short, long)]
+        rpc_url: Option<String>,
+    },
+}
+
+#[derive(Subcommand)]
+enum AliasCommands {
+    /// Register a name for an address
+    Add {
+        /// Alias name (letters, digits, '_' and '-')
+        name: String,
+        
+        /// Address the alias stands for
+        address: String,
+        
+        /// Replace an existing alias with the same name
+        #[arg(long)]
+        force: bool,
+    },
+    
+    /// Delete an alias
+    Remove {
+        name: String,
+    },
+    
+    /// List all aliases
+    List,
+}
+
+#[derive(Subcommand)]
+enum NetworkCommands {
+    /// Save a custom network, or override a built-in one
+    Add {
+        /// Network name, used with --network
+        name: String,
+        
+        /// Chain ID the RPC endpoints must report
+        #[arg(long)]
+        chain_id: u64,
+        
+        /// RPC URLs (comma-separated list for failover)
+        #[arg(short, long)]
+        rpc_url: String,
+        
+        /// Block explorer web UI
+        #[arg(long)]
+        explorer_url: Option<String>,
+        
+        /// Etherscan-compatible explorer API, for verified ABIs
+        #[arg(long)]
+        explorer_api: Option<String>,
+        
+        /// Default contract address as NAME=ADDRESS, e.g. OrderBookDEX=0x...; repeatable
+        #[arg(long = "contract")]
+        contracts: Vec<String>,
+        
+        /// Header sent to the RPC URLs as "NAME: VALUE"; repeatable. Values may use ${VAR}
+        /// to read an environment variable when sending instead of saving the secret
+        #[arg(long = "header")]
+        headers: Vec<String>,
+        
+        /// Bearer token sent to the RPC URLs (may use ${VAR})
+        #[arg(long)]
+        bearer_token: Option<String>,
+        
+        /// Basic auth for the RPC URLs as USER:PASSWORD (may use ${VAR})
+        #[arg(long)]
+        basic_auth: Option<String>,
+        
+        /// Mark the network read-only: signing and sending transactions is refused while it
+        /// is selected, e.g. for a config shared with analysts
+        #[arg(long)]
+        mark_read_only: bool,
+    },
+    
+    /// Delete a saved network
+    Remove {
+        name: String,
+    },
+    
+    /// List built-in and saved networks
+    List,
+    
+    /// Show the settings of a network (defaults to the one selected with --network)
+    Show {
+        name: Option<String>,
+    },
+}
+
+#[derive(Subcommand)]
+enum CtlCommands {
+    /// Show the daemon's tasks, index progress and any trading halt
+    Status,
+    
+    /// Halt the daemon's bots and have them cancel their resting orders
+    CancelAll,
+    
+    /// Lift a halt so the bots quote again
+    Resume,
+    
+    /// Re-read the bot and alerts configs
+    Reload,
+    
+    /// Shut the daemon down
+    Stop,
+}
+
+#[derive(Subcommand)]
+enum JournalCommands {
+    /// List recent transactions, newest first
+    List {
+        /// Only transactions with this status
+        #[arg(long, value_enum)]
+        status: Option<TxStatus>,
+        
+        /// Number of transactions to show
+        #[arg(long, default_value_t = 20)]
+        limit: usize,
+    },
+    
+    /// Show one transaction by journal ID, transaction hash or idempotency key
+    Show {
+        entry: String,
+    },
+    
+    /// List batch runs (place-orders, airdrop, deployment pipelines) and their progress
+    Runs {
+        /// Include finished and abandoned runs
+        #[arg(long)]
+        all: bool,
+    },
+}
+
+#[derive(Subcommand)]
+enum ExportCommands {
+    /// Every matched trade: block, transaction, pair, order IDs, amount, price and fee
+    Trades {
+        /// DEX contract address (defaults to the latest recorded OrderBookDEX deployment)
+        #[arg(short, long, default_value = "OrderBookDEX")]
+        address: String,
+        
+        /// Only this base token's pair (needs --quote-token)
+        #[arg(short, long, requires = "quote_token")]
+        base_token: Option<String>,
+        
+        /// Only this quote token's pair (needs --base-token)
+        #[arg(short, long, requires = "base_token")]
+        quote_token: Option<String>,
+        
+        /// Only events from this block on
+        #[arg(long)]
+        start_block: Option<u64>,
+        
+        /// Only events up to this block
+        #[arg(long)]
+        end_block: Option<u64>,
+        
+        /// File format
+        #[arg(long, value_enum, default_value_t = ExportFormat::Csv)]
+        format: ExportFormat,
+        
+        /// Output file ("-" for stdout, CSV only; default: trades.csv or trades.parquet)
+        #[arg(long)]
+        out: Option<String>,
+        
+        /// Block to start indexing from when no index exists yet
+        #[arg(long, default_value_t = 0)]
+        from_block: u64,
+        
+        /// RPC URL (comma-separated list for failover)
+        #[arg(short, long)]
+        rpc_url: Option<String>,
+    },
+    
+    /// Every placed order, with the amount filled so far and whether it was cancelled
+    Orders {
+        /// DEX contract address (defaults to the latest recorded OrderBookDEX deployment)
+        #[arg(short, long, default_value = "OrderBookDEX")]
+        address: String,
+        
+        /// Only this base token's pair (needs --quote-token)
+        #[arg(short, long, requires = "quote_token")]
+        base_token: Option<String>,
+        
+        /// Only this quote token's pair (needs --base-token)
+        #[arg(short, long, requires = "base_token")]
+        quote_token: Option<String>,
+        
+        /// Only events from this block on
+        #[arg(long)]
+        start_block: Option<u64>,
+        
+        /// Only events up to this block
+        #[arg(long)]
+        end_block: Option<u64>,
+        
+        /// File format
+        #[arg(long, value_enum, default_value_t = ExportFormat::Csv)]
+        format: ExportFormat,
+        
+        /// Output file ("-" for stdout, CSV only; default: orders.csv or orders.parquet)
+        #[arg(long)]
+        out: Option<String>,
+        
+        /// Block to start indexing from when no index exists yet
+        #[arg(long, default_value_t = 0)]
+        from_block: u64,
+        
+        /// RPC URL (comma-separated list for failover)
+        #[arg(short, long)]
+        rpc_url: Option<String>,
+    },
+}
+
+#[derive(Subcommand)]
+enum OfflineCommands {
+    /// List the transactions in a prepared or signed file
+    Show {
+        file: String,
+    },
+    
+    /// Sign a file written by --prepare with --private-key or --account; never touches the network
+    Sign {
+        /// Prepared transaction file
+        file: String,
+        
+        /// Where to write the signed transactions
+        #[arg(long, default_value = "signed-txs.json")]
+        out: String,
+        
+        /// Private key (or --account)
+        #[arg(short, long)]
+        private_key: Option<String>,
+    },
+    
+    /// Broadcast a file written by `offline sign`, waiting for each transaction to be mined
+    Broadcast {
+        /// Signed transaction file
+        file: String,
+        
+        /// RPC URL (comma-separated list for failover)
+        #[arg(short, long)]
+        rpc_url: Option<String>,
+    },
+    
+    /// Show a prepared or signed file as QR codes to scan on the other machine, cycling
+    /// through several frames when it doesn't fit in one
+    QrShow {
+        file: String,
+        
+        /// Hex characters per frame; lower it if the scanner struggles
+        #[arg(long, default_value_t = qr::DEFAULT_FRAME_SIZE)]
+        frame_size: usize,
+        
+        /// How long each frame stays up, in milliseconds
+        #[arg(long, default_value_t = 500)]
+        interval_ms: u64,
+        
+        /// Show every frame once instead of cycling until Ctrl-C
+        #[arg(long)]
+        once: bool,
+    },
+    
+    /// Read frames shown by `offline qr-show` from a scanner typing them on stdin, one per
+    /// line, and write the file they carry
+    QrRead {
+        /// Where to write the scanned file
+        #[arg(long)]
+        out: String,
+    },
+}
+
+#[derive(Subcommand)]
+enum PriceBandCommands {
+    /// Set the bands applied to limit orders and to bots whose config has no [price_bands]
+    Set {
+        /// Largest distance from the book's mid price, in basis points
+        #[arg(long)]
+        max_mid_deviation_bps: Option<u32>,
+        
+        /// Reference price in DEX price units: a number, a JSON URL with a pointer
+        /// fragment (e.g. https://host/price#/mon/usdc), or feed:<SYMBOL>
+        #[arg(long, requires = "max_reference_deviation_bps")]
+        reference: Option<String>,
+        
+        /// Largest distance from the reference price, in basis points
+        #[arg(long, requires = "reference")]
+        max_reference_deviation_bps: Option<u32>,
+    },
+    
+    /// Show the current bands
+    Show,
+    
+    /// Remove all bands
+    Clear,
+}
+
+#[derive(Subcommand)]
+enum PriceFeedCommands {
+    /// Add or replace a feed. Sources are tried in order until one has a fresh price.
+    Set {
+        /// Symbol to refer to the feed by (e.g. MON-USDC, or feed:MON-USDC as a price source)
+        symbol: String,
+        
+        /// pyth:<price id>, chainlink:<aggregator>, binance:<symbol>, coinbase:<product>,
+        /// a number, or a JSON URL with a pointer fragment (repeat for fallbacks)
+        #[arg(long = "source", required = true)]
+        sources: Vec<String>,
+        
+        /// Skip prices published longer ago than this many seconds
+        #[arg(long, default_value_t = 60)]
+        max_age_secs: u64,
+        
+        /// Multiplier from the source's price to DEX price units
+        #[arg(long, default_value_t = 1.0)]
+        scale: f64,
+        
+        /// Node to read Chainlink aggregators from
+        #[arg(long)]
+        rpc_url: Option<String>,
+    },
+    
+    /// List the configured feeds
+    List,
+    
+    /// Remove a feed
+    Remove {
+        symbol: String,
+    },
+}
+
+#[derive(Subcommand)]
+enum RiskCommands {
+    /// Set the risk limits; limits left out are turned off. Amounts are raw token units.
+    Set {
+        /// Open orders across all pairs
+        #[arg(long)]
+        max_open_orders: Option<usize>,
+        
+        /// Quote notional of open orders in one pair
+        #[arg(long)]
+        max_pair_notional: Option<u64>,
+        
+        /// Net base position in one pair, counting open orders as filled
+        #[arg(long)]
+        max_position: Option<u64>,
+        
+        /// Realized quote loss in one pair since 00:00 UTC, after which new orders are refused
+        #[arg(long)]
+        max_daily_loss: Option<u64>,
+        
+        /// Block to start indexing fills from when no index exists yet
+        #[arg(long, default_value_t = 0)]
+        index_from_block: u64,
+    },
+    
+    /// Remove all risk limits
+    Clear,
+    
+    /// Show how much of each limit a trader is using in one pair
+    Status {
+        /// DEX contract address (defaults to the latest recorded OrderBookDEX deployment)
+        #[arg(short, long, default_value = "OrderBookDEX")]
+        address: String,
+        
+        /// Base token address
+        #[arg(short, long)]
+        base_token: String,
+        
+        /// Quote token address
+        #[arg(short, long)]
+        quote_token: String,
+        
+        /// Trader to report on (default: the --private-key or --account address)
+        #[arg(long)]
+        trader: Option<String>,
+        
+        /// Private key (or --account), used only for its address
+        #[arg(short, long)]
+        private_key: Option<String>,
+        
+        /// RPC URL (comma-separated list for failover)
+        #[arg(short, long)]
+        rpc_url: Option<String>,
+    },
+}
+
+#[derive(Subcommand)]
+enum LimitsCommands {
+    /// Set the spending caps; caps left out are turned off. Amounts are raw units of the
+    /// token spent (the quote token for order notional), capped per token.
+    Set {
+        /// Largest withdrawal one command may make
+        #[arg(long)]
+        max_withdrawal: Option<u64>,
+        
+        /// Total withdrawn since 00:00 UTC
+        #[arg(long)]
+        max_daily_withdrawal: Option<u64>,
+        
+        /// Largest order notional one command may place
+        #[arg(long)]
+        max_order_notional: Option<u64>,
+        
+        /// Total order notional placed since 00:00 UTC
+        #[arg(long)]
+        max_daily_order_notional: Option<u64>,
+        
+        /// Largest mint one command may make
+        #[arg(long)]
+        max_mint: Option<u64>,
+        
+        /// Total minted since 00:00 UTC
+        #[arg(long)]
+        max_daily_mint: Option<u64>,
+    },
+    
+    /// Remove all spending caps
+    Clear,
+    
+    /// Show the caps and what has been spent against them today
+    Status,
+}
+
+#[derive(Subcommand)]
+enum WalletCommands {
+    /// Encrypt a private key into a keystore under a label
+    /// (password from MONAD_WALLET_PASSWORD or prompted)
+    Add {
+        /// Wallet label (letters, digits, '_' and '-')
+        label: String,
+        
+        /// Private key to store
+        #[arg(short, long)]
+        private_key: String,
+    },
+    
+    /// Delete a stored wallet and its keystore
+    Remove {
+        label: String,
+    },
+    
+    /// List stored wallets
+    List,
+}
+
+#[derive(Subcommand)]
+enum SessionCommands {
+    /// Restrict a key to order placement and cancellation within notional and token limits
+    Grant {
+        /// Address of the delegated (hot) key
+        #[arg(long)]
+        key: String,
+        
+        /// Tokens the key may trade (comma-separated addresses or aliases)
+        #[arg(long, value_delimiter = ',', required = true)]
+        tokens: Vec<String>,
+        
+        /// Largest quote notional of a single order
+        #[arg(long)]
+        max_order_notional: u64,
+        
+        /// Quote notional the key may place over the whole session
+        #[arg(long)]
+        max_total_notional: u64,
+        
+        /// Seconds until the session expires (default: until revoked)
+        #[arg(long)]
+        ttl_secs: Option<u64>,
+        
+        /// Approve the key to pull up to this much of each token from the granting wallet,
+        /// so order deposits are funded just in time instead of held by the key
+        #[arg(long)]
+        allowance: Option<u64>,
+        
+        /// Private key of the granting wallet, needed with --allowance (or --account)
+        #[arg(short, long)]
+        private_key: Option<String>,
+        
+        /// RPC URL (comma-separated list for failover)
+        #[arg(short, long)]
+        rpc_url: Option<String>,
+    },
+    
+    /// End a key's session; it keeps its restrictions but can no longer place orders
+    Revoke {
+        /// Address of the delegated key
+        #[arg(long)]
+        key: String,
+    },
+    
+    /// List session keys and their limits
+    List,
+}
+
+#[derive(Subcommand)]
+enum SnapshotCommands {
+    /// Save a pair's full book to a compact JSON file
+    Save {
+        /// DEX contract address (defaults to the latest recorded OrderBookDEX deployment)
+        #[arg(short, long, default_value = "OrderBookDEX")]
+        address: String,
+        
+        /// Base token address
+        #[arg(short, long)]
+        base_token: String,
+        
+        /// Quote token address
+        #[arg(short, long)]
+        quote_token: String,
+        
+        /// Block to read the book at (default: latest)
+        #[arg(long)]
+        block: Option<u64>,
+        
+        /// Output file (default: config/snapshots/<base>-<quote>-<block>.json)
+        #[arg(short, long)]
+        out: Option<String>,
+        
+        /// RPC URL (comma-separated list for failover)
+        #[arg(short, long)]
+        rpc_url: Option<String>,
+    },
+    
+    /// Show the book stored in a snapshot file
+    Load {
+        file: String,
+    },
+    
+    /// Show level-by-level differences between two snapshots of the same book
+    Diff {
+        /// Earlier snapshot
+        before: String,
+        
+        /// Later snapshot
+        after: String,
+    },
+}
+
+#[derive(Subcommand)]
+enum ScheduleCommands {
+    /// Schedule an order, e.g. buy 100 every hour for 24 hours with --every 1h --for 24h
+    Add {
+        /// DEX contract address (defaults to the latest recorded OrderBookDEX deployment)
+        #[arg(short, long, default_value = "OrderBookDEX")]
+        address: String,
+        
+        /// Base token address
+        #[arg(short, long)]
+        base_token: String,
+        
+        /// Quote token address
+        #[arg(short, long)]
+        quote_token: String,
+        
+        /// Order amount per run
+        #[arg(long)]
+        amount: u64,
+        
+        /// Limit price (omit to place market orders)
+        #[arg(long)]
+        price: Option<u64>,
+        
+        /// Is buy order
+        #[arg(long)]
+        is_buy: bool,
+        
+        /// First run (RFC 3339, e.g. 2025-06-01T12:00:00Z; default: now)
+        #[arg(long, value_parser = humantime::parse_rfc3339_weak)]
+        start_at: Option<SystemTime>,
+        
+        /// Time between runs (e.g. 1h, 30m); omit for a one-off order
+        #[arg(long, value_parser = humantime::parse_duration)]
+        every: Option<Duration>,
+        
+        /// Number of runs
+        #[arg(long, conflicts_with = "duration")]
+        runs: Option<u32>,
+        
+        /// Keep running for this long (e.g. 24h), instead of --runs
+        #[arg(long = "for", value_parser = humantime::parse_duration, requires = "every")]
+        duration: Option<Duration>,
+    },
+    
+    /// List schedules and their progress
+    List,
+    
+    /// Show the run log of a schedule
+    Logs {
+        id: u64,
+    },
+    
+    /// Stop a schedule; runs already placed are left as they are
+    Cancel {
+        id: u64,
+    },
+    
+    /// Place scheduled orders as they come due
+    Run {
+        /// Seconds between checks
+        #[arg(long, default_value_t = 15)]
+        interval_secs: u64,
+        
+        /// Run whatever is due once and exit instead of running as a daemon
+        #[arg(long)]
+        once: bool,
+        
+        /// Private key (or --account)
+        #[arg(short, long)]
+        private_key: Option<String>,
+        
+        /// RPC URL (comma-separated list for failover)
+        #[arg(short, long)]
+        rpc_url: Option<String>,
+    },
+}
+
+#[derive(Subcommand)]
+enum RouteCommands {
+    /// Preview the best split of a swap between the direct pair and a route through `--via`
+    Quote {
+        /// DEX contract address (defaults to the latest recorded OrderBookDEX deployment)
+        #[arg(short, long, default_value = "OrderBookDEX")]
+        address: String,
+        
+        /// Token to spend
+        #[arg(long)]
+        from: String,
+        
+        /// Token to receive
+        #[arg(long)]
+        to: String,
+        
+        /// Intermediate token for the indirect route
+        #[arg(long, default_value = "WMON")]
+        via: String,
+        
+        /// Amount of `from` to spend
+        #[arg(long)]
+        amount: u64,
+        
+        /// Number of direct/indirect splits to compare (10 tries 0%, 10%, ..., 100% direct)
+        #[arg(long, default_value_t = 10)]
+        steps: usize,
+        
+        /// Gas budgeted per transaction when netting out costs
+        #[arg(long, default_value_t = 300_000)]
+        gas_per_tx: u64,
+        
+        /// RPC URL (comma-separated list for failover)
+        #[arg(short, long)]
+        rpc_url: Option<String>,
+    },
+    
+    /// Execute the best split with market orders (tokens must already be approved)
+    Execute {
+        /// DEX contract address (defaults to the latest recorded OrderBookDEX deployment)
+        #[arg(short, long, default_value = "OrderBookDEX")]
+        address: String,
+        
+        /// Token to spend
+        #[arg(long)]
+        from: String,
+        
+        /// Token to receive
+        #[arg(long)]
+        to: String,
+        
+        /// Intermediate token for the indirect route
+        #[arg(long, default_value = "WMON")]
+        via: String,
+        
+        /// Amount of `from` to spend
+        #[arg(long)]
+        amount: u64,
+        
+        /// Number of direct/indirect splits to compare (10 tries 0%, 10%, ..., 100% direct)
+        #[arg(long, default_value_t = 10)]
+        steps: usize,
+        
+        /// Gas budgeted per transaction when netting out costs
+        #[arg(long, default_value_t = 300_000)]
+        gas_per_tx: u64,
+        
+        /// Private key (or --account)
+        #[arg(short, long)]
+        private_key: Option<String>,
+        
+        /// RPC URL (comma-separated list for failover)
+        #[arg(short, long)]
+        rpc_url: Option<String>,
+    },
+}
+
+#[derive(Subcommand)]
+enum MempoolCommands {
+    /// Print order placements and cancellations sent to the DEX as they enter the mempool
+    Watch {
+        /// DEX contract address (defaults to the latest recorded OrderBookDEX deployment)
+        #[arg(short, long, default_value = "OrderBookDEX")]
+        address: String,
+        
+        /// Only show this base token's pair (requires --quote-token)
+        #[arg(short, long, requires = "quote_token")]
+        base_token: Option<String>,
+        
+        /// Only show this quote token's pair (requires --base-token)
+        #[arg(short, long, requires = "base_token")]
+        quote_token: Option<String>,
+        
+        /// Milliseconds between mempool polls
+        #[arg(long, default_value_t = 500)]
+        interval_ms: u64,
+        
+        /// RPC URL; the endpoint must support pending transaction filters
+        #[arg(short, long)]
+        rpc_url: Option<String>,
+    },
+}
+
+#[derive(Subcommand)]
+enum ContractCommands {
+    /// Call a function; view functions are read, others are sent as a transaction
+    Call {
+        /// Contract address or alias
+        #[arg(short, long)]
+        address: String,
+        
+        /// Function name, e.g. balanceOf
+        function: String,
+        
+        /// Function arguments, in order
+        args: Vec<String>,
+        
+        /// Artifact in out/ to take the ABI from instead of the explorer
+        #[arg(long)]
+        artifact: Option<String>,
+        
+        /// Native token to send with the call, in wei
+        #[arg(long)]
+        value: Option<String>,
+        
+        /// Private key of the sender, for functions that aren't view
+        #[arg(short, long)]
+        private_key: Option<String>,
+        
+        /// RPC URL (comma-separated list for failover)
+        #[arg(short, long)]
+        rpc_url: Option<String>,
+    },
+    
+    /// Show the contract's events in a block range, decoded
+    Logs {
+        /// Contract address or alias
+        #[arg(short, long)]
+        address: String,
+        
+        /// First block to search (defaults to 1000 blocks before the last)
+        #[arg(long)]
+        from_block: Option<u64>,
+        
+        /// Last block to search (defaults to the latest)
+        #[arg(long)]
+        to_block: Option<u64>,
+        
+        /// Artifact in out/ to take the ABI from instead of the explorer
+        #[arg(long)]
+        artifact: Option<String>,
+        
+        /// RPC URL (comma-separated list for failover)
+        #[arg(short, long)]
+        rpc_url: Option<String>,
+    },
+    
+    /// Show the functions and events in the contract's ABI
+    Abi {
+        /// Contract address or alias
+        #[arg(short, long)]
+        address: String,
+        
+        /// Fetch the ABI from the explorer again instead of using the cached one
+        #[arg(long)]
+        refresh: bool,
+        
+        /// RPC URL (comma-separated list for failover)
+        #[arg(short, long)]
+        rpc_url: Option<String>,
+    },
+}
+
+#[derive(Subcommand)]
+enum DoctorCommands {
+    /// Check that the DEX exposes the functions these tools call and which known release
+    /// its code is
+    Compat {
+        /// DEX contract address (defaults to the latest recorded OrderBookDEX deployment)
+        #[arg(short, long, default_value = "OrderBookDEX")]
+        address: String,
+        
+        /// RPC URL (comma-separated list for failover)
+        #[arg(short, long)]
+        rpc_url: Option<String>,
+    },
+    
+    /// Manage the code hashes of known DEX releases
+    Releases {
+        #[command(subcommand)]
+        command: ReleaseCommands,
+    },
+    
+    /// Check RPC reachability and chain ID, the DEX contract's code and interface, and the
+    /// wallet's gas, token balances and allowances, printing a fix for each problem
+    Check {
+        /// DEX contract address (defaults to the latest recorded OrderBookDEX deployment)
+        #[arg(short, long, default_value = "OrderBookDEX")]
+        address: String,
+        
+        /// Wallet address to check (defaults to the private key's or --account's)
+        #[arg(short, long)]
+        wallet: Option<String>,
+        
+        /// Private key of the wallet to check; only its address is used
+        #[arg(short, long)]
+        private_key: Option<String>,
+        
+        /// Token to check the wallet balance and DEX allowance of (repeatable)
+        #[arg(short, long = "token")]
+        tokens: Vec<String>,
+        
+        /// Also check the base and quote tokens of this bot config
+        #[arg(short, long)]
+        config: Option<String>,
+        
+        /// RPC URL (comma-separated list; each endpoint is checked)
+        #[arg(short, long)]
+        rpc_url: Option<String>,
+    },
+    
+    /// Measure p50/p95/p99 latency of getOrderBook calls, getLogs and transaction submission
+    /// on each endpoint, and save the fastest-first order used by every command
+    Bench {
+        /// DEX contract address (defaults to the latest recorded OrderBookDEX deployment)
+        #[arg(short, long, default_value = "OrderBookDEX")]
+        address: String,
+        
+        /// Base token of the book to read
+        #[arg(short, long)]
+        base_token: String,
+        
+        /// Quote token of the book to read
+        #[arg(short, long)]
+        quote_token: String,
+        
+        /// Requests per method per endpoint
+        #[arg(long, default_value_t = 20)]
+        samples: usize,
+        
+        /// Only report; leave the saved endpoint order as it is
+        #[arg(long)]
+        no_save: bool,
+        
+        /// Endpoints to compare (comma-separated)
+        #[arg(short, long)]
+        rpc_url: Option<String>,
+    },
+}
+
+#[derive(Subcommand)]
+enum ReleaseCommands {
+    /// Record the code deployed at a DEX address as a known release
+    Add {
+        /// Release name, e.g. v1.2.0
+        name: String,
+        
+        /// DEX contract address (defaults to the latest recorded OrderBookDEX deployment)
+        #[arg(short, long, default_value = "OrderBookDEX")]
+        address: String,
+        
+        /// RPC URL (comma-separated list for failover)
+        #[arg(short, long)]
+        rpc_url: Option<String>,
+    },
+    
+    /// List known releases
+    List,
+    
+    /// Forget a release
+    Remove {
+        name: String,
+    },
+    
+    /// Refuse (true) or allow (false) trading against code that matches no known release
+    Require {
+        #[arg(action = clap::ArgAction::Set)]
+        enabled: bool,
+    },
+}
+
+#[derive(Subcommand)]
+enum TokenCommands {
+    /// Fetch a token's symbol and decimals and add it to the registry
+    Add {
+        /// Token address
+        token: String,
+        
+        /// RPC URL (comma-separated list for failover)
+        #[arg(short, long)]
+        rpc_url: Option<String>,
+    },
+    
+    /// Refetch a token's metadata
+    Refresh {
+        /// Token address
+        token: String,
+        
+        /// RPC URL (comma-separated list for failover)
+        #[arg(short, long)]
+        rpc_url: Option<String>,
+    },
+    
+    /// Remove a token from the registry
+    Remove {
+        /// Token address
+        token: String,
+    },
+    
+    /// List known tokens
+    List,
+}
+
+/// Commands that send a transaction
+#[derive(Subcommand)]
+enum WriteCommands {
+    /// Add a new trading pair (owner only)
+    AddTradingPair {
+        /// DEX contract address (defaults to the latest recorded OrderBookDEX deployment)
+        #[arg(short, long, default_value = "OrderBookDEX")]
+        address: String,
+        
+        /// Base token address
+        #[arg(short, long)]
+        base_token: String,
+        
+        /// Quote token address
+        #[arg(short, long)]
+        quote_token: String,
+        
+        /// Minimum order size
+        #[arg(short, long)]
+        min_order_size: u64,
+        
+        /// Price precision
+        #[arg(long)]
+        price_precision: u64,
+        
+        /// Propose and execute through this Gnosis Safe (the DEX owner) instead of sending from the key
+        #[arg(long)]
+        safe: Option<String>,
+        
+        /// Private key (or --account)
+        #[arg(short, long)]
+        private_key: Option<String>,
+        
+        /// RPC URL (comma-separated list for failover)
+        #[arg(short, long)]
+        rpc_url: Option<String>,
+    },
+    
+    /// Place a limit order
+    PlaceLimitOrder {
+        /// DEX contract address (defaults to the latest recorded OrderBookDEX deployment)
+        #[arg(short, long, default_value = "OrderBookDEX")]
+        address: String,
+        
+        /// Base token address
+        #[arg(short, long)]
+        base_token: String,
+        
+        /// Quote token address
+        #[arg(short, long)]
+        quote_token: String,
+        
+        /// Order amount
+        #[arg(long)]
+        amount: u64,
+        
+        /// Order price
+        #[arg(long)]
+        price: u64,
+        
+        /// Is buy order
+        #[arg(long)]
+        is_buy: bool,
+        
+        /// Approve the deposited token with an EIP-2612 permit in the same transaction
+        #[arg(long)]
+        permit: bool,
+        
+        /// When the deposited token is WMON, first wrap whatever MON the wallet is short
+        #[arg(long)]
+        auto_wrap: bool,
+        
+        /// Wrapped MON contract used by --auto-wrap
+        #[arg(long, default_value = "WMON")]
+        wmon: String,
+        
+        /// Cover whatever the order's deposit is short of first: approve exactly the amount
+        /// needed, withdraw missing tokens from the DEX balance, and attach native MON
+        #[arg(long, conflicts_with = "permit")]
+        auto_deposit: bool,
+        
+        /// Refuse to send the order if it would cross the spread and take liquidity; the
+        /// live book is checked before funding the order and again just before sending
+        #[arg(long)]
+        post_only: bool,
+        
+        /// With --post-only, move a crossing order one tick inside the opposite best
+        /// instead of aborting
+        #[arg(long, requires = "post_only")]
+        reprice: bool,
+        
+        /// Send the order even if its price is outside the configured price bands
+        #[arg(long)]
+        force: bool,
+        
+        /// Cancel the order at this time (RFC 3339, e.g. 2025-06-01T12:00:00Z); needs `expire-orders` running
+        #[arg(long, value_parser = humantime::parse_rfc3339_weak, conflicts_with = "ttl")]
+        expires_at: Option<SystemTime>,
+        
+        /// Cancel the order if it is still open after this long (e.g. 30m, 2h); needs
+        /// `expire-orders` running, which picks up overdue orders from its journal after a restart
+        #[arg(long, visible_alias = "cancel-after", value_parser = humantime::parse_duration)]
+        ttl: Option<Duration>,
+        
+        /// Block until the order is fully filled, cancelled, or this long has passed
+        /// (default 5m), then print its execution summary instead of the placement
+        #[arg(long, value_parser = humantime::parse_duration, num_args = 0..=1, default_missing_value = "5m", value_name = "TIMEOUT")]
+        wait_fill: Option<Duration>,
+        
+        /// Private key (or --account)
+        #[arg(short, long)]
+        private_key: Option<String>,
+        
+        /// RPC URL (comma-separated list for failover)
+        #[arg(short, long)]
+        rpc_url: Option<String>,
+    },
+    
+    /// Place many limit orders from a JSON file in one pipelined batch
+    PlaceOrders {
+        /// DEX contract address (defaults to the latest recorded OrderBookDEX deployment)
+        #[arg(short, long, default_value = "OrderBookDEX")]
+        address: String,
+        
+        /// JSON file with an array of {base_token, quote_token, amount, price, is_buy}
+        #[arg(short, long)]
+        file: String,
+        
+        /// Continue the interrupted batch of this file, skipping orders already placed
+        #[arg(long, conflicts_with = "restart")]
+        resume: bool,
+        
+        /// Abandon the interrupted batch of this file and place every order again
+        #[arg(long)]
+        restart: bool,
+        
+        /// Private key (or --account)
+        #[arg(short, long)]
+        private_key: Option<String>,
+        
+        /// RPC URL (comma-separated list for failover)
+        #[arg(short, long)]
+        rpc_url: Option<String>,
+    },
+    
+    /// Place a market order
+    PlaceMarketOrder {
+        /// DEX contract address (defaults to the latest recorded OrderBookDEX deployment)
+        #[arg(short, long, default_value = "OrderBookDEX")]
+        address: String,
+        
+        /// Base token address
+        #[arg(short, long)]
+        base_token: String,
+        
+        /// Quote token address
+        #[arg(short, long)]
+        quote_token: String,
+        
+        /// Order amount
+        #[arg(long)]
+        amount: u64,
+        
+        /// Is buy order
+        #[arg(long)]
+        is_buy: bool,
+        
+        /// Private key (or --account)
+        #[arg(short, long)]
+        private_key: Option<String>,
+        
+        /// RPC URL (comma-separated list for failover)
+        #[arg(short, long)]
+        rpc_url: Option<String>,
+    },
+    
+    /// Cancel an order
+    CancelOrder {
+        /// DEX contract address (defaults to the latest recorded OrderBookDEX deployment)
+        #[arg(short, long, default_value = "OrderBookDEX")]
+        address: String,
+        
+        /// Order ID
+        #[arg(short, long)]
+        order_id: u64,
+        
+        /// Private key (or --account)
+        #[arg(short, long)]
+        private_key: Option<String>,
+        
+        /// RPC URL (comma-separated list for failover)
+        #[arg(short, long)]
+        rpc_url: Option<String>,
+    },
+    
+    /// Wrap native MON into WMON
+    Wrap {
+        /// Wrapped MON contract address
+        #[arg(long, default_value = "WMON")]
+        wmon: String,
+        
+        /// Amount in MON (e.g. 0.5)
+        #[arg(long)]
+        amount: String,
+        
+        /// Private key (or --account)
+        #[arg(short, long)]
+        private_key: Option<String>,
+        
+        /// RPC URL (comma-separated list for failover)
+        #[arg(short, long)]
+        rpc_url: Option<String>,
+    },
+    
+    /// Unwrap WMON back into native MON
+    Unwrap {
+        /// Wrapped MON contract address
+        #[arg(long, default_value = "WMON")]
+        wmon: String,
+        
+        /// Amount in WMON (e.g. 0.5)
+        #[arg(long)]
+        amount: String,
+        
+        /// Private key (or --account)
+        #[arg(short, long)]
+        private_key: Option<String>,
+        
+        /// RPC URL (comma-separated list for failover)
+        #[arg(short, long)]
+        rpc_url: Option<String>,
+    },
+    
+    /// Withdraw tokens from DEX
+    Withdraw {
+        /// DEX contract address (defaults to the latest recorded OrderBookDEX deployment)
+        #[arg(short, long, default_value = "OrderBookDEX")]
+        address: String,
+        
+        /// Token address
+        #[arg(short, long)]
+        token: String,
+        
+        /// Amount to withdraw
+        #[arg(short, long)]
+        amount: u64,
+        
+        /// Private key (or --account)
+        #[arg(short, long)]
+        private_key: Option<String>,
+        
+        /// RPC URL (comma-separated list for failover)
+        #[arg(short, long)]
+        rpc_url: Option<String>,
+    },
+    
+    /// Withdraw every token balance held on the DEX, optionally sweeping it to another address
+    WithdrawAll {
+        /// DEX contract address (defaults to the latest recorded OrderBookDEX deployment)
+        #[arg(short, long, default_value = "OrderBookDEX")]
+        address: String,
+        
+        /// Forward everything withdrawn to this address (e.g. a cold wallet)
+        #[arg(long)]
+        sweep_to: Option<String>,
+        
+        /// Cancel open orders first so their deposits are freed too; otherwise only the
+        /// part of each balance not backing an open order is withdrawn
+        #[arg(long)]
+        cancel_open: bool,
+        
+        /// Block to start indexing from when no index exists yet
+        #[arg(long, default_value_t = 0)]
+        from_block: u64,
+        
+        /// Private key (or --account)
+        #[arg(short, long)]
+        private_key: Option<String>,
+        
+        /// RPC URL (comma-separated list for failover)
+        #[arg(short, long)]
+        rpc_url: Option<String>,
+    },
+}
+
+#[tokio::main]
+async fn main() -> ExitCode {
+    let cli = Cli::parse();
+    let format = cli.output;
+    match run(cli).await {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) if format == OutputFormat::Json => error::report_json(&e),
+        Err(e) => error::report(&e),
+    }
+}
+
+async fn run(cli: Cli) -> Result<()> {
+    
+    // Initialize logging
+    let _log_guard = logging::init(&cli.log)?;
+    let _fork = rpc::configure(cli.rpc.clone());
+    
+    if let Some(addr) = cli.metrics_addr {
+        tokio::spawn(async move {
+            if let Err(e) = metrics::serve(addr).await {
+                tracing::error!("Metrics server stopped: {}", e);
+            }
+        });
+    }
+    
+    match cli.command {
+        Commands::Write(command) if cli.paper => {
+            run_paper_command(command).await?;
+        }
+        Commands::Write(command) => {
+            if !cli.skip_compat_check {
+                ensure_compatible(&command).await?;
+            }
+            run_write_command(command, &cli.tx, cli.output).await?;
+        }
+        Commands::EstimateGas { command } => {
+            if !cli.skip_compat_check {
+                ensure_compatible(&command).await?;
+            }
+            run_write_command(command, &cli.tx.estimate_only(), cli.output).await?;
+        }
+        Commands::GetOrderBook { address, base_token, quote_token, depth, offset, min_price, max_price, page_size, stream, rpc_url } => {
+            let query = PageQuery {
+                page_size,
+                min_price: min_price.as_deref().map(parse_price).transpose()?.unwrap_or_default(),
+                max_price: max_price.as_deref().map(parse_price).transpose()?,
+                ..Default::default()
+            };
+            get_order_book(address, base_token, quote_token, query, depth, offset, stream, rpc_url, cli.output).await?;
+        }
+        Commands::GetUserOrders { address, user, detailed, rpc_url } => {
+            get_user_orders(address, user, detailed, rpc_url).await?;
+        }
+        Commands::GetBalance { address, user, token, rpc_url } => {
+            get_balance(address, user, token, rpc_url).await?;
+        }
+        Commands::Index { address, from_block, chunk_size, rpc_url } => {
+            index(address, from_block, chunk_size, rpc_url).await?;
+        }
+        Commands::ListPairs { address, from_block, all, rpc_url } => {
+            list_pairs(address, from_block, all, rpc_url, cli.output).await?;
+        }
+        Commands::PairInfo { address, base_token, quote_token, rpc_url } => {
+            pair_info(address, base_token, quote_token, rpc_url, cli.output).await?;
+        }
+        Commands::Quote { address, base_token, quote_token, amount, is_buy, from, rpc_url } => {
+            quote_market_order(address, base_token, quote_token, amount, is_buy, from, rpc_url, cli.output).await?;
+        }
+        Commands::Analytics { address, base_token, quote_token, window_blocks, bands, walls, from_block, rpc_url } => {
+            analytics(address, base_token, quote_token, window_blocks, bands, walls, from_block, rpc_url, cli.output).await?;
+        }
+        Commands::Stats { address, base_token, quote_token, window, from_block, rpc_url } => {
+            ticker_stats(address, base_token.zip(quote_token), window, from_block, rpc_url, cli.output).await?;
+        }
+        Commands::Portfolio { address, user, from_block, rpc_url } => {
+            get_portfolio(address, user, from_block, rpc_url, cli.output).await?;
+        }
+        Commands::Position { address, user, base_token, quote_token, from_block, mark_feed, rpc_url } => {
+            get_positions(address, user, base_token.zip(quote_token), from_block, mark_feed, rpc_url, cli.output).await?;
+        }
+        Commands::Bot { address, config, force, record, private_key, rpc_url } => {
+            run_bot(address, config, force, record, private_key, rpc_url, cli.paper, cli.skip_compat_check, cli.tx).await?;
+        }
+        Commands::Backtest { address, config, from_block, rpc_url } => {
+            backtest(address, config, from_block, rpc_url, cli.output).await?;
+        }
+        Commands::ReplayStrategy { log, config } => {
+            replay_strategy(log, config, cli.output)?;
+        }
+        Commands::Arb { command: ArbCommands::Scan { address, config, execute, once, private_key, rpc_url } } => {
+            arb_scan(address, config, execute, once, private_key, rpc_url, &cli.tx).await?;
+        }
+        Commands::Alerts { address, config, rpc_url } => {
+            run_alerts(address, config, rpc_url).await?;
+        }
+        Commands::ExpireOrders { interval_secs, once, private_key, rpc_url } => {
+            expire_orders(interval_secs, once, private_key, rpc_url, &cli.tx).await?;
+        }
+        Commands::Paper { command } => {
+            run_paper_account_command(command, cli.output)?;
+        }
+        Commands::GetFees { address, rpc_url } => {
+            get_fees(address, rpc_url, cli.output).await?;
+        }
+        Commands::SetFees { address, fee, safe, private_key, rpc_url } => {
+            set_fees(address, fee, safe, private_key, rpc_url, &cli.tx).await?;
+        }
+        Commands::Admin { safe, command } => {
+            run_admin_command(command, safe, &cli.tx).await?;
+        }
+        Commands::Alias { command } => {
+            run_alias_command(command, cli.output)?;
+        }
+        Commands::Network { command } => {
+            run_network_command(command, cli.output)?;
+        }
+        Commands::Journal { command } => {
+            run_journal_command(command, cli.output)?;
+        }
+        Commands::Export { command } => {
+            run_export_command(command).await?;
+        }
+        Commands::Offline { command } => {
+            run_offline_command(command, cli.output, &cli.tx).await?;
+        }
+        Commands::Ctl { socket, command } => {
+            run_ctl_command(&socket, command, cli.output).await?;
+        }
+        Commands::Wallet { command } => {
+            run_wallet_command(command, cli.output)?;
+        }
+        Commands::Session { command } => {
+            run_session_command(command, cli.output, &cli.tx).await?;
+        }
+        Commands::Replay { address, base_token, quote_token, block, snapshot, step, to_block, from_block, rpc_url } => {
+            replay_book(address, base_token.zip(quote_token), block, snapshot, step, to_block, from_block, rpc_url, cli.output).await?;
+        }
+        Commands::Loadtest {
+            address, base_token, quote_token, keys_file, rate, duration, cancel_ratio, mid_price, spread_bps,
+            min_amount, max_amount, gas_limit, receipt_timeout, poll_ms, private_key, rpc_url,
+        } => {
+            let options = LoadtestOptions { rate, duration, cancel_ratio, mid_price, spread_bps, min_amount, max_amount, gas_limit, receipt_timeout, poll_ms };
+            run_loadtest(address, base_token, quote_token, keys_file, options, private_key, rpc_url, &cli.tx, cli.output).await?;
+        }
+        Commands::Snapshot { command } => {
+            run_snapshot_command(command, cli.output).await?;
+        }
+        Commands::Schedule { command } => {
+            run_schedule_command(command, cli.output, &cli.tx).await?;
+        }
+        Commands::Route { command: RouteCommands::Quote { address, from, to, via, amount, steps, gas_per_tx, rpc_url } } => {
+            route_swap(address, from, to, via, amount, steps, gas_per_tx, false, None, rpc_url, cli.output, &cli.tx).await?;
+        }
+        Commands::Route { command: RouteCommands::Execute { address, from, to, via, amount, steps, gas_per_tx, private_key, rpc_url } } => {
+            route_swap(address, from, to, via, amount, steps, gas_per_tx, true, private_key, rpc_url, cli.output, &cli.tx).await?;
+        }
+        Commands::Mempool { command: MempoolCommands::Watch { address, base_token, quote_token, interval_ms, rpc_url } } => {
+            watch_mempool(address, base_token, quote_token, interval_ms, rpc_url, cli.output).await?;
+        }
+        Commands::Doctor { command: DoctorCommands::Compat { address, rpc_url } } => {
+            check_compat(address, rpc_url, cli.output).await?;
+        }
+        Commands::Doctor { command: DoctorCommands::Releases { command } } => {
+            run_release_command(command, cli.output).await?;
+        }
+        Commands::Doctor { command: DoctorCommands::Check { address, wallet, private_key, tokens, config, rpc_url } } => {
+            doctor_check(address, wallet, private_key, tokens, config, rpc_url, cli.rpc.account.clone(), cli.output).await?;
+        }
+        Commands::Doctor { command: DoctorCommands::Bench { address, base_token, quote_token, samples, no_save, rpc_url } } => {
+            bench_rpc(address, base_token, quote_token, samples, no_save, rpc_url, cli.output).await?;
+        }
+        Commands::Contract { command: ContractCommands::Call { address, function, args, artifact, value, private_key, rpc_url } } => {
+            call_contract(address, function, args, artifact, value, private_key, rpc_url, &cli.tx, cli.output).await?;
+        }
+        Commands::Contract { command: ContractCommands::Logs { address, from_block, to_block, artifact, rpc_url } } => {
+            contract_logs(address, from_block, to_block, artifact, rpc_url, cli.output).await?;
+        }
+        Commands::Contract { command: ContractCommands::Abi { address, refresh, rpc_url } } => {
+            show_contract_abi(address, refresh, rpc_url, cli.output).await?;
+        }
+        Commands::Tokens { command } => {
+            run_token_command(command, cli.output).await?;
+        }
+        Commands::PriceBands { command } => {
+            run_price_band_command(command, cli.output)?;
+        }
+        Commands::Price { symbol } => {
+            get_feed_price(symbol, cli.output).await?;
+        }
+        Commands::PriceFeed { command } => {
+            run_price_feed_command(command, cli.output)?;
+        }
+        Commands::Halt { reason, cancel } => {
+            let halt = Halt::new(reason, cancel);
+            halt.save()?;
+            println!("Trading halted: {}{}", halt.reason, if cancel { " (bots will cancel their orders)" } else { "" });
+        }
+        Commands::Resume => match Halt::clear()? {
+            Some(halt) => println!("Resumed; the halt was: {}", halt.reason),
+            None => println!("Trading was not halted"),
+        },
+        Commands::Estop { address, sweep_to, from_block, socket, private_key, rpc_url } => {
+            // Stop the bots first so they don't requote into the cancels
+            emergency_halt(&socket).await?;
+            // Nobody is asked to confirm an emergency stop, and spending caps don't hold it up
+            withdraw_all(address, sweep_to, true, true, from_block, private_key, rpc_url, &cli.tx.unattended()).await?;
+        }
+        Commands::Risk { command } => {
+            run_risk_command(command, cli.output).await?;
+        }
+        Commands::Limits { command } => {
+            run_limits_command(command, cli.output)?;
+        }
+        Commands::SignOrder { address, base_token, quote_token, amount, price, is_buy, nonce, expires_in, out, private_key, rpc_url } => {
+            sign_order(address, base_token, quote_token, amount, price, is_buy, nonce, expires_in, out, private_key, rpc_url).await?;
+        }
+        Commands::VerifyOrder { address, file, rpc_url } => {
+            verify_order(address, file, rpc_url, cli.output).await?;
+        }
+        Commands::Relay { address, listen, batch_size, batch_interval_secs, private_key, rpc_url } => {
+            run_relay(address, listen, batch_size, batch_interval_secs, private_key, rpc_url, cli.tx).await?;
+        }
+        Commands::TxStatus { tx_hash, rpc_url } => {
+            tx_status(tx_hash, rpc_url).await?;
+        }
+        Commands::DecodeLogs { tx_hash, block, address, rpc_url } => {
+            decode_logs(tx_hash, block, address, rpc_url, cli.output).await?;
+        }
+        Commands::Trace { tx_hash, artifact, rpc_url } => {
+            trace_tx(tx_hash, artifact, rpc_url, cli.output).await?;
+        }
+        Commands::BumpTx { tx_hash, private_key, rpc_url } => {
+            bump_tx(tx_hash, private_key, rpc_url, &cli.tx).await?;
+        }
+        Commands::CancelTx { tx_hash, private_key, rpc_url } => {
+            cancel_tx(tx_hash, private_key, rpc_url, &cli.tx).await?;
+        }
+    }
+    
+    Ok(())
+}
+
+/// Refuse to send anything to a DEX these tools can't drive, rather than failing with an
+/// ABI error partway through a command
+async fn ensure_compatible(command: &WriteCommands) -> Result<()> {
+    let (address, rpc_url) = match command {
+        WriteCommands::AddTradingPair { address, rpc_url, .. }
+        | WriteCommands::PlaceLimitOrder { address, rpc_url, .. }
+        | WriteCommands::PlaceOrders { address, rpc_url, .. }
+        | WriteCommands::PlaceMarketOrder { address, rpc_url, .. }
+        | WriteCommands::CancelOrder { address, rpc_url, .. }
+        | WriteCommands::Withdraw { address, rpc_url, .. }
+        | WriteCommands::WithdrawAll { address, rpc_url, .. } => (address, rpc_url),
+        // WMON wrapping doesn't touch the DEX
+        WriteCommands::Wrap { .. } | WriteCommands::Unwrap { .. } => return Ok(()),
+    };
+    let provider = rpc::connect(rpc_url.as_deref()).await?;
+    compat::ensure(&provider, aliases::resolve(address)?).await?;
+    Ok(())
+}
+
+async fn run_write_command(command: WriteCommands, tx_args: &TxArgs, format: OutputFormat) -> Result<()> {
+    match command {
+        WriteCommands::AddTradingPair { address, base_token, quote_token, min_order_size, price_precision, safe, private_key, rpc_url } => {
+            add_trading_pair(address, base_token, quote_token, min_order_size, price_precision, safe, private_key, rpc_url, tx_args).await?;
+        }
+        WriteCommands::PlaceLimitOrder { address, base_token, quote_token, amount, price, is_buy, permit, auto_wrap, wmon, auto_deposit, post_only, reprice, force, expires_at, ttl, wait_fill, private_key, rpc_url } => {
+            let order = LimitOrder {
+                dex: aliases::resolve(&address)?,
+                base_token: aliases::resolve(&base_token)?,
+                quote_token: aliases::resolve(&quote_token)?,
+                amount: U256::from(amount),
+                price: U256::from(price),
+                is_buy,
+            };
+            let options = LimitOrderOptions {
+                use_permit: permit,
+                auto_wrap: auto_wrap.then(|| aliases::resolve(&wmon)).transpose()?,
+                auto_deposit,
+                post_only: post_only.then_some(reprice),
+                force,
+                expires_at: expires_at.or_else(|| ttl.map(|ttl| SystemTime::now() + ttl)),
+            };
+            place_limit_order(order, &options, wait_fill, private_key, rpc_url, tx_args, format).await?;
+        }
+        WriteCommands::PlaceOrders { address, file, resume, restart, private_key, rpc_url } => {
+            place_orders(address, file, resume, restart, private_key, rpc_url, tx_args).await?;
+        }
+        WriteCommands::PlaceMarketOrder { address, base_token, quote_token, amount, is_buy, private_key, rpc_url } => {
+            place_market_order(address, base_token, quote_token, amount, is_buy, private_key, rpc_url, tx_args).await?;
+        }
+        WriteCommands::CancelOrder { address, order_id, private_key, rpc_url } => {
+            cancel_order(address, order_id, private_key, rpc_url, tx_args).await?;
+        }
+        WriteCommands::Wrap { wmon, amount, private_key, rpc_url } => {
+            wrap(wmon, amount, private_key, rpc_url, tx_args).await?;
+        }
+        WriteCommands::Unwrap { wmon, amount, private_key, rpc_url } => {
+            unwrap(wmon, amount, private_key, rpc_url, tx_args).await?;
+        }
+        WriteCommands::Withdraw { address, token, amount, private_key, rpc_url } => {
+            withdraw(address, token, amount, private_key, rpc_url, tx_args).await?;
+        }
+        WriteCommands::WithdrawAll { address, sweep_to, cancel_open, from_block, private_key, rpc_url } => {
+            withdraw_all(address, sweep_to, cancel_open, false, from_block, private_key, rpc_url, tx_args).await?;
+        }
+    }
+    
+    Ok(())
+}
+
+#[allow(clippy::too_many_arguments)]
+async fn run_bot(
+    contract_address: String,
+    config_path: String,
+    force: bool,
+    record: Option<String>,
+    private_key: Option<String>,
+    rpc_url: Option<String>,
+    paper: bool,
+    skip_compat_check: bool,
+    tx_args: TxArgs
+) -> Result<()> {
+    let options = RunnerOptions {
+        dex: aliases::resolve(&contract_address)?,
+        private_key,
+        rpc_url,
+        paper,
+        force,
+        tx_args,
+        record,
+        cancel_on_shutdown: false,
+        skip_compat_check,
+    };
+    runner::run(&config_path, &StrategyRegistry::new(), options).await?;
+    Ok(())
+}
+
+fn replay_strategy(log: String, config_path: Option<String>, format: OutputFormat) -> Result<()> {
+    let strategy = config_path.map(|path| BotConfig::load(&path)).transpose()?.map(|config| config.strategy);
+    let report = recorder::replay(&log, &StrategyRegistry::new(), strategy.as_ref())?;
+    
+    match format {
+        OutputFormat::Json => output::print_json(&report)?,
+        OutputFormat::Csv => output::print_csv(&report.divergences.iter()
+            .map(|divergence| (divergence.seq, divergence.call, format!("{:?}", divergence.recorded), format!("{:?}", divergence.replayed)))
+            .collect::<Vec<_>>())?,
+        OutputFormat::Text => {
+            println!("Strategy replay: {}", log);
+            println!("================================");
+            println!("Runs: {}, calls: {}, intents: {}", report.runs, report.calls, report.intents);
+            if report.divergences.is_empty() {
+                println!("Every call reproduced the recorded intents");
+            } else {
+                println!("\n{} calls diverged:", report.divergences.len());
+                for divergence in &report.divergences {
+                    println!("  #{} {}", divergence.seq, divergence.call);
+                    println!("    recorded: {:?}", divergence.recorded);
+                    println!("    replayed: {:?}", divergence.replayed);
+                }
+            }
+        }
+    }
+    
+    Ok(())
+}
+
+async fn backtest(
+    contract_address: String,
+    config_path: String,
+    from_block: u64,
+    rpc_url: Option<String>,
+    format: OutputFormat
+) -> Result<()> {
+    let config = BotConfig::load(&config_path)?;
+    
+    let provider = rpc::connect(rpc_url.as_deref()).await?;
+    let contract_address = aliases::resolve(&contract_address)?;
+    
+    // Load contract ABI
+    let contract_abi = dex_abi();
+    
+    let mut store = EventStore::load(contract_address, from_block)?;
+    store.sync(&provider, &contract_abi, DEFAULT_CHUNK_SIZE).await?;
+    
+    // Create contract instance
+    let contract = OrderBookDEX::new(contract_address, Arc::new(provider));
+    let (_, _, _, _, price_precision) = contract
+        .trading_pairs(config.base_token, config.quote_token)
+        .call()
+        .await?;
+    
+    let mut strategy = StrategyRegistry::new().build(&config.strategy)?;
+    let report = Backtest::new(&store, config.base_token, config.quote_token, price_precision).run(strategy.as_mut());
+    
+    match format {
+        OutputFormat::Json => output::print_json(&report)?,
+        OutputFormat::Csv => output::print_csv(&report.curve)?,
+        OutputFormat::Text => {
+            println!("Backtest: {} on {:?} / {:?}", report.strategy, config.base_token, config.quote_token);
+            println!("================================");
+            println!("Blocks: {} to {} ({} with activity)", report.from_block, report.to_block, report.curve.len());
+            println!("Orders placed: {}, cancelled: {}", report.orders_placed, report.orders_cancelled);
+            println!("Final PnL: {:.6} (quote units)", report.final_pnl);
+            println!("Max drawdown: {:.6}", report.max_drawdown);
+            
+            let stats = &report.stats;
+            println!("\nFills: {} ({} buys, {} sells; {} maker, {} taker)", stats.fills, stats.buys, stats.sells, stats.maker_fills, stats.taker_fills);
+            println!("Base volume: {}", stats.base_volume);
+            println!("Quote volume: {:.6}", stats.quote_volume);
+            println!("Fees (base): {}", stats.fees);
+            println!("Slippage vs mid: {:.2} bps average, {:.2} bps worst", stats.average_slippage_bps, stats.worst_slippage_bps);
+        }
+    }
+    
+    Ok(())
+}
+
+async fn arb_scan(
+    contract_address: String,
+    config_path: String,
+    execute: bool,
+    once: bool,
+    private_key: Option<String>,
+    rpc_url: Option<String>,
+    tx_args: &TxArgs
+) -> Result<()> {
+    let config = ArbConfig::load(&config_path)?;
+    let routes = config.all_routes();
+    info!("Scanning {} routes across {} pairs", routes.len(), config.pairs.len());
+    
+    let provider = rpc::connect(rpc_url.as_deref()).await?;
+    let contract_address = aliases::resolve(&contract_address)?;
+    
+    // Create contract instance
+    let client = Arc::new(provider);
+    let contract = OrderBookDEX::new(contract_address, client.clone());
+    
+    let size = match (execute, config.max_size) {
+        (true, Some(max_size)) => config.trade_size.min(max_size),
+        _ => config.trade_size,
+    };
+    
+    loop {
+        let markets = arb::fetch_markets(&contract, &config.pairs).await?;
+        let gas_price = client.get_gas_price().await?;
+        
+        println!("Arbitrage scan at block {}", client.get_block_number().await?);
+        println!("================================");
+        for route in &routes {
+            let Some(opportunity) = arb::evaluate(&markets, route, U256::from(size), gas_price, config.gas_per_tx, config.min_profit_bps) else {
+                println!("  {}: not executable (missing pair or liquidity)", format_route(route));
+                continue;
+            };
+            println!(
+                "  {}: in {} out {} ({:+.2} bps, gas {} wei){}",
+                format_route(route), opportunity.input, opportunity.output, opportunity.profit_bps,
+                opportunity.gas_cost, if opportunity.profitable { " PROFITABLE" } else { "" }
+            );
+            
+            if opportunity.profitable {
+                tracing::warn!("Arbitrage opportunity on {}: {:+.2} bps", format_route(route), opportunity.profit_bps);
+                if execute {
+                    // Opportunities don't wait for a prompt
+                    if let Err(e) = execute_legs(&opportunity.legs, contract_address, private_key.clone(), rpc_url.as_deref(), &tx_args.unattended()).await {
+                        tracing::warn!("Route execution stopped: {}", e);
+                    }
+                }
+            }
+        }
+        
+        if once {
+            return Ok(());
+        }
+        tokio::select! {
+            _ = tokio::time::sleep(Duration::from_secs(config.interval_secs)) => {}
+            _ = tokio::signal::ctrl_c() => return Ok(()),
+        }
+    }
+}
+
+/// Send each leg of a route as a market order, with the checks of PlaceMarketOrder,
+/// withdrawing its proceeds for the next leg
+async fn execute_legs(
+    legs: &[Leg],
+    contract_address: Address,
+    private_key: Option<String>,
+    rpc_url: Option<&str>,
+    tx_args: &TxArgs
+) -> Result<()> {
+    let provider = rpc::connect(rpc_url).await?;
+    let fees = tx_args.fees.resolve(&provider).await?;
+    let wallet = rpc::wallet(&provider, private_key.as_deref()).await?;
+    let client = SignerMiddleware::new(provider, wallet);
+    // Every leg withdraws its proceeds, which a session key may not do
+    session::check(client.address(), session::Action::Withdraw)?;
+    
+    // Create contract instance
+    let client_arc = Arc::new(client);
+    let contract = OrderBookDEX::new(contract_address, client_arc.clone());
+    
+    let owner = client_arc.address();
+    for (i, leg) in legs.iter().enumerate() {
+        info!("Leg {}: {} {} of {:?}", i + 1, if leg.is_buy { "BUY" } else { "SELL" }, leg.amount, leg.base_token);
+        let order = LimitOrder { dex: contract_address, base_token: leg.base_token, quote_token: leg.quote_token, amount: leg.amount, price: U256::zero(), is_buy: leg.is_buy };
+        let notional = placement::check_market_order(client_arc.clone(), rpc_url, &order, tx_args).await?;
+        
+        // Proceeds are credited to the DEX balance; the next leg pulls from the wallet
+        let token = if leg.is_buy { leg.base_token } else { leg.quote_token };
+        let held = contract.get_user_balance(owner, token).call().await?;
+        
+        // The native side of a market order is paid with the transaction value
+        let native_in = (leg.is_buy && leg.quote_token == Address::zero()) || (!leg.is_buy && leg.base_token == Address::zero());
+        let mut method = contract.place_market_order(leg.base_token, leg.quote_token, leg.amount, leg.is_buy);
+        if native_in {
+            method = method.value(leg.input);
+        }
+        let receipt = tx::send(client_arc.as_ref(), method, &fees, &tx_args.step(format!("leg/{}", i + 1))).await?
+            .ok_or_else(|| anyhow::anyhow!("Leg {} was dropped from the mempool", i + 1))?;
+        tx::ensure_success(client_arc.as_ref(), &receipt).await?;
+        limits::record(Spend::OrderNotional, leg.quote_token, notional)?;
+        session::record(owner, notional)?;
+        
+        // The book may have moved since the quote, so withdraw what the leg actually paid out
+        let credited = contract.get_user_balance(owner, token).call().await?.saturating_sub(held);
+        info!("Leg {} credited {} of {:?} (quoted {})", i + 1, credited, token, leg.output);
+        let method = contract.withdraw(token, credited);
+        if let Some(receipt) = tx::send(client_arc.as_ref(), method, &fees, &tx_args.step(format!("withdraw/{}", i + 1))).await? {
+            tx::ensure_success(client_arc.as_ref(), &receipt).await?;
+        }
+    }
+    
+    info!("Route executed successfully!");
+    Ok(())
+}
+
+fn format_route(route: &[Address]) -> String {
+    let mut hops: Vec<String> = route.iter().map(|token| format!("{:?}", token)).collect();
+    if let Some(first) = hops.first().cloned() {
+        hops.push(first);
+    }
+    hops.join(" -> ")
+}
+
+async fn run_alerts(contract_address: String, config_path: String, rpc_url: Option<String>) -> Result<()> {
+    let config = AlertsConfig::load(&config_path)?;
+    let notifications = config.notify.build();
+    if notifications.is_empty() {
+        tracing::warn!("No notification channels configured; alerts will only be logged");
+    }
+    
+    let provider = Arc::new(rpc::connect(rpc_url.as_deref()).await?);
+    let contract_address = aliases::resolve(&contract_address)?;
+    
+    // Load contract ABI
+    let contract_abi = dex_abi();
+    
+    let mut engine = AlertEngine::new(provider.as_ref(), contract_address, &contract_abi, config.rules.clone()).await?;
+    info!("Watching {} alert rules every {}s", config.rules.len(), config.interval_secs);
+    
+    loop {
+        match engine.check(provider.clone(), &contract_abi).await {
+            Ok(alerts) => {
+                for alert in alerts {
+                    tracing::warn!("[{}] {}", alert.source, alert.message);
+                    notifications.send(&alert).await;
+                }
+            }
+            Err(e) => tracing::warn!("Alert check failed: {}", e),
+        }
+        
+        tokio::select! {
+            _ = tokio::time::sleep(Duration::from_secs(config.interval_secs)) => {}
+            _ = tokio::signal::ctrl_c() => return Ok(()),
+        }
+    }
+}
+
+/// Run an order command against the paper account instead of sending a transaction
+async fn run_paper_command(command: WriteCommands) -> Result<()> {
+    match command {
+        WriteCommands::AddTradingPair { .. } => {
+            return Err(anyhow::anyhow!("AddTradingPair is not available in paper mode"));
+        }
+        WriteCommands::Wrap { .. } | WriteCommands::Unwrap { .. } => {
+            return Err(anyhow::anyhow!("Wrapping MON is not available in paper mode"));
+        }
+        WriteCommands::PlaceLimitOrder { address, base_token, quote_token, amount, price, is_buy, rpc_url, .. } => {
+            paper_place_limit_order(address, base_token, quote_token, amount, price, is_buy, rpc_url).await?;
+        }
+        WriteCommands::PlaceOrders { address, file, rpc_url, .. } => {
+            let orders: Vec<OrderRequest> = serde_json::from_str(&std::fs::read_to_string(&file)?)?;
+            info!("Placing {} paper limit orders from {}", orders.len(), file);
+            for order in orders {
+                let result = paper_place_limit_order(
+                    address.clone(), order.base_token, order.quote_token, order.amount, order.price, order.is_buy, rpc_url.clone()
+                ).await;
+                if let Err(e) = result {
+                    println!("  Order failed: {}", e);
+                }
+            }
+        }
+        WriteCommands::PlaceMarketOrder { address, base_token, quote_token, amount, is_buy, rpc_url, .. } => {
+            paper_place_market_order(address, base_token, quote_token, amount, is_buy, rpc_url).await?;
+        }
+        WriteCommands::CancelOrder { order_id, .. } => {
+            let mut account = PaperAccount::load()?;
+            let order = account.cancel(order_id)?;
+            account.save()?;
+            println!("Paper order {} cancelled, {} unfilled released", order.id, order.amount);
+        }
+        WriteCommands::Withdraw { token, amount, .. } => {
+            let mut account = PaperAccount::load()?;
+            account.withdraw(aliases::resolve(&token)?, U256::from(amount))?;
+            account.save()?;
+            println!("Withdrew {} from the paper account", amount);
+        }
+        WriteCommands::WithdrawAll { .. } => {
+            return Err(anyhow::anyhow!("WithdrawAll is not available in paper mode"));
+        }
+    }
+    
+    Ok(())
+}
+
+fn run_paper_account_command(command: PaperCommands, format: OutputFormat) -> Result<()> {
+    match command {
+        PaperCommands::Fund { token, amount } => {
+            let token = aliases::resolve(&token)?;
+            let mut account = PaperAccount::load()?;
+            account.fund(token, U256::from(amount));
+            account.save()?;
+            println!("Paper balance of {:?}: {}", token, account.balance(token));
+        }
+        PaperCommands::Status => {
+            let account = PaperAccount::load()?;
+            match format {
+                OutputFormat::Json => output::print_json(&account)?,
+                OutputFormat::Csv => output::print_csv(&account.fills)?,
+                OutputFormat::Text => print_paper_account(&account),
+            }
+        }
+        PaperCommands::Reset => {
+            PaperAccount::reset()?;
+            println!("Paper account reset");
+        }
+    }
+    
+    Ok(())
+}
+
+async fn get_fees(contract_address: String, rpc_url: Option<String>, format: OutputFormat) -> Result<()> {
+    let provider = rpc::connect(rpc_url.as_deref()).await?;
+    let contract_address = aliases::resolve(&contract_address)?;
+    
+    // Create contract instance
+    let contract = OrderBookDEX::new(contract_address, Arc::new(provider));
+    
+    let trading_fee = contract.trading_fee().call().await?;
+    let fee_denominator = contract.fee_denominator().call().await?;
+    // Deployments from before fees were configurable have no maximum
+    let max_trading_fee = contract.max_trading_fee().call().await.unwrap_or(trading_fee);
+    
+    let report = FeeReport {
+        trading_fee: trading_fee.low_u64(),
+        max_trading_fee: max_trading_fee.low_u64(),
+        fee_denominator: fee_denominator.low_u64(),
+        trading_fee_percent: trading_fee.low_u64() as f64 * 100.0 / fee_denominator.low_u64().max(1) as f64,
+    };
+    
+    match format {
+        OutputFormat::Json => output::print_json(&report)?,
+        OutputFormat::Csv => output::print_csv(&[report])?,
+        OutputFormat::Text => {
+            println!("DEX Fees");
+            println!("========");
+            println!("Trading fee: {} / {} ({}%)", report.trading_fee, report.fee_denominator, report.trading_fee_percent);
+            println!("Maximum trading fee: {}", report.max_trading_fee);
+            println!("Charged on: base amount of every limit order match (market orders pay none)");
+        }
+    }
+    
+    Ok(())
+}
+
+async fn set_fees(
+    contract_address: String,
+    fee: u64,
+    safe: Option<String>,
+    private_key: Option<String>,
+    rpc_url: Option<String>,
+    tx_args: &TxArgs
+) -> Result<()> {
+    if fee > 100 {
+        return Err(anyhow::anyhow!("Fee {} exceeds the contract maximum of 100 (1%)", fee));
+    }
+    let summary = format!("Set the trading fee to {} bps ({}%)", fee, fee as f64 / 100.0);
+    send_admin_call(contract_address, safe, private_key, rpc_url, tx_args, &summary, "setTradingFee", |dex| dex.set_trading_fee(U256::from(fee))).await
+}
+
+async fn run_admin_command(command: AdminCommands, safe: Option<String>, tx_args: &TxArgs) -> Result<()> {
+    match command {
+        AdminCommands::PausePair { address, base_token, quote_token, private_key, rpc_url } => {
+            let (base, quote) = (aliases::resolve(&base_token)?, aliases::resolve(&quote_token)?);
+            let summary = format!("Pause trading pair {:?} / {:?}", base, quote);
+            send_admin_call(address, safe, private_key, rpc_url, tx_args, &summary, "setTradingPairActive", |dex| dex.set_trading_pair_active(base, quote, false)).await?;
+        }
+        AdminCommands::UnpausePair { address, base_token, quote_token, private_key, rpc_url } => {
+            let (base, quote) = (aliases::resolve(&base_token)?, aliases::resolve(&quote_token)?);
+            let summary = format!("Unpause trading pair {:?} / {:?}", base, quote);
+            send_admin_call(address, safe, private_key, rpc_url, tx_args, &summary, "setTradingPairActive", |dex| dex.set_trading_pair_active(base, quote, true)).await?;
+        }
+        AdminCommands::Pause { address, private_key, rpc_url } => {
+            send_admin_call(address, safe, private_key, rpc_url, tx_args, "Pause order placement on every pair", "pause", |dex| dex.pause()).await?;
+        }
+        AdminCommands::Unpause { address, private_key, rpc_url } => {
+            send_admin_call(address, safe, private_key, rpc_url, tx_args, "Resume order placement", "unpause", |dex| dex.unpause()).await?;
+        }
+        AdminCommands::UpdatePair { address, base_token, quote_token, min_order_size, price_precision, private_key, rpc_url } => {
+            if price_precision == 0 {
+                return Err(anyhow::anyhow!("Price precision must be greater than zero"));
+            }
+            let (base, quote) = (aliases::resolve(&base_token)?, aliases::resolve(&quote_token)?);
+            let summary = format!(
+                "Set {:?} / {:?} min order size to {} and price precision to {}",
+                base, quote, min_order_size, price_precision
+            );
+            let (min_order_size, price_precision) = (U256::from(min_order_size), U256::from(price_precision));
+            send_admin_call(address, safe, private_key, rpc_url, tx_args, &summary, "updateTradingPair", |dex| {
+                dex.update_trading_pair(base, quote, min_order_size, price_precision)
+            }).await?;
+        }
+        AdminCommands::TransferOwnership { address, new_owner, private_key, rpc_url } => {
+            let new_owner = aliases::resolve(&new_owner)?;
+            if new_owner.is_zero() {
+                return Err(anyhow::anyhow!("Refusing to transfer ownership to the zero address"));
+            }
+            let summary = format!("Transfer DEX ownership to {:?} (this cannot be undone from this key)", new_owner);
+            send_admin_call(address, safe, private_key, rpc_url, tx_args, &summary, "transferOwnership", |dex| dex.transfer_ownership(new_owner)).await?;
+        }
+    }
+    
+    Ok(())
+}
+
+/// Check the signer (or `safe`) owns the DEX, confirm `summary` with the user and send
+/// the call, or propose it to the Safe
+#[allow(clippy::too_many_arguments)]
+async fn send_admin_call(
+    contract_address: String,
+    safe: Option<String>,
+    private_key: Option<String>,
+    rpc_url: Option<String>,
+    tx_args: &TxArgs,
+    summary: &str,
+    function: &str,
+    call: impl FnOnce(&OrderBookDEX<SignerClient>) -> ContractCall<SignerClient, ()>
+) -> Result<()> {
+    let provider = rpc::connect(rpc_url.as_deref()).await?;
+    let fees = tx_args.fees.resolve(&provider).await?;
+    let wallet = rpc::wallet(&provider, private_key.as_deref()).await?;
+    let client = SignerMiddleware::new(provider, wallet);
+    
+    let contract_address = aliases::resolve(&contract_address)?;
+    
+    // Create contract instance
+    let client_arc = Arc::new(client);
+    let contract = OrderBookDEX::new(contract_address, client_arc.clone());
+    
+    let safe = safe.map(|safe| aliases::resolve(&safe)).transpose()?;
+    let admin = safe.unwrap_or(client_arc.address());
+    let owner = contract.owner().call().await?;
+    if owner != admin {
+        return Err(error::DexError::Input(format!("{:?} is not the DEX owner ({:?})", admin, owner)).into());
+    }
+    
+    if !tx_args.yes && !tx_args.estimate_only {
+        eprintln!("{}", summary);
+        tx::ask("Proceed?")?;
+    }
+    // Confirmed above, with the summary in place of a transaction preview
+    let tx_args = &tx_args.unattended();
+    
+    let method = call(&contract);
+    if let Some(safe) = safe {
+        let data = method.calldata().ok_or_else(|| anyhow::anyhow!("{} call has no calldata", function))?;
+        safe::submit(client_arc, safe, contract_address, data, summary, &fees, tx_args).await?;
+        return Ok(());
+    }
+    let receipt = tx::send(client_arc.as_ref(), method, &fees, tx_args).await?;
+    
+    if let Some(receipt) = receipt {
+        tx::ensure_success(client_arc.as_ref(), &receipt).await?;
+        info!("{} completed successfully!", function);
+        info!("Transaction hash: {:?}", receipt.transaction_hash);
+    }
+    
+    Ok(())
+}
+
+fn run_alias_command(command: AliasCommands, format: OutputFormat) -> Result<()> {
+    let mut book = AddressBook::load()?;
+    match command {
+        AliasCommands::Add { name, address, force } => {
+            let address = address.parse::<Address>()?;
+            book.add(&name, address, force)?;
+            book.save()?;
+            println!("{} -> {:?}", name.to_lowercase(), address);
+        }
+        AliasCommands::Remove { name } => {
+            let address = book.remove(&name)?;
+            book.save()?;
+            println!("Removed {} ({:?})", name, address);
+        }
+        AliasCommands::List => match format {
+            OutputFormat::Json => output::print_json(&book.aliases)?,
+            OutputFormat::Csv => {
+                let rows: Vec<_> = book.aliases.iter().collect();
+                output::print_csv(&rows)?;
+            }
+            OutputFormat::Text => {
+                println!("Aliases:");
+                println!("========");
+                if book.aliases.is_empty() {
+                    println!("(none)");
+                }
+                for (name, address) in &book.aliases {
+                    println!("{:<16} {:?}", name, address);
+                }
+            }
+        },
+    }
+    
+    Ok(())
+}
+
+/// CSV row for a journal entry: ID, status, intent, nonce, hash and idempotency key
+fn journal_row(entry: &JournalEntry) -> (u64, String, String, String, Option<String>, Option<String>) {
+    (
+        entry.id,
+        format!("{:?}", entry.status).to_lowercase(),
+        entry.intent.clone(),
+        entry.nonce.to_string(),
+        entry.tx_hash.map(|hash| format!("{:?}", hash)),
+        entry.idempotency_key.clone(),
+    )
+}
+
+async fn run_ctl_command(socket: &str, command: CtlCommands, format: OutputFormat) -> Result<()> {
+    let request = match command {
+        CtlCommands::Status => Request::Status,
+        CtlCommands::CancelAll => Request::CancelAll,
+        CtlCommands::Resume => Request::Resume,
+        CtlCommands::Reload => Request::Reload,
+        CtlCommands::Stop => Request::Stop,
+    };
+    match daemon::request(socket, &request).await? {
+        Response::Status(status) => print_daemon_status(&status, format)?,
+        Response::Done { message } => match format {
+            OutputFormat::Json => output::print_json(&serde_json::json!({ "message": message }))?,
+            _ => println!("{}", message),
+        },
+        Response::Error { message } => return Err(anyhow::anyhow!("dexd: {}", message)),
+    }
+    Ok(())
+}
+
+fn print_daemon_status(status: &DaemonStatus, format: OutputFormat) -> Result<()> {
+    match format {
+        OutputFormat::Json => output::print_json(status)?,
+        OutputFormat::Csv => {
+            let rows: Vec<_> = status
+                .tasks
+                .iter()
+                .map(|task| {
+                    (
+                        task.name.clone(),
+                        format!("{:?}", task.state).to_lowercase(),
+                        task.detail.clone().unwrap_or_default(),
+                        task.error.clone().unwrap_or_default(),
+                        task.updated_at,
+                    )
+                })
+                .collect();
+            output::print_csv(&rows)?;
+        }
+        OutputFormat::Text => {
+            println!("dexd Status");
+            println!("================================");
+            println!("PID: {}", status.pid);
+            println!("Up for: {}", humantime::format_duration(Duration::from_secs(expiry::unix_secs(SystemTime::now()).saturating_sub(status.started_at))));
+            println!("DEX: {:?}", status.dex);
+            match &status.halt {
+                Some(halt) => println!("Trading: HALTED ({}{})", halt.reason, if halt.cancel_orders { ", cancelling orders" } else { "" }),
+                None => println!("Trading: running"),
+            }
+            println!();
+            if status.tasks.is_empty() {
+                println!("No tasks configured.");
+            }
+            for task in &status.tasks {
+                let state = format!("{:?}", task.state).to_lowercase();
+                println!("{:<28} {:<9} {}", task.name, state, task.detail.as_deref().unwrap_or(""));
+                if let Some(error) = &task.error {
+                    println!("{:<28} error: {}", "", error);
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+fn run_journal_command(command: JournalCommands, format: OutputFormat) -> Result<()> {
+    let journal = TxJournal::load()?;
+    match command {
+        JournalCommands::List { status, limit } => {
+            let entries: Vec<&JournalEntry> = journal
+                .entries
+                .iter()
+                .rev()
+                .filter(|entry| status.is_none_or(|status| entry.status == status))
+                .take(limit)
+                .collect();
+            match format {
+                OutputFormat::Json => output::print_json(&entries)?,
+                OutputFormat::Csv => {
+                    let rows: Vec<_> = entries.into_iter().map(journal_row).collect();
+                    output::print_csv(&rows)?;
+                }
+                OutputFormat::Text => {
+                    println!("Transaction Journal");
+                    println!("================================");
+                    if entries.is_empty() {
+                        println!("No transactions recorded.");
+                    }
+                    for entry in entries {
+                        let hash = entry.tx_hash.map_or("-".to_string(), |hash| format!("{:?}", hash));
+                        let key = entry.idempotency_key.as_deref().map_or(String::new(), |key| format!(" [{}]", key));
+                        println!(
+                            "#{:<5} {:<9} {:<24} nonce {:<6} {}{}",
+                            entry.id,
+                            format!("{:?}", entry.status).to_lowercase(),
+                            entry.intent,
+                            entry.nonce,
+                            hash,
+                            key
+                        );
+                    }
+                }
+            }
+        }
+        JournalCommands::Show { entry } => {
+            let entry = journal
+                .find(&entry)
+                .ok_or_else(|| anyhow::anyhow!("No journal entry with ID, hash or idempotency key '{}'", entry))?;
+            match format {
+                OutputFormat::Json => output::print_json(entry)?,
+                OutputFormat::Csv => output::print_csv(&[journal_row(entry)])?,
+                OutputFormat::Text => {
+                    println!("Journal Entry #{}", entry.id);
+                    println!("================================");
+                    println!("Intent: {}", entry.intent);
+                    println!("Status: {:?}", entry.status);
+                    if let Some(key) = &entry.idempotency_key {
+                        println!("Idempotency key: {}", key);
+                    }
+                    println!("Chain ID: {}", entry.chain_id);
+                    println!("From: {:?} (nonce {})", entry.from, entry.nonce);
+                    println!("To: {}", entry.to.map_or("-".to_string(), |to| format!("{:?}", to)));
+                    if !entry.value.is_zero() {
+                        println!("Value: {} wei", entry.value);
+                    }
+                    println!("Calldata hash: {:?}", entry.calldata_hash);
+                    match entry.tx_hash {
+                        Some(hash) => {
+                            println!("Transaction: {:?}", hash);
+                            if let Some(link) = rpc::network().ok().and_then(|network| network.explorer_link(&format!("tx/{:?}", hash))) {
+                                println!("Explorer: {}", link);
+                            }
+                        }
+                        None => println!("Transaction: not broadcast"),
+                    }
+                    for hash in &entry.replaced {
+                        println!("Replaced: {:?}", hash);
+                    }
+                    if let Some(block) = entry.block {
+                        println!("Block: {}", block);
+                    }
+                    if let Some(error) = &entry.error {
+                        println!("Error: {}", error);
+                    }
+                    println!("Created: {}", entry.created_at);
+                    println!("Updated: {}", entry.updated_at);
+                }
+            }
+        }
+        JournalCommands::Runs { all } => {
+            let runs: Vec<_> = journal.runs.iter().rev().filter(|run| all || run.status == RunStatus::Running).collect();
+            match format {
+                OutputFormat::Json => output::print_json(&runs)?,
+                OutputFormat::Csv => {
+                    let rows: Vec<_> = runs
+                        .iter()
+                        .map(|run| (&run.id, &run.kind, &run.source, run.completed.len(), run.total, format!("{:?}", run.status).to_lowercase()))
+                        .collect();
+                    output::print_csv(&rows)?;
+                }
+                OutputFormat::Text => {
+                    println!("Batch Runs");
+                    println!("================================");
+                    if runs.is_empty() {
+                        println!("No {}runs recorded.", if all { "" } else { "interrupted " });
+                    }
+                    for run in runs {
+                        println!(
+                            "{:<24} {:<9} {}/{} steps  {}",
+                            run.id,
+                            format!("{:?}", run.status).to_lowercase(),
+                            run.completed.len(),
+                            run.total,
+                            run.source
+                        );
+                    }
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+fn run_network_command(command: NetworkCommands, format: OutputFormat) -> Result<()> {
+    let mut registry = NetworkRegistry::load()?;
+    match command {
+        NetworkCommands::Add { name, chain_id, rpc_url, explorer_url, explorer_api, contracts, headers, bearer_token, basic_auth, mark_read_only } => {
+            let contracts = contracts
+                .iter()
+                .map(|contract| {
+                    let (contract, address) = contract
+                        .split_once('=')
+                        .ok_or_else(|| anyhow::anyhow!("Expected NAME=ADDRESS, got '{}'", contract))?;
+                    Ok((contract.trim().to_string(), address.trim().parse::<Address>()?))
+                })
+                .collect::<Result<BTreeMap<_, _>>>()?;
+            let headers = headers
+                .iter()
+                .map(|header| {
+                    let (header, value) = header
+                        .split_once(':')
+                        .ok_or_else(|| anyhow::anyhow!("Expected \"NAME: VALUE\", got '{}'", header))?;
+                    Ok((header.trim().to_string(), value.trim().to_string()))
+                })
+                .collect::<Result<BTreeMap<_, _>>>()?;
+            let basic_auth = basic_auth
+                .map(|basic_auth| {
+                    let (username, password) = basic_auth
+                        .split_once(':')
+                        .ok_or_else(|| anyhow::anyhow!("Expected USER:PASSWORD for --basic-auth"))?;
+                    Ok::<_, anyhow::Error>(BasicAuth { username: username.to_string(), password: password.to_string() })
+                })
+                .transpose()?;
+            let auth = RpcAuth { headers, bearer_token, basic_auth };
+            let rpc_urls = rpc_url.split(',').map(str::trim).filter(|url| !url.is_empty()).map(str::to_string).collect();
+            registry.add(&name, Network { chain_id, rpc_urls, explorer_url, explorer_api, contracts, auth, read_only: mark_read_only });
+            registry.save()?;
+            println!("Saved network {} (chain {}){}", name.to_lowercase(), chain_id, if mark_read_only { ", read-only" } else { "" });
+        }
+        NetworkCommands::Remove { name } => {
+            registry.remove(&name)?;
+            registry.save()?;
+            println!("Removed {}", name);
+        }
+        NetworkCommands::List => {
+            let networks = registry.all();
+            match format {
+                OutputFormat::Json => output::print_json(&networks)?,
+                OutputFormat::Csv => {
+                    let rows: Vec<_> = networks
+                        .iter()
+                        .map(|(name, network)| (name, network.chain_id, network.rpc_urls.join(" "), network.explorer_url.clone()))
+                        .collect();
+                    output::print_csv(&rows)?;
+                }
+                OutputFormat::Text => {
+                    let selected = rpc::network_name();
+                    println!("Networks:");
+                    println!("=========");
+                    for (name, network) in &networks {
+                        let marker = if *name == selected { "*" } else { " " };
+                        let source = if registry.networks.contains_key(name) { "saved" } else { "built in" };
+                        println!("{} {:<16} chain {:<8} {}", marker, name, network.chain_id, source);
+                    }
+                }
+            }
+        }
+        NetworkCommands::Show { name } => {
+            let name = name.unwrap_or_else(rpc::network_name);
+            let network = registry.get(&name)?;
+            match format {
+                OutputFormat::Json => output::print_json(&network)?,
+                OutputFormat::Csv => {
+                    let rows: Vec<_> = network.contracts.iter().collect();
+                    output::print_csv(&rows)?;
+                }
+                OutputFormat::Text => {
+                    println!("Network {}", name.to_lowercase());
+                    println!("================================");
+                    println!("Chain ID: {}", network.chain_id);
+                    if network.rpc_urls.is_empty() {
+                        println!("RPC URLs: (none; pass --rpc-url)");
+                    } else {
+                        println!("RPC URLs: {}", network.rpc_urls.join(", "));
+                    }
+                    println!("Explorer: {}", network.explorer_url.as_deref().unwrap_or("-"));
+                    println!("Explorer API: {}", network.explorer_api.as_deref().unwrap_or("-"));
+                    if network.read_only {
+                        println!("Read-only: signing and sending transactions is refused");
+                    }
+                    // Names only; the values are secrets
+                    let mut auth: Vec<String> = network.auth.headers.keys().map(|header| format!("header {}", header)).collect();
+                    if network.auth.bearer_token.is_some() {
+                        auth.push("bearer token".to_string());
+                    }
+                    if let Some(basic) = &network.auth.basic_auth {
+                        auth.push(format!("basic auth as {}", basic.username));
+                    }
+                    if !auth.is_empty() {
+                        println!("RPC auth: {}", auth.join(", "));
+                    }
+                    println!("Contracts:");
+                    if network.contracts.is_empty() {
+                        println!("  (none)");
+                    }
+                    for (contract, address) in &network.contracts {
+                        println!("  {:<16} {:?}", contract, address);
+                    }
+                }
+            }
+        }
+    }
+    
+    Ok(())
+}
+
+fn run_price_band_command(command: PriceBandCommands, format: OutputFormat) -> Result<()> {
+    let bands = match command {
+        PriceBandCommands::Set { max_mid_deviation_bps, reference, max_reference_deviation_bps } => {
+            let bands = PriceBands { max_mid_deviation_bps, reference, max_reference_deviation_bps };
+            bands.save()?;
+            bands
+        }
+        PriceBandCommands::Show => PriceBands::load()?,
+        PriceBandCommands::Clear => {
+            let bands = PriceBands::default();
+            bands.save()?;
+            bands
+        }
+    };
+    
+    match format {
+        OutputFormat::Json => output::print_json(&bands)?,
+        OutputFormat::Csv => output::print_csv(&[(bands.max_mid_deviation_bps, bands.reference.clone(), bands.max_reference_deviation_bps)])?,
+        OutputFormat::Text => {
+            println!("Price bands:");
+            println!("============");
+            if bands.is_empty() {
+                println!("(none)");
+            }
+            if let Some(bps) = bands.max_mid_deviation_bps {
+                println!("Within {} bps of the book mid", bps);
+            }
+            if let (Some(bps), Some(reference)) = (bands.max_reference_deviation_bps, &bands.reference) {
+                println!("Within {} bps of the reference price {}", bps, reference);
+            }
+        }
+    }
+    
+    Ok(())
+}
+
+async fn get_feed_price(symbol: String, format: OutputFormat) -> Result<()> {
+    let price = PriceFeeds::load()?.price(&symbol).await?;
+    
+    match format {
+        OutputFormat::Json => output::print_json(&price)?,
+        OutputFormat::Csv => output::print_csv(&[&price])?,
+        OutputFormat::Text => {
+            println!("{}: {}", price.symbol, price.price);
+            match price.age_secs {
+                Some(age) => println!("Source: {} ({}s old)", price.source, age),
+                None => println!("Source: {}", price.source),
+            }
+        }
+    }
+    
+    Ok(())
+}
+
+fn run_price_feed_command(command: PriceFeedCommands, format: OutputFormat) -> Result<()> {
+    let mut feeds = PriceFeeds::load()?;
+    match command {
+        PriceFeedCommands::Set { symbol, sources, max_age_secs, scale, rpc_url } => {
+            feeds.set(&symbol, Feed { sources, max_age_secs, scale, rpc_url })?;
+            feeds.save()?;
+            println!("Price feed {} saved", symbol.to_uppercase());
+        }
+        PriceFeedCommands::Remove { symbol } => {
+            if feeds.remove(&symbol).is_none() {
+                return Err(anyhow::anyhow!("No price feed '{}'", symbol));
+            }
+            feeds.save()?;
+            println!("Price feed {} removed", symbol.to_uppercase());
+        }
+        PriceFeedCommands::List => match format {
+            OutputFormat::Json => output::print_json(&feeds.feeds)?,
+            OutputFormat::Csv => {
+                let rows: Vec<_> = feeds
+                    .feeds
+                    .iter()
+                    .map(|(symbol, feed)| (symbol.clone(), feed.sources.join(" "), feed.max_age_secs, feed.scale))
+                    .collect();
+                output::print_csv(&rows)?;
+            }
+            OutputFormat::Text => {
+                println!("Price feeds:");
+                println!("============");
+                if feeds.feeds.is_empty() {
+                    println!("(none)");
+                }
+                for (symbol, feed) in &feeds.feeds {
+                    println!("{}: {} (max age {}s, scale {})", symbol, feed.sources.join(", "), feed.max_age_secs, feed.scale);
+                }
+            }
+        },
+    }
+    
+    Ok(())
+}
+
+async fn run_risk_command(command: RiskCommands, format: OutputFormat) -> Result<()> {
+    match command {
+        RiskCommands::Set { max_open_orders, max_pair_notional, max_position, max_daily_loss, index_from_block } => {
+            let limits = RiskLimits {
+                max_open_orders,
+                max_pair_notional: max_pair_notional.map(U256::from),
+                max_position: max_position.map(U256::from),
+                max_daily_loss: max_daily_loss.map(U256::from),
+                index_from_block,
+            };
+            limits.save()?;
+            println!("Risk limits saved{}", if limits.is_empty() { " (all off)" } else { "" });
+        }
+        RiskCommands::Clear => {
+            RiskLimits::default().save()?;
+            println!("Risk limits cleared");
+        }
+        RiskCommands::Status { address, base_token, quote_token, trader, private_key, rpc_url } => {
+            let provider = rpc::connect(rpc_url.as_deref()).await?;
+            let trader = match trader {
+                Some(trader) => aliases::resolve(&trader)?,
+                None => rpc::wallet(&provider, private_key.as_deref()).await?.address(),
+            };
+            let contract_address = aliases::resolve(&address)?;
+            let base_token = aliases::resolve(&base_token)?;
+            let quote_token = aliases::resolve(&quote_token)?;
+            
+            let limits = RiskLimits::load()?;
+            let mut engine = RiskEngine::new(Arc::new(provider), contract_address, trader, limits.clone());
+            let exposure = engine.exposure(base_token, quote_token).await?;
+            
+            // (limit, used, maximum); usage is shown even for limits that are off
+            let rows: Vec<(&str, f64, Option<f64>)> = vec![
+                ("open_orders", exposure.open_orders as f64, limits.max_open_orders.map(|max| max as f64)),
+                ("pair_notional", portfolio::u256_to_f64(exposure.pair_notional), limits.max_pair_notional.map(portfolio::u256_to_f64)),
+                ("position", exposure.worst_position(0.0, 0.0), limits.max_position.map(portfolio::u256_to_f64)),
+                ("daily_loss", exposure.daily_pnl.map_or(0.0, |pnl| (-pnl).max(0.0)), limits.max_daily_loss.map(portfolio::u256_to_f64)),
+            ];
+            let utilization = |used: f64, max: Option<f64>| max.filter(|max| *max > 0.0).map(|max| used / max * 100.0);
+            
+            match format {
+                OutputFormat::Json => output::print_json(&serde_json::json!({
+                    "trader": trader,
+                    "limits": limits,
+                    "exposure": exposure,
+                }))?,
+                OutputFormat::Csv => output::print_csv(&rows.iter()
+                    .map(|(limit, used, max)| (*limit, *used, *max, utilization(*used, *max)))
+                    .collect::<Vec<_>>())?,
+                OutputFormat::Text => {
+                    println!("Risk status for {:?}", trader);
+                    println!("=============================================================");
+                    if limits.is_empty() {
+                        println!("No risk limits set (see `risk set`)");
+                    }
+                    for (limit, used, max) in &rows {
+                        match (max, utilization(*used, *max)) {
+                            (Some(max), Some(pct)) => println!("{:<14} {:>16.0} / {:<16.0} {:>6.1}%", limit, used, max, pct),
+                            (Some(max), None) => println!("{:<14} {:>16.0} / {:<16.0}", limit, used, max),
+                            (None, _) => println!("{:<14} {:>16.0} / off", limit, used),
+                        }
+                    }
+                    if exposure.position.is_none() {
+                        println!("Position and daily loss come from indexed fills and are only computed while a limit on them is set");
+                    }
+                }
+            }
+        }
+    }
+    
+    Ok(())
+}
+
+fn run_limits_command(command: LimitsCommands, format: OutputFormat) -> Result<()> {
+    match command {
+        LimitsCommands::Set { max_withdrawal, max_daily_withdrawal, max_order_notional, max_daily_order_notional, max_mint, max_daily_mint } => {
+            let cap = |per_command: Option<u64>, per_day: Option<u64>| Cap {
+                per_command: per_command.map(U256::from),
+                per_day: per_day.map(U256::from),
+            };
+            let limits = SpendingLimits {
+                withdrawal: cap(max_withdrawal, max_daily_withdrawal),
+                order_notional: cap(max_order_notional, max_daily_order_notional),
+                mint: cap(max_mint, max_daily_mint),
+            };
+            limits.save()?;
+            println!("Spending limits saved{}", if limits.is_empty() { " (all off)" } else { "" });
+        }
+        LimitsCommands::Clear => {
+            SpendingLimits::default().save()?;
+            println!("Spending limits cleared");
+        }
+        LimitsCommands::Status => {
+            let limits = SpendingLimits::load()?;
+            let ledger = SpendingLedger::load()?;
+            match format {
+                OutputFormat::Json => output::print_json(&serde_json::json!({
+                    "limits": limits,
+                    "spent_today": ledger.spent,
+                }))?,
+                OutputFormat::Csv => {
+                    let mut rows = Vec::new();
+                    for spend in Spend::ALL {
+                        let cap = limits.cap(spend);
+                        for (token, spent) in ledger.spent.get(&spend).into_iter().flatten() {
+                            rows.push((spend.to_string(), *token, *spent, cap.per_command, cap.per_day));
+                        }
+                    }
+                    output::print_csv(&rows)?;
+                }
+                OutputFormat::Text => {
+                    let cap = |max: Option<U256>| max.map_or("off".to_string(), |max| max.to_string());
+                    println!("Spending Limits");
+                    println!("================================");
+                    if limits.is_empty() {
+                        println!("No spending limits set (see `limits set`)");
+                    }
+                    for spend in Spend::ALL {
+                        let limit = limits.cap(spend);
+                        println!("{}: {} per command, {} per day", spend, cap(limit.per_command), cap(limit.per_day));
+                        for (token, spent) in ledger.spent.get(&spend).into_iter().flatten() {
+                            println!("  {:?}: {} spent today", token, spent);
+                        }
+                    }
+                }
+            }
+        }
+    }
+    
+    Ok(())
+}
+
+async fn run_export_command(command: ExportCommands) -> Result<()> {
+    type Build = fn(&EventStore, &ExportFilter) -> Table;
+    let (kind, build, address, pair, start_block, end_block, format, out, from_block, rpc_url) = match command {
+        ExportCommands::Trades { address, base_token, quote_token, start_block, end_block, format, out, from_block, rpc_url } => {
+            ("trades", Table::trades as Build, address, base_token.zip(quote_token), start_block, end_block, format, out, from_block, rpc_url)
+        }
+        ExportCommands::Orders { address, base_token, quote_token, start_block, end_block, format, out, from_block, rpc_url } => {
+            ("orders", Table::orders as Build, address, base_token.zip(quote_token), start_block, end_block, format, out, from_block, rpc_url)
+        }
+    };
+    let pair = match pair {
+        Some((base, quote)) => Some((aliases::resolve(&base)?, aliases::resolve(&quote)?)),
+        None => None,
+    };
+    let filter = ExportFilter { pair, from_block: start_block, to_block: end_block };
+    
+    let provider = rpc::connect(rpc_url.as_deref()).await?;
+    let contract_address = aliases::resolve(&address)?;
+    let mut store = EventStore::load(contract_address, from_block)?;
+    store.sync(&provider, &dex_abi(), DEFAULT_CHUNK_SIZE).await?;
+    
+    let table = build(&store, &filter);
+    let out = out.unwrap_or_else(|| format!("{}.{}", kind, format.extension()));
+    table.write(format, &out)?;
+    if out != "-" {
+        println!("Exported {} {} to {}", table.rows(), kind, out);
+    }
+    
+    Ok(())
+}
+
+async fn run_offline_command(command: OfflineCommands, format: OutputFormat, tx_args: &TxArgs) -> Result<()> {
+    match command {
+        OfflineCommands::Show { file } => {
+            if !std::path::Path::new(&file).exists() {
+                return Err(anyhow::anyhow!("{} does not exist", file));
+            }
+            // Signed files carry the hash and raw bytes on top of the prepared fields
+            let (chain_id, txs): (u64, Vec<(String, TypedTransaction, Option<H256>)>) = match SignedBatch::load(&file) {
+                Ok(signed) => (signed.chain_id, signed.txs.into_iter().map(|tx| (tx.intent, tx.tx, Some(tx.hash))).collect()),
+                Err(_) => {
+                    let prepared = PreparedBatch::load(&file)?;
+                    (prepared.chain_id, prepared.txs.into_iter().map(|tx| (tx.intent, tx.tx, None)).collect())
+                }
+            };
+            match format {
+                OutputFormat::Json => {
+                    let txs: Vec<_> = txs
+                        .iter()
+                        .map(|(intent, tx, hash)| serde_json::json!({ "intent": intent, "tx": tx, "hash": hash }))
+                        .collect();
+                    output::print_json(&serde_json::json!({ "chain_id": chain_id, "txs": txs }))?;
+                }
+                OutputFormat::Csv => {
+                    let rows: Vec<_> = txs
+                        .iter()
+                        .map(|(intent, tx, hash)| {
+                            (
+                                intent,
+                                tx.from().map(|from| format!("{:?}", from)).unwrap_or_default(),
+                                tx.to_addr().map(|to| format!("{:?}", to)).unwrap_or_default(),
+                                tx.nonce().map(|nonce| nonce.to_string()).unwrap_or_default(),
+                                hash.map(|hash| format!("{:?}", hash)).unwrap_or_default(),
+                            )
+                        })
+                        .collect();
+                    output::print_csv(&rows)?;
+                }
+                OutputFormat::Text => {
+                    println!("{} transaction(s) for chain {}", txs.len(), chain_id);
+                    println!("================================");
+                    for (intent, tx, hash) in &txs {
+                        let to = tx.to_addr().map_or("(deployment)".to_string(), |to| format!("{:?}", to));
+                        let status = hash.map_or("unsigned".to_string(), |hash| format!("{:?}", hash));
+                        println!(
+                            "nonce {:<6} {:<24} {:?} -> {} {}",
+                            tx.nonce().copied().unwrap_or_default(),
+                            intent,
+                            tx.from().copied().unwrap_or_default(),
+                            to,
+                            status
+                        );
+                    }
+                }
+            }
+        }
+        OfflineCommands::Sign { file, out, private_key } => {
+            let batch = PreparedBatch::load(&file)?;
+            if batch.txs.is_empty() {
+                return Err(anyhow::anyhow!("{} holds no prepared transactions", file));
+            }
+            let wallet = rpc::signing_key(private_key.as_deref())?;
+            let signed = offline::sign(&batch, &wallet)?;
+            signed.save(&out)?;
+            println!("Signed {} transaction(s) for chain {} into {}", signed.txs.len(), signed.chain_id, out);
+        }
+        OfflineCommands::Broadcast { file, rpc_url } => {
+            let batch = SignedBatch::load(&file)?;
+            let provider = rpc::connect(rpc_url.as_deref()).await?;
+            let receipts = offline::broadcast(&provider, &batch, tx_args.confirmations).await?;
+            for (signed, receipt) in batch.txs.iter().zip(&receipts) {
+                println!(
+                    "{}: {:?} in block {}",
+                    signed.intent,
+                    receipt.transaction_hash,
+                    receipt.block_number.unwrap_or_default()
+                );
+                if let Some(address) = receipt.contract_address {
+                    println!("  Contract deployed at {:?}", address);
+                }
+            }
+        }
+        OfflineCommands::QrShow { file, frame_size, interval_ms, once } => {
+            // Sent without the file's indentation, to keep the frame count down
+            let content = std::fs::read_to_string(&file)?;
+            let value: serde_json::Value = serde_json::from_str(&content)
+                .map_err(|e| anyhow::anyhow!("{} is not a prepared or signed transaction file: {}", file, e))?;
+            let frames = qr::Frames::encode(&serde_json::to_vec(&value)?, frame_size)?;
+            info!("{} as {} QR frame(s)", file, frames.frames.len());
+            frames.show(Duration::from_millis(interval_ms), once).await?;
+        }
+        OfflineCommands::QrRead { out } => {
+            let payload = qr::read_frames()?;
+            // Check it is what the other side meant to send before anything uses it
+            let value: serde_json::Value = serde_json::from_slice(&payload)?;
+            let summary = match (serde_json::from_value::<SignedBatch>(value.clone()), serde_json::from_value::<PreparedBatch>(value.clone())) {
+                (Ok(signed), _) => format!("{} signed transaction(s)", signed.txs.len()),
+                (_, Ok(prepared)) => format!("{} prepared transaction(s)", prepared.txs.len()),
+                _ => return Err(anyhow::anyhow!("The scanned payload is not a prepared or signed transaction file")),
+            };
+            std::fs::write(&out, serde_json::to_string_pretty(&value)?)?;
+            println!("Wrote {} for chain {} to {}", summary, value["chain_id"], out);
+        }
+    }
+    
+    Ok(())
+}
+
+fn run_wallet_command(command: WalletCommands, format: OutputFormat) -> Result<()> {
+    let mut store = WalletStore::load()?;
+    match command {
+        WalletCommands::Add { label, private_key } => {
+            let password = wallets::password(&label)?;
+            let address = store.add(&label, &private_key, &password)?;
+            store.save()?;
+            println!("{} -> {:?}", label.to_lowercase(), address);
+        }
+        WalletCommands::Remove { label } => {
+            let entry = store.remove(&label)?;
+            store.save()?;
+            println!("Removed {} ({:?})", label, entry.address);
+        }
+        WalletCommands::List => match format {
+            OutputFormat::Json => output::print_json(&store.wallets)?,
+            OutputFormat::Csv => {
+                let rows: Vec<_> = store.wallets.iter().map(|(label, entry)| (label, entry.address)).collect();
+                output::print_csv(&rows)?;
+            }
+            OutputFormat::Text => {
+                println!("Wallets:");
+                println!("========");
+                if store.wallets.is_empty() {
+                    println!("(none)");
+                }
+                for (label, entry) in &store.wallets {
+                    println!("{:<16} {:?}", label, entry.address);
+                }
+            }
+        },
+    }
+    
+    Ok(())
+}
+
+async fn run_session_command(command: SessionCommands, format: OutputFormat, tx_args: &TxArgs) -> Result<()> {
+    let mut store = SessionStore::load()?;
+    match command {
+        SessionCommands::Grant { key, tokens, max_order_notional, max_total_notional, ttl_secs, allowance, private_key, rpc_url } => {
+            let key = aliases::resolve(&key)?;
+            let tokens = tokens.iter().map(|token| aliases::resolve(token)).collect::<Result<Vec<_>, _>>()?;
+            let expires_at = ttl_secs.map(|ttl| {
+                std::time::SystemTime::now()
+                    .duration_since(std::time::UNIX_EPOCH)
+                    .map(|d| d.as_secs())
+                    .unwrap_or_default()
+                    + ttl
+            });
+            
+            // Cap what a compromised key could ever pull with an ERC-20 allowance per token
+            let mut pull_from = None;
+            if let Some(allowance) = allowance {
+                let provider = rpc::connect(rpc_url.as_deref()).await?;
+                let fees = tx_args.fees.resolve(&provider).await?;
+                let wallet = rpc::wallet(&provider, private_key.as_deref()).await?;
+                let client = Arc::new(SignerMiddleware::new(provider, wallet));
+                for token in &tokens {
+                    let method = ERC20::new(*token, client.clone()).approve(key, U256::from(allowance));
+                    tx::send(client.as_ref(), method, &fees, &tx_args.step(format!("approve/{:?}", token))).await?;
+                    info!("Approved {:?} to pull {} of {:?}", key, allowance, token);
+                }
+                pull_from = Some(client.address());
+            }
+            
+            store.sessions.insert(key, SessionPolicy {
+                tokens,
+                max_order_notional: U256::from(max_order_notional),
+                max_total_notional: U256::from(max_total_notional),
+                spent_notional: U256::zero(),
+                expires_at,
+                pull_from,
+            });
+            store.save()?;
+            println!("Session granted to {:?}", key);
+        }
+        SessionCommands::Revoke { key } => {
+            let key = aliases::resolve(&key)?;
+            store.revoke(key)?;
+            store.save()?;
+            println!("Session for {:?} revoked", key);
+        }
+        SessionCommands::List => match format {
+            OutputFormat::Json => output::print_json(&store.sessions)?,
+            OutputFormat::Csv => {
+                let rows: Vec<_> = store
+                    .sessions
+                    .iter()
+                    .map(|(key, policy)| (key, policy.max_order_notional.to_string(), policy.max_total_notional.to_string(), policy.spent_notional.to_string(), policy.expires_at))
+                    .collect();
+                output::print_csv(&rows)?;
+            }
+            OutputFormat::Text => {
+                println!("Session Keys:");
+                println!("=============");
+                if store.sessions.is_empty() {
+                    println!("(none)");
+                }
+                for (key, policy) in &store.sessions {
+                    let status = if policy.is_expired() { " (expired)" } else { "" };
+                    println!("{:?}{}", key, status);
+                    println!("  Tokens: {}", policy.tokens.iter().map(|t| format!("{:?}", t)).collect::<Vec<_>>().join(", "));
+                    println!("  Per order: {}", policy.max_order_notional);
+                    println!("  Used: {} of {}", policy.spent_notional, policy.max_total_notional);
+                    if let Some(from) = policy.pull_from {
+                        println!("  Funded from: {:?}", from);
+                    }
+                }
+            }
+        },
+    }
+    
+    Ok(())
+}
+
+#[allow(clippy::too_many_arguments)]
+async fn replay_book(
+    contract_address: String,
+    pair: Option<(String, String)>,
+    block: Option<u64>,
+    snapshot: Option<String>,
+    step: bool,
+    to_block: Option<u64>,
+    from_block: u64,
+    rpc_url: Option<String>,
+    format: OutputFormat
+) -> Result<()> {
+    let provider = rpc::connect(rpc_url.as_deref()).await?;
+    let contract_address = aliases::resolve(&contract_address)?;
+    
+    // Load contract ABI
+    let contract_abi = dex_abi();
+    
+    let mut store = EventStore::load(contract_address, from_block)?;
+    store.sync(&provider, &contract_abi, DEFAULT_CHUNK_SIZE).await?;
+    
+    let mut replay = match &snapshot {
+        Some(path) => {
+            let snapshot = BookSnapshot::load(std::path::Path::new(path))?;
+            info!("Starting from snapshot at block {}", snapshot.block);
+            Replay::from_snapshot(&store, &snapshot)?
+        }
+        None => {
+            let (base_token, quote_token) = pair.ok_or_else(|| anyhow::anyhow!("--base-token and --quote-token are required without --snapshot"))?;
+            Replay::new(&store, aliases::resolve(&base_token)?, aliases::resolve(&quote_token)?)
+        }
+    };
+    let events = replay.advance_to(block.unwrap_or(replay.block()))?;
+    print_replay_step(&replay, &events, format)?;
+    
+    if !step {
+        return Ok(());
+    }
+    let to_block = to_block.unwrap_or(store.last_block);
+    let stdin = std::io::stdin();
+    while let Some(next) = replay.next_active_block().filter(|&next| next <= to_block) {
+        if format == OutputFormat::Text {
+            eprint!("Enter for block {}, q to quit: ", next);
+            std::io::stderr().flush()?;
+            let mut line = String::new();
+            if stdin.read_line(&mut line)? == 0 || line.trim().eq_ignore_ascii_case("q") {
+                break;
+            }
+        }
+        let events = replay.advance_to(next)?;
+        print_replay_step(&replay, &events, format)?;
+    }
+    
+    Ok(())
+}
+
+fn print_replay_step(replay: &Replay, events: &[ReplayEvent], format: OutputFormat) -> Result<()> {
+    let orders: Vec<&ReplayOrder> = replay.orders().collect();
+    match format {
+        OutputFormat::Json => {
+            // One object per line so stepped output can be streamed
+            println!("{}", serde_json::json!({ "block": replay.block(), "events": events, "orders": orders }));
+        }
+        OutputFormat::Csv => {
+            let rows: Vec<_> = orders.iter().map(|o| (replay.block(), o.id, o.trader, o.is_buy, o.price, o.amount)).collect();
+            output::print_csv(&rows)?;
+        }
+        OutputFormat::Text => {
+            let title = format!("Book at Block {}", replay.block());
+            println!("{}", title);
+            println!("{}", "=".repeat(title.len()));
+            for event in events.iter().filter(|event| event.block() == replay.block()) {
+                match event {
+                    ReplayEvent::Placed { order, .. } => println!(
+                        "  + ID {} {} {} at {} by {:?}",
+                        order.id, if order.is_buy { "BUY" } else { "SELL" }, order.amount, order.price, order.trader
+                    ),
+                    ReplayEvent::Matched { buy_order_id, sell_order_id, amount, price, .. } => {
+                        println!("  = {} matched at {} (buy {} / sell {})", amount, price, buy_order_id, sell_order_id)
+                    }
+                    ReplayEvent::Cancelled { order_id, .. } => println!("  - ID {} cancelled", order_id),
+                }
+            }
+            
+            let (bids, asks): (Vec<&ReplayOrder>, Vec<&ReplayOrder>) = orders.iter().copied().partition(|o| o.is_buy);
+            println!("Bids:");
+            if bids.is_empty() {
+                println!("  (none)");
+            }
+            for order in bids {
+                println!("  ID {}: {} at {} ({:?})", order.id, order.amount, order.price, order.trader);
+            }
+            println!("Asks:");
+            if asks.is_empty() {
+                println!("  (none)");
+            }
+            for order in asks {
+                println!("  ID {}: {} at {} ({:?})", order.id, order.amount, order.price, order.trader);
+            }
+            println!();
+        }
+    }
+    Ok(())
+}
+
+/// Loadtest flags that feed straight into `LoadTestParams`
+struct LoadtestOptions {
+    rate: f64,
+    duration: Duration,
+    cancel_ratio: f64,
+    mid_price: Option<u64>,
+    spread_bps: u64,
+    min_amount: Option<u64>,
+    max_amount: Option<u64>,
+    gas_limit: u64,
+    receipt_timeout: Duration,
+    poll_ms: u64,
+}
+
+#[allow(clippy::too_many_arguments)]
+async fn run_loadtest(
+    contract_address: String,
+    base_token: String,
+    quote_token: String,
+    keys_file: Option<String>,
+    options: LoadtestOptions,
+    private_key: Option<String>,
+    rpc_url: Option<String>,
+    tx_args: &TxArgs,
+    format: OutputFormat
+) -> Result<()> {
+    let contract_address = aliases::resolve(&contract_address)?;
+    let base_token = aliases::resolve(&base_token)?;
+    let quote_token = aliases::resolve(&quote_token)?;
+    
+    // One provider per account so their requests don't queue behind each other
+    let mut clients = Vec::new();
+    match keys_file {
+        Some(path) => {
+            let keys = std::fs::read_to_string(&path)?;
+            for key in keys.lines().map(str::trim).filter(|line| !line.is_empty() && !line.starts_with('#')) {
+                let provider = rpc::connect(rpc_url.as_deref()).await?;
+                let wallet = rpc::wallet(&provider, Some(key)).await?;
+                clients.push(Arc::new(SignerMiddleware::new(provider, wallet)));
+            }
+        }
+        None => {
+            let provider = rpc::connect(rpc_url.as_deref()).await?;
+            let wallet = rpc::wallet(&provider, private_key.as_deref()).await?;
+            clients.push(Arc::new(SignerMiddleware::new(provider, wallet)));
+        }
+    }
+    if clients.is_empty() {
+        return Err(anyhow::anyhow!("No private keys found"));
+    }
+    
+    let fees = tx_args.fees.resolve(clients[0].inner()).await?;
+    
+    // Create contract instance
+    let contract = OrderBookDEX::new(contract_address, clients[0].clone());
+    let market = PairMarket::fetch(&contract, base_token, quote_token).await?;
+    let mid_price = match options.mid_price {
+        Some(price) => U256::from(price),
+        None => {
+            let book = market.book();
+            match (book.best_bid(), book.best_ask()) {
+                (Some(bid), Some(ask)) => (bid + ask) / 2,
+                (Some(price), None) | (None, Some(price)) => price,
+                (None, None) => return Err(anyhow::anyhow!("The book is empty; pass --mid-price")),
+            }
+        }
+    };
+    let min_amount = options.min_amount.map_or(market.min_order_size, U256::from);
+    let max_amount = options.max_amount.map_or(min_amount * 10, U256::from);
+    if max_amount < min_amount {
+        return Err(anyhow::anyhow!("--max-amount is below --min-amount"));
+    }
+    
+    let params = LoadTestParams {
+        dex: contract_address,
+        base_token,
+        quote_token,
+        rate: options.rate,
+        duration: options.duration,
+        cancel_ratio: options.cancel_ratio,
+        mid_price,
+        spread_bps: options.spread_bps,
+        min_amount,
+        max_amount,
+        gas_limit: U256::from(options.gas_limit),
+        receipt_timeout: options.receipt_timeout,
+        poll_interval: Duration::from_millis(options.poll_ms),
+    };
+    loadtest::prepare(&clients, &params, &fees, &tx_args.unattended()).await?;
+    if tx_args.estimate_only {
+        return Ok(());
+    }
+    let report = loadtest::run(clients, params, fees).await?;
+    
+    match format {
+        OutputFormat::Json => output::print_json(&report)?,
+        OutputFormat::Csv => output::print_csv(&[&report.confirmation_latency])?,
+        OutputFormat::Text => {
+            let latency = |stats: &LatencyStats| {
+                format!("p50 {} ms, p90 {} ms, p99 {} ms, max {} ms (mean {} ms, {} samples)",
+                    stats.p50_ms, stats.p90_ms, stats.p99_ms, stats.max_ms, stats.mean_ms, stats.samples)
+            };
+            println!("Load Test Results");
+            println!("=================");
+            println!("Accounts: {}", report.accounts);
+            println!("Elapsed: {:.1}s", report.elapsed_secs);
+            println!("Sent: {} places, {} cancels ({:.2} tx/s, target {:.2})", report.places_sent, report.cancels_sent, report.send_rate, report.target_rate);
+            println!("Confirmed: {} ({:.2} tx/s)", report.confirmed, report.throughput);
+            println!("Failed: {} refused, {} reverted, {} timed out ({:.2}%)",
+                report.send_failures, report.reverted, report.timed_out, report.failure_rate * 100.0);
+            println!("Confirmation latency: {}", latency(&report.confirmation_latency));
+            println!("Submit latency: {}", latency(&report.submit_latency));
+        }
+    }
+    
+    Ok(())
+}
+
+async fn run_snapshot_command(command: SnapshotCommands, format: OutputFormat) -> Result<()> {
+    match command {
+        SnapshotCommands::Save { address, base_token, quote_token, block, out, rpc_url } => {
+            let provider = rpc::connect(rpc_url.as_deref()).await?;
+            let contract_address = aliases::resolve(&address)?;
+            let base_token = aliases::resolve(&base_token)?;
+            let quote_token = aliases::resolve(&quote_token)?;
+            
+            // Pin both reads to one block so the snapshot is consistent
+            let block = match block {
+                Some(block) => block,
+                None => provider.get_block_number().await?.as_u64(),
+            };
+            
+            // Create contract instance
+            let contract = OrderBookDEX::new(contract_address, Arc::new(provider));
+            let (_, _, _, _, price_precision) = contract.trading_pairs(base_token, quote_token).block(block).call().await?;
+            let (buy_prices, buy_amounts, sell_prices, sell_amounts) =
+                contract.get_order_book(base_token, quote_token).block(block).call().await?;
+            
+            let snapshot = BookSnapshot {
+                dex: contract_address,
+                base_token,
+                quote_token,
+                block,
+                price_precision,
+                bids: buy_prices.into_iter().zip(buy_amounts).collect(),
+                asks: sell_prices.into_iter().zip(sell_amounts).collect(),
+            };
+            let path = out.map_or_else(|| snapshot.default_path(), std::path::PathBuf::from);
+            snapshot.save(&path)?;
+            println!("Saved {} bids and {} asks at block {} to {}", snapshot.bids.len(), snapshot.asks.len(), block, path.display());
+        }
+        SnapshotCommands::Load { file } => {
+            let snapshot = BookSnapshot::load(std::path::Path::new(&file))?;
+            match format {
+                OutputFormat::Json => output::print_json(&snapshot)?,
+                OutputFormat::Csv => {
+                    let rows: Vec<_> = snapshot
+                        .levels()
+                        .into_iter()
+                        .map(|((is_buy, price), (amount, orders))| (is_buy, price, amount, orders))
+                        .collect();
+                    output::print_csv(&rows)?;
+                }
+                OutputFormat::Text => {
+                    let title = format!("Order Book at Block {}", snapshot.block);
+                    println!("{}", title);
+                    println!("{}", "=".repeat(title.len()));
+                    println!("Pair: {:?} / {:?}", snapshot.base_token, snapshot.quote_token);
+                    
+                    let levels = snapshot.levels();
+                    println!("\nAsks:");
+                    for ((_, price), (amount, orders)) in levels.iter().filter(|((is_buy, _), _)| !is_buy).rev() {
+                        println!("  {} @ {} ({} orders)", amount, price, orders);
+                    }
+                    println!("\nBids:");
+                    for ((_, price), (amount, orders)) in levels.iter().filter(|((is_buy, _), _)| *is_buy).rev() {
+                        println!("  {} @ {} ({} orders)", amount, price, orders);
+                    }
+                }
+            }
+        }
+        SnapshotCommands::Diff { before, after } => {
+            let before = BookSnapshot::load(std::path::Path::new(&before))?;
+            let after = BookSnapshot::load(std::path::Path::new(&after))?;
+            let diffs = before.diff(&after)?;
+            match format {
+                OutputFormat::Json => output::print_json(&diffs)?,
+                OutputFormat::Csv => output::print_csv(&diffs)?,
+                OutputFormat::Text => {
+                    let title = format!("Book Changes: Block {} -> {}", before.block, after.block);
+                    println!("{}", title);
+                    println!("{}", "=".repeat(title.len()));
+                    if diffs.is_empty() {
+                        println!("No changes.");
+                    }
+                    for diff in &diffs {
+                        println!(
+                            "  {} {} {}: {} -> {} ({} -> {} orders)",
+                            if diff.is_buy { "BID" } else { "ASK" }, diff.price, diff.kind(),
+                            diff.before, diff.after, diff.orders_before, diff.orders_after
+                        );
+                    }
+                }
+            }
+        }
+    }
+    
+    Ok(())
+}
+
+async fn run_schedule_command(command: ScheduleCommands, format: OutputFormat, tx_args: &TxArgs) -> Result<()> {
+    match command {
+        ScheduleCommands::Add { address, base_token, quote_token, amount, price, is_buy, start_at, every, runs, duration } => {
+            let interval_secs = every.map(|every| every.as_secs().max(1));
+            let total_runs = match (runs, duration, interval_secs) {
+                (Some(runs), _, _) => runs,
+                (None, Some(duration), Some(interval)) => (duration.as_secs() / interval).max(1) as u32,
+                (None, _, _) => 1,
+            };
+            if total_runs > 1 && interval_secs.is_none() {
+                return Err(anyhow::anyhow!("--runs above 1 needs --every"));
+            }
+            
+            let mut store = ScheduleStore::load()?;
+            let id = store.add(ScheduledOrder {
+                id: 0,
+                dex: aliases::resolve(&address)?,
+                base_token: aliases::resolve(&base_token)?,
+                quote_token: aliases::resolve(&quote_token)?,
+                amount,
+                price,
+                is_buy,
+                next_run_at: expiry::unix_secs(start_at.unwrap_or_else(SystemTime::now)),
+                interval_secs,
+                total_runs,
+                runs: Vec::new(),
+                cancelled: false,
+            });
+            store.save()?;
+            println!("Schedule {} added: {} run(s); place them with `monad-dex schedule run`", id, total_runs);
+        }
+        ScheduleCommands::List => {
+            let store = ScheduleStore::load()?;
+            match format {
+                OutputFormat::Json => output::print_json(&store.schedules)?,
+                OutputFormat::Csv => {
+                    let rows: Vec<_> = store
+                        .schedules
+                        .iter()
+                        .map(|s| (s.id, s.base_token, s.quote_token, s.is_buy, s.amount, s.price, s.runs.len(), s.total_runs, s.next_run_at, s.cancelled))
+                        .collect();
+                    output::print_csv(&rows)?;
+                }
+                OutputFormat::Text => {
+                    println!("Schedules:");
+                    println!("==========");
+                    if store.schedules.is_empty() {
+                        println!("(none)");
+                    }
+                    for s in &store.schedules {
+                        let side = if s.is_buy { "BUY" } else { "SELL" };
+                        let price = s.price.map_or("market".to_string(), |price| format!("at {}", price));
+                        let status = if s.cancelled {
+                            "cancelled".to_string()
+                        } else if s.is_finished() {
+                            "finished".to_string()
+                        } else {
+                            format!("next {}", humantime::format_rfc3339_seconds(std::time::UNIX_EPOCH + Duration::from_secs(s.next_run_at)))
+                        };
+                        println!("{:>4}  {} {} {} ({:?}/{:?})  {}/{} runs, {}", s.id, side, s.amount, price, s.base_token, s.quote_token, s.runs.len(), s.total_runs, status);
+                    }
+                }
+            }
+        }
+        ScheduleCommands::Logs { id } => {
+            let mut store = ScheduleStore::load()?;
+            let schedule = store.get_mut(id)?;
+            match format {
+                OutputFormat::Json => output::print_json(&schedule.runs)?,
+                OutputFormat::Csv => {
+                    let rows: Vec<_> = schedule.runs.iter().map(|run| (run.at, run.tx_hash, run.order_id, run.error.clone())).collect();
+                    output::print_csv(&rows)?;
+                }
+                OutputFormat::Text => {
+                    println!("Schedule {} Runs:", id);
+                    println!("{}", "=".repeat(format!("Schedule {} Runs:", id).len()));
+                    if schedule.runs.is_empty() {
+                        println!("(none)");
+                    }
+                    for (i, run) in schedule.runs.iter().enumerate() {
+                        let at = humantime::format_rfc3339_seconds(std::time::UNIX_EPOCH + Duration::from_secs(run.at));
+                        let outcome = match (&run.error, run.tx_hash, run.order_id) {
+                            (Some(error), _, _) => format!("failed: {}", error),
+                            (None, Some(hash), Some(order_id)) => format!("order ID {} (tx {:?})", order_id, hash),
+                            (None, Some(hash), None) => format!("placed (tx {:?})", hash),
+                            (None, None, _) => "dropped from mempool".to_string(),
+                        };
+                        println!("  {}: {} {}", i + 1, at, outcome);
+                    }
+                }
+            }
+        }
+        ScheduleCommands::Cancel { id } => {
+            let mut store = ScheduleStore::load()?;
+            store.cancel(id)?;
+            store.save()?;
+            println!("Schedule {} cancelled", id);
+        }
+        ScheduleCommands::Run { interval_secs, once, private_key, rpc_url } => {
+            run_schedules(interval_secs, once, private_key, rpc_url, tx_args).await?;
+        }
+    }
+    
+    Ok(())
+}
+
+/// Place every scheduled order that has come due, logging each run to the schedule store
+async fn run_schedules(
+    interval_secs: u64,
+    once: bool,
+    private_key: Option<String>,
+    rpc_url: Option<String>,
+    tx_args: &TxArgs
+) -> Result<()> {
+    let provider = rpc::connect(rpc_url.as_deref()).await?;
+    let wallet = rpc::wallet(&provider, private_key.as_deref()).await?;
+    let client = SignerMiddleware::new(provider, wallet);
+    
+    let client_arc = Arc::new(client);
+    let tx_args = &tx_args.unattended();
+    info!("Running scheduled orders for {:?} every {}s", client_arc.address(), interval_secs);
+    
+    loop {
+        schedule::run_due(client_arc.clone(), rpc_url.as_deref(), tx_args).await?;
+        
+        if once {
+            return Ok(());
+        }
+        tokio::select! {
+            _ = tokio::time::sleep(Duration::from_secs(interval_secs)) => {}
+            _ = tokio::signal::ctrl_c() => return Ok(()),
+        }
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+async fn route_swap(
+    contract_address: String,
+    from: String,
+    to: String,
+    via: String,
+    amount: u64,
+    steps: usize,
+    gas_per_tx: u64,
+    execute: bool,
+    private_key: Option<String>,
+    rpc_url: Option<String>,
+    format: OutputFormat,
+    tx_args: &TxArgs
+) -> Result<()> {
+    let provider = rpc::connect(rpc_url.as_deref()).await?;
+    let contract_address = aliases::resolve(&contract_address)?;
+    let from = aliases::resolve(&from)?;
+    let to = aliases::resolve(&to)?;
+    let via = aliases::resolve(&via)?;
+    if from == to || via == from || via == to {
+        return Err(anyhow::anyhow!("--from, --to and --via must be three different tokens"));
+    }
+    
+    // Create contract instance
+    let client = Arc::new(provider);
+    let contract = OrderBookDEX::new(contract_address, client.clone());
+    
+    // Every pair between the tokens involved, plus native pairs for pricing gas
+    let mut tokens = vec![from, to, via];
+    if !tokens.contains(&Address::zero()) {
+        tokens.push(Address::zero());
+    }
+    let mut markets = arb::Markets::new();
+    for &base_token in &tokens {
+        for &quote_token in tokens.iter().filter(|&&token| token != base_token) {
+            if let Ok(market) = PairMarket::fetch(&contract, base_token, quote_token).await {
+                markets.insert((base_token, quote_token), market);
+            }
+        }
+    }
+    let gas_price = client.get_gas_price().await?;
+    
+    let quote = route::quote(&markets, from, to, via, U256::from(amount), gas_price, gas_per_tx, steps)
+        .ok_or_else(|| anyhow::anyhow!("No direct or routed path can fill {} of {:?}", amount, from))?;
+    
+    match format {
+        OutputFormat::Json => output::print_json(&quote)?,
+        OutputFormat::Csv => {
+            let rows: Vec<_> = quote
+                .best
+                .legs()
+                .map(|leg| (leg.base_token, leg.quote_token, leg.is_buy, leg.amount, leg.input, leg.output))
+                .collect();
+            output::print_csv(&rows)?;
+        }
+        OutputFormat::Text => {
+            let best = &quote.best;
+            println!("Route Quote");
+            println!("===========");
+            println!("{:?} -> {:?} (via {:?})", from, to, via);
+            println!("Split: {:.0}% direct, {:.0}% via intermediate", best.direct_pct, 100.0 - best.direct_pct);
+            for leg in best.legs() {
+                println!(
+                    "  {} {} on {:?} / {:?}: in {} out {}",
+                    if leg.is_buy { "BUY" } else { "SELL" }, leg.amount, leg.base_token, leg.quote_token, leg.input, leg.output
+                );
+            }
+            println!("Input:        {}", best.input);
+            println!("Output:       {}", best.output);
+            match best.gas_in_output {
+                Some(gas) => println!("Gas:          {} wei ({} in output token)", best.gas_cost, gas),
+                None => println!("Gas:          {} wei (no book prices it in the output token)", best.gas_cost),
+            }
+            println!("Net output:   {}", best.net_output);
+            match &quote.direct_only {
+                Some(direct) => println!("Direct only:  {} net", direct.net_output),
+                None => println!("Direct only:  book too thin to fill the whole amount"),
+            }
+            if let Some(improvement) = quote.improvement_bps {
+                println!("Improvement:  {:+.2} bps over direct", improvement);
+            }
+        }
+    }
+    
+    if execute {
+        let legs: Vec<Leg> = quote.best.legs().cloned().collect();
+        execute_legs(&legs, contract_address, private_key, rpc_url.as_deref(), tx_args).await?;
+    }
+    
+    Ok(())
+}
+
+async fn watch_mempool(
+    contract_address: String,
+    base_token: Option<String>,
+    quote_token: Option<String>,
+    interval_ms: u64,
+    rpc_url: Option<String>,
+    format: OutputFormat
+) -> Result<()> {
+    let provider = rpc::connect(rpc_url.as_deref()).await?;
+    let contract_address = aliases::resolve(&contract_address)?;
+    let pair = match (base_token, quote_token) {
+        (Some(base_token), Some(quote_token)) => Some((aliases::resolve(&base_token)?, aliases::resolve(&quote_token)?)),
+        _ => None,
+    };
+    
+    // Create contract instance
+    let contract = OrderBookDEX::new(contract_address, Arc::new(provider));
+    let mut watcher = MempoolWatcher::new(contract, pair, None).await?;
+    info!("Watching pending transactions to {:?}", contract_address);
+    
+    if format == OutputFormat::Text {
+        println!("Pending DEX Transactions");
+        println!("========================");
+    }
+    loop {
+        for activity in watcher.poll().await? {
+            print_pending(&activity, format)?;
+        }
+        
+        tokio::select! {
+            _ = tokio::time::sleep(Duration::from_millis(interval_ms)) => {}
+            _ = tokio::signal::ctrl_c() => {
+                info!("Stopping mempool watch");
+                return Ok(());
+            }
+        }
+    }
+}
+
+fn print_pending(activity: &PendingActivity, format: OutputFormat) -> Result<()> {
+    match format {
+        // One object per line so the output can be streamed
+        OutputFormat::Json => println!("{}", serde_json::to_string(activity)?),
+        OutputFormat::Csv => {
+            let (kind, order_id, price, amount, is_buy) = match &activity.action {
+                PendingAction::LimitOrder { price, amount, is_buy } => ("limit", None, Some(*price), *amount, *is_buy),
+                PendingAction::MarketOrder { amount, is_buy } => ("market", None, None, *amount, *is_buy),
+                PendingAction::Cancel { order_id, price, amount, is_buy } => ("cancel", Some(*order_id), Some(*price), *amount, *is_buy),
+            };
+            output::print_csv(&[(activity.tx_hash, activity.from, activity.base_token, activity.quote_token, kind, order_id, price, amount, is_buy)])?;
+        }
+        OutputFormat::Text => {
+            let side = |is_buy: bool| if is_buy { "BUY" } else { "SELL" };
+            let description = match &activity.action {
+                PendingAction::LimitOrder { price, amount, is_buy } => format!("LIMIT {} {} at {}", side(*is_buy), amount, price),
+                PendingAction::MarketOrder { amount, is_buy } => format!("MARKET {} {}", side(*is_buy), amount),
+                PendingAction::Cancel { order_id, price, amount, is_buy } => {
+                    format!("CANCEL ID {} ({} {} at {})", order_id, side(*is_buy), amount, price)
+                }
+            };
+            println!("{:?}  {}  from {:?}", activity.tx_hash, description, activity.from);
+        }
+    }
+    Ok(())
+}
+
+#[allow(clippy::too_many_arguments)]
+async fn doctor_check(
+    contract_address: String,
+    wallet: Option<String>,
+    private_key: Option<String>,
+    mut tokens: Vec<String>,
+    config_path: Option<String>,
+    rpc_url: Option<String>,
+    account: Option<String>,
+    format: OutputFormat
+) -> Result<()> {
+    if let Some(path) = config_path {
+        let config = BotConfig::load(&path)?;
+        tokens.push(format!("{:?}", config.base_token));
+        tokens.push(format!("{:?}", config.quote_token));
+    }
+    let options = DoctorOptions { rpc_url, dex: contract_address, wallet, private_key, account, tokens };
+    let checks = doctor::diagnose(&options).await;
+    
+    match format {
+        OutputFormat::Json => output::print_json(&checks)?,
+        OutputFormat::Csv => output::print_csv(&checks)?,
+        OutputFormat::Text => {
+            println!("Doctor");
+            println!("================================");
+            for check in &checks {
+                let status = match check.status {
+                    Status::Ok => "ok",
+                    Status::Warn => "warn",
+                    Status::Fail => "FAIL",
+                    Status::Skip => "skip",
+                };
+                println!("[{:>4}] {}: {}", status, check.name, check.detail);
+                if let Some(fix) = &check.fix {
+                    println!("       fix: {}", fix);
+                }
+            }
+        }
+    }
+    
+    let failed = checks.iter().filter(|check| check.status == Status::Fail).count();
+    if failed > 0 {
+        return Err(anyhow::anyhow!("{} of {} checks failed", failed, checks.len()));
+    }
+    Ok(())
+}
+
+async fn check_compat(contract_address: String, rpc_url: Option<String>, format: OutputFormat) -> Result<()> {
+    let provider = rpc::connect(rpc_url.as_deref()).await?;
+    let releases = DexReleases::load()?;
+    let compatibility = compat::inspect(&provider, aliases::resolve(&contract_address)?, &releases).await?;
+    
+    match format {
+        OutputFormat::Json => output::print_json(&compatibility)?,
+        OutputFormat::Csv => output::print_csv(&[(
+            compatibility.address,
+            compatibility.implementation,
+            compatibility.code_size,
+            compatibility.code_hash,
+            compatibility.release.clone(),
+            compatibility.erc165,
+            compatibility.missing.join(" "),
+        )])?,
+        OutputFormat::Text => {
+            println!("Compatibility of {:?}", compatibility.address);
+            println!("================================");
+            if let Some(implementation) = compatibility.implementation {
+                println!("Proxy for: {:?}", implementation);
+            }
+            println!("Code: {} bytes, hash {:?}", compatibility.code_size, compatibility.code_hash);
+            println!("Release: {}", compatibility.release.as_deref().unwrap_or("unknown"));
+            if compatibility.code_size > 0 {
+                println!("Interface: {}", if compatibility.erc165 { "confirmed through ERC-165" } else { "checked by selector" });
+                if !compatibility.missing.is_empty() {
+                    println!("Missing: {}", compatibility.missing.join(", "));
+                }
+            }
+        }
+    }
+    
+    compatibility.ensure(&releases)?;
+    Ok(())
+}
+
+async fn run_release_command(command: ReleaseCommands, format: OutputFormat) -> Result<()> {
+    let mut releases = DexReleases::load()?;
+    match command {
+        ReleaseCommands::Add { name, address, rpc_url } => {
+            let provider = rpc::connect(rpc_url.as_deref()).await?;
+            let compatibility = compat::inspect(&provider, aliases::resolve(&address)?, &releases).await?;
+            if compatibility.code_size == 0 {
+                return Err(anyhow::anyhow!("No contract at {:?}", compatibility.address));
+            }
+            releases.releases.insert(name.clone(), compatibility.code_hash);
+            releases.save()?;
+            println!("Recorded release {} with code hash {:?}", name, compatibility.code_hash);
+        }
+        ReleaseCommands::List => match format {
+            OutputFormat::Json => output::print_json(&releases)?,
+            OutputFormat::Csv => output::print_csv(&releases.releases.iter().collect::<Vec<_>>())?,
+            OutputFormat::Text => {
+                println!("Known DEX releases");
+                println!("================================");
+                for (name, hash) in &releases.releases {
+                    println!("{}: {:?}", name, hash);
+                }
+                println!("Unknown code is {}", if releases.require_known { "refused" } else { "allowed" });
+            }
+        },
+        ReleaseCommands::Remove { name } => {
+            let hash = releases.remove(&name)?;
+            releases.save()?;
+            println!("Removed release {} ({:?})", name, hash);
+        }
+        ReleaseCommands::Require { enabled } => {
+            releases.require_known = enabled;
+            releases.save()?;
+            println!("Code matching no known release is now {}", if enabled { "refused" } else { "allowed" });
+        }
+    }
+    Ok(())
+}
+
+async fn bench_rpc(
+    contract_address: String,
+    base_token: String,
+    quote_token: String,
+    samples: usize,
+    no_save: bool,
+    rpc_url: Option<String>,
+    format: OutputFormat
+) -> Result<()> {
+    let contract_address = aliases::resolve(&contract_address)?;
+    let base_token = aliases::resolve(&base_token)?;
+    let quote_token = aliases::resolve(&quote_token)?;
+    
+    let rpc_url = rpc::resolve_rpc_url(rpc_url.as_deref())?;
+    let mut benches = Vec::new();
+    for url in rpc_url.split(',').map(str::trim).filter(|url| !url.is_empty()) {
+        info!("Benchmarking {} ({} samples per method)", url, samples);
+        match bench::bench_endpoint(url, contract_address, base_token, quote_token, samples.max(1)).await {
+            Ok(result) => benches.push(result),
+            Err(e) => tracing::warn!("Skipping {}: {}", url, e),
+        }
+    }
+    if benches.is_empty() {
+        return Err(anyhow::anyhow!("No endpoint answered"));
+    }
+    let benches = bench::rank(benches);
+    
+    if !no_save {
+        let profile = RpcProfile { endpoints: benches.iter().filter(|b| b.score_ms.is_some()).map(|b| b.url.clone()).collect() };
+        profile.save()?;
+        info!("Saved endpoint order to the RPC profile");
+    }
+    
+    match format {
+        OutputFormat::Json => output::print_json(&benches)?,
+        OutputFormat::Csv => {
+            let rows: Vec<_> = benches
+                .iter()
+                .flat_map(|b| b.methods.iter().map(move |m| (&b.url, &m.method, m.p50_ms, m.p95_ms, m.p99_ms, m.errors)))
+                .collect();
+            output::print_csv(&rows)?;
+        }
+        OutputFormat::Text => {
+            println!("RPC Benchmark");
+            println!("=============");
+            for bench in &benches {
+                println!("{}", bench.url);
+                for m in &bench.methods {
+                    println!(
+                        "  {:<24} p50 {:>7.1} ms  p95 {:>7.1} ms  p99 {:>7.1} ms  ({} of {} failed)",
+                        m.method, m.p50_ms, m.p95_ms, m.p99_ms, m.errors, m.samples
+                    );
+                }
+            }
+            match benches.iter().find(|b| b.score_ms.is_some()) {
+                Some(fastest) => println!("\nRecommended: {} (p95 total {:.1} ms)", fastest.url, fastest.score_ms.unwrap_or_default()),
+                None => println!("\nNo endpoint completed every method"),
+            }
+        }
+    }
+    
+    Ok(())
+}
+
+async fn run_token_command(command: TokenCommands, format: OutputFormat) -> Result<()> {
+    let mut registry = TokenRegistry::load()?;
+    match command {
+        TokenCommands::Add { token, rpc_url } => {
+            let token = aliases::resolve(&token)?;
+            let provider = Arc::new(rpc::connect(rpc_url.as_deref()).await?);
+            let info = registry.resolve(provider, token).await?;
+            println!("{} ({}): {:?}, {} decimals", info.symbol, info.name, info.address, info.decimals);
+        }
+        TokenCommands::Refresh { token, rpc_url } => {
+            let token = aliases::resolve(&token)?;
+            let provider = Arc::new(rpc::connect(rpc_url.as_deref()).await?);
+            let info = registry.refresh(provider, token).await?;
+            println!("{} ({}): {:?}, {} decimals", info.symbol, info.name, info.address, info.decimals);
+        }
+        TokenCommands::Remove { token } => {
+            let info = registry.remove(aliases::resolve(&token)?)?;
+            registry.save()?;
+            println!("Removed {} ({:?})", info.symbol, info.address);
+        }
+        TokenCommands::List => {
+            let tokens: Vec<TokenInfo> = registry.tokens.into_values().collect();
+            match format {
+                OutputFormat::Json => output::print_json(&tokens)?,
+                OutputFormat::Csv => output::print_csv(&tokens)?,
+                OutputFormat::Text => {
+                    println!("Known tokens:");
+                    println!("=============");
+                    if tokens.is_empty() {
+                        println!("(none)");
+                    }
+                    for info in &tokens {
+                        println!("{:<10} {:?}  {} decimals  {}", info.symbol, info.address, info.decimals, info.name);
+                    }
+                }
+            }
+        }
+    }
+    
+    Ok(())
+}
+
+async fn paper_place_limit_order(
+    contract_address: String,
+    base_token: String,
+    quote_token: String,
+    amount: u64,
+    price: u64,
+    is_buy: bool,
+    rpc_url: Option<String>
+) -> Result<()> {
+    info!("Placing paper limit order: {} {} at price {}", if is_buy { "BUY" } else { "SELL" }, amount, price);
+    
+    let provider = rpc::connect(rpc_url.as_deref()).await?;
+    let contract_address = aliases::resolve(&contract_address)?;
+    let base_token = aliases::resolve(&base_token)?;
+    let quote_token = aliases::resolve(&quote_token)?;
+    
+    // Create contract instance
+    let contract = OrderBookDEX::new(contract_address, Arc::new(provider));
+    let market = PairMarket::fetch(&contract, base_token, quote_token).await?;
+    
+    let mut account = PaperAccount::load()?;
+    print_paper_fills("Resting orders filled", &account.settle(&market));
+    let (order_id, fills) = account.place_limit(&market, U256::from(amount), U256::from(price), is_buy)?;
+    account.save()?;
+    
+    println!("Paper order ID: {}", order_id);
+    print_paper_fills("Filled on placement", &fills);
+    
+    Ok(())
+}
+
+async fn paper_place_market_order(
+    contract_address: String,
+    base_token: String,
+    quote_token: String,
+    amount: u64,
+    is_buy: bool,
+    rpc_url: Option<String>
+) -> Result<()> {
+    info!("Placing paper market order: {} {}", if is_buy { "BUY" } else { "SELL" }, amount);
+    
+    let provider = rpc::connect(rpc_url.as_deref()).await?;
+    let contract_address = aliases::resolve(&contract_address)?;
+    let base_token = aliases::resolve(&base_token)?;
+    let quote_token = aliases::resolve(&quote_token)?;
+    
+    // Create contract instance
+    let contract = OrderBookDEX::new(contract_address, Arc::new(provider));
+    let market = PairMarket::fetch(&contract, base_token, quote_token).await?;
+    
+    let mut account = PaperAccount::load()?;
+    print_paper_fills("Resting orders filled", &account.settle(&market));
+    let fills = account.place_market(&market, U256::from(amount), is_buy)?;
+    account.save()?;
+    
+    print_paper_fills("Market order filled", &fills);
+    
+    Ok(())
+}
+
+fn print_paper_fills(title: &str, fills: &[PaperFill]) {
+    if fills.is_empty() {
+        return;
+    }
+    println!("{}:", title);
+    for fill in fills {
+        println!(
+            "  {} {} at price {} for {} quote (fee {})",
+            if fill.is_buy { "BUY" } else { "SELL" }, fill.amount, fill.price, fill.quote_amount, fill.fee
+        );
+    }
+}
+
+fn print_paper_account(account: &PaperAccount) {
+    println!("Paper Account");
+    println!("================================");
+    
+    println!("Balances:");
+    for (token, balance) in &account.balances {
+        println!("  {:?}: {} available, {} in orders", token, balance, account.reserved(*token));
+    }
+    
+    println!("\nOpen Orders:");
+    if account.orders.is_empty() {
+        println!("  No open orders.");
+    }
+    for order in &account.orders {
+        println!(
+            "  ID {}: {} {} at price {} ({:?} / {:?})",
+            order.id, if order.is_buy { "BUY" } else { "SELL" }, order.amount, order.price,
+            order.base_token, order.quote_token
+        );
+    }
+    
+    println!("\nFills ({}):", account.fills.len());
+    for fill in account.fills.iter().rev().take(20) {
+        println!(
+            "  {} {} at price {} for {} quote (fee {}){}",
+            if fill.is_buy { "BUY" } else { "SELL" }, fill.amount, fill.price, fill.quote_amount, fill.fee,
+            fill.order_id.map(|id| format!(", order {}", id)).unwrap_or_default()
+        );
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+async fn add_trading_pair(
+    contract_address: String,
+    base_token: String,
+    quote_token: String,
+    min_order_size: u64,
+    price_precision: u64,
+    safe: Option<String>,
+    private_key: Option<String>,
+    rpc_url: Option<String>,
+    tx_args: &TxArgs
+) -> Result<()> {
+    info!("Adding trading pair: {} / {}", base_token, quote_token);
+    
+    let provider = rpc::connect(rpc_url.as_deref()).await?;
+    let fees = tx_args.fees.resolve(&provider).await?;
+    let wallet = rpc::wallet(&provider, private_key.as_deref()).await?;
+    let client = SignerMiddleware::new(provider, wallet);
+    
+    let contract_address = aliases::resolve(&contract_address)?;
+    let base_token = aliases::resolve(&base_token)?;
+    let quote_token = aliases::resolve(&quote_token)?;
+    
+    // Create contract instance
+    let client_arc = Arc::new(client);
+    let contract = OrderBookDEX::new(contract_address, client_arc.clone());
+    
+    // Call addTradingPair function
+    let min_order_size_u256 = U256::from(min_order_size);
+    let price_precision_u256 = U256::from(price_precision);
+    let method = contract.add_trading_pair(base_token, quote_token, min_order_size_u256, price_precision_u256);
+    if let Some(safe) = safe {
+        let safe = aliases::resolve(&safe)?;
+        let data = method.calldata().ok_or_else(|| anyhow::anyhow!("addTradingPair call has no calldata"))?;
+        let description = format!("Add trading pair {:?} / {:?}", base_token, quote_token);
+        safe::submit(client_arc, safe, contract_address, data, &description, &fees, tx_args).await?;
+        return Ok(());
+    }
+    let receipt = tx::send(client_arc.as_ref(), method, &fees, tx_args).await?;
+    
+    if let Some(receipt) = receipt {
+        tx::ensure_success(client_arc.as_ref(), &receipt).await?;
+        info!("Trading pair added successfully!");
+        info!("Transaction hash: {:?}", receipt.transaction_hash);
+    }
+    
+    Ok(())
+}
+
+async fn place_limit_order(
+    order: LimitOrder,
+    options: &LimitOrderOptions,
+    wait_fill: Option<Duration>,
+    private_key: Option<String>,
+    rpc_url: Option<String>,
+    tx_args: &TxArgs,
+    format: OutputFormat
+) -> Result<()> {
+    info!("Placing limit order: {} {} at price {}", if order.is_buy { "BUY" } else { "SELL" }, order.amount, order.price);
+    
+    let provider = rpc::connect(rpc_url.as_deref()).await?;
+    let fees = tx_args.fees.resolve(&provider).await?;
+    let wallet = rpc::wallet(&provider, private_key.as_deref()).await?;
+    let client = Arc::new(SignerMiddleware::new(provider, wallet));
+    let receipt = placement::place_limit_order(client.clone(), rpc_url.as_deref(), order, options, &fees, tx_args).await?;
+    
+    if let Some(receipt) = receipt {
+        let placement = events::Placement::from_receipt(&receipt);
+        // Scripts waiting on the fill want a single document: the execution summary
+        if wait_fill.is_none() || matches!(format, OutputFormat::Text) {
+            print_placement(&placement, format)?;
+        }
+        
+        if let Some(timeout) = wait_fill {
+            let order_id = placement.order_id
+                .ok_or_else(|| anyhow::anyhow!("Order placed but its ID was not found in the receipt; cannot wait for fills"))?;
+            let from_block = receipt.block_number
+                .ok_or_else(|| anyhow::anyhow!("Receipt has no block number"))?;
+            info!("Waiting up to {} for order {} to fill", humantime::format_duration(timeout), order_id);
+            let contract = OrderBookDEX::new(order.dex, client);
+            let execution = fills::wait_for_fill(&contract, order_id, order.amount, from_block, timeout, Duration::from_secs(2)).await?;
+            print_execution(&execution, format)?;
+        }
+    }
+    
+    Ok(())
+}
+
+fn print_placement(placement: &events::Placement, format: OutputFormat) -> Result<()> {
+    match format {
+        OutputFormat::Json => output::print_json(placement)?,
+        OutputFormat::Csv => output::print_csv(&[(placement.tx_hash, placement.order_id, placement.filled())])?,
+        OutputFormat::Text => {
+            match placement.order_id {
+                Some(order_id) => println!("Order ID: {}", order_id),
+                None => println!("Order ID: not found in the receipt"),
+            }
+            println!("Transaction: {:?}", placement.tx_hash);
+            if placement.fills.is_empty() {
+                println!("Resting on the book, no immediate fills");
+            }
+            for fill in &placement.fills {
+                println!("Filled {} at price {} against order {}", fill.amount, fill.price, fill.counter_order_id);
+            }
+        }
+    }
+    Ok(())
+}
+
+fn print_execution(execution: &fills::Execution, format: OutputFormat) -> Result<()> {
+    match format {
+        OutputFormat::Json => output::print_json(execution)?,
+        OutputFormat::Csv => output::print_csv(&execution.fills.iter()
+            .map(|fill| (execution.order_id, fill.tx_hash, fill.counter_order_id, fill.amount, fill.price))
+            .collect::<Vec<_>>())?,
+        OutputFormat::Text => {
+            println!();
+            println!("Execution of order {}", execution.order_id);
+            println!("=======================");
+            println!("Status: {:?}", execution.status);
+            println!("Filled: {} of {}", execution.filled, execution.amount);
+            println!("Remaining: {}", execution.remaining);
+            if let Some(average_price) = execution.average_price {
+                println!("Average price: {:.2}", average_price);
+            }
+            for fill in &execution.fills {
+                println!("  {} at price {} against order {} (tx {:?})", fill.amount, fill.price, fill.counter_order_id, fill.tx_hash.unwrap_or_default());
+            }
+        }
+    }
+    Ok(())
+}
+
+async fn place_orders(
+    contract_address: String,
+    file: String,
+    resume: bool,
+    restart: bool,
+    private_key: Option<String>,
+    rpc_url: Option<String>,
+    tx_args: &TxArgs
+) -> Result<()> {
+    let orders: Vec<OrderRequest> = serde_json::from_str(&std::fs::read_to_string(&file)?)?;
+    info!("Placing {} limit orders from {}", orders.len(), file);
+    
+    let provider = rpc::connect(rpc_url.as_deref()).await?;
+    let fees = tx_args.fees.resolve(&provider).await?;
+    let wallet = rpc::wallet(&provider, private_key.as_deref()).await?;
+    let client = SignerMiddleware::new(provider, wallet);
+    
+    let contract_address = aliases::resolve(&contract_address)?;
+    
+    // Create contract instance
+    let client_arc = Arc::new(client);
+    let contract = OrderBookDEX::new(contract_address, client_arc.clone());
+    
+    // Every order in the batch counts toward the limits of the ones after it
+    let limits = RiskLimits::load()?;
+    let mut risk = if limits.is_empty() {
+        None
+    } else {
+        Some(RiskEngine::new(Arc::new(rpc::connect(rpc_url.as_deref()).await?), contract_address, client_arc.address(), limits))
+    };
+    
+    // Progress goes to the transaction journal so an interrupted batch can be resumed
+    let run = if tx_args.estimate_only {
+        None
+    } else {
+        Some(journal::start_run("place-orders", &file, orders.len(), resume, restart)?)
+    };
+    let pending: Vec<usize> = (0..orders.len()).filter(|i| !run.as_ref().is_some_and(|run| run.is_done(*i))).collect();
+    
+    // A session key's orders are checked against its policy as if placed one by one
+    let mut session = SessionStore::load()?.sessions.remove(&client_arc.address());
+    
+    // The DEX has no batch entry point, so each order is its own placeLimitOrder call
+    let mut calls = Vec::with_capacity(pending.len());
+    let mut pairs: BTreeMap<(Address, Address), PairParams> = BTreeMap::new();
+    let mut notionals = Vec::with_capacity(pending.len());
+    for i in &pending {
+        let order = &orders[*i];
+        let base_token = aliases::resolve(&order.base_token)?;
+        let quote_token = aliases::resolve(&order.quote_token)?;
+        let (amount, price) = (U256::from(order.amount), U256::from(order.price));
+        if let Some(risk) = risk.as_mut() {
+            risk.check(base_token, quote_token, amount, Some(price), order.is_buy).await?;
+            risk.assume_placed(base_token, quote_token, amount, price, order.is_buy);
+        }
+        if let Entry::Vacant(entry) = pairs.entry((base_token, quote_token)) {
+            entry.insert(PairParams::fetch(&contract, base_token, quote_token).await?);
+        }
+        let notional = pairs[&(base_token, quote_token)].quote_amount(amount, price);
+        if let Some(policy) = session.as_mut() {
+            policy.authorize(session::Action::Place { base_token, quote_token, notional })?;
+            policy.spent_notional += notional;
+        }
+        notionals.push((quote_token, notional));
+        let key = run.as_ref().map(|run| run.key(*i));
+        calls.push((key, contract.place_limit_order(base_token, quote_token, amount, price, order.is_buy)));
+    }
+    
+    // The whole batch counts as one command against the caps
+    let mut batch_notional: BTreeMap<Address, U256> = BTreeMap::new();
+    for (quote_token, notional) in &notionals {
+        *batch_notional.entry(*quote_token).or_default() += *notional;
+    }
+    limits::check(Spend::OrderNotional, &batch_notional, tx_args)?;
+    
+    let results = tx::send_keyed_batch(client_arc.as_ref(), calls, &fees, tx_args).await?;
+    let Some(mut run) = run else {
+        return Ok(());
+    };
+    
+    println!("Batch Results");
+    println!("================================");
+    
+    let mut outcomes: BTreeMap<usize, String> = run
+        .completed
+        .iter()
+        .map(|(i, hash)| (*i, format!("already placed (tx {:?})", hash)))
+        .collect();
+    for ((i, result), (quote_token, notional)) in pending.iter().zip(results).zip(notionals) {
+        let outcome = match result {
+            Ok(Some(receipt)) if receipt.status.is_none_or(|s| s.as_u64() == 1) => {
+                run.step_done(*i, receipt.transaction_hash);
+                limits::record(Spend::OrderNotional, quote_token, notional)?;
+                session::record(client_arc.address(), notional)?;
+                match events::placed_order_id(&receipt) {
+                    Some(order_id) => format!("order ID {} (tx {:?})", order_id, receipt.transaction_hash),
+                    None => format!("placed (tx {:?})", receipt.transaction_hash),
+                }
+            }
+            Ok(Some(receipt)) => format!("reverted (tx {:?})", receipt.transaction_hash),
+            Ok(None) => "dropped from mempool".to_string(),
+            Err(e) => format!("failed: {}", e),
+        };
+        outcomes.insert(*i, outcome);
+    }
+    for (i, outcome) in &outcomes {
+        let order = &orders[*i];
+        let side = if order.is_buy { "BUY" } else { "SELL" };
+        println!("  {}: {} {} at price {}: {}", i + 1, side, order.amount, order.price, outcome);
+    }
+    println!("{} of {} orders placed", run.completed.len(), orders.len());
+    
+    run.finish()?;
+    if run.completed.len() < orders.len() {
+        println!("\nRun the same command with --resume to retry the orders that didn't go through.");
+    }
+    
+    Ok(())
+}
+
+#[allow(clippy::too_many_arguments)]
+async fn place_market_order(
+    contract_address: String,
+    base_token: String,
+    quote_token: String,
+    amount: u64,
+    is_buy: bool,
+    private_key: Option<String>,
+    rpc_url: Option<String>,
+    tx_args: &TxArgs
+) -> Result<()> {
+    info!("Placing market order: {} {}", if is_buy { "BUY" } else { "SELL" }, amount);
+    
+    let provider = rpc::connect(rpc_url.as_deref()).await?;
+    let fees = tx_args.fees.resolve(&provider).await?;
+    let wallet = rpc::wallet(&provider, private_key.as_deref()).await?;
+    let client = SignerMiddleware::new(provider, wallet);
+    
+    let contract_address = aliases::resolve(&contract_address)?;
+    let base_token = aliases::resolve(&base_token)?;
+    let quote_token = aliases::resolve(&quote_token)?;
+    
+    // Create contract instance
+    let client_arc = Arc::new(client);
+    let contract = OrderBookDEX::new(contract_address, client_arc.clone());
+    
+    // Catch orders the contract or the user's limits would reject before paying for the transaction
+    let amount_u256 = U256::from(amount);
+    let order = LimitOrder { dex: contract_address, base_token, quote_token, amount: amount_u256, price: U256::zero(), is_buy };
+    let notional = placement::check_market_order(client_arc.clone(), rpc_url.as_deref(), &order, tx_args).await?;
+    
+    // Call placeMarketOrder function
+    let method = contract.place_market_order(base_token, quote_token, amount_u256, is_buy);
+    let receipt = tx::send(client_arc.as_ref(), method, &fees, tx_args).await?;
+    
+    if let Some(receipt) = receipt {
+        tx::ensure_success(client_arc.as_ref(), &receipt).await?;
+        limits::record(Spend::OrderNotional, quote_token, notional)?;
+        session::record(client_arc.address(), notional)?;
+        info!("Market order placed successfully!");
+        info!("Transaction hash: {:?}", receipt.transaction_hash);
+    }
+    
+    Ok(())
+}
+
+async fn cancel_order(
+    contract_address: String,
+    order_id: u64,
+    private_key: Option<String>,
+    rpc_url: Option<String>,
+    tx_args: &TxArgs
+) -> Result<()> {
+    info!("Cancelling order: {}", order_id);
+    
+    let provider = rpc::connect(rpc_url.as_deref()).await?;
+    let fees = tx_args.fees.resolve(&provider).await?;
+    let wallet = rpc::wallet(&provider, private_key.as_deref()).await?;
+    let client = SignerMiddleware::new(provider, wallet);
+    
+    let contract_address = aliases::resolve(&contract_address)?;
+    
+    // Create contract instance
+    let client_arc = Arc::new(client);
+    let contract = OrderBookDEX::new(contract_address, client_arc.clone());
+    
+    // Call cancelOrder function
+    let order_id_u256 = U256::from(order_id);
+    let method = contract.cancel_order(order_id_u256);
+    let receipt = tx::send(client_arc.as_ref(), method, &fees, tx_args).await?;
+    
+    if let Some(receipt) = receipt {
+        tx::ensure_success(client_arc.as_ref(), &receipt).await?;
+        info!("Order cancelled successfully!");
+        info!("Transaction hash: {:?}", receipt.transaction_hash);
+    }
+    
+    Ok(())
+}
+
+/// Cancel journaled orders of the signer whose expiry has passed, dropping any that
+/// were filled or cancelled in the meantime
+async fn expire_orders(
+    interval_secs: u64,
+    once: bool,
+    private_key: Option<String>,
+    rpc_url: Option<String>,
+    tx_args: &TxArgs
+) -> Result<()> {
+    let provider = rpc::connect(rpc_url.as_deref()).await?;
+    let wallet = rpc::wallet(&provider, private_key.as_deref()).await?;
+    let client = SignerMiddleware::new(provider, wallet);
+    let trader = client.address();
+    
+    let client_arc = Arc::new(client);
+    let tx_args = &tx_args.unattended();
+    info!("Expiring orders of {:?} every {}s", trader, interval_secs);
+    
+    loop {
+        expiry::expire_due(client_arc.clone(), tx_args).await?;
+        
+        if once {
+            return Ok(());
+        }
+        tokio::select! {
+            _ = tokio::time::sleep(Duration::from_secs(interval_secs)) => {}
+            _ = tokio::signal::ctrl_c() => return Ok(()),
+        }
+    }
+}
+
+fn parse_price(price: &str) -> Result<U256> {
+    U256::from_dec_str(price).map_err(|e| anyhow::anyhow!("Invalid price '{}': {}", price, e))
+}
+
+#[allow(clippy::too_many_arguments)]
+async fn get_order_book(
+    contract_address: String,
+    base_token: String,
+    quote_token: String,
+    query: PageQuery,
+    depth: Option<usize>,
+    offset: usize,
+    stream: bool,
+    rpc_url: Option<String>,
+    format: OutputFormat
+) -> Result<()> {
+    info!("Getting order book for {} / {}", base_token, quote_token);
+    
+    let provider = rpc::connect(rpc_url.as_deref()).await?;
+    let contract_address = aliases::resolve(&contract_address)?;
+    let base_token = aliases::resolve(&base_token)?;
+    let quote_token = aliases::resolve(&quote_token)?;
+    
+    // Create contract instance
+    let client = Arc::new(provider);
+    let contract = OrderBookDEX::new(contract_address, client.clone());
+    let mut pages = BookPages::new(contract, base_token, quote_token, query);
+    
+    let mut registry = TokenRegistry::load()?;
+    let base = registry.resolve(client.clone(), base_token).await.ok();
+    let quote = registry.resolve(client, quote_token).await.ok();
+    let symbol = |info: &Option<TokenInfo>, address: Address| info.as_ref().map_or(format!("{:?}", address), |info| info.symbol.clone());
+    let amount = |amount: &U256| base.as_ref().map_or(amount.to_string(), |info| info.format(*amount));
+    
+    if stream {
+        // Print each page as it arrives, so nothing but the current page is held
+        let mut writer = csv::Writer::from_writer(std::io::stdout());
+        if format == OutputFormat::Text {
+            println!("Orders for {} / {}", symbol(&base, base_token), symbol(&quote, quote_token));
+            println!("================================");
+        }
+        while let Some(page) = pages.next_page().await? {
+            for order in &page {
+                match format {
+                    OutputFormat::Json => println!("{}", serde_json::to_string(order)?),
+                    OutputFormat::Csv => writer.serialize(order)?,
+                    OutputFormat::Text => {
+                        let id = order.id.map_or("-".to_string(), |id| id.to_string());
+                        println!("  #{} {}: Price: {}, Amount: {}", id, order.side, order.price, amount(&order.amount));
+                    }
+                }
+            }
+            writer.flush()?;
+        }
+        return Ok(());
+    }
+    
+    // Only the levels that can be shown are kept while paging
+    let mut book = Depth::new(depth.map_or(usize::MAX, |depth| depth.saturating_add(offset)));
+    while let Some(page) = pages.next_page().await? {
+        for order in &page {
+            book.add(order);
+        }
+    }
+    let shown = depth.unwrap_or(usize::MAX);
+    let bids: Vec<_> = book.bids().skip(offset).take(shown).collect();
+    let asks: Vec<_> = book.asks().skip(offset).take(shown).collect();
+    
+    match format {
+        OutputFormat::Json => Ok(output::print_json(&serde_json::json!({ "bids": bids, "asks": asks }))?),
+        OutputFormat::Csv => {
+            let rows: Vec<_> = bids
+                .iter()
+                .map(|level| ("buy", level.price.to_string(), level.amount.to_string(), level.orders))
+                .chain(asks.iter().map(|level| ("sell", level.price.to_string(), level.amount.to_string(), level.orders)))
+                .collect();
+            Ok(output::print_csv(&rows)?)
+        }
+        OutputFormat::Text => {
+            println!("Order Book for {} / {}", symbol(&base, base_token), symbol(&quote, quote_token));
+            println!("==========================================");
+            
+            println!("Buy Orders:");
+            for (i, level) in bids.iter().enumerate() {
+                println!("  {}: Price: {}, Amount: {} ({} orders)", offset + i + 1, level.price, amount(&level.amount), level.orders);
+            }
+            
+            println!("\nSell Orders:");
+            for (i, level) in asks.iter().enumerate() {
+                println!("  {}: Price: {}, Amount: {} ({} orders)", offset + i + 1, level.price, amount(&level.amount), level.orders);
+            }
+            Ok(())
+        }
+    }
+}
+
+async fn get_user_orders(
+    contract_address: String,
+    user_address: String,
+    detailed: bool,
+    rpc_url: Option<String>
+) -> Result<()> {
+    info!("Getting orders for user: {}", user_address);
+    
+    let provider = rpc::connect(rpc_url.as_deref()).await?;
+    let contract_address = aliases::resolve(&contract_address)?;
+    let user_address = aliases::resolve(&user_address)?;
+    
+    // Create contract instance
+    let client = Arc::new(provider);
+    let contract = OrderBookDEX::new(contract_address, client.clone());
+    
+    // Call getUserOrders function
+    let order_ids = contract.get_user_orders(user_address).call().await?;
+    
+    println!("Active Orders for {}", user_address);
+    println!("================================");
+    
+    if order_ids.is_empty() {
+        println!("No active orders found.");
+    } else if detailed {
+        // Fetch every order in one round trip
+        let mut multicall = Multicall::new(client)?;
+        for order_id in &order_ids {
+            multicall.add(contract.orders(*order_id))?;
+        }
+        let orders = multicall.call_as::<OrderInfo>().await?;
+        
+        for (i, (order_id, order)) in order_ids.iter().zip(orders).enumerate() {
+            match order {
+                Ok((_, _, base_token, quote_token, amount, price, is_buy, _, timestamp)) => {
+                    println!(
+                        "Order {}: ID {} {} {} at price {} ({:?} / {:?}, placed at {})",
+                        i + 1, order_id, if is_buy { "BUY" } else { "SELL" }, amount, price,
+                        base_token, quote_token, timestamp
+                    );
+                }
+                Err(e) => println!("Order {}: ID {} (details unavailable: {})", i + 1, order_id, e),
+            }
+        }
+    } else {
+        for (i, order_id) in order_ids.iter().enumerate() {
+            println!("Order {}: ID {}", i + 1, order_id);
+        }
+    }
+    
+    Ok(())
+}
+
+async fn get_balance(
+    contract_address: String,
+    user_address: String,
+    token_addresses: Vec<String>,
+    rpc_url: Option<String>
+) -> Result<()> {
+    info!("Getting balance for user: {} tokens: {}", user_address, token_addresses.join(", "));
+    
+    let provider = rpc::connect(rpc_url.as_deref()).await?;
+    let contract_address = aliases::resolve(&contract_address)?;
+    let user_address = aliases::resolve(&user_address)?;
+    let token_addresses = token_addresses
+        .iter()
+        .map(|token| aliases::resolve(token))
+        .collect::<Result<Vec<_>, _>>()?;
+    
+    // Create contract instance
+    let client = Arc::new(provider);
+    let contract = OrderBookDEX::new(contract_address, client.clone());
+    
+    // Call getUserBalance function
+    if let [token_address] = token_addresses[..] {
+        let balance = contract.get_user_balance(user_address, token_address).call().await?;
+        
+        println!("Balance: {}", TokenRegistry::load()?.display(client, token_address, balance).await);
+        return Ok(());
+    }
+    
+    // Fetch every balance in one round trip
+    let mut multicall = Multicall::new(client.clone())?;
+    for token_address in &token_addresses {
+        multicall.add(contract.get_user_balance(user_address, *token_address))?;
+    }
+    let balances = multicall.call_as::<U256>().await?;
+    
+    let mut registry = TokenRegistry::load()?;
+    println!("Balances for {:?}", user_address);
+    for (token_address, balance) in token_addresses.iter().zip(balances) {
+        match balance {
+            Ok(balance) => println!("  {:?}: {}", token_address, registry.display(client.clone(), *token_address, balance).await),
+            Err(e) => println!("  {:?}: unavailable ({})", token_address, e),
+        }
+    }
+    
+    Ok(())
+}
+
+async fn withdraw(
+    contract_address: String,
+    token_address: String,
+    amount: u64,
+    private_key: Option<String>,
+    rpc_url: Option<String>,
+    tx_args: &TxArgs
+) -> Result<()> {
+    info!("Withdrawing {} tokens", amount);
+    
+    let provider = rpc::connect(rpc_url.as_deref()).await?;
+    let fees = tx_args.fees.resolve(&provider).await?;
+    let wallet = rpc::wallet(&provider, private_key.as_deref()).await?;
+    let client = SignerMiddleware::new(provider, wallet);
+    
+    let contract_address = aliases::resolve(&contract_address)?;
+    let token_address = aliases::resolve(&token_address)?;
+    
+    // Create contract instance
+    let client_arc = Arc::new(client);
+    let contract = OrderBookDEX::new(contract_address, client_arc.clone());
+    
+    session::check(client_arc.address(), session::Action::Withdraw)?;
+    
+    // Call withdraw function
+    let amount_u256 = U256::from(amount);
+    limits::check(Spend::Withdrawal, &BTreeMap::from([(token_address, amount_u256)]), tx_args)?;
+    let method = contract.withdraw(token_address, amount_u256);
+    let receipt = tx::send(client_arc.as_ref(), method, &fees, tx_args).await?;
+    
+    if let Some(receipt) = receipt {
+        tx::ensure_success(client_arc.as_ref(), &receipt).await?;
+        limits::record(Spend::Withdrawal, token_address, amount_u256)?;
+        info!("Withdrawal successful!");
+        info!("Transaction hash: {:?}", receipt.transaction_hash);
+    }
+    
+    Ok(())
+}
+
+/// One token emptied by `withdraw-all`
+struct Sweep {
+    token: Address,
+    withdrawn: U256,
+    withdraw_tx: Option<H256>,
+    sweep_tx: Option<H256>,
+    /// Why the withdrawal or the sweep failed
+    error: Option<String>,
+}
+
+/// Withdraw `sweep.withdrawn` of `sweep.token` and, once that succeeded, send it on to
+/// `sweep_to`
+async fn withdraw_and_sweep(
+    client: Arc<SignerClient>,
+    contract: &OrderBookDEX<SignerClient>,
+    sweep: &mut Sweep,
+    sweep_to: Option<Address>,
+    fees: &Fees,
+    tx_args: &TxArgs
+) -> Result<()> {
+    let (token, amount) = (sweep.token, sweep.withdrawn);
+    info!("Withdrawing {} of {:?}", amount, token);
+    let Some(receipt) = tx::send(client.as_ref(), contract.withdraw(token, amount), fees, &tx_args.step(format!("withdraw/{:?}", token))).await? else {
+        return Ok(());
+    };
+    sweep.withdraw_tx = Some(receipt.transaction_hash);
+    tx::ensure_success(client.as_ref(), &receipt).await?;
+    limits::record(Spend::Withdrawal, token, amount)?;
+    
+    let Some(to) = sweep_to else {
+        return Ok(());
+    };
+    info!("Sweeping {} of {:?} to {:?}", amount, token, to);
+    let tx_args = &tx_args.step(format!("sweep/{:?}", token));
+    let receipt = if token.is_zero() {
+        let mut tx: TypedTransaction = TransactionRequest::new().from(client.address()).to(to).value(amount).into();
+        fees.apply(&mut tx);
+        tx::broadcast(client.as_ref(), tx, tx_args).await?
+    } else {
+        tx::send(client.as_ref(), ERC20::new(token, client.clone()).transfer(to, amount), fees, tx_args).await?
+    };
+    if let Some(receipt) = receipt {
+        sweep.sweep_tx = Some(receipt.transaction_hash);
+        tx::ensure_success(client.as_ref(), &receipt).await?;
+    }
+    Ok(())
+}
+
+/// Halt the bots of the daemon on `socket` and those reading the halt file here. Either
+/// may be missing; the halt file is written regardless.
+async fn emergency_halt(socket: &str) -> Result<()> {
+    match daemon::request(socket, &Request::CancelAll).await {
+        Ok(Response::Done { message }) => println!("dexd: {}", message),
+        Ok(Response::Error { message }) => tracing::warn!("dexd could not halt: {}", message),
+        Ok(Response::Status(_)) => tracing::warn!("dexd answered the halt with its status"),
+        Err(e) => tracing::warn!("{}", e),
+    }
+    Halt::new("Emergency stop".to_string(), true).save()?;
+    println!(
+        "Trading halted in {}; running bots will cancel their orders and stand down until `resume`",
+        Halt::path()?.display()
+    );
+    Ok(())
+}
+
+/// Withdraw every free DEX balance, cancelling open orders first with `cancel_open`. An
+/// `emergency` withdrawal is not held to the spending caps.
+#[allow(clippy::too_many_arguments)]
+async fn withdraw_all(
+    contract_address: String,
+    sweep_to: Option<String>,
+    cancel_open: bool,
+    emergency: bool,
+    from_block: u64,
+    private_key: Option<String>,
+    rpc_url: Option<String>,
+    tx_args: &TxArgs
+) -> Result<()> {
+    let provider = rpc::connect(rpc_url.as_deref()).await?;
+    let fees = tx_args.fees.resolve(&provider).await?;
+    let contract_address = aliases::resolve(&contract_address)?;
+    let sweep_to = sweep_to.map(|to| aliases::resolve(&to)).transpose()?;
+    
+    // Every token listed on the DEX could hold a balance; the contract can't enumerate them
+    let mut store = EventStore::load(contract_address, from_block)?;
+    store.sync(&provider, &dex_abi(), DEFAULT_CHUNK_SIZE).await?;
+    let tokens: BTreeSet<Address> = store.listed_pairs().into_iter().flat_map(|(base, quote)| [base, quote]).collect();
+    
+    let wallet = rpc::wallet(&provider, private_key.as_deref()).await?;
+    let client = SignerMiddleware::new(provider, wallet);
+    let owner = client.address();
+    session::check(owner, session::Action::Withdraw)?;
+    if sweep_to.is_some() {
+        session::check(owner, session::Action::Transfer)?;
+    }
+    
+    // Create contract instance
+    let client_arc = Arc::new(client);
+    let contract = OrderBookDEX::new(contract_address, client_arc.clone());
+    
+    // Deposits backing open orders are part of the balance but must stay put
+    let mut locked: BTreeMap<Address, U256> = BTreeMap::new();
+    for order_id in contract.get_user_orders(owner).call().await? {
+        let (_, _, base_token, quote_token, amount, price, is_buy, is_active, _) = contract.orders(order_id).call().await?;
+        if !is_active {
+            continue;
+        }
+        if cancel_open {
+            info!("Cancelling order {}", order_id);
+            let cancelled = match tx::send(client_arc.as_ref(), contract.cancel_order(order_id), &fees, &tx_args.step(format!("cancel/{}", order_id))).await {
+                Ok(Some(receipt)) => tx::ensure_success(client_arc.as_ref(), &receipt).await.map_err(anyhow::Error::from),
+                // An estimate frees the deposit as the cancel would
+                Ok(None) if tx_args.estimate_only => Ok(()),
+                Ok(None) => Err(anyhow::anyhow!("the cancel was dropped from the mempool")),
+                Err(e) => Err(e.into()),
+            };
+            // A bot may have cancelled it in the meantime; only a still-live order stays locked
+            if let Err(e) = cancelled {
+                tracing::warn!("Cancelling order {} failed: {}", order_id, e);
+                let (_, _, _, _, _, _, _, still_active, _) = contract.orders(order_id).call().await?;
+                if !still_active {
+                    continue;
+                }
+            } else {
+                continue;
+            }
+        }
+        let (_, _, _, _, price_precision) = contract.trading_pairs(base_token, quote_token).call().await?;
+        let (token, deposit) = if is_buy {
+            (quote_token, (amount * price).checked_div(price_precision).unwrap_or_default())
+        } else {
+            (base_token, amount)
+        };
+        *locked.entry(token).or_default() += deposit;
+    }
+    
+    let mut withdrawals = BTreeMap::new();
+    for token in tokens {
+        let balance = contract.get_user_balance(owner, token).call().await?;
+        let free = balance.saturating_sub(locked.get(&token).copied().unwrap_or_default());
+        if !free.is_zero() {
+            withdrawals.insert(token, free);
+        }
+    }
+    if !emergency {
+        limits::check(Spend::Withdrawal, &withdrawals, tx_args)?;
+    }
+    
+    // One token failing to withdraw or sweep must not strand the others
+    let mut sweeps = Vec::new();
+    for (token, free) in withdrawals {
+        let mut sweep = Sweep { token, withdrawn: free, withdraw_tx: None, sweep_tx: None, error: None };
+        if let Err(e) = withdraw_and_sweep(client_arc.clone(), &contract, &mut sweep, sweep_to, &fees, tx_args).await {
+            tracing::warn!("Withdrawing {:?} failed: {}", token, e);
+            sweep.error = Some(e.to_string());
+        }
+        sweeps.push(sweep);
+    }
+    if tx_args.estimate_only {
+        return Ok(());
+    }
+    
+    println!("Withdraw All");
+    println!("============");
+    if sweeps.is_empty() {
+        println!("Nothing to withdraw");
+    }
+    let mut registry = TokenRegistry::load()?;
+    for sweep in &sweeps {
+        println!("{:?}: {}", sweep.token, registry.display(client_arc.clone(), sweep.token, sweep.withdrawn).await);
+        println!("  Withdrawn: {:?}", sweep.withdraw_tx);
+        if let (Some(to), Some(tx)) = (sweep_to, sweep.sweep_tx) {
+            println!("  Swept to {:?}: {:?}", to, tx);
+        }
+        if let Some(error) = &sweep.error {
+            println!("  Failed: {}", error);
+        }
+    }
+    let held: Vec<_> = locked.iter().filter(|(_, amount)| !amount.is_zero()).collect();
+    if !held.is_empty() {
+        println!("\nLeft backing open orders (use --cancel-open to free):");
+        for (token, amount) in held {
+            println!("  {}", registry.display(client_arc.clone(), *token, *amount).await);
+        }
+    }
+    
+    let failed = sweeps.iter().filter(|sweep| sweep.error.is_some()).count();
+    if failed > 0 {
+        return Err(anyhow::anyhow!("{} of {} tokens failed to withdraw or sweep", failed, sweeps.len()));
+    }
+    Ok(())
+}
+
+async fn wrap(
+    wmon_address: String,
+    amount: String,
+    private_key: Option<String>,
+    rpc_url: Option<String>,
+    tx_args: &TxArgs
+) -> Result<()> {
+    info!("Wrapping {} MON", amount);
+    
+    let provider = rpc::connect(rpc_url.as_deref()).await?;
+    let fees = tx_args.fees.resolve(&provider).await?;
+    let wallet = rpc::wallet(&provider, private_key.as_deref()).await?;
+    let client = SignerMiddleware::new(provider, wallet);
+    
+    let wmon_address = aliases::resolve(&wmon_address)?;
+    let value = parse_ether(&amount).map_err(|e| anyhow::anyhow!("Invalid MON amount '{}': {}", amount, e))?;
+    
+    // Create contract instance
+    let client_arc = Arc::new(client);
+    let contract = WMON::new(wmon_address, client_arc.clone());
+    
+    // Call deposit function with the MON attached
+    let method = contract.deposit().value(value);
+    let receipt = tx::send(client_arc.as_ref(), method, &fees, tx_args).await?;
+    
+    if let Some(receipt) = receipt {
+        tx::ensure_success(client_arc.as_ref(), &receipt).await?;
+        info!("Wrap successful!");
+        info!("Transaction hash: {:?}", receipt.transaction_hash);
+    }
+    
+    Ok(())
+}
+
+async fn unwrap(
+    wmon_address: String,
+    amount: String,
+    private_key: Option<String>,
+    rpc_url: Option<String>,
+    tx_args: &TxArgs
+) -> Result<()> {
+    info!("Unwrapping {} WMON", amount);
+    
+    let provider = rpc::connect(rpc_url.as_deref()).await?;
+    let fees = tx_args.fees.resolve(&provider).await?;
+    let wallet = rpc::wallet(&provider, private_key.as_deref()).await?;
+    let client = SignerMiddleware::new(provider, wallet);
+    
+    let wmon_address = aliases::resolve(&wmon_address)?;
+    let value = parse_ether(&amount).map_err(|e| anyhow::anyhow!("Invalid WMON amount '{}': {}", amount, e))?;
+    
+    // Create contract instance
+    let client_arc = Arc::new(client);
+    let contract = WMON::new(wmon_address, client_arc.clone());
+    
+    let held = contract.balance_of(client_arc.address()).call().await?;
+    if held < value {
+        return Err(anyhow::anyhow!("Only {} WMON held, cannot unwrap {}", format_ether(held), amount));
+    }
+    
+    // Call withdraw function
+    let method = contract.withdraw(value);
+    let receipt = tx::send(client_arc.as_ref(), method, &fees, tx_args).await?;
+    
+    if let Some(receipt) = receipt {
+        tx::ensure_success(client_arc.as_ref(), &receipt).await?;
+        info!("Unwrap successful!");
+        info!("Transaction hash: {:?}", receipt.transaction_hash);
+    }
+    
+    Ok(())
+}
+
+async fn index(
+    contract_address: String,
+    from_block: u64,
+    chunk_size: u64,
+    rpc_url: Option<String>
+) -> Result<()> {
+    let provider = rpc::connect(rpc_url.as_deref()).await?;
+    let contract_address = aliases::resolve(&contract_address)?;
+    
+    // Load contract ABI
+    let contract_abi = dex_abi();
+    
+    let mut store = EventStore::load(contract_address, from_block)?;
+    store.sync(&provider, &contract_abi, chunk_size).await?;
+    
+    println!("Event Index for {:?}", contract_address);
+    println!("================================");
+    println!("Last block: {}", store.last_block);
+    println!("Orders placed: {}", store.orders.len());
+    println!("Trades: {}", store.trades.len());
+    println!("Cancellations: {}", store.cancellations.len());
+    println!("Trading pairs: {}", store.listed_pairs().len());
+    
+    Ok(())
+}
+
+#[allow(clippy::too_many_arguments)]
+async fn sign_order(
+    contract_address: String,
+    base_token: String,
+    quote_token: String,
+    amount: u64,
+    price: u64,
+    is_buy: bool,
+    nonce: Option<u64>,
+    expires_in: u64,
+    out: Option<String>,
+    private_key: Option<String>,
+    rpc_url: Option<String>
+) -> Result<()> {
+    let provider = rpc::connect(rpc_url.as_deref()).await?;
+    let wallet = rpc::wallet(&provider, private_key.as_deref()).await?;
+    let contract_address = aliases::resolve(&contract_address)?;
+    let base_token = aliases::resolve(&base_token)?;
+    let quote_token = aliases::resolve(&quote_token)?;
+    
+    // Create contract instance
+    let contract = OrderBookDEX::new(contract_address, Arc::new(provider));
+    
+    // A relayer would reject orders the contract can't settle, so check them up front
+    PairParams::fetch(&contract, base_token, quote_token)
+        .await?
+        .validate_order(U256::from(amount), Some(U256::from(price)), is_buy)?;
+    
+    let domain = OrderDomain::fetch(&contract).await?;
+    let now = signing::unix_now();
+    let order = OffchainOrder {
+        maker: wallet.address(),
+        base_token,
+        quote_token,
+        amount,
+        price,
+        is_buy,
+        nonce: nonce.unwrap_or(now * 1000),
+        expiry: now + expires_in,
+    };
+    let signed = SignedOrder::sign(&wallet, domain, order)?;
+    let json = serde_json::to_string_pretty(&signed)?;
+    
+    match out {
+        Some(path) => {
+            std::fs::write(&path, json)?;
+            info!("Signed order written to {}", path);
+            info!("Digest: {:?}", signed.digest);
+        }
+        None => println!("{}", json),
+    }
+    
+    Ok(())
+}
+
+async fn verify_order(contract_address: String, file: String, rpc_url: Option<String>, format: OutputFormat) -> Result<()> {
+    let signed: SignedOrder = serde_json::from_str(&std::fs::read_to_string(&file)?)
+        .map_err(|e| anyhow::anyhow!("{} is not a signed order: {}", file, e))?;
+    
+    let provider = rpc::connect(rpc_url.as_deref()).await?;
+    let contract_address = aliases::resolve(&contract_address)?;
     
-    match cli.command {
-        Commands::AddTradingPair { address, base_token, quote_token, min_order_size, price_precision, private_key, rpc_url } => {
-            add_trading_pair(address, base_token, quote_token, min_order_size, price_precision, private_key, rpc_url).await?;
-        }
-        Commands::PlaceLimitOrder { address, base_token, quote_token, amount, price, is_buy, private_key, rpc_url } => {
-            place_limit_order(address, base_token, quote_token, amount, price, is_buy, private_key, rpc_url).await?;
-        }
-        Commands::PlaceMarketOrder { address, base_token, quote_token, amount, is_buy, private_key, rpc_url } => {
-            place_market_order(address, base_token, quote_token, amount, is_buy, private_key, rpc_url).await?;
-        }
-        Commands::CancelOrder { address, order_id, private_key, rpc_url } => {
-            cancel_order(address, order_id, private_key, rpc_url).await?;
-        }
-        Commands::GetOrderBook { address, base_token, quote_token, rpc_url } => {
-            get_order_book(address, base_token, quote_token, rpc_url).await?;
-        }
-        Commands::GetUserOrders { address, user, rpc_url } => {
-            get_user_orders(address, user, rpc_url).await?;
+    // Create contract instance
+    let contract = OrderBookDEX::new(contract_address, Arc::new(provider));
+    
+    let order = &signed.order;
+    let mut problems = Vec::new();
+    
+    let domain = OrderDomain::fetch(&contract).await?;
+    if signed.domain != domain {
+        problems.push(format!(
+            "Signed for {} v{} on chain {} at {:?}, not this DEX",
+            signed.domain.name, signed.domain.version, signed.domain.chain_id, signed.domain.verifying_contract
+        ));
+    }
+    
+    let digest = order.digest(&domain);
+    if digest != signed.digest {
+        problems.push(format!("Digest in file {:?} does not match the order", signed.digest));
+    }
+    let on_chain = contract
+        .hash_order(
+            order.maker,
+            order.base_token,
+            order.quote_token,
+            U256::from(order.amount),
+            U256::from(order.price),
+            order.is_buy,
+            U256::from(order.nonce),
+            U256::from(order.expiry),
+        )
+        .call()
+        .await?;
+    if H256::from(on_chain) != digest {
+        problems.push(format!("DEX computes digest {:?}, expected {:?}", H256::from(on_chain), digest));
+    }
+    
+    let signer = match signed.signature.recover(digest) {
+        Ok(signer) => {
+            if signer != order.maker {
+                problems.push(format!("Signed by {:?}, not the maker", signer));
+            }
+            Some(signer)
         }
-        Commands::GetBalance { address, user, token, rpc_url } => {
-            get_balance(address, user, token, rpc_url).await?;
+        Err(e) => {
+            problems.push(format!("Invalid signature: {}", e));
+            None
         }
-        Commands::Withdraw { address, token, amount, private_key, rpc_url } => {
-            withdraw(address, token, amount, private_key, rpc_url).await?;
+    };
+    
+    if order.is_expired() {
+        problems.push(format!("Expired at {}", order.expiry));
+    }
+    if let Err(e) = PairParams::fetch(&contract, order.base_token, order.quote_token)
+        .await?
+        .validate_order(U256::from(order.amount), Some(U256::from(order.price)), order.is_buy)
+    {
+        problems.push(e.to_string());
+    }
+    
+    let report = OrderVerification {
+        digest,
+        maker: order.maker,
+        signer,
+        valid: problems.is_empty(),
+        problems: problems.join("; "),
+    };
+    
+    match format {
+        OutputFormat::Json => output::print_json(&report)?,
+        OutputFormat::Csv => output::print_csv(std::slice::from_ref(&report))?,
+        OutputFormat::Text => {
+            println!("Signed Order");
+            println!("============");
+            println!("Maker: {:?}", order.maker);
+            println!(
+                "{} {} at price {} ({:?} / {:?})",
+                if order.is_buy { "BUY" } else { "SELL" },
+                order.amount,
+                order.price,
+                order.base_token,
+                order.quote_token
+            );
+            println!("Nonce: {}", order.nonce);
+            println!("Expiry: {}", order.expiry);
+            println!("Digest: {:?}", report.digest);
+            if report.valid {
+                println!("Signature: valid");
+            } else {
+                println!("Signature: INVALID");
+                for problem in &problems {
+                    println!("  - {}", problem);
+                }
+            }
         }
     }
     
+    if !report.valid {
+        return Err(anyhow::anyhow!("Order in {} failed verification", file));
+    }
     Ok(())
 }
 
-async fn add_trading_pair(
+async fn run_relay(
+    contract_address: String,
+    listen: SocketAddr,
+    batch_size: usize,
+    batch_interval_secs: u64,
+    private_key: Option<String>,
+    rpc_url: Option<String>,
+    tx_args: TxArgs
+) -> Result<()> {
+    if batch_size == 0 {
+        return Err(anyhow::anyhow!("--batch-size must be at least 1"));
+    }
+    
+    let provider = rpc::connect(rpc_url.as_deref()).await?;
+    let wallet = rpc::wallet(&provider, private_key.as_deref()).await?;
+    let client = Arc::new(SignerMiddleware::new(provider, wallet));
+    let contract_address = aliases::resolve(&contract_address)?;
+    
+    let relay = Relay::new(client, contract_address, tx_args.unattended()).await?;
+    Arc::new(relay)
+        .run(listen, batch_size, Duration::from_secs(batch_interval_secs))
+        .await?;
+    Ok(())
+}
+
+async fn pair_info(
     contract_address: String,
     base_token: String,
     quote_token: String,
-    min_order_size: u64,
-    price_precision: u64,
-    private_key: String,
-    rpc_url: String
+    rpc_url: Option<String>,
+    format: OutputFormat
 ) -> Result<()> {
-    info!("Adding trading pair: {} / {}", base_token, quote_token);
-    
-    let provider = Provider::<Http>::try_from(rpc_url)?;
-    let wallet = private_key.parse::<LocalWallet>()?;
-    let client = SignerMiddleware::new(provider, wallet);
+    let provider = rpc::connect(rpc_url.as_deref()).await?;
+    let contract_address = aliases::resolve(&contract_address)?;
+    let base_token = aliases::resolve(&base_token)?;
+    let quote_token = aliases::resolve(&quote_token)?;
     
-    let contract_address = contract_address.parse::<Address>()?;
-    let base_token = base_token.parse::<Address>()?;
-    let quote_token = quote_token.parse::<Address>()?;
+    // Create contract instance
+    let client = Arc::new(provider);
+    let contract = OrderBookDEX::new(contract_address, client.clone());
     
-    // Load contract ABI
-    let contract_abi = load_dex_abi()?;
+    let params = PairParams::fetch(&contract, base_token, quote_token).await?;
+    if !params.is_registered() {
+        return Err(anyhow::anyhow!("{:?} / {:?} is not a trading pair on this DEX", base_token, quote_token));
+    }
     
-    // Create contract instance
-    let client_arc = Arc::new(client);
-    let contract = Contract::new(contract_address, contract_abi, client_arc);
+    if format == OutputFormat::Json {
+        return Ok(output::print_json(&params)?);
+    }
+    if format == OutputFormat::Csv {
+        return Ok(output::print_csv(&[params])?);
+    }
     
-    // Call addTradingPair function
-    let min_order_size_u256 = U256::from(min_order_size);
-    let price_precision_u256 = U256::from(price_precision);
-    let args = (base_token, quote_token, min_order_size_u256, price_precision_u256);
-    let method = contract.method::<_, ()>("addTradingPair", args)?;
-    let legacy = method.legacy();
-    let tx = legacy.send().await?;
+    let mut registry = TokenRegistry::load()?;
+    let base = registry.resolve(client.clone(), base_token).await.ok();
+    let quote = registry.resolve(client, quote_token).await.ok();
+    let symbol = |info: &Option<TokenInfo>| info.as_ref().map_or("?".to_string(), |info| info.symbol.clone());
     
-    let receipt = tx.await?;
-    info!("Trading pair added successfully!");
-    if let Some(receipt) = receipt {
-        info!("Transaction hash: {:?}", receipt.transaction_hash);
+    println!("Trading Pair {} / {}", symbol(&base), symbol(&quote));
+    println!("================================");
+    println!("Base: {:?}", base_token);
+    println!("Quote: {:?}", quote_token);
+    println!("Active: {}", params.is_active);
+    match &base {
+        Some(info) => println!("Min order size: {} ({})", params.min_order_size, info.format(params.min_order_size)),
+        None => println!("Min order size: {}", params.min_order_size),
     }
+    println!("Price precision: {}", params.price_precision);
+    println!("Buy cost: amount * price / {} quote tokens", params.price_precision);
     
     Ok(())
 }
 
-async fn place_limit_order(
+/// Cost-to-fill of a market order, with the network fee when it can be estimated
+#[derive(Serialize)]
+struct QuoteReport {
+    #[serde(flatten)]
+    quote: MarketQuote,
+    /// Market orders pay no trading fee; kept so the report states it explicitly
+    trading_fee: U256,
+    gas_estimate: Option<U256>,
+    network_fee_wei: Option<U256>,
+}
+
+#[allow(clippy::too_many_arguments)]
+async fn quote_market_order(
     contract_address: String,
     base_token: String,
     quote_token: String,
     amount: u64,
-    price: u64,
     is_buy: bool,
-    private_key: String,
-    rpc_url: String
+    from: Option<String>,
+    rpc_url: Option<String>,
+    format: OutputFormat
 ) -> Result<()> {
-    info!("Placing limit order: {} {} at price {}", if is_buy { "BUY" } else { "SELL" }, amount, price);
+    let provider = rpc::connect(rpc_url.as_deref()).await?;
+    let contract_address = aliases::resolve(&contract_address)?;
+    let base_token = aliases::resolve(&base_token)?;
+    let quote_token = aliases::resolve(&quote_token)?;
     
-    let provider = Provider::<Http>::try_from(rpc_url)?;
-    let wallet = private_key.parse::<LocalWallet>()?;
-    let client = SignerMiddleware::new(provider, wallet);
+    // Create contract instance
+    let client = Arc::new(provider);
+    let contract = OrderBookDEX::new(contract_address, client.clone());
     
-    let contract_address = contract_address.parse::<Address>()?;
-    let base_token = base_token.parse::<Address>()?;
-    let quote_token = quote_token.parse::<Address>()?;
+    let market = PairMarket::fetch(&contract, base_token, quote_token).await?;
+    let amount_u256 = U256::from(amount);
+    let quote = market.book().quote_market(amount_u256, is_buy);
+    let fully_filled = quote.filled == amount_u256;
     
-    // Load contract ABI
-    let contract_abi = load_dex_abi()?;
+    // Gas can only be estimated for a fill that would succeed, from an account able to pay
+    let mut gas_estimate = None;
+    if let (Some(from), true) = (from, fully_filled) {
+        let mut method = contract.place_market_order(base_token, quote_token, amount_u256, is_buy).from(aliases::resolve(&from)?);
+        if is_buy && quote_token == Address::zero() {
+            method = method.value(quote.quote_amount);
+        } else if !is_buy && base_token == Address::zero() {
+            method = method.value(amount_u256);
+        }
+        match method.estimate_gas().await {
+            Ok(gas) => gas_estimate = Some(gas),
+            Err(e) => tracing::warn!("Could not estimate gas: {}", e),
+        }
+    }
+    let network_fee_wei = match gas_estimate {
+        Some(gas) => Some(gas * client.get_gas_price().await?),
+        None => None,
+    };
     
-    // Create contract instance
-    let client_arc = Arc::new(client);
-    let contract = Contract::new(contract_address, contract_abi, client_arc);
+    if !fully_filled {
+        tracing::warn!(
+            "Insufficient depth: the book fills {} of {} ({:.2}%); the contract reverts market orders it cannot fill completely",
+            quote.filled, amount, quote.filled_pct
+        );
+    }
     
-    // Call placeLimitOrder function
-    let amount_u256 = U256::from(amount);
-    let price_u256 = U256::from(price);
-    let args = (base_token, quote_token, amount_u256, price_u256, is_buy);
-    let method = contract.method::<_, ()>("placeLimitOrder", args)?;
-    let legacy = method.legacy();
-    let tx = legacy.send().await?;
-    
-    let receipt = tx.await?;
-    info!("Limit order placed successfully!");
-    if let Some(receipt) = receipt {
-        info!("Transaction hash: {:?}", receipt.transaction_hash);
+    let report = QuoteReport { quote, trading_fee: U256::zero(), gas_estimate, network_fee_wei };
+    if format == OutputFormat::Json {
+        return Ok(output::print_json(&report)?);
+    }
+    if format == OutputFormat::Csv {
+        let q = &report.quote;
+        return Ok(output::print_csv(&[(q.amount, q.is_buy, q.filled, q.filled_pct, q.quote_amount, q.best_price, q.average_price, q.worst_price, q.impact_bps, report.network_fee_wei)])?);
+    }
+    
+    let q = &report.quote;
+    let price = |price: Option<U256>| price.map_or("-".to_string(), |price| price.to_string());
+    println!("Market {} Quote", if is_buy { "Buy" } else { "Sell" });
+    println!("{}", "=".repeat(if is_buy { 16 } else { 17 }));
+    println!("Amount: {}", q.amount);
+    println!("Filled: {} ({:.2}%) across {} orders", q.filled, q.filled_pct, q.orders_taken);
+    println!("{}: {}", if is_buy { "Cost" } else { "Proceeds" }, q.quote_amount);
+    println!("Best price: {}", price(q.best_price));
+    println!("Average price: {}", price(q.average_price));
+    println!("Worst price: {}", price(q.worst_price));
+    match q.impact_bps {
+        Some(impact) => println!("Price impact: {:.2} bps", impact),
+        None => println!("Price impact: -"),
+    }
+    println!("Trading fee: 0 (market orders pay no trading fee)");
+    match (report.gas_estimate, report.network_fee_wei) {
+        (Some(gas), Some(fee)) => println!("Network fee: {} MON ({} gas)", format_ether(fee), gas),
+        _ => println!("Network fee: not estimated (pass --from)"),
+    }
+    if !fully_filled {
+        println!("WARNING: insufficient depth, this order would revert");
     }
     
     Ok(())
 }
 
-async fn place_market_order(
+#[allow(clippy::too_many_arguments)]
+async fn analytics(
     contract_address: String,
     base_token: String,
     quote_token: String,
-    amount: u64,
-    is_buy: bool,
-    private_key: String,
-    rpc_url: String
+    window_blocks: Option<u64>,
+    bands: Vec<u64>,
+    walls: usize,
+    from_block: u64,
+    rpc_url: Option<String>,
+    format: OutputFormat
 ) -> Result<()> {
-    info!("Placing market order: {} {}", if is_buy { "BUY" } else { "SELL" }, amount);
-    
-    let provider = Provider::<Http>::try_from(rpc_url)?;
-    let wallet = private_key.parse::<LocalWallet>()?;
-    let client = SignerMiddleware::new(provider, wallet);
-    
-    let contract_address = contract_address.parse::<Address>()?;
-    let base_token = base_token.parse::<Address>()?;
-    let quote_token = quote_token.parse::<Address>()?;
+    let provider = rpc::connect(rpc_url.as_deref()).await?;
+    let contract_address = aliases::resolve(&contract_address)?;
+    let base_token = aliases::resolve(&base_token)?;
+    let quote_token = aliases::resolve(&quote_token)?;
     
     // Load contract ABI
-    let contract_abi = load_dex_abi()?;
+    let contract_abi = dex_abi();
+    
+    let mut store = EventStore::load(contract_address, from_block)?;
+    store.sync(&provider, &contract_abi, DEFAULT_CHUNK_SIZE).await?;
     
     // Create contract instance
-    let client_arc = Arc::new(client);
-    let contract = Contract::new(contract_address, contract_abi, client_arc);
+    let contract = OrderBookDEX::new(contract_address, Arc::new(provider));
+    let market = PairMarket::fetch(&contract, base_token, quote_token).await?;
     
-    // Call placeMarketOrder function
-    let amount_u256 = U256::from(amount);
-    let args = (base_token, quote_token, amount_u256, is_buy);
-    let method = contract.method::<_, ()>("placeMarketOrder", args)?;
-    let legacy = method.legacy();
-    let tx = legacy.send().await?;
+    let report = AnalyticsReport {
+        base_token,
+        quote_token,
+        vwap: analytics::vwap(&store, base_token, quote_token, market.price_precision, window_blocks),
+        liquidity: analytics::liquidity(&market.buy_prices, &market.buy_amounts, &market.sell_prices, &market.sell_amounts, &bands, walls),
+    };
     
-    let receipt = tx.await?;
-    info!("Market order placed successfully!");
-    if let Some(receipt) = receipt {
-        info!("Transaction hash: {:?}", receipt.transaction_hash);
+    match format {
+        OutputFormat::Json => output::print_json(&report)?,
+        OutputFormat::Csv => output::print_csv(&report.liquidity.depth)?,
+        OutputFormat::Text => {
+            let optional = |value: Option<f64>, decimals: usize| value.map_or("-".to_string(), |v| format!("{:.*}", decimals, v));
+            println!("Analytics: {:?} / {:?}", base_token, quote_token);
+            println!("================================");
+            match &report.vwap {
+                Some(vwap) => {
+                    println!("VWAP (blocks {} to {}): {:.4}", vwap.from_block, vwap.to_block, vwap.vwap);
+                    println!("  {} trades, {} base, {} quote volume", vwap.trades, vwap.base_volume, vwap.quote_volume);
+                }
+                None => println!("VWAP: no trades in window"),
+            }
+            
+            let liquidity = &report.liquidity;
+            println!("\nBest bid: {}  Best ask: {}  Mid: {}  Spread: {} bps",
+                optional(liquidity.best_bid, 0), optional(liquidity.best_ask, 0), optional(liquidity.mid_price, 2), optional(liquidity.spread_bps, 2));
+            
+            println!("\nDepth around mid:");
+            for band in &liquidity.depth {
+                println!("  +/-{} bps: {} bid, {} ask", band.bps, band.bid_depth, band.ask_depth);
+            }
+            
+            println!("\nLargest walls:");
+            if liquidity.walls.is_empty() {
+                println!("  (book is empty)");
+            }
+            for wall in &liquidity.walls {
+                println!(
+                    "  {} {} at {} ({} orders, {} bps from mid)",
+                    if wall.is_buy { "BID" } else { "ASK" }, wall.amount, wall.price, wall.orders, optional(wall.distance_bps, 1)
+                );
+            }
+        }
     }
     
     Ok(())
 }
 
-async fn cancel_order(
+async fn ticker_stats(
     contract_address: String,
-    order_id: u64,
-    private_key: String,
-    rpc_url: String
+    pair: Option<(String, String)>,
+    window: Duration,
+    from_block: u64,
+    rpc_url: Option<String>,
+    format: OutputFormat
 ) -> Result<()> {
-    info!("Cancelling order: {}", order_id);
+    let provider = rpc::connect(rpc_url.as_deref()).await?;
+    let contract_address = aliases::resolve(&contract_address)?;
     
-    let provider = Provider::<Http>::try_from(rpc_url)?;
-    let wallet = private_key.parse::<LocalWallet>()?;
-    let client = SignerMiddleware::new(provider, wallet);
+    // Load contract ABI
+    let contract_abi = dex_abi();
     
-    let contract_address = contract_address.parse::<Address>()?;
+    let mut store = EventStore::load(contract_address, from_block)?;
+    store.sync(&provider, &contract_abi, DEFAULT_CHUNK_SIZE).await?;
     
-    // Load contract ABI
-    let contract_abi = load_dex_abi()?;
+    let pairs = match pair {
+        Some((base_token, quote_token)) => vec![(aliases::resolve(&base_token)?, aliases::resolve(&quote_token)?)],
+        None => store.listed_pairs(),
+    };
+    
+    // Trades carry block numbers only, so find where the window starts on chain
+    let since = expiry::unix_secs(SystemTime::now()).saturating_sub(window.as_secs());
+    let window_start = indexer::block_at(&provider, since).await?;
     
     // Create contract instance
-    let client_arc = Arc::new(client);
-    let contract = Contract::new(contract_address, contract_abi, client_arc);
+    let client = Arc::new(provider);
+    let contract = OrderBookDEX::new(contract_address, client.clone());
     
-    // Call cancelOrder function
-    let order_id_u256 = U256::from(order_id);
-    let method = contract.method::<_, ()>("cancelOrder", order_id_u256)?;
-    let legacy = method.legacy();
-    let tx = legacy.send().await?;
+    // Read the price precision of every pair in one round trip
+    let mut multicall = Multicall::new(client.clone())?;
+    for (base_token, quote_token) in &pairs {
+        multicall.add(contract.trading_pairs(*base_token, *quote_token))?;
+    }
+    let params = multicall.call_as::<(Address, Address, bool, U256, U256)>().await?;
     
-    let receipt = tx.await?;
-    info!("Order cancelled successfully!");
-    if let Some(receipt) = receipt {
-        info!("Transaction hash: {:?}", receipt.transaction_hash);
+    let mut tickers = Vec::new();
+    for ((base_token, quote_token), params) in pairs.into_iter().zip(params) {
+        let (_, _, _, _, price_precision) = params?;
+        tickers.push(analytics::ticker(&store, base_token, quote_token, price_precision, window_start));
+    }
+    
+    match format {
+        OutputFormat::Json => output::print_json(&tickers)?,
+        OutputFormat::Csv => output::print_csv(&tickers)?,
+        OutputFormat::Text => {
+            let title = format!("Ticker Statistics ({})", humantime::format_duration(window));
+            println!("{}", title);
+            println!("{}", "=".repeat(title.len()));
+            if tickers.is_empty() {
+                println!("No trading pairs.");
+            }
+            let optional = |value: Option<f64>| value.map_or("-".to_string(), |v| v.to_string());
+            for ticker in &tickers {
+                println!("{:?} / {:?}", ticker.base_token, ticker.quote_token);
+                println!("  Last: {}  High: {}  Low: {}", optional(ticker.last_price), optional(ticker.high_price), optional(ticker.low_price));
+                match (ticker.price_change, ticker.price_change_percent) {
+                    (Some(change), Some(percent)) => println!("  Change: {:+} ({:+.2}%)", change, percent),
+                    _ => println!("  Change: -"),
+                }
+                println!("  Volume: {} base, {} quote in {} trades", ticker.volume, ticker.quote_volume, ticker.count);
+            }
+        }
     }
     
     Ok(())
 }
 
-async fn get_order_book(
+async fn list_pairs(
     contract_address: String,
-    base_token: String,
-    quote_token: String,
-    rpc_url: String
+    from_block: u64,
+    all: bool,
+    rpc_url: Option<String>,
+    format: OutputFormat
 ) -> Result<()> {
-    info!("Getting order book for {} / {}", base_token, quote_token);
-    
-    let provider = Provider::<Http>::try_from(rpc_url)?;
-    let contract_address = contract_address.parse::<Address>()?;
-    let base_token = base_token.parse::<Address>()?;
-    let quote_token = quote_token.parse::<Address>()?;
+    let provider = rpc::connect(rpc_url.as_deref()).await?;
+    let contract_address = aliases::resolve(&contract_address)?;
     
     // Load contract ABI
-    let contract_abi = load_dex_abi()?;
+    let contract_abi = dex_abi();
     
-    // Create contract instance
-    let contract = Contract::new(contract_address, contract_abi, Arc::new(provider));
+    let mut store = EventStore::load(contract_address, from_block)?;
+    store.sync(&provider, &contract_abi, DEFAULT_CHUNK_SIZE).await?;
+    let pairs = store.listed_pairs();
     
-    // Call getOrderBook function
-    let result: (Vec<U256>, Vec<U256>, Vec<U256>, Vec<U256>) = contract
-        .method("getOrderBook", (base_token, quote_token))?
-        .call()
-        .await?;
+    // Create contract instance
+    let client = Arc::new(provider);
+    let contract = OrderBookDEX::new(contract_address, client.clone());
     
-    println!("Order Book for {} / {}", base_token, quote_token);
-    println!("==========================================");
+    // Read the current parameters of every pair in one round trip
+    let mut multicall = Multicall::new(client.clone())?;
+    for (base_token, quote_token) in &pairs {
+        multicall.add(contract.trading_pairs(*base_token, *quote_token))?;
+    }
+    let params = multicall.call_as::<(Address, Address, bool, U256, U256)>().await?;
     
-    println!("Buy Orders:");
-    for (i, (price, amount)) in result.0.iter().zip(result.1.iter()).enumerate() {
-        println!("  {}: Price: {}, Amount: {}", i + 1, price, amount);
+    let mut registry = TokenRegistry::load()?;
+    let mut rows = Vec::new();
+    for ((base_token, quote_token), params) in pairs.into_iter().zip(params) {
+        let (_, _, is_active, min_order_size, price_precision) = params?;
+        if !is_active && !all {
+            continue;
+        }
+        let symbol = |info: error::Result<TokenInfo>| info.map_or_else(|_| "?".to_string(), |info| info.symbol);
+        rows.push(PairRow {
+            base_token,
+            base_symbol: symbol(registry.resolve(client.clone(), base_token).await),
+            quote_token,
+            quote_symbol: symbol(registry.resolve(client.clone(), quote_token).await),
+            is_active,
+            min_order_size,
+            price_precision,
+        });
     }
     
-    println!("\nSell Orders:");
-    for (i, (price, amount)) in result.2.iter().zip(result.3.iter()).enumerate() {
-        println!("  {}: Price: {}, Amount: {}", i + 1, price, amount);
+    match format {
+        OutputFormat::Json => output::print_json(&rows)?,
+        OutputFormat::Csv => output::print_csv(&rows)?,
+        OutputFormat::Text => {
+            println!("Trading Pairs on {:?}", contract_address);
+            println!("================================");
+            if rows.is_empty() {
+                println!("No trading pairs found.");
+            }
+            for row in &rows {
+                println!("{} / {}{}", row.base_symbol, row.quote_symbol, if row.is_active { "" } else { " (inactive)" });
+                println!("  Base: {:?}", row.base_token);
+                println!("  Quote: {:?}", row.quote_token);
+                println!("  Min order size: {}", row.min_order_size);
+                println!("  Price precision: {}", row.price_precision);
+            }
+        }
     }
     
     Ok(())
 }
 
-async fn get_user_orders(
+async fn get_portfolio(
     contract_address: String,
     user_address: String,
-    rpc_url: String
+    from_block: u64,
+    rpc_url: Option<String>,
+    format: OutputFormat
 ) -> Result<()> {
-    info!("Getting orders for user: {}", user_address);
+    info!("Building portfolio for user: {}", user_address);
     
-    let provider = Provider::<Http>::try_from(rpc_url)?;
-    let contract_address = contract_address.parse::<Address>()?;
-    let user_address = user_address.parse::<Address>()?;
+    let provider = rpc::connect(rpc_url.as_deref()).await?;
+    let contract_address = aliases::resolve(&contract_address)?;
+    let user_address = aliases::resolve(&user_address)?;
     
     // Load contract ABI
-    let contract_abi = load_dex_abi()?;
+    let contract_abi = dex_abi();
+    
+    // Bring the event index up to date; fills only come from OrderMatched events
+    let mut store = EventStore::load(contract_address, from_block)?;
+    store.sync(&provider, &contract_abi, DEFAULT_CHUNK_SIZE).await?;
     
     // Create contract instance
-    let contract = Contract::new(contract_address, contract_abi, Arc::new(provider));
+    let client = Arc::new(provider);
+    let contract = OrderBookDEX::new(contract_address, client.clone());
     
-    // Call getUserOrders function
-    let order_ids: Vec<U256> = contract
-        .method("getUserOrders", user_address)?
-        .call()
-        .await?;
+    let open_order_ids = contract.get_user_orders(user_address).call().await?;
     
-    println!("Active Orders for {}", user_address);
-    println!("================================");
+    // Every token the user has traded through the DEX
+    let pairs: BTreeSet<(Address, Address)> = store
+        .orders
+        .iter()
+        .filter(|order| order.trader == user_address)
+        .map(|order| (order.base_token, order.quote_token))
+        .collect();
+    let tokens: BTreeSet<Address> = pairs.iter().flat_map(|(base, quote)| [*base, *quote]).collect();
     
-    if order_ids.is_empty() {
-        println!("No active orders found.");
-    } else {
-        for (i, order_id) in order_ids.iter().enumerate() {
-            println!("Order {}: ID {}", i + 1, order_id);
+    // Read the balances of every token concurrently
+    let tokens: Vec<Address> = tokens.into_iter().collect();
+    let balances = fanout::map(tokens.clone(), rpc::max_concurrency(), |token| {
+        let client = client.clone();
+        let contract = contract.clone();
+        async move {
+            // The DEX uses the zero address for the native token
+            let wallet_balance = if token == Address::zero() {
+                client.get_balance(user_address, None).await?
+            } else {
+                ERC20::new(token, client.clone()).balance_of(user_address).call().await?
+            };
+            let dex_balance = contract.get_user_balance(user_address, token).call().await?;
+            Ok::<_, anyhow::Error>((wallet_balance, dex_balance))
+        }
+    })
+    .await?;
+    
+    let mut holdings = Vec::with_capacity(tokens.len());
+    for (token, balances) in tokens.into_iter().zip(balances) {
+        let (wallet_balance, dex_balance) = balances?;
+        let open_orders = open_order_ids
+            .iter()
+            .filter_map(|id| store.order(*id))
+            .filter(|order| order.base_token == token || order.quote_token == token)
+            .count();
+        
+        holdings.push(TokenHolding {
+            token,
+            wallet_balance,
+            dex_balance,
+            open_orders,
+        });
+    }
+    
+    // Read the precision and book of every traded pair concurrently
+    let positions = portfolio::positions(&store, user_address);
+    let books = fanout::map(positions.keys().copied().collect(), rpc::max_concurrency(), |(base_token, quote_token)| {
+        let contract = contract.clone();
+        async move {
+            let (_, _, _, _, price_precision) = contract.trading_pairs(base_token, quote_token).call().await?;
+            let (buy_prices, _, sell_prices, _) = contract.get_order_book(base_token, quote_token).call().await?;
+            Ok::<_, anyhow::Error>((price_precision, buy_prices, sell_prices))
+        }
+    })
+    .await?;
+    
+    let mut pnl = Vec::new();
+    for (((base_token, quote_token), tracker), book) in positions.into_iter().zip(books) {
+        let (price_precision, buy_prices, sell_prices) = book?;
+        let precision = portfolio::u256_to_f64(price_precision).max(1.0);
+        
+        // Mark at the mid of the best bid and ask, or whichever side exists
+        let best_bid = buy_prices.iter().max().map(|p| portfolio::u256_to_f64(*p));
+        let best_ask = sell_prices.iter().min().map(|p| portfolio::u256_to_f64(*p));
+        let mark_price = match (best_bid, best_ask) {
+            (Some(bid), Some(ask)) => Some((bid + ask) / 2.0),
+            (bid, ask) => bid.or(ask),
+        };
+        
+        pnl.push(PairPnl {
+            base_token,
+            quote_token,
+            position: tracker.position,
+            average_price: tracker.average_price,
+            mark_price,
+            realized_pnl: tracker.realized / precision,
+            unrealized_pnl: mark_price.map(|mark| tracker.unrealized(mark) / precision),
+            fees: tracker.fees / precision,
+            fills: tracker.fills,
+        });
+    }
+    
+    match format {
+        OutputFormat::Json => output::print_json(&PortfolioReport {
+            user: user_address,
+            holdings,
+            pairs: pnl,
+        })?,
+        OutputFormat::Csv => output::print_csv(&pnl)?,
+        OutputFormat::Text => {
+            println!("Portfolio for {:?}", user_address);
+            println!("================================");
+            
+            println!("Holdings:");
+            if holdings.is_empty() {
+                println!("  No DEX activity found.");
+            }
+            for holding in &holdings {
+                println!(
+                    "  {:?}: wallet {}, DEX {}, open orders {}",
+                    holding.token, holding.wallet_balance, holding.dex_balance, holding.open_orders
+                );
+            }
+            
+            println!("\nPositions:");
+            if pnl.is_empty() {
+                println!("  No fills found.");
+            }
+            for pair in &pnl {
+                println!("  {:?} / {:?}", pair.base_token, pair.quote_token);
+                println!("    Position: {} @ {:.4} ({} fills)", pair.position, pair.average_price, pair.fills);
+                match pair.mark_price {
+                    Some(mark) => println!("    Mark price: {:.4}", mark),
+                    None => println!("    Mark price: unavailable (empty order book)"),
+                }
+                println!("    Realized PnL: {:.6}", pair.realized_pnl);
+                println!("    Fees paid: {:.6}", pair.fees);
+                match pair.unrealized_pnl {
+                    Some(unrealized) => println!("    Unrealized PnL: {:.6}", unrealized),
+                    None => println!("    Unrealized PnL: unavailable"),
+                }
+            }
         }
     }
     
     Ok(())
 }
 
-async fn get_balance(
+async fn get_positions(
     contract_address: String,
     user_address: String,
-    token_address: String,
-    rpc_url: String
+    pair: Option<(String, String)>,
+    from_block: u64,
+    mark_feed: Option<String>,
+    rpc_url: Option<String>,
+    format: OutputFormat
 ) -> Result<()> {
-    info!("Getting balance for user: {} token: {}", user_address, token_address);
+    info!("Building positions for user: {}", user_address);
     
-    let provider = Provider::<Http>::try_from(rpc_url)?;
-    let contract_address = contract_address.parse::<Address>()?;
-    let user_address = user_address.parse::<Address>()?;
-    let token_address = token_address.parse::<Address>()?;
+    let provider = rpc::connect(rpc_url.as_deref()).await?;
+    let contract_address = aliases::resolve(&contract_address)?;
+    let user_address = aliases::resolve(&user_address)?;
     
-    // Load contract ABI
-    let contract_abi = load_dex_abi()?;
+    // Bring the event index up to date; fills only come from OrderMatched events
+    let mut store = EventStore::load(contract_address, from_block)?;
+    store.sync(&provider, &dex_abi(), DEFAULT_CHUNK_SIZE).await?;
     
     // Create contract instance
-    let contract = Contract::new(contract_address, contract_abi, Arc::new(provider));
+    let client = Arc::new(provider);
+    let contract = OrderBookDEX::new(contract_address, client.clone());
     
-    // Call getUserBalance function
-    let balance: U256 = contract
-        .method("getUserBalance", (user_address, token_address))?
-        .call()
-        .await?;
+    // One pair, or every pair the user has traded
+    let pairs: BTreeSet<(Address, Address)> = match pair {
+        Some((base_token, quote_token)) => BTreeSet::from([(aliases::resolve(&base_token)?, aliases::resolve(&quote_token)?)]),
+        None => portfolio::positions(&store, user_address).into_keys().collect(),
+    };
+    
+    let mut positions = Vec::with_capacity(pairs.len());
+    for (base_token, quote_token) in pairs {
+        let (tracker, _) = portfolio::pair_tracker(&store, user_address, base_token, quote_token, 0);
+        let (_, _, _, _, price_precision) = contract.trading_pairs(base_token, quote_token).call().await?;
+        
+        // What the user holds right now, in the wallet and on the DEX
+        let mut inventory = [U256::zero(); 2];
+        for (held, token) in inventory.iter_mut().zip([base_token, quote_token]) {
+            let wallet_balance = if token == Address::zero() {
+                client.get_balance(user_address, None).await?
+            } else {
+                ERC20::new(token, client.clone()).balance_of(user_address).call().await?
+            };
+            *held = wallet_balance + contract.get_user_balance(user_address, token).call().await?;
+        }
+        
+        // Mark at the feed price, else the mid of the best bid and ask, or whichever side exists
+        let mark_price = match &mark_feed {
+            Some(symbol) => Some(PriceFeeds::load()?.price(symbol).await?.price),
+            None => {
+                let (buy_prices, buy_amounts, sell_prices, sell_amounts) = contract.get_order_book(base_token, quote_token).call().await?;
+                let book = BookView::from_levels(&buy_prices, &buy_amounts, &sell_prices, &sell_amounts);
+                book.mid().or_else(|| book.best_bid().or(book.best_ask()).map(portfolio::u256_to_f64))
+            }
+        };
+        
+        positions.push(PairPosition::new(base_token, quote_token, &tracker, price_precision, inventory[0], inventory[1], mark_price));
+    }
     
-    println!("Balance: {} tokens", balance);
+    match format {
+        OutputFormat::Json => output::print_json(&positions)?,
+        OutputFormat::Csv => output::print_csv(&positions)?,
+        OutputFormat::Text => {
+            println!("Positions for {:?}", user_address);
+            println!("================================");
+            if positions.is_empty() {
+                println!("No fills found.");
+            }
+            for position in &positions {
+                println!("{:?} / {:?}", position.base_token, position.quote_token);
+                println!("  Net position: {} @ {:.4} average cost ({} fills)", position.position, position.average_cost, position.fills);
+                println!("  Inventory: {} base, {} quote (wallet + DEX)", position.base_inventory, position.quote_inventory);
+                match position.mark_price {
+                    Some(mark) => println!("  Mark price: {:.4}", mark),
+                    None => println!("  Mark price: unavailable (empty order book)"),
+                }
+                println!("  Realized PnL: {:.6}", position.realized_pnl);
+                match position.unrealized_pnl {
+                    Some(unrealized) => println!("  Unrealized PnL: {:.6}", unrealized),
+                    None => println!("  Unrealized PnL: unavailable"),
+                }
+            }
+        }
+    }
     
     Ok(())
 }
 
-async fn withdraw(
-    contract_address: String,
-    token_address: String,
-    amount: u64,
-    private_key: String,
-    rpc_url: String
+async fn tx_status(tx_hash: String, rpc_url: Option<String>) -> Result<()> {
+    info!("Getting status for transaction: {}", tx_hash);
+    
+    let provider = rpc::connect(rpc_url.as_deref()).await?;
+    let tx_hash = tx_hash.parse::<H256>()?;
+    
+    println!("Transaction {:?}", tx_hash);
+    println!("================================");
+    
+    let Some(receipt) = provider.get_transaction_receipt(tx_hash).await? else {
+        match provider.get_transaction(tx_hash).await? {
+            Some(tx) => println!("Status: Pending (nonce {})", tx.nonce),
+            None => println!("Status: Not found"),
+        }
+        return Ok(());
+    };
+    
+    let status = match receipt.status.map(|s| s.as_u64()) {
+        Some(1) => "Mined (success)",
+        Some(_) => "Failed (reverted)",
+        None => "Mined",
+    };
+    println!("Status: {}", status);
+    if let Some(block) = receipt.block_number {
+        println!("Block: {}", block);
+    }
+    println!("Confirmations: {}", tx::confirmations(&provider, &receipt).await?);
+    if let Some(gas_used) = receipt.gas_used {
+        println!("Gas used: {}", gas_used);
+    }
+    if let Some(price) = receipt.effective_gas_price {
+        println!("Effective gas price: {} wei", price);
+    }
+    
+    // Decode against the DEX ABI, then the emitting contract's verified ABI; other logs are shown raw
+    let abi = dex_abi();
+    let mut abis = AbiLookup::default();
+    println!("\nLogs ({}):", receipt.logs.len());
+    for (i, log) in receipt.logs.iter().enumerate() {
+        let log_abi = match events::decode_log(&abi, log) {
+            Some(_) => Some(&abi),
+            None => abis.get(&provider, log.address).await,
+        };
+        events::print_log(i, log_abi, log);
+    }
+    
+    Ok(())
+}
+
+async fn trace_tx(tx_hash: String, artifact: Option<String>, rpc_url: Option<String>, format: OutputFormat) -> Result<()> {
+    let provider = rpc::connect(rpc_url.as_deref()).await?;
+    let tx_hash = tx_hash.parse::<H256>()?;
+    let report = trace::trace(&provider, tx_hash, artifact.as_deref()).await?;
+    
+    match format {
+        OutputFormat::Json => output::print_json(&report)?,
+        OutputFormat::Csv => {
+            let rows: Vec<_> = report
+                .calls
+                .iter()
+                .enumerate()
+                .map(|(depth, call)| (depth, &call.kind, call.to, &call.function, &call.error, &call.reason))
+                .collect();
+            output::print_csv(&rows)?;
+        }
+        OutputFormat::Text => {
+            println!("Trace of {:?}", report.tx_hash);
+            println!("================================");
+            for (depth, call) in report.calls.iter().enumerate() {
+                let to = call.to.map_or("(new contract)".to_string(), |to| format!("{:?}", to));
+                println!("{}{} {} {}", "  ".repeat(depth), call.kind, to, call.function);
+                if let Some(error) = &call.error {
+                    println!("{}  error: {}", "  ".repeat(depth), error);
+                }
+                if let Some(reason) = &call.reason {
+                    println!("{}  reason: {}", "  ".repeat(depth), reason);
+                }
+            }
+            match (&report.source, report.pc) {
+                (Some(source), _) => {
+                    println!("\nReverted in {} at {}:{}", source.function.as_deref().unwrap_or("(no function)"), source.file, source.line);
+                    println!("  {}", source.code);
+                }
+                (None, Some(pc)) => println!("\nReverted at pc {} (pass --artifact with a built contract to see the source line)", pc),
+                (None, None) => {}
+            }
+        }
+    }
+    
+    Ok(())
+}
+
+/// One log as `decode-logs` prints it for JSON and CSV
+#[derive(Serialize)]
+struct LogRow {
+    block: Option<u64>,
+    tx_hash: Option<H256>,
+    log_index: Option<u64>,
+    address: Address,
+    event: Option<String>,
+    params: String,
+}
+
+async fn decode_logs(
+    tx_hash: Option<String>,
+    block: Option<u64>,
+    address: Option<String>,
+    rpc_url: Option<String>,
+    format: OutputFormat,
 ) -> Result<()> {
-    info!("Withdrawing {} tokens", amount);
+    let provider = rpc::connect(rpc_url.as_deref()).await?;
+    let address = address.map(|address| aliases::resolve(&address)).transpose()?;
+    
+    let (title, logs) = match (tx_hash, block) {
+        (Some(tx_hash), _) => {
+            let tx_hash = tx_hash.parse::<H256>()?;
+            let receipt = provider
+                .get_transaction_receipt(tx_hash)
+                .await?
+                .ok_or_else(|| anyhow::anyhow!("No receipt for {:?}; it is pending or unknown", tx_hash))?;
+            (format!("Logs of transaction {:?}", tx_hash), receipt.logs)
+        }
+        (None, Some(block)) => {
+            let filter = Filter::new().from_block(block).to_block(block);
+            (format!("Logs of block {}", block), provider.get_logs(&filter).await?)
+        }
+        (None, None) => return Err(anyhow::anyhow!("Pass --tx-hash or --block")),
+    };
+    let logs: Vec<_> = logs.into_iter().filter(|log| address.is_none_or(|address| log.address == address)).collect();
+    
+    // The DEX and token events are known up front; anything else needs the emitter's ABI
+    let dex = dex_abi();
+    let known = [&dex, &*ERC20_ABI, &*WMON_ABI];
+    let mut abis = AbiLookup::default();
+    let mut decoded = Vec::with_capacity(logs.len());
+    for log in &logs {
+        let log_abi = match events::decode_any(&known, log) {
+            Some((abi, _)) => Some(abi.clone()),
+            None => abis.get(&provider, log.address).await.cloned(),
+        };
+        decoded.push(log_abi);
+    }
+    
+    match format {
+        OutputFormat::Json | OutputFormat::Csv => {
+            let rows: Vec<LogRow> = logs
+                .iter()
+                .zip(&decoded)
+                .map(|(log, abi)| {
+                    let event = abi.as_ref().and_then(|abi| events::decode_log(abi, log));
+                    let params = match &event {
+                        Some(event) => {
+                            let params: Vec<String> = event.params.iter().map(|(name, value)| format!("{}={}", name, value)).collect();
+                            params.join(" ")
+                        }
+                        None => log.data.to_string(),
+                    };
+                    LogRow {
+                        block: log.block_number.map(|block| block.as_u64()),
+                        tx_hash: log.transaction_hash,
+                        log_index: log.log_index.map(|index| index.as_u64()),
+                        address: log.address,
+                        event: event.map(|event| event.event),
+                        params,
+                    }
+                })
+                .collect();
+            if format == OutputFormat::Json {
+                output::print_json(&rows)?;
+            } else {
+                output::print_csv(&rows)?;
+            }
+        }
+        OutputFormat::Text => {
+            println!("{}", title);
+            println!("================================");
+            let mut current_tx = None;
+            for (i, (log, abi)) in logs.iter().zip(&decoded).enumerate() {
+                // A block's logs are grouped under the transaction that emitted them
+                if block.is_some() && log.transaction_hash != current_tx {
+                    current_tx = log.transaction_hash;
+                    println!("\nTransaction {:?}", current_tx.unwrap_or_default());
+                }
+                events::print_log(i, abi.as_ref(), log);
+            }
+            if logs.is_empty() {
+                println!("No logs");
+            }
+        }
+    }
+    
+    Ok(())
+}
+
+async fn bump_tx(
+    tx_hash: String,
+    private_key: Option<String>,
+    rpc_url: Option<String>,
+    tx_args: &TxArgs
+) -> Result<()> {
+    info!("Bumping fees for transaction: {}", tx_hash);
     
-    let provider = Provider::<Http>::try_from(rpc_url)?;
-    let wallet = private_key.parse::<LocalWallet>()?;
+    let provider = rpc::connect(rpc_url.as_deref()).await?;
+    let wallet = rpc::wallet(&provider, private_key.as_deref()).await?;
     let client = SignerMiddleware::new(provider, wallet);
     
-    let contract_address = contract_address.parse::<Address>()?;
-    let token_address = token_address.parse::<Address>()?;
+    let tx_hash = tx_hash.parse::<H256>()?;
+    let original = replace::pending_transaction(&client, tx_hash, client.address()).await?;
     
-    // Load contract ABI
-    let contract_abi = load_dex_abi()?;
+    let replacement = replace::to_request(&original);
+    let receipt = replace::replace(&client, &original, replacement, tx_args.bump_percent, tx_args.confirmations).await?;
     
-    // Create contract instance
-    let contract = Contract::new(contract_address, contract_abi, Arc::new(client));
+    if let Some(receipt) = receipt {
+        info!("Replacement transaction mined!");
+        info!("Transaction hash: {:?}", receipt.transaction_hash);
+    }
     
-    // Call withdraw function
-    let amount_u256 = U256::from(amount);
-    let args = (token_address, amount_u256);
-    let method = contract.method::<_, ()>("withdraw", args)?;
-    let legacy = method.legacy();
-    let tx = legacy.send().await?;
+    Ok(())
+}
+
+async fn cancel_tx(
+    tx_hash: String,
+    private_key: Option<String>,
+    rpc_url: Option<String>,
+    tx_args: &TxArgs
+) -> Result<()> {
+    info!("Cancelling transaction: {}", tx_hash);
+    
+    let provider = rpc::connect(rpc_url.as_deref()).await?;
+    let wallet = rpc::wallet(&provider, private_key.as_deref()).await?;
+    let client = SignerMiddleware::new(provider, wallet);
+    
+    let tx_hash = tx_hash.parse::<H256>()?;
+    let original = replace::pending_transaction(&client, tx_hash, client.address()).await?;
+    
+    let cancellation = replace::to_cancellation(&original);
+    let receipt = replace::replace(&client, &original, cancellation, tx_args.bump_percent, tx_args.confirmations).await?;
+    
+    if let Some(receipt) = receipt {
+        info!("Transaction cancelled successfully!");
+        info!("Transaction hash: {:?}", receipt.transaction_hash);
+    }
+    
+    Ok(())
+}
+
+/// ABI for `address`: from the artifact when one is given, else the verified ABI on the explorer
+async fn contract_abi<M: Middleware + 'static>(client: &M, address: Address, artifact: Option<&str>) -> Result<Abi> {
+    match artifact {
+        Some(artifact) => Ok(pipeline::load_artifact(artifact)?.0),
+        None => Ok(explorer::abi(client, address).await?),
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+async fn call_contract(
+    contract_address: String,
+    function: String,
+    args: Vec<String>,
+    artifact: Option<String>,
+    value: Option<String>,
+    private_key: Option<String>,
+    rpc_url: Option<String>,
+    tx_args: &TxArgs,
+    format: OutputFormat,
+) -> Result<()> {
+    let provider = rpc::connect(rpc_url.as_deref()).await?;
+    let contract_address = aliases::resolve(&contract_address)?;
+    let abi = contract_abi(&provider, contract_address, artifact.as_deref()).await?;
+    let abi_function = abi.function(&function)?.clone();
+    let tokens = pipeline::tokenize_args(&function, &abi_function.inputs, &args)?;
+    
+    if matches!(abi_function.state_mutability, StateMutability::View | StateMutability::Pure) {
+        let data = abi_function.encode_input(&tokens)?;
+        let call: TypedTransaction = TransactionRequest::new().to(contract_address).data(data).into();
+        let result = provider.call(&call, None).await?;
+        let outputs: Vec<(String, String)> = abi_function
+            .decode_output(&result)?
+            .into_iter()
+            .zip(&abi_function.outputs)
+            .enumerate()
+            .map(|(i, (token, param))| {
+                let name = if param.name.is_empty() { format!("output{}", i) } else { param.name.clone() };
+                (name, token.to_string())
+            })
+            .collect();
+        
+        match format {
+            OutputFormat::Json => output::print_json(&outputs.into_iter().collect::<BTreeMap<_, _>>())?,
+            OutputFormat::Csv => output::print_csv(&outputs)?,
+            OutputFormat::Text => {
+                println!("{} on {:?}", function, contract_address);
+                println!("================================");
+                for (name, value) in outputs {
+                    println!("{}: {}", name, value);
+                }
+            }
+        }
+        return Ok(());
+    }
+    
+    let value = value
+        .as_deref()
+        .map(U256::from_dec_str)
+        .transpose()
+        .map_err(|e| anyhow::anyhow!("Invalid value: {}", e))?;
+    let fees = tx_args.fees.resolve(&provider).await?;
+    let wallet = rpc::wallet(&provider, private_key.as_deref()).await?;
+    let client = Arc::new(SignerMiddleware::new(provider, wallet));
+    
+    let contract = Contract::new(contract_address, abi, client.clone());
+    let mut method = contract.method::<_, ()>(&function, Token::Tuple(tokens))?;
+    if let Some(value) = value {
+        method = method.value(value);
+    }
+    let receipt = tx::send(client.as_ref(), method, &fees, tx_args).await?;
     
-    let receipt = tx.await?;
-    info!("Withdrawal successful!");
     if let Some(receipt) = receipt {
+        tx::ensure_success(client.as_ref(), &receipt).await?;
+        info!("{} completed successfully!", function);
         info!("Transaction hash: {:?}", receipt.transaction_hash);
     }
     
     Ok(())
 }
 
-fn load_dex_abi() -> Result<Abi> {
-    info!("Loading DEX contract ABI...");
+async fn contract_logs(
+    contract_address: String,
+    from_block: Option<u64>,
+    to_block: Option<u64>,
+    artifact: Option<String>,
+    rpc_url: Option<String>,
+    format: OutputFormat,
+) -> Result<()> {
+    let provider = rpc::connect(rpc_url.as_deref()).await?;
+    let contract_address = aliases::resolve(&contract_address)?;
+    let abi = contract_abi(&provider, contract_address, artifact.as_deref()).await?;
+    
+    let to_block = match to_block {
+        Some(block) => block,
+        None => provider.get_block_number().await?.as_u64(),
+    };
+    let from_block = from_block.unwrap_or(to_block.saturating_sub(DEFAULT_CHUNK_SIZE));
+    let filter = Filter::new().address(contract_address).from_block(from_block).to_block(to_block);
+    let logs = provider.get_logs(&filter).await?;
+    
+    match format {
+        OutputFormat::Json | OutputFormat::Csv => {
+            let rows: Vec<ContractLogRow> = logs
+                .iter()
+                .map(|log| {
+                    let decoded = events::decode_log(&abi, log);
+                    let params = decoded
+                        .as_ref()
+                        .map(|decoded| {
+                            let params: Vec<String> = decoded.params.iter().map(|(name, value)| format!("{}={}", name, value)).collect();
+                            params.join(" ")
+                        })
+                        .unwrap_or_else(|| log.data.to_string());
+                    (log.block_number.map(|block| block.as_u64()), log.transaction_hash, decoded.map(|decoded| decoded.event), params)
+                })
+                .collect();
+            if format == OutputFormat::Json {
+                output::print_json(&rows)?;
+            } else {
+                output::print_csv(&rows)?;
+            }
+        }
+        OutputFormat::Text => {
+            println!("Logs of {:?} in blocks {}-{}", contract_address, from_block, to_block);
+            println!("================================");
+            for (i, log) in logs.iter().enumerate() {
+                events::print_log(i, Some(&abi), log);
+            }
+            if logs.is_empty() {
+                println!("No logs");
+            }
+        }
+    }
     
-    // Load the compiled ABI from the out directory
-    let abi_path = "out/OrderBookDEX.sol/OrderBookDEX.json";
-    let abi_content = std::fs::read_to_string(abi_path)?;
+    Ok(())
+}
+
+async fn show_contract_abi(contract_address: String, refresh: bool, rpc_url: Option<String>, format: OutputFormat) -> Result<()> {
+    let provider = rpc::connect(rpc_url.as_deref()).await?;
+    let contract_address = aliases::resolve(&contract_address)?;
+    let abi = if refresh {
+        explorer::refresh(&provider, contract_address).await?
+    } else {
+        explorer::abi(&provider, contract_address).await?
+    };
     
-    // Parse the JSON and extract the ABI
-    let json: serde_json::Value = serde_json::from_str(&abi_content)?;
-    let abi_json = json["abi"].to_string();
+    match format {
+        OutputFormat::Json => output::print_json(&abi)?,
+        OutputFormat::Csv => {
+            let functions = abi.functions().map(|function| ("function", function_signature(function)));
+            let events = abi.events().map(|event| ("event", event_signature(event)));
+            output::print_csv(&functions.chain(events).collect::<Vec<_>>())?;
+        }
+        OutputFormat::Text => {
+            println!("ABI of {:?}", contract_address);
+            println!("================================");
+            println!("Functions:");
+            for function in abi.functions() {
+                println!("  {}", function_signature(function));
+            }
+            println!("Events:");
+            for event in abi.events() {
+                println!("  {}", event_signature(event));
+            }
+        }
+    }
     
-    let abi: Abi = serde_json::from_str(&abi_json)?;
-    Ok(abi)
-} 
\ No newline at end of file
+    Ok(())
+}
+
+/// e.g. `balanceOf(address account) view returns (uint256)`
+fn function_signature(function: &Function) -> String {
+    let inputs: Vec<String> = function.inputs.iter().map(|p| format!("{} {}", p.kind, p.name).trim().to_string()).collect();
+    let outputs: Vec<String> = function.outputs.iter().map(|p| format!("{} {}", p.kind, p.name).trim().to_string()).collect();
+    let mutability = match function.state_mutability {
+        StateMutability::View => " view",
+        StateMutability::Pure => " pure",
+        StateMutability::Payable => " payable",
+        StateMutability::NonPayable => "",
+    };
+    let returns = if outputs.is_empty() { String::new() } else { format!(" returns ({})", outputs.join(", ")) };
+    format!("{}({}){}{}", function.name, inputs.join(", "), mutability, returns)
+}
+
+/// e.g. `Transfer(address indexed from, address indexed to, uint256 value)`
+fn event_signature(event: &Event) -> String {
+    let inputs: Vec<String> = event
+        .inputs
+        .iter()
+        .map(|p| format!("{}{} {}", p.kind, if p.indexed { " indexed" } else { "" }, p.name).trim().to_string())
+        .collect();
+    format!("{}({})", event.name, inputs.join(", "))
+}
+
+/// DEX ABI from the typed bindings, for decoding its events
+fn dex_abi() -> Abi {
+    ORDERBOOKDEX_ABI.clone()
+}
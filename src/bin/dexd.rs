@@ -0,0 +1,115 @@
+use anyhow::Result;
+use clap::Parser;
+use monad_app::daemon::{self, DaemonOptions, DaemonStatus};
+use monad_app::error;
+use monad_app::journal::{TxJournal, TxStatus};
+use monad_app::logging::{self, LogArgs};
+use monad_app::metrics;
+use monad_app::rpc::{self, RpcArgs};
+use monad_app::tx::TxArgs;
+use std::net::SocketAddr;
+use std::process::ExitCode;
+
+/// Long-running host for the indexer, alerts monitor, bots, order expiry and schedules, controlled with `monad-dex ctl`
+#[derive(Parser)]
+#[command(author, version, about, long_about = None, after_help = error::EXIT_CODES)]
+struct Cli {
+    #[command(flatten)]
+    log: LogArgs,
+
+    #[command(flatten)]
+    rpc: RpcArgs,
+
+    #[command(flatten)]
+    tx: TxArgs,
+
+    /// Daemon config file (see dexd.example.toml)
+    #[arg(short, long, default_value = "dexd.toml")]
+    config: String,
+
+    /// Control socket to listen on (overrides the config)
+    #[arg(long)]
+    socket: Option<String>,
+
+    /// Private key or --account for the bots, order expiry and schedules (not needed with --paper)
+    #[arg(short, long)]
+    private_key: Option<String>,
+
+    /// RPC URL (comma-separated list for failover)
+    #[arg(short, long)]
+    rpc_url: Option<String>,
+
+    /// Run the bots against paper accounts
+    #[arg(long)]
+    paper: bool,
+
+    /// Trade without first checking that the DEX exposes the expected interface
+    #[arg(long)]
+    skip_compat_check: bool,
+
+    /// Serve Prometheus metrics on this address (e.g. 0.0.0.0:9090)
+    #[arg(long)]
+    metrics_addr: Option<SocketAddr>,
+}
+
+#[tokio::main]
+async fn main() -> ExitCode {
+    match run(Cli::parse()).await {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => error::report(&e),
+    }
+}
+
+async fn run(cli: Cli) -> Result<()> {
+    let _log_guard = logging::init(&cli.log)?;
+    let _fork = rpc::configure(cli.rpc.clone());
+
+    if let Some(addr) = cli.metrics_addr {
+        tokio::spawn(async move {
+            if let Err(e) = metrics::serve(addr).await {
+                tracing::error!("Metrics server stopped: {}", e);
+            }
+        });
+    }
+
+    let options = DaemonOptions {
+        rpc_url: cli.rpc_url,
+        private_key: cli.private_key,
+        paper: cli.paper,
+        skip_compat_check: cli.skip_compat_check,
+        tx_args: cli.tx,
+        socket: cli.socket,
+    };
+    let status = daemon::run(&cli.config, options).await?;
+    print_summary(&status)
+}
+
+/// What the daemon left behind, so an operator can tell whether anything needs attention
+fn print_summary(status: &DaemonStatus) -> Result<()> {
+    println!("dexd Shutdown Summary");
+    println!("================================");
+    for task in &status.tasks {
+        let state = format!("{:?}", task.state).to_lowercase();
+        println!("{:<28} {:<9} {}", task.name, state, task.detail.as_deref().unwrap_or(""));
+        if let Some(error) = &task.error {
+            println!("{:<28} error: {}", "", error);
+        }
+    }
+    if let Some(halt) = &status.halt {
+        println!("Trading is halted: {}", halt.reason);
+    }
+
+    // Bots journal every transaction as it is sent; these never got a receipt
+    let journal = TxJournal::load()?;
+    let pending: Vec<_> = journal.entries.iter().filter(|entry| entry.status == TxStatus::Pending).collect();
+    if pending.is_empty() {
+        println!("No transactions pending.");
+    } else {
+        println!("{} transactions still pending (see `monad-dex journal list --status pending`):", pending.len());
+        for entry in pending {
+            let hash = entry.tx_hash.map_or("-".to_string(), |hash| format!("{:?}", hash));
+            println!("  #{} {} nonce {} {}", entry.id, entry.intent, entry.nonce, hash);
+        }
+    }
+    Ok(())
+}
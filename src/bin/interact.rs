@@ -1,55 +1,161 @@
 use clap::{Parser, Subcommand};
 use ethers::{
     middleware::SignerMiddleware,
-    providers::{Http, Provider},
-    signers::LocalWallet,
-    types::{Address, U256},
+    providers::Middleware,
+    signers::Signer,
+    types::{transaction::eip2718::TypedTransaction, TransactionRequest, U256},
+    utils::{format_ether, parse_ether},
     contract::Contract,
     abi::Abi,
 };
-use anyhow::Result;
+use anyhow::{anyhow, Context, Result};
+use monad_app::{aliases, error, faucet, journal, limits, permit, session};
+use monad_app::bindings::ERC20;
+use monad_app::limits::Spend;
+use monad_app::logging::{self, LogArgs};
+use monad_app::rpc::{self, RpcArgs};
+use monad_app::tx::{self, TxArgs};
+use serde::Deserialize;
 use tracing::info;
+use std::collections::BTreeMap;
+use std::process::ExitCode;
 use std::sync::Arc;
+use std::time::Duration;
 
 #[derive(Parser)]
-#[command(author, version, about, long_about = None)]
+#[command(author, version, about, long_about = None, after_help = error::EXIT_CODES)]
 struct Cli {
+    #[command(flatten)]
+    log: LogArgs,
+
+    #[command(flatten)]
+    rpc: RpcArgs,
+
+    #[command(flatten)]
+    tx: TxArgs,
+
     #[command(subcommand)]
     command: Commands,
 }
 
 #[derive(Subcommand)]
 enum Commands {
+    #[command(flatten)]
+    Write(WriteCommands),
+    
+    /// Estimate gas and cost for a write command without sending it
+    EstimateGas {
+        #[command(subcommand)]
+        command: WriteCommands,
+    },
+    
     /// Get token information
     Info {
-        /// Contract address
-        #[arg(short, long)]
+        /// Contract address (defaults to the latest recorded MonadToken deployment)
+        #[arg(short, long, default_value = "MonadToken")]
         address: String,
         
-        /// RPC URL
-        #[arg(short, long, default_value = "https://monad-testnet.g.alchemy.com/v2/hl5Gau0XVV37m-RDdhcRzqCh7ISwmOAe")]
-        rpc_url: String,
+        /// RPC URL (comma-separated list for failover)
+        #[arg(short, long)]
+        rpc_url: Option<String>,
     },
     
     /// Get account balance
     Balance {
-        /// Contract address
-        #[arg(long)]
+        /// Contract address (defaults to the latest recorded MonadToken deployment)
+        #[arg(long, default_value = "MonadToken")]
         address: String,
         
         /// Account address
         #[arg(long)]
         account: String,
         
-        /// RPC URL
-        #[arg(short, long, default_value = "https://monad-testnet.g.alchemy.com/v2/hl5Gau0XVV37m-RDdhcRzqCh7ISwmOAe")]
-        rpc_url: String,
+        /// RPC URL (comma-separated list for failover)
+        #[arg(short, long)]
+        rpc_url: Option<String>,
     },
     
+    /// Get an account's native MON balance (the funds that pay for gas)
+    NativeBalance {
+        /// Account address
+        #[arg(long)]
+        account: String,
+        
+        /// RPC URL (comma-separated list for failover)
+        #[arg(short, long)]
+        rpc_url: Option<String>,
+    },
+    
+    /// Sign an EIP-2612 permit so `spender` can pull tokens without a separate approve transaction
+    Permit {
+        /// Token contract address (must implement EIP-2612)
+        #[arg(short, long)]
+        address: String,
+        
+        /// Spender address, usually the DEX
+        #[arg(short, long)]
+        spender: String,
+        
+        /// Amount the spender may transfer
+        #[arg(long)]
+        amount: u64,
+        
+        /// Seconds until the permit expires
+        #[arg(long, default_value_t = 3600)]
+        deadline_secs: u64,
+        
+        /// Submit the permit on-chain instead of only printing the signature
+        #[arg(long)]
+        submit: bool,
+        
+        /// Private key of the token owner (or --account)
+        #[arg(short, long)]
+        private_key: Option<String>,
+        
+        /// RPC URL (comma-separated list for failover)
+        #[arg(short, long)]
+        rpc_url: Option<String>,
+    },
+    
+    /// Request testnet MON from the Monad faucet and wait for it to arrive
+    Faucet {
+        /// Address to fund (defaults to the private key's address)
+        #[arg(long)]
+        to: Option<String>,
+        
+        /// Private key of the account to fund (or --account); required for --public-mint
+        #[arg(short, long)]
+        private_key: Option<String>,
+        
+        /// Also call publicMint on the test token once the MON has arrived
+        #[arg(long)]
+        public_mint: bool,
+        
+        /// Token to public mint (defaults to the latest recorded MonadToken deployment)
+        #[arg(long, default_value = "MonadToken")]
+        token: String,
+        
+        /// Faucet claim endpoint
+        #[arg(long, default_value = faucet::DEFAULT_FAUCET_URL)]
+        faucet_url: String,
+        
+        /// Seconds to wait for the funds before giving up
+        #[arg(long, default_value_t = 120)]
+        timeout_secs: u64,
+        
+        /// RPC URL (comma-separated list for failover)
+        #[arg(short, long)]
+        rpc_url: Option<String>,
+    },
+}
+
+/// Commands that send a transaction
+#[derive(Subcommand)]
+enum WriteCommands {
     /// Mint tokens (owner only)
     Mint {
-        /// Contract address
-        #[arg(short, long)]
+        /// Contract address (defaults to the latest recorded MonadToken deployment)
+        #[arg(short, long, default_value = "MonadToken")]
         address: String,
         
         /// Recipient address
@@ -60,53 +166,53 @@ enum Commands {
         #[arg(short, long)]
         amount: u64,
         
-        /// Private key
+        /// Private key (or --account)
         #[arg(short, long)]
-        private_key: String,
+        private_key: Option<String>,
         
-        /// RPC URL
-        #[arg(short, long, default_value = "https://monad-testnet.g.alchemy.com/v2/hl5Gau0XVV37m-RDdhcRzqCh7ISwmOAe")]
-        rpc_url: String,
+        /// RPC URL (comma-separated list for failover)
+        #[arg(short, long)]
+        rpc_url: Option<String>,
     },
     
     /// Public mint tokens
     PublicMint {
-        /// Contract address
-        #[arg(short, long)]
+        /// Contract address (defaults to the latest recorded MonadToken deployment)
+        #[arg(short, long, default_value = "MonadToken")]
         address: String,
         
-        /// Private key
+        /// Private key (or --account)
         #[arg(short, long)]
-        private_key: String,
+        private_key: Option<String>,
         
-        /// RPC URL
-        #[arg(short, long, default_value = "https://monad-testnet.g.alchemy.com/v2/hl5Gau0XVV37m-RDdhcRzqCh7ISwmOAe")]
-        rpc_url: String,
+        /// RPC URL (comma-separated list for failover)
+        #[arg(short, long)]
+        rpc_url: Option<String>,
     },
     
     /// Burn tokens
     Burn {
-        /// Contract address
-        #[arg(short, long)]
+        /// Contract address (defaults to the latest recorded MonadToken deployment)
+        #[arg(short, long, default_value = "MonadToken")]
         address: String,
         
         /// Amount to burn
         #[arg(short, long)]
         amount: u64,
         
-        /// Private key
+        /// Private key (or --account)
         #[arg(short, long)]
-        private_key: String,
+        private_key: Option<String>,
         
-        /// RPC URL
-        #[arg(short, long, default_value = "https://monad-testnet.g.alchemy.com/v2/hl5Gau0XVV37m-RDdhcRzqCh7ISwmOAe")]
-        rpc_url: String,
+        /// RPC URL (comma-separated list for failover)
+        #[arg(short, long)]
+        rpc_url: Option<String>,
     },
     
     /// Transfer tokens
     Transfer {
-        /// Contract address
-        #[arg(short, long)]
+        /// Contract address (defaults to the latest recorded MonadToken deployment)
+        #[arg(short, long, default_value = "MonadToken")]
         address: String,
         
         /// Recipient address
@@ -117,52 +223,145 @@ enum Commands {
         #[arg(short, long)]
         amount: u64,
         
-        /// Private key
+        /// Private key (or --account)
         #[arg(short, long)]
-        private_key: String,
+        private_key: Option<String>,
         
-        /// RPC URL
-        #[arg(short, long, default_value = "https://monad-testnet.g.alchemy.com/v2/hl5Gau0XVV37m-RDdhcRzqCh7ISwmOAe")]
-        rpc_url: String,
+        /// RPC URL (comma-separated list for failover)
+        #[arg(short, long)]
+        rpc_url: Option<String>,
     },
+    
+    /// Send native MON
+    SendNative {
+        /// Recipient address
+        #[arg(short, long)]
+        to: String,
+        
+        /// Amount in MON (e.g. 0.5)
+        #[arg(short, long)]
+        amount: String,
+        
+        /// Private key (or --account)
+        #[arg(short, long)]
+        private_key: Option<String>,
+        
+        /// RPC URL (comma-separated list for failover)
+        #[arg(short, long)]
+        rpc_url: Option<String>,
+    },
+    
+    /// Transfer tokens to every recipient in a CSV file (columns: address, amount).
+    /// Progress is saved in the transaction journal; resume an interrupted airdrop with --resume.
+    Airdrop {
+        /// Token contract address (any ERC-20)
+        #[arg(short, long)]
+        address: String,
+        
+        /// Recipients CSV with `address,amount` header
+        #[arg(long)]
+        csv: String,
+        
+        /// Transfers sent back to back before waiting for receipts
+        #[arg(long, default_value_t = 20)]
+        chunk_size: usize,
+        
+        /// Continue the interrupted airdrop of this file, skipping recipients already paid
+        #[arg(long, conflicts_with = "restart")]
+        resume: bool,
+        
+        /// Abandon the interrupted airdrop of this file and pay every recipient again
+        #[arg(long)]
+        restart: bool,
+        
+        /// Private key (or --account)
+        #[arg(short, long)]
+        private_key: Option<String>,
+        
+        /// RPC URL (comma-separated list for failover)
+        #[arg(short, long)]
+        rpc_url: Option<String>,
+    },
+}
+
+/// One row of an airdrop CSV
+#[derive(Debug, Deserialize)]
+struct AirdropRow {
+    address: String,
+    amount: String,
 }
 
 #[tokio::main]
-async fn main() -> Result<()> {
-    // Initialize logging
-    tracing_subscriber::fmt::init();
+async fn main() -> ExitCode {
+    match run(Cli::parse()).await {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => error::report(&e),
+    }
+}
+
+async fn run(cli: Cli) -> Result<()> {
     
-    let cli = Cli::parse();
+    // Initialize logging
+    let _log_guard = logging::init(&cli.log)?;
+    let _fork = rpc::configure(cli.rpc.clone());
     
     match cli.command {
+        Commands::Write(command) => {
+            run_write_command(command, &cli.tx).await?;
+        }
+        Commands::EstimateGas { command } => {
+            run_write_command(command, &cli.tx.estimate_only()).await?;
+        }
         Commands::Info { address, rpc_url } => {
             get_token_info(address, rpc_url).await?;
         }
         Commands::Balance { address, account, rpc_url } => {
             get_balance(address, account, rpc_url).await?;
         }
-        Commands::Mint { address, to, amount, private_key, rpc_url } => {
-            mint_tokens(address, to, amount, private_key, rpc_url).await?;
+        Commands::NativeBalance { account, rpc_url } => {
+            get_native_balance(account, rpc_url).await?;
         }
-        Commands::PublicMint { address, private_key, rpc_url } => {
-            public_mint(address, private_key, rpc_url).await?;
+        Commands::Permit { address, spender, amount, deadline_secs, submit, private_key, rpc_url } => {
+            sign_permit(address, spender, amount, deadline_secs, submit, private_key, rpc_url, &cli.tx).await?;
         }
-        Commands::Burn { address, amount, private_key, rpc_url } => {
-            burn_tokens(address, amount, private_key, rpc_url).await?;
+        Commands::Faucet { to, private_key, public_mint, token, faucet_url, timeout_secs, rpc_url } => {
+            claim_faucet(to, private_key, public_mint, token, faucet_url, timeout_secs, rpc_url, &cli.tx).await?;
         }
-        Commands::Transfer { address, to, amount, private_key, rpc_url } => {
-            transfer_tokens(address, to, amount, private_key, rpc_url).await?;
+    }
+    
+    Ok(())
+}
+
+async fn run_write_command(command: WriteCommands, tx_args: &TxArgs) -> Result<()> {
+    match command {
+        WriteCommands::Mint { address, to, amount, private_key, rpc_url } => {
+            mint_tokens(address, to, amount, private_key, rpc_url, tx_args).await?;
+        }
+        WriteCommands::PublicMint { address, private_key, rpc_url } => {
+            public_mint(address, private_key, rpc_url, tx_args).await?;
+        }
+        WriteCommands::Burn { address, amount, private_key, rpc_url } => {
+            burn_tokens(address, amount, private_key, rpc_url, tx_args).await?;
+        }
+        WriteCommands::Transfer { address, to, amount, private_key, rpc_url } => {
+            transfer_tokens(address, to, amount, private_key, rpc_url, tx_args).await?;
+        }
+        WriteCommands::SendNative { to, amount, private_key, rpc_url } => {
+            send_native(to, amount, private_key, rpc_url, tx_args).await?;
+        }
+        WriteCommands::Airdrop { address, csv, chunk_size, resume, restart, private_key, rpc_url } => {
+            airdrop(address, csv, chunk_size, resume, restart, private_key, rpc_url, tx_args).await?;
         }
     }
     
     Ok(())
 }
 
-async fn get_token_info(contract_address: String, rpc_url: String) -> Result<()> {
+async fn get_token_info(contract_address: String, rpc_url: Option<String>) -> Result<()> {
     info!("Getting token information for contract: {}", contract_address);
     
-    let provider = Provider::<Http>::try_from(rpc_url)?;
-    let contract_address = contract_address.parse::<Address>()?;
+    let provider = rpc::connect(rpc_url.as_deref()).await?;
+    let contract_address = aliases::resolve(&contract_address)?;
     
     // Load contract ABI
     let contract_abi = load_contract_abi()?;
@@ -185,12 +384,12 @@ async fn get_token_info(contract_address: String, rpc_url: String) -> Result<()>
     Ok(())
 }
 
-async fn get_balance(contract_address: String, account_address: String, rpc_url: String) -> Result<()> {
+async fn get_balance(contract_address: String, account_address: String, rpc_url: Option<String>) -> Result<()> {
     info!("Getting balance for account: {} on contract: {}", account_address, contract_address);
     
-    let provider = Provider::<Http>::try_from(rpc_url)?;
-    let contract_address = contract_address.parse::<Address>()?;
-    let account_address = account_address.parse::<Address>()?;
+    let provider = rpc::connect(rpc_url.as_deref()).await?;
+    let contract_address = aliases::resolve(&contract_address)?;
+    let account_address = aliases::resolve(&account_address)?;
     
     // Load contract ABI
     let contract_abi = load_contract_abi()?;
@@ -209,69 +408,191 @@ async fn get_balance(contract_address: String, account_address: String, rpc_url:
     Ok(())
 }
 
+async fn get_native_balance(account_address: String, rpc_url: Option<String>) -> Result<()> {
+    info!("Getting native balance for account: {}", account_address);
+    
+    let provider = rpc::connect(rpc_url.as_deref()).await?;
+    let account_address = aliases::resolve(&account_address)?;
+    
+    let balance = provider.get_balance(account_address, None).await?;
+    
+    println!("Native Balance:");
+    println!("===============");
+    println!("Account: {:?}", account_address);
+    println!("Balance: {} MON", format_ether(balance));
+    println!("Wei: {}", balance);
+    
+    Ok(())
+}
+
 async fn mint_tokens(
     contract_address: String,
     to_address: String,
     amount: u64,
-    private_key: String,
-    rpc_url: String
+    private_key: Option<String>,
+    rpc_url: Option<String>,
+    tx_args: &TxArgs
 ) -> Result<()> {
     info!("Minting {} tokens to {}", amount, to_address);
     
-    let provider = Provider::<Http>::try_from(rpc_url)?;
-    let wallet = private_key.parse::<LocalWallet>()?;
+    let provider = rpc::connect(rpc_url.as_deref()).await?;
+    let fees = tx_args.fees.resolve(&provider).await?;
+    let wallet = rpc::wallet(&provider, private_key.as_deref()).await?;
     let client = SignerMiddleware::new(provider, wallet);
     
-    let contract_address = contract_address.parse::<Address>()?;
-    let to_address = to_address.parse::<Address>()?;
+    let contract_address = aliases::resolve(&contract_address)?;
+    let to_address = aliases::resolve(&to_address)?;
     
     // Load contract ABI
     let contract_abi = load_contract_abi()?;
     
     // Create contract instance
     let client_arc = Arc::new(client);
-    let contract = Contract::new(contract_address, contract_abi, client_arc);
+    let contract = Contract::new(contract_address, contract_abi, client_arc.clone());
     
     // Call mint function
     let amount_u256 = U256::from(amount);
+    limits::check(Spend::Mint, &BTreeMap::from([(contract_address, amount_u256)]), tx_args)?;
     let args = (to_address, amount_u256);
     let method = contract.method::<_, ()>("mint", args)?;
-    let legacy = method.legacy();
-    let tx = legacy.send().await?;
+    let receipt = tx::send(client_arc.as_ref(), method, &fees, tx_args).await?;
     
-    let receipt = tx.await?;
-    info!("Mint transaction successful!");
     if let Some(receipt) = receipt {
+        tx::ensure_success(client_arc.as_ref(), &receipt).await?;
+        limits::record(Spend::Mint, contract_address, amount_u256)?;
+        info!("Mint transaction successful!");
         info!("Transaction hash: {:?}", receipt.transaction_hash);
     }
     
     Ok(())
 }
 
-async fn public_mint(contract_address: String, private_key: String, rpc_url: String) -> Result<()> {
+async fn public_mint(contract_address: String, private_key: Option<String>, rpc_url: Option<String>, tx_args: &TxArgs) -> Result<()> {
     info!("Performing public mint on contract: {}", contract_address);
     
-    let provider = Provider::<Http>::try_from(rpc_url)?;
-    let wallet = private_key.parse::<LocalWallet>()?;
+    let provider = rpc::connect(rpc_url.as_deref()).await?;
+    let fees = tx_args.fees.resolve(&provider).await?;
+    let wallet = rpc::wallet(&provider, private_key.as_deref()).await?;
     let client = SignerMiddleware::new(provider, wallet);
     
-    let contract_address = contract_address.parse::<Address>()?;
+    let contract_address = aliases::resolve(&contract_address)?;
     
     // Load contract ABI
     let contract_abi = load_contract_abi()?;
     
     // Create contract instance
     let client_arc = Arc::new(client);
-    let contract = Contract::new(contract_address, contract_abi, client_arc);
+    let contract = Contract::new(contract_address, contract_abi, client_arc.clone());
     
     // Call publicMint function
     let method = contract.method::<_, ()>("publicMint", ())?;
-    let legacy = method.legacy();
-    let tx = legacy.send().await?;
+    let receipt = tx::send(client_arc.as_ref(), method, &fees, tx_args).await?;
     
-    let receipt = tx.await?;
-    info!("Public mint transaction successful!");
     if let Some(receipt) = receipt {
+        info!("Public mint transaction successful!");
+        info!("Transaction hash: {:?}", receipt.transaction_hash);
+    }
+    
+    Ok(())
+}
+
+#[allow(clippy::too_many_arguments)]
+async fn claim_faucet(
+    to: Option<String>,
+    private_key: Option<String>,
+    public_mint_after: bool,
+    token: String,
+    faucet_url: String,
+    timeout_secs: u64,
+    rpc_url: Option<String>,
+    tx_args: &TxArgs
+) -> Result<()> {
+    let provider = rpc::connect(rpc_url.as_deref()).await?;
+    
+    // Fund the explicit recipient, or the account behind the private key
+    let address = match &to {
+        Some(to) => aliases::resolve(to)?,
+        None => rpc::wallet(&provider, private_key.as_deref()).await?.address(),
+    };
+    
+    let previous = provider.get_balance(address, None).await?;
+    info!("Requesting testnet MON for {:?} from {}", address, faucet_url);
+    let reply = faucet::request(&faucet_url, address).await?;
+    info!("Faucet replied: {}", reply);
+    
+    // Poll until the faucet's transfer lands
+    let balance = faucet::wait_for_balance(
+        &provider,
+        address,
+        previous,
+        Duration::from_secs(timeout_secs),
+        Duration::from_secs(3),
+    )
+    .await?;
+    
+    println!("Faucet Funds Received:");
+    println!("======================");
+    println!("Address: {:?}", address);
+    println!("Received: {} MON", format_ether(balance - previous));
+    println!("Balance: {} MON", format_ether(balance));
+    
+    if public_mint_after {
+        public_mint(token, private_key, rpc_url, tx_args).await?;
+    }
+    
+    Ok(())
+}
+
+async fn send_native(
+    to_address: String,
+    amount: String,
+    private_key: Option<String>,
+    rpc_url: Option<String>,
+    tx_args: &TxArgs
+) -> Result<()> {
+    info!("Sending {} MON to {}", amount, to_address);
+    
+    let provider = rpc::connect(rpc_url.as_deref()).await?;
+    let fees = tx_args.fees.resolve(&provider).await?;
+    let wallet = rpc::wallet(&provider, private_key.as_deref()).await?;
+    let client = SignerMiddleware::new(provider, wallet);
+    session::check(client.address(), session::Action::Transfer)?;
+    
+    let to_address = aliases::resolve(&to_address)?;
+    let value = parse_ether(&amount).map_err(|e| anyhow!("Invalid MON amount '{}': {}", amount, e))?;
+    
+    // Build the transfer in the envelope --tx-type selects (EIP-1559 by default)
+    let mut tx: TypedTransaction = TransactionRequest::new()
+        .from(client.address())
+        .to(to_address)
+        .value(value)
+        .into();
+    fees.apply(&mut tx);
+    let estimated_gas = client.estimate_gas(&tx, None).await?;
+    
+    if tx_args.estimate_only {
+        tx::print_estimate(estimated_gas, &fees, tx_args).await;
+        return Ok(());
+    }
+    
+    // Refuse up front rather than have the node reject the transaction
+    let balance = client.get_balance(client.address(), None).await?;
+    let max_cost = value + estimated_gas * fees.max_gas_price().unwrap_or_default();
+    if balance < max_cost {
+        return Err(anyhow!(
+            "Insufficient MON: sending {} plus up to {} for gas, but the balance is {}",
+            format_ether(value),
+            format_ether(max_cost - value),
+            format_ether(balance)
+        ));
+    }
+    
+    tx.set_gas(estimated_gas);
+    let receipt = tx::broadcast(&client, tx, tx_args).await?;
+    
+    if let Some(receipt) = receipt {
+        tx::report(estimated_gas, &receipt, tx_args).await;
+        info!("Native transfer successful!");
         info!("Transaction hash: {:?}", receipt.transaction_hash);
     }
     
@@ -281,33 +602,33 @@ async fn public_mint(contract_address: String, private_key: String, rpc_url: Str
 async fn burn_tokens(
     contract_address: String,
     amount: u64,
-    private_key: String,
-    rpc_url: String
+    private_key: Option<String>,
+    rpc_url: Option<String>,
+    tx_args: &TxArgs
 ) -> Result<()> {
     info!("Burning {} tokens", amount);
     
-    let provider = Provider::<Http>::try_from(rpc_url)?;
-    let wallet = private_key.parse::<LocalWallet>()?;
+    let provider = rpc::connect(rpc_url.as_deref()).await?;
+    let fees = tx_args.fees.resolve(&provider).await?;
+    let wallet = rpc::wallet(&provider, private_key.as_deref()).await?;
     let client = SignerMiddleware::new(provider, wallet);
     
-    let contract_address = contract_address.parse::<Address>()?;
+    let contract_address = aliases::resolve(&contract_address)?;
     
     // Load contract ABI
     let contract_abi = load_contract_abi()?;
     
     // Create contract instance
     let client_arc = Arc::new(client);
-    let contract = Contract::new(contract_address, contract_abi, client_arc);
+    let contract = Contract::new(contract_address, contract_abi, client_arc.clone());
     
     // Call burn function
     let amount_u256 = U256::from(amount);
     let method = contract.method::<_, ()>("burn", amount_u256)?;
-    let legacy = method.legacy();
-    let tx = legacy.send().await?;
+    let receipt = tx::send(client_arc.as_ref(), method, &fees, tx_args).await?;
     
-    let receipt = tx.await?;
-    info!("Burn transaction successful!");
     if let Some(receipt) = receipt {
+        info!("Burn transaction successful!");
         info!("Transaction hash: {:?}", receipt.transaction_hash);
     }
     
@@ -318,41 +639,200 @@ async fn transfer_tokens(
     contract_address: String,
     to_address: String,
     amount: u64,
-    private_key: String,
-    rpc_url: String
+    private_key: Option<String>,
+    rpc_url: Option<String>,
+    tx_args: &TxArgs
 ) -> Result<()> {
     info!("Transferring {} tokens to {}", amount, to_address);
     
-    let provider = Provider::<Http>::try_from(rpc_url)?;
-    let wallet = private_key.parse::<LocalWallet>()?;
+    let provider = rpc::connect(rpc_url.as_deref()).await?;
+    let fees = tx_args.fees.resolve(&provider).await?;
+    let wallet = rpc::wallet(&provider, private_key.as_deref()).await?;
     let client = SignerMiddleware::new(provider, wallet);
+    session::check(client.address(), session::Action::Transfer)?;
     
-    let contract_address = contract_address.parse::<Address>()?;
-    let to_address = to_address.parse::<Address>()?;
+    let contract_address = aliases::resolve(&contract_address)?;
+    let to_address = aliases::resolve(&to_address)?;
     
     // Load contract ABI
     let contract_abi = load_contract_abi()?;
     
     // Create contract instance
     let client_arc = Arc::new(client);
-    let contract = Contract::new(contract_address, contract_abi, client_arc);
+    let contract = Contract::new(contract_address, contract_abi, client_arc.clone());
     
     // Call transfer function
     let amount_u256 = U256::from(amount);
     let args = (to_address, amount_u256);
     let method = contract.method::<_, ()>("transfer", args)?;
-    let legacy = method.legacy();
-    let tx = legacy.send().await?;
+    let receipt = tx::send(client_arc.as_ref(), method, &fees, tx_args).await?;
+    
+    if let Some(receipt) = receipt {
+        info!("Transfer transaction successful!");
+        info!("Transaction hash: {:?}", receipt.transaction_hash);
+    }
+    
+    Ok(())
+}
+
+#[allow(clippy::too_many_arguments)]
+async fn sign_permit(
+    contract_address: String,
+    spender: String,
+    amount: u64,
+    deadline_secs: u64,
+    submit: bool,
+    private_key: Option<String>,
+    rpc_url: Option<String>,
+    tx_args: &TxArgs
+) -> Result<()> {
+    let provider = rpc::connect(rpc_url.as_deref()).await?;
+    let fees = tx_args.fees.resolve(&provider).await?;
+    let wallet = rpc::wallet(&provider, private_key.as_deref()).await?;
+    let client = Arc::new(SignerMiddleware::new(provider, wallet.clone()));
+    
+    let contract_address = aliases::resolve(&contract_address)?;
+    let spender = aliases::resolve(&spender)?;
+    // A permit lets the spender pull the tokens as surely as a transfer would
+    session::check(client.address(), session::Action::Transfer)?;
+    
+    let signed = permit::sign(
+        client.clone(),
+        &wallet,
+        contract_address,
+        spender,
+        U256::from(amount),
+        permit::deadline_in(deadline_secs),
+    )
+    .await?;
+    println!("{}", serde_json::to_string_pretty(&signed)?);
+    
+    if !submit {
+        return Ok(());
+    }
+    
+    // Anyone may submit a permit; here the owner pays for it
+    let contract = ERC20::new(contract_address, client.clone());
+    let method = contract.permit(
+        signed.owner,
+        signed.spender,
+        signed.value,
+        signed.deadline,
+        signed.v,
+        signed.r.to_fixed_bytes(),
+        signed.s.to_fixed_bytes(),
+    );
+    let receipt = tx::send(client.as_ref(), method, &fees, tx_args).await?;
     
-    let receipt = tx.await?;
-    info!("Transfer transaction successful!");
     if let Some(receipt) = receipt {
+        info!("Permit submitted successfully!");
         info!("Transaction hash: {:?}", receipt.transaction_hash);
     }
     
     Ok(())
 }
 
+#[allow(clippy::too_many_arguments)]
+async fn airdrop(
+    contract_address: String,
+    csv_path: String,
+    chunk_size: usize,
+    resume: bool,
+    restart: bool,
+    private_key: Option<String>,
+    rpc_url: Option<String>,
+    tx_args: &TxArgs
+) -> Result<()> {
+    // Validate the whole file before sending anything
+    let mut recipients = Vec::new();
+    for (index, row) in csv::Reader::from_path(&csv_path)?.deserialize::<AirdropRow>().enumerate() {
+        let line = index + 2;
+        let row = row.with_context(|| format!("{} line {}", csv_path, line))?;
+        let address = aliases::resolve(&row.address).with_context(|| format!("{} line {}", csv_path, line))?;
+        let amount = U256::from_dec_str(row.amount.trim())
+            .map_err(|e| anyhow!("{} line {}: invalid amount '{}': {}", csv_path, line, row.amount, e))?;
+        recipients.push((address, amount));
+    }
+    
+    let provider = rpc::connect(rpc_url.as_deref()).await?;
+    let fees = tx_args.fees.resolve(&provider).await?;
+    let wallet = rpc::wallet(&provider, private_key.as_deref()).await?;
+    let client = SignerMiddleware::new(provider, wallet);
+    session::check(client.address(), session::Action::Transfer)?;
+    
+    let contract_address = aliases::resolve(&contract_address)?;
+    
+    // Create contract instance
+    let client_arc = Arc::new(client);
+    let contract = ERC20::new(contract_address, client_arc.clone());
+    
+    // A run per file and token, so the same file airdropping another token starts afresh
+    let source = format!("{} ({:?})", csv_path, contract_address);
+    let mut run = if tx_args.estimate_only {
+        None
+    } else {
+        Some(journal::start_run("airdrop", &source, recipients.len(), resume, restart)?)
+    };
+    
+    let pending: Vec<usize> = (0..recipients.len()).filter(|i| !run.as_ref().is_some_and(|run| run.is_done(*i))).collect();
+    info!(
+        "Airdropping to {} recipients ({} already done)",
+        pending.len(),
+        recipients.len() - pending.len()
+    );
+    
+    let total = pending.iter().fold(U256::zero(), |total, i| total + recipients[*i].1);
+    let balance = contract.balance_of(client_arc.address()).call().await?;
+    if balance < total {
+        return Err(anyhow!("Airdrop needs {} tokens but the sender holds {}", total, balance));
+    }
+    
+    let mut failures: Vec<(usize, String)> = Vec::new();
+    for chunk in pending.chunks(chunk_size.max(1)) {
+        let mut calls = Vec::with_capacity(chunk.len());
+        for i in chunk {
+            let (recipient, amount) = recipients[*i];
+            calls.push((run.as_ref().map(|run| run.key(*i)), contract.transfer(recipient, amount)));
+        }
+        
+        let results = tx::send_keyed_batch(client_arc.as_ref(), calls, &fees, tx_args).await?;
+        for (i, result) in chunk.iter().zip(results) {
+            match result {
+                Ok(Some(receipt)) if receipt.status == Some(1.into()) => {
+                    if let Some(run) = run.as_mut() {
+                        run.step_done(*i, receipt.transaction_hash);
+                    }
+                }
+                Ok(Some(receipt)) => failures.push((*i, format!("reverted in {:?}", receipt.transaction_hash))),
+                Ok(None) if tx_args.estimate_only => {}
+                Ok(None) => failures.push((*i, "dropped from the mempool".to_string())),
+                Err(e) => failures.push((*i, e.to_string())),
+            }
+        }
+    }
+    
+    let Some(mut run) = run else {
+        return Ok(());
+    };
+    run.finish()?;
+    
+    println!("Airdrop Report");
+    println!("==============");
+    println!("Sent: {} / {}", run.completed.len(), recipients.len());
+    println!("Failed: {}", failures.len());
+    for (i, error) in &failures {
+        let (address, amount) = recipients[*i];
+        println!("  line {}: {:?} {}: {}", i + 2, address, amount, error);
+    }
+    if failures.is_empty() {
+        info!("Airdrop completed successfully!");
+    } else {
+        println!("\nRun the same command with --resume to retry the failed transfers.");
+    }
+    
+    Ok(())
+}
+
 fn load_contract_abi() -> Result<Abi> {
     // In a real implementation, you would load the compiled ABI
     // For now, we'll return a placeholder
@@ -0,0 +1,119 @@
+//! Typed bindings for the contracts the binaries call, generated with `abigen!`.
+//!
+//! The ABIs are spelled out in human-readable form rather than read from `out/`, so the
+//! crate builds from a clean checkout. Keep them in sync with `contracts/` when the
+//! contracts' external interface changes.
+
+use ethers::contract::abigen;
+
+abigen!(
+    OrderBookDEX,
+    r#"[
+        struct SignedOrder { address maker; address baseToken; address quoteToken; uint256 amount; uint256 price; bool isBuy; uint256 nonce; uint256 expiry; bytes signature; }
+        struct Settlement { SignedOrder buy; SignedOrder sell; uint256 amount; uint256 price; }
+        struct BookEntry { uint256 id; uint256 price; uint256 amount; bool isBuy; }
+        function initialize(address initialOwner)
+        function owner() view returns (address)
+        function transferOwnership(address newOwner)
+        function paused() view returns (bool)
+        function pause()
+        function unpause()
+        function addTradingPair(address baseToken, address quoteToken, uint256 minOrderSize, uint256 pricePrecision)
+        function setTradingPairActive(address baseToken, address quoteToken, bool isActive)
+        function updateTradingPair(address baseToken, address quoteToken, uint256 minOrderSize, uint256 pricePrecision)
+        function setTradingFee(uint256 newFee)
+        function tradingPairs(address baseToken, address quoteToken) view returns (address baseToken, address quoteToken, bool isActive, uint256 minOrderSize, uint256 pricePrecision)
+        function isTradingPairActive(address baseToken, address quoteToken) view returns (bool)
+        function TRADING_FEE() view returns (uint256)
+        function MAX_TRADING_FEE() view returns (uint256)
+        function FEE_DENOMINATOR() view returns (uint256)
+        function NATIVE_TOKEN() view returns (address)
+        function placeLimitOrder(address baseToken, address quoteToken, uint256 amount, uint256 price, bool isBuy) payable returns (uint256 orderId)
+        function placeLimitOrderWithPermit(address baseToken, address quoteToken, uint256 amount, uint256 price, bool isBuy, uint256 permitValue, uint256 deadline, uint8 v, bytes32 r, bytes32 s) returns (uint256 orderId)
+        function placeMarketOrder(address baseToken, address quoteToken, uint256 amount, bool isBuy) payable
+        function cancelOrder(uint256 orderId)
+        function withdraw(address token, uint256 amount)
+        function orders(uint256 orderId) view returns (uint256 id, address trader, address baseToken, address quoteToken, uint256 amount, uint256 price, bool isBuy, bool isActive, uint256 timestamp)
+        function getOrderBook(address baseToken, address quoteToken) view returns (uint256[] buyPrices, uint256[] buyAmounts, uint256[] sellPrices, uint256[] sellAmounts)
+        function getOrderBookPage(address baseToken, address quoteToken, uint256 startId, uint256 maxScan, uint256 maxOrders, uint256 minPrice, uint256 maxPrice) view returns (BookEntry[] entries, uint256 nextId)
+        function getUserOrders(address user) view returns (uint256[])
+        function getUserBalance(address user, address token) view returns (uint256)
+        function eip712Domain() view returns (bytes1 fields, string name, string version, uint256 chainId, address verifyingContract, bytes32 salt, uint256[] extensions)
+        function ORDER_TYPEHASH() view returns (bytes32)
+        function hashOrder(address maker, address baseToken, address quoteToken, uint256 amount, uint256 price, bool isBuy, uint256 nonce, uint256 expiry) view returns (bytes32)
+        function signedOrderFilled(bytes32 orderHash) view returns (uint256)
+        function settleSignedOrders(Settlement[] settlements)
+        function cancelSignedOrder(SignedOrder order)
+        event OrderPlaced(uint256 indexed orderId, address indexed trader, address baseToken, address quoteToken, uint256 amount, uint256 price, bool isBuy)
+        event OrderMatched(uint256 indexed buyOrderId, uint256 indexed sellOrderId, address baseToken, address quoteToken, uint256 amount, uint256 price)
+        event OrderCancelled(uint256 indexed orderId, address indexed trader)
+        event TradingPairAdded(address indexed baseToken, address indexed quoteToken, uint256 minOrderSize)
+        event TradingPairUpdated(address indexed baseToken, address indexed quoteToken, bool isActive, uint256 minOrderSize, uint256 pricePrecision)
+        event TradingFeeUpdated(uint256 oldFee, uint256 newFee)
+        event SignedOrderSettled(bytes32 indexed buyHash, bytes32 indexed sellHash, address baseToken, address quoteToken, uint256 amount, uint256 price)
+        event SignedOrderCancelled(bytes32 indexed orderHash, address indexed maker)
+        event LiquidityAdded(address indexed provider, address baseToken, address quoteToken, uint256 baseAmount, uint256 quoteAmount)
+        event LiquidityRemoved(address indexed provider, address baseToken, address quoteToken, uint256 baseAmount, uint256 quoteAmount)
+        event OwnershipTransferred(address indexed previousOwner, address indexed newOwner)
+        event Paused(address account)
+        event Unpaused(address account)
+    ]"#,
+    methods {
+        eip712Domain() as eip712_domain;
+    }
+);
+
+abigen!(
+    ERC20,
+    r#"[
+        function name() view returns (string)
+        function symbol() view returns (string)
+        function decimals() view returns (uint8)
+        function totalSupply() view returns (uint256)
+        function balanceOf(address account) view returns (uint256)
+        function allowance(address owner, address spender) view returns (uint256)
+        function approve(address spender, uint256 amount) returns (bool)
+        function transfer(address to, uint256 amount) returns (bool)
+        function transferFrom(address from, address to, uint256 amount) returns (bool)
+        function nonces(address owner) view returns (uint256)
+        function DOMAIN_SEPARATOR() view returns (bytes32)
+        function permit(address owner, address spender, uint256 value, uint256 deadline, uint8 v, bytes32 r, bytes32 s)
+        event Transfer(address indexed from, address indexed to, uint256 value)
+        event Approval(address indexed owner, address indexed spender, uint256 value)
+    ]"#
+);
+
+abigen!(
+    WMON,
+    r#"[
+        function deposit() payable
+        function withdraw(uint256 wad)
+        function balanceOf(address account) view returns (uint256)
+        function totalSupply() view returns (uint256)
+        event Deposit(address indexed dst, uint256 wad)
+        event Withdrawal(address indexed src, uint256 wad)
+    ]"#
+);
+
+abigen!(
+    GnosisSafe,
+    r#"[
+        function nonce() view returns (uint256)
+        function getThreshold() view returns (uint256)
+        function getOwners() view returns (address[])
+        function isOwner(address owner) view returns (bool)
+        function getTransactionHash(address to, uint256 value, bytes data, uint8 operation, uint256 safeTxGas, uint256 baseGas, uint256 gasPrice, address gasToken, address refundReceiver, uint256 _nonce) view returns (bytes32)
+        function execTransaction(address to, uint256 value, bytes data, uint8 operation, uint256 safeTxGas, uint256 baseGas, uint256 gasPrice, address gasToken, address refundReceiver, bytes signatures) payable returns (bool success)
+        event ExecutionSuccess(bytes32 txHash, uint256 payment)
+        event ExecutionFailure(bytes32 txHash, uint256 payment)
+    ]"#
+);
+
+abigen!(
+    ChainlinkAggregator,
+    r#"[
+        function decimals() view returns (uint8)
+        function description() view returns (string)
+        function latestRoundData() view returns (uint80 roundId, int256 answer, uint256 startedAt, uint256 updatedAt, uint80 answeredInRound)
+    ]"#
+);
@@ -0,0 +1,637 @@
+use crate::bindings::OrderBookDEX;
+use crate::breaker::{self, CircuitBreaker, Halt};
+use crate::error::{DexError, Result};
+use crate::events;
+use crate::fees::Fees;
+use crate::marketdata::SharedBook;
+use crate::mempool::MempoolWatcher;
+use crate::metrics::metrics;
+use crate::notify::{Alert, Notifications};
+use crate::paper::{PairMarket, PaperAccount};
+use crate::reload::ConfigWatcher;
+use crate::rpc::RpcProvider;
+use crate::shutdown;
+use crate::strategy::{to_f64, BookView, Fill, Intent, OpenOrder, PendingActivity, Strategy};
+use crate::submit::{PipelineConfig, SubmissionPipeline};
+use crate::tx::{self, TxArgs};
+use crate::wallets::AccountSigner;
+use anyhow::anyhow;
+use async_trait::async_trait;
+use ethers::{
+    middleware::SignerMiddleware,
+    providers::Middleware,
+    types::{Address, U256},
+};
+use std::collections::BTreeMap;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::time::{sleep, Instant};
+use tracing::{field, info, info_span, warn, Instrument, Span};
+
+/// Signing client used by the live bot
+pub type SignerClient = SignerMiddleware<RpcProvider, AccountSigner>;
+
+/// Where a strategy's intents are carried out
+#[async_trait]
+pub trait Executor: Send + Sync {
+    /// Fetch the latest book for the bot's pair
+    async fn book(&mut self) -> Result<BookView>;
+
+    /// Fills of the bot's orders since the last call
+    async fn poll_fills(&mut self) -> Result<Vec<Fill>>;
+
+    /// The bot's resting orders
+    async fn open_orders(&mut self) -> Result<Vec<OpenOrder>>;
+
+    /// Other traders' transactions for the bot's pair pending since the last call
+    async fn poll_pending(&mut self) -> Result<Vec<PendingActivity>> {
+        Ok(Vec::new())
+    }
+
+    /// Place a limit order and return its ID
+    async fn place(&mut self, price: U256, amount: U256, is_buy: bool) -> Result<u64>;
+
+    async fn cancel(&mut self, order_id: u64) -> Result<()>;
+
+    /// Carry out one iteration's intents, returning the new order ID for each placement.
+    /// Results line up with `intents`. By default each intent runs to completion before the next.
+    async fn execute(&mut self, intents: &[Intent]) -> Vec<Result<Option<u64>>> {
+        execute_sequentially(self, intents).await
+    }
+}
+
+async fn execute_sequentially<E: Executor + ?Sized>(executor: &mut E, intents: &[Intent]) -> Vec<Result<Option<u64>>> {
+    let mut results = Vec::with_capacity(intents.len());
+    for intent in intents {
+        results.push(match intent {
+            Intent::Place { price, amount, is_buy } => executor.place(*price, *amount, *is_buy).await.map(Some),
+            Intent::Cancel { order_id } => executor.cancel(*order_id).await.map(|_| None),
+        });
+    }
+    results
+}
+
+/// Lets executor wrappers stack over a boxed executor chosen at runtime
+#[async_trait]
+impl<E: Executor + ?Sized> Executor for Box<E> {
+    async fn book(&mut self) -> Result<BookView> {
+        (**self).book().await
+    }
+
+    async fn poll_fills(&mut self) -> Result<Vec<Fill>> {
+        (**self).poll_fills().await
+    }
+
+    async fn open_orders(&mut self) -> Result<Vec<OpenOrder>> {
+        (**self).open_orders().await
+    }
+
+    async fn poll_pending(&mut self) -> Result<Vec<PendingActivity>> {
+        (**self).poll_pending().await
+    }
+
+    async fn place(&mut self, price: U256, amount: U256, is_buy: bool) -> Result<u64> {
+        (**self).place(price, amount, is_buy).await
+    }
+
+    async fn cancel(&mut self, order_id: u64) -> Result<()> {
+        (**self).cancel(order_id).await
+    }
+
+    async fn execute(&mut self, intents: &[Intent]) -> Vec<Result<Option<u64>>> {
+        (**self).execute(intents).await
+    }
+}
+
+/// Run `strategy` until SIGINT or SIGTERM, polling the book every `interval`.
+///
+/// Fills and failures are sent to `notifications`, plus a heartbeat every
+/// `heartbeat` so a silent bot can be told apart from a dead one. The bot stands down
+/// while a [`Halt`] is in force, whether `breaker` tripped it or the `halt` command did.
+/// With a `watcher`, edits to the strategy's parameters apply between iterations.
+///
+/// A shutdown lets the current iteration finish, then cancels the bot's resting orders
+/// when `cancel_on_shutdown` is set.
+#[allow(clippy::too_many_arguments)]
+pub async fn run(
+    strategy: &mut dyn Strategy,
+    executor: &mut dyn Executor,
+    interval: Duration,
+    notifications: &Notifications,
+    heartbeat: Option<Duration>,
+    breaker: &mut CircuitBreaker,
+    mut watcher: Option<&mut ConfigWatcher>,
+    cancel_on_shutdown: bool,
+) -> Result<()> {
+    info!("Starting {} bot, polling every {:?}", strategy.name(), interval);
+    let source = format!("{} bot", strategy.name());
+    let mut last_heartbeat = Instant::now();
+    let mut fills_since_heartbeat = 0;
+    let mut halted = false;
+    let mut last_timer = None;
+    let mut iterations = 0;
+    let mut fills = 0;
+
+    while !shutdown::requested() {
+        match Halt::load() {
+            Ok(Some(halt)) => {
+                if !halted {
+                    warn!("Trading halted: {}", halt.reason);
+                    notifications.send(&Alert::new(&source, format!("Halted: {}", halt.reason))).await;
+                    if halt.cancel_orders {
+                        for error in breaker::cancel_all(executor).await {
+                            warn!("{}", error);
+                            notifications.send(&Alert::new(&source, error)).await;
+                        }
+                    }
+                    halted = true;
+                }
+            }
+            Ok(None) if halted => {
+                info!("Halt lifted, resuming");
+                notifications.send(&Alert::new(&source, "Resumed")).await;
+                breaker.reset();
+                halted = false;
+            }
+            Ok(None) => {}
+            Err(e) => warn!("Couldn't read the halt state: {}", e),
+        }
+
+        if let Some(watcher) = watcher.as_deref_mut() {
+            match watcher.apply(strategy) {
+                Ok(Some(change)) => {
+                    info!("{}", change);
+                    notifications.send(&Alert::new(&source, change)).await;
+                }
+                Ok(None) => {}
+                Err(e) => {
+                    warn!("Config change rejected: {}", e);
+                    notifications.send(&Alert::new(&source, format!("Config change rejected: {}", e))).await;
+                }
+            }
+        }
+
+        let result = if halted { None } else { Some(step(strategy, executor, &mut last_timer).await) };
+        iterations += usize::from(result.is_some());
+        let trip = match &result {
+            Some(Ok(outcome)) => breaker.observe(Some(&outcome.book), &outcome.fills, !outcome.errors.is_empty()).await,
+            Some(Err(_)) => breaker.observe(None, &[], true).await,
+            None => None,
+        };
+        match result {
+            None => {}
+            Some(Ok(outcome)) => {
+                for fill in &outcome.fills {
+                    let message = format!(
+                        "Order {} filled: {} {} at price {}",
+                        fill.order_id, if fill.is_buy { "BUY" } else { "SELL" }, fill.amount, fill.price
+                    );
+                    notifications.send(&Alert::new(&source, message)).await;
+                }
+                fills_since_heartbeat += outcome.fills.len();
+                fills += outcome.fills.len();
+                for error in &outcome.errors {
+                    notifications.send(&Alert::new(&source, error.clone())).await;
+                }
+            }
+            Some(Err(e)) => {
+                warn!("Bot iteration failed: {}", e);
+                notifications.send(&Alert::new(&source, format!("Iteration failed: {}", e))).await;
+            }
+        }
+        if let Some(reason) = trip {
+            warn!("Circuit breaker tripped: {}", reason);
+            match breaker.trip(reason) {
+                // Stand down straight away rather than after another interval
+                Ok(_) => continue,
+                Err(e) => warn!("Couldn't record the halt: {}", e),
+            }
+        }
+
+        if let Some(heartbeat) = heartbeat {
+            if last_heartbeat.elapsed() >= heartbeat {
+                let open_orders = executor.open_orders().await.map(|orders| orders.len()).unwrap_or_default();
+                let message = format!("Alive: {} open orders, {} fills since last heartbeat", open_orders, fills_since_heartbeat);
+                notifications.send(&Alert::new(&source, message)).await;
+                last_heartbeat = Instant::now();
+                fills_since_heartbeat = 0;
+            }
+        }
+
+        tokio::select! {
+            _ = sleep(interval) => {}
+            _ = shutdown::wait() => {}
+        }
+    }
+
+    info!("Stopping {} after {} iterations and {} fills", source, iterations, fills);
+    if cancel_on_shutdown {
+        let errors = breaker::cancel_all(executor).await;
+        for error in &errors {
+            warn!("{}", error);
+            notifications.send(&Alert::new(&source, error.clone())).await;
+        }
+        info!("Cancelled resting orders on shutdown with {} failures", errors.len());
+    }
+    notifications.send(&Alert::new(&source, format!("Stopped after {} iterations and {} fills", iterations, fills))).await;
+    Ok(())
+}
+
+/// What happened in one bot iteration
+#[derive(Debug, Default)]
+struct StepOutcome {
+    /// The book the strategy traded on
+    book: BookView,
+    fills: Vec<Fill>,
+    /// Intents that failed to execute
+    errors: Vec<String>,
+}
+
+async fn step(strategy: &mut dyn Strategy, executor: &mut dyn Executor, last_timer: &mut Option<Instant>) -> Result<StepOutcome> {
+    let book = executor.book().await?;
+    let mut outcome = StepOutcome::default();
+
+    let mut intents = Vec::new();
+    for fill in executor.poll_fills().await? {
+        info!(
+            "Order {} filled: {} {} at price {}",
+            fill.order_id, if fill.is_buy { "BUY" } else { "SELL" }, fill.amount, fill.price
+        );
+        let amount = to_f64(fill.amount);
+        metrics().fills.inc();
+        metrics()
+            .bot_inventory
+            .with_label_values(&[strategy.name()])
+            .add(if fill.is_buy { amount } else { -amount });
+        intents.extend(strategy.on_fill(&fill));
+        outcome.fills.push(fill);
+    }
+
+    let open_orders = executor.open_orders().await?;
+    let pending = executor.poll_pending().await?;
+    for activity in &pending {
+        info!("Pending {:?} from {:?} in {:?}", activity.action, activity.from, activity.tx_hash);
+    }
+    intents.extend(strategy.on_pending(&pending, &open_orders));
+    if let Some(every) = strategy.timer_interval() {
+        if last_timer.is_none_or(|last: Instant| last.elapsed() >= every) {
+            *last_timer = Some(Instant::now());
+            intents.extend(strategy.on_timer(&open_orders));
+        }
+    }
+    intents.extend(strategy.on_book_update(&book, &open_orders));
+
+    let results = executor.execute(&intents).await;
+    for (intent, result) in intents.iter().zip(results) {
+        let result = result.map(|id| match intent {
+            Intent::Place { price, amount, is_buy } => {
+                metrics().orders_placed.inc();
+                info!("Placed order {}: {} {} at price {}", id.unwrap_or_default(), if *is_buy { "BUY" } else { "SELL" }, amount, price)
+            }
+            Intent::Cancel { order_id } => {
+                metrics().orders_cancelled.inc();
+                info!("Cancelled order {}", order_id)
+            }
+        });
+        if let Err(e) = result {
+            warn!("{:?} failed: {}", intent, e);
+            outcome.errors.push(format!("{:?} failed: {}", intent, e));
+        }
+    }
+    outcome.book = book;
+    Ok(outcome)
+}
+
+/// Executes intents as DEX transactions
+pub struct LiveExecutor {
+    client: Arc<SignerClient>,
+    contract: OrderBookDEX<SignerClient>,
+    base_token: Address,
+    quote_token: Address,
+    tx_args: TxArgs,
+    fees: Option<Fees>,
+    orders: BTreeMap<u64, OpenOrder>,
+    /// One `order` span per resting order, open from the intent until it is filled or
+    /// cancelled, so a trace shows the whole lifecycle
+    spans: BTreeMap<u64, Span>,
+    pipeline: Option<SubmissionPipeline>,
+    mempool: Option<MempoolWatcher<SignerClient>>,
+    shared_book: Option<SharedBook>,
+    /// Whether the last book came from the chain although there is a shared book
+    shared_stale: bool,
+}
+
+fn order_span(price: U256, amount: U256, is_buy: bool) -> Span {
+    info_span!(
+        "order",
+        side = if is_buy { "buy" } else { "sell" },
+        price = %price,
+        amount = %amount,
+        order_id = field::Empty
+    )
+}
+
+impl LiveExecutor {
+    pub fn new(
+        client: Arc<SignerClient>,
+        dex: Address,
+        base_token: Address,
+        quote_token: Address,
+        tx_args: TxArgs,
+    ) -> Self {
+        Self {
+            contract: OrderBookDEX::new(dex, client.clone()),
+            client,
+            base_token,
+            quote_token,
+            tx_args,
+            fees: None,
+            orders: BTreeMap::new(),
+            spans: BTreeMap::new(),
+            pipeline: None,
+            mempool: None,
+            shared_book: None,
+            shared_stale: false,
+        }
+    }
+
+    /// Send each iteration's intents through a [`SubmissionPipeline`] instead of one at a time
+    pub fn with_pipeline(mut self, config: PipelineConfig) -> Self {
+        self.pipeline = Some(SubmissionPipeline::new(self.client.clone(), config));
+        self
+    }
+
+    /// Report other traders' pending transactions for the pair to the strategy
+    pub async fn with_mempool(mut self) -> Result<Self> {
+        let pair = Some((self.base_token, self.quote_token));
+        let watcher = MempoolWatcher::new(self.contract.clone(), pair, Some(self.client.address())).await?;
+        self.mempool = Some(watcher);
+        Ok(self)
+    }
+
+    /// Read the book from a daemon's Redis cache, falling back to the chain while it is stale
+    pub fn with_shared_book(mut self, shared_book: SharedBook) -> Self {
+        self.shared_book = Some(shared_book);
+        self
+    }
+}
+
+#[async_trait]
+impl Executor for LiveExecutor {
+    async fn book(&mut self) -> Result<BookView> {
+        // Refresh fees once per iteration rather than per transaction
+        self.fees = Some(self.tx_args.fees.resolve(self.client.inner()).await?);
+
+        if let Some(shared_book) = &self.shared_book {
+            match shared_book.book(self.contract.address(), self.base_token, self.quote_token).await {
+                Ok(Some(book)) => {
+                    if self.shared_stale {
+                        info!("Shared book is current again");
+                        self.shared_stale = false;
+                    }
+                    return Ok(book);
+                }
+                Ok(None) if !self.shared_stale => warn!("Shared book is stale or lacks the pair; reading the chain"),
+                Err(e) if !self.shared_stale => warn!("Shared book unavailable ({}); reading the chain", e),
+                _ => {}
+            }
+            self.shared_stale = true;
+        }
+
+        let (buy_prices, buy_amounts, sell_prices, sell_amounts) = self
+            .contract
+            .get_order_book(self.base_token, self.quote_token)
+            .call()
+            .await?;
+        let mut book = BookView::from_levels(&buy_prices, &buy_amounts, &sell_prices, &sell_amounts);
+        book.block = Some(self.client.get_block_number().await.map_err(|e| DexError::middleware(&e))?.as_u64());
+        Ok(book)
+    }
+
+    async fn poll_fills(&mut self) -> Result<Vec<Fill>> {
+        let mut fills = Vec::new();
+        let ids: Vec<u64> = self.orders.keys().copied().collect();
+        for id in ids {
+            let (_, _, _, _, amount, _, _, is_active, _) = self.contract.orders(U256::from(id)).call().await?;
+            let tracked = self.orders.get_mut(&id).expect("tracked order");
+
+            if amount < tracked.amount {
+                let filled = tracked.amount - amount;
+                if let Some(span) = self.spans.get(&id) {
+                    span.in_scope(|| info!(amount = %filled, remaining = %amount, "filled"));
+                }
+                fills.push(Fill {
+                    order_id: id,
+                    is_buy: tracked.is_buy,
+                    amount: filled,
+                    price: tracked.price,
+                });
+                tracked.amount = amount;
+            }
+            if !is_active {
+                self.orders.remove(&id);
+                self.spans.remove(&id);
+            }
+        }
+        Ok(fills)
+    }
+
+    async fn open_orders(&mut self) -> Result<Vec<OpenOrder>> {
+        Ok(self.orders.values().cloned().collect())
+    }
+
+    async fn poll_pending(&mut self) -> Result<Vec<PendingActivity>> {
+        match &mut self.mempool {
+            Some(mempool) => mempool.poll().await,
+            None => Ok(Vec::new()),
+        }
+    }
+
+    async fn place(&mut self, price: U256, amount: U256, is_buy: bool) -> Result<u64> {
+        let fees = self.fees.ok_or_else(|| anyhow!("Fees not resolved yet"))?;
+        let method = self
+            .contract
+            .place_limit_order(self.base_token, self.quote_token, amount, price, is_buy);
+        let span = order_span(price, amount, is_buy);
+        let receipt = tx::send(self.client.as_ref(), method, &fees, &self.tx_args)
+            .instrument(span.clone())
+            .await?
+            .ok_or_else(|| anyhow!("Order transaction was dropped"))?;
+
+        let id = events::placed_order_id(&receipt)
+            .ok_or_else(|| anyhow!("No OrderPlaced event in {:?}", receipt.transaction_hash))?
+            .low_u64();
+        span.record("order_id", id);
+        self.orders.insert(id, OpenOrder { id, price, amount, is_buy });
+        self.spans.insert(id, span);
+        Ok(id)
+    }
+
+    async fn cancel(&mut self, order_id: u64) -> Result<()> {
+        let fees = self.fees.ok_or_else(|| anyhow!("Fees not resolved yet"))?;
+        let method = self.contract.cancel_order(U256::from(order_id));
+        let span = self.spans.get(&order_id).cloned().unwrap_or_else(Span::none);
+        tx::send(self.client.as_ref(), method, &fees, &self.tx_args)
+            .instrument(info_span!(parent: &span, "cancel"))
+            .await?;
+        self.orders.remove(&order_id);
+        self.spans.remove(&order_id);
+        Ok(())
+    }
+
+    async fn execute(&mut self, intents: &[Intent]) -> Vec<Result<Option<u64>>> {
+        let Some(pipeline) = self.pipeline.as_mut() else {
+            return execute_sequentially(self, intents).await;
+        };
+        let Some(fees) = self.fees else {
+            return intents.iter().map(|_| Err(anyhow!("Fees not resolved yet").into())).collect();
+        };
+
+        // Backpressure: intents past the queue limit fail now and are re-issued next iteration
+        let max_queue = pipeline.max_queue();
+        let (accepted, overflow) = intents.split_at(intents.len().min(max_queue));
+        let txs = accepted
+            .iter()
+            .map(|intent| match intent {
+                Intent::Place { price, amount, is_buy } => {
+                    self.contract
+                        .place_limit_order(self.base_token, self.quote_token, *amount, *price, *is_buy)
+                        .tx
+                }
+                Intent::Cancel { order_id } => self.contract.cancel_order(U256::from(*order_id)).tx,
+            })
+            .collect();
+        let spans: Vec<Option<Span>> = accepted
+            .iter()
+            .map(|intent| match intent {
+                Intent::Place { price, amount, is_buy } => Some(order_span(*price, *amount, *is_buy)),
+                Intent::Cancel { .. } => None,
+            })
+            .collect();
+        let receipts = pipeline
+            .submit(txs, &fees, &self.tx_args)
+            .instrument(info_span!("submit", intents = accepted.len()))
+            .await;
+
+        let mut results: Vec<Result<Option<u64>>> = accepted
+            .iter()
+            .zip(receipts.into_iter().zip(spans))
+            .map(|(intent, (receipt, span))| -> Result<Option<u64>> {
+                let receipt = receipt?;
+                match intent {
+                    Intent::Place { price, amount, is_buy } => {
+                        let id = events::placed_order_id(&receipt)
+                            .ok_or_else(|| anyhow!("No OrderPlaced event in {:?}", receipt.transaction_hash))?
+                            .low_u64();
+                        self.orders.insert(id, OpenOrder { id, price: *price, amount: *amount, is_buy: *is_buy });
+                        if let Some(span) = span {
+                            span.record("order_id", id);
+                            self.spans.insert(id, span);
+                        }
+                        Ok(Some(id))
+                    }
+                    Intent::Cancel { order_id } => {
+                        self.orders.remove(order_id);
+                        self.spans.remove(order_id);
+                        Ok(None)
+                    }
+                }
+            })
+            .collect();
+        results.extend(overflow.iter().map(|_| Err(anyhow!("Submission queue full ({} intents per iteration)", max_queue).into())));
+        results
+    }
+}
+
+/// Executes intents on the local paper account against the live book
+pub struct PaperExecutor<M> {
+    contract: OrderBookDEX<M>,
+    base_token: Address,
+    quote_token: Address,
+    market: Option<PairMarket>,
+    pending_fills: Vec<Fill>,
+}
+
+impl<M: Middleware + 'static> PaperExecutor<M> {
+    pub fn new(contract: OrderBookDEX<M>, base_token: Address, quote_token: Address) -> Self {
+        Self {
+            contract,
+            base_token,
+            quote_token,
+            market: None,
+            pending_fills: Vec::new(),
+        }
+    }
+
+    fn market(&self) -> Result<&PairMarket> {
+        self.market.as_ref().ok_or_else(|| anyhow!("Book not fetched yet").into())
+    }
+}
+
+#[async_trait]
+impl<M: Middleware + 'static> Executor for PaperExecutor<M> {
+    async fn book(&mut self) -> Result<BookView> {
+        let market = PairMarket::fetch(&self.contract, self.base_token, self.quote_token).await?;
+        let book = BookView::from_levels(
+            &market.buy_prices,
+            &market.buy_amounts,
+            &market.sell_prices,
+            &market.sell_amounts,
+        );
+        self.market = Some(market);
+        Ok(book)
+    }
+
+    async fn poll_fills(&mut self) -> Result<Vec<Fill>> {
+        let market = self.market()?.clone();
+        let mut account = PaperAccount::load()?;
+        let settled = account.settle(&market);
+        account.save()?;
+
+        let mut fills = std::mem::take(&mut self.pending_fills);
+        fills.extend(settled.into_iter().filter_map(|fill| {
+            Some(Fill {
+                order_id: fill.order_id?,
+                is_buy: fill.is_buy,
+                amount: fill.amount,
+                price: fill.price,
+            })
+        }));
+        Ok(fills)
+    }
+
+    async fn open_orders(&mut self) -> Result<Vec<OpenOrder>> {
+        Ok(PaperAccount::load()?
+            .orders
+            .iter()
+            .filter(|order| order.base_token == self.base_token && order.quote_token == self.quote_token)
+            .map(|order| OpenOrder {
+                id: order.id,
+                price: order.price,
+                amount: order.amount,
+                is_buy: order.is_buy,
+            })
+            .collect())
+    }
+
+    async fn place(&mut self, price: U256, amount: U256, is_buy: bool) -> Result<u64> {
+        let market = self.market()?.clone();
+        let mut account = PaperAccount::load()?;
+        let (id, fills) = account.place_limit(&market, amount, price, is_buy)?;
+        account.save()?;
+
+        self.pending_fills.extend(fills.into_iter().map(|fill| Fill {
+            order_id: id,
+            is_buy: fill.is_buy,
+            amount: fill.amount,
+            price: fill.price,
+        }));
+        Ok(id)
+    }
+
+    async fn cancel(&mut self, order_id: u64) -> Result<()> {
+        let mut account = PaperAccount::load()?;
+        account.cancel(order_id)?;
+        account.save()
+    }
+}
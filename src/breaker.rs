@@ -0,0 +1,218 @@
+use crate::bot::Executor;
+use crate::error::{DexError, Result};
+use crate::expiry;
+use crate::indexer::EventStore;
+use crate::portfolio::PositionTracker;
+use crate::price::PriceSource;
+use crate::strategy::{to_f64, BookView, Fill, Intent};
+use anyhow::Context;
+use ethers::types::{Address, U256};
+use serde::{Deserialize, Serialize};
+use std::env;
+use std::fs;
+use std::path::{self, PathBuf};
+use std::time::SystemTime;
+use tracing::warn;
+
+const HALT_PATH: &str = "config/halt.json";
+
+/// Environment variable naming the halt file, so processes started from different
+/// directories share one halt
+const HALT_PATH_VAR: &str = "MONAD_HALT_PATH";
+
+/// A trading halt in force. While the file at [`Halt::path`] exists every bot reading it
+/// stands down; it stays until `resume`, across bot restarts.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Halt {
+    pub reason: String,
+    /// Unix time the halt started
+    pub since: u64,
+    /// Cancel the bot's resting orders when it sees the halt
+    #[serde(default)]
+    pub cancel_orders: bool,
+}
+
+impl Halt {
+    pub fn new(reason: String, cancel_orders: bool) -> Self {
+        Self { reason, since: expiry::unix_secs(SystemTime::now()), cancel_orders }
+    }
+
+    /// Where the halt is kept: `MONAD_HALT_PATH`, else `config/halt.json` under the
+    /// current directory
+    pub fn path() -> Result<PathBuf> {
+        let path = env::var(HALT_PATH_VAR).unwrap_or_else(|_| HALT_PATH.to_string());
+        Ok(path::absolute(&path).with_context(|| format!("Failed to resolve {}", path))?)
+    }
+
+    /// The halt in force, if any
+    pub fn load() -> Result<Option<Self>> {
+        let path = Self::path()?;
+        if !path.exists() {
+            return Ok(None);
+        }
+        let content = fs::read_to_string(&path)
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+        Ok(Some(serde_json::from_str(&content)?))
+    }
+
+    pub fn save(&self) -> Result<()> {
+        let path = Self::path()?;
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(&path, serde_json::to_string_pretty(self)?)
+            .with_context(|| format!("Failed to write {}", path.display()))?;
+        Ok(())
+    }
+
+    /// Lift the halt, returning the one that was in force
+    pub fn clear() -> Result<Option<Self>> {
+        let halt = Self::load()?;
+        if halt.is_some() {
+            fs::remove_file(Self::path()?)?;
+        }
+        Ok(halt)
+    }
+}
+
+/// Anomalies that halt a bot. Each trigger is off while unset; a halt set with the
+/// `halt` command is honoured either way.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct BreakerConfig {
+    /// Largest distance between the book mid and the reference price, in basis points
+    #[serde(default)]
+    pub max_reference_deviation_bps: Option<f64>,
+    /// Reference price as for `--usd-price-source` (a number, a JSON URL or
+    /// `feed:<SYMBOL>`); the bot's `reference_feed` when unset
+    #[serde(default)]
+    pub reference: Option<String>,
+    /// Iterations in a row that may fail, from RPC errors or failed intents
+    #[serde(default)]
+    pub max_consecutive_failures: Option<u32>,
+    /// Blocks the on-disk event index may fall behind the book. The index is kept current
+    /// by the risk engine and by `index`; the check is skipped for books without a block.
+    #[serde(default)]
+    pub max_index_lag_blocks: Option<u64>,
+    /// Loss realized from the bot's fills since it started, in raw quote units
+    #[serde(default)]
+    pub max_realized_loss: Option<f64>,
+    /// Cancel the bot's resting orders when a trigger fires
+    #[serde(default)]
+    pub cancel_on_halt: bool,
+}
+
+/// Watches a bot's iterations for the anomalies in a [`BreakerConfig`] and trips a
+/// [`Halt`] when one shows up
+pub struct CircuitBreaker {
+    config: BreakerConfig,
+    reference: Option<PriceSource>,
+    dex: Address,
+    price_precision: f64,
+    failures: u32,
+    tracker: PositionTracker,
+}
+
+impl CircuitBreaker {
+    pub fn new(config: BreakerConfig, dex: Address, price_precision: U256) -> Result<Self> {
+        let reference = config
+            .reference
+            .as_deref()
+            .map(|reference| reference.parse::<PriceSource>().map_err(|e| DexError::Config(format!("Invalid breaker reference: {}", e))))
+            .transpose()?;
+        Ok(Self {
+            config,
+            reference,
+            dex,
+            price_precision: to_f64(price_precision).max(1.0),
+            failures: 0,
+            tracker: PositionTracker::default(),
+        })
+    }
+
+    /// Start counting afresh after a halt is lifted
+    pub fn reset(&mut self) {
+        self.failures = 0;
+        self.tracker = PositionTracker::default();
+    }
+
+    /// Look at one iteration: the book it traded on (`None` if it failed before getting
+    /// one), the fills it saw and whether anything failed. Returns why to halt, if it should.
+    pub async fn observe(&mut self, book: Option<&BookView>, fills: &[Fill], failed: bool) -> Option<String> {
+        self.failures = if failed { self.failures + 1 } else { 0 };
+        if let Some(max) = self.config.max_consecutive_failures {
+            if self.failures >= max {
+                return Some(format!("{} iterations in a row failed", self.failures));
+            }
+        }
+
+        for fill in fills {
+            self.tracker.fill(fill.is_buy, to_f64(fill.amount), to_f64(fill.price));
+        }
+        if let Some(max) = self.config.max_realized_loss {
+            let loss = -self.tracker.realized / self.price_precision;
+            if loss >= max {
+                return Some(format!("Realized loss of {:.0} reached the {:.0} limit", loss, max));
+            }
+        }
+
+        let book = book?;
+        if let (Some(max), Some(mid)) = (self.config.max_reference_deviation_bps, book.mid()) {
+            match self.reference_price(book).await {
+                Ok(Some(reference)) if reference > 0.0 => {
+                    let deviation_bps = (mid - reference).abs() / reference * 10_000.0;
+                    if deviation_bps > max {
+                        return Some(format!(
+                            "Book mid {:.2} is {:.0} bps from the reference price {:.2}",
+                            mid, deviation_bps, reference
+                        ));
+                    }
+                }
+                Ok(_) => {}
+                Err(e) => warn!("Circuit breaker couldn't fetch the reference price: {}", e),
+            }
+        }
+
+        if let (Some(max), Some(block)) = (self.config.max_index_lag_blocks, book.block) {
+            match EventStore::load(self.dex, block) {
+                Ok(store) => {
+                    let lag = block.saturating_sub(store.last_block);
+                    if lag > max {
+                        return Some(format!("The event index is {} blocks behind", lag));
+                    }
+                }
+                Err(e) => warn!("Circuit breaker couldn't read the event index: {}", e),
+            }
+        }
+        None
+    }
+
+    async fn reference_price(&self, book: &BookView) -> Result<Option<f64>> {
+        match &self.reference {
+            Some(source) => Ok(Some(source.fetch().await?)),
+            None => Ok(book.reference),
+        }
+    }
+
+    /// Record a halt for `reason`; with `cancel_on_halt` the bot cancels its orders once
+    /// it sees the halt
+    pub fn trip(&self, reason: String) -> Result<Halt> {
+        let halt = Halt::new(reason, self.config.cancel_on_halt);
+        halt.save()?;
+        Ok(halt)
+    }
+}
+
+/// Cancel every resting order of the bot, returning the failures
+pub async fn cancel_all(executor: &mut dyn Executor) -> Vec<String> {
+    let open_orders = match executor.open_orders().await {
+        Ok(orders) => orders,
+        Err(e) => return vec![format!("Listing open orders failed: {}", e)],
+    };
+    let cancels: Vec<Intent> = open_orders.iter().map(|order| Intent::Cancel { order_id: order.id }).collect();
+    let results = executor.execute(&cancels).await;
+    cancels
+        .iter()
+        .zip(results)
+        .filter_map(|(intent, result)| result.err().map(|e| format!("{:?} failed: {}", intent, e)))
+        .collect()
+}
@@ -0,0 +1,210 @@
+use crate::error::{DexError, Result};
+use anyhow::{anyhow, Context};
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use tracing::{info, warn};
+
+const FOUNDRY_CONFIG_PATH: &str = "foundry.toml";
+const REMAPPINGS_PATH: &str = "remappings.txt";
+
+/// Compiler settings from the `[profile.default]` section of foundry.toml
+#[derive(Debug, Deserialize)]
+#[serde(default)]
+struct Profile {
+    src: String,
+    out: String,
+    libs: Vec<String>,
+    solc_version: Option<String>,
+    optimizer: bool,
+    optimizer_runs: u32,
+}
+
+impl Default for Profile {
+    fn default() -> Self {
+        Self {
+            src: "src".to_string(),
+            out: "out".to_string(),
+            libs: vec!["lib".to_string()],
+            solc_version: None,
+            optimizer: false,
+            optimizer_runs: 200,
+        }
+    }
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct FoundryConfig {
+    #[serde(default)]
+    profile: Profiles,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct Profiles {
+    #[serde(default)]
+    default: Profile,
+}
+
+fn load_profile() -> Result<Profile> {
+    if !Path::new(FOUNDRY_CONFIG_PATH).exists() {
+        return Ok(Profile::default());
+    }
+    let content = fs::read_to_string(FOUNDRY_CONFIG_PATH)
+        .with_context(|| format!("Failed to read {}", FOUNDRY_CONFIG_PATH))?;
+    let config: FoundryConfig = toml::from_str(&content)
+        .with_context(|| format!("Invalid {}", FOUNDRY_CONFIG_PATH))?;
+    Ok(config.profile.default)
+}
+
+fn available(program: &str) -> bool {
+    Command::new(program)
+        .arg("--version")
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status()
+        .is_ok_and(|status| status.success())
+}
+
+/// Check out the git submodules the contracts import from (OpenZeppelin, forge-std)
+/// if a fresh clone left their directories empty
+fn init_submodules(profile: &Profile) -> Result<()> {
+    let is_empty = |dir: &Path| fs::read_dir(dir).map_or(true, |mut entries| entries.next().is_none());
+    let missing = profile.libs.iter().any(|lib| {
+        fs::read_dir(lib).is_ok_and(|deps| deps.flatten().any(|dep| is_empty(&dep.path())))
+    });
+    if !missing {
+        return Ok(());
+    }
+
+    info!("Fetching contract dependencies (git submodules)...");
+    let status = Command::new("git")
+        .args(["submodule", "update", "--init", "--recursive"])
+        .status()
+        .context("Failed to run git; check out the submodules under lib/ manually")?;
+    if !status.success() {
+        return Err(anyhow!("git submodule update failed with {}", status).into());
+    }
+    Ok(())
+}
+
+/// Compile the Solidity sources into forge-style artifacts (`out/<File>.sol/<Contract>.json`),
+/// the layout the deploy, dex and interact commands load ABIs and bytecode from.
+/// Uses forge when installed and falls back to the `solc` on `PATH`.
+pub fn build(force: bool) -> Result<()> {
+    let profile = load_profile()?;
+    init_submodules(&profile)?;
+
+    if available("forge") {
+        info!("Compiling contracts with forge...");
+        let mut command = Command::new("forge");
+        command.arg("build");
+        if force {
+            command.arg("--force");
+        }
+        let status = command.status().context("Failed to run forge")?;
+        if !status.success() {
+            return Err(anyhow!("forge build failed with {}", status).into());
+        }
+        return Ok(());
+    }
+
+    if available("solc") {
+        return build_with_solc(&profile);
+    }
+
+    Err(anyhow!(
+        "Neither forge nor solc was found on PATH; install Foundry (https://getfoundry.sh) or solc {}",
+        profile.solc_version.as_deref().unwrap_or("")
+    ).into())
+}
+
+fn solidity_sources(dir: &Path, sources: &mut Vec<PathBuf>) -> Result<()> {
+    for entry in fs::read_dir(dir).with_context(|| format!("Failed to read {}", dir.display()))? {
+        let path = entry?.path();
+        if path.is_dir() {
+            solidity_sources(&path, sources)?;
+        } else if path.extension().is_some_and(|ext| ext == "sol") {
+            sources.push(path);
+        }
+    }
+    Ok(())
+}
+
+fn solc_version() -> Option<String> {
+    let output = Command::new("solc").arg("--version").output().ok()?;
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let version = stdout.lines().find_map(|line| line.strip_prefix("Version: "))?;
+    Some(version.split('+').next().unwrap_or(version).to_string())
+}
+
+/// solc reports some combined-json fields as JSON and, in older versions, as JSON-encoded strings
+fn json_field(value: &Value) -> Result<Value> {
+    match value {
+        Value::String(s) => Ok(serde_json::from_str(s)?),
+        other => Ok(other.clone()),
+    }
+}
+
+fn build_with_solc(profile: &Profile) -> Result<()> {
+    let version = solc_version();
+    info!("Compiling contracts with solc {}...", version.as_deref().unwrap_or("(unknown version)"));
+    if let (Some(wanted), Some(found)) = (&profile.solc_version, &version) {
+        if wanted != found {
+            warn!(
+                "{} pins solc {} but solc {} is installed; bytecode will not match forge builds",
+                FOUNDRY_CONFIG_PATH, wanted, found
+            );
+        }
+    }
+
+    let mut sources = Vec::new();
+    solidity_sources(Path::new(&profile.src), &mut sources)?;
+    if sources.is_empty() {
+        return Err(DexError::Config(format!("No Solidity sources found in {}", profile.src)));
+    }
+
+    let mut command = Command::new("solc");
+    command
+        .args(["--combined-json", "abi,bin,storage-layout"])
+        .args(["--base-path", ".", "--allow-paths", "."]);
+    if profile.optimizer {
+        command.arg("--optimize").args(["--optimize-runs", &profile.optimizer_runs.to_string()]);
+    }
+    if let Ok(remappings) = fs::read_to_string(REMAPPINGS_PATH) {
+        command.args(remappings.lines().map(str::trim).filter(|line| !line.is_empty()));
+    }
+    command.args(&sources);
+
+    let output = command.output().context("Failed to run solc")?;
+    if !output.status.success() {
+        return Err(anyhow!("solc failed:\n{}", String::from_utf8_lossy(&output.stderr)).into());
+    }
+    let compiled: Value = serde_json::from_slice(&output.stdout).context("Invalid solc output")?;
+    let contracts = compiled["contracts"]
+        .as_object()
+        .ok_or_else(|| anyhow!("solc produced no contracts"))?;
+
+    // Lay artifacts out the way forge does so both builds are interchangeable
+    for (key, contract) in contracts {
+        let (source, name) = key
+            .rsplit_once(':')
+            .ok_or_else(|| anyhow!("Unexpected contract key '{}' in solc output", key))?;
+        let file = Path::new(source)
+            .file_name()
+            .ok_or_else(|| anyhow!("Unexpected source path '{}' in solc output", source))?;
+        let dir = Path::new(&profile.out).join(file);
+        fs::create_dir_all(&dir)?;
+
+        let artifact = json!({
+            "abi": json_field(&contract["abi"])?,
+            "bytecode": { "object": format!("0x{}", contract["bin"].as_str().unwrap_or_default()) },
+            "storageLayout": json_field(&contract["storage-layout"])?,
+        });
+        fs::write(dir.join(format!("{}.json", name)), serde_json::to_string_pretty(&artifact)?)?;
+    }
+
+    info!("Wrote {} artifact(s) to {}/", contracts.len(), profile.out);
+    Ok(())
+}
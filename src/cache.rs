@@ -0,0 +1,154 @@
+use crate::metrics::metrics;
+use async_trait::async_trait;
+use ethers::providers::{HttpClientError, JsonRpcClient};
+use serde::{de::DeserializeOwned, Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::fmt::Debug;
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, Instant};
+
+/// How long answers that can't change are kept: chain ID, token metadata, reads at a
+/// fixed block
+const IMMUTABLE_TTL: Duration = Duration::from_secs(3600);
+
+/// How long contract code is kept; it only changes through a proxy upgrade
+const CODE_TTL: Duration = Duration::from_secs(60);
+
+/// Entries kept before expired ones are swept, then everything is dropped
+const MAX_ENTRIES: usize = 10_000;
+
+/// Selectors of `name()`, `symbol()` and `decimals()`, which tokens never change
+const METADATA_SELECTORS: &[&str] = &["0x06fdde03", "0x95d89b41", "0x313ce567"];
+
+/// Methods after which state read before may be stale: something was sent, or a
+/// transaction someone waits for was mined
+const INVALIDATING_METHODS: &[&str] = &["eth_sendRawTransaction", "eth_sendTransaction", "eth_getTransactionReceipt"];
+
+struct Entry {
+    value: Value,
+    expires: Instant,
+    immutable: bool,
+}
+
+/// Shared by every client in the process, so a transaction sent through one client
+/// invalidates what the others read
+fn entries() -> &'static Mutex<HashMap<String, Entry>> {
+    static ENTRIES: OnceLock<Mutex<HashMap<String, Entry>>> = OnceLock::new();
+    ENTRIES.get_or_init(Default::default)
+}
+
+/// How long to keep the answer to `method`, or `None` to not cache it; the flag says
+/// whether the answer can never change
+fn ttl(method: &str, params: &Value, read_ttl: Duration) -> Option<(Duration, bool)> {
+    let at_block = |index: usize| match params.get(index) {
+        Some(Value::String(tag)) => !matches!(tag.as_str(), "latest" | "pending" | "safe" | "finalized"),
+        Some(Value::Object(_)) => true,
+        _ => false,
+    };
+    match method {
+        "eth_chainId" => Some((IMMUTABLE_TTL, true)),
+        "eth_getCode" if at_block(1) => Some((IMMUTABLE_TTL, true)),
+        "eth_getCode" => Some((CODE_TTL, false)),
+        "eth_call" if at_block(1) => Some((IMMUTABLE_TTL, true)),
+        "eth_call" => {
+            let call = params.get(0)?;
+            let data = call.get("data").or_else(|| call.get("input")).and_then(Value::as_str).unwrap_or_default();
+            if METADATA_SELECTORS.iter().any(|selector| data.eq_ignore_ascii_case(selector)) {
+                Some((IMMUTABLE_TTL, true))
+            } else {
+                (!read_ttl.is_zero()).then_some((read_ttl, false))
+            }
+        }
+        _ => None,
+    }
+}
+
+/// A transport wrapper answering idempotent reads from a short-lived cache
+#[derive(Debug)]
+pub struct CacheClient<C> {
+    inner: C,
+    /// Prefix keeping answers from different endpoints apart
+    scope: String,
+    read_ttl: Duration,
+    enabled: bool,
+}
+
+impl<C> CacheClient<C> {
+    /// Wrap `inner`, keeping reads of the latest state for `read_ttl`
+    pub fn new(inner: C, scope: &str, read_ttl: Duration) -> Self {
+        Self { inner, scope: scope.to_string(), read_ttl, enabled: true }
+    }
+
+    /// Wrap `inner` without caching anything, for `--no-cache`
+    pub fn pass_through(inner: C) -> Self {
+        Self { inner, scope: String::new(), read_ttl: Duration::ZERO, enabled: false }
+    }
+
+    pub fn inner(&self) -> &C {
+        &self.inner
+    }
+}
+
+fn store(key: String, value: Value, ttl: Duration, immutable: bool) {
+    let mut entries = entries().lock().unwrap();
+    if entries.len() >= MAX_ENTRIES {
+        let now = Instant::now();
+        entries.retain(|_, entry| entry.expires > now);
+        if entries.len() >= MAX_ENTRIES {
+            entries.clear();
+        }
+    }
+    entries.insert(key, Entry { value, expires: Instant::now() + ttl, immutable });
+    metrics().rpc_cache_entries.set(entries.len() as i64);
+}
+
+fn invalidate() {
+    let mut entries = entries().lock().unwrap();
+    entries.retain(|_, entry| entry.immutable);
+    metrics().rpc_cache_entries.set(entries.len() as i64);
+}
+
+#[async_trait]
+impl<C: JsonRpcClient<Error = HttpClientError>> JsonRpcClient for CacheClient<C> {
+    type Error = HttpClientError;
+
+    async fn request<T, R>(&self, method: &str, params: T) -> Result<R, Self::Error>
+    where
+        T: Debug + Serialize + Send + Sync,
+        R: DeserializeOwned + Send,
+    {
+        if !self.enabled {
+            return self.inner.request(method, params).await;
+        }
+        if INVALIDATING_METHODS.contains(&method) {
+            invalidate();
+        }
+        let params_json = serde_json::to_value(&params).unwrap_or(Value::Null);
+        let Some((ttl, immutable)) = ttl(method, &params_json, self.read_ttl) else {
+            return self.inner.request(method, params).await;
+        };
+
+        let key = format!("{}|{}|{}", self.scope, method, params_json);
+        let cached = {
+            let entries = entries().lock().unwrap();
+            entries
+                .get(&key)
+                .filter(|entry| entry.expires > Instant::now())
+                .map(|entry| entry.value.clone())
+        };
+        let value = match cached {
+            Some(value) => {
+                metrics().rpc_cache_hits.with_label_values(&[method]).inc();
+                value
+            }
+            None => {
+                metrics().rpc_cache_misses.with_label_values(&[method]).inc();
+                let value: Value = self.inner.request(method, params).await?;
+                store(key, value.clone(), ttl, immutable);
+                value
+            }
+        };
+        serde_json::from_value(value.clone()).map_err(|err| HttpClientError::SerdeJson { err, text: value.to_string() })
+    }
+}
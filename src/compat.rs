@@ -0,0 +1,185 @@
+use crate::bindings::ORDERBOOKDEX_ABI;
+use crate::error::{DexError, Result};
+use crate::proxy;
+use anyhow::Context;
+use ethers::{
+    providers::Middleware,
+    types::{transaction::eip2718::TypedTransaction, Address, TransactionRequest, H256},
+    utils::keccak256,
+};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+
+const RELEASES_PATH: &str = "config/dex_releases.json";
+
+/// DEX functions the commands call
+pub const REQUIRED_FUNCTIONS: &[&str] = &[
+    "placeLimitOrder",
+    "placeMarketOrder",
+    "cancelOrder",
+    "getOrderBook",
+    "tradingPairs",
+    "orders",
+    "getUserBalance",
+    "withdraw",
+];
+
+/// ERC-165 interface ID, which is also the selector of `supportsInterface(bytes4)`
+const ERC165_INTERFACE_ID: [u8; 4] = [0x01, 0xff, 0xc9, 0xa7];
+
+/// Code hashes of known OrderBookDEX releases
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct DexReleases {
+    /// Keccak-256 of the runtime code, by release name
+    pub releases: BTreeMap<String, H256>,
+    /// Refuse to trade against code that matches no release
+    #[serde(default)]
+    pub require_known: bool,
+}
+
+impl DexReleases {
+    pub fn load() -> Result<Self> {
+        if !Path::new(RELEASES_PATH).exists() {
+            return Ok(Self::default());
+        }
+        let content = fs::read_to_string(RELEASES_PATH)
+            .with_context(|| format!("Failed to read {}", RELEASES_PATH))?;
+        Ok(serde_json::from_str(&content)?)
+    }
+
+    pub fn save(&self) -> Result<()> {
+        fs::create_dir_all("config")?;
+        fs::write(RELEASES_PATH, serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+
+    pub fn remove(&mut self, name: &str) -> Result<H256> {
+        self.releases
+            .remove(name)
+            .ok_or_else(|| DexError::Input(format!("No release named '{}'", name)))
+    }
+
+    /// Name of the release with this code hash
+    pub fn find(&self, code_hash: H256) -> Option<&str> {
+        self.releases
+            .iter()
+            .find(|(_, hash)| **hash == code_hash)
+            .map(|(name, _)| name.as_str())
+    }
+}
+
+/// What a DEX address holds, as far as these tools are concerned
+#[derive(Debug, Clone, Serialize)]
+pub struct Compatibility {
+    pub address: Address,
+    /// Implementation behind an EIP-1967 proxy; its code is the one checked
+    pub implementation: Option<Address>,
+    /// Size of the checked code; 0 when nothing is deployed
+    pub code_size: usize,
+    /// Keccak-256 of the checked code
+    pub code_hash: H256,
+    /// Known release the code hash matches
+    pub release: Option<String>,
+    /// Whether the contract confirmed the interface through ERC-165
+    pub erc165: bool,
+    /// Required functions whose selectors the code lacks
+    pub missing: Vec<String>,
+}
+
+impl Compatibility {
+    /// Fail with a "contract version mismatch" error unless these tools can trade on the
+    /// contract: it must exist, expose every required function and, when `releases`
+    /// requires it, match a known release
+    pub fn ensure(&self, releases: &DexReleases) -> Result<()> {
+        if self.code_size == 0 {
+            return Err(DexError::Config(format!(
+                "Contract version mismatch: no contract at {:?} on this chain; check --address and the RPC network",
+                self.address
+            )));
+        }
+        if !self.missing.is_empty() {
+            return Err(DexError::Config(format!(
+                "Contract version mismatch: {:?} doesn't expose {} that this CLI calls, so it isn't a compatible OrderBookDEX; \
+                 run `monad-dex doctor compat` for details, or pass --skip-compat-check",
+                self.address,
+                self.missing.join(", ")
+            )));
+        }
+        if releases.require_known && self.release.is_none() {
+            let known: Vec<&str> = releases.releases.keys().map(String::as_str).collect();
+            return Err(DexError::Config(format!(
+                "Contract version mismatch: code hash {:?} at {:?} matches no known release ({}); \
+                 record it with `monad-dex doctor releases add` if it is trusted",
+                self.code_hash,
+                self.address,
+                if known.is_empty() { "none recorded".to_string() } else { known.join(", ") }
+            )));
+        }
+        Ok(())
+    }
+}
+
+/// Look at the code at `dex`, following an EIP-1967 proxy to its implementation
+pub async fn inspect<M: Middleware + 'static>(client: &M, dex: Address, releases: &DexReleases) -> Result<Compatibility> {
+    let implementation = proxy::implementation(client, dex).await.ok();
+    let target = implementation.unwrap_or(dex);
+    let code = client
+        .get_code(target, None)
+        .await
+        .map_err(|e| DexError::Rpc(format!("Failed to fetch the code at {:?}: {}", target, e)))?;
+    let code_hash = H256::from(keccak256(&code));
+
+    let selectors: Vec<(&str, [u8; 4])> = REQUIRED_FUNCTIONS
+        .iter()
+        .filter_map(|name| ORDERBOOKDEX_ABI.function(name).ok().map(|function| (*name, function.short_signature())))
+        .collect();
+    let interface_id = selectors.iter().fold([0u8; 4], |mut id, (_, selector)| {
+        for (byte, selector_byte) in id.iter_mut().zip(selector) {
+            *byte ^= selector_byte;
+        }
+        id
+    });
+    let erc165 = !code.is_empty()
+        && supports_interface(client, dex, ERC165_INTERFACE_ID).await
+        && supports_interface(client, dex, interface_id).await;
+    let missing = if erc165 || code.is_empty() {
+        Vec::new()
+    } else {
+        selectors
+            .iter()
+            .filter(|(_, selector)| !code.windows(4).any(|window| window == selector))
+            .map(|(name, _)| name.to_string())
+            .collect()
+    };
+
+    Ok(Compatibility {
+        address: dex,
+        implementation,
+        code_size: code.len(),
+        code_hash,
+        release: releases.find(code_hash).map(str::to_string),
+        erc165,
+        missing,
+    })
+}
+
+/// Check `dex` before trading on it; see [`Compatibility::ensure`]
+pub async fn ensure<M: Middleware + 'static>(client: &M, dex: Address) -> Result<Compatibility> {
+    let releases = DexReleases::load()?;
+    let compatibility = inspect(client, dex, &releases).await?;
+    compatibility.ensure(&releases)?;
+    Ok(compatibility)
+}
+
+async fn supports_interface<M: Middleware + 'static>(client: &M, contract: Address, interface_id: [u8; 4]) -> bool {
+    let mut data = ERC165_INTERFACE_ID.to_vec();
+    data.extend_from_slice(&interface_id);
+    data.resize(36, 0);
+    let call: TypedTransaction = TransactionRequest::new().to(contract).data(data).into();
+    match client.call(&call, None).await {
+        Ok(result) => result.len() == 32 && result[31] == 1 && result[..31].iter().all(|byte| *byte == 0),
+        Err(_) => false,
+    }
+}
@@ -0,0 +1,84 @@
+use crate::error::{DexError, Result};
+use crate::fees::Fees;
+use crate::tx::{self, TxArgs};
+use ethers::{
+    abi::{Abi, Token},
+    providers::Middleware,
+    types::{transaction::eip2718::TypedTransaction, Address, Bytes, TransactionReceipt, TransactionRequest, H256},
+    utils::get_create2_address,
+};
+use tracing::info;
+
+/// The widely deployed deterministic deployment proxy (github.com/Arachnid/deterministic-deployment-proxy).
+/// Calldata is `salt ‖ initCode`; it deploys with CREATE2 and returns the new address.
+pub const DETERMINISTIC_DEPLOYER: &str = "0x4e59b44847b379578588920cA78FbF26c0B4956C";
+
+pub fn deployer() -> Address {
+    DETERMINISTIC_DEPLOYER.parse().expect("valid deployer address")
+}
+
+/// Parse a salt of up to 32 bytes of hex, left-padded with zeros
+pub fn parse_salt(salt: &str) -> Result<H256> {
+    let digits = salt.trim().trim_start_matches("0x");
+    if digits.is_empty() || digits.len() > 64 {
+        return Err(DexError::Input(format!("Salt must be 1 to 32 bytes of hex, got '{}'", salt)));
+    }
+    let padded = format!("{:0>64}", digits);
+    let bytes = hex::decode(&padded).map_err(|e| DexError::Input(format!("Invalid salt '{}': {}", salt, e)))?;
+    Ok(H256::from_slice(&bytes))
+}
+
+/// Creation bytecode followed by the ABI-encoded constructor arguments
+pub fn init_code(abi: &Abi, bytecode: &Bytes, args: Vec<Token>) -> Result<Bytes> {
+    match abi.constructor() {
+        Some(constructor) => Ok(constructor.encode_input(bytecode.to_vec(), &args)?.into()),
+        None if args.is_empty() => Ok(bytecode.clone()),
+        None => Err(DexError::Input(format!("Contract has no constructor but {} argument(s) were given", args.len()))),
+    }
+}
+
+/// Address the deterministic deployer will create `init_code` at with `salt`, on any chain
+pub fn predict(salt: H256, init_code: &Bytes) -> Address {
+    get_create2_address(deployer(), salt.as_bytes(), init_code)
+}
+
+/// Deploy through the deterministic deployer, returning the new address and the receipt
+/// (`None` for estimates). Constructors see the deployer as `msg.sender`, so contracts
+/// that take ownership of or mint to `msg.sender` end up owned by the deployer instead.
+pub async fn deploy<M: Middleware + 'static>(
+    client: &M,
+    salt: H256,
+    init_code: Bytes,
+    fees: &Fees,
+    tx_args: &TxArgs,
+) -> Result<(Address, Option<TransactionReceipt>)> {
+    let address = predict(salt, &init_code);
+    if client.get_code(deployer(), None).await.map_err(|e| DexError::middleware(&e))?.is_empty() {
+        return Err(DexError::Input(format!(
+            "The deterministic deployer {} is not deployed on this network",
+            DETERMINISTIC_DEPLOYER
+        )));
+    }
+    if !client.get_code(address, None).await.map_err(|e| DexError::middleware(&e))?.is_empty() {
+        return Err(DexError::Input(format!("A contract is already deployed at {:?} for this salt and bytecode", address)));
+    }
+
+    let mut data = salt.as_bytes().to_vec();
+    data.extend_from_slice(&init_code);
+    let mut tx: TypedTransaction = TransactionRequest::new().to(deployer()).data(data).into();
+    fees.apply(&mut tx);
+    let estimated_gas = client.estimate_gas(&tx, None).await.map_err(|e| DexError::middleware(&e))?;
+
+    if tx_args.estimate_only {
+        tx::print_estimate(estimated_gas, fees, tx_args).await;
+        return Ok((address, None));
+    }
+
+    info!("Deploying to {:?} via CREATE2...", address);
+    tx.set_gas(estimated_gas);
+    let receipt = tx::broadcast(client, tx, tx_args).await?;
+    if let Some(receipt) = &receipt {
+        tx::report(estimated_gas, receipt, tx_args).await;
+    }
+    Ok((address, receipt))
+}
@@ -0,0 +1,605 @@
+use crate::aliases;
+use crate::alerts::{AlertEngine, AlertsConfig};
+use crate::bindings::ORDERBOOKDEX_ABI;
+use crate::bot::SignerClient;
+use crate::breaker::Halt;
+use crate::error::{DexError, Result};
+use crate::expiry::{self, unix_secs};
+use crate::health::{self, HealthConfig};
+use crate::indexer::{EventStore, DEFAULT_CHUNK_SIZE};
+use crate::publish::{EventPublisher, PublishConfig};
+use crate::reload;
+use crate::rpc;
+use crate::runner::{self, RunnerOptions};
+use crate::schedule;
+use crate::shutdown;
+use crate::strategy::StrategyRegistry;
+use crate::tx::TxArgs;
+use anyhow::{anyhow, Context};
+use ethers::{middleware::SignerMiddleware, providers::Middleware, types::Address};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime};
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tokio::net::{UnixListener, UnixStream};
+use tokio::sync::watch;
+use tokio::task::JoinHandle;
+use tracing::{info, warn};
+
+/// Control socket used when neither the config nor `--socket` names one
+pub const DEFAULT_SOCKET_PATH: &str = "config/dexd.sock";
+
+/// How often the RPC is probed, so `/readyz` reflects connectivity while nothing else calls it
+const PROBE_INTERVAL: Duration = Duration::from_secs(15);
+
+/// What `monad-dexd` hosts (TOML)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DaemonConfig {
+    /// DEX address or deployment name
+    #[serde(default = "default_dex")]
+    pub dex: String,
+    /// Unix socket `ctl` commands connect to
+    #[serde(default)]
+    pub socket: Option<String>,
+    /// Keep the event index of the DEX up to date
+    #[serde(default)]
+    pub indexer: Option<IndexerConfig>,
+    /// Publish the events the indexer indexes to Kafka or NATS
+    #[serde(default)]
+    pub publish: Option<PublishConfig>,
+    /// Alerts config file to watch, as for the `alerts` command
+    #[serde(default)]
+    pub alerts: Option<String>,
+    /// Bot config files, each run as by the `bot` command
+    #[serde(default)]
+    pub bots: Vec<String>,
+    /// Cancel the signer's orders once their expiry passes, as `expire-orders` would
+    #[serde(default)]
+    pub expire_orders: Option<PollConfig>,
+    /// Place scheduled orders as they come due, as `schedule run` would
+    #[serde(default)]
+    pub schedules: Option<PollConfig>,
+    #[serde(default)]
+    pub shutdown: ShutdownConfig,
+    /// Thresholds for `/healthz` and `/readyz` on `--metrics-addr`
+    #[serde(default)]
+    pub health: HealthConfig,
+}
+
+fn default_dex() -> String {
+    "OrderBookDEX".to_string()
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IndexerConfig {
+    /// Block a new index starts from
+    #[serde(default)]
+    pub from_block: u64,
+    #[serde(default = "default_index_interval_secs")]
+    pub interval_secs: u64,
+}
+
+fn default_index_interval_secs() -> u64 {
+    10
+}
+
+/// A task that checks for due work every `interval_secs`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PollConfig {
+    #[serde(default = "default_poll_interval_secs")]
+    pub interval_secs: u64,
+}
+
+fn default_poll_interval_secs() -> u64 {
+    15
+}
+
+/// What happens on SIGINT, SIGTERM or `ctl stop`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ShutdownConfig {
+    /// Cancel every bot's resting orders before exiting, whatever the bot configs say
+    #[serde(default)]
+    pub cancel_orders: bool,
+    /// Seconds to let tasks finish their current iteration before they are aborted
+    #[serde(default = "default_shutdown_timeout_secs")]
+    pub timeout_secs: u64,
+}
+
+impl Default for ShutdownConfig {
+    fn default() -> Self {
+        Self { cancel_orders: false, timeout_secs: default_shutdown_timeout_secs() }
+    }
+}
+
+fn default_shutdown_timeout_secs() -> u64 {
+    30
+}
+
+impl DaemonConfig {
+    pub fn load(path: &str) -> Result<Self> {
+        let content = fs::read_to_string(path).with_context(|| DexError::Config(format!("Failed to read {}", path)))?;
+        Ok(toml::from_str(&content).with_context(|| DexError::Config(format!("Invalid daemon config {}", path)))?)
+    }
+}
+
+/// How the daemon connects and trades, from the command line
+#[derive(Debug, Clone)]
+pub struct DaemonOptions {
+    pub rpc_url: Option<String>,
+    pub private_key: Option<String>,
+    /// Run the bots against paper accounts
+    pub paper: bool,
+    pub skip_compat_check: bool,
+    pub tx_args: TxArgs,
+    /// Overrides the socket in the config
+    pub socket: Option<String>,
+}
+
+/// A request sent to the control socket, one JSON object per line
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "command", rename_all = "kebab-case")]
+pub enum Request {
+    Status,
+    /// Halt the bots and have them cancel their resting orders
+    CancelAll,
+    /// Lift a halt
+    Resume,
+    /// Re-read the bot and alerts configs
+    Reload,
+    /// Shut the daemon down
+    Stop,
+}
+
+/// The daemon's answer to a [`Request`]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "result", rename_all = "kebab-case")]
+pub enum Response {
+    Status(DaemonStatus),
+    Done { message: String },
+    Error { message: String },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TaskState {
+    Starting,
+    Running,
+    Stopped,
+    Failed,
+}
+
+/// One of the daemon's tasks: the indexer, the alerts monitor, a bot, order expiry or schedules
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TaskStatus {
+    pub name: String,
+    pub state: TaskState,
+    /// What the task last did, e.g. the block the index is synced to
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub detail: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    /// Unix time of the last update
+    pub updated_at: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DaemonStatus {
+    pub pid: u32,
+    /// Unix time the daemon started
+    pub started_at: u64,
+    pub dex: Address,
+    pub tasks: Vec<TaskStatus>,
+    /// The trading halt in force, if any
+    pub halt: Option<Halt>,
+}
+
+type SharedStatus = Arc<Mutex<DaemonStatus>>;
+
+fn set_task(status: &SharedStatus, name: &str, state: TaskState, detail: Option<String>, error: Option<String>) {
+    match &error {
+        Some(error) => health::failed(name, error),
+        None => health::ok(name),
+    }
+    let mut status = status.lock().unwrap();
+    let task = TaskStatus { name: name.to_string(), state, detail, error, updated_at: unix_secs(SystemTime::now()) };
+    match status.tasks.iter_mut().find(|task| task.name == name) {
+        Some(existing) => *existing = task,
+        None => status.tasks.push(task),
+    }
+}
+
+/// Run the indexer, alerts monitor, bots, order expiry and schedules in `config_path` until SIGINT, SIGTERM or
+/// `ctl stop`, answering control requests on the socket meanwhile.
+///
+/// On shutdown the bots finish their current iteration (cancelling their orders if
+/// configured) and the index is saved; tasks still busy after the timeout are aborted.
+/// Returns the tasks' final state.
+pub async fn run(config_path: &str, options: DaemonOptions) -> Result<DaemonStatus> {
+    let config = DaemonConfig::load(config_path)?;
+    let dex = aliases::resolve(&config.dex)?;
+    let socket = options
+        .socket
+        .clone()
+        .or_else(|| config.socket.clone())
+        .unwrap_or_else(|| DEFAULT_SOCKET_PATH.to_string());
+    let listener = bind(&socket).await?;
+    info!("dexd listening on {} for {:?}", socket, dex);
+
+    let status: SharedStatus = Arc::new(Mutex::new(DaemonStatus {
+        pid: std::process::id(),
+        started_at: unix_secs(SystemTime::now()),
+        dex,
+        tasks: Vec::new(),
+        halt: None,
+    }));
+    health::configure(config.health.clone());
+    let (reload_tx, reload_rx) = watch::channel(0u64);
+    let mut tasks: Vec<JoinHandle<()>> = vec![tokio::spawn(probe_rpc(options.rpc_url.clone()))];
+
+    if let Some(indexer) = config.indexer.clone() {
+        tasks.push(tokio::spawn(run_indexer(dex, indexer, config.publish.clone(), options.rpc_url.clone(), status.clone())));
+    } else if config.publish.is_some() {
+        warn!("[publish] has no effect without [indexer]");
+    }
+    if let Some(alerts) = config.alerts.clone() {
+        tasks.push(tokio::spawn(run_monitor(dex, alerts, options.rpc_url.clone(), status.clone(), reload_rx.clone())));
+    }
+    for bot in &config.bots {
+        let runner_options = RunnerOptions {
+            dex,
+            private_key: options.private_key.clone(),
+            rpc_url: options.rpc_url.clone(),
+            paper: options.paper,
+            force: false,
+            tx_args: options.tx_args.clone(),
+            record: None,
+            cancel_on_shutdown: config.shutdown.cancel_orders,
+            skip_compat_check: options.skip_compat_check,
+        };
+        tasks.push(tokio::spawn(run_bot(bot.clone(), runner_options, status.clone())));
+    }
+    if options.paper && (config.expire_orders.is_some() || config.schedules.is_some()) {
+        // Both send real transactions, which --paper promises not to
+        warn!("[expire_orders] and [schedules] are not run with --paper");
+    } else {
+        if let Some(expire_orders) = config.expire_orders.clone() {
+            tasks.push(tokio::spawn(run_expiry(expire_orders, options.clone(), status.clone())));
+        }
+        if let Some(schedules) = config.schedules.clone() {
+            tasks.push(tokio::spawn(run_schedules(schedules, options.clone(), status.clone())));
+        }
+    }
+
+    loop {
+        tokio::select! {
+            accepted = listener.accept() => {
+                let (stream, _) = match accepted {
+                    Ok(connection) => connection,
+                    Err(e) => {
+                        warn!("Control socket accept failed: {}", e);
+                        continue;
+                    }
+                };
+                let (status, reload_tx) = (status.clone(), reload_tx.clone());
+                tokio::spawn(async move {
+                    if let Err(e) = serve(stream, &status, &reload_tx).await {
+                        warn!("Control connection failed: {}", e);
+                    }
+                });
+            }
+            _ = shutdown::wait() => break,
+        }
+    }
+
+    info!("dexd shutting down; waiting up to {}s for tasks to finish", config.shutdown.timeout_secs);
+    let drain = async {
+        for task in tasks.iter_mut() {
+            let _ = task.await;
+        }
+    };
+    if tokio::time::timeout(Duration::from_secs(config.shutdown.timeout_secs), drain).await.is_err() {
+        warn!("Tasks still running after {}s; aborting them", config.shutdown.timeout_secs);
+        for task in &tasks {
+            task.abort();
+        }
+        let mut status = status.lock().unwrap();
+        for task in status.tasks.iter_mut().filter(|task| task.state != TaskState::Stopped && task.state != TaskState::Failed) {
+            task.state = TaskState::Failed;
+            task.error = Some("aborted at shutdown".to_string());
+        }
+    }
+    let _ = fs::remove_file(&socket);
+
+    let mut status = status.lock().unwrap().clone();
+    status.halt = Halt::load().unwrap_or_default();
+    Ok(status)
+}
+
+/// Bind the control socket, replacing a stale one left by a daemon that died
+async fn bind(socket: &str) -> Result<UnixListener> {
+    if Path::new(socket).exists() {
+        if UnixStream::connect(socket).await.is_ok() {
+            return Err(anyhow!("Another dexd is already listening on {}", socket).into());
+        }
+        fs::remove_file(socket).with_context(|| format!("Failed to remove stale socket {}", socket))?;
+    }
+    if let Some(parent) = Path::new(socket).parent() {
+        fs::create_dir_all(parent)?;
+    }
+    Ok(UnixListener::bind(socket).with_context(|| format!("Failed to bind {}", socket))?)
+}
+
+async fn serve(stream: UnixStream, status: &SharedStatus, reload_tx: &watch::Sender<u64>) -> Result<()> {
+    let (reader, mut writer) = stream.into_split();
+    let mut lines = BufReader::new(reader).lines();
+    while let Some(line) = lines.next_line().await? {
+        let response = match serde_json::from_str::<Request>(&line) {
+            Ok(request) => handle(request, status, reload_tx),
+            Err(e) => Response::Error { message: format!("Invalid request: {}", e) },
+        };
+        let mut answer = serde_json::to_string(&response)?;
+        answer.push('\n');
+        writer.write_all(answer.as_bytes()).await?;
+    }
+    Ok(())
+}
+
+fn handle(request: Request, status: &SharedStatus, reload_tx: &watch::Sender<u64>) -> Response {
+    let result = match request {
+        Request::Status => {
+            let mut status = status.lock().unwrap().clone();
+            status.halt = Halt::load().unwrap_or_default();
+            return Response::Status(status);
+        }
+        Request::CancelAll => Halt::new("cancel-all from dexd ctl".to_string(), true)
+            .save()
+            .map(|_| "Trading halted; bots cancel their orders and stand down until `ctl resume`".to_string()),
+        Request::Resume => Halt::clear().map(|halt| match halt {
+            Some(halt) => format!("Resumed; the halt was: {}", halt.reason),
+            None => "Trading was not halted".to_string(),
+        }),
+        Request::Reload => {
+            reload::request_reload();
+            reload_tx.send_modify(|generation| *generation += 1);
+            Ok("Bots and alerts re-read their configs on their next check".to_string())
+        }
+        Request::Stop => {
+            shutdown::request();
+            Ok("dexd stopping".to_string())
+        }
+    };
+    match result {
+        Ok(message) => Response::Done { message },
+        Err(e) => Response::Error { message: e.to_string() },
+    }
+}
+
+/// Poll the chain head so the RPC's health stays current; the transport records the outcome
+async fn probe_rpc(rpc_url: Option<String>) {
+    let provider = match rpc::connect(rpc_url.as_deref()).await {
+        Ok(provider) => provider,
+        Err(e) => {
+            health::failed(health::RPC, &e);
+            warn!("RPC probe couldn't connect: {}", e);
+            return;
+        }
+    };
+    while !shutdown::requested() {
+        let _ = provider.get_block_number().await;
+        tokio::select! {
+            _ = tokio::time::sleep(PROBE_INTERVAL) => {}
+            _ = shutdown::wait() => {}
+        }
+    }
+}
+
+async fn run_indexer(dex: Address, config: IndexerConfig, publish: Option<PublishConfig>, rpc_url: Option<String>, status: SharedStatus) {
+    const NAME: &str = "indexer";
+    set_task(&status, NAME, TaskState::Starting, None, None);
+    let result: Result<String> = async {
+        let provider = rpc::connect(rpc_url.as_deref()).await?;
+        let mut store = EventStore::load(dex, config.from_block)?;
+        let mut publisher = match &publish {
+            Some(publish) => Some(EventPublisher::connect(publish, &store).await?),
+            None => None,
+        };
+        while !shutdown::requested() {
+            match store.sync(&provider, &ORDERBOOKDEX_ABI, DEFAULT_CHUNK_SIZE).await {
+                Ok(()) => {
+                    let detail = format!(
+                        "synced to block {}: {} orders, {} trades",
+                        store.last_block,
+                        store.orders.len(),
+                        store.trades.len()
+                    );
+                    let error = match publisher.as_mut() {
+                        Some(publisher) => publisher.publish(&store).await.err().map(|e| {
+                            warn!("Event publishing failed: {}", e);
+                            e.to_string()
+                        }),
+                        None => None,
+                    };
+                    set_task(&status, NAME, TaskState::Running, Some(detail), error);
+                }
+                Err(e) => {
+                    warn!("Index sync failed: {}", e);
+                    let detail = Some(format!("synced to block {}", store.last_block));
+                    set_task(&status, NAME, TaskState::Running, detail, Some(e.to_string()));
+                }
+            }
+            tokio::select! {
+                _ = tokio::time::sleep(Duration::from_secs(config.interval_secs)) => {}
+                _ = shutdown::wait() => {}
+            }
+        }
+
+        // Keep the checkpoint so the next start carries on from here
+        store.save()?;
+        Ok(format!("saved at block {}", store.last_block))
+    }
+    .await;
+    match result {
+        Ok(detail) => set_task(&status, NAME, TaskState::Stopped, Some(detail), None),
+        Err(e) => {
+            warn!("Indexer stopped: {}", e);
+            set_task(&status, NAME, TaskState::Failed, None, Some(e.to_string()));
+        }
+    }
+}
+
+async fn run_monitor(dex: Address, config_path: String, rpc_url: Option<String>, status: SharedStatus, mut reload: watch::Receiver<u64>) {
+    const NAME: &str = "alerts";
+    set_task(&status, NAME, TaskState::Starting, Some(config_path.clone()), None);
+    let result: Result<()> = async {
+        let provider = Arc::new(rpc::connect(rpc_url.as_deref()).await?);
+        let abi = ORDERBOOKDEX_ABI.clone();
+        let mut config = AlertsConfig::load(&config_path)?;
+        let mut notifications = config.notify.build();
+        let mut engine = AlertEngine::new(provider.as_ref(), dex, &abi, config.rules.clone()).await?;
+        while !shutdown::requested() {
+            match engine.check(provider.clone(), &abi).await {
+                Ok(alerts) => {
+                    for alert in &alerts {
+                        warn!("[{}] {}", alert.source, alert.message);
+                        notifications.send(alert).await;
+                    }
+                    let detail = format!("{} rules from {}", config.rules.len(), config_path);
+                    set_task(&status, NAME, TaskState::Running, Some(detail), None);
+                }
+                Err(e) => {
+                    warn!("Alert check failed: {}", e);
+                    set_task(&status, NAME, TaskState::Running, Some(config_path.clone()), Some(e.to_string()));
+                }
+            }
+
+            tokio::select! {
+                _ = tokio::time::sleep(Duration::from_secs(config.interval_secs)) => {}
+                _ = reload.changed() => match AlertsConfig::load(&config_path) {
+                    Ok(reloaded) => {
+                        engine = AlertEngine::new(provider.as_ref(), dex, &abi, reloaded.rules.clone()).await?;
+                        notifications = reloaded.notify.build();
+                        config = reloaded;
+                        info!("Reloaded {} alert rules from {}", config.rules.len(), config_path);
+                    }
+                    Err(e) => warn!("Alerts config reload rejected: {}", e),
+                },
+                _ = shutdown::wait() => {}
+            }
+        }
+        Ok(())
+    }
+    .await;
+    match result {
+        Ok(()) => set_task(&status, NAME, TaskState::Stopped, Some(config_path), None),
+        Err(e) => {
+            warn!("Alerts monitor stopped: {}", e);
+            set_task(&status, NAME, TaskState::Failed, Some(config_path), Some(e.to_string()));
+        }
+    }
+}
+
+async fn run_bot(config_path: String, options: RunnerOptions, status: SharedStatus) {
+    let name = format!("bot {}", config_path);
+    set_task(&status, &name, TaskState::Running, None, None);
+    match runner::run(&config_path, &StrategyRegistry::new(), options).await {
+        Ok(()) => set_task(&status, &name, TaskState::Stopped, None, None),
+        Err(e) => {
+            warn!("Bot {} stopped: {}", config_path, e);
+            set_task(&status, &name, TaskState::Failed, None, Some(e.to_string()));
+        }
+    }
+}
+
+async fn signer(options: &DaemonOptions) -> Result<Arc<SignerClient>> {
+    let provider = rpc::connect(options.rpc_url.as_deref()).await?;
+    let wallet = rpc::wallet(&provider, options.private_key.as_deref()).await?;
+    Ok(Arc::new(SignerMiddleware::new(provider, wallet)))
+}
+
+async fn run_expiry(config: PollConfig, options: DaemonOptions, status: SharedStatus) {
+    const NAME: &str = "expire-orders";
+    set_task(&status, NAME, TaskState::Starting, None, None);
+    let result: Result<()> = async {
+        let client = signer(&options).await?;
+        let tx_args = options.tx_args.unattended();
+        while !shutdown::requested() {
+            match expiry::expire_due(client.clone(), &tx_args).await {
+                Ok(settled) => {
+                    let detail = format!("{} orders of {:?} settled in the last pass", settled, client.address());
+                    set_task(&status, NAME, TaskState::Running, Some(detail), None);
+                }
+                Err(e) => {
+                    warn!("Order expiry pass failed: {}", e);
+                    set_task(&status, NAME, TaskState::Running, None, Some(e.to_string()));
+                }
+            }
+            tokio::select! {
+                _ = tokio::time::sleep(Duration::from_secs(config.interval_secs)) => {}
+                _ = shutdown::wait() => {}
+            }
+        }
+        Ok(())
+    }
+    .await;
+    match result {
+        Ok(()) => set_task(&status, NAME, TaskState::Stopped, None, None),
+        Err(e) => {
+            warn!("Order expiry stopped: {}", e);
+            set_task(&status, NAME, TaskState::Failed, None, Some(e.to_string()));
+        }
+    }
+}
+
+async fn run_schedules(config: PollConfig, options: DaemonOptions, status: SharedStatus) {
+    const NAME: &str = "schedules";
+    set_task(&status, NAME, TaskState::Starting, None, None);
+    let result: Result<()> = async {
+        let client = signer(&options).await?;
+        let tx_args = options.tx_args.unattended();
+        while !shutdown::requested() {
+            match schedule::run_due(client.clone(), options.rpc_url.as_deref(), &tx_args).await {
+                Ok(runs) => {
+                    let detail = format!("{} runs in the last pass", runs);
+                    set_task(&status, NAME, TaskState::Running, Some(detail), None);
+                }
+                Err(e) => {
+                    warn!("Schedule pass failed: {}", e);
+                    set_task(&status, NAME, TaskState::Running, None, Some(e.to_string()));
+                }
+            }
+            tokio::select! {
+                _ = tokio::time::sleep(Duration::from_secs(config.interval_secs)) => {}
+                _ = shutdown::wait() => {}
+            }
+        }
+        Ok(())
+    }
+    .await;
+    match result {
+        Ok(()) => set_task(&status, NAME, TaskState::Stopped, None, None),
+        Err(e) => {
+            warn!("Schedule runner stopped: {}", e);
+            set_task(&status, NAME, TaskState::Failed, None, Some(e.to_string()));
+        }
+    }
+}
+
+/// Send `request` to the daemon listening on `socket` and wait for its answer
+pub async fn request(socket: &str, request: &Request) -> Result<Response> {
+    let stream = UnixStream::connect(socket)
+        .await
+        .with_context(|| format!("No dexd is listening on {} (start it with `monad-dexd`)", socket))?;
+    let (reader, mut writer) = stream.into_split();
+    let mut line = serde_json::to_string(request)?;
+    line.push('\n');
+    writer.write_all(line.as_bytes()).await?;
+
+    let answer = BufReader::new(reader)
+        .lines()
+        .next_line()
+        .await?
+        .ok_or_else(|| anyhow!("dexd closed the connection without answering"))?;
+    Ok(serde_json::from_str(&answer)?)
+}
@@ -0,0 +1,150 @@
+use crate::error::Result;
+use crate::rpc;
+use anyhow::Context;
+use ethers::types::{Address, H256};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+const DEPLOYMENT_REGISTRY_PATH: &str = "config/deployment.json";
+
+/// Key deployments on the `--network` network are recorded under, e.g. `monad_testnet`
+pub fn current_network() -> String {
+    rpc::network_name().replace('-', "_")
+}
+
+/// One deployment of a contract
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Deployment {
+    pub address: Address,
+    /// 1 for the first deployment of this contract on the network, then counting up
+    pub version: u32,
+    /// Unix timestamp the deployment was recorded at
+    pub timestamp: u64,
+    pub deployer: Option<Address>,
+    pub tx: Option<H256>,
+    /// Name the deployment was given, e.g. a pipeline step id
+    #[serde(default)]
+    pub label: Option<String>,
+    /// Constructor arguments as given on the command line
+    #[serde(default)]
+    pub constructor_args: Vec<String>,
+    /// ABI-encoded constructor arguments, as block explorers ask for when verifying
+    #[serde(default)]
+    pub encoded_constructor_args: Option<String>,
+    /// Salt used with the deterministic deployer, for CREATE2 deployments
+    #[serde(default)]
+    pub create2_salt: Option<String>,
+    /// Implementation behind `address`, for proxy deployments
+    #[serde(default)]
+    pub implementation: Option<Address>,
+}
+
+/// The single-deployment format `deployment.json` had before the registry
+#[derive(Debug, Deserialize)]
+struct LegacyDeployment {
+    contract_address: Option<String>,
+    deployer_address: Option<String>,
+    network: String,
+    deployment_tx: Option<String>,
+}
+
+/// Every deployment made by these tools, keyed by network and contract name, oldest first
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct DeploymentRegistry {
+    pub networks: BTreeMap<String, BTreeMap<String, Vec<Deployment>>>,
+}
+
+impl DeploymentRegistry {
+    pub fn load() -> Result<Self> {
+        if !Path::new(DEPLOYMENT_REGISTRY_PATH).exists() {
+            return Ok(Self::default());
+        }
+        let content = fs::read_to_string(DEPLOYMENT_REGISTRY_PATH)
+            .with_context(|| format!("Failed to read {}", DEPLOYMENT_REGISTRY_PATH))?;
+        if let Ok(registry) = serde_json::from_str(&content) {
+            return Ok(registry);
+        }
+
+        // Carry over the token deployment recorded by older versions
+        let legacy: LegacyDeployment = serde_json::from_str(&content)
+            .with_context(|| format!("Invalid deployment registry {}", DEPLOYMENT_REGISTRY_PATH))?;
+        let mut registry = Self::default();
+        if let Some(address) = legacy.contract_address.and_then(|a| a.parse().ok()) {
+            registry.record(
+                &legacy.network,
+                "MonadToken",
+                Deployment {
+                    address,
+                    deployer: legacy.deployer_address.and_then(|a| a.parse().ok()),
+                    tx: legacy.deployment_tx.and_then(|tx| tx.parse().ok()),
+                    ..Default::default()
+                },
+            );
+        }
+        Ok(registry)
+    }
+
+    pub fn save(&self) -> Result<()> {
+        if rpc::is_fork() {
+            return Ok(());
+        }
+        fs::create_dir_all("config")?;
+        fs::write(DEPLOYMENT_REGISTRY_PATH, serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+
+    /// Append a deployment to the contract's history, numbering and timestamping it
+    pub fn record(&mut self, network: &str, contract: &str, mut deployment: Deployment) -> &Deployment {
+        let history = self
+            .networks
+            .entry(network.to_string())
+            .or_default()
+            .entry(contract.to_string())
+            .or_default();
+        deployment.version = history.len() as u32 + 1;
+        deployment.timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or_default();
+        history.push(deployment);
+        history.last().expect("just pushed")
+    }
+
+    /// Latest deployment of a contract name or label (case-insensitive) on `network`
+    pub fn latest(&self, network: &str, name: &str) -> Option<&Deployment> {
+        let contracts = self.networks.get(network)?;
+        if let Some((_, history)) = contracts.iter().find(|(contract, _)| contract.eq_ignore_ascii_case(name)) {
+            return history.last();
+        }
+        contracts
+            .values()
+            .flatten()
+            .filter(|d| d.label.as_deref().is_some_and(|label| label.eq_ignore_ascii_case(name)))
+            .max_by_key(|d| d.timestamp)
+    }
+
+    /// Most recent record of `address` on any network, with its network and contract name
+    pub fn find(&self, address: Address) -> Option<(&str, &str, &Deployment)> {
+        self.networks
+            .iter()
+            .flat_map(|(network, contracts)| {
+                contracts.iter().flat_map(move |(contract, history)| {
+                    history.iter().map(move |d| (network.as_str(), contract.as_str(), d))
+                })
+            })
+            .filter(|(_, _, d)| d.address == address)
+            .max_by_key(|(_, _, d)| d.timestamp)
+    }
+}
+
+/// Address of the latest deployment named `name` on the `--network` network, or else the
+/// network preset's address for it
+pub fn resolve(name: &str) -> Result<Option<Address>> {
+    if let Some(deployment) = DeploymentRegistry::load()?.latest(&current_network(), name) {
+        return Ok(Some(deployment.address));
+    }
+    Ok(rpc::network()?.contract(name))
+}
@@ -0,0 +1,357 @@
+use crate::aliases;
+use crate::bindings::{OrderBookDEX, ERC20};
+use crate::compat::{self, DexReleases};
+use crate::error::{DexError, Result};
+use crate::expiry;
+use crate::rpc::{self, RpcProvider};
+use crate::tokens::TokenRegistry;
+use crate::wallets::WalletStore;
+use ethers::{
+    providers::{Middleware, Provider},
+    signers::{LocalWallet, Signer},
+    types::{Address, BlockNumber},
+    utils::format_ether,
+};
+use serde::Serialize;
+use std::sync::Arc;
+use std::time::{Instant, SystemTime};
+
+/// Gas budgeted per order transaction when judging whether a wallet can pay for trading
+const ORDER_GAS: u64 = 300_000;
+
+/// Order transactions a wallet should be able to pay for before it counts as low on gas
+const MIN_TRANSACTIONS: u64 = 10;
+
+/// Age of the latest block past which an endpoint looks stalled
+const MAX_BLOCK_AGE_SECS: u64 = 60;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Status {
+    Ok,
+    Warn,
+    Fail,
+    /// Not checked, usually for lack of an input
+    Skip,
+}
+
+/// Outcome of one diagnostic
+#[derive(Debug, Clone, Serialize)]
+pub struct Check {
+    pub name: String,
+    pub status: Status,
+    pub detail: String,
+    /// What to do about a warning or failure
+    pub fix: Option<String>,
+}
+
+impl Check {
+    fn ok(name: impl Into<String>, detail: impl Into<String>) -> Self {
+        Self { name: name.into(), status: Status::Ok, detail: detail.into(), fix: None }
+    }
+
+    fn with_fix(name: impl Into<String>, status: Status, detail: impl Into<String>, fix: impl Into<String>) -> Self {
+        Self { name: name.into(), status, detail: detail.into(), fix: Some(fix.into()) }
+    }
+}
+
+/// What `doctor check` looks at
+#[derive(Debug, Clone, Default)]
+pub struct DoctorOptions {
+    /// Comma-separated RPC URLs; the network's endpoints when unset
+    pub rpc_url: Option<String>,
+    /// DEX address or recorded deployment name
+    pub dex: String,
+    /// Wallet address to check; the private key's or the `--account` wallet's when unset
+    pub wallet: Option<String>,
+    pub private_key: Option<String>,
+    pub account: Option<String>,
+    /// Tokens whose wallet balance and DEX allowance to check
+    pub tokens: Vec<String>,
+}
+
+/// Run every check. Checks that depend on an earlier one that failed are left out.
+pub async fn diagnose(options: &DoctorOptions) -> Vec<Check> {
+    let mut checks = Vec::new();
+
+    let rpc_url = match rpc::resolve_rpc_url(options.rpc_url.as_deref()) {
+        Ok(rpc_url) => rpc_url,
+        Err(e) => {
+            checks.push(Check::with_fix("RPC", Status::Fail, e.to_string(), "Pass --rpc-url"));
+            return checks;
+        }
+    };
+    let urls: Vec<&str> = rpc_url.split(',').map(str::trim).filter(|url| !url.is_empty()).collect();
+    if urls.is_empty() {
+        checks.push(Check::with_fix("RPC", Status::Fail, "No RPC URL given", "Pass --rpc-url"));
+        return checks;
+    }
+    for url in &urls {
+        checks.push(check_endpoint(url).await);
+    }
+    if checks.iter().all(|check| check.status == Status::Fail) {
+        return checks;
+    }
+    let provider = match rpc::connect(options.rpc_url.as_deref()).await {
+        Ok(provider) => Arc::new(provider),
+        Err(e) => {
+            checks.push(Check::with_fix("RPC", Status::Fail, e.to_string(), "Check --rpc-url"));
+            return checks;
+        }
+    };
+    checks.push(check_chain(&provider).await);
+    checks.push(check_sync(&provider).await);
+
+    let dex = match aliases::resolve(&options.dex) {
+        Ok(dex) => {
+            let (dex_checks, found) = check_dex(&provider, dex).await;
+            checks.extend(dex_checks);
+            found.then_some(dex)
+        }
+        Err(e) => {
+            checks.push(Check::with_fix(
+                "DEX address",
+                Status::Fail,
+                e.to_string(),
+                "Pass --address with the DEX address, or deploy it with `monad-deploy deploy` so its deployment is recorded",
+            ));
+            None
+        }
+    };
+
+    let wallet = match wallet_address(options) {
+        Ok(Some(wallet)) => wallet,
+        Ok(None) => {
+            checks.push(Check::with_fix(
+                "Wallet",
+                Status::Skip,
+                "No wallet given",
+                "Pass --wallet, --private-key or --account to check gas, balances and allowances",
+            ));
+            return checks;
+        }
+        Err(e) => {
+            checks.push(Check::with_fix("Wallet", Status::Fail, e.to_string(), "Check --wallet, --private-key or --account"));
+            return checks;
+        }
+    };
+    checks.push(check_gas(&provider, wallet).await);
+
+    let mut registry = TokenRegistry::load().unwrap_or_default();
+    for token in &options.tokens {
+        checks.push(match aliases::resolve(token) {
+            Ok(token) => check_token(&provider, &mut registry, dex, wallet, token).await,
+            Err(e) => Check::with_fix(format!("Token {}", token), Status::Fail, e.to_string(), "Pass a token address or a saved alias"),
+        });
+    }
+    checks
+}
+
+/// Each endpoint on its own, without failover or retries, so a dead one shows up
+async fn check_endpoint(url: &str) -> Check {
+    let name = format!("RPC {}", url);
+    let provider = match rpc::http(url) {
+        Ok(http) => Provider::new(http),
+        Err(e) => return Check::with_fix(name, Status::Fail, e.to_string(), "Fix the URL in --rpc-url or the network's auth settings"),
+    };
+    let started = Instant::now();
+    match provider.get_block_number().await {
+        Ok(block) => Check::ok(name, format!("At block {} ({} ms)", block, started.elapsed().as_millis())),
+        Err(e) => Check::with_fix(
+            name,
+            Status::Fail,
+            format!("No answer: {}", e),
+            "Check the URL and API key and that nothing blocks the connection, or list a backup endpoint after a comma",
+        ),
+    }
+}
+
+async fn check_chain(provider: &RpcProvider) -> Check {
+    let expected = rpc::expected_chain_id();
+    match provider.get_chainid().await {
+        Ok(chain_id) if expected == 0 => Check::ok("Chain ID", format!("{} (--chain-id 0 accepts any chain)", chain_id)),
+        Ok(chain_id) if chain_id.as_u64() == expected => Check::ok("Chain ID", chain_id.to_string()),
+        Ok(chain_id) => Check::with_fix(
+            "Chain ID",
+            Status::Fail,
+            format!("The endpoint is on chain {} but chain {} is configured, so nothing will be signed", chain_id, expected),
+            format!("Use an RPC URL for chain {}, or pass --chain-id {} if that network is intended", expected, chain_id),
+        ),
+        Err(e) => Check::with_fix("Chain ID", Status::Fail, e.to_string(), "Use an RPC URL that answers eth_chainId"),
+    }
+}
+
+async fn check_sync(provider: &RpcProvider) -> Check {
+    match provider.get_block(BlockNumber::Latest).await {
+        Ok(Some(block)) => {
+            let age = expiry::unix_secs(SystemTime::now()).saturating_sub(block.timestamp.as_u64());
+            let detail = format!("Latest block is {} s old", age);
+            if age > MAX_BLOCK_AGE_SECS {
+                Check::with_fix("Sync", Status::Warn, detail, "The endpoint may be stalled or still syncing; try another RPC URL")
+            } else {
+                Check::ok("Sync", detail)
+            }
+        }
+        Ok(None) => Check::with_fix("Sync", Status::Warn, "The endpoint has no latest block", "Try another RPC URL"),
+        Err(e) => Check::with_fix("Sync", Status::Warn, e.to_string(), "Try another RPC URL"),
+    }
+}
+
+/// Code at the DEX address, the interface it exposes and whether trading is paused, and
+/// whether there is a contract at all
+async fn check_dex(provider: &Arc<RpcProvider>, dex: Address) -> (Vec<Check>, bool) {
+    let releases = DexReleases::load().unwrap_or_default();
+    let compatibility = match compat::inspect(provider.as_ref(), dex, &releases).await {
+        Ok(compatibility) => compatibility,
+        Err(e) => return (vec![Check::with_fix("DEX code", Status::Fail, e.to_string(), "Retry, or try another RPC URL")], false),
+    };
+    if compatibility.code_size == 0 {
+        let check = Check::with_fix(
+            "DEX code",
+            Status::Fail,
+            format!("No contract at {:?} on this chain", dex),
+            "Pass the right --address, or deploy OrderBookDEX with `monad-deploy deploy`",
+        );
+        return (vec![check], false);
+    }
+
+    let mut checks = Vec::new();
+    let detail = format!("{} bytes at {:?}, code hash {:?}", compatibility.code_size, dex, compatibility.code_hash);
+    checks.push(match &compatibility.release {
+        Some(release) => Check::ok("DEX code", format!("{} (release {})", detail, release)),
+        None if releases.require_known => Check::with_fix(
+            "DEX code",
+            Status::Fail,
+            format!("{}, which matches no known release", detail),
+            "Record it with `monad-dex doctor releases add` if it is trusted, or pass the right --address",
+        ),
+        None => Check::ok("DEX code", detail),
+    });
+    if let Some(implementation) = compatibility.implementation {
+        checks.push(Check::ok("DEX proxy", format!("EIP-1967 proxy for {:?}", implementation)));
+    }
+    checks.push(if compatibility.erc165 {
+        Check::ok("DEX interface", "ERC-165 reports the OrderBookDEX interface")
+    } else if compatibility.missing.is_empty() {
+        Check::ok("DEX interface", format!("All {} functions the commands use are there", compat::REQUIRED_FUNCTIONS.len()))
+    } else {
+        Check::with_fix(
+            "DEX interface",
+            Status::Fail,
+            format!("Missing {}", compatibility.missing.join(", ")),
+            "This isn't an OrderBookDEX these tools can drive; pass the right --address or redeploy from contracts/OrderBookDEX.sol",
+        )
+    });
+
+    checks.push(match OrderBookDEX::new(dex, provider.clone()).paused().call().await {
+        Ok(true) => Check::with_fix(
+            "Trading",
+            Status::Warn,
+            "The DEX is paused, so orders revert",
+            "The owner can resume trading with `monad-dex unpause`",
+        ),
+        Ok(false) => Check::ok("Trading", "Not paused"),
+        Err(_) => Check::ok("Trading", "The contract has no pause switch"),
+    });
+    (checks, true)
+}
+
+fn wallet_address(options: &DoctorOptions) -> Result<Option<Address>> {
+    if let Some(wallet) = &options.wallet {
+        return Ok(Some(aliases::resolve(wallet)?));
+    }
+    if let Some(private_key) = &options.private_key {
+        let wallet = private_key
+            .parse::<LocalWallet>()
+            .map_err(|e| DexError::Input(format!("Failed to parse private key: {}", e)))?;
+        return Ok(Some(wallet.address()));
+    }
+    if let Some(label) = &options.account {
+        let store = WalletStore::load()?;
+        let entry = store
+            .wallets
+            .get(&label.to_lowercase())
+            .ok_or_else(|| DexError::Input(format!("Unknown wallet '{}' (see `monad-dex wallet list`)", label)))?;
+        return Ok(Some(entry.address));
+    }
+    Ok(None)
+}
+
+async fn check_gas(provider: &RpcProvider, wallet: Address) -> Check {
+    let (balance, gas_price) = match (provider.get_balance(wallet, None).await, provider.get_gas_price().await) {
+        (Ok(balance), Ok(gas_price)) => (balance, gas_price),
+        (Err(e), _) | (_, Err(e)) => return Check::with_fix("Gas", Status::Fail, e.to_string(), "Retry, or try another RPC URL"),
+    };
+    let needed = gas_price * ORDER_GAS * MIN_TRANSACTIONS;
+    let detail = format!("{:?} holds {} MON", wallet, format_ether(balance));
+    let faucet = format!("`monad-interact faucet --to {:?}`", wallet);
+    if balance.is_zero() {
+        Check::with_fix("Gas", Status::Fail, detail, format!("Fund it with MON, e.g. {}", faucet))
+    } else if balance < needed {
+        Check::with_fix(
+            "Gas",
+            Status::Warn,
+            detail,
+            format!(
+                "Top it up to at least {} MON, enough for {} order transactions at the current gas price, e.g. {}",
+                format_ether(needed),
+                MIN_TRANSACTIONS,
+                faucet
+            ),
+        )
+    } else {
+        Check::ok("Gas", detail)
+    }
+}
+
+/// Wallet balance of `token` and, with a DEX, the allowance it gave the DEX
+async fn check_token(
+    provider: &Arc<RpcProvider>,
+    registry: &mut TokenRegistry,
+    dex: Option<Address>,
+    wallet: Address,
+    token: Address,
+) -> Check {
+    let name = format!("Token {:?}", token);
+    if token.is_zero() {
+        return Check::ok(name, "Native MON, sent as the order's transaction value; needs no allowance");
+    }
+    match provider.get_code(token, None).await {
+        Ok(code) if code.is_empty() => {
+            return Check::with_fix(name, Status::Fail, "No contract at this address on this chain", "Check the token address")
+        }
+        Ok(_) => {}
+        Err(e) => return Check::with_fix(name, Status::Fail, e.to_string(), "Retry, or try another RPC URL"),
+    }
+
+    let erc20 = ERC20::new(token, provider.clone());
+    let balance = match erc20.balance_of(wallet).call().await {
+        Ok(balance) => balance,
+        Err(e) => return Check::with_fix(name, Status::Fail, format!("balanceOf failed: {}", e), "Check that this is an ERC-20 token"),
+    };
+    let held = registry.display(provider.clone(), token, balance).await;
+    let Some(dex) = dex else {
+        return Check::ok(name, format!("Wallet holds {}; allowance not checked without a DEX", held));
+    };
+    let allowance = match erc20.allowance(wallet, dex).call().await {
+        Ok(allowance) => allowance,
+        Err(e) => return Check::with_fix(name, Status::Fail, format!("allowance failed: {}", e), "Check that this is an ERC-20 token"),
+    };
+    let detail = format!("Wallet holds {}, DEX allowance {}", held, registry.display(provider.clone(), token, allowance).await);
+
+    if allowance.is_zero() {
+        Check::with_fix(
+            name,
+            Status::Warn,
+            detail,
+            format!(
+                "Bots and market orders need the DEX approved up front: `cast send {:?} \"approve(address,uint256)\" {:?} <amount> --rpc-url <url> --private-key <key>` (`place-limit-order --auto-deposit` approves single orders itself)",
+                token, dex
+            ),
+        )
+    } else if balance.is_zero() {
+        Check::with_fix(name, Status::Warn, detail, "Fund the wallet with this token, or withdraw it from the DEX balance")
+    } else {
+        Check::ok(name, detail)
+    }
+}
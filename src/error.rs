@@ -0,0 +1,305 @@
+use crate::bot::SignerClient;
+use crate::rpc::RpcProvider;
+use ethers::{
+    abi,
+    contract::{AbiError, ContractError, EthError},
+    providers::{HttpClientError, JsonRpcError, Middleware, MiddlewareError, ProviderError, RpcError},
+    types::{transaction::eip2718::TypedTransaction, TransactionReceipt, TransactionRequest, H256, U256},
+    utils::hex,
+};
+use serde::Serialize;
+use std::io;
+use std::process::ExitCode;
+use std::sync::Arc;
+use thiserror::Error;
+
+/// Exit codes of the binaries, shown in `--help`
+pub const EXIT_CODES: &str = "Exit codes: 0 success, 1 other error, 2 invalid input, 3 config error, \
+    4 RPC error, 5 call would revert, 6 transaction reverted";
+
+/// What went wrong, in categories scripts can branch on. The library APIs return it
+/// through [`Result`]; in the binaries it travels inside `anyhow::Error` and [`classify`]
+/// recovers it.
+#[derive(Debug, Clone, Error)]
+pub enum DexError {
+    /// A config file, stored setting or deployment record is missing or invalid
+    #[error("{0}")]
+    Config(String),
+    /// The RPC endpoint was unreachable or rejected the request
+    #[error("{0}")]
+    Rpc(String),
+    /// Simulating the call (`eth_call` / `eth_estimateGas`) reverted, so nothing was sent
+    #[error("Call would revert: {}", reason.as_deref().unwrap_or("no reason given"))]
+    SimulationRevert { reason: Option<String> },
+    /// The transaction was mined but reverted
+    #[error("Transaction {tx_hash:?} reverted: {}", reason.as_deref().unwrap_or("no reason given"))]
+    Reverted { tx_hash: H256, reason: Option<String> },
+    /// An argument or input file is invalid
+    #[error("{0}")]
+    Input(String),
+    /// Anything outside the categories above
+    #[error("{0:#}")]
+    Other(Arc<anyhow::Error>),
+}
+
+/// `Result` of the APIs that fail with a [`DexError`]
+pub type Result<T, E = DexError> = std::result::Result<T, E>;
+
+impl DexError {
+    /// Process exit code for the category; anything unclassified exits with 1
+    pub fn exit_code(&self) -> u8 {
+        match self {
+            DexError::Input(_) => 2,
+            DexError::Config(_) => 3,
+            DexError::Rpc(_) => 4,
+            DexError::SimulationRevert { .. } => 5,
+            DexError::Reverted { .. } => 6,
+            DexError::Other(_) => 1,
+        }
+    }
+
+    /// Name of the category in [`ErrorReport`]s
+    pub fn category(&self) -> &'static str {
+        match self {
+            DexError::Input(_) => "input",
+            DexError::Config(_) => "config",
+            DexError::Rpc(_) => "rpc",
+            DexError::SimulationRevert { .. } => "simulation_revert",
+            DexError::Reverted { .. } => "reverted",
+            DexError::Other(_) => "other",
+        }
+    }
+
+    /// The category of an error from any middleware: a revert the node reported while
+    /// simulating, else an RPC failure
+    pub fn middleware<E: MiddlewareError>(error: &E) -> Self {
+        match error.as_error_response() {
+            Some(response) if response.is_revert() => {
+                DexError::SimulationRevert { reason: response.as_revert_data().and_then(|data| revert_reason(&data)) }
+            }
+            _ => DexError::Rpc(error.to_string()),
+        }
+    }
+
+    /// Replay a reverted transaction at its block to recover the revert reason
+    pub async fn reverted<M: Middleware>(client: &M, receipt: &TransactionReceipt) -> Self {
+        let tx_hash = receipt.transaction_hash;
+        let reason = match client.get_transaction(tx_hash).await {
+            Ok(Some(tx)) => {
+                let mut call = TransactionRequest::new().from(tx.from).data(tx.input).value(tx.value).gas(tx.gas);
+                if let Some(to) = tx.to {
+                    call = call.to(to);
+                }
+                let call: TypedTransaction = call.into();
+                match client.call(&call, receipt.block_number.map(Into::into)).await {
+                    Err(e) => e.as_error_response().and_then(JsonRpcError::as_revert_data).and_then(|data| revert_reason(&data)),
+                    // The state it reverted on has changed since
+                    Ok(_) => None,
+                }
+            }
+            _ => None,
+        };
+        DexError::Reverted { tx_hash, reason }
+    }
+}
+
+/// Keeps the category of a [`DexError`] anywhere in the error, as [`classify`] finds it
+impl From<anyhow::Error> for DexError {
+    fn from(error: anyhow::Error) -> Self {
+        classify(&error).unwrap_or_else(|| DexError::Other(Arc::new(error)))
+    }
+}
+
+impl<M: Middleware> From<ContractError<M>> for DexError {
+    fn from(error: ContractError<M>) -> Self {
+        (&error).into()
+    }
+}
+
+impl From<ProviderError> for DexError {
+    fn from(error: ProviderError) -> Self {
+        from_provider(&error)
+    }
+}
+
+impl From<io::Error> for DexError {
+    fn from(error: io::Error) -> Self {
+        DexError::Other(Arc::new(error.into()))
+    }
+}
+
+impl From<serde_json::Error> for DexError {
+    fn from(error: serde_json::Error) -> Self {
+        DexError::Other(Arc::new(error.into()))
+    }
+}
+
+impl From<csv::Error> for DexError {
+    fn from(error: csv::Error) -> Self {
+        DexError::Other(Arc::new(error.into()))
+    }
+}
+
+impl From<AbiError> for DexError {
+    fn from(error: AbiError) -> Self {
+        DexError::Other(Arc::new(error.into()))
+    }
+}
+
+impl From<abi::Error> for DexError {
+    fn from(error: abi::Error) -> Self {
+        DexError::Other(Arc::new(error.into()))
+    }
+}
+
+/// Like the transports' own failures, an HTTP error means the endpoint couldn't be reached
+impl From<reqwest::Error> for DexError {
+    fn from(error: reqwest::Error) -> Self {
+        DexError::Rpc(error.to_string())
+    }
+}
+
+impl<M: Middleware> From<&ContractError<M>> for DexError {
+    fn from(error: &ContractError<M>) -> Self {
+        match error {
+            ContractError::Revert(data) => DexError::SimulationRevert { reason: revert_reason(data) },
+            ContractError::MiddlewareError { e } => DexError::middleware(e),
+            ContractError::ProviderError { e } => from_provider(e),
+            ContractError::ContractNotDeployed => DexError::Config(error.to_string()),
+            // Undecodable output: the address doesn't hold the contract the ABI describes
+            _ => DexError::Config(format!("{} (is the contract address right?)", error)),
+        }
+    }
+}
+
+fn from_provider(error: &ProviderError) -> DexError {
+    match RpcError::as_error_response(error) {
+        Some(response) if response.is_revert() => {
+            DexError::SimulationRevert { reason: response.as_revert_data().and_then(|data| revert_reason(data.as_ref())) }
+        }
+        _ => DexError::Rpc(error.to_string()),
+    }
+}
+
+/// Solidity's `Panic(uint256)` selector
+const PANIC_SELECTOR: [u8; 4] = [0x4e, 0x48, 0x7b, 0x71];
+
+/// The `require` message, panic code or raw data a call reverted with
+pub fn revert_reason(data: &[u8]) -> Option<String> {
+    if let Some(reason) = <String as EthError>::decode_with_selector(data) {
+        return Some(reason);
+    }
+    if data.len() == 36 && data[..4] == PANIC_SELECTOR {
+        return Some(format!("panic 0x{:02x}", U256::from_big_endian(&data[4..])));
+    }
+    (!data.is_empty()).then(|| format!("0x{}", hex::encode(data)))
+}
+
+/// The category of `error`: a [`DexError`] raised or attached as context anywhere in it,
+/// else one inferred from the contract, provider or transport error underneath
+pub fn classify(error: &anyhow::Error) -> Option<DexError> {
+    if let Some(error) = error.downcast_ref::<DexError>() {
+        return match error {
+            DexError::Other(_) => None,
+            error => Some(error.clone()),
+        };
+    }
+    if let Some(error) = error.downcast_ref::<ContractError<SignerClient>>() {
+        return Some(error.into());
+    }
+    if let Some(error) = error.downcast_ref::<ContractError<RpcProvider>>() {
+        return Some(error.into());
+    }
+    error.chain().find_map(|cause| {
+        if let Some(error) = cause.downcast_ref::<ProviderError>() {
+            Some(from_provider(error))
+        } else if cause.is::<HttpClientError>() || cause.is::<reqwest::Error>() {
+            Some(DexError::Rpc(cause.to_string()))
+        } else {
+            None
+        }
+    })
+}
+
+/// A failure as written to stderr under `--output json`
+#[derive(Debug, Clone, Serialize)]
+pub struct ErrorReport {
+    /// `input`, `config`, `rpc`, `simulation_revert`, `reverted` or `other`
+    pub category: &'static str,
+    /// The error and its causes, outermost first
+    pub message: String,
+    /// Decoded revert reason, for reverts
+    pub reason: Option<String>,
+    /// The reverted transaction, for on-chain reverts
+    pub tx_hash: Option<H256>,
+    pub exit_code: u8,
+}
+
+impl ErrorReport {
+    pub fn new(error: &anyhow::Error) -> Self {
+        let classified = classify(error);
+        let (reason, tx_hash) = match &classified {
+            Some(DexError::SimulationRevert { reason }) => (reason.clone(), None),
+            Some(DexError::Reverted { tx_hash, reason }) => (reason.clone(), Some(*tx_hash)),
+            _ => (None, None),
+        };
+        Self {
+            category: classified.as_ref().map_or("other", DexError::category),
+            message: format!("{:#}", error),
+            reason,
+            tx_hash,
+            exit_code: classified.as_ref().map_or(1, DexError::exit_code),
+        }
+    }
+}
+
+/// Print `error` the way a failed `main` would and pick the exit code for its category
+pub fn report(error: &anyhow::Error) -> ExitCode {
+    eprintln!("Error: {:?}", error);
+    ExitCode::from(classify(error).map_or(1, |error| error.exit_code()))
+}
+
+/// [`report`] for `--output json`: one `{"error": ...}` line on stderr
+pub fn report_json(error: &anyhow::Error) -> ExitCode {
+    let report = ErrorReport::new(error);
+    eprintln!("{}", serde_json::json!({ "error": &report }));
+    ExitCode::from(report.exit_code)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::anyhow;
+
+    #[test]
+    fn converting_from_anyhow_keeps_the_category() {
+        let error = anyhow::Error::from(DexError::Input("bad amount".to_string())).context("Placing the order");
+        let error = DexError::from(error);
+        assert_eq!(error.category(), "input");
+        assert_eq!(error.exit_code(), 2);
+    }
+
+    #[test]
+    fn unclassified_errors_exit_with_one() {
+        let error = anyhow::Error::from(DexError::from(anyhow!("disk full"))).context("Saving the journal");
+        assert!(classify(&error).is_none());
+        assert_eq!(ErrorReport::new(&error).category, "other");
+        assert_eq!(ErrorReport::new(&error).exit_code, 1);
+        assert_eq!(ErrorReport::new(&error).message, "Saving the journal: disk full");
+    }
+
+    #[test]
+    fn revert_reasons_decode_require_messages_and_panics() {
+        let mut require = vec![0x08, 0xc3, 0x79, 0xa0];
+        require.extend(ethers::abi::encode(&[ethers::abi::Token::String("Order expired".to_string())]));
+        assert_eq!(revert_reason(&require).as_deref(), Some("Order expired"));
+
+        let mut panic = PANIC_SELECTOR.to_vec();
+        panic.extend([0; 31]);
+        panic.push(0x11);
+        assert_eq!(revert_reason(&panic).as_deref(), Some("panic 0x11"));
+
+        assert_eq!(revert_reason(&[]), None);
+        assert_eq!(revert_reason(&[0xde, 0xad]).as_deref(), Some("0xdead"));
+    }
+}
@@ -0,0 +1,112 @@
+use crate::bindings::{OrderMatchedFilter, OrderPlacedFilter};
+use ethers::{
+    abi::{Abi, RawLog, Token},
+    contract::parse_log,
+    types::{Log, TransactionReceipt, H256, U256},
+};
+use serde::Serialize;
+
+/// A log matched against an event in a contract ABI
+#[derive(Debug, Clone)]
+pub struct DecodedLog {
+    pub event: String,
+    pub params: Vec<(String, Token)>,
+}
+
+/// Decode a log using the first event in `abi` whose signature matches topic 0
+pub fn decode_log(abi: &Abi, log: &Log) -> Option<DecodedLog> {
+    let topic = log.topics.first()?;
+    let event = abi.events().find(|event| event.signature() == *topic)?;
+
+    let parsed = event
+        .parse_log(RawLog {
+            topics: log.topics.clone(),
+            data: log.data.to_vec(),
+        })
+        .ok()?;
+
+    Some(DecodedLog {
+        event: event.name.clone(),
+        params: parsed
+            .params
+            .into_iter()
+            .map(|param| (param.name, param.value))
+            .collect(),
+    })
+}
+
+/// Decode a log with the first of `abis` that has a matching event, along with that ABI
+pub fn decode_any<'a>(abis: &[&'a Abi], log: &Log) -> Option<(&'a Abi, DecodedLog)> {
+    abis.iter().find_map(|abi| decode_log(abi, log).map(|decoded| (*abi, decoded)))
+}
+
+/// Order ID from the OrderPlaced event in a placement receipt
+pub fn placed_order_id(receipt: &TransactionReceipt) -> Option<U256> {
+    receipt
+        .logs
+        .iter()
+        .find_map(|log| parse_log::<OrderPlacedFilter>(log.clone()).ok())
+        .map(|event| event.order_id)
+}
+
+/// A match the placing transaction triggered against the new order
+#[derive(Debug, Clone, Serialize)]
+pub struct ImmediateFill {
+    /// The resting order on the other side
+    pub counter_order_id: U256,
+    pub amount: U256,
+    pub price: U256,
+}
+
+/// What a placement receipt says about the new order
+#[derive(Debug, Clone, Serialize)]
+pub struct Placement {
+    pub tx_hash: H256,
+    pub order_id: Option<U256>,
+    pub fills: Vec<ImmediateFill>,
+}
+
+impl Placement {
+    pub fn from_receipt(receipt: &TransactionReceipt) -> Self {
+        let order_id = placed_order_id(receipt);
+        let fills = receipt
+            .logs
+            .iter()
+            .filter_map(|log| parse_log::<OrderMatchedFilter>(log.clone()).ok())
+            .filter_map(|event| {
+                let counter_order_id = if Some(event.buy_order_id) == order_id {
+                    event.sell_order_id
+                } else if Some(event.sell_order_id) == order_id {
+                    event.buy_order_id
+                } else {
+                    return None;
+                };
+                Some(ImmediateFill { counter_order_id, amount: event.amount, price: event.price })
+            })
+            .collect();
+        Self { tx_hash: receipt.transaction_hash, order_id, fills }
+    }
+
+    pub fn filled(&self) -> U256 {
+        self.fills.iter().fold(U256::zero(), |total, fill| total + fill.amount)
+    }
+}
+
+/// Print a log, decoded if possible, indented for command output
+pub fn print_log(index: usize, abi: Option<&Abi>, log: &Log) {
+    match abi.and_then(|abi| decode_log(abi, log)) {
+        Some(decoded) => {
+            println!("  [{}] {} ({:?})", index, decoded.event, log.address);
+            for (name, value) in decoded.params {
+                println!("      {}: {}", name, value);
+            }
+        }
+        None => {
+            println!("  [{}] Unknown event ({:?})", index, log.address);
+            for (i, topic) in log.topics.iter().enumerate() {
+                println!("      topic{}: {:?}", i, topic);
+            }
+            println!("      data: {}", log.data);
+        }
+    }
+}
@@ -0,0 +1,207 @@
+use crate::bindings::OrderBookDEX;
+use crate::bot::SignerClient;
+use crate::error::Result;
+use crate::tx::{self, TxArgs};
+use anyhow::Context;
+use ethers::{
+    providers::Middleware,
+    types::{Address, U256},
+};
+use fs2::FileExt;
+use serde::{Deserialize, Serialize};
+use std::fs::{self, File, OpenOptions};
+use std::path::Path;
+use std::sync::Arc;
+use std::time::{SystemTime, UNIX_EPOCH};
+use tracing::{info, warn};
+
+const EXPIRY_JOURNAL_PATH: &str = "config/expiries.json";
+const EXPIRY_LOCK_PATH: &str = "config/expiries.lock";
+
+/// A good-til-date order the expiry daemon cancels once `expires_at` passes
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ExpiringOrder {
+    pub dex: Address,
+    pub order_id: u64,
+    pub trader: Address,
+    /// Unix time in seconds
+    pub expires_at: u64,
+}
+
+impl ExpiringOrder {
+    pub fn is_due(&self, now: u64) -> bool {
+        now >= self.expires_at
+    }
+}
+
+/// Orders placed with `--expires-at`/`--ttl`/`--cancel-after`, since the contract has no
+/// native expiry. Kept on disk so a restarted `expire-orders` still cancels what came due
+/// while it was down.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ExpiryJournal {
+    pub orders: Vec<ExpiringOrder>,
+}
+
+impl ExpiryJournal {
+    pub fn load() -> Result<Self> {
+        let lock = lock()?;
+        lock.lock_shared().with_context(|| format!("Failed to lock {}", EXPIRY_LOCK_PATH))?;
+        Self::read()
+    }
+
+    /// Change the journal on disk under an exclusive lock, so an order recorded by one
+    /// process while another cancels expired ones isn't lost
+    pub fn update<R>(change: impl FnOnce(&mut Self) -> R) -> Result<R> {
+        let lock = lock()?;
+        lock.lock_exclusive().with_context(|| format!("Failed to lock {}", EXPIRY_LOCK_PATH))?;
+        let mut journal = Self::read()?;
+        let result = change(&mut journal);
+        journal.write()?;
+        Ok(result)
+    }
+
+    /// The caller holds the lock
+    fn read() -> Result<Self> {
+        if !Path::new(EXPIRY_JOURNAL_PATH).exists() {
+            return Ok(Self::default());
+        }
+        let content = fs::read_to_string(EXPIRY_JOURNAL_PATH)
+            .with_context(|| format!("Failed to read {}", EXPIRY_JOURNAL_PATH))?;
+        Ok(serde_json::from_str(&content)?)
+    }
+
+    /// Written to a temporary file and renamed over the old one, so readers never see
+    /// half of it. The caller holds the lock.
+    fn write(&self) -> Result<()> {
+        let temp = format!("{}.tmp", EXPIRY_JOURNAL_PATH);
+        fs::write(&temp, serde_json::to_string_pretty(self)?).with_context(|| format!("Failed to write {}", temp))?;
+        fs::rename(&temp, EXPIRY_JOURNAL_PATH).with_context(|| format!("Failed to replace {}", EXPIRY_JOURNAL_PATH))?;
+        Ok(())
+    }
+
+    pub fn record(&mut self, order: ExpiringOrder) {
+        self.orders.retain(|o| !(o.dex == order.dex && o.order_id == order.order_id));
+        self.orders.push(order);
+        self.orders.sort_by_key(|o| o.expires_at);
+    }
+
+    pub fn remove(&mut self, dex: Address, order_id: u64) {
+        self.orders.retain(|o| !(o.dex == dex && o.order_id == order_id));
+    }
+}
+
+/// One pass of `expire-orders`: cancel the journaled orders of the signer whose expiry has
+/// passed, dropping any that were filled or cancelled in the meantime. Returns how many
+/// orders left the journal.
+pub async fn expire_due(client: Arc<SignerClient>, tx_args: &TxArgs) -> Result<usize> {
+    let trader = client.address();
+    // Fees move over the lifetime of the daemon, so they are resolved for every pass
+    let fees = tx_args.fees.resolve(client.inner()).await?;
+    let now = unix_secs(SystemTime::now());
+    let due: Vec<ExpiringOrder> = ExpiryJournal::load()?
+        .orders
+        .into_iter()
+        .filter(|order| order.trader == trader && order.is_due(now))
+        .collect();
+
+    let mut settled = Vec::new();
+    for order in due {
+        let contract = OrderBookDEX::new(order.dex, client.clone());
+        let (_, _, _, _, _, _, _, is_active, _) = contract.orders(U256::from(order.order_id)).call().await?;
+        if !is_active {
+            info!("Order {} is no longer open, dropping it from the journal", order.order_id);
+            settled.push(order);
+            continue;
+        }
+
+        info!("Order {} expired, cancelling", order.order_id);
+        let method = contract.cancel_order(U256::from(order.order_id));
+        match tx::send(client.as_ref(), method, &fees, &tx_args.step(format!("cancel/{}", order.order_id))).await {
+            Ok(Some(receipt)) => match tx::ensure_success(client.as_ref(), &receipt).await {
+                Ok(()) => {
+                    info!("Order {} cancelled (tx {:?})", order.order_id, receipt.transaction_hash);
+                    settled.push(order);
+                }
+                Err(e) => warn!("Cancelling expired order {} reverted: {}", order.order_id, e),
+            },
+            // Estimates and dropped transactions stay in the journal for the next pass
+            Ok(None) => {}
+            Err(e) => warn!("Failed to cancel expired order {}: {}", order.order_id, e),
+        }
+    }
+    // Only what was settled here is removed, from the journal as it is now: orders
+    // placed while this pass ran are kept
+    if !tx_args.estimate_only && !settled.is_empty() {
+        ExpiryJournal::update(|journal| {
+            for order in &settled {
+                journal.remove(order.dex, order.order_id);
+            }
+        })?;
+    }
+    Ok(settled.len())
+}
+
+/// The journal's lock file, opened but not yet locked
+fn lock() -> Result<File> {
+    fs::create_dir_all("config")?;
+    let file = OpenOptions::new()
+        .create(true)
+        .truncate(false)
+        .write(true)
+        .open(EXPIRY_LOCK_PATH)
+        .with_context(|| format!("Failed to open {}", EXPIRY_LOCK_PATH))?;
+    Ok(file)
+}
+
+/// Seconds since the Unix epoch for `time`
+pub fn unix_secs(time: SystemTime) -> u64 {
+    time.duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DEX: Address = Address::repeat_byte(1);
+    const TRADER: Address = Address::repeat_byte(2);
+
+    fn expiring(order_id: u64, expires_at: u64) -> ExpiringOrder {
+        ExpiringOrder { dex: DEX, order_id, trader: TRADER, expires_at }
+    }
+
+    #[test]
+    fn orders_come_due_once_their_expiry_passes() {
+        let order = expiring(1, 100);
+        assert!(!order.is_due(99));
+        assert!(order.is_due(100));
+        assert!(order.is_due(101));
+    }
+
+    #[test]
+    fn recording_keeps_the_journal_ordered_by_expiry() {
+        let mut journal = ExpiryJournal::default();
+        journal.record(expiring(1, 300));
+        journal.record(expiring(2, 100));
+        journal.record(expiring(3, 200));
+        let ids: Vec<u64> = journal.orders.iter().map(|order| order.order_id).collect();
+        assert_eq!(ids, [2, 3, 1]);
+    }
+
+    #[test]
+    fn recording_an_order_again_replaces_its_expiry() {
+        let mut journal = ExpiryJournal::default();
+        journal.record(expiring(1, 300));
+        journal.record(expiring(1, 100));
+        assert_eq!(journal.orders, [expiring(1, 100)]);
+    }
+
+    #[test]
+    fn removing_only_drops_the_order_on_that_dex() {
+        let mut journal = ExpiryJournal::default();
+        journal.record(expiring(1, 100));
+        journal.record(ExpiringOrder { dex: Address::repeat_byte(9), ..expiring(1, 200) });
+        journal.remove(DEX, 1);
+        assert_eq!(journal.orders.len(), 1);
+        assert_eq!(journal.orders[0].dex, Address::repeat_byte(9));
+    }
+}
@@ -0,0 +1,138 @@
+use crate::error::{DexError, Result};
+use crate::rpc;
+use anyhow::{anyhow, Context};
+use ethers::{abi::Abi, providers::Middleware, types::Address};
+use std::collections::hash_map::{Entry, HashMap};
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+use tracing::{info, warn};
+
+/// Etherscan-compatible API serving verified contracts on Monad, queried with `chainid`
+pub const DEFAULT_EXPLORER_API: &str = "https://api.etherscan.io/v2/api";
+
+/// Overrides the `--network` explorer API, e.g. with a Blockscout instance's `/api`
+pub const EXPLORER_API_VAR: &str = "MONAD_EXPLORER_API";
+
+/// API key sent with explorer requests, for explorers that require one
+pub const EXPLORER_API_KEY_VAR: &str = "MONAD_EXPLORER_API_KEY";
+
+const ABI_CACHE_DIR: &str = "config/abis";
+
+fn cache_path(chain_id: u64, address: Address) -> PathBuf {
+    PathBuf::from(ABI_CACHE_DIR)
+        .join(chain_id.to_string())
+        .join(format!("{:?}.json", address))
+}
+
+/// ABI cached by an earlier fetch, if any
+pub fn cached(chain_id: u64, address: Address) -> Result<Option<Abi>> {
+    let path = cache_path(chain_id, address);
+    if !path.exists() {
+        return Ok(None);
+    }
+    let content = fs::read_to_string(&path).with_context(|| format!("Failed to read {}", path.display()))?;
+    let abi = serde_json::from_str(&content).with_context(|| format!("{} is not an ABI", path.display()))?;
+    Ok(Some(abi))
+}
+
+fn save(chain_id: u64, address: Address, abi: &Abi) -> Result<()> {
+    let path = cache_path(chain_id, address);
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    fs::write(&path, serde_json::to_string_pretty(abi)?)?;
+    Ok(())
+}
+
+/// Ask the explorer for the verified ABI of `address`
+pub async fn fetch(chain_id: u64, address: Address) -> Result<Abi> {
+    let api = match env::var(EXPLORER_API_VAR) {
+        Ok(api) => api,
+        Err(_) => rpc::network()?.explorer_api.ok_or_else(|| {
+            DexError::Config(format!("Network {} has no explorer API; set {} or pass an artifact", rpc::network_name(), EXPLORER_API_VAR))
+        })?,
+    };
+    let mut query = vec![
+        ("chainid", chain_id.to_string()),
+        ("module", "contract".to_string()),
+        ("action", "getabi".to_string()),
+        ("address", format!("{:?}", address)),
+    ];
+    if let Ok(key) = env::var(EXPLORER_API_KEY_VAR) {
+        query.push(("apikey", key));
+    }
+
+    let json: serde_json::Value = rpc::client_builder()?
+        .build()?
+        .get(&api)
+        .query(&query)
+        .send()
+        .await?
+        .error_for_status()?
+        .json()
+        .await?;
+
+    // Etherscan-style replies carry the ABI as a JSON string in `result`, or the reason in it
+    let result = json["result"].as_str().unwrap_or_default();
+    if json["status"].as_str() != Some("1") {
+        let reason = if result.is_empty() { json["message"].as_str().unwrap_or("no reason given") } else { result };
+        return Err(anyhow!("Explorer has no verified ABI for {:?}: {}", address, reason).into());
+    }
+    Ok(serde_json::from_str(result).with_context(|| format!("Explorer returned an invalid ABI for {:?}", address))?)
+}
+
+async fn chain_id<M: Middleware + 'static>(client: &M) -> Result<u64> {
+    let chain_id = client
+        .get_chainid()
+        .await
+        .map_err(|e| DexError::Rpc(format!("Failed to get the chain ID: {}", e)))?;
+    Ok(chain_id.as_u64())
+}
+
+/// ABI of `address` on the client's chain: from the cache, or fetched from the explorer
+/// and cached for next time
+pub async fn abi<M: Middleware + 'static>(client: &M, address: Address) -> Result<Abi> {
+    let chain_id = chain_id(client).await?;
+    if rpc::cache_enabled() {
+        if let Some(abi) = cached(chain_id, address)? {
+            return Ok(abi);
+        }
+    }
+
+    info!("Fetching the verified ABI of {:?} from the explorer", address);
+    let abi = fetch(chain_id, address).await?;
+    save(chain_id, address, &abi)?;
+    Ok(abi)
+}
+
+/// Fetch the ABI of `address` again, replacing the cached one
+pub async fn refresh<M: Middleware + 'static>(client: &M, address: Address) -> Result<Abi> {
+    let chain_id = chain_id(client).await?;
+    let abi = fetch(chain_id, address).await?;
+    save(chain_id, address, &abi)?;
+    Ok(abi)
+}
+
+/// ABIs of the contracts a batch of logs came from, looked up once per address; addresses
+/// the explorer has nothing verified for are remembered as such and shown raw
+#[derive(Default)]
+pub struct AbiLookup {
+    abis: HashMap<Address, Option<Abi>>,
+}
+
+impl AbiLookup {
+    pub async fn get<M: Middleware + 'static>(&mut self, client: &M, address: Address) -> Option<&Abi> {
+        if let Entry::Vacant(entry) = self.abis.entry(address) {
+            let abi = match abi(client, address).await {
+                Ok(abi) => Some(abi),
+                Err(e) => {
+                    warn!("{}", e);
+                    None
+                }
+            };
+            entry.insert(abi);
+        }
+        self.abis.get(&address).and_then(Option::as_ref)
+    }
+}
@@ -0,0 +1,226 @@
+use crate::error::{DexError, Result};
+use crate::indexer::{EventStore, PlacedOrder, Trade};
+use clap::ValueEnum;
+use ethers::types::{Address, U256};
+#[cfg(feature = "parquet")]
+use parquet::{
+    basic::Compression,
+    data_type::{BoolType, ByteArray, ByteArrayType, Int64Type},
+    file::{properties::WriterProperties, writer::SerializedFileWriter},
+    schema::parser::parse_message_type,
+};
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs::File;
+use std::io::{self, Write};
+#[cfg(feature = "parquet")]
+use std::sync::Arc;
+
+/// File formats `export` writes
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum ExportFormat {
+    Csv,
+    Parquet,
+}
+
+impl ExportFormat {
+    pub fn extension(self) -> &'static str {
+        match self {
+            ExportFormat::Csv => "csv",
+            ExportFormat::Parquet => "parquet",
+        }
+    }
+}
+
+/// Which indexed events to export
+#[derive(Debug, Clone, Default)]
+pub struct ExportFilter {
+    pub pair: Option<(Address, Address)>,
+    pub from_block: Option<u64>,
+    pub to_block: Option<u64>,
+}
+
+impl ExportFilter {
+    fn matches(&self, base_token: Address, quote_token: Address, block: u64) -> bool {
+        self.pair.is_none_or(|pair| pair == (base_token, quote_token))
+            && self.from_block.is_none_or(|from| block >= from)
+            && self.to_block.is_none_or(|to| block <= to)
+    }
+}
+
+/// Values of one column; token amounts don't fit 64 bits, so they are decimal strings
+#[derive(Debug, Clone)]
+enum Values {
+    Int64(Vec<i64>),
+    Bool(Vec<bool>),
+    Utf8(Vec<String>),
+}
+
+impl Values {
+    fn len(&self) -> usize {
+        match self {
+            Values::Int64(values) => values.len(),
+            Values::Bool(values) => values.len(),
+            Values::Utf8(values) => values.len(),
+        }
+    }
+
+    fn cell(&self, row: usize) -> String {
+        match self {
+            Values::Int64(values) => values[row].to_string(),
+            Values::Bool(values) => values[row].to_string(),
+            Values::Utf8(values) => values[row].clone(),
+        }
+    }
+
+    #[cfg(feature = "parquet")]
+    fn parquet_type(&self) -> &'static str {
+        match self {
+            Values::Int64(_) => "INT64",
+            Values::Bool(_) => "BOOLEAN",
+            Values::Utf8(_) => "BYTE_ARRAY",
+        }
+    }
+}
+
+/// Indexed events laid out as named columns, ready for either format
+#[derive(Debug, Clone)]
+pub struct Table {
+    /// The Parquet schema's message name
+    #[cfg_attr(not(feature = "parquet"), allow(dead_code))]
+    name: &'static str,
+    columns: Vec<(&'static str, Values)>,
+}
+
+impl Table {
+    /// Every indexed `OrderMatched` event, oldest first
+    pub fn trades(store: &EventStore, filter: &ExportFilter) -> Self {
+        let trades: Vec<&Trade> = store
+            .trades
+            .iter()
+            .filter(|trade| filter.matches(trade.base_token, trade.quote_token, trade.block))
+            .collect();
+        Self {
+            name: "trades",
+            columns: vec![
+                ("block", Values::Int64(trades.iter().map(|trade| trade.block as i64).collect())),
+                ("tx_hash", strings(trades.iter().map(|trade| format!("{:?}", trade.tx_hash)))),
+                ("base_token", strings(trades.iter().map(|trade| format!("{:?}", trade.base_token)))),
+                ("quote_token", strings(trades.iter().map(|trade| format!("{:?}", trade.quote_token)))),
+                ("buy_order_id", decimals(trades.iter().map(|trade| trade.buy_order_id))),
+                ("sell_order_id", decimals(trades.iter().map(|trade| trade.sell_order_id))),
+                ("amount", decimals(trades.iter().map(|trade| trade.amount))),
+                ("price", decimals(trades.iter().map(|trade| trade.price))),
+                ("fee", decimals(trades.iter().map(|trade| trade.fee))),
+            ],
+        }
+    }
+
+    /// Every indexed `OrderPlaced` event, with how much of it has been filled and whether
+    /// it was cancelled
+    pub fn orders(store: &EventStore, filter: &ExportFilter) -> Self {
+        let mut filled: BTreeMap<U256, U256> = BTreeMap::new();
+        for trade in &store.trades {
+            *filled.entry(trade.buy_order_id).or_default() += trade.matched();
+            *filled.entry(trade.sell_order_id).or_default() += trade.matched();
+        }
+        let cancelled: BTreeSet<U256> = store.cancellations.iter().map(|cancellation| cancellation.order_id).collect();
+
+        let orders: Vec<&PlacedOrder> = store
+            .orders
+            .iter()
+            .filter(|order| filter.matches(order.base_token, order.quote_token, order.block))
+            .collect();
+        Self {
+            name: "orders",
+            columns: vec![
+                ("block", Values::Int64(orders.iter().map(|order| order.block as i64).collect())),
+                ("tx_hash", strings(orders.iter().map(|order| format!("{:?}", order.tx_hash)))),
+                ("order_id", decimals(orders.iter().map(|order| order.order_id))),
+                ("trader", strings(orders.iter().map(|order| format!("{:?}", order.trader)))),
+                ("base_token", strings(orders.iter().map(|order| format!("{:?}", order.base_token)))),
+                ("quote_token", strings(orders.iter().map(|order| format!("{:?}", order.quote_token)))),
+                ("is_buy", Values::Bool(orders.iter().map(|order| order.is_buy).collect())),
+                ("amount", decimals(orders.iter().map(|order| order.amount))),
+                ("price", decimals(orders.iter().map(|order| order.price))),
+                ("filled", decimals(orders.iter().map(|order| filled.get(&order.order_id).copied().unwrap_or_default()))),
+                ("cancelled", Values::Bool(orders.iter().map(|order| cancelled.contains(&order.order_id)).collect())),
+            ],
+        }
+    }
+
+    pub fn rows(&self) -> usize {
+        self.columns.first().map_or(0, |(_, values)| values.len())
+    }
+
+    pub fn write_csv<W: Write>(&self, writer: W) -> Result<()> {
+        let mut writer = csv::Writer::from_writer(writer);
+        writer.write_record(self.columns.iter().map(|(name, _)| *name))?;
+        for row in 0..self.rows() {
+            writer.write_record(self.columns.iter().map(|(_, values)| values.cell(row)))?;
+        }
+        writer.flush()?;
+        Ok(())
+    }
+
+    /// Write the table as a single Snappy-compressed row group
+    #[cfg(feature = "parquet")]
+    pub fn write_parquet(&self, file: File) -> Result<()> {
+        let fields: Vec<String> = self
+            .columns
+            .iter()
+            .map(|(name, values)| match values {
+                Values::Utf8(_) => format!("REQUIRED {} {} (UTF8);", values.parquet_type(), name),
+                _ => format!("REQUIRED {} {};", values.parquet_type(), name),
+            })
+            .collect();
+        let schema = Arc::new(parse_message_type(&format!("message {} {{ {} }}", self.name, fields.join(" ")))?);
+        let properties = Arc::new(WriterProperties::builder().set_compression(Compression::SNAPPY).build());
+
+        let mut writer = SerializedFileWriter::new(file, schema, properties)?;
+        let mut row_group = writer.next_row_group()?;
+        for (name, values) in &self.columns {
+            let mut column = row_group
+                .next_column()?
+                .ok_or_else(|| anyhow!("Parquet schema has no column {}", name))?;
+            match values {
+                Values::Int64(values) => {
+                    column.typed::<Int64Type>().write_batch(values, None, None)?;
+                }
+                Values::Bool(values) => {
+                    column.typed::<BoolType>().write_batch(values, None, None)?;
+                }
+                Values::Utf8(values) => {
+                    let values: Vec<ByteArray> = values.iter().map(|value| ByteArray::from(value.as_str())).collect();
+                    column.typed::<ByteArrayType>().write_batch(&values, None, None)?;
+                }
+            }
+            column.close()?;
+        }
+        row_group.close()?;
+        writer.close()?;
+        Ok(())
+    }
+
+    /// Write the table to `out` ("-" for stdout, CSV only)
+    pub fn write(&self, format: ExportFormat, out: &str) -> Result<()> {
+        match (format, out) {
+            (ExportFormat::Csv, "-") => self.write_csv(io::stdout()),
+            (ExportFormat::Parquet, "-") => Err(DexError::Input("Parquet can't be written to stdout; pass --out with a file name".to_string())),
+            (ExportFormat::Csv, out) => self.write_csv(File::create(out)?),
+            #[cfg(feature = "parquet")]
+            (ExportFormat::Parquet, out) => self.write_parquet(File::create(out)?),
+            #[cfg(not(feature = "parquet"))]
+            (ExportFormat::Parquet, _) => {
+                Err(DexError::Config("This build has no Parquet support; rebuild with `--features parquet`".to_string()))
+            }
+        }
+    }
+}
+
+fn strings(values: impl Iterator<Item = String>) -> Values {
+    Values::Utf8(values.collect())
+}
+
+fn decimals(values: impl Iterator<Item = U256>) -> Values {
+    Values::Utf8(values.map(|value| value.to_string()).collect())
+}
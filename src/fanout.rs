@@ -0,0 +1,34 @@
+use crate::error::Result;
+use anyhow::anyhow;
+use std::future::Future;
+use tokio::task::JoinSet;
+
+/// Run `task` on every item with at most `limit` running at once, returning the
+/// results in item order
+pub async fn map<T, R, F, Fut>(items: Vec<T>, limit: usize, task: F) -> Result<Vec<R>>
+where
+    T: Send + 'static,
+    R: Send + 'static,
+    F: Fn(T) -> Fut,
+    Fut: Future<Output = R> + Send + 'static,
+{
+    let total = items.len();
+    let mut results: Vec<Option<R>> = (0..total).map(|_| None).collect();
+    let mut pending = items.into_iter().enumerate();
+    let mut running = JoinSet::new();
+
+    for (index, item) in pending.by_ref().take(limit.max(1)) {
+        let future = task(item);
+        running.spawn(async move { (index, future.await) });
+    }
+    while let Some(joined) = running.join_next().await {
+        let (index, result) = joined.map_err(|e| anyhow!("Read task failed: {}", e))?;
+        results[index] = Some(result);
+        if let Some((index, item)) = pending.next() {
+            let future = task(item);
+            running.spawn(async move { (index, future.await) });
+        }
+    }
+
+    Ok(results.into_iter().map(|result| result.expect("every task joined")).collect())
+}
@@ -0,0 +1,54 @@
+use crate::error::{DexError, Result};
+use crate::rpc;
+use anyhow::anyhow;
+use ethers::{
+    providers::Middleware,
+    types::{Address, U256},
+};
+use std::time::{Duration, Instant};
+use tracing::info;
+
+/// Monad testnet faucet claim endpoint
+pub const DEFAULT_FAUCET_URL: &str = "https://faucet.monad.xyz/api/claim";
+
+/// Ask the faucet to send testnet MON to `address`, returning the faucet's reply
+pub async fn request(faucet_url: &str, address: Address) -> Result<serde_json::Value> {
+    let body = serde_json::json!({ "address": format!("{:?}", address) });
+    let response = rpc::client_builder()?.build()?.post(faucet_url).json(&body).send().await?;
+    let status = response.status();
+    let text = response.text().await?;
+    if !status.is_success() {
+        // Faucets explain rate limits and eligibility in the body, so surface it
+        return Err(anyhow!("Faucet returned {}: {}", status, text.trim()).into());
+    }
+    Ok(serde_json::from_str(&text).unwrap_or(serde_json::Value::String(text)))
+}
+
+/// Poll the native balance of `address` until it rises above `previous`, returning the new balance
+pub async fn wait_for_balance<M: Middleware>(
+    client: &M,
+    address: Address,
+    previous: U256,
+    timeout: Duration,
+    poll_interval: Duration,
+) -> Result<U256> {
+    let started = Instant::now();
+    loop {
+        let balance = client
+            .get_balance(address, None)
+            .await
+            .map_err(|e| DexError::Rpc(format!("Failed to fetch balance: {}", e)))?;
+        if balance > previous {
+            return Ok(balance);
+        }
+        if started.elapsed() >= timeout {
+            return Err(anyhow!(
+                "Balance of {:?} did not change within {}s; the faucet may still be processing the claim",
+                address,
+                timeout.as_secs()
+            ).into());
+        }
+        info!("Waiting for faucet funds to arrive...");
+        tokio::time::sleep(poll_interval).await;
+    }
+}
@@ -0,0 +1,182 @@
+use crate::error::{DexError, Result};
+use clap::{Args, ValueEnum};
+use ethers::{
+    providers::{JsonRpcClient, Middleware, Provider},
+    types::{
+        transaction::eip2718::TypedTransaction, BlockNumber, Eip1559TransactionRequest,
+        TransactionRequest, U256,
+    },
+    utils::parse_units,
+};
+use std::str::FromStr;
+use tracing::info;
+
+/// Priority fee used when the node returns no fee history
+const FALLBACK_PRIORITY_FEE_GWEI: u64 = 1;
+
+/// Number of recent blocks sampled for the auto priority fee
+const FEE_HISTORY_BLOCKS: u64 = 10;
+
+/// Transaction envelope to send
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum TxType {
+    /// Pre-London transaction with a single gas price
+    Legacy,
+    /// Dynamic-fee transaction (EIP-1559)
+    Eip1559,
+}
+
+/// A fee value given on the command line, either in gwei or derived from the network
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FeeValue {
+    Auto,
+    Wei(U256),
+}
+
+impl FromStr for FeeValue {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s.eq_ignore_ascii_case("auto") {
+            return Ok(FeeValue::Auto);
+        }
+        parse_units(s, "gwei")
+            .map(|units| FeeValue::Wei(units.into()))
+            .map_err(|e| format!("invalid gwei amount '{}': {}", s, e))
+    }
+}
+
+/// Fee options shared by every command that sends a transaction
+#[derive(Debug, Clone, Args)]
+pub struct FeeArgs {
+    /// Transaction type
+    #[arg(long, value_enum, default_value = "eip1559", global = true)]
+    pub tx_type: TxType,
+
+    /// Max fee per gas in gwei, or "auto" to derive it from the latest base fee
+    #[arg(long, default_value = "auto", global = true)]
+    pub max_fee: FeeValue,
+
+    /// Max priority fee per gas in gwei, or "auto" to use recent fee history
+    #[arg(long, default_value = "auto", global = true)]
+    pub priority_fee: FeeValue,
+}
+
+impl Default for FeeArgs {
+    fn default() -> Self {
+        Self {
+            tx_type: TxType::Eip1559,
+            max_fee: FeeValue::Auto,
+            priority_fee: FeeValue::Auto,
+        }
+    }
+}
+
+/// Fees resolved against the network, ready to be applied to a transaction
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Fees {
+    /// Legacy gas price; `None` lets the node fill it in
+    Legacy { gas_price: Option<U256> },
+    Eip1559 { max_fee: U256, priority_fee: U256 },
+}
+
+impl FeeArgs {
+    /// Resolve `auto` values using the latest block and recent fee history
+    pub async fn resolve<P: JsonRpcClient>(&self, provider: &Provider<P>) -> Result<Fees> {
+        if self.tx_type == TxType::Legacy {
+            let gas_price = provider.get_gas_price().await?;
+            return Ok(Fees::Legacy { gas_price: Some(gas_price) });
+        }
+
+        let priority_fee = match self.priority_fee {
+            FeeValue::Wei(fee) => fee,
+            FeeValue::Auto => suggested_priority_fee(provider).await?,
+        };
+
+        let max_fee = match self.max_fee {
+            FeeValue::Wei(fee) => fee,
+            FeeValue::Auto => latest_base_fee(provider).await? * 2 + priority_fee,
+        };
+
+        if priority_fee > max_fee {
+            return Err(DexError::Input(format!(
+                "Priority fee ({} wei) exceeds max fee ({} wei)",
+                priority_fee,
+                max_fee
+            )));
+        }
+
+        info!("Using EIP-1559 fees: max fee {} wei, priority fee {} wei", max_fee, priority_fee);
+        Ok(Fees::Eip1559 { max_fee, priority_fee })
+    }
+}
+
+impl Fees {
+    /// Highest price per gas these fees allow, if known up front
+    pub fn max_gas_price(&self) -> Option<U256> {
+        match *self {
+            Fees::Legacy { gas_price } => gas_price,
+            Fees::Eip1559 { max_fee, .. } => Some(max_fee),
+        }
+    }
+
+    /// Rewrite a transaction into the envelope and fee fields described by these fees
+    pub fn apply(&self, tx: &mut TypedTransaction) {
+        *tx = match *self {
+            Fees::Legacy { gas_price } => TypedTransaction::Legacy(TransactionRequest {
+                from: tx.from().copied(),
+                to: tx.to().cloned(),
+                gas: tx.gas().copied(),
+                gas_price: gas_price.or_else(|| tx.gas_price()),
+                value: tx.value().copied(),
+                data: tx.data().cloned(),
+                nonce: tx.nonce().copied(),
+                chain_id: tx.chain_id(),
+            }),
+            Fees::Eip1559 { max_fee, priority_fee } => {
+                let mut request = Eip1559TransactionRequest::new()
+                    .max_fee_per_gas(max_fee)
+                    .max_priority_fee_per_gas(priority_fee);
+                request.from = tx.from().copied();
+                request.to = tx.to().cloned();
+                request.gas = tx.gas().copied();
+                request.value = tx.value().copied();
+                request.data = tx.data().cloned();
+                request.nonce = tx.nonce().copied();
+                request.chain_id = tx.chain_id();
+                TypedTransaction::Eip1559(request)
+            }
+        };
+    }
+}
+
+async fn latest_base_fee<P: JsonRpcClient>(provider: &Provider<P>) -> Result<U256> {
+    let block = provider
+        .get_block(BlockNumber::Latest)
+        .await?
+        .ok_or_else(|| DexError::Rpc("Latest block not available".to_string()))?;
+
+    block
+        .base_fee_per_gas
+        .ok_or_else(|| DexError::Input("Network does not report a base fee, use --tx-type legacy".to_string()))
+}
+
+async fn suggested_priority_fee<P: JsonRpcClient>(provider: &Provider<P>) -> Result<U256> {
+    let history = provider
+        .fee_history(FEE_HISTORY_BLOCKS, BlockNumber::Latest, &[50.0])
+        .await?;
+
+    let mut rewards: Vec<U256> = history
+        .reward
+        .iter()
+        .filter_map(|block| block.first().copied())
+        .filter(|reward| !reward.is_zero())
+        .collect();
+
+    if rewards.is_empty() {
+        return Ok(U256::from(FALLBACK_PRIORITY_FEE_GWEI) * U256::exp10(9));
+    }
+
+    rewards.sort();
+    Ok(rewards[rewards.len() / 2])
+}
@@ -0,0 +1,123 @@
+use crate::bindings::{OrderBookDEX, OrderMatchedFilter};
+use crate::error::{DexError, Result};
+use crate::strategy::to_f64;
+use ethers::{
+    contract::{parse_log, EthEvent},
+    providers::Middleware,
+    types::{BigEndianHash, Filter, H256, U256, U64},
+};
+use serde::Serialize;
+use std::time::{Duration, Instant};
+use tokio::time::sleep;
+use tracing::info;
+
+/// How a watched order stood when [`wait_for_fill`] returned
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FillStatus {
+    Filled,
+    /// Still resting with part of it filled when the timeout ran out
+    PartiallyFilled,
+    /// Still resting untouched when the timeout ran out
+    Open,
+    /// Cancelled before it filled completely; `filled` may still be non-zero
+    Cancelled,
+}
+
+/// One match against the watched order
+#[derive(Debug, Clone, Serialize)]
+pub struct Fill {
+    pub tx_hash: Option<H256>,
+    pub block_number: Option<U64>,
+    /// The order on the other side
+    pub counter_order_id: U256,
+    pub amount: U256,
+    pub price: U256,
+}
+
+/// What happened to an order between its placement and the end of the wait
+#[derive(Debug, Clone, Serialize)]
+pub struct Execution {
+    pub order_id: U256,
+    pub status: FillStatus,
+    pub amount: U256,
+    pub filled: U256,
+    pub remaining: U256,
+    /// Amount-weighted over `fills`; `None` before the first fill
+    pub average_price: Option<f64>,
+    pub fills: Vec<Fill>,
+}
+
+/// Poll `order_id` every `poll` until it is fully filled, cancelled, or `timeout` runs
+/// out, then collect its OrderMatched events from `from_block` onwards.
+///
+/// The order's on-chain state decides the status: the contract deactivates an order
+/// when its remaining amount reaches zero, so an inactive order with something left
+/// was cancelled.
+pub async fn wait_for_fill<M: Middleware + 'static>(
+    contract: &OrderBookDEX<M>,
+    order_id: U256,
+    amount: U256,
+    from_block: U64,
+    timeout: Duration,
+    poll: Duration,
+) -> Result<Execution> {
+    let deadline = Instant::now() + timeout;
+    let mut last_remaining = amount;
+    let (remaining, is_active) = loop {
+        let (_, _, _, _, remaining, _, _, is_active, _) = contract.orders(order_id).call().await?;
+        if remaining != last_remaining {
+            info!("Order {}: {} of {} filled", order_id, amount.saturating_sub(remaining), amount);
+            last_remaining = remaining;
+        }
+        if !is_active || Instant::now() >= deadline {
+            break (remaining, is_active);
+        }
+        sleep(poll.min(deadline.saturating_duration_since(Instant::now()))).await;
+    };
+
+    let filled = amount.saturating_sub(remaining);
+    let status = match (is_active, remaining.is_zero(), filled.is_zero()) {
+        (false, true, _) => FillStatus::Filled,
+        (false, false, _) => FillStatus::Cancelled,
+        (true, _, true) => FillStatus::Open,
+        (true, _, false) => FillStatus::PartiallyFilled,
+    };
+
+    let fills = matched_fills(contract, order_id, from_block).await?;
+    let matched: f64 = fills.iter().map(|fill| to_f64(fill.amount)).sum();
+    let average_price = (matched > 0.0).then(|| {
+        fills.iter().map(|fill| to_f64(fill.amount) * to_f64(fill.price)).sum::<f64>() / matched
+    });
+
+    Ok(Execution { order_id, status, amount, filled, remaining, average_price, fills })
+}
+
+/// OrderMatched events on either side of `order_id`, oldest first
+async fn matched_fills<M: Middleware + 'static>(
+    contract: &OrderBookDEX<M>,
+    order_id: U256,
+    from_block: U64,
+) -> Result<Vec<Fill>> {
+    let id_topic = H256::from_uint(&order_id);
+    let base = Filter::new()
+        .address(contract.address())
+        .from_block(from_block)
+        .topic0(OrderMatchedFilter::signature());
+    let client = contract.client();
+
+    // The ID is indexed as either the buy or the sell order, so ask for each separately
+    let mut logs = client.get_logs(&base.clone().topic1(id_topic)).await.map_err(|e| DexError::middleware(&e))?;
+    logs.extend(client.get_logs(&base.topic2(id_topic)).await.map_err(|e| DexError::middleware(&e))?);
+    logs.sort_by_key(|log| (log.block_number, log.log_index));
+
+    Ok(logs
+        .into_iter()
+        .filter_map(|log| {
+            let (tx_hash, block_number) = (log.transaction_hash, log.block_number);
+            let event = parse_log::<OrderMatchedFilter>(log).ok()?;
+            let counter_order_id = if event.buy_order_id == order_id { event.sell_order_id } else { event.buy_order_id };
+            Some(Fill { tx_hash, block_number, counter_order_id, amount: event.amount, price: event.price })
+        })
+        .collect())
+}
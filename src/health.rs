@@ -0,0 +1,139 @@
+use crate::metrics::metrics;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt::Display;
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, Instant};
+
+/// Component the transport reports every RPC round trip under
+pub const RPC: &str = "rpc";
+
+/// When `/healthz` and `/readyz` report a problem
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HealthConfig {
+    /// Not ready while the event index is more than this many blocks behind the head
+    #[serde(default = "default_max_indexer_lag")]
+    pub max_indexer_lag: u64,
+    /// Unhealthy once a component has been failing for this many seconds; until then
+    /// only not ready, so a blip doesn't get the process restarted
+    #[serde(default = "default_grace_secs")]
+    pub grace_secs: u64,
+}
+
+impl Default for HealthConfig {
+    fn default() -> Self {
+        Self { max_indexer_lag: default_max_indexer_lag(), grace_secs: default_grace_secs() }
+    }
+}
+
+fn default_max_indexer_lag() -> u64 {
+    100
+}
+
+fn default_grace_secs() -> u64 {
+    120
+}
+
+#[derive(Default)]
+struct Component {
+    last_ok: Option<Instant>,
+    failing_since: Option<Instant>,
+    error: Option<String>,
+}
+
+#[derive(Default)]
+struct State {
+    config: HealthConfig,
+    components: BTreeMap<String, Component>,
+}
+
+fn state() -> &'static Mutex<State> {
+    static STATE: OnceLock<Mutex<State>> = OnceLock::new();
+    STATE.get_or_init(Default::default)
+}
+
+/// Set the thresholds, e.g. from the daemon config
+pub fn configure(config: HealthConfig) {
+    state().lock().unwrap().config = config;
+}
+
+/// Record that `component` (the RPC, a subscription, a daemon task) is working
+pub fn ok(component: &str) {
+    let mut state = state().lock().unwrap();
+    let component = state.components.entry(component.to_string()).or_default();
+    component.last_ok = Some(Instant::now());
+    component.failing_since = None;
+    component.error = None;
+}
+
+/// Record that `component` failed; it stays failing until the next [`ok`]
+pub fn failed(component: &str, error: impl Display) {
+    let mut state = state().lock().unwrap();
+    let component = state.components.entry(component.to_string()).or_default();
+    component.failing_since.get_or_insert_with(Instant::now);
+    component.error = Some(error.to_string());
+}
+
+/// One line of the health report
+#[derive(Debug, Clone, Serialize)]
+pub struct Check {
+    pub name: String,
+    /// Failing for less than the grace period counts as live
+    pub live: bool,
+    pub ready: bool,
+    pub detail: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Report {
+    /// Whether `/healthz` passes: nothing has been failing past the grace period
+    pub healthy: bool,
+    /// Whether `/readyz` passes: everything works now and the index is caught up
+    pub ready: bool,
+    pub checks: Vec<Check>,
+}
+
+/// The state of every component that has reported so far, plus the indexer lag
+pub fn report() -> Report {
+    let state = state().lock().unwrap();
+    let grace = Duration::from_secs(state.config.grace_secs);
+    let mut checks: Vec<Check> = state
+        .components
+        .iter()
+        .map(|(name, component)| match component.failing_since {
+            Some(since) => Check {
+                name: name.clone(),
+                live: since.elapsed() < grace,
+                ready: false,
+                detail: format!(
+                    "failing for {}s: {}",
+                    since.elapsed().as_secs(),
+                    component.error.as_deref().unwrap_or("unknown error")
+                ),
+            },
+            None => Check {
+                name: name.clone(),
+                live: true,
+                ready: true,
+                detail: component
+                    .last_ok
+                    .map_or("ok".to_string(), |last| format!("ok {}s ago", last.elapsed().as_secs())),
+            },
+        })
+        .collect();
+
+    // Only set once something has synced an event index
+    let lag = metrics().indexer_lag.get().max(0) as u64;
+    checks.push(Check {
+        name: "indexer".to_string(),
+        live: true,
+        ready: lag <= state.config.max_indexer_lag,
+        detail: format!("{} blocks behind (max {})", lag, state.config.max_indexer_lag),
+    });
+
+    Report {
+        healthy: checks.iter().all(|check| check.live),
+        ready: checks.iter().all(|check| check.ready),
+        checks,
+    }
+}
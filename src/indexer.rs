@@ -0,0 +1,310 @@
+use crate::error::{DexError, Result};
+use crate::events::{decode_log, DecodedLog};
+use crate::matching::{self, TRADING_FEE};
+use crate::metrics::metrics;
+use anyhow::{anyhow, Context};
+use ethers::{
+    abi::{Abi, Token},
+    providers::{JsonRpcClient, Middleware, Provider},
+    types::{Address, Filter, Log, H256, U256},
+};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::PathBuf;
+use tracing::info;
+
+/// Directory holding one event index file per DEX address
+const INDEX_DIR: &str = "config/index";
+
+/// Default number of blocks requested per `eth_getLogs` call
+pub const DEFAULT_CHUNK_SIZE: u64 = 1000;
+
+/// An `OrderPlaced` event
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PlacedOrder {
+    pub order_id: U256,
+    pub trader: Address,
+    pub base_token: Address,
+    pub quote_token: Address,
+    pub amount: U256,
+    pub price: U256,
+    pub is_buy: bool,
+    pub block: u64,
+    pub tx_hash: H256,
+}
+
+/// An `OrderMatched` event; `amount` is the base amount after the trading fee
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Trade {
+    pub buy_order_id: U256,
+    pub sell_order_id: U256,
+    pub base_token: Address,
+    pub quote_token: Address,
+    pub amount: U256,
+    pub price: U256,
+    /// Base amount the contract kept as the trading fee
+    #[serde(default)]
+    pub fee: U256,
+    pub block: u64,
+    pub tx_hash: H256,
+}
+
+impl Trade {
+    /// Base amount removed from both orders
+    pub fn matched(&self) -> U256 {
+        self.amount + self.fee
+    }
+}
+
+/// An `OrderCancelled` event
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Cancellation {
+    pub order_id: U256,
+    pub trader: Address,
+    pub block: u64,
+    pub tx_hash: H256,
+}
+
+/// A `TradingPairAdded` event
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ListedPair {
+    pub base_token: Address,
+    pub quote_token: Address,
+    pub min_order_size: U256,
+    pub block: u64,
+    pub tx_hash: H256,
+}
+
+/// DEX events collected from `eth_getLogs`, persisted between runs so each sync
+/// only fetches blocks it has not seen
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EventStore {
+    pub dex: Address,
+    /// Last block included in the store
+    pub last_block: u64,
+    pub orders: Vec<PlacedOrder>,
+    pub trades: Vec<Trade>,
+    pub cancellations: Vec<Cancellation>,
+    /// Empty for indexes created before pairs were tracked; delete the file to rebuild
+    #[serde(default)]
+    pub pairs: Vec<ListedPair>,
+    /// Trading fee as of `last_block`, following `TradingFeeUpdated` events
+    #[serde(default = "default_trading_fee")]
+    pub trading_fee: u64,
+}
+
+fn default_trading_fee() -> u64 {
+    TRADING_FEE
+}
+
+impl EventStore {
+    fn path(dex: Address) -> PathBuf {
+        PathBuf::from(INDEX_DIR).join(format!("{:?}.json", dex))
+    }
+
+    /// Load the store for `dex`, or start an empty one ending just before `from_block`
+    pub fn load(dex: Address, from_block: u64) -> Result<Self> {
+        let path = Self::path(dex);
+        if !path.exists() {
+            return Ok(Self {
+                dex,
+                last_block: from_block.saturating_sub(1),
+                orders: Vec::new(),
+                trades: Vec::new(),
+                cancellations: Vec::new(),
+                pairs: Vec::new(),
+                trading_fee: TRADING_FEE,
+            });
+        }
+
+        let content = fs::read_to_string(&path)
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+        Ok(serde_json::from_str(&content)?)
+    }
+
+    pub fn save(&self) -> Result<()> {
+        fs::create_dir_all(INDEX_DIR)?;
+        fs::write(Self::path(self.dex), serde_json::to_string(self)?)?;
+        Ok(())
+    }
+
+    /// Fetch and decode every DEX event up to the latest block, saving after each chunk
+    pub async fn sync<P: JsonRpcClient>(
+        &mut self,
+        provider: &Provider<P>,
+        abi: &Abi,
+        chunk_size: u64,
+    ) -> Result<()> {
+        let latest = provider.get_block_number().await?.as_u64();
+        metrics().indexer_lag.set(latest.saturating_sub(self.last_block) as i64);
+        if self.last_block >= latest {
+            return Ok(());
+        }
+
+        info!("Indexing DEX events from block {} to {}", self.last_block + 1, latest);
+        let mut from = self.last_block + 1;
+        while from <= latest {
+            let to = (from + chunk_size.max(1) - 1).min(latest);
+            let filter = Filter::new().address(self.dex).from_block(from).to_block(to);
+            for log in provider.get_logs(&filter).await? {
+                self.apply(abi, &log)?;
+            }
+            self.last_block = to;
+            self.save()?;
+            metrics().indexer_lag.set((latest - to) as i64);
+            from = to + 1;
+        }
+
+        info!(
+            "Indexed {} orders, {} trades, {} cancellations",
+            self.orders.len(),
+            self.trades.len(),
+            self.cancellations.len()
+        );
+        Ok(())
+    }
+
+    fn apply(&mut self, abi: &Abi, log: &Log) -> Result<()> {
+        let Some(decoded) = decode_log(abi, log) else {
+            return Ok(());
+        };
+        let block = log.block_number.map(|b| b.as_u64()).unwrap_or_default();
+        let tx_hash = log.transaction_hash.unwrap_or_default();
+
+        match decoded.event.as_str() {
+            "OrderPlaced" => self.orders.push(PlacedOrder {
+                order_id: uint(&decoded, "orderId")?,
+                trader: address(&decoded, "trader")?,
+                base_token: address(&decoded, "baseToken")?,
+                quote_token: address(&decoded, "quoteToken")?,
+                amount: uint(&decoded, "amount")?,
+                price: uint(&decoded, "price")?,
+                is_buy: boolean(&decoded, "isBuy")?,
+                block,
+                tx_hash,
+            }),
+            "OrderMatched" => {
+                let amount = uint(&decoded, "amount")?;
+                self.trades.push(Trade {
+                    buy_order_id: uint(&decoded, "buyOrderId")?,
+                    sell_order_id: uint(&decoded, "sellOrderId")?,
+                    base_token: address(&decoded, "baseToken")?,
+                    quote_token: address(&decoded, "quoteToken")?,
+                    amount,
+                    price: uint(&decoded, "price")?,
+                    fee: matching::matched_from_net(amount, self.trading_fee) - amount,
+                    block,
+                    tx_hash,
+                })
+            }
+            "OrderCancelled" => self.cancellations.push(Cancellation {
+                order_id: uint(&decoded, "orderId")?,
+                trader: address(&decoded, "trader")?,
+                block,
+                tx_hash,
+            }),
+            "TradingFeeUpdated" => self.trading_fee = uint(&decoded, "newFee")?.low_u64(),
+            "TradingPairAdded" => self.pairs.push(ListedPair {
+                base_token: address(&decoded, "baseToken")?,
+                quote_token: address(&decoded, "quoteToken")?,
+                min_order_size: uint(&decoded, "minOrderSize")?,
+                block,
+                tx_hash,
+            }),
+            _ => {}
+        }
+        Ok(())
+    }
+
+    /// Distinct pairs that have been added, in the order they were last listed
+    pub fn listed_pairs(&self) -> Vec<(Address, Address)> {
+        let mut pairs: Vec<(Address, Address)> = Vec::new();
+        for pair in self.pairs.iter().rev() {
+            if !pairs.contains(&(pair.base_token, pair.quote_token)) {
+                pairs.push((pair.base_token, pair.quote_token));
+            }
+        }
+        pairs.reverse();
+        pairs
+    }
+
+    /// The placement event for an order ID
+    pub fn order(&self, order_id: U256) -> Option<&PlacedOrder> {
+        self.orders.iter().find(|order| order.order_id == order_id)
+    }
+
+    /// Trades where `trader` owned the buy or the sell side, with their side
+    pub fn fills_for(&self, trader: Address) -> Vec<(&Trade, bool)> {
+        self.trades
+            .iter()
+            .filter_map(|trade| {
+                let owner = |id| self.order(id).map(|order| order.trader);
+                if owner(trade.buy_order_id) == Some(trader) {
+                    Some((trade, true))
+                } else if owner(trade.sell_order_id) == Some(trader) {
+                    Some((trade, false))
+                } else {
+                    None
+                }
+            })
+            .collect()
+    }
+}
+
+/// First block at or after Unix time `timestamp`, by binary search over block headers
+pub async fn block_at<M: Middleware>(provider: &M, timestamp: u64) -> Result<u64> {
+    let timestamp_of = |number: u64| async move {
+        provider
+            .get_block(number)
+            .await
+            .map_err(|e| DexError::Rpc(format!("Failed to fetch block {}: {}", number, e)))?
+            .map(|block| block.timestamp.as_u64())
+            .ok_or_else(|| DexError::Rpc(format!("Block {} not found", number)))
+    };
+
+    let latest = provider
+        .get_block_number()
+        .await
+        .map_err(|e| DexError::Rpc(format!("Failed to fetch the latest block: {}", e)))?
+        .as_u64();
+    let (mut low, mut high) = (0, latest);
+    while low < high {
+        let mid = low + (high - low) / 2;
+        if timestamp_of(mid).await? < timestamp {
+            low = mid + 1;
+        } else {
+            high = mid;
+        }
+    }
+    Ok(low)
+}
+
+fn param<'a>(decoded: &'a DecodedLog, name: &str) -> Result<&'a Token> {
+    decoded
+        .params
+        .iter()
+        .find(|(param, _)| param == name)
+        .map(|(_, value)| value)
+        .ok_or_else(|| anyhow!("{} event has no '{}' field", decoded.event, name).into())
+}
+
+fn uint(decoded: &DecodedLog, name: &str) -> Result<U256> {
+    param(decoded, name)?
+        .clone()
+        .into_uint()
+        .ok_or_else(|| anyhow!("'{}' is not a uint", name).into())
+}
+
+fn address(decoded: &DecodedLog, name: &str) -> Result<Address> {
+    param(decoded, name)?
+        .clone()
+        .into_address()
+        .ok_or_else(|| anyhow!("'{}' is not an address", name).into())
+}
+
+fn boolean(decoded: &DecodedLog, name: &str) -> Result<bool> {
+    param(decoded, name)?
+        .clone()
+        .into_bool()
+        .ok_or_else(|| anyhow!("'{}' is not a bool", name).into())
+}
@@ -0,0 +1,664 @@
+use crate::error::{DexError, Result};
+use crate::expiry::unix_secs;
+use crate::rpc;
+use anyhow::Context;
+use clap::ValueEnum;
+use ethers::{
+    providers::{Middleware, PendingTransaction},
+    types::{transaction::eip2718::TypedTransaction, Address, BlockNumber, TransactionReceipt, H256, U256, U64},
+    utils::keccak256,
+};
+use fs2::FileExt;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use std::fs::{self, File, OpenOptions};
+use std::io::{BufRead, BufReader, Write};
+use std::path::Path;
+use std::time::SystemTime;
+use tracing::{info, warn};
+
+/// Snapshot of the journal as of the last compaction
+const TX_JOURNAL_PATH: &str = "config/tx_journal.json";
+
+/// Changes since the snapshot, one [`Record`] per line
+const TX_JOURNAL_LOG_PATH: &str = "config/tx_journal.log";
+
+/// Held while the journal is read or changed, by every process sharing it
+const TX_JOURNAL_LOCK_PATH: &str = "config/tx_journal.lock";
+
+/// Settled entries kept; the oldest are dropped beyond this, pending ones never are
+const MAX_ENTRIES: usize = 5_000;
+
+/// Log records folded into the snapshot once there are this many
+const COMPACT_AFTER: usize = 1_000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum TxStatus {
+    /// Sent, or about to be, and not yet seen mined
+    Pending,
+    Mined,
+    Reverted,
+    /// Never mined: gone from the mempool
+    Dropped,
+    /// Rejected before reaching the mempool
+    Failed,
+}
+
+/// One transaction the tools sent
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct JournalEntry {
+    pub id: u64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub idempotency_key: Option<String>,
+    /// What the transaction does: the contract function, or `transfer`
+    pub intent: String,
+    pub chain_id: u64,
+    pub from: Address,
+    pub to: Option<Address>,
+    pub value: U256,
+    /// keccak256 of the calldata, so the same intent is recognised without storing it
+    pub calldata_hash: H256,
+    pub nonce: U256,
+    /// Hash of the latest broadcast
+    pub tx_hash: Option<H256>,
+    /// Hashes of broadcasts replaced with higher fees
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub replaced: Vec<H256>,
+    pub status: TxStatus,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub block: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    /// Unix time in seconds
+    pub created_at: u64,
+    pub updated_at: u64,
+}
+
+impl JournalEntry {
+    /// Whether `tx` does the same thing as this entry, fees and nonce aside
+    fn same_intent(&self, tx: &TypedTransaction) -> bool {
+        self.to == tx.to_addr().copied()
+            && self.value == tx.value().copied().unwrap_or_default()
+            && self.calldata_hash == calldata_hash(tx)
+    }
+
+    /// Every hash this transaction was broadcast under, latest first
+    pub fn hashes(&self) -> impl Iterator<Item = &H256> {
+        self.tx_hash.iter().chain(self.replaced.iter().rev())
+    }
+
+    fn settle(&mut self, receipt: &TransactionReceipt) {
+        self.tx_hash = Some(receipt.transaction_hash);
+        self.replaced.retain(|hash| *hash != receipt.transaction_hash);
+        self.status = if receipt.status == Some(U64::one()) { TxStatus::Mined } else { TxStatus::Reverted };
+        self.block = receipt.block_number.map(|block| block.as_u64());
+    }
+}
+
+fn calldata_hash(tx: &TypedTransaction) -> H256 {
+    H256::from(keccak256(tx.data().map(|data| data.as_ref()).unwrap_or_default()))
+}
+
+fn now() -> u64 {
+    unix_secs(SystemTime::now())
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RunStatus {
+    /// Started and not every step has gone through yet
+    Running,
+    Finished,
+    /// Given up with `--restart`
+    Abandoned,
+}
+
+/// Progress of a command that sends many transactions, e.g. `place-orders` or `airdrop`.
+/// Each step is sent under the idempotency key `<run id>/<step>`, so a resumed run
+/// waits for a step that was in flight when it stopped instead of sending it twice.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BatchRun {
+    pub id: String,
+    /// Command that started the run
+    pub kind: String,
+    /// File the steps come from
+    pub source: String,
+    pub total: usize,
+    /// Transaction of every step that went through, by step index
+    #[serde(default)]
+    pub completed: BTreeMap<usize, H256>,
+    pub status: RunStatus,
+    pub created_at: u64,
+    pub updated_at: u64,
+}
+
+impl BatchRun {
+    fn new(id: String, kind: &str, source: &str, total: usize) -> Self {
+        Self {
+            id,
+            kind: kind.to_string(),
+            source: source.to_string(),
+            total,
+            completed: BTreeMap::new(),
+            status: RunStatus::Running,
+            created_at: now(),
+            updated_at: now(),
+        }
+    }
+
+    /// Idempotency key of step `index`
+    pub fn key(&self, index: usize) -> String {
+        format!("{}/{}", self.id, index)
+    }
+
+    pub fn is_done(&self, index: usize) -> bool {
+        self.completed.contains_key(&index)
+    }
+
+    /// Record that step `index` went through in `hash`
+    pub fn step_done(&mut self, index: usize, hash: H256) {
+        self.completed.insert(index, hash);
+        let (id, completed) = (self.id.clone(), self.completed.clone());
+        let result = update(|journal| {
+            if let Some(run) = journal.runs.iter_mut().find(|run| run.id == id) {
+                run.completed = completed;
+                run.updated_at = now();
+            }
+        });
+        if let Err(e) = result {
+            warn!("Failed to record progress of run {}: {}", self.id, e);
+        }
+    }
+
+    /// Mark the run finished if every step went through
+    pub fn finish(&mut self) -> Result<()> {
+        if self.completed.len() < self.total {
+            return Ok(());
+        }
+        self.status = RunStatus::Finished;
+        update(|journal| {
+            if let Some(run) = journal.runs.iter_mut().find(|run| run.id == self.id) {
+                run.status = RunStatus::Finished;
+                run.updated_at = now();
+            }
+        })
+    }
+}
+
+/// Every transaction sent through `tx::send`, `tx::send_batch` and `tx::broadcast`, so a
+/// crashed command can tell what already went out
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct TxJournal {
+    pub next_id: u64,
+    pub entries: Vec<JournalEntry>,
+    #[serde(default)]
+    pub runs: Vec<BatchRun>,
+}
+
+impl TxJournal {
+    pub fn load() -> Result<Self> {
+        let lock = lock()?;
+        lock.lock_shared().with_context(|| format!("Failed to lock {}", TX_JOURNAL_LOCK_PATH))?;
+        Ok(Self::read()?.0)
+    }
+
+    /// The snapshot with the log replayed over it, and how many records the log holds.
+    /// The caller holds the lock.
+    fn read() -> Result<(Self, usize)> {
+        let mut journal: Self = if Path::new(TX_JOURNAL_PATH).exists() {
+            let content = fs::read_to_string(TX_JOURNAL_PATH)
+                .with_context(|| format!("Failed to read {}", TX_JOURNAL_PATH))?;
+            serde_json::from_str(&content)?
+        } else {
+            Self::default()
+        };
+        if !Path::new(TX_JOURNAL_LOG_PATH).exists() {
+            return Ok((journal, 0));
+        }
+        let log = File::open(TX_JOURNAL_LOG_PATH).with_context(|| format!("Failed to read {}", TX_JOURNAL_LOG_PATH))?;
+        let mut records = 0;
+        for line in BufReader::new(log).lines() {
+            let line = line?;
+            match serde_json::from_str::<Record>(&line) {
+                Ok(record) => journal.apply(record),
+                // Only the last line can be torn, by a crash while it was appended
+                Err(e) => warn!("Skipping unreadable record in {}: {}", TX_JOURNAL_LOG_PATH, e),
+            }
+            records += 1;
+        }
+        Ok((journal, records))
+    }
+
+    fn apply(&mut self, record: Record) {
+        match record {
+            Record::Entry(entry) => match self.entries.iter_mut().find(|existing| existing.id == entry.id) {
+                Some(existing) => *existing = entry,
+                None => self.entries.push(entry),
+            },
+            Record::Run(run) => match self.runs.iter_mut().find(|existing| existing.id == run.id) {
+                Some(existing) => *existing = run,
+                None => self.runs.push(run),
+            },
+            Record::NextId { next_id } => self.next_id = self.next_id.max(next_id),
+        }
+    }
+
+    /// What changed since `before`, as records to append to the log
+    fn changes_since(&self, before: &Self) -> Vec<Record> {
+        let entries: HashMap<u64, &JournalEntry> = before.entries.iter().map(|entry| (entry.id, entry)).collect();
+        let runs: HashMap<&str, &BatchRun> = before.runs.iter().map(|run| (run.id.as_str(), run)).collect();
+        let mut records = Vec::new();
+        if self.next_id != before.next_id {
+            records.push(Record::NextId { next_id: self.next_id });
+        }
+        records.extend(
+            self.entries
+                .iter()
+                .filter(|entry| entries.get(&entry.id) != Some(entry))
+                .map(|entry| Record::Entry(entry.clone())),
+        );
+        records.extend(
+            self.runs
+                .iter()
+                .filter(|run| runs.get(run.id.as_str()) != Some(run))
+                .map(|run| Record::Run(run.clone())),
+        );
+        records
+    }
+
+    /// Append `records` to the log and flush them to disk. The caller holds the lock.
+    fn append(records: &[Record]) -> Result<()> {
+        let mut lines = String::new();
+        for record in records {
+            lines.push_str(&serde_json::to_string(record)?);
+            lines.push('\n');
+        }
+        let mut log = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(TX_JOURNAL_LOG_PATH)
+            .with_context(|| format!("Failed to open {}", TX_JOURNAL_LOG_PATH))?;
+        log.write_all(lines.as_bytes())?;
+        log.sync_data()?;
+        Ok(())
+    }
+
+    /// Drop old entries, replace the snapshot with this journal and empty the log. The
+    /// snapshot is written to a temporary file and renamed over the old one, so a crash
+    /// leaves one or the other. The caller holds the lock.
+    fn compact(&mut self) -> Result<()> {
+        self.prune();
+        let temp = format!("{}.tmp", TX_JOURNAL_PATH);
+        let mut file = File::create(&temp).with_context(|| format!("Failed to write {}", temp))?;
+        file.write_all(serde_json::to_string_pretty(self)?.as_bytes())?;
+        file.sync_all()?;
+        fs::rename(&temp, TX_JOURNAL_PATH).with_context(|| format!("Failed to replace {}", TX_JOURNAL_PATH))?;
+        // Everything in the log is in the snapshot now
+        File::create(TX_JOURNAL_LOG_PATH)?.sync_all()?;
+        Ok(())
+    }
+
+    /// The entry with this ID, transaction hash or idempotency key (latest use)
+    pub fn find(&self, query: &str) -> Option<&JournalEntry> {
+        if let Ok(id) = query.parse::<u64>() {
+            return self.entries.iter().find(|entry| entry.id == id);
+        }
+        if let Ok(hash) = query.parse::<H256>() {
+            return self.entries.iter().rev().find(|entry| entry.hashes().any(|h| *h == hash));
+        }
+        self.entries.iter().rev().find(|entry| entry.idempotency_key.as_deref() == Some(query))
+    }
+
+    fn by_key(&self, chain_id: u64, key: &str) -> Option<&JournalEntry> {
+        self.entries
+            .iter()
+            .rev()
+            .find(|entry| entry.chain_id == chain_id && entry.idempotency_key.as_deref() == Some(key))
+    }
+
+    fn prune(&mut self) {
+        let settled = self.entries.iter().filter(|entry| entry.status != TxStatus::Pending).count();
+        let mut excess = settled.saturating_sub(MAX_ENTRIES);
+        self.entries.retain(|entry| {
+            let drop = excess > 0 && entry.status != TxStatus::Pending;
+            if drop {
+                excess -= 1;
+            }
+            !drop
+        });
+    }
+}
+
+/// Start a run of `total` steps from `source`, or with `resume` pick up the interrupted
+/// one. An interrupted run is never silently started over: that takes `restart`.
+pub fn start_run(kind: &str, source: &str, total: usize, resume: bool, restart: bool) -> Result<BatchRun> {
+    // Runs against a fork are throwaway and must not trip over the real chain's
+    if rpc::is_fork() {
+        return Ok(BatchRun::new(format!("{}-fork", kind), kind, source, total));
+    }
+    update(|journal| {
+        let interrupted = journal
+            .runs
+            .iter_mut()
+            .rev()
+            .find(|run| run.kind == kind && run.source == source && run.status == RunStatus::Running);
+        match interrupted {
+            Some(run) if resume => {
+                if run.total != total {
+                    return Err(DexError::Input(format!(
+                        "Run {} had {} steps but {} now has {}; the file changed since (pass --restart to start over)",
+                        run.id,
+                        run.total,
+                        source,
+                        total
+                    )));
+                }
+                info!("Resuming run {}: {} of {} steps already done", run.id, run.completed.len(), run.total);
+                return Ok(run.clone());
+            }
+            Some(run) if !restart => {
+                return Err(DexError::Input(format!(
+                    "{} of {} was interrupted with {} of {} steps done (run {}); pass --resume to continue it or --restart to start over",
+                    kind,
+                    source,
+                    run.completed.len(),
+                    run.total,
+                    run.id
+                )));
+            }
+            Some(run) => {
+                info!("Abandoning run {}", run.id);
+                run.status = RunStatus::Abandoned;
+                run.updated_at = now();
+            }
+            None if resume => return Err(DexError::Input(format!("No interrupted {} of {} to resume", kind, source))),
+            None => {}
+        }
+
+        let run = BatchRun::new(format!("{}-{}", kind, journal.next_id), kind, source, total);
+        journal.next_id += 1;
+        journal.runs.push(run.clone());
+        Ok(run)
+    })?
+}
+
+/// A change to the journal, as appended to its log
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "record", rename_all = "snake_case")]
+enum Record {
+    /// An entry added or changed, in full
+    Entry(JournalEntry),
+    /// A run started or changed, in full
+    Run(BatchRun),
+    NextId { next_id: u64 },
+}
+
+/// The journal's lock file, opened but not yet locked
+fn lock() -> Result<File> {
+    fs::create_dir_all("config")?;
+    let file = OpenOptions::new()
+        .create(true)
+        .truncate(false)
+        .write(true)
+        .open(TX_JOURNAL_LOCK_PATH)
+        .with_context(|| format!("Failed to open {}", TX_JOURNAL_LOCK_PATH))?;
+    Ok(file)
+}
+
+/// Change the journal under an exclusive lock, so processes sharing it take turns, and
+/// append what changed to its log
+fn update<R>(change: impl FnOnce(&mut TxJournal) -> R) -> Result<R> {
+    let lock = lock()?;
+    lock.lock_exclusive().with_context(|| format!("Failed to lock {}", TX_JOURNAL_LOCK_PATH))?;
+    let (mut journal, records) = TxJournal::read()?;
+    let before = journal.clone();
+    let result = change(&mut journal);
+    if rpc::is_fork() {
+        return Ok(result);
+    }
+    let changes = journal.changes_since(&before);
+    if records + changes.len() >= COMPACT_AFTER {
+        journal.compact()?;
+    } else if !changes.is_empty() {
+        TxJournal::append(&changes)?;
+    }
+    Ok(result)
+}
+
+fn update_entry(id: u64, change: impl FnOnce(&mut JournalEntry)) {
+    let result = update(|journal| {
+        if let Some(entry) = journal.entries.iter_mut().find(|entry| entry.id == id) {
+            change(entry);
+            entry.updated_at = now();
+        }
+    });
+    if let Err(e) = result {
+        warn!("Failed to update transaction journal entry {}: {}", id, e);
+    }
+}
+
+/// Record a transaction about to be sent; `tx` must have its sender and nonce filled
+pub fn begin(chain_id: u64, key: Option<&str>, intent: &str, tx: &TypedTransaction) -> Result<u64> {
+    let from = *tx.from().ok_or_else(|| DexError::Input("Transaction to journal has no sender".to_string()))?;
+    let nonce = *tx.nonce().ok_or_else(|| DexError::Input("Transaction to journal has no nonce".to_string()))?;
+    update(|journal| {
+        let id = journal.next_id;
+        journal.next_id += 1;
+        journal.entries.push(JournalEntry {
+            id,
+            idempotency_key: key.map(str::to_string),
+            intent: intent.to_string(),
+            chain_id,
+            from,
+            to: tx.to_addr().copied(),
+            value: tx.value().copied().unwrap_or_default(),
+            calldata_hash: calldata_hash(tx),
+            nonce,
+            tx_hash: None,
+            replaced: Vec::new(),
+            status: TxStatus::Pending,
+            block: None,
+            error: None,
+            created_at: now(),
+            updated_at: now(),
+        });
+        id
+    })
+}
+
+/// Record a broadcast of entry `id`; a second one replaces the first with higher fees
+pub fn sent(id: u64, hash: H256) {
+    update_entry(id, |entry| {
+        if let Some(previous) = entry.tx_hash.replace(hash) {
+            if previous != hash {
+                entry.replaced.push(previous);
+            }
+        }
+    });
+}
+
+/// Record how sending entry `id` ended
+pub fn finish<E: fmt::Display>(id: u64, result: &std::result::Result<Option<TransactionReceipt>, E>) {
+    match result {
+        Ok(Some(receipt)) => update_entry(id, |entry| entry.settle(receipt)),
+        Ok(None) => update_entry(id, |entry| entry.status = TxStatus::Dropped),
+        Err(e) => failed(id, e),
+    }
+}
+
+/// Record that sending entry `id` failed with `error`
+pub fn failed(id: u64, error: &impl fmt::Display) {
+    update_entry(id, |entry| {
+        // Without a hash nothing went out; with one it may still be mined
+        if entry.tx_hash.is_none() {
+            entry.status = TxStatus::Failed;
+        }
+        entry.error = Some(error.to_string());
+    });
+}
+
+/// What became of a transaction sent earlier under an idempotency key
+pub enum Resumed {
+    /// Nothing went out under the key, or what did had no effect: send it
+    Send,
+    /// It went out and succeeded; don't send it again
+    Sent(Box<TransactionReceipt>),
+}
+
+/// Look up what was sent under `key` before sending `tx` under it again.
+///
+/// A transaction still pending is waited for. A reverted one is only sent again with
+/// `resend_reverted`: whatever made it revert may still hold. One that vanished is only
+/// sent again if its nonce is still unused; otherwise something else may have taken it,
+/// so this refuses.
+pub async fn resume<M>(
+    client: &M,
+    chain_id: u64,
+    key: &str,
+    tx: &TypedTransaction,
+    confirmations: usize,
+    resend_reverted: bool,
+) -> Result<Resumed>
+where
+    M: Middleware + 'static,
+{
+    // The journal records the real chain, which the fork may have moved past
+    if rpc::is_fork() {
+        return Ok(Resumed::Send);
+    }
+    let Some(entry) = TxJournal::load()?.by_key(chain_id, key).cloned() else {
+        return Ok(Resumed::Send);
+    };
+    if !entry.same_intent(tx) {
+        return Err(DexError::Input(format!(
+            "Idempotency key '{}' was already used for a different transaction ({}, journal entry {})",
+            key,
+            entry.intent,
+            entry.id
+        )));
+    }
+    if entry.status == TxStatus::Reverted {
+        return reverted(key, &entry, resend_reverted);
+    }
+    if matches!(entry.status, TxStatus::Dropped | TxStatus::Failed) {
+        return Ok(Resumed::Send);
+    }
+
+    for hash in entry.hashes() {
+        if let Some(receipt) = client.get_transaction_receipt(*hash).await.map_err(|e| DexError::middleware(&e))? {
+            update_entry(entry.id, |entry| entry.settle(&receipt));
+            if receipt.status != Some(U64::one()) {
+                return reverted(key, &entry, resend_reverted);
+            }
+            info!("Idempotency key '{}' was already sent as {:?}; not sending again", key, hash);
+            crate::tx::wait_for_confirmations(client, &receipt, confirmations).await?;
+            return Ok(Resumed::Sent(Box::new(receipt)));
+        }
+    }
+    for hash in entry.hashes() {
+        if client.get_transaction(*hash).await.map_err(|e| DexError::middleware(&e))?.is_some() {
+            info!("Idempotency key '{}' is pending as {:?}; waiting for it", key, hash);
+            let receipt = PendingTransaction::new(*hash, client.provider()).confirmations(confirmations).await?;
+            if let Some(receipt) = receipt {
+                update_entry(entry.id, |entry| entry.settle(&receipt));
+                if receipt.status != Some(U64::one()) {
+                    return reverted(key, &entry, resend_reverted);
+                }
+                return Ok(Resumed::Sent(Box::new(receipt)));
+            }
+        }
+    }
+
+    let next_nonce = client.get_transaction_count(entry.from, Some(BlockNumber::Pending.into())).await.map_err(|e| DexError::middleware(&e))?;
+    if next_nonce > entry.nonce {
+        return Err(DexError::Input(format!(
+            "Transaction for idempotency key '{}' (journal entry {}) can't be found, but nonce {} of {:?} has been used since; \
+             check `monad-dex journal show {}` and use a new key to send it again",
+            key,
+            entry.id,
+            entry.nonce,
+            entry.from,
+            entry.id
+        )));
+    }
+    warn!("Transaction for idempotency key '{}' (journal entry {}) was never mined; sending it again", key, entry.id);
+    update_entry(entry.id, |entry| entry.status = TxStatus::Dropped);
+    Ok(Resumed::Send)
+}
+
+/// Send a transaction that reverted under `key` again only when asked to
+fn reverted(key: &str, entry: &JournalEntry, resend: bool) -> Result<Resumed> {
+    if !resend {
+        return Err(DexError::Input(format!(
+            "Transaction for idempotency key '{}' (journal entry {}) reverted; check `monad-dex journal show {}` \
+             and pass --resend-reverted to send it again",
+            key,
+            entry.id,
+            entry.id
+        )));
+    }
+    info!("Idempotency key '{}' reverted (journal entry {}); sending it again", key, entry.id);
+    Ok(Resumed::Send)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(id: u64, status: TxStatus) -> JournalEntry {
+        JournalEntry {
+            id,
+            idempotency_key: None,
+            intent: "placeLimitOrder".to_string(),
+            chain_id: 1,
+            from: Address::repeat_byte(1),
+            to: Some(Address::repeat_byte(2)),
+            value: U256::zero(),
+            calldata_hash: H256::zero(),
+            nonce: U256::from(id),
+            tx_hash: None,
+            replaced: Vec::new(),
+            status,
+            block: None,
+            error: None,
+            created_at: 0,
+            updated_at: 0,
+        }
+    }
+
+    #[test]
+    fn replaying_the_changes_reproduces_the_journal() {
+        let before = TxJournal { next_id: 2, entries: vec![entry(0, TxStatus::Mined), entry(1, TxStatus::Pending)], runs: Vec::new() };
+        let mut after = before.clone();
+        after.entries[1].status = TxStatus::Reverted;
+        after.entries.push(entry(2, TxStatus::Pending));
+        after.runs.push(BatchRun::new("airdrop-3".to_string(), "airdrop", "recipients.csv", 4));
+        after.next_id = 4;
+
+        let changes = after.changes_since(&before);
+        // The untouched entry isn't logged again
+        assert_eq!(changes.len(), 4);
+
+        let mut replayed = before.clone();
+        for record in changes {
+            let line = serde_json::to_string(&record).unwrap();
+            replayed.apply(serde_json::from_str(&line).unwrap());
+        }
+        assert_eq!(replayed.next_id, after.next_id);
+        assert_eq!(replayed.entries, after.entries);
+        assert_eq!(replayed.runs, after.runs);
+    }
+
+    #[test]
+    fn compaction_keeps_pending_entries() {
+        let mut journal = TxJournal::default();
+        journal.entries.push(entry(0, TxStatus::Pending));
+        journal.entries.extend((1..=MAX_ENTRIES as u64 + 1).map(|id| entry(id, TxStatus::Mined)));
+        journal.prune();
+        assert_eq!(journal.entries.len(), MAX_ENTRIES + 1);
+        assert_eq!(journal.entries[0].id, 0);
+        assert_eq!(journal.entries[1].id, 2);
+    }
+}
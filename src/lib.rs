@@ -0,0 +1,79 @@
+//! Shared building blocks for the Monad CLI binaries.
+
+pub mod alerts;
+pub mod aliases;
+pub mod analytics;
+pub mod arb;
+pub mod backtest;
+pub mod bands;
+pub mod bench;
+pub mod bindings;
+pub mod bot;
+pub mod breaker;
+pub mod build;
+pub mod cache;
+pub mod compat;
+pub mod create2;
+pub mod daemon;
+pub mod deployments;
+pub mod doctor;
+pub mod error;
+pub mod events;
+pub mod expiry;
+pub mod explorer;
+pub mod export;
+pub mod fanout;
+pub mod faucet;
+pub mod fees;
+pub mod fills;
+pub mod health;
+pub mod indexer;
+pub mod journal;
+pub mod limits;
+pub mod loadtest;
+pub mod logging;
+pub mod marketdata;
+pub mod matching;
+pub mod mempool;
+pub mod metrics;
+pub mod multicall;
+pub mod networks;
+pub mod notify;
+pub mod offline;
+pub mod orderbook;
+pub mod output;
+pub mod pairs;
+pub mod paper;
+pub mod permit;
+pub mod pipeline;
+pub mod placement;
+pub mod portfolio;
+pub mod price;
+pub mod pricefeed;
+pub mod proxy;
+pub mod publish;
+pub mod qr;
+pub mod recorder;
+pub mod relay;
+pub mod reload;
+pub mod replace;
+pub mod replay;
+pub mod retry;
+pub mod risk;
+pub mod route;
+pub mod rpc;
+pub mod runner;
+pub mod safe;
+pub mod schedule;
+pub mod script;
+pub mod session;
+pub mod shutdown;
+pub mod signing;
+pub mod snapshot;
+pub mod strategy;
+pub mod submit;
+pub mod testing;
+pub mod tokens;
+pub mod trace;
+pub mod tx;
+pub mod wallets;
@@ -0,0 +1,313 @@
+use crate::error::{DexError, Result};
+use crate::expiry;
+use crate::tx::TxArgs;
+use anyhow::Context;
+use ethers::types::{Address, U256};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::fs;
+use std::io::{self, BufRead, IsTerminal, Write};
+use std::path::Path;
+use std::time::SystemTime;
+use tracing::warn;
+
+const SPENDING_LIMITS_PATH: &str = "config/spending_limits.json";
+
+const SPENDING_LEDGER_PATH: &str = "config/spending.json";
+
+const SECS_PER_DAY: u64 = 86_400;
+
+/// What a spending cap applies to
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Spend {
+    /// Tokens withdrawn from the DEX
+    Withdrawal,
+    /// Quote notional of orders placed, limit and market
+    OrderNotional,
+    /// Tokens minted
+    Mint,
+}
+
+impl Spend {
+    pub const ALL: [Spend; 3] = [Spend::Withdrawal, Spend::OrderNotional, Spend::Mint];
+}
+
+impl fmt::Display for Spend {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Spend::Withdrawal => "withdrawal",
+            Spend::OrderNotional => "order notional",
+            Spend::Mint => "mint",
+        })
+    }
+}
+
+/// Caps on one kind of spending, per token
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct Cap {
+    /// Most a single command may spend
+    #[serde(default)]
+    pub per_command: Option<U256>,
+    /// Most all commands together may spend since 00:00 UTC
+    #[serde(default)]
+    pub per_day: Option<U256>,
+}
+
+impl Cap {
+    pub fn is_empty(&self) -> bool {
+        self.per_command.is_none() && self.per_day.is_none()
+    }
+}
+
+/// Caps on what CLI commands spend, checked before anything is signed. Amounts are raw
+/// units of the token spent (the quote token for order notional) and apply to each token
+/// separately. Unset caps are off.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct SpendingLimits {
+    #[serde(default)]
+    pub withdrawal: Cap,
+    #[serde(default)]
+    pub order_notional: Cap,
+    #[serde(default)]
+    pub mint: Cap,
+}
+
+impl SpendingLimits {
+    pub fn load() -> Result<Self> {
+        if !Path::new(SPENDING_LIMITS_PATH).exists() {
+            return Ok(Self::default());
+        }
+        let content = fs::read_to_string(SPENDING_LIMITS_PATH)
+            .with_context(|| format!("Failed to read {}", SPENDING_LIMITS_PATH))?;
+        Ok(serde_json::from_str(&content)?)
+    }
+
+    pub fn save(&self) -> Result<()> {
+        fs::create_dir_all("config")?;
+        fs::write(SPENDING_LIMITS_PATH, serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+
+    pub fn is_empty(&self) -> bool {
+        Spend::ALL.iter().all(|spend| self.cap(*spend).is_empty())
+    }
+
+    pub fn cap(&self, spend: Spend) -> Cap {
+        match spend {
+            Spend::Withdrawal => self.withdrawal,
+            Spend::OrderNotional => self.order_notional,
+            Spend::Mint => self.mint,
+        }
+    }
+}
+
+/// What commands have spent of each token today
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SpendingLedger {
+    /// UTC day number the totals are for
+    pub day: u64,
+    #[serde(default)]
+    pub spent: BTreeMap<Spend, BTreeMap<Address, U256>>,
+}
+
+fn today() -> u64 {
+    expiry::unix_secs(SystemTime::now()) / SECS_PER_DAY
+}
+
+impl SpendingLedger {
+    /// Today's totals; a ledger left from an earlier day starts over
+    pub fn load() -> Result<Self> {
+        let today = today();
+        if !Path::new(SPENDING_LEDGER_PATH).exists() {
+            return Ok(Self { day: today, spent: BTreeMap::new() });
+        }
+        let content = fs::read_to_string(SPENDING_LEDGER_PATH)
+            .with_context(|| format!("Failed to read {}", SPENDING_LEDGER_PATH))?;
+        let ledger: Self = serde_json::from_str(&content)?;
+        Ok(ledger.on_day(today))
+    }
+
+    /// The ledger as of UTC day `day`: totals from any other day no longer count
+    pub fn on_day(self, day: u64) -> Self {
+        if self.day == day {
+            self
+        } else {
+            Self { day, spent: BTreeMap::new() }
+        }
+    }
+
+    pub fn save(&self) -> Result<()> {
+        fs::create_dir_all("config")?;
+        fs::write(SPENDING_LEDGER_PATH, serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+
+    pub fn spent(&self, spend: Spend, token: Address) -> U256 {
+        self.spent
+            .get(&spend)
+            .and_then(|tokens| tokens.get(&token))
+            .copied()
+            .unwrap_or_default()
+    }
+}
+
+/// Refuse a command about to spend `amounts` (its total per token) beyond the limits set
+/// with `limits set`. With `--override-limit` the breach is confirmed on the terminal
+/// instead; `--yes` does not skip that.
+pub fn check(spend: Spend, amounts: &BTreeMap<Address, U256>, args: &TxArgs) -> Result<()> {
+    let cap = SpendingLimits::load()?.cap(spend);
+    if cap.is_empty() {
+        return Ok(());
+    }
+    check_against(cap, &SpendingLedger::load()?, spend, amounts, args, confirm_override)
+}
+
+/// [`check`] against a given cap and ledger, with `confirm` asked about overridden breaches
+fn check_against(
+    cap: Cap,
+    ledger: &SpendingLedger,
+    spend: Spend,
+    amounts: &BTreeMap<Address, U256>,
+    args: &TxArgs,
+    confirm: impl FnOnce(&str) -> Result<()>,
+) -> Result<()> {
+    let mut breaches = Vec::new();
+    for (token, amount) in amounts {
+        if let Some(max) = cap.per_command {
+            if *amount > max {
+                breaches.push(format!("{} {} of {:?} exceeds the per-command limit of {}", spend, amount, token, max));
+            }
+        }
+        if let Some(max) = cap.per_day {
+            let total = ledger.spent(spend, *token) + *amount;
+            if total > max {
+                breaches.push(format!("{} of {:?} today would reach {}, the daily limit is {}", spend, token, total, max));
+            }
+        }
+    }
+    if breaches.is_empty() {
+        return Ok(());
+    }
+    let breach = breaches.join("; ");
+    if !args.override_limit {
+        return Err(DexError::Input(format!("Spending limit: {} (pass --override-limit to exceed it)", breach)));
+    }
+    // Nothing is signed when only estimating
+    if args.estimate_only {
+        return Ok(());
+    }
+    confirm(&breach)
+}
+
+/// Ask for the word `override` on the terminal before exceeding a limit
+fn confirm_override(breach: &str) -> Result<()> {
+    if !io::stdin().is_terminal() {
+        return Err(DexError::Input(format!("Spending limit: {} (--override-limit needs a terminal to confirm on)", breach)));
+    }
+    eprintln!("Spending limit: {}", breach);
+    eprint!("Type 'override' to exceed it: ");
+    io::stderr().flush()?;
+    let mut answer = String::new();
+    io::stdin().lock().read_line(&mut answer)?;
+    if answer.trim() != "override" {
+        return Err(DexError::Input("Aborted: spending limit not overridden".to_string()));
+    }
+    warn!("Spending limit overridden: {}", breach);
+    Ok(())
+}
+
+/// Count `amount` of `token` toward today's total once it has been spent
+pub fn record(spend: Spend, token: Address, amount: U256) -> Result<()> {
+    let mut ledger = SpendingLedger::load()?;
+    *ledger.spent.entry(spend).or_default().entry(token).or_default() += amount;
+    ledger.save()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TOKEN: Address = Address::repeat_byte(1);
+    const OTHER: Address = Address::repeat_byte(2);
+
+    fn amounts(entries: &[(Address, u64)]) -> BTreeMap<Address, U256> {
+        entries.iter().map(|(token, amount)| (*token, U256::from(*amount))).collect()
+    }
+
+    fn ledger(day: u64, spent: u64) -> SpendingLedger {
+        let mut ledger = SpendingLedger { day, spent: BTreeMap::new() };
+        ledger.spent.entry(Spend::Withdrawal).or_default().insert(TOKEN, U256::from(spent));
+        ledger
+    }
+
+    fn cap(per_command: Option<u64>, per_day: Option<u64>) -> Cap {
+        Cap { per_command: per_command.map(U256::from), per_day: per_day.map(U256::from) }
+    }
+
+    fn check(cap: Cap, ledger: &SpendingLedger, amounts: &BTreeMap<Address, U256>, args: &TxArgs) -> Result<()> {
+        check_against(cap, ledger, Spend::Withdrawal, amounts, args, |_| panic!("nothing to confirm"))
+    }
+
+    #[test]
+    fn spending_within_both_caps_passes() {
+        let args = TxArgs::default();
+        check(cap(Some(100), Some(200)), &ledger(0, 100), &amounts(&[(TOKEN, 100)]), &args).unwrap();
+    }
+
+    #[test]
+    fn per_command_cap_applies_to_each_token() {
+        let args = TxArgs::default();
+        let error = check(cap(Some(100), None), &ledger(0, 0), &amounts(&[(TOKEN, 50), (OTHER, 101)]), &args)
+            .unwrap_err()
+            .to_string();
+        assert!(error.contains("per-command limit"), "{}", error);
+        assert!(error.contains(&format!("{:?}", OTHER)), "{}", error);
+        assert!(!error.contains(&format!("{:?}", TOKEN)), "{}", error);
+    }
+
+    #[test]
+    fn daily_cap_counts_what_was_spent_today() {
+        let args = TxArgs::default();
+        let error = check(cap(None, Some(200)), &ledger(0, 150), &amounts(&[(TOKEN, 51)]), &args).unwrap_err();
+        assert!(error.to_string().contains("daily limit"), "{}", error);
+        // Other tokens have a budget of their own
+        check(cap(None, Some(200)), &ledger(0, 150), &amounts(&[(OTHER, 200)]), &args).unwrap();
+    }
+
+    #[test]
+    fn a_new_day_starts_the_totals_over() {
+        let args = TxArgs::default();
+        let yesterday = ledger(9, 200).on_day(10);
+        assert_eq!(yesterday.day, 10);
+        assert!(yesterday.spent(Spend::Withdrawal, TOKEN).is_zero());
+        check(cap(None, Some(200)), &yesterday, &amounts(&[(TOKEN, 200)]), &args).unwrap();
+
+        let today = ledger(10, 200).on_day(10);
+        assert_eq!(today.spent(Spend::Withdrawal, TOKEN), U256::from(200));
+    }
+
+    #[test]
+    fn override_asks_for_confirmation() {
+        let args = TxArgs { override_limit: true, ..TxArgs::default() };
+        let mut asked = None;
+        check_against(cap(Some(10), None), &ledger(0, 0), Spend::Withdrawal, &amounts(&[(TOKEN, 11)]), &args, |breach| {
+            asked = Some(breach.to_string());
+            Ok(())
+        })
+        .unwrap();
+        assert!(asked.unwrap().contains("per-command limit"));
+
+        let refused = check_against(cap(Some(10), None), &ledger(0, 0), Spend::Withdrawal, &amounts(&[(TOKEN, 11)]), &args, |_| {
+            Err(DexError::Input("Aborted: spending limit not overridden".to_string()))
+        });
+        assert!(refused.is_err());
+    }
+
+    #[test]
+    fn estimates_skip_the_override_prompt() {
+        let args = TxArgs { override_limit: true, estimate_only: true, ..TxArgs::default() };
+        check(cap(Some(10), None), &ledger(0, 0), &amounts(&[(TOKEN, 11)]), &args).unwrap();
+    }
+}
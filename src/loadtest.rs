@@ -0,0 +1,314 @@
+use crate::bindings::{OrderBookDEX, ERC20};
+use crate::bot::SignerClient;
+use crate::error::{DexError, Result};
+use crate::events;
+use crate::fees::Fees;
+use crate::matching::FEE_DENOMINATOR;
+use crate::rpc;
+use crate::tx::{self, TxArgs};
+use ethers::{
+    providers::Middleware,
+    types::{transaction::eip2718::TypedTransaction, Address, BlockNumber, H256, U256},
+};
+use rand::Rng;
+use serde::Serialize;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::task::JoinSet;
+use tokio::time::{sleep, timeout, Instant, MissedTickBehavior};
+use tracing::{info, warn};
+
+/// What to send and how fast
+#[derive(Debug, Clone)]
+pub struct LoadTestParams {
+    pub dex: Address,
+    pub base_token: Address,
+    pub quote_token: Address,
+    /// Target transactions per second across all accounts
+    pub rate: f64,
+    pub duration: Duration,
+    /// Share of transactions that cancel one of the account's own resting orders
+    pub cancel_ratio: f64,
+    /// Orders are priced uniformly within `spread_bps` either side of this
+    pub mid_price: U256,
+    pub spread_bps: u64,
+    pub min_amount: U256,
+    pub max_amount: U256,
+    /// Fixed gas limit, so sending never waits on `eth_estimateGas`
+    pub gas_limit: U256,
+    pub receipt_timeout: Duration,
+    pub poll_interval: Duration,
+}
+
+/// Latency percentiles in milliseconds
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct LatencyStats {
+    pub samples: usize,
+    pub min_ms: u64,
+    pub p50_ms: u64,
+    pub p90_ms: u64,
+    pub p99_ms: u64,
+    pub max_ms: u64,
+    pub mean_ms: u64,
+}
+
+impl LatencyStats {
+    fn from_samples(mut samples: Vec<Duration>) -> Self {
+        if samples.is_empty() {
+            return Self::default();
+        }
+        samples.sort();
+        let percentile = |p: usize| samples[((samples.len() - 1) * p / 100).min(samples.len() - 1)].as_millis() as u64;
+        let total: Duration = samples.iter().sum();
+        Self {
+            samples: samples.len(),
+            min_ms: samples[0].as_millis() as u64,
+            p50_ms: percentile(50),
+            p90_ms: percentile(90),
+            p99_ms: percentile(99),
+            max_ms: samples[samples.len() - 1].as_millis() as u64,
+            mean_ms: (total / samples.len() as u32).as_millis() as u64,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct LoadReport {
+    pub accounts: usize,
+    pub elapsed_secs: f64,
+    pub target_rate: f64,
+    pub places_sent: usize,
+    pub cancels_sent: usize,
+    /// Transactions the node refused outright (nonce, funds, rate limits)
+    pub send_failures: usize,
+    pub confirmed: usize,
+    /// Mined with a failed status; cancels of orders filled in the meantime land here
+    pub reverted: usize,
+    /// No receipt within the receipt timeout
+    pub timed_out: usize,
+    /// Sent transactions per second actually achieved
+    pub send_rate: f64,
+    /// Confirmed transactions per second
+    pub throughput: f64,
+    pub failure_rate: f64,
+    /// Send to receipt
+    pub confirmation_latency: LatencyStats,
+    /// Time `eth_sendRawTransaction` took to return
+    pub submit_latency: LatencyStats,
+}
+
+#[derive(Default)]
+struct Counters {
+    places_sent: usize,
+    cancels_sent: usize,
+    send_failures: usize,
+    confirmed: usize,
+    reverted: usize,
+    timed_out: usize,
+    confirmation: Vec<Duration>,
+    submit: Vec<Duration>,
+}
+
+/// One funded account: its client, the next nonce to use and its resting orders
+struct Account {
+    client: Arc<SignerClient>,
+    nonce: Mutex<U256>,
+    open_orders: Mutex<Vec<U256>>,
+}
+
+/// Approve the DEX for both tokens from every account that hasn't already
+pub async fn prepare(clients: &[Arc<SignerClient>], params: &LoadTestParams, fees: &Fees, tx_args: &TxArgs) -> Result<()> {
+    for client in clients {
+        for token in [params.base_token, params.quote_token] {
+            if token.is_zero() {
+                continue;
+            }
+            let contract = ERC20::new(token, client.clone());
+            if contract.allowance(client.address(), params.dex).call().await? >= U256::MAX / 2 {
+                continue;
+            }
+            info!("Approving {:?} on {:?} for {:?}", params.dex, token, client.address());
+            tx::send(client.as_ref(), contract.approve(params.dex, U256::MAX), fees, tx_args).await?;
+        }
+    }
+    Ok(())
+}
+
+/// Send randomized limit orders and cancels at `params.rate` for `params.duration`,
+/// round-robin across `clients`, then wait for outstanding receipts and report.
+///
+/// Each account pipelines transactions with locally tracked nonces instead of waiting for
+/// every receipt, so throughput is bounded by the node, not by confirmation latency.
+pub async fn run(clients: Vec<Arc<SignerClient>>, params: LoadTestParams, fees: Fees) -> Result<LoadReport> {
+    if clients.is_empty() {
+        return Err(DexError::Input("Load test needs at least one account".to_string()));
+    }
+    if params.rate <= 0.0 {
+        return Err(DexError::Input("Rate must be positive".to_string()));
+    }
+
+    let mut accounts = Vec::with_capacity(clients.len());
+    for client in clients {
+        let nonce = client
+            .get_transaction_count(client.address(), Some(BlockNumber::Pending.into()))
+            .await.map_err(|e| DexError::middleware(&e))?;
+        accounts.push(Arc::new(Account { client, nonce: Mutex::new(nonce), open_orders: Mutex::new(Vec::new()) }));
+    }
+
+    let counters = Arc::new(Mutex::new(Counters::default()));
+    let params = Arc::new(params);
+    let fees = Arc::new(fees);
+    let mut tasks = JoinSet::new();
+
+    let mut ticker = tokio::time::interval(Duration::from_secs_f64(1.0 / params.rate));
+    ticker.set_missed_tick_behavior(MissedTickBehavior::Burst);
+    let started = Instant::now();
+    let mut next = 0;
+    info!("Sending {} tx/s from {} accounts for {:?}", params.rate, accounts.len(), params.duration);
+
+    while started.elapsed() < params.duration {
+        tokio::select! {
+            _ = ticker.tick() => {}
+            _ = tokio::signal::ctrl_c() => {
+                warn!("Interrupted, waiting for outstanding receipts");
+                break;
+            }
+        }
+        let account = accounts[next % accounts.len()].clone();
+        next += 1;
+        tasks.spawn(send_one(account, params.clone(), fees.clone(), counters.clone()));
+    }
+    let sending = started.elapsed();
+
+    while tasks.join_next().await.is_some() {}
+    let elapsed = started.elapsed().as_secs_f64();
+
+    let counters = std::mem::take(&mut *counters.lock().expect("counters lock"));
+    let sent = counters.places_sent + counters.cancels_sent;
+    let attempted = sent + counters.send_failures;
+    let failed = counters.send_failures + counters.reverted + counters.timed_out;
+    Ok(LoadReport {
+        accounts: accounts.len(),
+        elapsed_secs: elapsed,
+        target_rate: params.rate,
+        places_sent: counters.places_sent,
+        cancels_sent: counters.cancels_sent,
+        send_failures: counters.send_failures,
+        confirmed: counters.confirmed,
+        reverted: counters.reverted,
+        timed_out: counters.timed_out,
+        send_rate: sent as f64 / sending.as_secs_f64().max(f64::EPSILON),
+        throughput: counters.confirmed as f64 / elapsed.max(f64::EPSILON),
+        failure_rate: if attempted == 0 { 0.0 } else { failed as f64 / attempted as f64 },
+        confirmation_latency: LatencyStats::from_samples(counters.confirmation),
+        submit_latency: LatencyStats::from_samples(counters.submit),
+    })
+}
+
+async fn send_one(account: Arc<Account>, params: Arc<LoadTestParams>, fees: Arc<Fees>, counters: Arc<Mutex<Counters>>) {
+    let contract = OrderBookDEX::new(params.dex, account.client.clone());
+
+    // Cancel only when there is something of ours to cancel
+    let cancel = {
+        let mut rng = rand::thread_rng();
+        let mut open = account.open_orders.lock().expect("open orders lock");
+        if !open.is_empty() && rng.gen_bool(params.cancel_ratio.clamp(0.0, 1.0)) {
+            let index = rng.gen_range(0..open.len());
+            Some(open.swap_remove(index))
+        } else {
+            None
+        }
+    };
+    let mut tx: TypedTransaction = match cancel {
+        Some(order_id) => contract.cancel_order(order_id).tx,
+        None => {
+            let (amount, price, is_buy) = random_order(&params);
+            contract.place_limit_order(params.base_token, params.quote_token, amount, price, is_buy).tx
+        }
+    };
+
+    fees.apply(&mut tx);
+    tx.set_gas(params.gas_limit);
+    let nonce = {
+        let mut nonce = account.nonce.lock().expect("nonce lock");
+        let current = *nonce;
+        *nonce += U256::one();
+        current
+    };
+    tx.set_nonce(nonce);
+
+    let sent_at = Instant::now();
+    let hash = match submit(&account, tx).await {
+        Ok(hash) => hash,
+        Err(e) => {
+            warn!("Send failed from {:?}: {}", account.client.address(), e);
+            counters.lock().expect("counters lock").send_failures += 1;
+            resync_nonce(&account).await;
+            return;
+        }
+    };
+    {
+        let mut counters = counters.lock().expect("counters lock");
+        counters.submit.push(sent_at.elapsed());
+        if cancel.is_some() {
+            counters.cancels_sent += 1;
+        } else {
+            counters.places_sent += 1;
+        }
+    }
+
+    let receipt = timeout(params.receipt_timeout, async {
+        loop {
+            match account.client.get_transaction_receipt(hash).await {
+                Ok(Some(receipt)) => return receipt,
+                Ok(None) => {}
+                Err(e) => warn!("Receipt poll for {:?} failed: {}", hash, e),
+            }
+            sleep(params.poll_interval).await;
+        }
+    })
+    .await;
+
+    let mut counters = counters.lock().expect("counters lock");
+    match receipt {
+        Ok(receipt) if receipt.status.is_none_or(|s| s.as_u64() == 1) => {
+            counters.confirmed += 1;
+            counters.confirmation.push(sent_at.elapsed());
+            if let Some(order_id) = events::placed_order_id(&receipt) {
+                account.open_orders.lock().expect("open orders lock").push(order_id);
+            }
+        }
+        Ok(_) => counters.reverted += 1,
+        Err(_) => counters.timed_out += 1,
+    }
+}
+
+async fn submit(account: &Account, tx: TypedTransaction) -> Result<H256> {
+    rpc::ensure_writable()?;
+    let pending = account.client.send_transaction(tx, None).await.map_err(|e| DexError::middleware(&e))?;
+    Ok(*pending)
+}
+
+/// After a refused send the local nonce is ahead of the chain; take the node's view again
+async fn resync_nonce(account: &Account) {
+    let address = account.client.address();
+    match account.client.get_transaction_count(address, Some(BlockNumber::Pending.into())).await {
+        Ok(pending) => *account.nonce.lock().expect("nonce lock") = pending,
+        Err(e) => warn!("Failed to resync nonce for {:?}: {}", address, e),
+    }
+}
+
+fn random_order(params: &LoadTestParams) -> (U256, U256, bool) {
+    let mut rng = rand::thread_rng();
+    let is_buy = rng.gen_bool(0.5);
+    let offset_bps = rng.gen_range(0..=params.spread_bps);
+    let offset = params.mid_price * offset_bps / FEE_DENOMINATOR;
+    // Buys rest at or below mid and sells at or above, so only orders right at mid match
+    let price = if is_buy { params.mid_price.saturating_sub(offset) } else { params.mid_price + offset }.max(U256::one());
+    let amount = if params.max_amount > params.min_amount {
+        params.min_amount + rng.gen_range(0..=(params.max_amount - params.min_amount).low_u64())
+    } else {
+        params.min_amount
+    };
+    (amount, price, is_buy)
+}
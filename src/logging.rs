@@ -0,0 +1,137 @@
+use crate::error::{DexError, Result};
+use anyhow::{anyhow, Context};
+use clap::{Args, ValueEnum};
+use opentelemetry::KeyValue;
+use opentelemetry_otlp::WithExportConfig;
+use opentelemetry_sdk::{runtime, trace, Resource};
+use std::path::Path;
+use tracing_appender::non_blocking::WorkerGuard;
+use tracing_appender::rolling::{RollingFileAppender, Rotation};
+use tracing_subscriber::fmt::{self, writer::BoxMakeWriter};
+use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt, EnvFilter, Layer};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, ValueEnum)]
+pub enum LogFormat {
+    #[default]
+    Text,
+    Json,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, ValueEnum)]
+pub enum LogRotation {
+    Never,
+    Hourly,
+    #[default]
+    Daily,
+}
+
+/// Logging settings shared by every binary
+#[derive(Debug, Clone, Args)]
+pub struct LogArgs {
+    /// Log filter, e.g. `info` or `info,monad_app::rpc=debug` (defaults to RUST_LOG, then info)
+    #[arg(long, global = true)]
+    pub log_level: Option<String>,
+
+    /// Log line format
+    #[arg(long, value_enum, default_value_t = LogFormat::Text, global = true)]
+    pub log_format: LogFormat,
+
+    /// Write logs to this file instead of stderr
+    #[arg(long, global = true)]
+    pub log_file: Option<String>,
+
+    /// When to start a new log file; rotated files get a date suffix
+    #[arg(long, value_enum, default_value_t = LogRotation::Daily, global = true)]
+    pub log_rotation: LogRotation,
+
+    /// Also export spans (order lifecycle, transactions) over OTLP/HTTP to this collector,
+    /// e.g. http://localhost:4318
+    #[arg(long, global = true)]
+    pub otlp_endpoint: Option<String>,
+
+    /// Service name the exported spans are reported under
+    #[arg(long, default_value = "monad-app", global = true)]
+    pub otlp_service_name: String,
+}
+
+/// Flushes buffered log file output and exported spans when dropped
+pub struct LogGuard {
+    _file: Option<WorkerGuard>,
+    otlp: bool,
+}
+
+impl Drop for LogGuard {
+    fn drop(&mut self) {
+        if self.otlp {
+            opentelemetry::global::shutdown_tracer_provider();
+        }
+    }
+}
+
+/// Install the global subscriber. Keep the returned guard alive until exit so
+/// buffered file output and spans are flushed.
+pub fn init(args: &LogArgs) -> Result<LogGuard> {
+    let filter = match &args.log_level {
+        Some(level) => EnvFilter::try_new(level).with_context(|| format!("Invalid log level '{}'", level))?,
+        None => EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new("info")),
+    };
+    let otlp = args
+        .otlp_endpoint
+        .as_deref()
+        .map(|endpoint| otlp_tracer(endpoint, &args.otlp_service_name))
+        .transpose()?
+        .map(|tracer| tracing_opentelemetry::layer().with_tracer(tracer));
+    let exporting = otlp.is_some();
+
+    let Some(log_file) = &args.log_file else {
+        let layer = fmt::layer();
+        let layer = match args.log_format {
+            LogFormat::Text => layer.boxed(),
+            LogFormat::Json => layer.json().boxed(),
+        };
+        tracing_subscriber::registry()
+            .with(filter)
+            .with(layer)
+            .with(otlp)
+            .try_init()
+            .map_err(|e| anyhow!("Failed to initialize logging: {}", e))?;
+        return Ok(LogGuard { _file: None, otlp: exporting });
+    };
+
+    let path = Path::new(log_file);
+    let directory = path.parent().filter(|dir| !dir.as_os_str().is_empty()).unwrap_or(Path::new("."));
+    let file_name = path
+        .file_name()
+        .ok_or_else(|| DexError::Input(format!("Invalid log file '{}'", log_file)))?;
+    let rotation = match args.log_rotation {
+        LogRotation::Never => Rotation::NEVER,
+        LogRotation::Hourly => Rotation::HOURLY,
+        LogRotation::Daily => Rotation::DAILY,
+    };
+    let (writer, file_guard) = tracing_appender::non_blocking(RollingFileAppender::new(rotation, directory, file_name));
+
+    let layer = fmt::layer().with_writer(BoxMakeWriter::new(writer)).with_ansi(false);
+    let layer = match args.log_format {
+        LogFormat::Text => layer.boxed(),
+        LogFormat::Json => layer.json().boxed(),
+    };
+    tracing_subscriber::registry()
+        .with(filter)
+        .with(layer)
+        .with(otlp)
+        .try_init()
+        .map_err(|e| anyhow!("Failed to initialize logging: {}", e))?;
+    Ok(LogGuard { _file: Some(file_guard), otlp: exporting })
+}
+
+/// Batch spans to the OTLP/HTTP collector at `endpoint` from the tokio runtime
+fn otlp_tracer(endpoint: &str, service_name: &str) -> Result<trace::Tracer> {
+    opentelemetry_otlp::new_pipeline()
+        .tracing()
+        .with_exporter(opentelemetry_otlp::new_exporter().http().with_endpoint(endpoint))
+        .with_trace_config(
+            trace::config().with_resource(Resource::new(vec![KeyValue::new("service.name", service_name.to_string())])),
+        )
+        .install_batch(runtime::Tokio)
+        .map_err(|e| anyhow!("Failed to start exporting spans to {}: {}", endpoint, e).into())
+}
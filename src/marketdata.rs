@@ -0,0 +1,181 @@
+use crate::error::Result;
+use crate::expiry::unix_secs;
+#[cfg(feature = "redis")]
+use crate::publish::{MarketSnapshot, Publisher};
+use crate::publish::{PairBook, PairTicker};
+use crate::strategy::BookView;
+#[cfg(feature = "redis")]
+use anyhow::anyhow;
+#[cfg(feature = "redis")]
+use async_trait::async_trait;
+use ethers::types::Address;
+#[cfg(feature = "redis")]
+use redis::{aio::ConnectionManager as Connection, AsyncCommands};
+use serde::{Deserialize, Serialize};
+use std::time::SystemTime;
+
+/// Stands in for the connection in builds without the `redis` feature, where none is made
+#[cfg(not(feature = "redis"))]
+type Connection = std::convert::Infallible;
+
+/// Where the daemon keeps the markets it indexes in Redis (`redis` under `[publish]`)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RedisConfig {
+    /// Server URL, e.g. redis://localhost:6379
+    pub url: String,
+}
+
+/// Where a bot reads the book the daemon keeps in Redis (`[shared_book]` in its config)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SharedBookConfig {
+    /// Server URL, e.g. redis://localhost:6379
+    pub redis: String,
+    /// The daemon's `topic_prefix`
+    #[serde(default = "default_prefix")]
+    pub prefix: String,
+    /// Read the chain instead once the daemon hasn't synced for this long
+    #[serde(default = "default_max_age_secs")]
+    pub max_age_secs: u64,
+}
+
+fn default_prefix() -> String {
+    "monad-dex".to_string()
+}
+
+fn default_max_age_secs() -> u64 {
+    30
+}
+
+/// When the daemon last synced the index, stored under `<prefix>:<dex>:head`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Head {
+    pub block: u64,
+    /// Unix time of the sync
+    pub updated_at: u64,
+}
+
+fn head_key(prefix: &str, dex: Address) -> String {
+    format!("{}:{:?}:head", prefix, dex)
+}
+
+fn book_key(prefix: &str, dex: Address, base_token: Address, quote_token: Address) -> String {
+    format!("{}:{:?}:book:{:?}:{:?}", prefix, dex, base_token, quote_token)
+}
+
+fn ticker_key(prefix: &str, dex: Address, base_token: Address, quote_token: Address) -> String {
+    format!("{}:{:?}:ticker:{:?}:{:?}", prefix, dex, base_token, quote_token)
+}
+
+#[cfg(feature = "redis")]
+async fn connect(url: &str) -> Result<Connection> {
+    let client = redis::Client::open(url).map_err(|e| DexError::Config(format!("Invalid Redis URL: {}", e)))?;
+    client
+        .get_connection_manager()
+        .await
+        .map_err(|e| anyhow!("Failed to connect to Redis: {}", e))
+}
+
+#[cfg(not(feature = "redis"))]
+async fn connect(_url: &str) -> Result<Connection> {
+    Err(crate::publish::not_built("redis"))
+}
+
+/// Publishes events on Redis channels named after their topics and keeps each pair's
+/// latest book and ticker as JSON under `<prefix>:<dex>:book:<base>:<quote>` and
+/// `<prefix>:<dex>:ticker:<base>:<quote>`
+#[cfg(feature = "redis")]
+pub struct RedisPublisher {
+    connection: Connection,
+    prefix: String,
+}
+
+#[cfg(feature = "redis")]
+impl RedisPublisher {
+    pub async fn connect(config: &RedisConfig, prefix: &str) -> Result<Self> {
+        Ok(Self { connection: connect(&config.url).await?, prefix: prefix.to_string() })
+    }
+}
+
+#[cfg(feature = "redis")]
+#[async_trait]
+impl Publisher for RedisPublisher {
+    fn name(&self) -> &str {
+        "redis"
+    }
+
+    async fn publish(&self, topic: &str, _key: &str, payload: &[u8]) -> Result<()> {
+        let mut connection = self.connection.clone();
+        connection.publish::<_, _, ()>(topic, payload).await?;
+        Ok(())
+    }
+
+    async fn cache(&self, snapshot: &MarketSnapshot) -> Result<()> {
+        let mut pipe = redis::pipe();
+        for book in &snapshot.books {
+            let key = book_key(&self.prefix, snapshot.dex, book.base_token, book.quote_token);
+            pipe.set(key, serde_json::to_string(book)?).ignore();
+        }
+        for ticker in &snapshot.tickers {
+            let key = ticker_key(&self.prefix, snapshot.dex, ticker.base_token, ticker.quote_token);
+            pipe.set(key, serde_json::to_string(ticker)?).ignore();
+        }
+        // Last, so a fresh head never points at books not yet written
+        let head = Head { block: snapshot.block, updated_at: snapshot.updated_at };
+        pipe.set(head_key(&self.prefix, snapshot.dex), serde_json::to_string(&head)?).ignore();
+
+        let mut connection = self.connection.clone();
+        pipe.query_async::<_, ()>(&mut connection).await?;
+        Ok(())
+    }
+}
+
+/// Reads the books and tickers a daemon keeps in Redis, so bots on one host share its
+/// chain connection instead of each polling the book
+pub struct SharedBook {
+    connection: Connection,
+    config: SharedBookConfig,
+}
+
+impl SharedBook {
+    pub async fn connect(config: &SharedBookConfig) -> Result<Self> {
+        Ok(Self { connection: connect(&config.redis).await?, config: config.clone() })
+    }
+
+    #[cfg(not(feature = "redis"))]
+    async fn get<T: for<'de> Deserialize<'de>>(&self, _key: String) -> Result<Option<T>> {
+        match self.connection {}
+    }
+
+    #[cfg(feature = "redis")]
+    async fn get<T: for<'de> Deserialize<'de>>(&self, key: String) -> Result<Option<T>> {
+        let mut connection = self.connection.clone();
+        let value: Option<String> = connection.get(&key).await?;
+        value
+            .map(|value| serde_json::from_str(&value).map_err(|e| anyhow!("Invalid JSON under {}: {}", key, e)))
+            .transpose()
+    }
+
+    /// The last sync, unless it is older than `max_age_secs`
+    pub async fn head(&self, dex: Address) -> Result<Option<Head>> {
+        let head: Option<Head> = self.get(head_key(&self.config.prefix, dex)).await?;
+        let now = unix_secs(SystemTime::now());
+        Ok(head.filter(|head| now.saturating_sub(head.updated_at) <= self.config.max_age_secs))
+    }
+
+    /// The pair's book as of the last sync; `None` when the daemon is stale or hasn't
+    /// seen the pair
+    pub async fn book(&self, dex: Address, base_token: Address, quote_token: Address) -> Result<Option<BookView>> {
+        let Some(head) = self.head(dex).await? else {
+            return Ok(None);
+        };
+        let book: Option<PairBook> = self.get(book_key(&self.config.prefix, dex, base_token, quote_token)).await?;
+        Ok(book.map(|book| BookView { block: Some(head.block), bids: book.bids, asks: book.asks, reference: None }))
+    }
+
+    pub async fn ticker(&self, dex: Address, base_token: Address, quote_token: Address) -> Result<Option<PairTicker>> {
+        if self.head(dex).await?.is_none() {
+            return Ok(None);
+        }
+        self.get(ticker_key(&self.config.prefix, dex, base_token, quote_token)).await
+    }
+}
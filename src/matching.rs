@@ -0,0 +1,447 @@
+use crate::error::{DexError, Result};
+use ethers::types::{Address, U256};
+use serde::{Deserialize, Serialize};
+
+/// `FEE_DENOMINATOR` in the DEX contract
+pub const FEE_DENOMINATOR: u64 = 10_000;
+
+/// Default `tradingFee` of the DEX contract, taken from the base amount of every limit match.
+/// Owners can change it with `setTradingFee`; simulations assume the default.
+pub const TRADING_FEE: u64 = 30;
+
+/// Fee the contract keeps from a limit match of `matched` base at `fee_rate`
+pub fn trading_fee(matched: U256, fee_rate: u64) -> U256 {
+    matched * fee_rate / FEE_DENOMINATOR
+}
+
+/// Recover the matched amount from the post-fee amount an `OrderMatched` event carries
+pub fn matched_from_net(net: U256, fee_rate: u64) -> U256 {
+    if fee_rate >= FEE_DENOMINATOR {
+        return net;
+    }
+    // The fee is rounded down, so the estimate can be one short
+    let estimate = net * FEE_DENOMINATOR / (FEE_DENOMINATOR - fee_rate);
+    let next = estimate + 1;
+    if next - trading_fee(next, fee_rate) == net {
+        next
+    } else {
+        estimate
+    }
+}
+
+/// `numerator / denominator` to two decimal places, without overflowing on 18-decimal amounts
+fn ratio(numerator: U256, denominator: U256) -> f64 {
+    (numerator * U256::from(100))
+        .checked_div(denominator)
+        .map_or(0.0, |hundredths| hundredths.low_u128() as f64 / 100.0)
+}
+
+/// A resting order in the local book
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BookOrder {
+    pub id: u64,
+    /// Owner of the order, `None` for liquidity copied from the live book
+    pub owner: Option<Address>,
+    pub amount: U256,
+    pub price: U256,
+    pub is_buy: bool,
+}
+
+/// A limit order match, as the contract emits it in `OrderMatched`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Match {
+    pub buy_order_id: u64,
+    pub sell_order_id: u64,
+    /// Base amount removed from both orders
+    pub matched: U256,
+    /// Base amount credited to the buyer after the trading fee
+    pub base_amount: U256,
+    /// Quote amount credited to the seller
+    pub quote_amount: U256,
+    /// Average of the two order prices
+    pub price: U256,
+}
+
+/// A resting order consumed by a market order
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Take {
+    pub order_id: u64,
+    pub owner: Option<Address>,
+    pub amount: U256,
+    pub price: U256,
+    pub quote_amount: U256,
+}
+
+/// What a market order would cost against the current book
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MarketQuote {
+    pub amount: U256,
+    pub is_buy: bool,
+    /// Base amount the book can fill; the contract reverts the whole order unless this is `amount`
+    pub filled: U256,
+    pub filled_pct: f64,
+    /// Quote paid (buy) or received (sell) for `filled`
+    pub quote_amount: U256,
+    /// Best ask for a buy, best bid for a sell
+    pub best_price: Option<U256>,
+    pub average_price: Option<U256>,
+    pub worst_price: Option<U256>,
+    /// Distance of the average price from the best price
+    pub impact_bps: Option<f64>,
+    pub orders_taken: usize,
+}
+
+/// Local model of one trading pair's order book following the contract's matching rules:
+///
+/// - placing a limit order triggers at most one match, between the highest bid and the
+///   lowest ask (earliest order wins ties), at the average of their prices
+/// - the trading fee comes out of the buyer's base amount
+/// - order ID 0 never matches, as in `_tryMatchOrders`
+/// - market orders walk resting orders in ID order, not price order, and fail unless
+///   they fill completely
+///
+/// Paper trading and backtests match through this model; `tests/matching.rs` replays
+/// random order flow against the contract to keep the two in step.
+#[derive(Debug, Clone)]
+pub struct OrderBook {
+    price_precision: U256,
+    orders: Vec<BookOrder>,
+    next_id: u64,
+}
+
+impl OrderBook {
+    pub fn new(price_precision: U256) -> Self {
+        Self {
+            price_precision,
+            orders: Vec::new(),
+            next_id: 0,
+        }
+    }
+
+    /// Seed a book from `getOrderBook` output; IDs start at 1 so every level can match
+    pub fn from_levels(
+        price_precision: U256,
+        buy_prices: &[U256],
+        buy_amounts: &[U256],
+        sell_prices: &[U256],
+        sell_amounts: &[U256],
+    ) -> Self {
+        let mut book = Self::new(price_precision);
+        book.next_id = 1;
+        for (price, amount) in buy_prices.iter().zip(buy_amounts) {
+            book.insert(None, *amount, *price, true);
+        }
+        for (price, amount) in sell_prices.iter().zip(sell_amounts) {
+            book.insert(None, *amount, *price, false);
+        }
+        book
+    }
+
+    pub fn price_precision(&self) -> U256 {
+        self.price_precision
+    }
+
+    pub fn orders(&self) -> &[BookOrder] {
+        &self.orders
+    }
+
+    pub fn order(&self, id: u64) -> Option<&BookOrder> {
+        self.orders.iter().find(|order| order.id == id)
+    }
+
+    pub fn best_bid(&self) -> Option<U256> {
+        self.orders.iter().filter(|o| o.is_buy).map(|o| o.price).max()
+    }
+
+    pub fn best_ask(&self) -> Option<U256> {
+        self.orders.iter().filter(|o| !o.is_buy).map(|o| o.price).min()
+    }
+
+    /// Closest price to `price` at which a new order rests instead of matching: `price`
+    /// itself unless it reaches the opposite best, otherwise one tick (one price unit)
+    /// short of it. `None` when no such price exists, e.g. a buy against an ask of 1.
+    pub fn post_only_price(&self, price: U256, is_buy: bool) -> Option<U256> {
+        if is_buy {
+            match self.best_ask() {
+                Some(ask) if ask <= price => ask.checked_sub(U256::one()).filter(|price| !price.is_zero()),
+                _ => Some(price),
+            }
+        } else {
+            match self.best_bid() {
+                Some(bid) if bid >= price => bid.checked_add(U256::one()),
+                _ => Some(price),
+            }
+        }
+    }
+
+    /// Quote value of `amount` base at `price`, rounded down like the contract
+    pub fn quote_amount(&self, amount: U256, price: U256) -> U256 {
+        (amount * price)
+            .checked_div(self.price_precision)
+            .unwrap_or_default()
+    }
+
+    /// Add a resting order without matching; returns its ID
+    pub fn insert(&mut self, owner: Option<Address>, amount: U256, price: U256, is_buy: bool) -> u64 {
+        let id = self.next_id;
+        self.next_id += 1;
+        self.orders.push(BookOrder {
+            id,
+            owner,
+            amount,
+            price,
+            is_buy,
+        });
+        id
+    }
+
+    /// Place a limit order and run the single match the contract attempts afterwards
+    pub fn place_limit(
+        &mut self,
+        owner: Option<Address>,
+        amount: U256,
+        price: U256,
+        is_buy: bool,
+    ) -> (u64, Option<Match>) {
+        let id = self.insert(owner, amount, price, is_buy);
+        (id, self.try_match())
+    }
+
+    fn try_match(&mut self) -> Option<Match> {
+        let mut best_buy: Option<&BookOrder> = None;
+        let mut best_sell: Option<&BookOrder> = None;
+        for order in &self.orders {
+            if order.is_buy {
+                if best_buy.is_none_or(|best| order.price > best.price) {
+                    best_buy = Some(order);
+                }
+            } else if best_sell.is_none_or(|best| order.price < best.price) {
+                best_sell = Some(order);
+            }
+        }
+
+        let (buy, sell) = (best_buy?, best_sell?);
+        if buy.price < sell.price || buy.id == 0 || sell.id == 0 {
+            return None;
+        }
+
+        let matched = buy.amount.min(sell.amount);
+        let price = (buy.price + sell.price) / 2;
+        let fee = trading_fee(matched, TRADING_FEE);
+        let base_amount = matched - fee;
+        let result = Match {
+            buy_order_id: buy.id,
+            sell_order_id: sell.id,
+            matched,
+            base_amount,
+            quote_amount: self.quote_amount(base_amount, price),
+            price,
+        };
+
+        self.reduce(result.buy_order_id, matched);
+        self.reduce(result.sell_order_id, matched);
+        Some(result)
+    }
+
+    /// Resting orders a market order of `amount` would take, in the contract's ID order,
+    /// and the base amount left unfilled
+    pub fn walk_market(&self, amount: U256, is_buy: bool) -> (Vec<Take>, U256) {
+        let mut remaining = amount;
+        let mut takes = Vec::new();
+        for order in self.orders.iter().filter(|order| order.is_buy != is_buy) {
+            if remaining.is_zero() {
+                break;
+            }
+            let taken = remaining.min(order.amount);
+            remaining -= taken;
+            takes.push(Take {
+                order_id: order.id,
+                owner: order.owner,
+                amount: taken,
+                price: order.price,
+                quote_amount: self.quote_amount(taken, order.price),
+            });
+        }
+        (takes, remaining)
+    }
+
+    /// Cost-to-fill of a market order of `amount` against the book as it stands
+    pub fn quote_market(&self, amount: U256, is_buy: bool) -> MarketQuote {
+        let (takes, remaining) = self.walk_market(amount, is_buy);
+        let filled = amount - remaining;
+        let quote_amount = takes.iter().fold(U256::zero(), |sum, take| sum + take.quote_amount);
+        let average_price = (quote_amount * self.price_precision).checked_div(filled);
+        let worst_price = if is_buy {
+            takes.iter().map(|take| take.price).max()
+        } else {
+            takes.iter().map(|take| take.price).min()
+        };
+        let best_price = if is_buy { self.best_ask() } else { self.best_bid() };
+        let impact_bps = match (average_price, best_price) {
+            (Some(average), Some(best)) if !best.is_zero() => {
+                let distance = if average > best { average - best } else { best - average };
+                Some(ratio(distance * FEE_DENOMINATOR, best))
+            }
+            _ => None,
+        };
+        MarketQuote {
+            amount,
+            is_buy,
+            filled,
+            filled_pct: ratio(filled * 100, amount),
+            quote_amount,
+            best_price,
+            average_price,
+            worst_price,
+            impact_bps,
+            orders_taken: takes.len(),
+        }
+    }
+
+    /// Execute a market order against resting orders in ID order; nothing changes on failure
+    pub fn place_market(&mut self, amount: U256, is_buy: bool) -> Result<Vec<Take>> {
+        let (takes, remaining) = self.walk_market(amount, is_buy);
+        if !remaining.is_zero() {
+            return Err(DexError::Input("Insufficient liquidity".to_string()));
+        }
+
+        for take in &takes {
+            self.reduce(take.order_id, take.amount);
+        }
+        Ok(takes)
+    }
+
+    /// Remove a resting order, returning what was left of it
+    pub fn cancel(&mut self, id: u64) -> Option<BookOrder> {
+        let index = self.orders.iter().position(|order| order.id == id)?;
+        Some(self.orders.remove(index))
+    }
+
+    fn reduce(&mut self, id: u64, amount: U256) {
+        if let Some(index) = self.orders.iter().position(|order| order.id == id) {
+            self.orders[index].amount -= amount;
+            if self.orders[index].amount.is_zero() {
+                self.orders.remove(index);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn units(n: u64) -> U256 {
+        U256::from(n) * U256::exp10(18)
+    }
+
+    /// A book at 18-decimal price precision with order 0 out of the way, since it never
+    /// matches
+    fn book() -> OrderBook {
+        let mut book = OrderBook::new(U256::exp10(18));
+        book.insert(None, U256::one(), U256::one(), true);
+        book.cancel(0);
+        book
+    }
+
+    #[test]
+    fn matched_from_net_inverts_the_fee() {
+        for matched in [1u64, 333, 10_000, 123_456_789] {
+            let net = U256::from(matched) - trading_fee(U256::from(matched), TRADING_FEE);
+            // Amounts whose fees round to the same net can't be told apart; any of them will do
+            let recovered = matched_from_net(net, TRADING_FEE);
+            assert_eq!(recovered - trading_fee(recovered, TRADING_FEE), net);
+        }
+        assert_eq!(matched_from_net(U256::from(7), FEE_DENOMINATOR), U256::from(7));
+    }
+
+    #[test]
+    fn crossing_limit_orders_match_at_the_average_price() {
+        let mut book = book();
+        book.place_limit(None, units(10), units(3), false);
+        let (_, matched) = book.place_limit(None, units(4), units(5), true);
+
+        let matched = matched.expect("the orders cross");
+        let fee = trading_fee(units(4), TRADING_FEE);
+        assert_eq!(matched.matched, units(4));
+        assert_eq!(matched.price, units(4));
+        assert_eq!(matched.base_amount, units(4) - fee);
+        assert_eq!(matched.quote_amount, (units(4) - fee) * 4);
+        assert_eq!(book.orders().len(), 1);
+        assert_eq!(book.orders()[0].amount, units(6));
+    }
+
+    #[test]
+    fn orders_that_do_not_cross_rest() {
+        let mut book = book();
+        book.place_limit(None, units(1), units(3), false);
+        let (_, matched) = book.place_limit(None, units(1), units(2), true);
+        assert_eq!(matched, None);
+        assert_eq!(book.best_bid(), Some(units(2)));
+        assert_eq!(book.best_ask(), Some(units(3)));
+    }
+
+    #[test]
+    fn order_zero_never_matches() {
+        let mut book = OrderBook::new(U256::exp10(18));
+        book.place_limit(None, units(1), units(1), false);
+        let (_, matched) = book.place_limit(None, units(1), units(1), true);
+        assert_eq!(matched, None);
+        assert_eq!(book.orders().len(), 2);
+    }
+
+    #[test]
+    fn market_orders_walk_in_id_order_and_fail_unless_filled() {
+        let mut book = book();
+        book.insert(None, units(2), units(5), false);
+        book.insert(None, units(2), units(3), false);
+
+        let quote = book.quote_market(units(3), true);
+        assert_eq!(quote.filled, units(3));
+        assert_eq!(quote.orders_taken, 2);
+        assert_eq!(quote.best_price, Some(units(3)));
+        assert_eq!(quote.worst_price, Some(units(5)));
+        assert_eq!(quote.quote_amount, units(2) * 5 + units(1) * 3);
+
+        assert!(book.place_market(units(5), true).is_err());
+        assert_eq!(book.orders().len(), 2, "a failed market order leaves the book alone");
+
+        let takes = book.place_market(units(3), true).unwrap();
+        assert_eq!(takes.iter().map(|take| take.order_id).collect::<Vec<_>>(), vec![1, 2]);
+        assert_eq!(book.orders().len(), 1);
+        assert_eq!(book.orders()[0].amount, units(1));
+    }
+
+    #[test]
+    fn post_only_price_keeps_prices_that_rest() {
+        let mut book = book();
+        book.insert(None, units(1), units(2), true);
+        book.insert(None, units(1), units(4), false);
+        assert_eq!(book.post_only_price(units(3), true), Some(units(3)));
+        assert_eq!(book.post_only_price(units(3), false), Some(units(3)));
+    }
+
+    #[test]
+    fn post_only_price_moves_a_crossing_order_one_tick_inside() {
+        let mut book = book();
+        book.insert(None, units(1), units(2), true);
+        book.insert(None, units(1), units(4), false);
+        assert_eq!(book.post_only_price(units(5), true), Some(units(4) - 1));
+        assert_eq!(book.post_only_price(units(1), false), Some(units(2) + 1));
+    }
+
+    #[test]
+    fn post_only_price_has_no_answer_at_the_edges() {
+        let mut book = book();
+        book.insert(None, units(1), U256::one(), false);
+        assert_eq!(book.post_only_price(units(1), true), None);
+
+        let mut book = self::book();
+        book.insert(None, units(1), U256::zero(), false);
+        assert_eq!(book.post_only_price(U256::zero(), true), None);
+
+        let mut book = self::book();
+        book.insert(None, units(1), U256::MAX, true);
+        assert_eq!(book.post_only_price(units(1), false), None);
+    }
+}
@@ -0,0 +1,108 @@
+use crate::bindings::{OrderBookDEX, OrderBookDEXCalls};
+use crate::error::{DexError, Result};
+use crate::health;
+use crate::strategy::{PendingAction, PendingActivity};
+use ethers::{
+    abi::AbiDecode,
+    providers::{FilterKind, Middleware},
+    types::{Address, Transaction, H256, U256},
+};
+use tracing::warn;
+
+/// Name the pending-transaction filter reports its health under
+const HEALTH_COMPONENT: &str = "mempool filter";
+
+/// Polls the node's pending-transaction filter and decodes order placements and
+/// cancellations sent to the DEX. Works over plain HTTP, but only against nodes that
+/// expose `eth_newPendingTransactionFilter`.
+pub struct MempoolWatcher<M> {
+    contract: OrderBookDEX<M>,
+    /// Only report this `(base, quote)` pair; every pair when unset
+    pair: Option<(Address, Address)>,
+    /// Transactions from this address (the bot's own) are skipped
+    ignore: Option<Address>,
+    filter_id: U256,
+}
+
+impl<M: Middleware + 'static> MempoolWatcher<M> {
+    pub async fn new(contract: OrderBookDEX<M>, pair: Option<(Address, Address)>, ignore: Option<Address>) -> Result<Self> {
+        let filter_id = new_filter(&contract).await?;
+        Ok(Self { contract, pair, ignore, filter_id })
+    }
+
+    /// DEX transactions that entered the mempool since the last poll
+    pub async fn poll(&mut self) -> Result<Vec<PendingActivity>> {
+        let hashes: Vec<H256> = match self.contract.client().get_filter_changes(self.filter_id).await {
+            Ok(hashes) => hashes,
+            Err(e) => {
+                // Nodes drop filters that go unpolled for a while; start a fresh one
+                warn!("Pending transaction filter lost ({}), recreating it", e);
+                self.filter_id = new_filter(&self.contract).await.inspect_err(|e| health::failed(HEALTH_COMPONENT, e))?;
+                health::ok(HEALTH_COMPONENT);
+                return Ok(Vec::new());
+            }
+        };
+        health::ok(HEALTH_COMPONENT);
+
+        let mut activity = Vec::new();
+        for hash in hashes {
+            // Mined or dropped between the filter and this lookup
+            let Some(tx) = self.contract.client().get_transaction(hash).await.map_err(|e| DexError::middleware(&e))? else {
+                continue;
+            };
+            if tx.block_number.is_some() || tx.to != Some(self.contract.address()) || Some(tx.from) == self.ignore {
+                continue;
+            }
+            if let Some(decoded) = self.decode(&tx).await? {
+                activity.push(decoded);
+            }
+        }
+        Ok(activity)
+    }
+
+    async fn decode(&self, tx: &Transaction) -> Result<Option<PendingActivity>> {
+        let Ok(call) = OrderBookDEXCalls::decode(&tx.input) else {
+            return Ok(None);
+        };
+        let (base_token, quote_token, action) = match call {
+            OrderBookDEXCalls::PlaceLimitOrder(c) => (
+                c.base_token,
+                c.quote_token,
+                PendingAction::LimitOrder { price: c.price, amount: c.amount, is_buy: c.is_buy },
+            ),
+            OrderBookDEXCalls::PlaceLimitOrderWithPermit(c) => (
+                c.base_token,
+                c.quote_token,
+                PendingAction::LimitOrder { price: c.price, amount: c.amount, is_buy: c.is_buy },
+            ),
+            OrderBookDEXCalls::PlaceMarketOrder(c) => (
+                c.base_token,
+                c.quote_token,
+                PendingAction::MarketOrder { amount: c.amount, is_buy: c.is_buy },
+            ),
+            OrderBookDEXCalls::CancelOrder(c) => {
+                // The calldata only carries the ID; the pair and side come from the order itself
+                let (_, _, base_token, quote_token, amount, price, is_buy, is_active, _) =
+                    self.contract.orders(c.order_id).call().await?;
+                if !is_active {
+                    return Ok(None);
+                }
+                (base_token, quote_token, PendingAction::Cancel { order_id: c.order_id.low_u64(), price, amount, is_buy })
+            }
+            _ => return Ok(None),
+        };
+
+        if self.pair.is_some_and(|pair| pair != (base_token, quote_token)) {
+            return Ok(None);
+        }
+        Ok(Some(PendingActivity { tx_hash: tx.hash, from: tx.from, base_token, quote_token, action }))
+    }
+}
+
+async fn new_filter<M: Middleware + 'static>(contract: &OrderBookDEX<M>) -> Result<U256> {
+    contract
+        .client()
+        .new_filter(FilterKind::PendingTransactions)
+        .await
+        .map_err(|e| DexError::Rpc(format!("Node does not support pending transaction filters: {}", e)))
+}
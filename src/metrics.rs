@@ -0,0 +1,147 @@
+use crate::error::Result;
+use crate::health;
+use anyhow::Context;
+use prometheus::{
+    Encoder, GaugeVec, HistogramOpts, HistogramVec, IntCounter, IntCounterVec, IntGauge, Opts, Registry, TextEncoder,
+};
+use std::net::SocketAddr;
+use std::sync::OnceLock;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpListener;
+use tracing::{info, warn};
+
+/// Counters and gauges exported on `/metrics` by the long-running commands
+pub struct Metrics {
+    registry: Registry,
+    pub orders_placed: IntCounter,
+    pub orders_cancelled: IntCounter,
+    pub fills: IntCounter,
+    /// RPC round trip per JSON-RPC method, in seconds
+    pub rpc_latency: HistogramVec,
+    /// Requests moved to another endpoint after a transport failure
+    pub rpc_failovers: IntCounter,
+    /// Reads answered from the RPC cache, and reads it had to forward, per method
+    pub rpc_cache_hits: IntCounterVec,
+    pub rpc_cache_misses: IntCounterVec,
+    pub rpc_cache_entries: IntGauge,
+    /// Blocks between the chain head and the last indexed block
+    pub indexer_lag: IntGauge,
+    /// Net base inventory the bot accumulated from fills, per strategy
+    pub bot_inventory: GaugeVec,
+    /// Book mid and the price the strategy centred its quotes on, per strategy
+    pub bot_mid: GaugeVec,
+    pub bot_quote_center: GaugeVec,
+    /// Distance between the innermost bid and ask, in basis points of mid
+    pub bot_quote_spread_bps: GaugeVec,
+    /// The strategy's running estimate of mid volatility per iteration, in basis points
+    pub bot_volatility_bps: GaugeVec,
+}
+
+impl Metrics {
+    fn new() -> Self {
+        let registry = Registry::new_custom(Some("monad_dex".to_string()), None).expect("valid metrics prefix");
+        let metrics = Self {
+            orders_placed: IntCounter::new("orders_placed_total", "Orders placed").unwrap(),
+            orders_cancelled: IntCounter::new("orders_cancelled_total", "Orders cancelled").unwrap(),
+            fills: IntCounter::new("fills_total", "Fills of the bot's orders").unwrap(),
+            rpc_latency: HistogramVec::new(
+                HistogramOpts::new("rpc_latency_seconds", "JSON-RPC request latency"),
+                &["method"],
+            )
+            .unwrap(),
+            rpc_failovers: IntCounter::new("rpc_failovers_total", "Requests failed over to another RPC endpoint").unwrap(),
+            rpc_cache_hits: IntCounterVec::new(Opts::new("rpc_cache_hits_total", "Reads answered from the RPC cache"), &["method"]).unwrap(),
+            rpc_cache_misses: IntCounterVec::new(Opts::new("rpc_cache_misses_total", "Cacheable reads sent to the RPC endpoint"), &["method"]).unwrap(),
+            rpc_cache_entries: IntGauge::new("rpc_cache_entries", "Answers held in the RPC cache").unwrap(),
+            indexer_lag: IntGauge::new("indexer_lag_blocks", "Blocks behind the chain head").unwrap(),
+            bot_inventory: GaugeVec::new(Opts::new("bot_inventory", "Net base inventory from bot fills"), &["strategy"]).unwrap(),
+            bot_mid: GaugeVec::new(Opts::new("bot_mid", "Book mid seen by the bot"), &["strategy"]).unwrap(),
+            bot_quote_center: GaugeVec::new(Opts::new("bot_quote_center", "Price the bot centred its quotes on"), &["strategy"]).unwrap(),
+            bot_quote_spread_bps: GaugeVec::new(Opts::new("bot_quote_spread_bps", "Bot quote spread in basis points of mid"), &["strategy"]).unwrap(),
+            bot_volatility_bps: GaugeVec::new(Opts::new("bot_volatility_bps", "Estimated mid volatility per iteration"), &["strategy"]).unwrap(),
+            registry,
+        };
+
+        metrics.registry.register(Box::new(metrics.orders_placed.clone())).unwrap();
+        metrics.registry.register(Box::new(metrics.orders_cancelled.clone())).unwrap();
+        metrics.registry.register(Box::new(metrics.fills.clone())).unwrap();
+        metrics.registry.register(Box::new(metrics.rpc_latency.clone())).unwrap();
+        metrics.registry.register(Box::new(metrics.rpc_failovers.clone())).unwrap();
+        metrics.registry.register(Box::new(metrics.rpc_cache_hits.clone())).unwrap();
+        metrics.registry.register(Box::new(metrics.rpc_cache_misses.clone())).unwrap();
+        metrics.registry.register(Box::new(metrics.rpc_cache_entries.clone())).unwrap();
+        metrics.registry.register(Box::new(metrics.indexer_lag.clone())).unwrap();
+        metrics.registry.register(Box::new(metrics.bot_inventory.clone())).unwrap();
+        metrics.registry.register(Box::new(metrics.bot_mid.clone())).unwrap();
+        metrics.registry.register(Box::new(metrics.bot_quote_center.clone())).unwrap();
+        metrics.registry.register(Box::new(metrics.bot_quote_spread_bps.clone())).unwrap();
+        metrics.registry.register(Box::new(metrics.bot_volatility_bps.clone())).unwrap();
+        metrics
+    }
+
+    /// Everything in the Prometheus text exposition format
+    pub fn render(&self) -> Result<String> {
+        let mut buffer = Vec::new();
+        TextEncoder::new().encode(&self.registry.gather(), &mut buffer).context("Failed to encode metrics")?;
+        Ok(String::from_utf8(buffer).context("Metrics are not UTF-8")?)
+    }
+}
+
+static METRICS: OnceLock<Metrics> = OnceLock::new();
+
+/// The process-wide metrics; recording is cheap whether or not they are served
+pub fn metrics() -> &'static Metrics {
+    METRICS.get_or_init(Metrics::new)
+}
+
+/// Serve `/metrics` on `addr` until the process exits, along with `/healthz` and `/readyz`
+/// for orchestrators: 200 when healthy or ready, 503 otherwise, with a JSON report
+pub async fn serve(addr: SocketAddr) -> Result<()> {
+    let listener = TcpListener::bind(addr).await?;
+    info!("Serving metrics on http://{}/metrics, health on /healthz and /readyz", addr);
+
+    loop {
+        let (mut socket, _) = listener.accept().await?;
+        tokio::spawn(async move {
+            let mut request = [0u8; 1024];
+            let read = match socket.read(&mut request).await {
+                Ok(read) => read,
+                Err(e) => {
+                    warn!("Failed to read metrics request: {}", e);
+                    return;
+                }
+            };
+
+            let request = String::from_utf8_lossy(&request[..read]);
+            let path = request.split_whitespace().nth(1).unwrap_or_default();
+            let response = match path {
+                "/healthz" | "/readyz" => {
+                    let report = health::report();
+                    let passing = if path == "/healthz" { report.healthy } else { report.ready };
+                    let body = serde_json::to_string(&report).unwrap_or_default();
+                    format!(
+                        "HTTP/1.1 {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                        if passing { "200 OK" } else { "503 Service Unavailable" },
+                        body.len(),
+                        body
+                    )
+                }
+                "/metrics" => match metrics().render() {
+                    Ok(body) => format!(
+                        "HTTP/1.1 200 OK\r\nContent-Type: text/plain; version=0.0.4\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                        body.len(),
+                        body
+                    ),
+                    Err(e) => {
+                        warn!("Failed to render metrics: {}", e);
+                        "HTTP/1.1 500 Internal Server Error\r\nContent-Length: 0\r\nConnection: close\r\n\r\n".to_string()
+                    }
+                },
+                _ => "HTTP/1.1 404 Not Found\r\nContent-Length: 0\r\nConnection: close\r\n\r\n".to_string(),
+            };
+            if let Err(e) = socket.write_all(response.as_bytes()).await {
+                warn!("Failed to write metrics response: {}", e);
+            }
+        });
+    }
+}
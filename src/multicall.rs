@@ -0,0 +1,137 @@
+use crate::error::{DexError, Result};
+use crate::fanout;
+use crate::rpc;
+use anyhow::anyhow;
+use ethers::{
+    abi::{parse_abi, Detokenize, Function, Token},
+    contract::{AbiError, Contract, ContractCall},
+    providers::Middleware,
+    types::{transaction::eip2718::TypedTransaction, Address, Bytes, TransactionRequest},
+};
+use std::sync::Arc;
+use tracing::debug;
+
+/// Canonical Multicall3 deployment address, identical on every chain that has it
+pub const MULTICALL3_ADDRESS: &str = "0xcA11bde05977b3631167028862bE2a173976CA11";
+
+struct Call {
+    target: Address,
+    data: Bytes,
+    function: Function,
+}
+
+/// Aggregates read-only contract calls into a single `eth_call` through Multicall3.
+///
+/// Calls are allowed to fail individually; each result slot carries its own error. On
+/// chains without Multicall3 the calls are made one by one, `--max-concurrency` at a time.
+pub struct Multicall<M> {
+    client: Arc<M>,
+    contract: Contract<M>,
+    calls: Vec<Call>,
+}
+
+impl<M: Middleware + 'static> Multicall<M> {
+    pub fn new(client: Arc<M>) -> Result<Self> {
+        Self::at(client, MULTICALL3_ADDRESS.parse().expect("valid Multicall3 address"))
+    }
+
+    pub fn at(client: Arc<M>, address: Address) -> Result<Self> {
+        let abi = parse_abi(&[
+            "function aggregate3((address,bool,bytes)[] calls) payable returns ((bool,bytes)[] returnData)",
+        ])
+        .expect("valid aggregate3 signature");
+        Ok(Self {
+            contract: Contract::new(address, abi, client.clone()),
+            client,
+            calls: Vec::new(),
+        })
+    }
+
+    /// Queue a contract call; only its target, calldata and ABI are used
+    pub fn add<D: Detokenize>(&mut self, call: ContractCall<M, D>) -> Result<&mut Self> {
+        let target = *call
+            .tx
+            .to_addr()
+            .ok_or_else(|| DexError::Input(format!("Call to {} has no target address", call.function.name)))?;
+        let data = call.tx.data().cloned().unwrap_or_default();
+
+        self.calls.push(Call {
+            target,
+            data,
+            function: call.function,
+        });
+        Ok(self)
+    }
+
+    pub fn len(&self) -> usize {
+        self.calls.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.calls.is_empty()
+    }
+
+    /// Execute every queued call and decode each return value
+    pub async fn call(&self) -> Result<Vec<Result<Vec<Token>>>> {
+        if self.calls.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let requests: Vec<(Address, bool, Bytes)> = self
+            .calls
+            .iter()
+            .map(|call| (call.target, true, call.data.clone()))
+            .collect();
+
+        let aggregated = self.contract.method::<_, Vec<(bool, Bytes)>>("aggregate3", (requests,))?.call().await;
+        let responses: Vec<Option<Bytes>> = match aggregated {
+            Ok(responses) => responses.into_iter().map(|(success, data)| success.then_some(data)).collect(),
+            Err(e) => {
+                let deployed = self
+                    .client
+                    .get_code(self.contract.address(), None)
+                    .await
+                    .map_or(true, |code| !code.is_empty());
+                if deployed {
+                    return Err(e.into());
+                }
+                debug!("No Multicall3 on this chain, making {} calls one by one", self.calls.len());
+                self.call_each().await?
+            }
+        };
+
+        Ok(self
+            .calls
+            .iter()
+            .zip(responses)
+            .map(|(call, data)| {
+                let data = data.ok_or_else(|| anyhow!("{} reverted", call.function.name))?;
+                Ok(call.function.decode_output(&data)?)
+            })
+            .collect())
+    }
+
+    /// Make every queued call as its own `eth_call`, concurrently; `None` where one failed
+    async fn call_each(&self) -> Result<Vec<Option<Bytes>>> {
+        let requests: Vec<(Address, Bytes)> = self.calls.iter().map(|call| (call.target, call.data.clone())).collect();
+        let client = self.client.clone();
+        fanout::map(requests, rpc::max_concurrency(), move |(target, data)| {
+            let client = client.clone();
+            async move {
+                let tx: TypedTransaction = TransactionRequest::new().to(target).data(data).into();
+                client.call(&tx, None).await.ok()
+            }
+        })
+        .await
+    }
+
+    /// Execute every queued call, decoding each result as `D`
+    pub async fn call_as<D: Detokenize>(&self) -> Result<Vec<Result<D>>> {
+        Ok(self
+            .call()
+            .await?
+            .into_iter()
+            .map(|tokens| Ok(D::from_tokens(tokens?).map_err(AbiError::from)?))
+            .collect())
+    }
+}
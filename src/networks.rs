@@ -0,0 +1,225 @@
+use crate::error::{DexError, Result};
+use crate::explorer;
+use anyhow::Context;
+use ethers::{providers::Authorization, types::Address};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::env;
+use std::fs;
+use std::path::Path;
+
+const NETWORKS_PATH: &str = "config/networks.json";
+
+/// Network used when `--network` isn't given
+pub const DEFAULT_NETWORK: &str = "monad-testnet";
+
+/// Public Monad testnet endpoint, the fallback after the default RPC URL
+const MONAD_TESTNET_PUBLIC_RPC: &str = "https://testnet-rpc.monad.xyz";
+
+/// Everything the binaries need to know about a chain
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Network {
+    pub chain_id: u64,
+    /// Endpoints in failover order
+    #[serde(default)]
+    pub rpc_urls: Vec<String>,
+    /// Block explorer web UI, for links to transactions and addresses
+    #[serde(default)]
+    pub explorer_url: Option<String>,
+    /// Etherscan-compatible explorer API, for verified ABIs
+    #[serde(default)]
+    pub explorer_api: Option<String>,
+    /// Contract addresses by name, e.g. `OrderBookDEX`, used when no deployment of that
+    /// name is recorded
+    #[serde(default)]
+    pub contracts: BTreeMap<String, Address>,
+    /// Credentials sent to `rpc_urls`
+    #[serde(default, skip_serializing_if = "RpcAuth::is_empty")]
+    pub auth: RpcAuth,
+    /// Refuse to sign or send transactions on this network, as `--read-only` does, so the
+    /// config can be shared with people who should only read
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub read_only: bool,
+}
+
+/// Headers and credentials an RPC provider requires. Values may name environment
+/// variables as `${VAR}`, so keys needn't be saved in the config file.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct RpcAuth {
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub headers: BTreeMap<String, String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub bearer_token: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub basic_auth: Option<BasicAuth>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BasicAuth {
+    pub username: String,
+    pub password: String,
+}
+
+impl RpcAuth {
+    pub fn is_empty(&self) -> bool {
+        self.headers.is_empty() && self.bearer_token.is_none() && self.basic_auth.is_none()
+    }
+
+    /// Headers to send, with environment variables substituted
+    pub fn resolve(&self) -> Result<Vec<(String, String)>> {
+        let mut headers = Vec::new();
+        for (name, value) in &self.headers {
+            headers.push((name.clone(), expand_env(value)?));
+        }
+        if let Some(token) = &self.bearer_token {
+            headers.push(("Authorization".to_string(), Authorization::bearer(expand_env(token)?).to_string()));
+        }
+        if let Some(basic) = &self.basic_auth {
+            let authorization = Authorization::basic(expand_env(&basic.username)?, expand_env(&basic.password)?);
+            headers.push(("Authorization".to_string(), authorization.to_string()));
+        }
+        Ok(headers)
+    }
+}
+
+/// Replace every `${VAR}` in `value` with the environment variable's value
+fn expand_env(value: &str) -> Result<String> {
+    let mut expanded = String::new();
+    let mut rest = value;
+    while let Some(start) = rest.find("${") {
+        let end = rest[start..]
+            .find('}')
+            .ok_or_else(|| DexError::Config(format!("Unclosed ${{ in '{}'", value)))?;
+        let name = &rest[start + 2..start + end];
+        let var = env::var(name).map_err(|_| DexError::Config(format!("Environment variable {} is not set", name)))?;
+        expanded.push_str(&rest[..start]);
+        expanded.push_str(&var);
+        rest = &rest[start + end + 1..];
+    }
+    expanded.push_str(rest);
+    Ok(expanded)
+}
+
+impl Network {
+    /// Credentials to send to `url`: the network's, if `url` is one of its endpoints, so
+    /// they never reach an endpoint given with `--rpc-url` for another provider
+    pub fn auth_for(&self, url: &str) -> Option<&RpcAuth> {
+        let url = url.trim().trim_end_matches('/');
+        let own = self.rpc_urls.iter().any(|own| own.trim().trim_end_matches('/') == url);
+        (own && !self.auth.is_empty()).then_some(&self.auth)
+    }
+
+    /// Address of the preset contract named `name` (case-insensitive)
+    pub fn contract(&self, name: &str) -> Option<Address> {
+        self.contracts
+            .iter()
+            .find(|(contract, _)| contract.eq_ignore_ascii_case(name))
+            .map(|(_, address)| *address)
+    }
+
+    /// Explorer page of a transaction, address or block, e.g. `address/0x...`
+    pub fn explorer_link(&self, path: &str) -> Option<String> {
+        self.explorer_url
+            .as_ref()
+            .map(|url| format!("{}/{}", url.trim_end_matches('/'), path))
+    }
+}
+
+/// Networks built into the binaries
+pub fn builtin() -> BTreeMap<String, Network> {
+    let mut networks = BTreeMap::new();
+    networks.insert(
+        "monad-testnet".to_string(),
+        Network {
+            chain_id: crate::rpc::MONAD_TESTNET_CHAIN_ID,
+            rpc_urls: vec![MONAD_TESTNET_PUBLIC_RPC.to_string()],
+            explorer_url: Some("https://testnet.monadexplorer.com".to_string()),
+            explorer_api: Some(explorer::DEFAULT_EXPLORER_API.to_string()),
+            contracts: BTreeMap::from([
+                ("OrderBookDEX".to_string(), "0x6045fe7667E22CE9ff8106429128DDdC90F6F9Ae".parse().expect("valid address")),
+                ("MonadToken".to_string(), "0x14F49BedD983423198d5402334dbccD9c45AC767".parse().expect("valid address")),
+            ]),
+            auth: RpcAuth::default(),
+            read_only: false,
+        },
+    );
+    // Devnet endpoints are handed out per team, so they come from config/networks.json
+    networks.insert(
+        "monad-devnet".to_string(),
+        Network {
+            chain_id: 20143,
+            rpc_urls: Vec::new(),
+            explorer_url: None,
+            explorer_api: None,
+            contracts: BTreeMap::new(),
+            auth: RpcAuth::default(),
+            read_only: false,
+        },
+    );
+    networks.insert(
+        "local".to_string(),
+        Network {
+            chain_id: 31337,
+            rpc_urls: vec!["http://127.0.0.1:8545".to_string()],
+            explorer_url: None,
+            explorer_api: None,
+            contracts: BTreeMap::new(),
+            auth: RpcAuth::default(),
+            read_only: false,
+        },
+    );
+    networks
+}
+
+/// Custom networks, and overrides of built-in ones, saved in `config/networks.json`
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct NetworkRegistry {
+    pub networks: BTreeMap<String, Network>,
+}
+
+impl NetworkRegistry {
+    pub fn load() -> Result<Self> {
+        if !Path::new(NETWORKS_PATH).exists() {
+            return Ok(Self::default());
+        }
+        let content = fs::read_to_string(NETWORKS_PATH)
+            .with_context(|| format!("Failed to read {}", NETWORKS_PATH))?;
+        Ok(serde_json::from_str(&content)?)
+    }
+
+    pub fn save(&self) -> Result<()> {
+        fs::create_dir_all("config")?;
+        fs::write(NETWORKS_PATH, serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+
+    /// Built-in networks with the saved ones on top
+    pub fn all(&self) -> BTreeMap<String, Network> {
+        let mut networks = builtin();
+        networks.extend(self.networks.clone());
+        networks
+    }
+
+    pub fn get(&self, name: &str) -> Result<Network> {
+        let name = name.to_lowercase();
+        let mut networks = self.all();
+        networks.remove(&name).ok_or_else(|| {
+            let known: Vec<String> = networks.into_keys().collect();
+            DexError::Config(format!("Unknown network '{}' (known: {}); add it with `monad-dex network add`", name, known.join(", ")))
+        })
+    }
+
+    pub fn add(&mut self, name: &str, network: Network) {
+        self.networks.insert(name.to_lowercase(), network);
+    }
+
+    pub fn remove(&mut self, name: &str) -> Result<Network> {
+        self.networks.remove(&name.to_lowercase()).ok_or_else(|| {
+            if builtin().contains_key(&name.to_lowercase()) {
+                DexError::Config(format!("'{}' is built in and has no saved override to remove", name))
+            } else {
+                DexError::Config(format!("No saved network named '{}'", name))
+            }
+        })
+    }
+}
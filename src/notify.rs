@@ -0,0 +1,232 @@
+use crate::error::Result;
+use anyhow::anyhow;
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tokio::time::sleep;
+use tracing::warn;
+
+/// A notification raised by an alert rule or a long-running command
+#[derive(Debug, Clone, Serialize)]
+pub struct Alert {
+    /// Name of the rule or component that raised it
+    pub source: String,
+    pub message: String,
+    pub timestamp: u64,
+}
+
+impl Alert {
+    pub fn new(source: impl Into<String>, message: impl Into<String>) -> Self {
+        Self {
+            source: source.into(),
+            message: message.into(),
+            timestamp: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|d| d.as_secs())
+                .unwrap_or_default(),
+        }
+    }
+}
+
+/// A delivery channel for alerts
+#[async_trait]
+pub trait Notifier: Send + Sync {
+    fn name(&self) -> &str;
+
+    async fn send(&self, alert: &Alert) -> Result<()>;
+}
+
+/// Telegram bot credentials
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TelegramConfig {
+    pub bot_token: String,
+    pub chat_id: String,
+}
+
+/// Notification channel settings in a config file
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct NotifyConfig {
+    /// Generic webhook receiving each alert as a JSON POST body
+    #[serde(default)]
+    pub webhook_url: Option<String>,
+    /// Discord channel webhook
+    #[serde(default)]
+    pub discord_webhook_url: Option<String>,
+    #[serde(default)]
+    pub telegram: Option<TelegramConfig>,
+    /// Delivery attempts after the first failure
+    #[serde(default = "default_max_retries")]
+    pub max_retries: u32,
+}
+
+fn default_max_retries() -> u32 {
+    3
+}
+
+impl NotifyConfig {
+    pub fn notifiers(&self) -> Vec<Box<dyn Notifier>> {
+        let mut notifiers: Vec<Box<dyn Notifier>> = Vec::new();
+        if let Some(url) = &self.webhook_url {
+            notifiers.push(Box::new(WebhookNotifier::new(url.clone())));
+        }
+        if let Some(url) = &self.discord_webhook_url {
+            notifiers.push(Box::new(DiscordNotifier::new(url.clone())));
+        }
+        if let Some(telegram) = &self.telegram {
+            notifiers.push(Box::new(TelegramNotifier::new(telegram.clone())));
+        }
+        notifiers
+    }
+
+    pub fn build(&self) -> Notifications {
+        Notifications {
+            notifiers: self.notifiers(),
+            max_retries: self.max_retries,
+        }
+    }
+}
+
+/// The configured channels, ready to deliver
+#[derive(Default)]
+pub struct Notifications {
+    notifiers: Vec<Box<dyn Notifier>>,
+    max_retries: u32,
+}
+
+impl Notifications {
+    pub fn is_empty(&self) -> bool {
+        self.notifiers.is_empty()
+    }
+
+    pub async fn send(&self, alert: &Alert) {
+        deliver(&self.notifiers, alert, self.max_retries).await;
+    }
+}
+
+/// POSTs alerts as JSON to a URL
+pub struct WebhookNotifier {
+    url: String,
+    client: reqwest::Client,
+}
+
+impl WebhookNotifier {
+    pub fn new(url: String) -> Self {
+        Self {
+            url,
+            client: reqwest::Client::new(),
+        }
+    }
+}
+
+#[async_trait]
+impl Notifier for WebhookNotifier {
+    fn name(&self) -> &str {
+        "webhook"
+    }
+
+    async fn send(&self, alert: &Alert) -> Result<()> {
+        let response = self.client.post(&self.url).json(alert).send().await?;
+        if !response.status().is_success() {
+            return Err(anyhow!("Webhook returned {}", response.status()).into());
+        }
+        Ok(())
+    }
+}
+
+/// Posts alerts to a Discord channel webhook
+pub struct DiscordNotifier {
+    url: String,
+    client: reqwest::Client,
+}
+
+impl DiscordNotifier {
+    pub fn new(url: String) -> Self {
+        Self {
+            url,
+            client: reqwest::Client::new(),
+        }
+    }
+}
+
+#[async_trait]
+impl Notifier for DiscordNotifier {
+    fn name(&self) -> &str {
+        "discord"
+    }
+
+    async fn send(&self, alert: &Alert) -> Result<()> {
+        let body = serde_json::json!({ "content": format!("**{}**: {}", alert.source, alert.message) });
+        let response = self.client.post(&self.url).json(&body).send().await?;
+        if !response.status().is_success() {
+            return Err(anyhow!("Discord returned {}", response.status()).into());
+        }
+        Ok(())
+    }
+}
+
+/// Sends alerts as messages from a Telegram bot
+pub struct TelegramNotifier {
+    config: TelegramConfig,
+    client: reqwest::Client,
+}
+
+impl TelegramNotifier {
+    pub fn new(config: TelegramConfig) -> Self {
+        Self {
+            config,
+            client: reqwest::Client::new(),
+        }
+    }
+}
+
+#[async_trait]
+impl Notifier for TelegramNotifier {
+    fn name(&self) -> &str {
+        "telegram"
+    }
+
+    async fn send(&self, alert: &Alert) -> Result<()> {
+        let url = format!("https://api.telegram.org/bot{}/sendMessage", self.config.bot_token);
+        let body = serde_json::json!({
+            "chat_id": self.config.chat_id,
+            "text": format!("{}: {}", alert.source, alert.message),
+        });
+        // The URL contains the bot token, so keep it out of errors
+        let response = self
+            .client
+            .post(&url)
+            .json(&body)
+            .send()
+            .await
+            .map_err(|e| anyhow!("Telegram request failed: {}", e.without_url()))?;
+        if !response.status().is_success() {
+            let status = response.status();
+            let description = response.text().await.unwrap_or_default();
+            return Err(anyhow!("Telegram returned {}: {}", status, description).into());
+        }
+        Ok(())
+    }
+}
+
+/// Deliver an alert on every channel, retrying each with exponential backoff.
+/// Failures are logged rather than returned so one broken channel doesn't stop the rest.
+pub async fn deliver(notifiers: &[Box<dyn Notifier>], alert: &Alert, max_retries: u32) {
+    for notifier in notifiers {
+        let mut attempt = 0;
+        loop {
+            match notifier.send(alert).await {
+                Ok(()) => break,
+                Err(e) if attempt < max_retries => {
+                    let delay = Duration::from_secs(1 << attempt.min(5));
+                    attempt += 1;
+                    warn!("{} delivery failed ({}), retry {}/{} in {:?}", notifier.name(), e, attempt, max_retries, delay);
+                    sleep(delay).await;
+                }
+                Err(e) => {
+                    warn!("{} delivery failed after {} retries: {}", notifier.name(), max_retries, e);
+                    break;
+                }
+            }
+        }
+    }
+}
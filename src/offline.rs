@@ -0,0 +1,243 @@
+use crate::error::{DexError, Result};
+use crate::journal;
+use crate::rpc;
+use anyhow::{anyhow, Context};
+use ethers::{
+    providers::Middleware,
+    signers::{LocalWallet, Signer},
+    types::{transaction::eip2718::TypedTransaction, Bytes, TransactionReceipt, H256, U256},
+    utils::{get_contract_address, keccak256},
+};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::Path;
+use tracing::info;
+
+/// A transaction filled in on an online machine (nonce, gas, fees, chain ID), waiting to
+/// be signed where the key is
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PreparedTx {
+    /// What the transaction does, e.g. `withdraw`
+    pub intent: String,
+    pub tx: TypedTransaction,
+}
+
+/// The file `--prepare` writes: every transaction a command would have sent, in order
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct PreparedBatch {
+    pub chain_id: u64,
+    pub txs: Vec<PreparedTx>,
+}
+
+impl PreparedBatch {
+    /// The batch in `path`, or an empty one if the file doesn't exist yet
+    pub fn load(path: &str) -> Result<Self> {
+        if !Path::new(path).exists() {
+            return Ok(Self::default());
+        }
+        let content = fs::read_to_string(path).with_context(|| format!("Failed to read {}", path))?;
+        Ok(serde_json::from_str(&content).with_context(|| format!("{} is not a prepared transaction file", path))?)
+    }
+
+    pub fn save(&self, path: &str) -> Result<()> {
+        fs::write(path, serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+}
+
+/// A prepared transaction with its signature, ready for any online machine to broadcast
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SignedTx {
+    pub intent: String,
+    pub tx: TypedTransaction,
+    pub hash: H256,
+    /// The signed transaction as `eth_sendRawTransaction` takes it
+    pub raw: Bytes,
+}
+
+/// The file `offline sign` writes
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SignedBatch {
+    pub chain_id: u64,
+    pub txs: Vec<SignedTx>,
+}
+
+impl SignedBatch {
+    pub fn load(path: &str) -> Result<Self> {
+        let content = fs::read_to_string(path).with_context(|| format!("Failed to read {}", path))?;
+        Ok(serde_json::from_str(&content).with_context(|| format!("{} is not a signed transaction file", path))?)
+    }
+
+    pub fn save(&self, path: &str) -> Result<()> {
+        fs::write(path, serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+}
+
+/// Fill in `tx` and append it to the prepared batch in `path` instead of sending it.
+/// Transactions already in the file haven't been sent, so a later one from the same
+/// sender takes the nonce after theirs rather than the node's.
+pub async fn prepare<M>(client: &M, mut tx: TypedTransaction, intent: &str, path: &str) -> Result<()>
+where
+    M: Middleware + 'static,
+{
+    if let (None, Some(sender)) = (tx.from(), client.default_sender()) {
+        tx.set_from(sender);
+    }
+    client.fill_transaction(&mut tx, None).await.map_err(|e| DexError::middleware(&e))?;
+    let chain_id = client.get_chainid().await.map_err(|e| DexError::middleware(&e))?.as_u64();
+    tx.set_chain_id(chain_id);
+
+    let mut batch = PreparedBatch::load(path)?;
+    if batch.txs.is_empty() {
+        batch.chain_id = chain_id;
+    } else if batch.chain_id != chain_id {
+        return Err(DexError::Input(format!("{} holds transactions for chain {}, not chain {}", path, batch.chain_id, chain_id)));
+    }
+    let from = tx.from().copied();
+    let last_nonce = batch
+        .txs
+        .iter()
+        .filter(|prepared| prepared.tx.from().copied() == from)
+        .filter_map(|prepared| prepared.tx.nonce().copied())
+        .max();
+    if let Some(last_nonce) = last_nonce {
+        let next = last_nonce + U256::one();
+        if tx.nonce().is_none_or(|nonce| *nonce < next) {
+            tx.set_nonce(next);
+        }
+    }
+
+    info!(
+        "Prepared {} for {:?} at nonce {} in {}",
+        intent,
+        from.unwrap_or_default(),
+        tx.nonce().copied().unwrap_or_default(),
+        path
+    );
+    if let (None, Some(from), Some(nonce)) = (tx.to(), from, tx.nonce()) {
+        info!("{} will deploy to {:?}", intent, get_contract_address(from, *nonce));
+    }
+    batch.txs.push(PreparedTx { intent: intent.to_string(), tx });
+    batch.save(path)
+}
+
+/// Sign every transaction in `batch`; needs no network access. Fails if any of them is
+/// from another address than `wallet`'s, or in read-only mode.
+pub fn sign(batch: &PreparedBatch, wallet: &LocalWallet) -> Result<SignedBatch> {
+    rpc::ensure_writable()?;
+    let wallet = wallet.clone().with_chain_id(batch.chain_id);
+    let mut txs = Vec::with_capacity(batch.txs.len());
+    for prepared in &batch.txs {
+        let from = prepared.tx.from().copied().unwrap_or_default();
+        if from != wallet.address() {
+            return Err(DexError::Input(format!(
+                "{} was prepared for {:?}, but the key is for {:?}",
+                prepared.intent,
+                from,
+                wallet.address()
+            )));
+        }
+        let signature = wallet
+            .sign_transaction_sync(&prepared.tx)
+            .map_err(|e| anyhow!("Failed to sign {}: {}", prepared.intent, e))?;
+        let raw = prepared.tx.rlp_signed(&signature);
+        txs.push(SignedTx {
+            intent: prepared.intent.clone(),
+            tx: prepared.tx.clone(),
+            hash: H256::from(keccak256(&raw)),
+            raw,
+        });
+    }
+    Ok(SignedBatch { chain_id: batch.chain_id, txs })
+}
+
+/// Send the signed transactions in order, each once the one before it is mined, and
+/// record them in the transaction journal. Stops at the first that fails or reverts.
+pub async fn broadcast<M>(client: &M, batch: &SignedBatch, confirmations: usize) -> Result<Vec<TransactionReceipt>>
+where
+    M: Middleware + 'static,
+{
+    rpc::ensure_writable()?;
+    let chain_id = client.get_chainid().await.map_err(|e| DexError::middleware(&e))?.as_u64();
+    if chain_id != batch.chain_id {
+        return Err(DexError::Config(format!(
+            "The transactions were signed for chain {} but the RPC endpoint is on chain {}",
+            batch.chain_id, chain_id
+        )));
+    }
+
+    let mut receipts = Vec::with_capacity(batch.txs.len());
+    for signed in &batch.txs {
+        let id = journal::begin(chain_id, None, &signed.intent, &signed.tx)?;
+        let pending = match client.send_raw_transaction(signed.raw.clone()).await {
+            Ok(pending) => pending,
+            Err(e) => {
+                let e = DexError::Rpc(format!("Failed to broadcast {}: {}", signed.intent, e));
+                journal::failed(id, &e);
+                return Err(e);
+            }
+        };
+        info!("Broadcast {}: {:?}", signed.intent, *pending);
+        journal::sent(id, *pending);
+        let result: Result<Option<TransactionReceipt>> = pending.confirmations(confirmations).await.map_err(Into::into);
+        journal::finish(id, &result);
+        let receipt = result?.ok_or_else(|| anyhow!("{} ({:?}) was dropped from the mempool", signed.intent, signed.hash))?;
+        if receipt.status.is_some_and(|status| status.is_zero()) {
+            return Err(DexError::reverted(client, &receipt).await);
+        }
+        receipts.push(receipt);
+    }
+    Ok(receipts)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ethers::types::{Address, TransactionRequest};
+    use ethers::utils::rlp::Rlp;
+
+    const CHAIN_ID: u64 = 10143;
+
+    fn wallet() -> LocalWallet {
+        "ac0974bec39a17e36ba4a6b4d238ff944bacb478cbed5efcae784d7bf4f2ff80".parse().unwrap()
+    }
+
+    fn prepared(from: Address, nonce: u64) -> PreparedTx {
+        let tx = TransactionRequest::new()
+            .from(from)
+            .to(Address::repeat_byte(1))
+            .value(1_000)
+            .nonce(nonce)
+            .gas(21_000)
+            .gas_price(1_000_000_000u64)
+            .chain_id(CHAIN_ID);
+        PreparedTx { intent: format!("transfer {}", nonce), tx: tx.into() }
+    }
+
+    #[test]
+    fn signed_transactions_recover_to_the_key_on_the_batch_chain() {
+        let wallet = wallet();
+        let batch = PreparedBatch { chain_id: CHAIN_ID, txs: vec![prepared(wallet.address(), 0), prepared(wallet.address(), 1)] };
+        let signed = sign(&batch, &wallet).unwrap();
+        assert_eq!(signed.chain_id, CHAIN_ID);
+        assert_eq!(signed.txs.len(), 2);
+
+        for (prepared, signed) in batch.txs.iter().zip(&signed.txs) {
+            assert_eq!(signed.intent, prepared.intent);
+            assert_eq!(signed.hash, H256::from(keccak256(&signed.raw)));
+            let (tx, signature) = TypedTransaction::decode_signed(&Rlp::new(&signed.raw)).unwrap();
+            assert_eq!(tx.nonce(), prepared.tx.nonce());
+            assert_eq!(signature.recover(tx.sighash()).unwrap(), wallet.address());
+            // EIP-155: v encodes the chain ID, so the transaction can't be replayed elsewhere
+            assert!(signature.v == CHAIN_ID * 2 + 35 || signature.v == CHAIN_ID * 2 + 36);
+        }
+    }
+
+    #[test]
+    fn transactions_for_another_sender_are_refused() {
+        let batch = PreparedBatch { chain_id: CHAIN_ID, txs: vec![prepared(Address::repeat_byte(2), 0)] };
+        let error = sign(&batch, &wallet()).unwrap_err();
+        assert_eq!(error.category(), "input");
+    }
+}
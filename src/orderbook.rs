@@ -0,0 +1,194 @@
+use crate::bindings::{BookEntry, OrderBookDEX};
+use crate::error::Result;
+use crate::output;
+use ethers::{
+    providers::Middleware,
+    types::{Address, U256},
+};
+use serde::Serialize;
+use std::collections::BTreeMap;
+use tracing::warn;
+
+/// Orders returned per `getOrderBookPage` call, keeping responses under node size limits
+pub const DEFAULT_PAGE_SIZE: u64 = 500;
+
+/// Order IDs scanned per call, keeping each `eth_call` under node gas caps
+pub const DEFAULT_SCAN_SIZE: u64 = 5_000;
+
+/// Which orders to fetch and how many per round trip
+#[derive(Debug, Clone)]
+pub struct PageQuery {
+    pub page_size: u64,
+    pub scan_size: u64,
+    pub min_price: U256,
+    pub max_price: Option<U256>,
+}
+
+impl Default for PageQuery {
+    fn default() -> Self {
+        Self {
+            page_size: DEFAULT_PAGE_SIZE,
+            scan_size: DEFAULT_SCAN_SIZE,
+            min_price: U256::zero(),
+            max_price: None,
+        }
+    }
+}
+
+impl PageQuery {
+    fn contains(&self, price: U256) -> bool {
+        price >= self.min_price && self.max_price.is_none_or(|max| price <= max)
+    }
+}
+
+/// A resting order as read from the book
+#[derive(Debug, Clone, Serialize)]
+pub struct BookOrder {
+    /// Unknown when the book came from `getOrderBook` on an older deployment
+    pub id: Option<u64>,
+    pub side: &'static str,
+    #[serde(serialize_with = "output::decimal")]
+    pub price: U256,
+    #[serde(serialize_with = "output::decimal")]
+    pub amount: U256,
+}
+
+impl BookOrder {
+    fn new(id: Option<u64>, is_buy: bool, price: U256, amount: U256) -> Self {
+        Self { id, side: if is_buy { "buy" } else { "sell" }, price, amount }
+    }
+
+    pub fn is_buy(&self) -> bool {
+        self.side == "buy"
+    }
+}
+
+impl From<BookEntry> for BookOrder {
+    fn from(entry: BookEntry) -> Self {
+        Self::new(Some(entry.id.low_u64()), entry.is_buy, entry.price, entry.amount)
+    }
+}
+
+/// A `BookEntry` as the generated getter returns it: (id, price, amount, isBuy)
+impl From<(U256, U256, U256, bool)> for BookOrder {
+    fn from((id, price, amount, is_buy): (U256, U256, U256, bool)) -> Self {
+        Self::new(Some(id.low_u64()), is_buy, price, amount)
+    }
+}
+
+/// Reads a pair's order book a page at a time through `getOrderBookPage`, so deep books
+/// never have to fit in one RPC response.
+///
+/// Deployments that predate `getOrderBookPage` are read whole with `getOrderBook` instead,
+/// filtered to the price window and handed out as a single page.
+pub struct BookPages<M> {
+    dex: OrderBookDEX<M>,
+    base_token: Address,
+    quote_token: Address,
+    query: PageQuery,
+    /// Order ID the next page starts from; `None` once the book has been read
+    next: Option<U256>,
+}
+
+impl<M: Middleware + 'static> BookPages<M> {
+    pub fn new(dex: OrderBookDEX<M>, base_token: Address, quote_token: Address, query: PageQuery) -> Self {
+        Self { dex, base_token, quote_token, query, next: Some(U256::zero()) }
+    }
+
+    /// The next non-empty page of orders in ID order, or `None` once the book has been read
+    pub async fn next_page(&mut self) -> Result<Option<Vec<BookOrder>>> {
+        while let Some(start) = self.next {
+            let result = self
+                .dex
+                .get_order_book_page(
+                    self.base_token,
+                    self.quote_token,
+                    start,
+                    U256::from(self.query.scan_size.max(1)),
+                    U256::from(self.query.page_size.max(1)),
+                    self.query.min_price,
+                    self.query.max_price.unwrap_or_default(),
+                )
+                .call()
+                .await;
+            let (entries, next_id) = match result {
+                Ok(page) => page,
+                Err(err) if start.is_zero() => {
+                    self.next = None;
+                    return self.whole_book().await.map_err(|_| err.into());
+                }
+                Err(err) => return Err(err.into()),
+            };
+
+            self.next = (!next_id.is_zero()).then_some(next_id);
+            if !entries.is_empty() {
+                return Ok(Some(entries.into_iter().map(BookOrder::from).collect()));
+            }
+        }
+        Ok(None)
+    }
+
+    async fn whole_book(&self) -> Result<Option<Vec<BookOrder>>> {
+        let (buy_prices, buy_amounts, sell_prices, sell_amounts) =
+            self.dex.get_order_book(self.base_token, self.quote_token).call().await?;
+        warn!(
+            "DEX at {:?} has no getOrderBookPage; read the whole book with getOrderBook",
+            self.dex.address()
+        );
+
+        let buys = buy_prices.into_iter().zip(buy_amounts).map(|(price, amount)| BookOrder::new(None, true, price, amount));
+        let sells = sell_prices.into_iter().zip(sell_amounts).map(|(price, amount)| BookOrder::new(None, false, price, amount));
+        let orders: Vec<BookOrder> = buys.chain(sells).filter(|order| self.query.contains(order.price)).collect();
+        Ok((!orders.is_empty()).then_some(orders))
+    }
+}
+
+/// Resting amount at one price
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct Level {
+    #[serde(serialize_with = "output::decimal")]
+    pub price: U256,
+    #[serde(serialize_with = "output::decimal")]
+    pub amount: U256,
+    pub orders: usize,
+}
+
+/// The best price levels of each side, built page by page while holding at most
+/// `max_levels` levels per side
+pub struct Depth {
+    max_levels: usize,
+    bids: BTreeMap<U256, Level>,
+    asks: BTreeMap<U256, Level>,
+}
+
+impl Depth {
+    pub fn new(max_levels: usize) -> Self {
+        Self { max_levels: max_levels.max(1), bids: BTreeMap::new(), asks: BTreeMap::new() }
+    }
+
+    pub fn add(&mut self, order: &BookOrder) {
+        let levels = if order.is_buy() { &mut self.bids } else { &mut self.asks };
+        let level = levels.entry(order.price).or_insert_with(|| Level { price: order.price, ..Default::default() });
+        level.amount += order.amount;
+        level.orders += 1;
+
+        // A dropped level can't come back: every level kept is better than it
+        if levels.len() > self.max_levels {
+            if order.is_buy() {
+                levels.pop_first();
+            } else {
+                levels.pop_last();
+            }
+        }
+    }
+
+    /// Bid levels, best (highest) first
+    pub fn bids(&self) -> impl Iterator<Item = &Level> {
+        self.bids.values().rev()
+    }
+
+    /// Ask levels, best (lowest) first
+    pub fn asks(&self) -> impl Iterator<Item = &Level> {
+        self.asks.values()
+    }
+}
@@ -0,0 +1,93 @@
+use crate::bindings::OrderBookDEX;
+use crate::error::{DexError, Result};
+use crate::output;
+use ethers::{
+    providers::Middleware,
+    types::{Address, U256},
+};
+use serde::Serialize;
+use tracing::warn;
+
+/// A trading pair's parameters as stored in the DEX `tradingPairs` mapping
+#[derive(Debug, Clone, Serialize)]
+pub struct PairParams {
+    pub base_token: Address,
+    pub quote_token: Address,
+    pub is_active: bool,
+    #[serde(serialize_with = "output::decimal")]
+    pub min_order_size: U256,
+    #[serde(serialize_with = "output::decimal")]
+    pub price_precision: U256,
+}
+
+impl PairParams {
+    pub async fn fetch<M: Middleware + 'static>(
+        contract: &OrderBookDEX<M>,
+        base_token: Address,
+        quote_token: Address,
+    ) -> Result<Self> {
+        let (_, _, is_active, min_order_size, price_precision) =
+            contract.trading_pairs(base_token, quote_token).call().await?;
+        Ok(Self {
+            base_token,
+            quote_token,
+            is_active,
+            min_order_size,
+            price_precision,
+        })
+    }
+
+    /// Unset mappings read back as all zeros
+    pub fn is_registered(&self) -> bool {
+        self.is_active || !self.price_precision.is_zero()
+    }
+
+    /// Quote amount the contract charges a buyer: `amount * price / pricePrecision`
+    pub fn quote_amount(&self, amount: U256, price: U256) -> U256 {
+        if self.price_precision.is_zero() {
+            return U256::zero();
+        }
+        amount * price / self.price_precision
+    }
+
+    /// Check an order against the contract's `require`s before sending it, so mistakes
+    /// fail here with a clear message instead of as an on-chain revert. `price` is
+    /// `None` for market orders.
+    pub fn validate_order(&self, amount: U256, price: Option<U256>, is_buy: bool) -> Result<()> {
+        if !self.is_registered() {
+            return Err(DexError::Input(format!("{:?} / {:?} is not a trading pair on this DEX", self.base_token, self.quote_token)));
+        }
+        if !self.is_active {
+            return Err(DexError::Input(format!("Trading pair {:?} / {:?} is not active", self.base_token, self.quote_token)));
+        }
+        if amount < self.min_order_size {
+            return Err(DexError::Input(format!("Order size {} is below the pair minimum of {}", amount, self.min_order_size)));
+        }
+
+        let Some(price) = price else {
+            return Ok(());
+        };
+        if price.is_zero() {
+            return Err(DexError::Input("Price must be greater than zero".to_string()));
+        }
+        if is_buy {
+            if self.price_precision.is_zero() {
+                return Err(DexError::Input("Pair has no price precision set; buy orders would revert".to_string()));
+            }
+            let quote_amount = self.quote_amount(amount, price);
+            if quote_amount.is_zero() {
+                return Err(DexError::Input(format!(
+                    "amount * price / {} rounds to 0 quote tokens; raise the amount or price",
+                    self.price_precision
+                )));
+            }
+            if !(amount * price % self.price_precision).is_zero() {
+                warn!(
+                    "Quote amount for this order is rounded down to {} (price precision {})",
+                    quote_amount, self.price_precision
+                );
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,345 @@
+use crate::bindings::OrderBookDEX;
+use crate::error::{DexError, Result};
+use crate::matching::{Match, OrderBook, Take};
+use anyhow::Context;
+use ethers::{
+    providers::Middleware,
+    types::{Address, H160, U256},
+};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Local simulated account used by `--paper`
+const PAPER_ACCOUNT_PATH: &str = "config/paper.json";
+
+/// Owner used for paper orders inside the local book, distinct from any live trader
+const PAPER_OWNER: Address = H160([0xff; 20]);
+
+/// Live state of a trading pair that paper orders are simulated against
+#[derive(Debug, Clone)]
+pub struct PairMarket {
+    pub base_token: Address,
+    pub quote_token: Address,
+    pub min_order_size: U256,
+    pub price_precision: U256,
+    pub buy_prices: Vec<U256>,
+    pub buy_amounts: Vec<U256>,
+    pub sell_prices: Vec<U256>,
+    pub sell_amounts: Vec<U256>,
+}
+
+impl PairMarket {
+    /// Read pair parameters and the current book from the DEX
+    pub async fn fetch<M: Middleware + 'static>(
+        contract: &OrderBookDEX<M>,
+        base_token: Address,
+        quote_token: Address,
+    ) -> Result<Self> {
+        let (_, _, is_active, min_order_size, price_precision) =
+            contract.trading_pairs(base_token, quote_token).call().await?;
+        if !is_active {
+            return Err(DexError::Input("Trading pair not active".to_string()));
+        }
+
+        let (buy_prices, buy_amounts, sell_prices, sell_amounts) =
+            contract.get_order_book(base_token, quote_token).call().await?;
+
+        Ok(Self {
+            base_token,
+            quote_token,
+            min_order_size,
+            price_precision,
+            buy_prices,
+            buy_amounts,
+            sell_prices,
+            sell_amounts,
+        })
+    }
+
+    /// A fresh local copy of the live book
+    pub fn book(&self) -> OrderBook {
+        OrderBook::from_levels(
+            self.price_precision,
+            &self.buy_prices,
+            &self.buy_amounts,
+            &self.sell_prices,
+            &self.sell_amounts,
+        )
+    }
+}
+
+/// A resting paper limit order
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PaperOrder {
+    pub id: u64,
+    pub base_token: Address,
+    pub quote_token: Address,
+    /// Unfilled base amount
+    pub amount: U256,
+    pub price: U256,
+    pub is_buy: bool,
+    /// Funds held for the unfilled amount (quote for buys, base for sells)
+    pub reserved: U256,
+    pub placed_at: u64,
+}
+
+/// A simulated fill of a paper order
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PaperFill {
+    /// Paper order ID, `None` for market orders
+    pub order_id: Option<u64>,
+    pub base_token: Address,
+    pub quote_token: Address,
+    pub is_buy: bool,
+    /// Base amount bought or sold
+    pub amount: U256,
+    pub price: U256,
+    pub quote_amount: U256,
+    /// Trading fee in base units
+    pub fee: U256,
+    pub timestamp: u64,
+}
+
+/// Simulated DEX account: balances, resting orders and fill history
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct PaperAccount {
+    /// Available (unreserved) balance per token
+    pub balances: BTreeMap<Address, U256>,
+    pub orders: Vec<PaperOrder>,
+    pub fills: Vec<PaperFill>,
+    pub next_order_id: u64,
+}
+
+impl PaperAccount {
+    pub fn load() -> Result<Self> {
+        if !Path::new(PAPER_ACCOUNT_PATH).exists() {
+            return Ok(Self {
+                next_order_id: 1,
+                ..Default::default()
+            });
+        }
+        let content = fs::read_to_string(PAPER_ACCOUNT_PATH)
+            .with_context(|| format!("Failed to read {}", PAPER_ACCOUNT_PATH))?;
+        Ok(serde_json::from_str(&content)?)
+    }
+
+    pub fn save(&self) -> Result<()> {
+        fs::create_dir_all("config")?;
+        fs::write(PAPER_ACCOUNT_PATH, serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+
+    pub fn reset() -> Result<()> {
+        if Path::new(PAPER_ACCOUNT_PATH).exists() {
+            fs::remove_file(PAPER_ACCOUNT_PATH)?;
+        }
+        Ok(())
+    }
+
+    pub fn balance(&self, token: Address) -> U256 {
+        self.balances.get(&token).copied().unwrap_or_default()
+    }
+
+    /// Funds held by resting orders
+    pub fn reserved(&self, token: Address) -> U256 {
+        self.orders
+            .iter()
+            .filter(|order| {
+                (order.is_buy && order.quote_token == token) || (!order.is_buy && order.base_token == token)
+            })
+            .fold(U256::zero(), |total, order| total + order.reserved)
+    }
+
+    pub fn fund(&mut self, token: Address, amount: U256) {
+        *self.balances.entry(token).or_default() += amount;
+    }
+
+    pub fn withdraw(&mut self, token: Address, amount: U256) -> Result<()> {
+        self.debit(token, amount)
+    }
+
+    fn credit(&mut self, token: Address, amount: U256) {
+        self.fund(token, amount);
+    }
+
+    fn debit(&mut self, token: Address, amount: U256) -> Result<()> {
+        let balance = self.balance(token);
+        if balance < amount {
+            return Err(DexError::Input(format!(
+                "Insufficient paper balance of {:?}: have {}, need {}",
+                token, balance, amount
+            )));
+        }
+        self.balances.insert(token, balance - amount);
+        Ok(())
+    }
+
+    /// Place a limit order against the live book, reserving its funds up front
+    pub fn place_limit(
+        &mut self,
+        market: &PairMarket,
+        amount: U256,
+        price: U256,
+        is_buy: bool,
+    ) -> Result<(u64, Vec<PaperFill>)> {
+        if amount < market.min_order_size {
+            return Err(DexError::Input("Order size too small".to_string()));
+        }
+        if price.is_zero() {
+            return Err(DexError::Input("Invalid price".to_string()));
+        }
+
+        let book = market.book();
+        let reserved = if is_buy { book.quote_amount(amount, price) } else { amount };
+        let token = if is_buy { market.quote_token } else { market.base_token };
+        self.debit(token, reserved)?;
+
+        let id = self.next_order_id;
+        self.next_order_id += 1;
+        self.orders.push(PaperOrder {
+            id,
+            base_token: market.base_token,
+            quote_token: market.quote_token,
+            amount,
+            price,
+            is_buy,
+            reserved,
+            placed_at: now(),
+        });
+
+        let fills = self.match_order(market, id).into_iter().collect();
+        Ok((id, fills))
+    }
+
+    /// Execute a market order against the live book
+    pub fn place_market(&mut self, market: &PairMarket, amount: U256, is_buy: bool) -> Result<Vec<PaperFill>> {
+        if amount < market.min_order_size {
+            return Err(DexError::Input("Order size too small".to_string()));
+        }
+
+        let mut book = market.book();
+        let takes = book.place_market(amount, is_buy)?;
+        let total_quote = takes.iter().fold(U256::zero(), |total, take| total + take.quote_amount);
+
+        // Market orders pay no trading fee in the contract
+        if is_buy {
+            self.debit(market.quote_token, total_quote)?;
+            self.credit(market.base_token, amount);
+        } else {
+            self.debit(market.base_token, amount)?;
+            self.credit(market.quote_token, total_quote);
+        }
+
+        let fills: Vec<PaperFill> = takes
+            .iter()
+            .map(|take: &Take| PaperFill {
+                order_id: None,
+                base_token: market.base_token,
+                quote_token: market.quote_token,
+                is_buy,
+                amount: take.amount,
+                price: take.price,
+                quote_amount: take.quote_amount,
+                fee: U256::zero(),
+                timestamp: now(),
+            })
+            .collect();
+        self.fills.extend(fills.iter().cloned());
+        Ok(fills)
+    }
+
+    /// Cancel a resting order and release its reserved funds
+    pub fn cancel(&mut self, id: u64) -> Result<PaperOrder> {
+        let index = self
+            .orders
+            .iter()
+            .position(|order| order.id == id)
+            .ok_or_else(|| DexError::Input(format!("Paper order {} not found or no longer active", id)))?;
+        let order = self.orders.remove(index);
+        let token = if order.is_buy { order.quote_token } else { order.base_token };
+        self.credit(token, order.reserved);
+        Ok(order)
+    }
+
+    /// Re-run every resting order in the pair against the latest live book.
+    ///
+    /// Live liquidity is not depleted by paper fills, so a large resting order can keep
+    /// filling against the same level on every refresh until it is done.
+    pub fn settle(&mut self, market: &PairMarket) -> Vec<PaperFill> {
+        let ids: Vec<u64> = self
+            .orders
+            .iter()
+            .filter(|order| order.base_token == market.base_token && order.quote_token == market.quote_token)
+            .map(|order| order.id)
+            .collect();
+        ids.into_iter().filter_map(|id| self.match_order(market, id)).collect()
+    }
+
+    /// Add one paper order to a copy of the live book and apply the contract's single match
+    fn match_order(&mut self, market: &PairMarket, id: u64) -> Option<PaperFill> {
+        let index = self.orders.iter().position(|order| order.id == id)?;
+        let order = self.orders[index].clone();
+
+        let mut book = market.book();
+        let (book_id, matched) = book.place_limit(Some(PAPER_OWNER), order.amount, order.price, order.is_buy);
+        let matched: Match = matched.filter(|m| m.buy_order_id == book_id || m.sell_order_id == book_id)?;
+
+        let fee = matched.matched - matched.base_amount;
+        let remaining = order.amount - matched.matched;
+        let fill = if order.is_buy {
+            // Pay the seller's proceeds and release the rest of the reservation for the filled part
+            let held = book.quote_amount(matched.matched, order.price);
+            let released = held.min(order.reserved);
+            self.credit(market.base_token, matched.base_amount);
+            self.credit(market.quote_token, released - matched.quote_amount.min(released));
+            self.orders[index].reserved -= released;
+            PaperFill {
+                order_id: Some(id),
+                base_token: market.base_token,
+                quote_token: market.quote_token,
+                is_buy: true,
+                amount: matched.base_amount,
+                price: matched.price,
+                quote_amount: matched.quote_amount,
+                fee,
+                timestamp: now(),
+            }
+        } else {
+            self.credit(market.quote_token, matched.quote_amount);
+            self.orders[index].reserved -= matched.matched;
+            PaperFill {
+                order_id: Some(id),
+                base_token: market.base_token,
+                quote_token: market.quote_token,
+                is_buy: false,
+                amount: matched.matched,
+                price: matched.price,
+                quote_amount: matched.quote_amount,
+                fee,
+                timestamp: now(),
+            }
+        };
+
+        if remaining.is_zero() {
+            let order = self.orders.remove(index);
+            // Rounding dust left in the reservation goes back to the balance
+            let token = if order.is_buy { order.quote_token } else { order.base_token };
+            self.credit(token, order.reserved);
+        } else {
+            self.orders[index].amount = remaining;
+        }
+
+        self.fills.push(fill.clone());
+        Some(fill)
+    }
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default()
+}
@@ -0,0 +1,92 @@
+use crate::bindings::ERC20;
+use crate::error::Result;
+use crate::rpc;
+use crate::wallets::AccountSigner;
+use anyhow::{anyhow, Context};
+use ethers::{
+    abi::{encode, Token},
+    providers::Middleware,
+    signers::Signer,
+    types::{Address, BigEndianHash, H256, U256},
+    utils::keccak256,
+};
+use serde::Serialize;
+use std::sync::Arc;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+const PERMIT_TYPE: &str = "Permit(address owner,address spender,uint256 value,uint256 nonce,uint256 deadline)";
+
+/// A signed EIP-2612 permit, ready to submit to the token or a contract that forwards it
+#[derive(Debug, Clone, Serialize)]
+pub struct SignedPermit {
+    pub token: Address,
+    pub owner: Address,
+    pub spender: Address,
+    pub value: U256,
+    pub nonce: U256,
+    pub deadline: U256,
+    pub v: u8,
+    pub r: H256,
+    pub s: H256,
+}
+
+/// Deadline `seconds` from now
+pub fn deadline_in(seconds: u64) -> U256 {
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default();
+    U256::from(now + seconds)
+}
+
+/// Sign a permit letting `spender` move `value` of `token` from the wallet.
+///
+/// The domain separator is read from the token rather than rebuilt, so tokens with a
+/// non-standard name or version still verify.
+pub async fn sign<M: Middleware + 'static>(
+    client: Arc<M>,
+    wallet: &AccountSigner,
+    token: Address,
+    spender: Address,
+    value: U256,
+    deadline: U256,
+) -> Result<SignedPermit> {
+    let contract = ERC20::new(token, client);
+    let domain_separator = contract
+        .domain_separator()
+        .call()
+        .await
+        .with_context(|| format!("{:?} does not support EIP-2612 permits", token))?;
+    let owner = wallet.address();
+    let nonce = contract.nonces(owner).call().await?;
+
+    let struct_hash = keccak256(encode(&[
+        Token::FixedBytes(keccak256(PERMIT_TYPE).to_vec()),
+        Token::Address(owner),
+        Token::Address(spender),
+        Token::Uint(value),
+        Token::Uint(nonce),
+        Token::Uint(deadline),
+    ]));
+    let mut message = Vec::with_capacity(66);
+    message.extend_from_slice(&[0x19, 0x01]);
+    message.extend_from_slice(&domain_separator);
+    message.extend_from_slice(&struct_hash);
+
+    rpc::ensure_writable()?;
+    let signature = wallet
+        .sign_hash(H256::from(keccak256(message)))
+        .map_err(|e| anyhow!("Failed to sign permit: {}", e))?;
+
+    Ok(SignedPermit {
+        token,
+        owner,
+        spender,
+        value,
+        nonce,
+        deadline,
+        v: signature.v as u8,
+        r: H256::from_uint(&signature.r),
+        s: H256::from_uint(&signature.s),
+    })
+}
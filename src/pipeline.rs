@@ -0,0 +1,438 @@
+use crate::aliases;
+use crate::bot::SignerClient;
+use crate::create2;
+use crate::deployments::{self, Deployment, DeploymentRegistry};
+use crate::error::{DexError, Result};
+use crate::explorer;
+use crate::fees::Fees;
+use crate::journal;
+use crate::rpc;
+use crate::tx::{self, TxArgs};
+use anyhow::{anyhow, Context};
+use ethers::{
+    abi::{
+        token::{LenientTokenizer, Tokenizer},
+        Abi, Param, Token,
+    },
+    contract::{Contract, ContractFactory},
+    providers::Middleware,
+    types::{Address, Bytes, TransactionReceipt, H256, U256},
+};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::path::Path;
+use std::sync::Arc;
+use tracing::info;
+
+/// A deployment manifest: steps run in order, later steps referring to addresses
+/// deployed by earlier ones as `${step_id}` (and to the sender as `${deployer}`)
+#[derive(Debug, Clone, Deserialize)]
+pub struct Manifest {
+    pub steps: Vec<Step>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum Step {
+    /// Deploy a contract compiled to `out/<contract>.sol/<contract>.json`
+    Deploy {
+        id: String,
+        contract: String,
+        #[serde(default)]
+        args: Vec<String>,
+        /// Deploy with CREATE2 through the deterministic deployer, so the address is
+        /// the same on every network
+        #[serde(default)]
+        salt: Option<String>,
+    },
+    /// Call a function on a deployed contract, e.g. addTradingPair, mint, approve or
+    /// placeLimitOrder to seed liquidity
+    Call {
+        #[serde(default)]
+        id: Option<String>,
+        /// Address or `${step_id}`
+        target: String,
+        /// Artifact to take the ABI from; defaults to the contract the target step deployed,
+        /// then to the target's verified ABI on the block explorer
+        #[serde(default)]
+        contract: Option<String>,
+        function: String,
+        #[serde(default)]
+        args: Vec<String>,
+        /// Native token to send with the call, in wei
+        #[serde(default)]
+        value: Option<String>,
+    },
+}
+
+impl Step {
+    pub fn id(&self) -> Option<&str> {
+        match self {
+            Step::Deploy { id, .. } => Some(id),
+            Step::Call { id, .. } => id.as_deref(),
+        }
+    }
+
+    fn describe(&self) -> String {
+        match self {
+            Step::Deploy { id, contract, .. } => format!("deploy {} as {}", contract, id),
+            Step::Call { target, function, .. } => format!("call {} on {}", function, target),
+        }
+    }
+}
+
+impl Manifest {
+    pub fn load(path: &str) -> Result<Self> {
+        let content = fs::read_to_string(path).with_context(|| format!("Failed to read {}", path))?;
+        let manifest: Self = toml::from_str(&content).with_context(|| format!("Invalid deploy manifest {}", path))?;
+
+        let mut ids = Vec::new();
+        for step in &manifest.steps {
+            if let Some(id) = step.id() {
+                if id == "deployer" || ids.contains(&id) {
+                    return Err(DexError::Config(format!("Step id '{}' is reserved or used more than once in {}", id, path)));
+                }
+                ids.push(id);
+            }
+        }
+        Ok(manifest)
+    }
+}
+
+/// Outcome of a completed step
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StepRecord {
+    pub id: Option<String>,
+    /// Deployed contract, for deploy steps
+    pub address: Option<Address>,
+    /// Artifact the address was deployed from
+    pub contract: Option<String>,
+    pub tx: H256,
+}
+
+/// Completed steps of a manifest keyed by step index, saved after every step so a
+/// failed pipeline resumes where it stopped
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct PipelineState {
+    pub completed: BTreeMap<usize, StepRecord>,
+}
+
+impl PipelineState {
+    pub fn path(manifest_path: &str) -> String {
+        format!("{}.state.json", manifest_path)
+    }
+
+    pub fn load(manifest_path: &str) -> Result<Self> {
+        let path = Self::path(manifest_path);
+        if !Path::new(&path).exists() {
+            return Ok(Self::default());
+        }
+        Ok(serde_json::from_str(&fs::read_to_string(&path)?)?)
+    }
+
+    pub fn save(&self, manifest_path: &str) -> Result<()> {
+        if rpc::is_fork() {
+            return Ok(());
+        }
+        fs::write(Self::path(manifest_path), serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+}
+
+/// ABI and creation bytecode of a contract compiled by forge or `monad-deploy build`
+pub fn load_artifact(contract: &str) -> Result<(Abi, Bytes)> {
+    let path = format!("out/{0}.sol/{0}.json", contract);
+    let content = fs::read_to_string(&path).with_context(|| format!("Failed to read {} (run `monad-deploy build`)", path))?;
+    let json: serde_json::Value = serde_json::from_str(&content)?;
+    let abi: Abi = serde_json::from_value(json["abi"].clone())?;
+    let bytecode = json["bytecode"]["object"]
+        .as_str()
+        .ok_or_else(|| DexError::Config(format!("{} has no bytecode", path)))?;
+    let bytecode = hex::decode(bytecode.trim_start_matches("0x")).with_context(|| format!("Invalid bytecode in {}", path))?;
+    Ok((abi, bytecode.into()))
+}
+
+/// Parse each argument as the type `params` declares for it
+pub fn tokenize_args(what: &str, params: &[Param], args: &[String]) -> Result<Vec<Token>> {
+    if params.len() != args.len() {
+        let expected: Vec<String> = params.iter().map(|p| format!("{} {}", p.kind, p.name)).collect();
+        return Err(DexError::Config(format!(
+            "{} takes {} argument(s) ({}), got {}",
+            what,
+            params.len(),
+            expected.join(", "),
+            args.len()
+        )));
+    }
+
+    params
+        .iter()
+        .zip(args)
+        .map(|(param, arg)| {
+            LenientTokenizer::tokenize(&param.kind, arg).map_err(|e| {
+                DexError::Input(format!("Invalid value '{}' for {} argument {} ({}): {}", arg, what, param.name, param.kind, e))
+            })
+        })
+        .collect()
+}
+
+/// Run every step not yet recorded in the manifest's state file.
+///
+/// Progress also goes to the transaction journal, each step sent under its own
+/// idempotency key, so a step in flight when the pipeline stopped isn't sent twice on
+/// `resume`. `restart` abandons the interrupted run and its state and starts over.
+pub async fn run(
+    manifest_path: &str,
+    client: Arc<SignerClient>,
+    fees: &Fees,
+    tx_args: &TxArgs,
+    resume: bool,
+    restart: bool,
+) -> Result<()> {
+    let manifest = Manifest::load(manifest_path)?;
+    let mut run = if tx_args.estimate_only {
+        None
+    } else {
+        Some(journal::start_run("pipeline", manifest_path, manifest.steps.len(), resume, restart)?)
+    };
+    let mut state = if restart { PipelineState::default() } else { PipelineState::load(manifest_path)? };
+    if let Some(run) = run.as_mut() {
+        for (index, record) in &state.completed {
+            if !run.is_done(*index) {
+                run.step_done(*index, record.tx);
+            }
+        }
+    }
+
+    let mut addresses: HashMap<String, Address> = HashMap::new();
+    let mut contracts: HashMap<Address, String> = HashMap::new();
+    addresses.insert("deployer".to_string(), client.address());
+
+    for (index, step) in manifest.steps.iter().enumerate() {
+        let label = format!("Step {}/{}", index + 1, manifest.steps.len());
+
+        if let Some(record) = state.completed.get(&index) {
+            if record.id.as_deref() != step.id() {
+                return Err(DexError::Config(format!(
+                    "{} was recorded as '{}' but is now '{}'; the manifest changed since the last run (delete {} to start over)",
+                    label,
+                    record.id.as_deref().unwrap_or("-"),
+                    step.id().unwrap_or("-"),
+                    PipelineState::path(manifest_path)
+                )));
+            }
+            info!("{}: {} already done in {:?}", label, step.describe(), record.tx);
+            remember(record, &mut addresses, &mut contracts);
+            continue;
+        }
+
+        info!("{}: {}", label, step.describe());
+        let tx_args = &TxArgs { idempotency_key: run.as_ref().map(|run| run.key(index)), ..tx_args.clone() };
+        let record = match step {
+            Step::Deploy { id, contract, args, salt } => {
+                let args = substitute(args, &addresses)?;
+                let salt = salt.as_deref().map(create2::parse_salt).transpose()?;
+                deploy(client.clone(), id, contract, &args, salt, fees, tx_args).await?
+            }
+            Step::Call { id, target, contract, function, args, value } => {
+                let target = resolve(target, &addresses)?;
+                // Without an artifact for the target, use its verified ABI from the explorer
+                let abi = match contract.as_ref().or_else(|| contracts.get(&target)) {
+                    Some(contract) => load_artifact(contract)?.0,
+                    None => explorer::abi(client.as_ref(), target).await.with_context(|| {
+                        format!("{}: set `contract` for {:?}, which was not deployed by this manifest", label, target)
+                    })?,
+                };
+                let args = substitute(args, &addresses)?;
+                let value = value
+                    .as_deref()
+                    .map(U256::from_dec_str)
+                    .transpose()
+                    .map_err(|e| DexError::Config(format!("{}: invalid value: {}", label, e)))?;
+                call(client.clone(), id.clone(), target, abi, function, &args, value, fees, tx_args).await?
+            }
+        };
+
+        let Some(record) = record else {
+            info!("Later steps depend on this one, so only the first pending step is estimated");
+            return Ok(());
+        };
+        remember(&record, &mut addresses, &mut contracts);
+        if let Some(run) = run.as_mut() {
+            run.step_done(index, record.tx);
+        }
+        state.completed.insert(index, record);
+        state.save(manifest_path)?;
+    }
+    if let Some(run) = run.as_mut() {
+        run.finish()?;
+    }
+
+    println!("Deployment Pipeline");
+    println!("================================");
+    for (index, record) in &state.completed {
+        let name = record.id.as_deref().unwrap_or("-");
+        match record.address {
+            Some(address) => println!("{:>3}. {}: {:?} (tx {:?})", index + 1, name, address, record.tx),
+            None => println!("{:>3}. {}: tx {:?}", index + 1, name, record.tx),
+        }
+    }
+    Ok(())
+}
+
+fn remember(record: &StepRecord, addresses: &mut HashMap<String, Address>, contracts: &mut HashMap<Address, String>) {
+    if let (Some(id), Some(address)) = (&record.id, record.address) {
+        addresses.insert(id.clone(), address);
+    }
+    if let (Some(address), Some(contract)) = (record.address, &record.contract) {
+        contracts.insert(address, contract.clone());
+    }
+}
+
+/// Replace `${step_id}` references with the addresses they point to
+fn substitute(args: &[String], addresses: &HashMap<String, Address>) -> Result<Vec<String>> {
+    args.iter()
+        .map(|arg| {
+            let mut result = String::new();
+            let mut rest = arg.as_str();
+            while let Some(start) = rest.find("${") {
+                let end = rest[start..]
+                    .find('}')
+                    .ok_or_else(|| DexError::Config(format!("Unterminated reference in '{}'", arg)))?;
+                let name = &rest[start + 2..start + end];
+                let address = addresses
+                    .get(name)
+                    .ok_or_else(|| DexError::Config(format!("'{}' refers to '{}', which no earlier step deployed", arg, name)))?;
+                result.push_str(&rest[..start]);
+                result.push_str(&format!("{:?}", address));
+                rest = &rest[start + end + 1..];
+            }
+            result.push_str(rest);
+            Ok(result)
+        })
+        .collect()
+}
+
+fn resolve(target: &str, addresses: &HashMap<String, Address>) -> Result<Address> {
+    let target = substitute(&[target.to_string()], addresses)?.remove(0);
+    aliases::resolve(&target)
+}
+
+async fn deploy(
+    client: Arc<SignerClient>,
+    id: &str,
+    contract: &str,
+    args: &[String],
+    salt: Option<H256>,
+    fees: &Fees,
+    tx_args: &TxArgs,
+) -> Result<Option<StepRecord>> {
+    let (abi, bytecode) = load_artifact(contract)?;
+    let params = abi.constructor().map(|c| c.inputs.as_slice()).unwrap_or_default();
+    let tokens = tokenize_args(&format!("{} constructor", contract), params, args)?;
+
+    let deployed = match salt {
+        Some(salt) => {
+            let init_code = create2::init_code(&abi, &bytecode, tokens)?;
+            let (address, receipt) = create2::deploy(client.as_ref(), salt, init_code, fees, tx_args).await?;
+            receipt.map(|receipt| (address, receipt))
+        }
+        None => deploy_contract(client.clone(), abi, bytecode, tokens, fees, tx_args).await?,
+    };
+    let Some((address, receipt)) = deployed else {
+        return Ok(None);
+    };
+    info!("Deployed {} at {:?}", contract, address);
+
+    let mut registry = DeploymentRegistry::load()?;
+    registry.record(
+        &deployments::current_network(),
+        contract,
+        Deployment {
+            address,
+            deployer: Some(client.address()),
+            tx: Some(receipt.transaction_hash),
+            label: Some(id.to_string()),
+            constructor_args: args.to_vec(),
+            create2_salt: salt.map(|salt| format!("{:?}", salt)),
+            ..Default::default()
+        },
+    );
+    registry.save()?;
+
+    Ok(Some(StepRecord {
+        id: Some(id.to_string()),
+        address: Some(address),
+        contract: Some(contract.to_string()),
+        tx: receipt.transaction_hash,
+    }))
+}
+
+/// Deploy with CREATE, returning the address and receipt (`None` for estimates and
+/// prepared transactions)
+pub async fn deploy_contract(
+    client: Arc<SignerClient>,
+    abi: Abi,
+    bytecode: Bytes,
+    args: Vec<Token>,
+    fees: &Fees,
+    tx_args: &TxArgs,
+) -> Result<Option<(Address, TransactionReceipt)>> {
+    let factory = ContractFactory::new(abi, bytecode, client.clone());
+    let mut deploy_tx = factory.deploy_tokens(args)?;
+    fees.apply(&mut deploy_tx.tx);
+    let estimated_gas = client.estimate_gas(&deploy_tx.tx, None).await.map_err(|e| DexError::middleware(&e))?;
+
+    if tx_args.estimate_only {
+        tx::print_estimate(estimated_gas, fees, tx_args).await;
+        return Ok(None);
+    }
+
+    // Sent like any other transaction, so it is journaled
+    deploy_tx.tx.set_gas(estimated_gas);
+    let receipt = tx::broadcast(client.as_ref(), deploy_tx.tx, tx_args).await?;
+    if tx_args.prepare.is_some() {
+        return Ok(None);
+    }
+    let receipt = receipt
+        .ok_or_else(|| anyhow!("Deployment transaction was dropped from the mempool"))?;
+    tx::ensure_success(client.as_ref(), &receipt).await?;
+    let address = receipt
+        .contract_address
+        .ok_or_else(|| anyhow!("No contract address in {:?}", receipt.transaction_hash))?;
+    tx::report(estimated_gas, &receipt, tx_args).await;
+    Ok(Some((address, receipt)))
+}
+
+#[allow(clippy::too_many_arguments)]
+async fn call(
+    client: Arc<SignerClient>,
+    id: Option<String>,
+    target: Address,
+    abi: Abi,
+    function: &str,
+    args: &[String],
+    value: Option<U256>,
+    fees: &Fees,
+    tx_args: &TxArgs,
+) -> Result<Option<StepRecord>> {
+    let params = abi.function(function)?.inputs.clone();
+    let tokens = tokenize_args(function, &params, args)?;
+
+    let contract = Contract::new(target, abi, client.clone());
+    let mut method = contract.method::<_, ()>(function, Token::Tuple(tokens))?;
+    if let Some(value) = value {
+        method = method.value(value);
+    }
+
+    let Some(receipt) = tx::send(client.as_ref(), method, fees, tx_args).await? else {
+        return Ok(None);
+    };
+    Ok(Some(StepRecord {
+        id,
+        address: None,
+        contract: None,
+        tx: receipt.transaction_hash,
+    }))
+}
@@ -0,0 +1,356 @@
+use crate::bands::PriceBands;
+use crate::bindings::{OrderBookDEX, ERC20, WMON};
+use crate::bot::SignerClient;
+use crate::error::{DexError, Result};
+use crate::events::Placement;
+use crate::expiry::{self, ExpiringOrder, ExpiryJournal};
+use crate::fees::Fees;
+use crate::limits::{self, Spend};
+use crate::pairs::PairParams;
+use crate::paper::PairMarket;
+use crate::permit;
+use crate::risk::{RiskEngine, RiskLimits};
+use crate::rpc;
+use crate::session::{self, Action};
+use crate::strategy::BookView;
+use crate::tx::{self, TxArgs};
+use ethers::{
+    providers::Middleware,
+    types::{Address, TransactionReceipt, U256},
+    utils::format_ether,
+};
+use std::collections::BTreeMap;
+use std::sync::Arc;
+use std::time::SystemTime;
+use tracing::info;
+
+/// A limit order, its addresses already resolved
+#[derive(Debug, Clone, Copy)]
+pub struct LimitOrder {
+    pub dex: Address,
+    pub base_token: Address,
+    pub quote_token: Address,
+    pub amount: U256,
+    pub price: U256,
+    pub is_buy: bool,
+}
+
+impl LimitOrder {
+    /// The token the order deposits and how much of it: buyers deposit the quote amount,
+    /// sellers the base amount
+    fn deposit(&self, pair: &PairParams) -> (Address, U256) {
+        if self.is_buy {
+            (self.quote_token, pair.quote_amount(self.amount, self.price))
+        } else {
+            (self.base_token, self.amount)
+        }
+    }
+}
+
+/// How `place_limit_order` checks and funds the order before sending it
+#[derive(Debug, Clone, Default)]
+pub struct LimitOrderOptions {
+    /// Approve the deposited token with an EIP-2612 permit in the same transaction
+    pub use_permit: bool,
+    /// WMON contract to wrap MON into when the order deposits WMON the wallet doesn't hold
+    pub auto_wrap: Option<Address>,
+    /// Approve, withdraw or attach exactly what the deposit is missing
+    pub auto_deposit: bool,
+    /// Refuse to take liquidity; `Some(true)` moves a crossing order inside the spread
+    /// instead of aborting
+    pub post_only: Option<bool>,
+    /// Skip the price band check
+    pub force: bool,
+    /// Hand the order to the expiry daemon to cancel at this time
+    pub expires_at: Option<SystemTime>,
+}
+
+/// Check, fund and send a limit order, returning its receipt (`None` for estimates and
+/// prepared transactions). `rpc_url` is read for the `risk set` limits' exposure.
+pub async fn place_limit_order(
+    client: Arc<SignerClient>,
+    rpc_url: Option<&str>,
+    mut order: LimitOrder,
+    options: &LimitOrderOptions,
+    fees: &Fees,
+    tx_args: &TxArgs,
+) -> Result<Option<TransactionReceipt>> {
+    let contract = OrderBookDEX::new(order.dex, client.clone());
+
+    // Catch orders the contract would reject before paying for the transaction
+    let pair = PairParams::fetch(&contract, order.base_token, order.quote_token).await?;
+
+    // Settle the post-only price before anything is sized off it
+    if let Some(reprice) = options.post_only {
+        order.price = post_only_price(&contract, &order, reprice).await?;
+    }
+    pair.validate_order(order.amount, Some(order.price), order.is_buy)?;
+
+    // Catch fat-fingered prices against the live mid and the reference price
+    let bands = PriceBands::load()?;
+    if !options.force && !bands.is_empty() {
+        let market = PairMarket::fetch(&contract, order.base_token, order.quote_token).await?;
+        let mid = BookView::from_levels(&market.buy_prices, &market.buy_amounts, &market.sell_prices, &market.sell_amounts).mid();
+        bands.check(order.price, mid, bands.reference_price().await?)?;
+    }
+    check_risk(rpc_url, client.address(), &order, Some(order.price)).await?;
+    let notional = pair.quote_amount(order.amount, order.price);
+    limits::check(Spend::OrderNotional, &BTreeMap::from([(order.quote_token, notional)]), tx_args)?;
+    session::check(client.address(), Action::Place { base_token: order.base_token, quote_token: order.quote_token, notional })?;
+
+    let (token, deposit) = order.deposit(&pair);
+
+    // Wrap MON first when the order deposits WMON the wallet doesn't hold yet
+    if let Some(wmon) = options.auto_wrap {
+        if token == wmon {
+            wrap_shortfall(client.clone(), wmon, deposit, fees, tx_args).await?;
+        }
+    }
+
+    let native_value = if options.auto_deposit {
+        fund_deposit(client.clone(), order.dex, token, deposit, fees, tx_args).await?
+    } else {
+        None
+    };
+
+    // The book may have moved while the order was funded. Deposits are already sized
+    // for this price, so a second cross aborts rather than repricing again.
+    if options.post_only.is_some() {
+        post_only_price(&contract, &order, false).await?;
+    }
+
+    let mut method = if options.use_permit {
+        if token.is_zero() {
+            return Err(DexError::Input("The native token is deposited with the order and needs no permit".to_string()));
+        }
+        let signed = permit::sign(client.clone(), client.signer(), token, order.dex, deposit, permit::deadline_in(3600)).await?;
+        contract.place_limit_order_with_permit(
+            order.base_token,
+            order.quote_token,
+            order.amount,
+            order.price,
+            order.is_buy,
+            signed.value,
+            signed.deadline,
+            signed.v,
+            signed.r.to_fixed_bytes(),
+            signed.s.to_fixed_bytes(),
+        )
+    } else {
+        contract.place_limit_order(order.base_token, order.quote_token, order.amount, order.price, order.is_buy)
+    };
+    if let Some(value) = native_value {
+        method = method.value(value);
+    }
+    let Some(receipt) = tx::send(client.as_ref(), method, fees, tx_args).await? else {
+        return Ok(None);
+    };
+    tx::ensure_success(client.as_ref(), &receipt).await?;
+    limits::record(Spend::OrderNotional, order.quote_token, notional)?;
+    session::record(client.address(), notional)?;
+    info!("Limit order placed successfully!");
+
+    // The contract has no native expiry, so hand the order to the expiry daemon
+    if let Some(expires_at) = options.expires_at {
+        let order_id = Placement::from_receipt(&receipt)
+            .order_id
+            .ok_or_else(|| DexError::Rpc("Order placed but its ID was not found in the receipt; it will not expire".to_string()))?;
+        let expiring = ExpiringOrder {
+            dex: order.dex,
+            order_id: order_id.as_u64(),
+            trader: client.address(),
+            expires_at: expiry::unix_secs(expires_at),
+        };
+        ExpiryJournal::update(|journal| journal.record(expiring))?;
+        info!("Order {} expires at {}", order_id, humantime::format_rfc3339_seconds(expires_at));
+    }
+
+    Ok(Some(receipt))
+}
+
+/// Check a market order against the pair's parameters, the `risk set` limits, the
+/// spending caps and the signer's session, returning the quote notional it is capped at:
+/// what the book says it will cost or fetch now. `order.price` is ignored.
+pub async fn check_market_order(client: Arc<SignerClient>, rpc_url: Option<&str>, order: &LimitOrder, tx_args: &TxArgs) -> Result<U256> {
+    let contract = OrderBookDEX::new(order.dex, client.clone());
+    PairParams::fetch(&contract, order.base_token, order.quote_token)
+        .await?
+        .validate_order(order.amount, None, order.is_buy)?;
+    // Market orders have no limit price; the risk check judges them at the book's
+    check_risk(rpc_url, client.address(), order, None).await?;
+    let notional = PairMarket::fetch(&contract, order.base_token, order.quote_token)
+        .await?
+        .book()
+        .quote_market(order.amount, order.is_buy)
+        .quote_amount;
+    limits::check(Spend::OrderNotional, &BTreeMap::from([(order.quote_token, notional)]), tx_args)?;
+    session::check(client.address(), Action::Place { base_token: order.base_token, quote_token: order.quote_token, notional })?;
+    Ok(notional)
+}
+
+/// Refuse an order the limits set with `risk set` don't allow; market orders pass no
+/// `price` and are judged at the book's
+pub async fn check_risk(rpc_url: Option<&str>, trader: Address, order: &LimitOrder, price: Option<U256>) -> Result<()> {
+    let limits = RiskLimits::load()?;
+    if limits.is_empty() {
+        return Ok(());
+    }
+    let mut engine = RiskEngine::new(Arc::new(rpc::connect(rpc_url).await?), order.dex, trader, limits);
+    engine.check(order.base_token, order.quote_token, order.amount, price, order.is_buy).await
+}
+
+/// Price at which a post-only order rests without matching, or an error if it would
+/// cross and `reprice` is off
+async fn post_only_price<M: Middleware + 'static>(contract: &OrderBookDEX<M>, order: &LimitOrder, reprice: bool) -> Result<U256> {
+    let book = PairMarket::fetch(contract, order.base_token, order.quote_token).await?.book();
+    let (side, opposite) = if order.is_buy { ("ask", book.best_ask()) } else { ("bid", book.best_bid()) };
+    let opposite = opposite.unwrap_or_default();
+    match book.post_only_price(order.price, order.is_buy) {
+        Some(resting) if resting == order.price => Ok(order.price),
+        Some(resting) if reprice => {
+            info!("Post-only: price {} would cross the best {} of {}, repricing to {}", order.price, side, opposite, resting);
+            Ok(resting)
+        }
+        _ => Err(DexError::Input(format!(
+            "Post-only: price {} would cross the best {} of {}; not sending the order",
+            order.price, side, opposite
+        ))),
+    }
+}
+
+/// Wrap enough MON that the wallet holds at least `needed` WMON
+async fn wrap_shortfall(client: Arc<SignerClient>, wmon_address: Address, needed: U256, fees: &Fees, tx_args: &TxArgs) -> Result<()> {
+    let contract = WMON::new(wmon_address, client.clone());
+    let held = contract.balance_of(client.address()).call().await?;
+    if held >= needed {
+        return Ok(());
+    }
+
+    let shortfall = needed - held;
+    info!("Wrapping {} MON to cover the order deposit", format_ether(shortfall));
+    let method = contract.deposit().value(shortfall);
+    if let Some(receipt) = tx::send(client.as_ref(), method, fees, &tx_args.step("wrap")).await? {
+        tx::ensure_success(client.as_ref(), &receipt).await?;
+    }
+
+    Ok(())
+}
+
+/// Make sure the wallet can fund an order deposit of `deposit` of `token`.
+///
+/// ERC-20 deposits are pulled with `transferFrom`, so a wallet short of tokens first
+/// withdraws the difference from its DEX balance (within the `limits set` withdrawal
+/// caps), and an allowance below the deposit is raised to exactly the deposit. Native
+/// deposits travel as the transaction value, which is returned for the caller to attach.
+async fn fund_deposit(
+    client: Arc<SignerClient>,
+    contract_address: Address,
+    token: Address,
+    deposit: U256,
+    fees: &Fees,
+    tx_args: &TxArgs,
+) -> Result<Option<U256>> {
+    let owner = client.address();
+    if token.is_zero() {
+        let held = client.get_balance(owner, None).await.map_err(|e| DexError::middleware(&e))?;
+        if held < deposit {
+            return Err(DexError::Input(format!(
+                "Wallet holds {} MON but the order deposits {} MON",
+                format_ether(held),
+                format_ether(deposit)
+            )));
+        }
+        return Ok(Some(deposit));
+    }
+
+    let erc20 = ERC20::new(token, client.clone());
+    let held = erc20.balance_of(owner).call().await?;
+    if held < deposit {
+        let dex = OrderBookDEX::new(contract_address, client.clone());
+        let on_dex = dex.get_user_balance(owner, token).call().await?;
+        let shortfall = withdrawal_for(token, deposit, held, on_dex)?;
+        limits::check(Spend::Withdrawal, &BTreeMap::from([(token, shortfall)]), tx_args)?;
+        session::check(owner, Action::Withdraw)?;
+        info!("Withdrawing {} of {:?} from the DEX balance to cover the deposit", shortfall, token);
+        if let Some(receipt) = tx::send(client.as_ref(), dex.withdraw(token, shortfall), fees, &tx_args.step("withdraw")).await? {
+            tx::ensure_success(client.as_ref(), &receipt).await?;
+            limits::record(Spend::Withdrawal, token, shortfall)?;
+        }
+    }
+
+    let allowance = erc20.allowance(owner, contract_address).call().await?;
+    if allowance < deposit {
+        info!("Approving {} of {:?} for the deposit (allowance was {})", deposit, token, allowance);
+        if let Some(receipt) = tx::send(client.as_ref(), erc20.approve(contract_address, deposit), fees, &tx_args.step("approve")).await? {
+            tx::ensure_success(client.as_ref(), &receipt).await?;
+        }
+    }
+
+    Ok(None)
+}
+
+/// What the wallet must withdraw from its DEX balance `on_dex` to fund a deposit of
+/// `deposit` of `token` while holding `held`
+fn withdrawal_for(token: Address, deposit: U256, held: U256, on_dex: U256) -> Result<U256> {
+    let shortfall = deposit.saturating_sub(held);
+    if on_dex < shortfall {
+        return Err(DexError::Input(format!(
+            "Order deposits {} of {:?} but the wallet holds {} and the DEX balance {}",
+            deposit, token, held, on_dex
+        )));
+    }
+    Ok(shortfall)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BASE: Address = Address::repeat_byte(1);
+    const QUOTE: Address = Address::repeat_byte(2);
+
+    fn pair() -> PairParams {
+        PairParams {
+            base_token: BASE,
+            quote_token: QUOTE,
+            is_active: true,
+            min_order_size: U256::one(),
+            price_precision: U256::from(100),
+        }
+    }
+
+    fn order(is_buy: bool) -> LimitOrder {
+        LimitOrder {
+            dex: Address::repeat_byte(9),
+            base_token: BASE,
+            quote_token: QUOTE,
+            amount: U256::from(50),
+            price: U256::from(300),
+            is_buy,
+        }
+    }
+
+    #[test]
+    fn buyers_deposit_the_quote_amount_and_sellers_the_base_amount() {
+        assert_eq!(order(true).deposit(&pair()), (QUOTE, U256::from(150)));
+        assert_eq!(order(false).deposit(&pair()), (BASE, U256::from(50)));
+    }
+
+    #[test]
+    fn a_wallet_holding_the_deposit_withdraws_nothing() {
+        let shortfall = withdrawal_for(QUOTE, U256::from(150), U256::from(200), U256::zero()).unwrap();
+        assert_eq!(shortfall, U256::zero());
+    }
+
+    #[test]
+    fn a_short_wallet_withdraws_exactly_the_shortfall() {
+        let shortfall = withdrawal_for(QUOTE, U256::from(150), U256::from(100), U256::from(1_000)).unwrap();
+        assert_eq!(shortfall, U256::from(50));
+    }
+
+    #[test]
+    fn a_dex_balance_below_the_shortfall_is_refused() {
+        let error = withdrawal_for(QUOTE, U256::from(150), U256::from(100), U256::from(49)).unwrap_err();
+        assert_eq!(error.category(), "input");
+    }
+}
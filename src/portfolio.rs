@@ -0,0 +1,197 @@
+use crate::indexer::EventStore;
+use crate::output::decimal;
+use ethers::types::{Address, U256};
+use serde::Serialize;
+use std::collections::BTreeMap;
+
+/// Average-cost position in one pair, in raw token units and contract price units
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct PositionTracker {
+    /// Net base amount: positive long, negative short
+    pub position: f64,
+    /// Average entry price of the open position
+    pub average_price: f64,
+    /// Realized PnL in price units times base amount (divide by price precision for quote units)
+    pub realized: f64,
+    pub fills: usize,
+    pub bought: f64,
+    pub sold: f64,
+    /// Trading fees paid, in price units times base amount like `realized`
+    pub fees: f64,
+}
+
+impl PositionTracker {
+    /// Apply a fill of `amount` base at `price`
+    pub fn fill(&mut self, is_buy: bool, amount: f64, price: f64) {
+        let signed = if is_buy { amount } else { -amount };
+        self.fills += 1;
+        if is_buy {
+            self.bought += amount;
+        } else {
+            self.sold += amount;
+        }
+
+        if self.position == 0.0 || self.position.signum() == signed.signum() {
+            // Adding to the position moves the average entry
+            let total = self.position.abs() + amount;
+            self.average_price = (self.average_price * self.position.abs() + price * amount) / total;
+            self.position += signed;
+            return;
+        }
+
+        // Reducing (and possibly flipping) the position realizes PnL on the closed part
+        let closed = amount.min(self.position.abs());
+        let direction = self.position.signum();
+        self.realized += (price - self.average_price) * closed * direction;
+        self.position += signed;
+
+        if self.position == 0.0 {
+            self.average_price = 0.0;
+        } else if self.position.signum() != direction {
+            self.average_price = price;
+        }
+    }
+
+    /// Apply a limit match the way the contract settles it: the buyer receives `matched`
+    /// minus `fee` base, the seller gives up `matched` but is paid for the post-fee amount.
+    /// Fees are folded into the effective price so PnL is net of them.
+    pub fn trade(&mut self, is_buy: bool, matched: f64, fee: f64, price: f64) {
+        let net = matched - fee;
+        if matched <= 0.0 || net <= 0.0 {
+            return;
+        }
+        self.fees += fee * price;
+        if is_buy {
+            self.fill(true, net, price * matched / net);
+        } else {
+            self.fill(false, matched, price * net / matched);
+        }
+    }
+
+    /// Mark-to-market PnL of the open position at `mark`
+    pub fn unrealized(&self, mark: f64) -> f64 {
+        (mark - self.average_price) * self.position
+    }
+}
+
+/// Per-pair PnL derived from indexed fills
+#[derive(Debug, Clone, Serialize)]
+pub struct PairPnl {
+    pub base_token: Address,
+    pub quote_token: Address,
+    pub position: f64,
+    pub average_price: f64,
+    pub mark_price: Option<f64>,
+    /// Realized and unrealized PnL in quote token units, net of trading fees
+    pub realized_pnl: f64,
+    pub unrealized_pnl: Option<f64>,
+    /// Trading fees paid, in quote token units
+    pub fees: f64,
+    pub fills: usize,
+}
+
+/// Wallet and DEX balances for one token
+#[derive(Debug, Clone, Serialize)]
+pub struct TokenHolding {
+    pub token: Address,
+    #[serde(serialize_with = "decimal")]
+    pub wallet_balance: U256,
+    #[serde(serialize_with = "decimal")]
+    pub dex_balance: U256,
+    pub open_orders: usize,
+}
+
+/// Replay `trader`'s indexed fills into one tracker per (base, quote) pair
+pub fn positions(store: &EventStore, trader: Address) -> BTreeMap<(Address, Address), PositionTracker> {
+    let mut fills = store.fills_for(trader);
+    fills.sort_by_key(|(trade, _)| trade.block);
+
+    let mut positions: BTreeMap<(Address, Address), PositionTracker> = BTreeMap::new();
+    for (trade, is_buy) in fills {
+        positions
+            .entry((trade.base_token, trade.quote_token))
+            .or_default()
+            .trade(is_buy, u256_to_f64(trade.matched()), u256_to_f64(trade.fee), u256_to_f64(trade.price));
+    }
+    positions
+}
+
+/// Replay `trader`'s indexed fills in one pair. Also returns the PnL realized from
+/// `since_block` on, in the same units as [`PositionTracker::realized`].
+pub fn pair_tracker(
+    store: &EventStore,
+    trader: Address,
+    base_token: Address,
+    quote_token: Address,
+    since_block: u64,
+) -> (PositionTracker, f64) {
+    let mut fills = store.fills_for(trader);
+    fills.retain(|(trade, _)| (trade.base_token, trade.quote_token) == (base_token, quote_token));
+    fills.sort_by_key(|(trade, _)| trade.block);
+
+    let mut tracker = PositionTracker::default();
+    let mut realized_before = None;
+    for (trade, is_buy) in fills {
+        if trade.block >= since_block && realized_before.is_none() {
+            realized_before = Some(tracker.realized);
+        }
+        tracker.trade(is_buy, u256_to_f64(trade.matched()), u256_to_f64(trade.fee), u256_to_f64(trade.price));
+    }
+    let since = tracker.realized - realized_before.unwrap_or(tracker.realized);
+    (tracker, since)
+}
+
+/// Net position in one pair from indexed fills, next to what the trader actually holds
+#[derive(Debug, Clone, Serialize)]
+pub struct PairPosition {
+    pub base_token: Address,
+    pub quote_token: Address,
+    /// Net base bought minus sold through the DEX, after fees
+    pub position: f64,
+    /// Average cost of `position` in contract price units, fees included
+    pub average_cost: f64,
+    /// Base held in the wallet plus the DEX balance, including deposits locked in open orders
+    #[serde(serialize_with = "decimal")]
+    pub base_inventory: U256,
+    #[serde(serialize_with = "decimal")]
+    pub quote_inventory: U256,
+    /// Mid of the best bid and ask, or whichever side exists
+    pub mark_price: Option<f64>,
+    /// Quote units, net of trading fees
+    pub realized_pnl: f64,
+    /// `position` marked to `mark_price`, in quote units
+    pub unrealized_pnl: Option<f64>,
+    pub fills: usize,
+}
+
+impl PairPosition {
+    pub fn new(
+        base_token: Address,
+        quote_token: Address,
+        tracker: &PositionTracker,
+        price_precision: U256,
+        base_inventory: U256,
+        quote_inventory: U256,
+        mark_price: Option<f64>,
+    ) -> Self {
+        let precision = u256_to_f64(price_precision).max(1.0);
+        Self {
+            base_token,
+            quote_token,
+            position: tracker.position,
+            average_cost: tracker.average_price,
+            base_inventory,
+            quote_inventory,
+            mark_price,
+            realized_pnl: tracker.realized / precision,
+            unrealized_pnl: mark_price.map(|mark| tracker.unrealized(mark) / precision),
+            fills: tracker.fills,
+        }
+    }
+}
+
+/// Lossy conversion for PnL arithmetic on raw token amounts
+pub fn u256_to_f64(value: U256) -> f64 {
+    value.to_string().parse().unwrap_or(f64::MAX)
+}
+
@@ -0,0 +1,70 @@
+use crate::error::Result;
+use crate::pricefeed::PriceFeeds;
+use anyhow::anyhow;
+use std::str::FromStr;
+
+/// Where to get a price from: the native token's USD price for cost reports, or a
+/// reference price for the price bands
+#[derive(Debug, Clone, PartialEq)]
+pub enum PriceSource {
+    /// A fixed price given on the command line
+    Fixed(f64),
+    /// A JSON endpoint plus a JSON pointer to the price field, e.g.
+    /// `https://api.coingecko.com/api/v3/simple/price?ids=monad&vs_currencies=usd#/monad/usd`
+    Url { url: String, pointer: String },
+    /// A feed configured with `price-feed set`, e.g. `feed:MON-USDC`
+    Feed(String),
+}
+
+impl FromStr for PriceSource {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if let Ok(price) = s.parse::<f64>() {
+            return Ok(PriceSource::Fixed(price));
+        }
+
+        if s.starts_with("http://") || s.starts_with("https://") {
+            let (url, pointer) = s.split_once('#').unwrap_or((s, ""));
+            return Ok(PriceSource::Url {
+                url: url.to_string(),
+                pointer: pointer.to_string(),
+            });
+        }
+
+        if let Some(symbol) = s.strip_prefix("feed:") {
+            return Ok(PriceSource::Feed(symbol.to_string()));
+        }
+
+        Err(format!("expected a number, an http(s) URL or feed:<SYMBOL>, got '{}'", s))
+    }
+}
+
+impl PriceSource {
+    /// Fetch the current price
+    pub async fn fetch(&self) -> Result<f64> {
+        match self {
+            PriceSource::Fixed(price) => Ok(*price),
+            PriceSource::Url { url, pointer } => fetch_url(url, pointer).await,
+            PriceSource::Feed(symbol) => Ok(PriceFeeds::load()?.price(symbol).await?.price),
+        }
+    }
+}
+
+/// Read the number at `pointer` in the JSON served at `url`
+pub(crate) async fn fetch_url(url: &str, pointer: &str) -> Result<f64> {
+    let json: serde_json::Value = reqwest::get(url)
+        .await?
+        .error_for_status()?
+        .json()
+        .await?;
+
+    let value = json
+        .pointer(pointer)
+        .ok_or_else(|| anyhow!("No value at '{}' in price response", pointer))?;
+
+    value
+        .as_f64()
+        .or_else(|| value.as_str().and_then(|s| s.parse().ok()))
+        .ok_or_else(|| anyhow!("Price at '{}' is not a number: {}", pointer, value).into())
+}
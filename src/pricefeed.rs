@@ -0,0 +1,309 @@
+use crate::bindings::ChainlinkAggregator;
+use crate::bot::Executor;
+use crate::error::{DexError, Result};
+use crate::expiry;
+use crate::price::{self, PriceSource};
+use crate::rpc;
+use crate::strategy::{BookView, Fill, Intent, OpenOrder, PendingActivity};
+use anyhow::{anyhow, Context};
+use async_trait::async_trait;
+use ethers::providers::Provider;
+use ethers::types::{Address, U256};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+use std::str::FromStr;
+use std::sync::Arc;
+use std::time::SystemTime;
+use tracing::warn;
+
+const PRICE_FEEDS_PATH: &str = "config/price_feeds.json";
+
+const PYTH_HERMES_URL: &str = "https://hermes.pyth.network/v2/updates/price/latest";
+const BINANCE_TICKER_URL: &str = "https://api.binance.com/api/v3/ticker/price";
+const COINBASE_PRODUCTS_URL: &str = "https://api.exchange.coinbase.com/products";
+
+/// One place a feed can read its price from, written `<kind>:<id>`
+#[derive(Debug, Clone, PartialEq)]
+pub enum FeedSource {
+    /// Pyth price feed id, read from the Hermes REST API (`pyth:0xff61...`)
+    Pyth { price_id: String },
+    /// Chainlink aggregator contract on the feed's `rpc_url` (`chainlink:0x5f4e...`)
+    Chainlink { aggregator: Address },
+    /// Binance spot ticker (`binance:ETHUSDT`)
+    Binance { symbol: String },
+    /// Coinbase Exchange product ticker (`coinbase:ETH-USD`)
+    Coinbase { product: String },
+    /// A fixed number or a JSON endpoint with a pointer, as for `--usd-price-source`.
+    /// These carry no publish time, so they are never stale.
+    Other(PriceSource),
+}
+
+impl FromStr for FeedSource {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if let Some(price_id) = s.strip_prefix("pyth:") {
+            return Ok(FeedSource::Pyth { price_id: price_id.trim_start_matches("0x").to_string() });
+        }
+        if let Some(aggregator) = s.strip_prefix("chainlink:") {
+            let aggregator = aggregator
+                .parse()
+                .map_err(|_| format!("invalid Chainlink aggregator address '{}'", aggregator))?;
+            return Ok(FeedSource::Chainlink { aggregator });
+        }
+        if let Some(symbol) = s.strip_prefix("binance:") {
+            return Ok(FeedSource::Binance { symbol: symbol.to_uppercase() });
+        }
+        if let Some(product) = s.strip_prefix("coinbase:") {
+            return Ok(FeedSource::Coinbase { product: product.to_uppercase() });
+        }
+        match s.parse::<PriceSource>()? {
+            PriceSource::Feed(_) => Err(format!("a feed can't read from another feed ('{}')", s)),
+            source => Ok(FeedSource::Other(source)),
+        }
+    }
+}
+
+impl FeedSource {
+    /// Current price and, when the source reports one, the unix time it was published
+    async fn fetch(&self, rpc_url: Option<&str>) -> Result<(f64, Option<u64>)> {
+        match self {
+            FeedSource::Pyth { price_id } => {
+                let url = format!("{}?ids[]={}&parsed=true", PYTH_HERMES_URL, price_id);
+                let json = get_json(&url).await?;
+                let quote = json
+                    .pointer("/parsed/0/price")
+                    .ok_or_else(|| anyhow!("Pyth returned no price for {}", price_id))?;
+                let price = json_number(quote, "/price")?;
+                let expo = quote.pointer("/expo").and_then(|e| e.as_i64()).unwrap_or_default();
+                let published = quote.pointer("/publish_time").and_then(|t| t.as_u64());
+                Ok((price * 10f64.powi(expo as i32), published))
+            }
+            FeedSource::Chainlink { aggregator } => {
+                let rpc_url = rpc_url.ok_or_else(|| DexError::Config("Chainlink sources need the feed's rpc_url".to_string()))?;
+                let provider = Provider::new(rpc::http(rpc_url)?);
+                let aggregator = ChainlinkAggregator::new(*aggregator, Arc::new(provider));
+                let decimals = aggregator.decimals().call().await?;
+                let (_, answer, _, updated_at, _) = aggregator.latest_round_data().call().await?;
+                let answer: f64 = answer.to_string().parse().context("Chainlink answer is not a number")?;
+                Ok((answer / 10f64.powi(decimals as i32), Some(updated_at.as_u64())))
+            }
+            FeedSource::Binance { symbol } => {
+                let json = get_json(&format!("{}?symbol={}", BINANCE_TICKER_URL, symbol)).await?;
+                Ok((json_number(&json, "/price")?, None))
+            }
+            FeedSource::Coinbase { product } => {
+                let json = get_json(&format!("{}/{}/ticker", COINBASE_PRODUCTS_URL, product)).await?;
+                let published = json
+                    .pointer("/time")
+                    .and_then(|t| t.as_str())
+                    .and_then(|t| humantime::parse_rfc3339_weak(t).ok())
+                    .map(expiry::unix_secs);
+                Ok((json_number(&json, "/price")?, published))
+            }
+            FeedSource::Other(PriceSource::Fixed(price)) => Ok((*price, None)),
+            FeedSource::Other(PriceSource::Url { url, pointer }) => Ok((price::fetch_url(url, pointer).await?, None)),
+            FeedSource::Other(PriceSource::Feed(symbol)) => Err(DexError::Config(format!("A feed can't read from feed:{}", symbol))),
+        }
+    }
+}
+
+/// Exchanges such as Coinbase refuse requests without a user agent
+async fn get_json(url: &str) -> Result<serde_json::Value> {
+    let client = reqwest::Client::builder()
+        .user_agent(concat!("monad-app/", env!("CARGO_PKG_VERSION")))
+        .build()?;
+    Ok(client.get(url).send().await?.error_for_status()?.json().await?)
+}
+
+/// Numbers arrive as JSON numbers or as decimal strings depending on the API
+fn json_number(json: &serde_json::Value, pointer: &str) -> Result<f64> {
+    let value = json
+        .pointer(pointer)
+        .ok_or_else(|| anyhow!("No value at '{}' in price response", pointer))?;
+    value
+        .as_f64()
+        .or_else(|| value.as_str().and_then(|s| s.parse().ok()))
+        .ok_or_else(|| anyhow!("Price at '{}' is not a number: {}", pointer, value).into())
+}
+
+/// A named price assembled from one or more sources, tried in order
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Feed {
+    /// `pyth:<id>`, `chainlink:<aggregator>`, `binance:<symbol>`, `coinbase:<product>`,
+    /// a number, or a JSON URL with a pointer fragment
+    pub sources: Vec<String>,
+    /// Skip a source whose price was published longer ago than this
+    #[serde(default = "default_max_age_secs")]
+    pub max_age_secs: u64,
+    /// Multiplier from the source's price to DEX price units, e.g. the pair's price precision
+    #[serde(default = "default_scale")]
+    pub scale: f64,
+    /// Node to read Chainlink aggregators from
+    #[serde(default)]
+    pub rpc_url: Option<String>,
+}
+
+fn default_max_age_secs() -> u64 {
+    60
+}
+
+fn default_scale() -> f64 {
+    1.0
+}
+
+impl Feed {
+    pub fn parse_sources(&self) -> Result<Vec<FeedSource>> {
+        if self.sources.is_empty() {
+            return Err(DexError::Config("A feed needs at least one source".to_string()));
+        }
+        self.sources
+            .iter()
+            .map(|source| source.parse().map_err(|e| DexError::Config(format!("Invalid price source: {}", e))))
+            .collect()
+    }
+
+    /// Price from the first source that answers with a fresh enough price
+    pub async fn fetch(&self, symbol: &str) -> Result<FeedPrice> {
+        let now = expiry::unix_secs(SystemTime::now());
+        let mut errors = Vec::new();
+        for (name, source) in self.sources.iter().zip(self.parse_sources()?) {
+            let (price, published_at) = match source.fetch(self.rpc_url.as_deref()).await {
+                Ok(quote) => quote,
+                Err(e) => {
+                    warn!("Price source {} for {} failed: {}", name, symbol, e);
+                    errors.push(format!("{}: {}", name, e));
+                    continue;
+                }
+            };
+            let age_secs = published_at.map(|published| now.saturating_sub(published));
+            if let Some(age) = age_secs.filter(|age| *age > self.max_age_secs) {
+                warn!("Price source {} for {} is {}s old, past the {}s limit", name, symbol, age, self.max_age_secs);
+                errors.push(format!("{}: stale ({}s old)", name, age));
+                continue;
+            }
+            if !price.is_finite() || price <= 0.0 {
+                errors.push(format!("{}: bad price {}", name, price));
+                continue;
+            }
+            return Ok(FeedPrice {
+                symbol: symbol.to_string(),
+                source: name.clone(),
+                price: price * self.scale,
+                published_at,
+                age_secs,
+            });
+        }
+        Err(anyhow!("No fresh price for {}: {}", symbol, errors.join("; ")).into())
+    }
+}
+
+/// A price read from a feed, already scaled to DEX price units
+#[derive(Debug, Clone, Serialize)]
+pub struct FeedPrice {
+    pub symbol: String,
+    /// The source that answered
+    pub source: String,
+    pub price: f64,
+    /// Unix time the source published the price; `None` for sources without one
+    pub published_at: Option<u64>,
+    pub age_secs: Option<u64>,
+}
+
+/// Configured feeds by symbol, stored in `config/price_feeds.json`. Symbols are upper case.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct PriceFeeds {
+    #[serde(default)]
+    pub feeds: BTreeMap<String, Feed>,
+}
+
+impl PriceFeeds {
+    pub fn load() -> Result<Self> {
+        if !Path::new(PRICE_FEEDS_PATH).exists() {
+            return Ok(Self::default());
+        }
+        let content = fs::read_to_string(PRICE_FEEDS_PATH)
+            .with_context(|| format!("Failed to read {}", PRICE_FEEDS_PATH))?;
+        Ok(serde_json::from_str(&content)?)
+    }
+
+    pub fn save(&self) -> Result<()> {
+        fs::create_dir_all("config")?;
+        fs::write(PRICE_FEEDS_PATH, serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+
+    pub fn set(&mut self, symbol: &str, feed: Feed) -> Result<()> {
+        let sources = feed.parse_sources()?;
+        if feed.rpc_url.is_none() && sources.iter().any(|source| matches!(source, FeedSource::Chainlink { .. })) {
+            return Err(DexError::Input("Chainlink sources need --rpc-url".to_string()));
+        }
+        self.feeds.insert(symbol.to_uppercase(), feed);
+        Ok(())
+    }
+
+    pub fn remove(&mut self, symbol: &str) -> Option<Feed> {
+        self.feeds.remove(&symbol.to_uppercase())
+    }
+
+    pub fn get(&self, symbol: &str) -> Result<&Feed> {
+        self.feeds
+            .get(&symbol.to_uppercase())
+            .ok_or_else(|| DexError::Config(format!("No price feed '{}' (add one with `price-feed set`)", symbol)))
+    }
+
+    pub async fn price(&self, symbol: &str) -> Result<FeedPrice> {
+        self.get(symbol)?.fetch(&symbol.to_uppercase()).await
+    }
+}
+
+/// Attaches a feed's price to every book the strategy sees, so makers quote around it
+/// instead of the book mid. A feed with no fresh price fails the iteration rather than
+/// letting quotes fall back to the book.
+pub struct ReferenceExecutor<E> {
+    inner: E,
+    symbol: String,
+    feed: Feed,
+}
+
+impl<E: Executor> ReferenceExecutor<E> {
+    pub fn new(inner: E, symbol: &str) -> Result<Self> {
+        let feed = PriceFeeds::load()?.get(symbol)?.clone();
+        Ok(Self { inner, symbol: symbol.to_uppercase(), feed })
+    }
+}
+
+#[async_trait]
+impl<E: Executor> Executor for ReferenceExecutor<E> {
+    async fn book(&mut self) -> Result<BookView> {
+        let mut book = self.inner.book().await?;
+        book.reference = Some(self.feed.fetch(&self.symbol).await?.price);
+        Ok(book)
+    }
+
+    async fn poll_fills(&mut self) -> Result<Vec<Fill>> {
+        self.inner.poll_fills().await
+    }
+
+    async fn open_orders(&mut self) -> Result<Vec<OpenOrder>> {
+        self.inner.open_orders().await
+    }
+
+    async fn poll_pending(&mut self) -> Result<Vec<PendingActivity>> {
+        self.inner.poll_pending().await
+    }
+
+    async fn place(&mut self, price: U256, amount: U256, is_buy: bool) -> Result<u64> {
+        self.inner.place(price, amount, is_buy).await
+    }
+
+    async fn cancel(&mut self, order_id: u64) -> Result<()> {
+        self.inner.cancel(order_id).await
+    }
+
+    async fn execute(&mut self, intents: &[Intent]) -> Vec<Result<Option<u64>>> {
+        self.inner.execute(intents).await
+    }
+}
@@ -0,0 +1,161 @@
+use crate::error::{DexError, Result};
+use crate::rpc;
+use anyhow::Context;
+use ethers::{
+    providers::Middleware,
+    types::{Address, H256},
+};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+
+const PROXY_REGISTRY_PATH: &str = "config/proxies.json";
+
+/// EIP-1967 slot holding the implementation address: `keccak256("eip1967.proxy.implementation") - 1`
+pub const IMPLEMENTATION_SLOT: &str = "0x360894a13ba1a3210667c828492db98dca3e2076cc3735a920a3ca505d382bbc";
+
+/// EIP-1967 slot holding the admin address: `keccak256("eip1967.proxy.admin") - 1`
+pub const ADMIN_SLOT: &str = "0xb53127684a568b3173ae13b9f8a6016e243e63b6e8ee1178d6a717850b5d6103";
+
+async fn read_address_slot<M: Middleware + 'static>(client: &M, proxy: Address, slot: &str) -> Result<Address> {
+    let slot: H256 = slot.parse().map_err(|e| DexError::Input(format!("Invalid storage slot '{}': {}", slot, e)))?;
+    let value = client
+        .get_storage_at(proxy, slot, None)
+        .await
+        .map_err(|e| DexError::Rpc(format!("Failed to read storage of {:?}: {}", proxy, e)))?;
+    Ok(Address::from_slice(&value.as_bytes()[12..]))
+}
+
+/// Current implementation of an EIP-1967 proxy
+pub async fn implementation<M: Middleware + 'static>(client: &M, proxy: Address) -> Result<Address> {
+    let implementation = read_address_slot(client, proxy, IMPLEMENTATION_SLOT).await?;
+    if implementation.is_zero() {
+        return Err(DexError::Input(format!("{:?} is not an EIP-1967 proxy", proxy)));
+    }
+    Ok(implementation)
+}
+
+/// ProxyAdmin contract allowed to upgrade a transparent proxy
+pub async fn admin<M: Middleware + 'static>(client: &M, proxy: Address) -> Result<Address> {
+    read_address_slot(client, proxy, ADMIN_SLOT).await
+}
+
+/// One state variable from a forge `storageLayout`, with its type spelled out in full
+/// so layouts from different compilations (and thus different AST ids) compare equal
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StorageSlot {
+    pub label: String,
+    pub slot: String,
+    pub offset: u64,
+    pub kind: String,
+}
+
+/// Storage layout of a contract as compiled with `extra_output = ["storageLayout"]`
+pub fn storage_layout(contract: &str) -> Result<Vec<StorageSlot>> {
+    let path = format!("out/{0}.sol/{0}.json", contract);
+    let content = fs::read_to_string(&path).with_context(|| format!("Failed to read {} (run `monad-deploy build`)", path))?;
+    let json: Value = serde_json::from_str(&content)?;
+    let layout = &json["storageLayout"];
+    let storage = layout["storage"]
+        .as_array()
+        .ok_or_else(|| DexError::Config(format!("{} has no storage layout; enable `extra_output = [\"storageLayout\"]` in foundry.toml", path)))?;
+
+    storage
+        .iter()
+        .map(|entry| {
+            Ok(StorageSlot {
+                label: entry["label"].as_str().unwrap_or_default().to_string(),
+                slot: entry["slot"].as_str().unwrap_or_default().to_string(),
+                offset: entry["offset"].as_u64().unwrap_or_default(),
+                kind: describe_type(entry["type"].as_str().unwrap_or_default(), &layout["types"]),
+            })
+        })
+        .collect()
+}
+
+fn describe_type(id: &str, types: &Value) -> String {
+    let info = &types[id];
+    let label = info["label"].as_str().unwrap_or(id);
+    if let Some(members) = info["members"].as_array() {
+        let members: Vec<String> = members
+            .iter()
+            .map(|m| {
+                format!(
+                    "{} {}@{}+{}",
+                    describe_type(m["type"].as_str().unwrap_or_default(), types),
+                    m["label"].as_str().unwrap_or_default(),
+                    m["slot"].as_str().unwrap_or_default(),
+                    m["offset"].as_u64().unwrap_or_default()
+                )
+            })
+            .collect();
+        return format!("{} {{{}}}", label, members.join(", "));
+    }
+    if let (Some(key), Some(value)) = (info["key"].as_str(), info["value"].as_str()) {
+        return format!("mapping({} => {})", describe_type(key, types), describe_type(value, types));
+    }
+    if let Some(base) = info["base"].as_str() {
+        if info["encoding"].as_str() == Some("dynamic_array") {
+            return format!("{}[]", describe_type(base, types));
+        }
+    }
+    format!("{} ({} bytes)", label, info["numberOfBytes"].as_str().unwrap_or("?"))
+}
+
+/// Problems that make `new` unsafe to upgrade to from `old`. Every existing variable must
+/// stay at its slot and offset with the same type; new variables may only be appended.
+/// Renames are allowed and returned as warnings.
+pub fn check_layout(old: &[StorageSlot], new: &[StorageSlot]) -> (Vec<String>, Vec<String>) {
+    let mut errors = Vec::new();
+    let mut warnings = Vec::new();
+    for var in old {
+        match new.iter().find(|n| n.slot == var.slot && n.offset == var.offset) {
+            None => errors.push(format!("'{}' at slot {}+{} was removed or moved", var.label, var.slot, var.offset)),
+            Some(n) if n.kind != var.kind => errors.push(format!(
+                "'{}' at slot {}+{} changed type from {} to {}",
+                var.label, var.slot, var.offset, var.kind, n.kind
+            )),
+            Some(n) if n.label != var.label => {
+                warnings.push(format!("'{}' at slot {}+{} was renamed to '{}'", var.label, var.slot, var.offset, n.label))
+            }
+            Some(_) => {}
+        }
+    }
+    (errors, warnings)
+}
+
+/// A proxy deployed by `monad-deploy`, with the layout of its current implementation
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProxyRecord {
+    pub contract: String,
+    pub implementation: Address,
+    pub admin: Address,
+    pub storage_layout: Vec<StorageSlot>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct ProxyRegistry {
+    pub proxies: BTreeMap<Address, ProxyRecord>,
+}
+
+impl ProxyRegistry {
+    pub fn load() -> Result<Self> {
+        if !Path::new(PROXY_REGISTRY_PATH).exists() {
+            return Ok(Self::default());
+        }
+        let content = fs::read_to_string(PROXY_REGISTRY_PATH)
+            .with_context(|| format!("Failed to read {}", PROXY_REGISTRY_PATH))?;
+        Ok(serde_json::from_str(&content)?)
+    }
+
+    pub fn save(&self) -> Result<()> {
+        if rpc::is_fork() {
+            return Ok(());
+        }
+        fs::create_dir_all("config")?;
+        fs::write(PROXY_REGISTRY_PATH, serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+}
@@ -0,0 +1,595 @@
+use crate::error::{DexError, Result};
+use crate::expiry::unix_secs;
+use crate::indexer::{Cancellation, EventStore, PlacedOrder, Trade};
+#[cfg(feature = "redis")]
+use crate::marketdata::RedisPublisher;
+use crate::marketdata::RedisConfig;
+use crate::output;
+use crate::strategy::Level;
+use anyhow::anyhow;
+use async_trait::async_trait;
+use ethers::types::{Address, H256, U256};
+#[cfg(feature = "kafka")]
+use rskafka::client::{
+    partition::{Compression, PartitionClient, UnknownTopicHandling},
+    Client, ClientBuilder,
+};
+#[cfg(feature = "kafka")]
+use rskafka::record::Record;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet, HashMap, VecDeque};
+#[cfg(feature = "kafka")]
+use std::sync::Arc;
+use std::time::SystemTime;
+#[cfg(feature = "kafka")]
+use tokio::sync::Mutex;
+use tracing::warn;
+
+/// Where the daemon's indexer publishes the events it indexes (`[publish]` in its config)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PublishConfig {
+    #[serde(default)]
+    pub kafka: Option<KafkaConfig>,
+    #[serde(default)]
+    pub nats: Option<NatsConfig>,
+    /// Also keeps the latest book and ticker of every pair, for bots sharing one feed
+    #[serde(default)]
+    pub redis: Option<RedisConfig>,
+    /// Events go to `<prefix>.orders`, `<prefix>.trades` and `<prefix>.book`
+    #[serde(default = "default_topic_prefix")]
+    pub topic_prefix: String,
+}
+
+fn default_topic_prefix() -> String {
+    "monad-dex".to_string()
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct KafkaConfig {
+    /// Bootstrap brokers, e.g. ["localhost:9092"]
+    pub brokers: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NatsConfig {
+    /// Server URL, e.g. nats://localhost:4222
+    pub url: String,
+}
+
+impl PublishConfig {
+    /// Connect to every configured broker; each needs the cargo feature of the same name
+    pub async fn publishers(&self) -> Result<Vec<Box<dyn Publisher>>> {
+        #[cfg_attr(not(any(feature = "kafka", feature = "nats", feature = "redis")), allow(unused_mut))]
+        let mut publishers: Vec<Box<dyn Publisher>> = Vec::new();
+        #[cfg(feature = "kafka")]
+        if let Some(kafka) = &self.kafka {
+            publishers.push(Box::new(KafkaPublisher::connect(kafka).await?));
+        }
+        #[cfg(not(feature = "kafka"))]
+        if self.kafka.is_some() {
+            return Err(not_built("kafka"));
+        }
+        #[cfg(feature = "nats")]
+        if let Some(nats) = &self.nats {
+            publishers.push(Box::new(NatsPublisher::connect(nats).await?));
+        }
+        #[cfg(not(feature = "nats"))]
+        if self.nats.is_some() {
+            return Err(not_built("nats"));
+        }
+        #[cfg(feature = "redis")]
+        if let Some(redis) = &self.redis {
+            publishers.push(Box::new(RedisPublisher::connect(redis, &self.topic_prefix).await?));
+        }
+        #[cfg(not(feature = "redis"))]
+        if self.redis.is_some() {
+            return Err(not_built("redis"));
+        }
+        Ok(publishers)
+    }
+
+    pub fn topic(&self, stream: Stream) -> String {
+        format!("{}.{}", self.topic_prefix, stream.name())
+    }
+}
+
+/// Error for a broker configured in a build without its cargo feature
+pub fn not_built(feature: &str) -> DexError {
+    DexError::Config(format!("[publish] {} needs a build with `--features {}`", feature, feature))
+}
+
+/// The topics events are split over
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Stream {
+    Orders,
+    Trades,
+    Book,
+}
+
+impl Stream {
+    pub fn name(self) -> &'static str {
+        match self {
+            Stream::Orders => "orders",
+            Stream::Trades => "trades",
+            Stream::Book => "book",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Side {
+    Buy,
+    Sell,
+}
+
+/// An indexed DEX event in the shape published to consumers; amounts and prices are
+/// decimal strings in the token's smallest unit
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum MarketEvent {
+    OrderPlaced {
+        dex: Address,
+        #[serde(serialize_with = "output::decimal")]
+        order_id: U256,
+        trader: Address,
+        base_token: Address,
+        quote_token: Address,
+        side: Side,
+        #[serde(serialize_with = "output::decimal")]
+        amount: U256,
+        #[serde(serialize_with = "output::decimal")]
+        price: U256,
+        block: u64,
+        tx_hash: H256,
+    },
+    OrderCancelled {
+        dex: Address,
+        #[serde(serialize_with = "output::decimal")]
+        order_id: U256,
+        trader: Address,
+        /// The order's pair; unknown when it was placed before the index starts
+        base_token: Option<Address>,
+        quote_token: Option<Address>,
+        block: u64,
+        tx_hash: H256,
+    },
+    Trade {
+        dex: Address,
+        base_token: Address,
+        quote_token: Address,
+        #[serde(serialize_with = "output::decimal")]
+        buy_order_id: U256,
+        #[serde(serialize_with = "output::decimal")]
+        sell_order_id: U256,
+        /// Base amount the buyer received, after the fee
+        #[serde(serialize_with = "output::decimal")]
+        amount: U256,
+        #[serde(serialize_with = "output::decimal")]
+        price: U256,
+        #[serde(serialize_with = "output::decimal")]
+        fee: U256,
+        block: u64,
+        tx_hash: H256,
+    },
+    /// A price level changed; `size` is the level's new total, zero once it is gone
+    BookDelta {
+        dex: Address,
+        base_token: Address,
+        quote_token: Address,
+        side: Side,
+        #[serde(serialize_with = "output::decimal")]
+        price: U256,
+        #[serde(serialize_with = "output::decimal")]
+        size: U256,
+        block: u64,
+    },
+}
+
+impl MarketEvent {
+    pub fn stream(&self) -> Stream {
+        match self {
+            MarketEvent::OrderPlaced { .. } | MarketEvent::OrderCancelled { .. } => Stream::Orders,
+            MarketEvent::Trade { .. } => Stream::Trades,
+            MarketEvent::BookDelta { .. } => Stream::Book,
+        }
+    }
+
+    /// Message key: the pair, for consumers that shard by pair. Only a cancellation of an
+    /// order placed before the index starts has no known pair; it is keyed by order ID.
+    pub fn key(&self) -> String {
+        match self {
+            MarketEvent::OrderPlaced { base_token, quote_token, .. }
+            | MarketEvent::Trade { base_token, quote_token, .. }
+            | MarketEvent::BookDelta { base_token, quote_token, .. } => format!("{:?}/{:?}", base_token, quote_token),
+            MarketEvent::OrderCancelled { base_token: Some(base_token), quote_token: Some(quote_token), .. } => {
+                format!("{:?}/{:?}", base_token, quote_token)
+            }
+            MarketEvent::OrderCancelled { order_id, .. } => order_id.to_string(),
+        }
+    }
+}
+
+type LevelKey = (Address, Address, Side, U256);
+
+/// One block's placements, trades and cancellations
+type BlockEvents<'a> = (Vec<&'a PlacedOrder>, Vec<&'a Trade>, Vec<&'a Cancellation>);
+
+/// The latest state of the pairs that changed, as of the block the index is synced to
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MarketSnapshot {
+    pub dex: Address,
+    pub block: u64,
+    /// Unix time the snapshot was taken
+    pub updated_at: u64,
+    pub books: Vec<PairBook>,
+    pub tickers: Vec<PairTicker>,
+}
+
+/// A pair's book rebuilt from indexed events
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PairBook {
+    pub base_token: Address,
+    pub quote_token: Address,
+    /// Bids, best (highest) first
+    pub bids: Vec<Level>,
+    /// Asks, best (lowest) first
+    pub asks: Vec<Level>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PairTicker {
+    pub base_token: Address,
+    pub quote_token: Address,
+    pub last_price: Option<U256>,
+    /// Base amount the last trade matched, fee included
+    pub last_amount: Option<U256>,
+    /// Block of the last trade
+    pub last_block: Option<u64>,
+    pub best_bid: Option<U256>,
+    pub best_ask: Option<U256>,
+}
+
+/// Turns what the indexer appends to an [`EventStore`] into [`MarketEvent`]s, keeping
+/// the book's levels to derive deltas from
+#[derive(Debug, Default)]
+pub struct EventFeed {
+    orders: usize,
+    trades: usize,
+    cancellations: usize,
+    /// Unfilled amount and level of every resting order
+    open: HashMap<U256, (LevelKey, U256)>,
+    levels: HashMap<LevelKey, U256>,
+    /// Price, matched amount and block of each pair's last trade
+    last_trades: HashMap<(Address, Address), (U256, U256, u64)>,
+    /// Pairs changed since the last [`EventFeed::snapshot`] was stored
+    touched: BTreeSet<(Address, Address)>,
+}
+
+impl EventFeed {
+    /// A feed that only reports what is indexed after `store`'s current contents
+    pub fn new(store: &EventStore) -> Self {
+        let mut feed = Self::default();
+        feed.drain(store);
+        feed
+    }
+
+    /// Events indexed since the last call, block by block: placements, then trades, then
+    /// cancellations within a block, each followed by the book deltas it caused
+    pub fn drain(&mut self, store: &EventStore) -> Vec<MarketEvent> {
+        let mut blocks: BTreeMap<u64, BlockEvents> = BTreeMap::new();
+        for order in store.orders.get(self.orders..).unwrap_or_default() {
+            blocks.entry(order.block).or_default().0.push(order);
+        }
+        for trade in store.trades.get(self.trades..).unwrap_or_default() {
+            blocks.entry(trade.block).or_default().1.push(trade);
+        }
+        for cancellation in store.cancellations.get(self.cancellations..).unwrap_or_default() {
+            blocks.entry(cancellation.block).or_default().2.push(cancellation);
+        }
+        (self.orders, self.trades, self.cancellations) = (store.orders.len(), store.trades.len(), store.cancellations.len());
+
+        let dex = store.dex;
+        let mut events = Vec::new();
+        for (block, (orders, trades, cancellations)) in blocks {
+            for order in orders {
+                let side = if order.is_buy { Side::Buy } else { Side::Sell };
+                events.push(MarketEvent::OrderPlaced {
+                    dex,
+                    order_id: order.order_id,
+                    trader: order.trader,
+                    base_token: order.base_token,
+                    quote_token: order.quote_token,
+                    side,
+                    amount: order.amount,
+                    price: order.price,
+                    block,
+                    tx_hash: order.tx_hash,
+                });
+                let level = (order.base_token, order.quote_token, side, order.price);
+                self.open.insert(order.order_id, (level, order.amount));
+                events.push(self.change(dex, level, order.amount, true, block));
+            }
+            for trade in trades {
+                events.push(MarketEvent::Trade {
+                    dex,
+                    base_token: trade.base_token,
+                    quote_token: trade.quote_token,
+                    buy_order_id: trade.buy_order_id,
+                    sell_order_id: trade.sell_order_id,
+                    amount: trade.amount,
+                    price: trade.price,
+                    fee: trade.fee,
+                    block,
+                    tx_hash: trade.tx_hash,
+                });
+                let pair = (trade.base_token, trade.quote_token);
+                self.last_trades.insert(pair, (trade.price, trade.matched(), block));
+                self.touched.insert(pair);
+                for order_id in [trade.buy_order_id, trade.sell_order_id] {
+                    if let Some(delta) = self.consume(dex, order_id, Some(trade.matched()), block) {
+                        events.push(delta);
+                    }
+                }
+            }
+            for cancellation in cancellations {
+                let order = store.order(cancellation.order_id);
+                events.push(MarketEvent::OrderCancelled {
+                    dex,
+                    order_id: cancellation.order_id,
+                    trader: cancellation.trader,
+                    base_token: order.map(|order| order.base_token),
+                    quote_token: order.map(|order| order.quote_token),
+                    block,
+                    tx_hash: cancellation.tx_hash,
+                });
+                if let Some(delta) = self.consume(dex, cancellation.order_id, None, block) {
+                    events.push(delta);
+                }
+            }
+        }
+        events
+    }
+
+    /// Take `amount` (everything left with `None`) off a resting order
+    fn consume(&mut self, dex: Address, order_id: U256, amount: Option<U256>, block: u64) -> Option<MarketEvent> {
+        let (level, remaining) = self.open.get_mut(&order_id)?;
+        let (level, taken) = (*level, amount.unwrap_or(*remaining).min(*remaining));
+        *remaining -= taken;
+        if remaining.is_zero() {
+            self.open.remove(&order_id);
+        }
+        Some(self.change(dex, level, taken, false, block))
+    }
+
+    /// Books and tickers of the pairs changed since the last [`EventFeed::cached`]
+    pub fn snapshot(&self, dex: Address, block: u64) -> MarketSnapshot {
+        let mut books = Vec::new();
+        let mut tickers = Vec::new();
+        for &(base_token, quote_token) in &self.touched {
+            let side = |wanted: Side| {
+                let mut levels: Vec<Level> = self
+                    .levels
+                    .iter()
+                    .filter(|((base, quote, side, _), _)| (*base, *quote, *side) == (base_token, quote_token, wanted))
+                    .map(|((_, _, _, price), amount)| Level { price: *price, amount: *amount })
+                    .collect();
+                levels.sort_by_key(|level| level.price);
+                if wanted == Side::Buy {
+                    levels.reverse();
+                }
+                levels
+            };
+            let (bids, asks) = (side(Side::Buy), side(Side::Sell));
+            let last = self.last_trades.get(&(base_token, quote_token));
+            tickers.push(PairTicker {
+                base_token,
+                quote_token,
+                last_price: last.map(|(price, _, _)| *price),
+                last_amount: last.map(|(_, amount, _)| *amount),
+                last_block: last.map(|(_, _, block)| *block),
+                best_bid: bids.first().map(|level| level.price),
+                best_ask: asks.first().map(|level| level.price),
+            });
+            books.push(PairBook { base_token, quote_token, bids, asks });
+        }
+        MarketSnapshot { dex, block, updated_at: unix_secs(SystemTime::now()), books, tickers }
+    }
+
+    /// The last snapshot was stored; only pairs changed from here on go in the next
+    pub fn cached(&mut self) {
+        self.touched.clear();
+    }
+
+    fn change(&mut self, dex: Address, level: LevelKey, amount: U256, add: bool, block: u64) -> MarketEvent {
+        let size = self.levels.entry(level).or_default();
+        *size = if add { *size + amount } else { size.saturating_sub(amount) };
+        let size = *size;
+        if size.is_zero() {
+            self.levels.remove(&level);
+        }
+        let (base_token, quote_token, side, price) = level;
+        self.touched.insert((base_token, quote_token));
+        MarketEvent::BookDelta { dex, base_token, quote_token, side, price, size, block }
+    }
+}
+
+/// Events kept for a broker that is down, after which the oldest are dropped
+const MAX_PENDING: usize = 100_000;
+
+/// A broker and the events it hasn't accepted yet
+struct Sink {
+    publisher: Box<dyn Publisher>,
+    /// (topic, key, payload), oldest first
+    pending: VecDeque<(String, String, Vec<u8>)>,
+}
+
+impl Sink {
+    /// Send the pending events in order, stopping at the first the broker rejects
+    async fn flush(&mut self) -> Result<()> {
+        while let Some((topic, key, payload)) = self.pending.front() {
+            self.publisher.publish(topic, key, payload).await?;
+            self.pending.pop_front();
+        }
+        Ok(())
+    }
+}
+
+/// Publishes what the indexer adds to a store to every configured broker.
+///
+/// Each broker gets every event once it accepts it, in the order the events were
+/// indexed: events a broker rejects are kept for that broker alone and sent again,
+/// ahead of newer ones, on the next call. A broker that fails mid-send may have taken
+/// the event anyway, so consumers can see repeats. Past [`MAX_PENDING`] events for one
+/// broker the oldest are dropped.
+pub struct EventPublisher {
+    config: PublishConfig,
+    sinks: Vec<Sink>,
+    feed: EventFeed,
+}
+
+impl EventPublisher {
+    /// Connect to the brokers; events already in `store` aren't published
+    pub async fn connect(config: &PublishConfig, store: &EventStore) -> Result<Self> {
+        let sinks = config
+            .publishers()
+            .await?
+            .into_iter()
+            .map(|publisher| Sink { publisher, pending: VecDeque::new() })
+            .collect();
+        Ok(Self { config: config.clone(), sinks, feed: EventFeed::new(store) })
+    }
+
+    /// Publish the events indexed since the last call, and retry those a broker rejected
+    /// before; returns how many events were new
+    pub async fn publish(&mut self, store: &EventStore) -> Result<usize> {
+        let events = self.feed.drain(store);
+        let mut messages = Vec::with_capacity(events.len());
+        for event in &events {
+            messages.push((self.config.topic(event.stream()), event.key(), serde_json::to_vec(event)?));
+        }
+
+        let mut failures = Vec::new();
+        for sink in &mut self.sinks {
+            sink.pending.extend(messages.iter().cloned());
+            if sink.pending.len() > MAX_PENDING {
+                let dropped = sink.pending.len() - MAX_PENDING;
+                sink.pending.drain(..dropped);
+                warn!("Dropped the {} oldest events waiting for {}", dropped, sink.publisher.name());
+            }
+            if let Err(e) = sink.flush().await {
+                failures.push(format!("{} ({} events pending): {}", sink.publisher.name(), sink.pending.len(), e));
+            }
+        }
+
+        // Written even when nothing changed, so readers can tell the feed is alive. A
+        // snapshot only overwrites keys, so a failed one is simply taken again next time.
+        let snapshot = self.feed.snapshot(store.dex, store.last_block);
+        let mut cached = true;
+        for sink in &self.sinks {
+            if let Err(e) = sink.publisher.cache(&snapshot).await {
+                failures.push(format!("{} cache: {}", sink.publisher.name(), e));
+                cached = false;
+            }
+        }
+        if cached {
+            self.feed.cached();
+        }
+
+        if !failures.is_empty() {
+            return Err(anyhow!("Publishing failed for {}", failures.join("; ")).into());
+        }
+        Ok(events.len())
+    }
+}
+
+/// A message broker events are published to
+#[async_trait]
+pub trait Publisher: Send + Sync {
+    fn name(&self) -> &str;
+
+    async fn publish(&self, topic: &str, key: &str, payload: &[u8]) -> Result<()>;
+
+    /// Store the latest books and tickers, for brokers that double as a cache
+    async fn cache(&self, _snapshot: &MarketSnapshot) -> Result<()> {
+        Ok(())
+    }
+}
+
+/// Produces every event to partition 0 of its topic, so a topic is one totally ordered
+/// stream, and each pair's events are in order too; consumers can't spread a topic
+/// over several partitions
+#[cfg(feature = "kafka")]
+pub struct KafkaPublisher {
+    client: Client,
+    partitions: Mutex<HashMap<String, Arc<PartitionClient>>>,
+}
+
+#[cfg(feature = "kafka")]
+impl KafkaPublisher {
+    pub async fn connect(config: &KafkaConfig) -> Result<Self> {
+        let client = ClientBuilder::new(config.brokers.clone())
+            .build()
+            .await
+            .map_err(|e| anyhow!("Failed to connect to Kafka at {}: {}", config.brokers.join(","), e))?;
+        Ok(Self { client, partitions: Mutex::new(HashMap::new()) })
+    }
+
+    async fn partition(&self, topic: &str) -> Result<Arc<PartitionClient>> {
+        let mut partitions = self.partitions.lock().await;
+        if let Some(partition) = partitions.get(topic) {
+            return Ok(partition.clone());
+        }
+        let partition = Arc::new(self.client.partition_client(topic, 0, UnknownTopicHandling::Retry).await?);
+        partitions.insert(topic.to_string(), partition.clone());
+        Ok(partition)
+    }
+}
+
+#[cfg(feature = "kafka")]
+#[async_trait]
+impl Publisher for KafkaPublisher {
+    fn name(&self) -> &str {
+        "kafka"
+    }
+
+    async fn publish(&self, topic: &str, key: &str, payload: &[u8]) -> Result<()> {
+        let record = Record {
+            key: Some(key.as_bytes().to_vec()),
+            value: Some(payload.to_vec()),
+            headers: BTreeMap::new(),
+            timestamp: chrono::Utc::now(),
+        };
+        self.partition(topic).await?.produce(vec![record], Compression::NoCompression).await?;
+        Ok(())
+    }
+}
+
+/// Publishes each event on the subject named after its topic
+#[cfg(feature = "nats")]
+pub struct NatsPublisher {
+    client: async_nats::Client,
+}
+
+#[cfg(feature = "nats")]
+impl NatsPublisher {
+    pub async fn connect(config: &NatsConfig) -> Result<Self> {
+        let client = async_nats::connect(config.url.as_str())
+            .await
+            .map_err(|e| anyhow!("Failed to connect to NATS: {}", e))?;
+        Ok(Self { client })
+    }
+}
+
+#[cfg(feature = "nats")]
+#[async_trait]
+impl Publisher for NatsPublisher {
+    fn name(&self) -> &str {
+        "nats"
+    }
+
+    async fn publish(&self, topic: &str, _key: &str, payload: &[u8]) -> Result<()> {
+        self.client.publish(topic.to_string(), payload.to_vec().into()).await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,177 @@
+use crate::error::{DexError, Result};
+use crate::shutdown;
+use anyhow::Context;
+use ethers::utils::keccak256;
+use qrcode::{render::unicode, EcLevel, QrCode};
+use std::collections::BTreeMap;
+use std::io::{self, BufRead, Write};
+use std::time::Duration;
+use tokio::time::sleep;
+
+/// Prefix of every frame, so stray scans are told apart from payload
+const FRAME_PREFIX: &str = "MDX";
+
+/// Hex characters of payload per frame by default; small enough for a phone or a desk
+/// scanner to read off a terminal at low error correction
+pub const DEFAULT_FRAME_SIZE: usize = 600;
+
+/// A payload cut into QR frames of the form `MDX:<index>/<total>:<checksum>:<hex>`.
+///
+/// Everything is uppercase hex, which QR codes carry in their compact alphanumeric
+/// mode, and each frame repeats the checksum of the whole payload so frames from two
+/// different files are never mixed.
+#[derive(Debug, Clone)]
+pub struct Frames {
+    pub frames: Vec<String>,
+}
+
+impl Frames {
+    pub fn encode(payload: &[u8], frame_size: usize) -> Result<Self> {
+        if frame_size == 0 {
+            return Err(DexError::Input("Frame size must be at least 1".to_string()));
+        }
+        let checksum = checksum(payload);
+        let data = hex::encode_upper(payload);
+        let chunks: Vec<&str> = data
+            .as_bytes()
+            .chunks(frame_size)
+            .map(|chunk| std::str::from_utf8(chunk).expect("hex is ASCII"))
+            .collect();
+        let total = chunks.len().max(1);
+        let frames = (0..total)
+            .map(|index| {
+                let chunk = chunks.get(index).copied().unwrap_or_default();
+                format!("{}:{}/{}:{}:{}", FRAME_PREFIX, index + 1, total, checksum, chunk)
+            })
+            .collect();
+        Ok(Self { frames })
+    }
+
+    /// Render one frame as a QR code drawn with Unicode half blocks
+    pub fn render(frame: &str) -> Result<String> {
+        let code = QrCode::with_error_correction_level(frame.as_bytes(), EcLevel::L)
+            .map_err(|e| DexError::Input(format!("Frame doesn't fit in a QR code ({}); use a smaller --frame-size", e)))?;
+        Ok(code
+            .render::<unicode::Dense1x2>()
+            .dark_color(unicode::Dense1x2::Light)
+            .light_color(unicode::Dense1x2::Dark)
+            .build())
+    }
+
+    /// Show the frames on the terminal: a single frame stays up, several cycle every
+    /// `interval` until Ctrl-C, or once through with `once`
+    pub async fn show(&self, interval: Duration, once: bool) -> Result<()> {
+        let rendered = self.frames.iter().map(|frame| Self::render(frame)).collect::<Result<Vec<_>>>()?;
+        if let [single] = rendered.as_slice() {
+            println!("{}", single);
+            return Ok(());
+        }
+        let total = rendered.len();
+        loop {
+            for (index, code) in rendered.iter().enumerate() {
+                if shutdown::requested() {
+                    return Ok(());
+                }
+                // Clear the screen so each frame is scanned on its own
+                print!("\x1b[2J\x1b[H{}\nFrame {}/{} (Ctrl-C to stop)\n", code, index + 1, total);
+                io::stdout().flush()?;
+                tokio::select! {
+                    _ = sleep(interval) => {}
+                    _ = shutdown::wait() => return Ok(()),
+                }
+            }
+            if once {
+                return Ok(());
+            }
+        }
+    }
+}
+
+/// Collects scanned frames, in any order and with repeats, until the payload is whole
+#[derive(Debug, Default)]
+pub struct FrameAssembler {
+    checksum: Option<String>,
+    total: usize,
+    chunks: BTreeMap<usize, String>,
+}
+
+impl FrameAssembler {
+    /// Add a scanned frame; lines that aren't frames are ignored
+    pub fn push(&mut self, line: &str) -> Result<()> {
+        let line = line.trim();
+        let Some(rest) = line.strip_prefix(FRAME_PREFIX).and_then(|rest| rest.strip_prefix(':')) else {
+            return Ok(());
+        };
+        let mut parts = rest.splitn(3, ':');
+        let (Some(position), Some(checksum), Some(chunk)) = (parts.next(), parts.next(), parts.next()) else {
+            return Err(DexError::Input(format!("Malformed frame: {}", line)));
+        };
+        let (index, total) = position
+            .split_once('/')
+            .and_then(|(index, total)| Some((index.parse::<usize>().ok()?, total.parse::<usize>().ok()?)))
+            .filter(|(index, total)| *index >= 1 && index <= total)
+            .ok_or_else(|| DexError::Input(format!("Malformed frame position: {}", position)))?;
+
+        match &self.checksum {
+            None => {
+                self.checksum = Some(checksum.to_string());
+                self.total = total;
+            }
+            Some(expected) if expected != checksum || self.total != total => {
+                return Err(DexError::Input(format!("Frame {}/{} belongs to another payload (checksum {}, expected {})", index, total, checksum, expected)));
+            }
+            Some(_) => {}
+        }
+        self.chunks.insert(index, chunk.to_string());
+        Ok(())
+    }
+
+    /// Frames received so far, and how many the payload has
+    pub fn progress(&self) -> (usize, usize) {
+        (self.chunks.len(), self.total)
+    }
+
+    pub fn is_complete(&self) -> bool {
+        self.total > 0 && self.chunks.len() == self.total
+    }
+
+    /// The payload, once every frame has arrived and it matches its checksum
+    pub fn finish(&self) -> Result<Vec<u8>> {
+        if !self.is_complete() {
+            let (received, total) = self.progress();
+            return Err(DexError::Input(format!("Only {} of {} frames were scanned", received, total)));
+        }
+        let data: String = self.chunks.values().map(String::as_str).collect();
+        let payload = hex::decode(data).context("Scanned frames aren't valid hex")?;
+        if Some(checksum(&payload)) != self.checksum {
+            return Err(DexError::Input("Scanned payload doesn't match its checksum; rescan it".to_string()));
+        }
+        Ok(payload)
+    }
+}
+
+/// Read frames from stdin, one per line as a keyboard-wedge QR scanner types them,
+/// until the payload is complete
+pub fn read_frames() -> Result<Vec<u8>> {
+    let mut assembler = FrameAssembler::default();
+    eprintln!("Scan the QR frames (in any order); Ctrl-D to stop");
+    for line in io::stdin().lock().lines() {
+        if let Err(e) = assembler.push(&line?) {
+            eprintln!("Skipped: {}", e);
+            continue;
+        }
+        let (received, total) = assembler.progress();
+        if total > 0 {
+            eprintln!("Frame {}/{} received", received, total);
+        }
+        if assembler.is_complete() {
+            break;
+        }
+    }
+    assembler.finish()
+}
+
+/// First 4 bytes of the payload's keccak256, in uppercase hex
+fn checksum(payload: &[u8]) -> String {
+    hex::encode_upper(&keccak256(payload)[..4])
+}
@@ -0,0 +1,314 @@
+use crate::error::{DexError, Result};
+use crate::expiry;
+use crate::strategy::{
+    BookView, Fill, Intent, Level, OpenOrder, PendingActivity, Strategy, StrategyConfig, StrategyRegistry,
+};
+use anyhow::Context;
+use ethers::types::U256;
+use serde::{Deserialize, Serialize};
+use std::fs::{self, File, OpenOptions};
+use std::io::Write;
+use std::time::{Duration, SystemTime};
+use tracing::warn;
+
+/// Changes from one recorded book to the next. A level with a zero amount is gone; the
+/// first book of a run is recorded against an empty one.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct BookDelta {
+    pub block: Option<u64>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub bids: Vec<Level>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub asks: Vec<Level>,
+    #[serde(default)]
+    pub reference: Option<f64>,
+}
+
+impl BookDelta {
+    pub fn between(previous: &BookView, next: &BookView) -> Self {
+        Self {
+            block: next.block,
+            bids: side_delta(&previous.bids, &next.bids),
+            asks: side_delta(&previous.asks, &next.asks),
+            reference: next.reference,
+        }
+    }
+
+    pub fn apply(&self, book: &mut BookView) {
+        book.block = self.block;
+        book.reference = self.reference;
+        apply_side(&mut book.bids, &self.bids);
+        apply_side(&mut book.asks, &self.asks);
+        book.bids.sort_by_key(|order| std::cmp::Reverse(order.price));
+        book.asks.sort_by_key(|order| order.price);
+    }
+}
+
+fn side_delta(previous: &[Level], next: &[Level]) -> Vec<Level> {
+    let changed = next.iter().filter(|level| !previous.contains(level)).copied();
+    let removed = previous
+        .iter()
+        .filter(|level| !next.iter().any(|next| next.price == level.price))
+        .map(|level| Level { price: level.price, amount: U256::zero() });
+    changed.chain(removed).collect()
+}
+
+fn apply_side(levels: &mut Vec<Level>, delta: &[Level]) {
+    for change in delta {
+        levels.retain(|level| level.price != change.price);
+        if !change.amount.is_zero() {
+            levels.push(*change);
+        }
+    }
+}
+
+/// One strategy call with everything it was given
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "call", rename_all = "snake_case")]
+pub enum Call {
+    /// A bot started with this strategy; later calls go to a fresh instance of it
+    Start { strategy: StrategyConfig },
+    Position { position: f64 },
+    Fill { fill: Fill },
+    Pending { pending: Vec<PendingActivity>, open_orders: Vec<OpenOrder> },
+    Timer { open_orders: Vec<OpenOrder> },
+    /// A book update, which also carries the reference price from any price feed
+    Book { book: BookDelta, open_orders: Vec<OpenOrder> },
+    Reconfigure {
+        strategy: StrategyConfig,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        error: Option<String>,
+    },
+}
+
+impl Call {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Call::Start { .. } => "start",
+            Call::Position { .. } => "position",
+            Call::Fill { .. } => "fill",
+            Call::Pending { .. } => "pending",
+            Call::Timer { .. } => "timer",
+            Call::Book { .. } => "book",
+            Call::Reconfigure { .. } => "reconfigure",
+        }
+    }
+}
+
+/// A line of a strategy event log: a call and the intents the strategy answered with
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Entry {
+    pub seq: u64,
+    /// Unix time of the call, for lining the log up with other records of an incident
+    pub time: u64,
+    #[serde(flatten)]
+    pub call: Call,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub intents: Vec<Intent>,
+}
+
+/// Records every input a strategy sees, and what it answered, to a JSON lines event log
+/// that [`replay`] can run a strategy against again.
+///
+/// Runs append to the log, each starting with a [`Call::Start`]. Lines are written as the
+/// calls happen so a crashed bot leaves a complete log behind; failing to write one is
+/// logged and doesn't stop the bot.
+pub struct RecordingStrategy {
+    inner: Box<dyn Strategy>,
+    path: String,
+    file: File,
+    seq: u64,
+    book: BookView,
+    write_failed: bool,
+}
+
+impl RecordingStrategy {
+    pub fn new(inner: Box<dyn Strategy>, config: &StrategyConfig, path: &str) -> Result<Self> {
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .with_context(|| format!("Failed to open {}", path))?;
+        let mut recorder = Self {
+            inner,
+            path: path.to_string(),
+            file,
+            seq: 0,
+            book: BookView::default(),
+            write_failed: false,
+        };
+        recorder.record(Call::Start { strategy: config.clone() }, &[]);
+        Ok(recorder)
+    }
+
+    fn record(&mut self, call: Call, intents: &[Intent]) {
+        let entry = Entry {
+            seq: self.seq,
+            time: expiry::unix_secs(SystemTime::now()),
+            call,
+            intents: intents.to_vec(),
+        };
+        self.seq += 1;
+        let written = serde_json::to_string(&entry)
+            .map_err(anyhow::Error::from)
+            .and_then(|line| Ok(writeln!(self.file, "{}", line)?));
+        match written {
+            Ok(()) => self.write_failed = false,
+            Err(e) if !self.write_failed => {
+                warn!("Couldn't record to {}: {}", self.path, e);
+                self.write_failed = true;
+            }
+            Err(_) => {}
+        }
+    }
+}
+
+impl Strategy for RecordingStrategy {
+    fn name(&self) -> &str {
+        self.inner.name()
+    }
+
+    fn on_book_update(&mut self, book: &BookView, open_orders: &[OpenOrder]) -> Vec<Intent> {
+        let intents = self.inner.on_book_update(book, open_orders);
+        let delta = BookDelta::between(&self.book, book);
+        self.book = book.clone();
+        self.record(Call::Book { book: delta, open_orders: open_orders.to_vec() }, &intents);
+        intents
+    }
+
+    fn on_fill(&mut self, fill: &Fill) -> Vec<Intent> {
+        let intents = self.inner.on_fill(fill);
+        self.record(Call::Fill { fill: fill.clone() }, &intents);
+        intents
+    }
+
+    fn on_pending(&mut self, pending: &[PendingActivity], open_orders: &[OpenOrder]) -> Vec<Intent> {
+        let intents = self.inner.on_pending(pending, open_orders);
+        // Nothing pending is the common case and replays the same without a line
+        if !pending.is_empty() || !intents.is_empty() {
+            let call = Call::Pending { pending: pending.to_vec(), open_orders: open_orders.to_vec() };
+            self.record(call, &intents);
+        }
+        intents
+    }
+
+    fn on_position(&mut self, position: f64) {
+        self.inner.on_position(position);
+        self.record(Call::Position { position }, &[]);
+    }
+
+    fn timer_interval(&self) -> Option<Duration> {
+        self.inner.timer_interval()
+    }
+
+    fn on_timer(&mut self, open_orders: &[OpenOrder]) -> Vec<Intent> {
+        let intents = self.inner.on_timer(open_orders);
+        self.record(Call::Timer { open_orders: open_orders.to_vec() }, &intents);
+        intents
+    }
+
+    fn reconfigure(&mut self, config: &StrategyConfig) -> Result<()> {
+        let result = self.inner.reconfigure(config);
+        let error = result.as_ref().err().map(|e| e.to_string());
+        self.record(Call::Reconfigure { strategy: config.clone(), error }, &[]);
+        result
+    }
+}
+
+/// A call whose replayed intents differ from the recorded ones
+#[derive(Debug, Clone, Serialize)]
+pub struct Divergence {
+    pub seq: u64,
+    pub call: &'static str,
+    pub recorded: Vec<Intent>,
+    pub replayed: Vec<Intent>,
+}
+
+/// Outcome of [`replay`]
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ReplayReport {
+    pub runs: usize,
+    pub calls: usize,
+    pub intents: usize,
+    pub divergences: Vec<Divergence>,
+}
+
+/// Run strategies from `registry` against the event log at `path`, comparing their
+/// intents with the recorded ones.
+///
+/// Each recorded run starts a fresh strategy from its recorded config, or from
+/// `strategy` to try a change against an incident; recorded reconfigurations are skipped
+/// then. Calls happen in recorded order with recorded inputs, so strategies that depend
+/// only on their inputs reproduce the recording exactly. Script strategies read their
+/// script from disk as it is now.
+pub fn replay(path: &str, registry: &StrategyRegistry, strategy: Option<&StrategyConfig>) -> Result<ReplayReport> {
+    let content = fs::read_to_string(path).with_context(|| format!("Failed to read {}", path))?;
+    let mut report = ReplayReport::default();
+    let mut current: Option<Box<dyn Strategy>> = None;
+    let mut book = BookView::default();
+
+    for (line_number, line) in content.lines().enumerate() {
+        if line.trim().is_empty() {
+            continue;
+        }
+        let entry: Entry = serde_json::from_str(line)
+            .with_context(|| format!("{} line {} is not a log entry", path, line_number + 1))?;
+
+        let replayed = match &entry.call {
+            Call::Start { strategy: recorded } => {
+                current = Some(registry.build(strategy.unwrap_or(recorded))?);
+                book = BookView::default();
+                report.runs += 1;
+                continue;
+            }
+            call => {
+                let running = current
+                    .as_deref_mut()
+                    .ok_or_else(|| DexError::Input(format!("{} line {} comes before any start", path, line_number + 1)))?;
+                replay_call(running, call, &mut book, strategy.is_none(), entry.seq)
+            }
+        };
+
+        report.calls += 1;
+        report.intents += replayed.len();
+        if replayed != entry.intents {
+            report.divergences.push(Divergence {
+                seq: entry.seq,
+                call: entry.call.name(),
+                recorded: entry.intents,
+                replayed,
+            });
+        }
+    }
+    Ok(report)
+}
+
+/// Make one recorded call on `strategy`, returning its intents
+fn replay_call(strategy: &mut dyn Strategy, call: &Call, book: &mut BookView, reconfigure: bool, seq: u64) -> Vec<Intent> {
+    match call {
+        Call::Start { .. } => Vec::new(),
+        Call::Position { position } => {
+            strategy.on_position(*position);
+            Vec::new()
+        }
+        Call::Fill { fill } => strategy.on_fill(fill),
+        Call::Pending { pending, open_orders } => strategy.on_pending(pending, open_orders),
+        Call::Timer { open_orders } => strategy.on_timer(open_orders),
+        Call::Book { book: delta, open_orders } => {
+            delta.apply(book);
+            strategy.on_book_update(book, open_orders)
+        }
+        Call::Reconfigure { strategy: config, error } => {
+            if reconfigure {
+                match (strategy.reconfigure(config), error) {
+                    (Err(e), None) => warn!("Entry {}: reconfiguring failed on replay: {}", seq, e),
+                    (Ok(()), Some(recorded)) => {
+                        warn!("Entry {}: reconfiguring failed when recorded ({}) but not on replay", seq, recorded)
+                    }
+                    _ => {}
+                }
+            }
+            Vec::new()
+        }
+    }
+}
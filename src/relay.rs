@@ -0,0 +1,572 @@
+use crate::bindings::{self, OrderBookDEX, Settlement};
+use crate::bot::SignerClient;
+use crate::error::{DexError, Result};
+use crate::pairs::PairParams;
+use crate::signing::{OrderDomain, SignedOrder};
+use crate::tx::{self, TxArgs};
+use anyhow::anyhow;
+use ethers::{
+    providers::Middleware,
+    types::{Address, H256, U256},
+};
+use serde::Serialize;
+use std::collections::{HashMap, HashSet};
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::Mutex;
+use tracing::{info, warn};
+
+/// Largest request body the relay will read
+const MAX_REQUEST_SIZE: usize = 64 * 1024;
+
+/// A signed order resting in the relay book with its unfilled amount
+#[derive(Debug, Clone)]
+pub struct RestingOrder {
+    pub signed: SignedOrder,
+    pub remaining: u64,
+    sequence: u64,
+}
+
+/// A crossing between a buy and a sell order, waiting to be settled on-chain
+#[derive(Debug, Clone)]
+pub struct Match {
+    pub buy: SignedOrder,
+    pub sell: SignedOrder,
+    pub amount: u64,
+    pub price: u64,
+}
+
+/// Off-chain book of one pair. Bids are kept best (highest) first and asks best
+/// (lowest) first, each in arrival order within a price.
+#[derive(Debug, Default)]
+pub struct PairBook {
+    pub bids: Vec<RestingOrder>,
+    pub asks: Vec<RestingOrder>,
+}
+
+impl PairBook {
+    /// Match `signed` against the opposite side at the resting orders' prices and rest
+    /// any remainder
+    fn submit(&mut self, signed: SignedOrder, sequence: u64) -> Vec<Match> {
+        let order = &signed.order;
+        let mut remaining = order.amount;
+        let mut matches = Vec::new();
+        let opposite = if order.is_buy { &mut self.asks } else { &mut self.bids };
+
+        while remaining > 0 && !opposite.is_empty() {
+            let best = &mut opposite[0];
+            if best.signed.order.is_expired() {
+                opposite.remove(0);
+                continue;
+            }
+            let crosses = if order.is_buy {
+                best.signed.order.price <= order.price
+            } else {
+                best.signed.order.price >= order.price
+            };
+            if !crosses {
+                break;
+            }
+
+            let amount = remaining.min(best.remaining);
+            let (buy, sell) = if order.is_buy {
+                (signed.clone(), best.signed.clone())
+            } else {
+                (best.signed.clone(), signed.clone())
+            };
+            matches.push(Match {
+                buy,
+                sell,
+                amount,
+                price: best.signed.order.price,
+            });
+            remaining -= amount;
+            best.remaining -= amount;
+            if best.remaining == 0 {
+                opposite.remove(0);
+            }
+        }
+
+        if remaining > 0 {
+            let is_buy = order.is_buy;
+            let resting = RestingOrder {
+                signed,
+                remaining,
+                sequence,
+            };
+            let side = if is_buy { &mut self.bids } else { &mut self.asks };
+            let position = side
+                .iter()
+                .position(|other| {
+                    let (price, other_price) = (resting.signed.order.price, other.signed.order.price);
+                    if is_buy {
+                        price > other_price
+                    } else {
+                        price < other_price
+                    }
+                })
+                .unwrap_or(side.len());
+            side.insert(position, resting);
+        }
+        matches
+    }
+
+    fn prune_expired(&mut self) {
+        self.bids.retain(|order| !order.signed.order.is_expired());
+        self.asks.retain(|order| !order.signed.order.is_expired());
+    }
+}
+
+/// Off-chain books of every pair the relay has seen orders for
+#[derive(Debug, Default)]
+pub struct RelayBook {
+    pairs: HashMap<(Address, Address), PairBook>,
+    seen: HashSet<H256>,
+    sequence: u64,
+}
+
+impl RelayBook {
+    pub fn submit(&mut self, signed: SignedOrder) -> Result<Vec<Match>> {
+        if !self.seen.insert(signed.digest) {
+            return Err(DexError::Input(format!("Order {:?} was already submitted", signed.digest)));
+        }
+        self.sequence += 1;
+        let key = (signed.order.base_token, signed.order.quote_token);
+        Ok(self.pairs.entry(key).or_default().submit(signed, self.sequence))
+    }
+
+    pub fn snapshot(&mut self) -> Vec<BookSnapshot> {
+        self.pairs
+            .iter_mut()
+            .map(|((base_token, quote_token), book)| {
+                book.prune_expired();
+                BookSnapshot {
+                    base_token: *base_token,
+                    quote_token: *quote_token,
+                    bids: book.bids.iter().map(BookEntry::from).collect(),
+                    asks: book.asks.iter().map(BookEntry::from).collect(),
+                }
+            })
+            .collect()
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct BookEntry {
+    pub digest: H256,
+    pub maker: Address,
+    pub price: u64,
+    pub remaining: u64,
+    pub expiry: u64,
+    pub sequence: u64,
+}
+
+impl From<&RestingOrder> for BookEntry {
+    fn from(order: &RestingOrder) -> Self {
+        Self {
+            digest: order.signed.digest,
+            maker: order.signed.order.maker,
+            price: order.signed.order.price,
+            remaining: order.remaining,
+            expiry: order.signed.order.expiry,
+            sequence: order.sequence,
+        }
+    }
+}
+
+/// Book of one pair as returned by `GET /orders`
+#[derive(Debug, Serialize)]
+pub struct BookSnapshot {
+    pub base_token: Address,
+    pub quote_token: Address,
+    pub bids: Vec<BookEntry>,
+    pub asks: Vec<BookEntry>,
+}
+
+#[derive(Default)]
+struct RelayState {
+    book: RelayBook,
+    pending: Vec<Match>,
+    pairs: HashMap<(Address, Address), PairParams>,
+}
+
+/// Accepts signed orders over HTTP, matches them off-chain and settles the matches
+/// on the DEX in batches
+pub struct Relay {
+    client: Arc<SignerClient>,
+    contract: OrderBookDEX<SignerClient>,
+    domain: OrderDomain,
+    tx_args: TxArgs,
+    state: Mutex<RelayState>,
+}
+
+impl Relay {
+    pub async fn new(client: Arc<SignerClient>, dex: Address, tx_args: TxArgs) -> Result<Self> {
+        let contract = OrderBookDEX::new(dex, client.clone());
+        let domain = OrderDomain::fetch(&contract).await?;
+        Ok(Self {
+            client,
+            contract,
+            domain,
+            tx_args,
+            state: Mutex::new(RelayState::default()),
+        })
+    }
+
+    /// Verify a signed order and add it to the book, returning the number of matches it made
+    pub async fn accept(&self, signed: SignedOrder) -> Result<usize> {
+        let order = &signed.order;
+        if signed.domain != self.domain {
+            return Err(DexError::Input("Order is signed for a different DEX or chain".to_string()));
+        }
+        if order.digest(&self.domain) != signed.digest {
+            return Err(DexError::Input("Digest does not match the order".to_string()));
+        }
+        let signer = signed
+            .signature
+            .recover(signed.digest)
+            .map_err(|e| DexError::Input(format!("Invalid signature: {}", e)))?;
+        if signer != order.maker {
+            return Err(DexError::Input(format!("Signed by {:?}, not the maker {:?}", signer, order.maker)));
+        }
+        if order.is_expired() {
+            return Err(DexError::Input(format!("Order expired at {}", order.expiry)));
+        }
+        if order.base_token.is_zero() || order.quote_token.is_zero() {
+            return Err(DexError::Input("Signed orders can't trade the native token".to_string()));
+        }
+
+        let key = (order.base_token, order.quote_token);
+        let cached = self.state.lock().await.pairs.get(&key).cloned();
+        let pair = match cached {
+            Some(pair) => pair,
+            None => {
+                let pair = PairParams::fetch(&self.contract, order.base_token, order.quote_token).await?;
+                self.state.lock().await.pairs.insert(key, pair.clone());
+                pair
+            }
+        };
+        pair.validate_order(U256::from(order.amount), Some(U256::from(order.price)), order.is_buy)?;
+
+        let mut state = self.state.lock().await;
+        let matches = state.book.submit(signed)?;
+        let count = matches.len();
+        state.pending.extend(matches);
+        Ok(count)
+    }
+
+    /// Settle up to `batch_size` pending matches in one transaction. Matches that would
+    /// revert on their own, e.g. because a maker revoked their allowance, are dropped so
+    /// they don't sink the rest of the batch. If the RPC fails or the settlement doesn't
+    /// go through, the batch goes back to the front of the queue: the book already took
+    /// the matched amounts off the orders, so the matches must not be lost.
+    pub async fn settle(&self, batch_size: usize) -> Result<usize> {
+        let batch: Vec<Match> = {
+            let mut state = self.state.lock().await;
+            let count = batch_size.min(state.pending.len());
+            state.pending.drain(..count).collect()
+        };
+        if batch.is_empty() {
+            return Ok(0);
+        }
+
+        let batch = match self.settleable(batch).await {
+            Ok(batch) => batch,
+            Err((batch, e)) => {
+                self.requeue(batch).await;
+                return Err(e);
+            }
+        };
+        if batch.is_empty() {
+            return Ok(0);
+        }
+
+        let count = batch.len();
+        if let Err(e) = self.send(&batch).await {
+            self.requeue(batch).await;
+            return Err(e);
+        }
+        Ok(count)
+    }
+
+    /// The matches of `batch` that settle, checked one by one if the whole batch would
+    /// revert. An RPC failure hands the batch back unchecked.
+    async fn settleable(&self, batch: Vec<Match>) -> Result<Vec<Match>, (Vec<Match>, DexError)> {
+        let call = self.contract.settle_signed_orders(batch.iter().map(settlement).collect());
+        match call.call().await {
+            Ok(()) => return Ok(batch),
+            Err(e) if !e.is_revert() => return Err((batch, e.into())),
+            Err(e) => warn!("Settlement batch would revert ({}); checking matches one by one", e),
+        }
+
+        let mut settleable = Vec::with_capacity(batch.len());
+        let mut unchecked = batch.into_iter();
+        while let Some(matched) = unchecked.next() {
+            let single = self.contract.settle_signed_orders(vec![settlement(&matched)]);
+            match single.call().await {
+                Ok(()) => settleable.push(matched),
+                Err(e) if e.is_revert() => warn!(
+                    "Dropping match of {:?} and {:?}: {}",
+                    matched.buy.digest, matched.sell.digest, e
+                ),
+                Err(e) => {
+                    settleable.push(matched);
+                    settleable.extend(unchecked);
+                    return Err((settleable, e.into()));
+                }
+            }
+        }
+        Ok(settleable)
+    }
+
+    /// Send `batch` in one transaction and make sure it was mined without reverting
+    async fn send(&self, batch: &[Match]) -> Result<()> {
+        let fees = self.tx_args.fees.resolve(self.client.inner()).await?;
+        let call = self.contract.settle_signed_orders(batch.iter().map(settlement).collect());
+        let receipt = tx::send(self.client.as_ref(), call, &fees, &self.tx_args)
+            .await?
+            .ok_or_else(|| anyhow!("Settlement of {} matches was dropped from the mempool", batch.len()))?;
+        tx::ensure_success(self.client.as_ref(), &receipt).await?;
+        info!("Settled {} matches in {:?}", batch.len(), receipt.transaction_hash);
+        Ok(())
+    }
+
+    /// Put `batch` back at the front of the queue, ahead of newer matches
+    async fn requeue(&self, batch: Vec<Match>) {
+        warn!("Requeueing {} matches for the next settlement", batch.len());
+        self.state.lock().await.pending.splice(0..0, batch);
+    }
+
+    /// Serve the HTTP API on `addr` and settle pending matches every `interval`
+    pub async fn run(self: Arc<Self>, addr: SocketAddr, batch_size: usize, interval: Duration) -> Result<()> {
+        let listener = TcpListener::bind(addr).await?;
+        info!("Relay listening on http://{} for {:?}", addr, self.domain.verifying_contract);
+
+        let settler = self.clone();
+        tokio::spawn(async move {
+            loop {
+                tokio::time::sleep(interval).await;
+                loop {
+                    match settler.settle(batch_size).await {
+                        Ok(count) if count == batch_size => continue,
+                        Ok(_) => break,
+                        Err(e) => {
+                            warn!("Settlement failed: {}", e);
+                            break;
+                        }
+                    }
+                }
+            }
+        });
+
+        loop {
+            let (socket, _) = listener.accept().await?;
+            let relay = self.clone();
+            tokio::spawn(async move {
+                if let Err(e) = relay.handle(socket).await {
+                    warn!("Relay request failed: {}", e);
+                }
+            });
+        }
+    }
+
+    async fn handle(&self, mut socket: TcpStream) -> Result<()> {
+        let (method, path, body) = read_request(&mut socket).await?;
+        let (status, body) = match (method.as_str(), path.as_str()) {
+            ("POST", "/orders") => match serde_json::from_slice::<SignedOrder>(&body) {
+                Ok(signed) => {
+                    let digest = signed.digest;
+                    match self.accept(signed).await {
+                        Ok(matches) => ("200 OK", serde_json::json!({ "digest": digest, "matches": matches })),
+                        Err(e) => ("400 Bad Request", serde_json::json!({ "error": e.to_string() })),
+                    }
+                }
+                Err(e) => ("400 Bad Request", serde_json::json!({ "error": format!("Invalid signed order: {}", e) })),
+            },
+            ("GET", "/orders") => {
+                let snapshot = self.state.lock().await.book.snapshot();
+                ("200 OK", serde_json::to_value(snapshot)?)
+            }
+            _ => ("404 Not Found", serde_json::json!({ "error": "Not found" })),
+        };
+
+        let body = body.to_string();
+        let response = format!(
+            "HTTP/1.1 {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+            status,
+            body.len(),
+            body
+        );
+        socket.write_all(response.as_bytes()).await?;
+        Ok(())
+    }
+}
+
+/// `(SignedOrder buy, SignedOrder sell, uint256 amount, uint256 price)` as the DEX expects it
+fn settlement(matched: &Match) -> Settlement {
+    Settlement {
+        buy: signed_order(&matched.buy),
+        sell: signed_order(&matched.sell),
+        amount: U256::from(matched.amount),
+        price: U256::from(matched.price),
+    }
+}
+
+fn signed_order(signed: &SignedOrder) -> bindings::SignedOrder {
+    let order = &signed.order;
+    bindings::SignedOrder {
+        maker: order.maker,
+        base_token: order.base_token,
+        quote_token: order.quote_token,
+        amount: U256::from(order.amount),
+        price: U256::from(order.price),
+        is_buy: order.is_buy,
+        nonce: U256::from(order.nonce),
+        expiry: U256::from(order.expiry),
+        signature: signed.signature.to_vec().into(),
+    }
+}
+
+/// Read a request line, headers and a `Content-Length` body
+async fn read_request(socket: &mut TcpStream) -> Result<(String, String, Vec<u8>)> {
+    let mut buffer = Vec::new();
+    let mut chunk = [0u8; 4096];
+    loop {
+        let read = socket.read(&mut chunk).await?;
+        if read == 0 {
+            return Err(DexError::Input("Connection closed mid-request".to_string()));
+        }
+        buffer.extend_from_slice(&chunk[..read]);
+        if buffer.len() > MAX_REQUEST_SIZE {
+            return Err(DexError::Input(format!("Request larger than {} bytes", MAX_REQUEST_SIZE)));
+        }
+
+        let Some(header_end) = buffer.windows(4).position(|w| w == b"\r\n\r\n") else {
+            continue;
+        };
+        let head = String::from_utf8_lossy(&buffer[..header_end]).to_string();
+        let content_length = head
+            .lines()
+            .filter_map(|line| line.split_once(':'))
+            .find(|(name, _)| name.trim().eq_ignore_ascii_case("content-length"))
+            .and_then(|(_, value)| value.trim().parse::<usize>().ok())
+            .unwrap_or(0);
+        let body_start = header_end + 4;
+        if buffer.len() < body_start + content_length {
+            continue;
+        }
+
+        let mut request_line = head.lines().next().unwrap_or_default().split_whitespace();
+        let method = request_line.next().unwrap_or_default().to_string();
+        let path = request_line.next().unwrap_or_default().to_string();
+        return Ok((method, path, buffer[body_start..body_start + content_length].to_vec()));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::signing::{unix_now, OffchainOrder};
+    use ethers::types::Signature;
+
+    const BASE: Address = Address::repeat_byte(1);
+    const QUOTE: Address = Address::repeat_byte(2);
+
+    fn signed(nonce: u64, is_buy: bool, amount: u64, price: u64) -> SignedOrder {
+        let order = OffchainOrder {
+            maker: Address::repeat_byte(nonce as u8),
+            base_token: BASE,
+            quote_token: QUOTE,
+            amount,
+            price,
+            is_buy,
+            nonce,
+            expiry: unix_now() + 3_600,
+        };
+        let domain = OrderDomain {
+            name: "OrderBookDEX".to_string(),
+            version: "1".to_string(),
+            chain_id: 10143,
+            verifying_contract: Address::repeat_byte(9),
+        };
+        let digest = order.digest(&domain);
+        SignedOrder { domain, order, digest, signature: Signature { r: U256::one(), s: U256::one(), v: 27 } }
+    }
+
+    fn nonces(side: &[RestingOrder]) -> Vec<u64> {
+        side.iter().map(|resting| resting.signed.order.nonce).collect()
+    }
+
+    #[test]
+    fn orders_rest_best_price_first_and_in_arrival_order_within_a_price() {
+        let mut book = PairBook::default();
+        book.submit(signed(1, true, 10, 100), 1);
+        book.submit(signed(2, true, 10, 101), 2);
+        book.submit(signed(3, true, 10, 100), 3);
+        book.submit(signed(4, false, 10, 105), 4);
+        book.submit(signed(5, false, 10, 103), 5);
+        assert_eq!(nonces(&book.bids), [2, 1, 3]);
+        assert_eq!(nonces(&book.asks), [5, 4]);
+    }
+
+    #[test]
+    fn a_crossing_order_fills_at_the_resting_prices_and_rests_the_remainder() {
+        let mut book = PairBook::default();
+        book.submit(signed(1, false, 10, 100), 1);
+        book.submit(signed(2, false, 10, 102), 2);
+        book.submit(signed(3, false, 10, 110), 3);
+
+        let matches = book.submit(signed(4, true, 25, 105), 4);
+        let fills: Vec<(u64, u64, u64)> = matches.iter().map(|m| (m.sell.order.nonce, m.amount, m.price)).collect();
+        assert_eq!(fills, [(1, 10, 100), (2, 10, 102)]);
+        assert!(matches.iter().all(|m| m.buy.order.nonce == 4));
+        assert_eq!(nonces(&book.asks), [3]);
+        assert_eq!(nonces(&book.bids), [4]);
+        assert_eq!(book.bids[0].remaining, 5);
+    }
+
+    #[test]
+    fn a_partial_fill_leaves_the_resting_order_with_the_rest() {
+        let mut book = PairBook::default();
+        book.submit(signed(1, true, 30, 100), 1);
+        let matches = book.submit(signed(2, false, 12, 95), 2);
+        assert_eq!(matches.len(), 1);
+        assert_eq!((matches[0].amount, matches[0].price), (12, 100));
+        assert_eq!(book.bids[0].remaining, 18);
+        assert!(book.asks.is_empty());
+    }
+
+    #[test]
+    fn expired_resting_orders_are_skipped() {
+        let mut book = PairBook::default();
+        let mut stale = signed(1, false, 10, 100);
+        stale.order.expiry = unix_now() - 1;
+        book.asks.push(RestingOrder { signed: stale, remaining: 10, sequence: 1 });
+        book.submit(signed(2, false, 10, 101), 2);
+
+        let matches = book.submit(signed(3, true, 10, 101), 3);
+        assert_eq!(matches.len(), 1);
+        assert_eq!(matches[0].sell.order.nonce, 2);
+        assert!(book.asks.is_empty());
+    }
+
+    #[test]
+    fn an_order_is_accepted_once() {
+        let mut book = RelayBook::default();
+        book.submit(signed(1, true, 10, 100)).unwrap();
+        assert!(book.submit(signed(1, true, 10, 100)).is_err());
+    }
+
+    #[test]
+    fn settlements_carry_both_signed_orders() {
+        let matched = Match { buy: signed(1, true, 10, 105), sell: signed(2, false, 10, 100), amount: 7, price: 100 };
+        let settlement = settlement(&matched);
+        assert_eq!((settlement.amount, settlement.price), (U256::from(7), U256::from(100)));
+        assert_eq!(settlement.buy.maker, matched.buy.order.maker);
+        assert!(settlement.buy.is_buy && !settlement.sell.is_buy);
+        assert_eq!(settlement.sell.signature.len(), 65);
+    }
+}
@@ -0,0 +1,80 @@
+use crate::error::{DexError, Result};
+use crate::risk::RiskLimits;
+use crate::strategy::{BotConfig, Strategy};
+use std::fs;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::SystemTime;
+use tracing::info;
+
+/// Bumped by [`request_reload`]; a watcher that saw an older value re-reads its file
+static RELOAD_GENERATION: AtomicU64 = AtomicU64::new(0);
+
+/// Make every watcher in the process re-read its config on its next check, even if the
+/// file's modification time didn't change (e.g. on `ctl reload`)
+pub fn request_reload() {
+    RELOAD_GENERATION.fetch_add(1, Ordering::Relaxed);
+}
+
+/// Watches a running bot's TOML config and hands `[strategy]` changes to the strategy.
+///
+/// Only strategy parameters of the same `kind` are applied; anything else in the file
+/// needs a restart. New parameters must pass the risk limits on their own.
+pub struct ConfigWatcher {
+    path: String,
+    modified: Option<SystemTime>,
+    generation: u64,
+    current: BotConfig,
+}
+
+impl ConfigWatcher {
+    pub fn new(path: &str, config: BotConfig) -> Self {
+        Self {
+            path: path.to_string(),
+            modified: modified(path),
+            generation: RELOAD_GENERATION.load(Ordering::Relaxed),
+            current: config,
+        }
+    }
+
+    /// Apply the file to `strategy` if it changed since the last call. Returns a summary
+    /// of an applied change; a rejected change is an error and isn't retried until the
+    /// file changes again.
+    pub fn apply(&mut self, strategy: &mut dyn Strategy) -> Result<Option<String>> {
+        let modified = modified(&self.path);
+        let generation = RELOAD_GENERATION.load(Ordering::Relaxed);
+        if modified == self.modified && generation == self.generation {
+            return Ok(None);
+        }
+        self.modified = modified;
+        self.generation = generation;
+
+        let config = BotConfig::load(&self.path)?;
+        let before = serde_json::to_value(&self.current.strategy)?;
+        let after = serde_json::to_value(&config.strategy)?;
+
+        // Everything outside [strategy] is wired up at startup
+        let mut rest = serde_json::to_value(&config)?;
+        let mut current_rest = serde_json::to_value(&self.current)?;
+        rest["strategy"] = serde_json::Value::Null;
+        current_rest["strategy"] = serde_json::Value::Null;
+        if rest != current_rest {
+            return Err(DexError::Config(format!("{} changed outside [strategy]; restart the bot to apply that", self.path)));
+        }
+        if before == after {
+            return Ok(None);
+        }
+
+        if let Some((orders_per_side, size_per_side)) = config.strategy.footprint() {
+            RiskLimits::load()?.check_quotes(orders_per_side, size_per_side)?;
+        }
+        strategy.reconfigure(&config.strategy)?;
+
+        info!(target: "audit", path = %self.path, before = %before, after = %after, "Strategy config changed");
+        self.current = config;
+        Ok(Some(format!("Applied new {} parameters from {}", self.current.strategy.kind(), self.path)))
+    }
+}
+
+fn modified(path: &str) -> Option<SystemTime> {
+    fs::metadata(path).and_then(|metadata| metadata.modified()).ok()
+}
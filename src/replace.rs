@@ -0,0 +1,122 @@
+use crate::error::{DexError, Result};
+use crate::rpc;
+use ethers::{
+    providers::Middleware,
+    types::{
+        transaction::eip2718::TypedTransaction, Address, Bytes, Eip1559TransactionRequest,
+        Transaction, TransactionReceipt, TransactionRequest, H256, U256,
+    },
+};
+use tracing::info;
+
+/// Gas limit of a plain value transfer
+const TRANSFER_GAS: u64 = 21_000;
+
+/// Increase every fee field of a transaction by `percent`, rounding up
+pub fn bump_fees(tx: &mut TypedTransaction, percent: u64) {
+    let bump = |fee: Option<U256>| fee.map(|fee| (fee * (100 + percent) + 99) / 100);
+
+    match tx {
+        TypedTransaction::Legacy(request) => {
+            request.gas_price = bump(request.gas_price);
+        }
+        TypedTransaction::Eip2930(request) => {
+            request.tx.gas_price = bump(request.tx.gas_price);
+        }
+        TypedTransaction::Eip1559(request) => {
+            request.max_fee_per_gas = bump(request.max_fee_per_gas);
+            request.max_priority_fee_per_gas = bump(request.max_priority_fee_per_gas);
+        }
+    }
+}
+
+/// Rebuild a sendable request from a transaction fetched from the node
+pub fn to_request(tx: &Transaction) -> TypedTransaction {
+    let chain_id = tx.chain_id.map(|id| id.as_u64());
+
+    if tx.transaction_type.map(|t| t.as_u64()) == Some(2) {
+        let mut request = Eip1559TransactionRequest::new()
+            .from(tx.from)
+            .gas(tx.gas)
+            .value(tx.value)
+            .data(tx.input.clone())
+            .nonce(tx.nonce)
+            .access_list(tx.access_list.clone().unwrap_or_default());
+        request.to = tx.to.map(Into::into);
+        request.max_fee_per_gas = tx.max_fee_per_gas;
+        request.max_priority_fee_per_gas = tx.max_priority_fee_per_gas;
+        request.chain_id = chain_id.map(Into::into);
+        TypedTransaction::Eip1559(request)
+    } else {
+        let mut request = TransactionRequest::new()
+            .from(tx.from)
+            .gas(tx.gas)
+            .value(tx.value)
+            .data(tx.input.clone())
+            .nonce(tx.nonce);
+        request.to = tx.to.map(Into::into);
+        request.gas_price = tx.gas_price;
+        request.chain_id = chain_id.map(Into::into);
+        TypedTransaction::Legacy(request)
+    }
+}
+
+/// Build a 0-value self-transfer that takes the place of `tx` at the same nonce
+pub fn to_cancellation(tx: &Transaction) -> TypedTransaction {
+    let mut request = to_request(tx);
+    request
+        .set_to(tx.from)
+        .set_value(U256::zero())
+        .set_data(Bytes::default())
+        .set_gas(TRANSFER_GAS);
+    request
+}
+
+/// Fetch a transaction and make sure it can still be replaced by `sender`
+pub async fn pending_transaction<M>(client: &M, tx_hash: H256, sender: Address) -> Result<Transaction>
+where
+    M: Middleware + 'static,
+{
+    let tx = client
+        .get_transaction(tx_hash)
+        .await.map_err(|e| DexError::middleware(&e))?
+        .ok_or_else(|| DexError::Input(format!("Transaction {:?} not found", tx_hash)))?;
+
+    if tx.block_number.is_some() {
+        return Err(DexError::Input(format!("Transaction {:?} is already mined", tx_hash)));
+    }
+    if tx.from != sender {
+        return Err(DexError::Input(format!(
+            "Transaction {:?} was sent by {:?}, not by the signing key {:?}",
+            tx_hash,
+            tx.from,
+            sender
+        )));
+    }
+
+    Ok(tx)
+}
+
+/// Broadcast a replacement for a pending transaction and wait for it to be mined
+pub async fn replace<M>(
+    client: &M,
+    original: &Transaction,
+    mut replacement: TypedTransaction,
+    bump_percent: u64,
+    confirmations: usize,
+) -> Result<Option<TransactionReceipt>>
+where
+    M: Middleware + 'static,
+{
+    rpc::ensure_writable()?;
+    bump_fees(&mut replacement, bump_percent);
+    info!(
+        "Replacing transaction {:?} at nonce {} with fees bumped by {}%",
+        original.hash, original.nonce, bump_percent
+    );
+
+    let pending = client.send_transaction(replacement, None).await.map_err(|e| DexError::middleware(&e))?;
+    info!("Replacement transaction hash: {:?}", *pending);
+
+    Ok(pending.confirmations(confirmations).await?)
+}
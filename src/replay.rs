@@ -0,0 +1,223 @@
+use crate::error::{DexError, Result};
+use crate::indexer::EventStore;
+use crate::snapshot::BookSnapshot;
+use ethers::types::{Address, U256};
+use serde::Serialize;
+use std::collections::BTreeMap;
+
+/// A resting order in the replayed book
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ReplayOrder {
+    pub id: U256,
+    pub trader: Address,
+    pub amount: U256,
+    pub price: U256,
+    pub is_buy: bool,
+}
+
+/// An indexed event applied to the replayed book
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "event")]
+pub enum ReplayEvent {
+    Placed { block: u64, order: ReplayOrder },
+    Matched { block: u64, buy_order_id: U256, sell_order_id: U256, amount: U256, price: U256 },
+    Cancelled { block: u64, order_id: U256 },
+}
+
+/// Rebuilds one pair's book at historical blocks from the event index.
+///
+/// Market orders emit no events, so the orders they consume are invisible to the index.
+/// Starting from a snapshot re-anchors the book to what `getOrderBook` returned at that
+/// block; replaying across market orders after it still drifts until the next snapshot.
+/// Within a block, placements are applied before matches and matches before cancellations.
+pub struct Replay<'a> {
+    store: &'a EventStore,
+    base_token: Address,
+    quote_token: Address,
+    block: u64,
+    /// Keyed by ID, which is also the order the contract matches in
+    orders: BTreeMap<U256, ReplayOrder>,
+}
+
+impl<'a> Replay<'a> {
+    /// An empty book before the first indexed block. The index has to cover the pair's
+    /// whole history for the result to be complete.
+    pub fn new(store: &'a EventStore, base_token: Address, quote_token: Address) -> Self {
+        Self {
+            store,
+            base_token,
+            quote_token,
+            block: 0,
+            orders: BTreeMap::new(),
+        }
+    }
+
+    /// The book at `snapshot.block`, with order IDs from the index and amounts from the
+    /// snapshot. Indexed orders missing from the snapshot were consumed by market orders.
+    pub fn from_snapshot(store: &'a EventStore, snapshot: &BookSnapshot) -> Result<Self> {
+        if snapshot.dex != store.dex {
+            return Err(DexError::Input(format!("Snapshot is of DEX {:?}, not {:?}", snapshot.dex, store.dex)));
+        }
+        let mut replay = Self::new(store, snapshot.base_token, snapshot.quote_token);
+        replay.advance_to(snapshot.block)?;
+
+        let mut anchored = BTreeMap::new();
+        for (is_buy, entries) in [(true, &snapshot.bids), (false, &snapshot.asks)] {
+            // getOrderBook lists each side in ID order, so walk both lists together
+            let mut indexed = replay.orders.values().filter(|order| order.is_buy == is_buy);
+            for (price, amount) in entries {
+                let order = indexed
+                    .by_ref()
+                    .find(|order| order.price == *price && order.amount >= *amount)
+                    .ok_or_else(|| {
+                        DexError::Input(format!(
+                            "Snapshot {} order at price {} has no matching indexed order; is the index complete up to block {}?",
+                            if is_buy { "buy" } else { "sell" },
+                            price,
+                            snapshot.block
+                        ))
+                    })?;
+                anchored.insert(order.id, ReplayOrder { amount: *amount, ..order.clone() });
+            }
+        }
+        replay.orders = anchored;
+        Ok(replay)
+    }
+
+    pub fn block(&self) -> u64 {
+        self.block
+    }
+
+    pub fn orders(&self) -> impl Iterator<Item = &ReplayOrder> {
+        self.orders.values()
+    }
+
+    /// The book in the shape `getOrderBook` returns, for comparing against snapshots
+    pub fn to_snapshot(&self, price_precision: U256) -> BookSnapshot {
+        let side = |is_buy: bool| {
+            self.orders()
+                .filter(|order| order.is_buy == is_buy)
+                .map(|order| (order.price, order.amount))
+                .collect()
+        };
+        BookSnapshot {
+            dex: self.store.dex,
+            base_token: self.base_token,
+            quote_token: self.quote_token,
+            block: self.block,
+            price_precision,
+            bids: side(true),
+            asks: side(false),
+        }
+    }
+
+    /// Next block after the current one with an event for this pair, if any is indexed
+    pub fn next_active_block(&self) -> Option<u64> {
+        let placed = self
+            .store
+            .orders
+            .iter()
+            .filter(|o| o.base_token == self.base_token && o.quote_token == self.quote_token)
+            .map(|o| o.block);
+        let matched = self
+            .store
+            .trades
+            .iter()
+            .filter(|t| t.base_token == self.base_token && t.quote_token == self.quote_token)
+            .map(|t| t.block);
+        let cancelled = self
+            .store
+            .cancellations
+            .iter()
+            .filter(|c| self.orders.contains_key(&c.order_id))
+            .map(|c| c.block);
+        placed.chain(matched).chain(cancelled).filter(|&block| block > self.block).min()
+    }
+
+    /// Apply every event after the current block up to and including `block`
+    pub fn advance_to(&mut self, block: u64) -> Result<Vec<ReplayEvent>> {
+        if block < self.block {
+            return Err(DexError::Input(format!("Cannot replay backwards from block {} to {}", self.block, block)));
+        }
+        if block > self.store.last_block {
+            return Err(DexError::Input(format!("Block {} is past the end of the index (block {})", block, self.store.last_block)));
+        }
+
+        let from = self.block;
+        let in_range = |b: u64| b > from && b <= block;
+        let mut events = Vec::new();
+
+        for order in self.store.orders.iter().filter(|o| in_range(o.block)) {
+            if order.base_token != self.base_token || order.quote_token != self.quote_token {
+                continue;
+            }
+            events.push(ReplayEvent::Placed {
+                block: order.block,
+                order: ReplayOrder {
+                    id: order.order_id,
+                    trader: order.trader,
+                    amount: order.amount,
+                    price: order.price,
+                    is_buy: order.is_buy,
+                },
+            });
+        }
+        for trade in self.store.trades.iter().filter(|t| in_range(t.block)) {
+            if trade.base_token != self.base_token || trade.quote_token != self.quote_token {
+                continue;
+            }
+            events.push(ReplayEvent::Matched {
+                block: trade.block,
+                buy_order_id: trade.buy_order_id,
+                sell_order_id: trade.sell_order_id,
+                amount: trade.matched(),
+                price: trade.price,
+            });
+        }
+        for cancellation in self.store.cancellations.iter().filter(|c| in_range(c.block)) {
+            events.push(ReplayEvent::Cancelled { block: cancellation.block, order_id: cancellation.order_id });
+        }
+        // Stable sort keeps placements, then matches, then cancellations within a block
+        events.sort_by_key(ReplayEvent::block);
+
+        let mut applied = Vec::with_capacity(events.len());
+        for event in events {
+            match &event {
+                ReplayEvent::Placed { order, .. } => {
+                    self.orders.insert(order.id, order.clone());
+                }
+                ReplayEvent::Matched { buy_order_id, sell_order_id, amount, .. } => {
+                    self.reduce(*buy_order_id, *amount);
+                    self.reduce(*sell_order_id, *amount);
+                }
+                ReplayEvent::Cancelled { order_id, .. } => {
+                    // Cancellations are indexed for every pair; skip other pairs' orders
+                    if self.orders.remove(order_id).is_none() {
+                        continue;
+                    }
+                }
+            }
+            applied.push(event);
+        }
+
+        self.block = block;
+        Ok(applied)
+    }
+
+    fn reduce(&mut self, id: U256, amount: U256) {
+        if let Some(order) = self.orders.get_mut(&id) {
+            order.amount = order.amount.saturating_sub(amount);
+            if order.amount.is_zero() {
+                self.orders.remove(&id);
+            }
+        }
+    }
+}
+
+impl ReplayEvent {
+    pub fn block(&self) -> u64 {
+        match self {
+            ReplayEvent::Placed { block, .. } | ReplayEvent::Matched { block, .. } | ReplayEvent::Cancelled { block, .. } => *block,
+        }
+    }
+}
@@ -0,0 +1,154 @@
+use async_trait::async_trait;
+use ethers::providers::{JsonRpcClient, RpcError};
+use rand::Rng;
+use serde::{de::DeserializeOwned, Serialize};
+use std::fmt::Debug;
+use std::sync::Mutex;
+use std::time::Duration;
+use tokio::time::{sleep, Instant};
+use tracing::warn;
+
+/// Longest single backoff between retries
+const MAX_BACKOFF: Duration = Duration::from_secs(30);
+
+/// JSON-RPC error codes providers use for rate limiting
+const RATE_LIMIT_CODES: &[i64] = &[429, -32005, -32029];
+
+/// JSON-RPC methods that are only retried when the node explicitly rate limited them,
+/// since a timeout may mean the transaction was accepted after all
+const SEND_METHODS: &[&str] = &["eth_sendRawTransaction", "eth_sendTransaction"];
+
+/// Spaces requests out so no more than `rps` are issued per second
+#[derive(Debug)]
+struct RateLimiter {
+    interval: Duration,
+    next_slot: Mutex<Instant>,
+}
+
+impl RateLimiter {
+    fn new(rps: u32) -> Self {
+        Self {
+            interval: Duration::from_secs(1) / rps,
+            next_slot: Mutex::new(Instant::now()),
+        }
+    }
+
+    async fn acquire(&self) {
+        let wait = {
+            let mut next_slot = self.next_slot.lock().unwrap();
+            let now = Instant::now();
+            let slot = (*next_slot).max(now);
+            *next_slot = slot + self.interval;
+            slot - now
+        };
+        if !wait.is_zero() {
+            sleep(wait).await;
+        }
+    }
+}
+
+/// A transport wrapper that retries transient failures with jittered exponential
+/// backoff and caps the request rate
+#[derive(Debug)]
+pub struct RetryClient<C> {
+    inner: C,
+    max_retries: u32,
+    initial_backoff: Duration,
+    limiter: Option<RateLimiter>,
+}
+
+impl<C> RetryClient<C> {
+    /// Wrap `inner`; `max_rps` of 0 disables rate limiting
+    pub fn new(inner: C, max_retries: u32, initial_backoff: Duration, max_rps: u32) -> Self {
+        Self {
+            inner,
+            max_retries,
+            initial_backoff,
+            limiter: (max_rps > 0).then(|| RateLimiter::new(max_rps)),
+        }
+    }
+
+    pub fn inner(&self) -> &C {
+        &self.inner
+    }
+
+    /// Half the exponential delay is fixed, the other half is random
+    fn backoff(&self, attempt: u32) -> Duration {
+        let exponential = self
+            .initial_backoff
+            .saturating_mul(1u32 << attempt.min(16))
+            .min(MAX_BACKOFF);
+        let half = exponential / 2;
+        half + half.mul_f64(rand::thread_rng().gen::<f64>())
+    }
+}
+
+fn is_rate_limited<E: RpcError>(error: &E) -> bool {
+    if let Some(response) = error.as_error_response() {
+        return RATE_LIMIT_CODES.contains(&response.code)
+            || response.message.to_lowercase().contains("rate limit");
+    }
+    let message = error.to_string().to_lowercase();
+    message.contains("429") || message.contains("too many requests")
+}
+
+fn is_transient<E: RpcError>(error: &E) -> bool {
+    if is_rate_limited(error) {
+        return true;
+    }
+    if error.as_error_response().is_some() {
+        return false;
+    }
+    let message = error.to_string().to_lowercase();
+    [
+        "timed out",
+        "timeout",
+        "connection reset",
+        "connection closed",
+        "connection refused",
+        "error sending request",
+        "502",
+        "503",
+        "504",
+    ]
+    .iter()
+    .any(|pattern| message.contains(pattern))
+}
+
+#[async_trait]
+impl<C: JsonRpcClient> JsonRpcClient for RetryClient<C> {
+    type Error = C::Error;
+
+    async fn request<T, R>(&self, method: &str, params: T) -> Result<R, Self::Error>
+    where
+        T: Debug + Serialize + Send + Sync,
+        R: DeserializeOwned + Send,
+    {
+        let is_send = SEND_METHODS.contains(&method);
+        let mut attempt = 0;
+
+        loop {
+            if let Some(limiter) = &self.limiter {
+                limiter.acquire().await;
+            }
+
+            match self.inner.request(method, &params).await {
+                Ok(response) => return Ok(response),
+                Err(e) => {
+                    let retry = if is_send { is_rate_limited(&e) } else { is_transient(&e) };
+                    if !retry || attempt >= self.max_retries {
+                        return Err(e);
+                    }
+
+                    let delay = self.backoff(attempt);
+                    attempt += 1;
+                    warn!(
+                        "{} failed ({}), retry {}/{} in {:?}",
+                        method, e, attempt, self.max_retries, delay
+                    );
+                    sleep(delay).await;
+                }
+            }
+        }
+    }
+}
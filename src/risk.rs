@@ -0,0 +1,437 @@
+use crate::bindings::{OrderBookDEX, ORDERBOOKDEX_ABI};
+use crate::bot::Executor;
+use crate::error::{DexError, Result};
+use crate::expiry;
+use crate::indexer::{self, EventStore, DEFAULT_CHUNK_SIZE};
+use crate::output::decimal;
+use crate::pairs::PairParams;
+use crate::portfolio::{self, u256_to_f64, PositionTracker};
+use crate::rpc::RpcProvider;
+use crate::strategy::{BookView, Fill, Intent, OpenOrder, PendingActivity};
+use anyhow::Context;
+use async_trait::async_trait;
+use ethers::types::{Address, U256};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::Path;
+use std::sync::Arc;
+use std::time::SystemTime;
+
+const RISK_LIMITS_PATH: &str = "config/risk.json";
+
+const SECS_PER_DAY: u64 = 86_400;
+
+/// Limits every order is checked against before it is sent, from the CLI or a bot.
+/// Unset limits are off. Notional and loss are in raw quote units of the order's pair.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct RiskLimits {
+    /// Open orders across all pairs, including the new one
+    #[serde(default)]
+    pub max_open_orders: Option<usize>,
+    /// Quote notional of open orders in one pair, including the new one
+    #[serde(default)]
+    pub max_pair_notional: Option<U256>,
+    /// Net base position in one pair if the new order and every open order on its side fill
+    #[serde(default)]
+    pub max_position: Option<U256>,
+    /// Realized loss in one pair since 00:00 UTC, net of fees; once reached, new orders
+    /// in the pair are refused until the next day
+    #[serde(default)]
+    pub max_daily_loss: Option<U256>,
+    /// Block to start indexing fills from when no index exists yet
+    #[serde(default)]
+    pub index_from_block: u64,
+}
+
+impl RiskLimits {
+    pub fn load() -> Result<Self> {
+        if !Path::new(RISK_LIMITS_PATH).exists() {
+            return Ok(Self::default());
+        }
+        let content = fs::read_to_string(RISK_LIMITS_PATH)
+            .with_context(|| format!("Failed to read {}", RISK_LIMITS_PATH))?;
+        Ok(serde_json::from_str(&content)?)
+    }
+
+    pub fn save(&self) -> Result<()> {
+        fs::create_dir_all("config")?;
+        fs::write(RISK_LIMITS_PATH, serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.max_open_orders.is_none()
+            && self.max_pair_notional.is_none()
+            && self.max_position.is_none()
+            && self.max_daily_loss.is_none()
+    }
+
+    /// Refuse quoting that breaches a limit on its own: `orders_per_side` orders on each
+    /// side holding `size_per_side` base, before counting anything already open
+    pub fn check_quotes(&self, orders_per_side: usize, size_per_side: U256) -> Result<()> {
+        if let Some(max) = self.max_open_orders {
+            if orders_per_side * 2 > max {
+                return Err(DexError::Input(format!("Risk: {} quotes exceed the limit of {} open orders", orders_per_side * 2, max)));
+            }
+        }
+        if let Some(max) = self.max_position {
+            if size_per_side > max {
+                return Err(DexError::Input(format!("Risk: {} base quoted per side exceeds the position limit of {}", size_per_side, max)));
+            }
+        }
+        Ok(())
+    }
+
+    /// Refuse an order of `amount` base on top of `exposure`. `notional` is the order's
+    /// quote value, `None` for market orders, which never rest and so only count toward
+    /// position and loss.
+    pub fn check_order(&self, exposure: &Exposure, amount: U256, notional: Option<U256>, is_buy: bool) -> Result<()> {
+        if let (Some(max), Some(_)) = (self.max_open_orders, notional) {
+            if exposure.open_orders + 1 > max {
+                return Err(DexError::Input(format!("Risk: {} open orders already, the limit is {}", exposure.open_orders, max)));
+            }
+        }
+        if let (Some(max), Some(notional)) = (self.max_pair_notional, notional) {
+            let notional = exposure.pair_notional + notional;
+            if notional > max {
+                return Err(DexError::Input(format!("Risk: open notional in the pair would reach {}, the limit is {}", notional, max)));
+            }
+        }
+        if let Some(max) = self.max_position {
+            let amount = u256_to_f64(amount);
+            let worst = if is_buy { exposure.worst_position(amount, 0.0) } else { exposure.worst_position(0.0, amount) };
+            if worst > u256_to_f64(max) {
+                return Err(DexError::Input(format!("Risk: position in the pair could reach {:.0}, the limit is {}", worst, max)));
+            }
+        }
+        if let (Some(max), Some(pnl)) = (self.max_daily_loss, exposure.daily_pnl) {
+            if pnl < 0.0 && -pnl >= u256_to_f64(max) {
+                return Err(DexError::Input(format!("Risk: {:.0} lost in the pair today, the daily limit is {}", -pnl, max)));
+            }
+        }
+        Ok(())
+    }
+
+    /// Position and loss limits need the trader's fills, which only the event index has
+    fn needs_fills(&self) -> bool {
+        self.max_position.is_some() || self.max_daily_loss.is_some()
+    }
+}
+
+/// What a trader currently uses of each limit in one pair
+#[derive(Debug, Clone, Serialize)]
+pub struct Exposure {
+    pub base_token: Address,
+    pub quote_token: Address,
+    /// Across all pairs
+    pub open_orders: usize,
+    #[serde(serialize_with = "decimal")]
+    pub pair_notional: U256,
+    /// Net base position from indexed fills, positive long; `None` when not computed
+    pub position: Option<f64>,
+    /// Base amount resting in open buy and sell orders in the pair
+    pub open_buys: f64,
+    pub open_sells: f64,
+    /// Realized PnL in the pair since 00:00 UTC in quote units, net of fees; `None` when
+    /// not computed
+    pub daily_pnl: Option<f64>,
+}
+
+impl Exposure {
+    /// Largest absolute position reachable if every open order on one side fills, plus
+    /// `extra` base on that side
+    pub fn worst_position(&self, extra_buy: f64, extra_sell: f64) -> f64 {
+        let position = self.position.unwrap_or_default();
+        (position + self.open_buys + extra_buy).abs().max((position - self.open_sells - extra_sell).abs())
+    }
+}
+
+/// Checks orders against [`RiskLimits`] using the trader's live open orders and indexed
+/// fills. The index is kept in sync incrementally, so repeated checks stay cheap.
+pub struct RiskEngine {
+    provider: Arc<RpcProvider>,
+    contract: OrderBookDEX<RpcProvider>,
+    trader: Address,
+    limits: RiskLimits,
+    store: Option<EventStore>,
+    /// UTC day number and the first block in it
+    day_start: Option<(u64, u64)>,
+    /// Limit orders checked but not on chain yet, as `(base, quote, amount, price, is_buy)`
+    pending: Vec<(Address, Address, U256, U256, bool)>,
+}
+
+impl RiskEngine {
+    pub fn new(provider: Arc<RpcProvider>, dex: Address, trader: Address, limits: RiskLimits) -> Self {
+        let contract = OrderBookDEX::new(dex, provider.clone());
+        Self { provider, contract, trader, limits, store: None, day_start: None, pending: Vec::new() }
+    }
+
+    /// Count a limit order that passed [`check`](Self::check) as open in later checks,
+    /// for orders sent together before any of them lands
+    pub fn assume_placed(&mut self, base_token: Address, quote_token: Address, amount: U256, price: U256, is_buy: bool) {
+        self.pending.push((base_token, quote_token, amount, price, is_buy));
+    }
+
+    pub fn limits(&self) -> &RiskLimits {
+        &self.limits
+    }
+
+    pub async fn exposure(&mut self, base_token: Address, quote_token: Address) -> Result<Exposure> {
+        Ok(self.measure(base_token, quote_token).await?.0)
+    }
+
+    async fn measure(&mut self, base_token: Address, quote_token: Address) -> Result<(Exposure, PairParams)> {
+        let pair = PairParams::fetch(&self.contract, base_token, quote_token).await?;
+
+        let open_ids = self.contract.get_user_orders(self.trader).call().await?;
+        let mut pair_notional = U256::zero();
+        let (mut open_buys, mut open_sells) = (0.0, 0.0);
+        for id in &open_ids {
+            let (_, _, base, quote, amount, price, is_buy, is_active, _) = self.contract.orders(*id).call().await?;
+            if !is_active || (base, quote) != (base_token, quote_token) {
+                continue;
+            }
+            pair_notional += pair.quote_amount(amount, price);
+            if is_buy {
+                open_buys += u256_to_f64(amount);
+            } else {
+                open_sells += u256_to_f64(amount);
+            }
+        }
+
+        for (base, quote, amount, price, is_buy) in &self.pending {
+            if (*base, *quote) != (base_token, quote_token) {
+                continue;
+            }
+            pair_notional += pair.quote_amount(*amount, *price);
+            if *is_buy {
+                open_buys += u256_to_f64(*amount);
+            } else {
+                open_sells += u256_to_f64(*amount);
+            }
+        }
+
+        let (position, daily_pnl) = if self.limits.needs_fills() {
+            let (tracker, daily) = self.pair_fills(base_token, quote_token).await?;
+            let precision = u256_to_f64(pair.price_precision).max(1.0);
+            (Some(tracker.position), Some(daily / precision))
+        } else {
+            (None, None)
+        };
+
+        let exposure = Exposure {
+            base_token,
+            quote_token,
+            open_orders: open_ids.len() + self.pending.len(),
+            pair_notional,
+            position,
+            open_buys,
+            open_sells,
+            daily_pnl,
+        };
+        Ok((exposure, pair))
+    }
+
+    /// Replay the trader's fills in the pair; returns the position and the PnL realized
+    /// since the start of the UTC day, in price units times base amount
+    async fn pair_fills(&mut self, base_token: Address, quote_token: Address) -> Result<(PositionTracker, f64)> {
+        if self.store.is_none() {
+            self.store = Some(EventStore::load(self.contract.address(), self.limits.index_from_block)?);
+        }
+        let store = self.store.as_mut().expect("store loaded above");
+        store.sync(self.provider.as_ref(), &ORDERBOOKDEX_ABI, DEFAULT_CHUNK_SIZE).await?;
+
+        let day = expiry::unix_secs(SystemTime::now()) / SECS_PER_DAY;
+        let day_block = match self.day_start {
+            Some((cached, block)) if cached == day => block,
+            _ => {
+                let block = indexer::block_at(self.provider.as_ref(), day * SECS_PER_DAY).await?;
+                self.day_start = Some((day, block));
+                block
+            }
+        };
+
+        let store = self.store.as_ref().expect("store loaded above");
+        Ok(portfolio::pair_tracker(store, self.trader, base_token, quote_token, day_block))
+    }
+
+    /// Refuse an order that would breach a limit. `price` is `None` for market orders,
+    /// which never rest and so only count toward position and loss.
+    pub async fn check(
+        &mut self,
+        base_token: Address,
+        quote_token: Address,
+        amount: U256,
+        price: Option<U256>,
+        is_buy: bool,
+    ) -> Result<()> {
+        if self.limits.is_empty() {
+            return Ok(());
+        }
+        let (exposure, pair) = self.measure(base_token, quote_token).await?;
+        self.limits.check_order(&exposure, amount, price.map(|price| pair.quote_amount(amount, price)), is_buy)
+    }
+}
+
+/// Runs a bot's placements through a [`RiskEngine`] before handing them to `inner`.
+/// Cancels always go through since they only reduce risk.
+pub struct RiskExecutor<E> {
+    inner: E,
+    engine: RiskEngine,
+    base_token: Address,
+    quote_token: Address,
+}
+
+impl<E: Executor> RiskExecutor<E> {
+    pub fn new(inner: E, engine: RiskEngine, base_token: Address, quote_token: Address) -> Self {
+        Self { inner, engine, base_token, quote_token }
+    }
+}
+
+#[async_trait]
+impl<E: Executor> Executor for RiskExecutor<E> {
+    async fn book(&mut self) -> Result<BookView> {
+        self.inner.book().await
+    }
+
+    async fn poll_fills(&mut self) -> Result<Vec<Fill>> {
+        self.inner.poll_fills().await
+    }
+
+    async fn open_orders(&mut self) -> Result<Vec<OpenOrder>> {
+        self.inner.open_orders().await
+    }
+
+    async fn poll_pending(&mut self) -> Result<Vec<PendingActivity>> {
+        self.inner.poll_pending().await
+    }
+
+    async fn place(&mut self, price: U256, amount: U256, is_buy: bool) -> Result<u64> {
+        self.engine.check(self.base_token, self.quote_token, amount, Some(price), is_buy).await?;
+        self.inner.place(price, amount, is_buy).await
+    }
+
+    async fn cancel(&mut self, order_id: u64) -> Result<()> {
+        self.inner.cancel(order_id).await
+    }
+
+    async fn execute(&mut self, intents: &[Intent]) -> Vec<Result<Option<u64>>> {
+        // Cancels go first so the orders they free count toward the checks that follow.
+        // Each placement is checked against the book as it stands after the ones before it.
+        let mut results: Vec<Option<Result<Option<u64>>>> = intents.iter().map(|_| None).collect();
+        let cancels: Vec<(usize, Intent)> = intents
+            .iter()
+            .enumerate()
+            .filter(|(_, intent)| matches!(intent, Intent::Cancel { .. }))
+            .map(|(i, intent)| (i, intent.clone()))
+            .collect();
+        let batch: Vec<Intent> = cancels.iter().map(|(_, intent)| intent.clone()).collect();
+        for ((i, _), result) in cancels.iter().zip(self.inner.execute(&batch).await) {
+            results[*i] = Some(result);
+        }
+
+        for (i, intent) in intents.iter().enumerate() {
+            if let Intent::Place { price, amount, is_buy } = intent {
+                results[i] = Some(self.place(*price, *amount, *is_buy).await.map(Some));
+            }
+        }
+        results.into_iter().map(|result| result.unwrap_or(Ok(None))).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn exposure(open_orders: usize, pair_notional: u64, position: f64, open_buys: f64, open_sells: f64) -> Exposure {
+        Exposure {
+            base_token: Address::repeat_byte(1),
+            quote_token: Address::repeat_byte(2),
+            open_orders,
+            pair_notional: U256::from(pair_notional),
+            position: Some(position),
+            open_buys,
+            open_sells,
+            daily_pnl: Some(0.0),
+        }
+    }
+
+    #[test]
+    fn worst_position_assumes_one_whole_side_fills() {
+        let exposure = exposure(0, 0, 10.0, 5.0, 30.0);
+        assert_eq!(exposure.worst_position(0.0, 0.0), 20.0);
+        assert_eq!(exposure.worst_position(20.0, 0.0), 35.0);
+        assert_eq!(exposure.worst_position(0.0, 5.0), 25.0);
+
+        let flat = Exposure { position: None, ..exposure };
+        assert_eq!(flat.worst_position(0.0, 0.0), 30.0);
+    }
+
+    #[test]
+    fn no_limits_allow_anything() {
+        RiskLimits::default().check_order(&exposure(1000, 1 << 60, 1e30, 0.0, 0.0), U256::MAX, Some(U256::MAX), true).unwrap();
+    }
+
+    #[test]
+    fn open_order_limit_counts_the_new_order() {
+        let limits = RiskLimits { max_open_orders: Some(3), ..RiskLimits::default() };
+        limits.check_order(&exposure(2, 0, 0.0, 0.0, 0.0), U256::one(), Some(U256::one()), true).unwrap();
+        let error = limits.check_order(&exposure(3, 0, 0.0, 0.0, 0.0), U256::one(), Some(U256::one()), true).unwrap_err();
+        assert!(error.to_string().contains("open orders"), "{}", error);
+    }
+
+    #[test]
+    fn pair_notional_limit_adds_the_new_order() {
+        let limits = RiskLimits { max_pair_notional: Some(U256::from(100)), ..RiskLimits::default() };
+        limits.check_order(&exposure(0, 60, 0.0, 0.0, 0.0), U256::one(), Some(U256::from(40)), true).unwrap();
+        let error = limits.check_order(&exposure(0, 60, 0.0, 0.0, 0.0), U256::one(), Some(U256::from(41)), true).unwrap_err();
+        assert!(error.to_string().contains("notional"), "{}", error);
+    }
+
+    #[test]
+    fn market_orders_only_count_toward_position_and_loss() {
+        let limits = RiskLimits {
+            max_open_orders: Some(1),
+            max_pair_notional: Some(U256::one()),
+            max_position: Some(U256::from(50)),
+            ..RiskLimits::default()
+        };
+        let exposure = exposure(5, 1000, 0.0, 0.0, 0.0);
+        limits.check_order(&exposure, U256::from(50), None, true).unwrap();
+        let error = limits.check_order(&exposure, U256::from(51), None, true).unwrap_err();
+        assert!(error.to_string().contains("position"), "{}", error);
+    }
+
+    #[test]
+    fn position_limit_checks_the_side_the_order_adds_to() {
+        let limits = RiskLimits { max_position: Some(U256::from(100)), ..RiskLimits::default() };
+        let exposure = exposure(0, 0, 50.0, 40.0, 0.0);
+        limits.check_order(&exposure, U256::from(10), Some(U256::one()), true).unwrap();
+        assert!(limits.check_order(&exposure, U256::from(11), Some(U256::one()), true).is_err());
+        // Selling reduces a long position
+        limits.check_order(&exposure, U256::from(150), Some(U256::one()), false).unwrap();
+        assert!(limits.check_order(&exposure, U256::from(151), Some(U256::one()), false).is_err());
+    }
+
+    #[test]
+    fn daily_loss_limit_blocks_once_reached() {
+        let limits = RiskLimits { max_daily_loss: Some(U256::from(100)), ..RiskLimits::default() };
+        let mut exposure = exposure(0, 0, 0.0, 0.0, 0.0);
+        exposure.daily_pnl = Some(-99.0);
+        limits.check_order(&exposure, U256::one(), Some(U256::one()), true).unwrap();
+        exposure.daily_pnl = Some(-100.0);
+        let error = limits.check_order(&exposure, U256::one(), Some(U256::one()), true).unwrap_err();
+        assert!(error.to_string().contains("lost"), "{}", error);
+        // Profits never trip it
+        exposure.daily_pnl = Some(1e9);
+        limits.check_order(&exposure, U256::one(), Some(U256::one()), true).unwrap();
+    }
+
+    #[test]
+    fn quotes_are_checked_against_the_limits_on_their_own() {
+        let limits = RiskLimits { max_open_orders: Some(6), max_position: Some(U256::from(10)), ..RiskLimits::default() };
+        limits.check_quotes(3, U256::from(10)).unwrap();
+        assert!(limits.check_quotes(4, U256::from(10)).is_err());
+        assert!(limits.check_quotes(3, U256::from(11)).is_err());
+    }
+}
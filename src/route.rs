@@ -0,0 +1,141 @@
+use crate::arb::{self, Leg, Markets};
+use crate::strategy::to_f64;
+use ethers::types::{Address, U256};
+use serde::Serialize;
+
+/// One way of turning `input` of `from` into `to`: part of it through the direct pair
+/// and the rest through `via`
+#[derive(Debug, Clone, Serialize)]
+pub struct RoutePlan {
+    pub from: Address,
+    pub to: Address,
+    pub via: Address,
+    /// Amount of `from` actually spent; a buy can leave dust under one base unit of price
+    pub input: U256,
+    /// Share of the requested input sent through the direct pair, in percent
+    pub direct_pct: f64,
+    pub direct: Option<Leg>,
+    /// `from -> via` then `via -> to`; empty when everything goes direct
+    pub routed: Vec<Leg>,
+    pub output: U256,
+    /// Market orders plus the withdrawals that free their proceeds
+    pub transactions: usize,
+    /// In wei
+    pub gas_cost: U256,
+    /// Gas cost expressed in `to`, when a book prices the native token in it
+    pub gas_in_output: Option<U256>,
+    /// `output` less `gas_in_output`
+    pub net_output: U256,
+}
+
+impl RoutePlan {
+    pub fn legs(&self) -> impl Iterator<Item = &Leg> {
+        self.direct.iter().chain(self.routed.iter())
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct RouteQuote {
+    /// Everything through the direct pair, if its book can take the whole input
+    pub direct_only: Option<RoutePlan>,
+    /// Highest net output across the splits tried
+    pub best: RoutePlan,
+    /// Net gain of `best` over `direct_only`, in basis points
+    pub improvement_bps: Option<f64>,
+}
+
+/// Try sending `0, 1/steps, ..., all` of `input` through the direct `from -> to` book and
+/// the rest through `from -> via -> to`, and pick the split with the highest output net
+/// of gas. Market orders pay no trading fee, so gas is the only cost besides slippage.
+///
+/// Each leg is simulated against its own fresh copy of the book; a split that sends a
+/// leg below the pair's minimum order size or beyond its depth is skipped.
+#[allow(clippy::too_many_arguments)]
+pub fn quote(
+    markets: &Markets,
+    from: Address,
+    to: Address,
+    via: Address,
+    input: U256,
+    gas_price: U256,
+    gas_per_tx: u64,
+    steps: usize,
+) -> Option<RouteQuote> {
+    let steps = steps.max(1);
+    let plans: Vec<RoutePlan> = (0..=steps)
+        .filter_map(|step| {
+            let direct_input = input * U256::from(step) / U256::from(steps);
+            plan(markets, from, to, via, input, direct_input, gas_price, gas_per_tx)
+        })
+        .collect();
+
+    let direct_only = plans.iter().find(|plan| plan.routed.is_empty()).cloned();
+    // Ties go to the plan with fewer transactions
+    let best = plans
+        .into_iter()
+        .max_by(|a, b| a.net_output.cmp(&b.net_output).then(b.transactions.cmp(&a.transactions)))?;
+    let improvement_bps = direct_only.as_ref().and_then(|direct| {
+        let baseline = to_f64(direct.net_output);
+        (baseline > 0.0).then(|| (to_f64(best.net_output) - baseline) / baseline * 10_000.0)
+    });
+
+    Some(RouteQuote { direct_only, best, improvement_bps })
+}
+
+#[allow(clippy::too_many_arguments)]
+fn plan(
+    markets: &Markets,
+    from: Address,
+    to: Address,
+    via: Address,
+    input: U256,
+    direct_input: U256,
+    gas_price: U256,
+    gas_per_tx: u64,
+) -> Option<RoutePlan> {
+    let routed_input = input - direct_input;
+
+    let direct = if direct_input.is_zero() {
+        None
+    } else {
+        Some(arb::swap_leg(markets, from, to, direct_input)?)
+    };
+    let routed = if routed_input.is_zero() {
+        Vec::new()
+    } else {
+        let first = arb::swap_leg(markets, from, via, routed_input)?;
+        let second = arb::swap_leg(markets, via, to, first.output)?;
+        vec![first, second]
+    };
+
+    let output = direct.as_ref().map_or(U256::zero(), |leg| leg.output)
+        + routed.last().map_or(U256::zero(), |leg| leg.output);
+    let spent = direct.as_ref().map_or(U256::zero(), |leg| leg.input)
+        + routed.first().map_or(U256::zero(), |leg| leg.input);
+    let transactions = (direct.iter().count() + routed.len()) * 2;
+    let gas_cost = gas_price * U256::from(gas_per_tx) * U256::from(transactions);
+    let gas_in_output = native_value_in(markets, to, gas_cost);
+
+    Some(RoutePlan {
+        from,
+        to,
+        via,
+        input: spent,
+        direct_pct: to_f64(direct_input) / to_f64(input).max(1.0) * 100.0,
+        direct,
+        routed,
+        output,
+        transactions,
+        gas_cost,
+        gas_in_output,
+        net_output: output.saturating_sub(gas_in_output.unwrap_or_default()),
+    })
+}
+
+/// What `wei` of the native token is worth in `token`, by selling it through the book
+fn native_value_in(markets: &Markets, token: Address, wei: U256) -> Option<U256> {
+    if token == Address::zero() || wei.is_zero() {
+        return Some(wei);
+    }
+    arb::swap_leg(markets, Address::zero(), token, wei).map(|leg| leg.output)
+}
@@ -0,0 +1,552 @@
+use crate::bench::RpcProfile;
+use crate::cache::CacheClient;
+use crate::error::{DexError, Result};
+use crate::health;
+use crate::metrics::metrics;
+use crate::networks::{self, Network, NetworkRegistry};
+use crate::retry::RetryClient;
+use crate::aliases;
+use crate::wallets::{self, AccountSigner};
+use anyhow::anyhow;
+use async_trait::async_trait;
+use clap::Args;
+use ethers::{
+    providers::{Http, HttpClientError, JsonRpcClient, Middleware, Provider},
+    signers::{LocalWallet, Signer},
+    types::U64,
+    utils::{Anvil, AnvilInstance},
+};
+use futures::future::join_all;
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue};
+use reqwest::{ClientBuilder, Proxy, Url};
+use serde::{de::DeserializeOwned, Serialize};
+use std::env;
+use std::fmt::Debug;
+use std::process::{Command, Stdio};
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, Instant};
+use tracing::{info, warn};
+
+/// Endpoints this many blocks behind the highest one are treated as unhealthy
+const MAX_BLOCK_LAG: u64 = 5;
+
+/// JSON-RPC methods that must not be replayed against another endpoint
+const NON_IDEMPOTENT_METHODS: &[&str] = &["eth_sendRawTransaction", "eth_sendTransaction"];
+
+/// Transport used by every provider the binaries construct
+pub type RpcTransport = CacheClient<RetryClient<FailoverClient>>;
+
+/// Provider over [`RpcTransport`]
+pub type RpcProvider = Provider<RpcTransport>;
+
+/// Chain ID of Monad testnet
+pub const MONAD_TESTNET_CHAIN_ID: u64 = 10143;
+
+/// Proxy used when `--proxy` isn't given
+pub const PROXY_VAR: &str = "MONAD_PROXY";
+
+/// How long anvil gets to fetch the fork's starting state before giving up
+const FORK_STARTUP_TIMEOUT_MS: u64 = 60_000;
+
+/// Retry, rate limit and network settings applied to every provider
+#[derive(Debug, Clone, Args)]
+pub struct RpcArgs {
+    /// Retries for transient RPC errors (rate limits, timeouts, dropped connections)
+    #[arg(long, default_value_t = 5, global = true)]
+    pub max_retries: u32,
+
+    /// Initial retry backoff in milliseconds, doubled on every attempt
+    #[arg(long, default_value_t = 250, global = true)]
+    pub initial_backoff_ms: u64,
+
+    /// Maximum RPC requests per second (0 = unlimited)
+    #[arg(long, default_value_t = 0, global = true)]
+    pub max_rps: u32,
+
+    /// Network preset (monad-testnet, monad-devnet, local, or one saved with `monad-dex
+    /// network add`) supplying the chain ID, explorer, contract addresses and the RPC
+    /// endpoints used when a command has no --rpc-url
+    #[arg(long, global = true)]
+    pub network: Option<String>,
+
+    /// Chain ID the RPC endpoint must report before anything is signed (0 = accept any;
+    /// defaults to the network's)
+    #[arg(long, global = true)]
+    pub chain_id: Option<u64>,
+
+    /// Stored wallet (see `monad-dex wallet list`) to sign with when no --private-key is given
+    #[arg(long, global = true)]
+    pub account: Option<String>,
+
+    /// Act as this address, whose key is kept offline, when no --private-key or --account
+    /// is given; transactions can then only be prepared with --prepare
+    #[arg(long, global = true)]
+    pub cold_address: Option<String>,
+
+    /// Run against a local anvil fork of the RPC endpoint instead of the endpoint itself;
+    /// transactions land on the fork only and cost no real gas
+    #[arg(long, global = true)]
+    pub fork: bool,
+
+    /// Block to fork from (default: latest)
+    #[arg(long, global = true, requires = "fork")]
+    pub fork_block: Option<u64>,
+
+    /// Send every read to the RPC endpoint instead of answering repeated ones from a
+    /// short-lived cache; also refetches explorer ABIs
+    #[arg(long, global = true)]
+    pub no_cache: bool,
+
+    /// How long reads of the latest chain state are cached, in milliseconds (0 caches
+    /// only answers that can't change)
+    #[arg(long, default_value_t = 1000, global = true)]
+    pub cache_ttl_ms: u64,
+
+    /// Most per-item reads bulk commands run at once
+    #[arg(long, default_value_t = 16, global = true)]
+    pub max_concurrency: usize,
+
+    /// Send RPC traffic through this proxy, e.g. socks5h://127.0.0.1:9050 for Tor or
+    /// http://proxy.corp:3128 (default: $MONAD_PROXY)
+    #[arg(long, global = true)]
+    pub proxy: Option<String>,
+
+    /// Refuse anything that would sign or send a transaction; reads and estimates still work
+    #[arg(long, global = true)]
+    pub read_only: bool,
+}
+
+impl Default for RpcArgs {
+    fn default() -> Self {
+        Self {
+            max_retries: 5,
+            initial_backoff_ms: 250,
+            max_rps: 0,
+            network: None,
+            chain_id: None,
+            account: None,
+            cold_address: None,
+            fork: false,
+            fork_block: None,
+            no_cache: false,
+            cache_ttl_ms: 1000,
+            max_concurrency: 16,
+            proxy: None,
+            read_only: false,
+        }
+    }
+}
+
+static RPC_ARGS: OnceLock<RpcArgs> = OnceLock::new();
+
+/// The anvil node every provider connects to under `--fork`
+static FORK: Mutex<Option<AnvilInstance>> = Mutex::new(None);
+
+/// Shuts down the `--fork` node when dropped
+pub struct ForkGuard;
+
+impl Drop for ForkGuard {
+    fn drop(&mut self) {
+        if let Ok(mut fork) = FORK.lock() {
+            fork.take();
+        }
+    }
+}
+
+/// Set the settings used by [`connect`] and [`wallet`]; call once at startup and keep
+/// the returned guard alive until the command finishes
+pub fn configure(args: RpcArgs) -> ForkGuard {
+    let _ = RPC_ARGS.set(args);
+    ForkGuard
+}
+
+/// Whether `--fork` is on, so records of what was deployed or executed must not be
+/// written: none of it happened on the real network
+pub fn is_fork() -> bool {
+    RPC_ARGS.get().is_some_and(|args| args.fork)
+}
+
+/// Whether signing is off, by `--read-only` or because the selected network is marked
+/// read-only
+pub fn is_read_only() -> bool {
+    // The network can't change while running, and this is checked before every send
+    static NETWORK_READ_ONLY: OnceLock<bool> = OnceLock::new();
+    RPC_ARGS.get().is_some_and(|args| args.read_only)
+        || *NETWORK_READ_ONLY.get_or_init(|| network().is_ok_and(|network| network.read_only))
+}
+
+/// Refuse to sign or broadcast in read-only mode; every path that does either checks this
+pub fn ensure_writable() -> Result<()> {
+    if is_read_only() {
+        return Err(DexError::Config(format!(
+            "Read-only mode (--read-only or network {} marked read_only): refusing to sign or send transactions",
+            network_name()
+        )));
+    }
+    Ok(())
+}
+
+/// Name of the network selected with `--network`
+pub fn network_name() -> String {
+    RPC_ARGS
+        .get()
+        .and_then(|args| args.network.clone())
+        .unwrap_or_else(|| networks::DEFAULT_NETWORK.to_string())
+        .to_lowercase()
+}
+
+/// The network selected with `--network`
+pub fn network() -> Result<Network> {
+    NetworkRegistry::load()?.get(&network_name())
+}
+
+/// Chain ID the RPC endpoint must report, per `--chain-id` or else the network; 0 accepts any chain
+pub fn expected_chain_id() -> u64 {
+    match RPC_ARGS.get().and_then(|args| args.chain_id) {
+        Some(chain_id) => chain_id,
+        None => network().map_or(MONAD_TESTNET_CHAIN_ID, |network| network.chain_id),
+    }
+}
+
+/// The RPC URLs a command connects to: its `--rpc-url` when given, else the endpoints of
+/// the `--network` preset, which defaults to Monad testnet
+pub fn resolve_rpc_url(rpc_url: Option<&str>) -> Result<String> {
+    if let Some(rpc_url) = rpc_url {
+        return Ok(rpc_url.to_string());
+    }
+    let network = network()?;
+    if network.rpc_urls.is_empty() {
+        return Err(DexError::Config(format!(
+            "Network {} has no RPC URLs; pass --rpc-url or save them with `monad-dex network add`",
+            network_name()
+        )));
+    }
+    Ok(network.rpc_urls.join(","))
+}
+
+/// Whether repeated reads may be answered from a cache, i.e. `--no-cache` isn't set
+pub fn cache_enabled() -> bool {
+    !RPC_ARGS.get().is_some_and(|args| args.no_cache)
+}
+
+/// Most concurrent reads, per `--max-concurrency`
+pub fn max_concurrency() -> usize {
+    RPC_ARGS.get().cloned().unwrap_or_default().max_concurrency.max(1)
+}
+
+/// Proxy selected with `--proxy` or `$MONAD_PROXY`
+pub fn proxy_url() -> Option<String> {
+    RPC_ARGS
+        .get()
+        .and_then(|args| args.proxy.clone())
+        .or_else(|| env::var(PROXY_VAR).ok())
+        .filter(|proxy| !proxy.trim().is_empty())
+}
+
+/// HTTP client builder sending through the `--proxy`, if any
+pub fn client_builder() -> Result<ClientBuilder> {
+    let mut builder = reqwest::Client::builder();
+    if let Some(proxy) = proxy_url() {
+        let proxy = Proxy::all(&proxy).map_err(|e| DexError::Input(format!("Invalid proxy '{}': {}", proxy, e)))?;
+        builder = builder.proxy(proxy);
+    }
+    Ok(builder)
+}
+
+/// HTTP transport to `url` through the `--proxy`, sending the `--network` credentials
+/// if it is one of the network's endpoints
+pub fn http(url: &str) -> Result<Http> {
+    let parsed = Url::parse(url).map_err(|e| DexError::Input(format!("Invalid RPC URL '{}': {}", url, e)))?;
+    let mut builder = client_builder()?;
+
+    if let Some(auth) = network()?.auth_for(url) {
+        let mut headers = HeaderMap::new();
+        for (name, value) in auth.resolve()? {
+            let header = HeaderName::from_bytes(name.as_bytes()).map_err(|e| DexError::Config(format!("Invalid header name '{}': {}", name, e)))?;
+            let mut value = HeaderValue::from_str(&value).map_err(|e| DexError::Config(format!("Invalid value for header {}: {}", name, e)))?;
+            value.set_sensitive(true);
+            headers.insert(header, value);
+        }
+        builder = builder.default_headers(headers);
+    }
+
+    let client = builder.build().map_err(|e| DexError::Config(format!("Failed to build the HTTP client: {}", e)))?;
+    Ok(Http::new_with_client(parsed, client))
+}
+
+/// Endpoint of the anvil fork of `rpc_url`, started on first use. Every later
+/// [`connect`] shares the same fork, so state written by one step is visible to the next.
+fn fork_endpoint(rpc_url: &str, fork_block: Option<u64>) -> Result<String> {
+    let mut fork = FORK.lock().map_err(|_| anyhow!("Fork node lock poisoned"))?;
+    if let Some(anvil) = fork.as_ref() {
+        return Ok(anvil.endpoint());
+    }
+
+    let upstream = rpc_url
+        .split(',')
+        .map(str::trim)
+        .find(|url| !url.is_empty())
+        .ok_or_else(|| DexError::Input("No RPC URL to fork from".to_string()))?;
+    let installed = Command::new("anvil")
+        .arg("--version")
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status()
+        .is_ok_and(|status| status.success());
+    if !installed {
+        return Err(DexError::Config("--fork needs anvil on PATH; install Foundry (https://getfoundry.sh)".to_string()));
+    }
+
+    if proxy_url().is_some() {
+        warn!("anvil fetches the fork's state from {} directly, not through the proxy", upstream);
+    }
+    let mut anvil = Anvil::new().fork(upstream).timeout(FORK_STARTUP_TIMEOUT_MS);
+    if let Some(block) = fork_block {
+        anvil = anvil.fork_block_number(block);
+    }
+    info!(
+        "Forking {} at block {}; transactions will not reach the real network",
+        upstream,
+        fork_block.map_or("latest".to_string(), |block| block.to_string())
+    );
+    let anvil = anvil.spawn();
+    let endpoint = anvil.endpoint();
+    *fork = Some(anvil);
+    Ok(endpoint)
+}
+
+#[derive(Debug, Default, Clone)]
+struct Health {
+    failures: u32,
+    lagging: bool,
+    latency: Option<Duration>,
+}
+
+#[derive(Debug)]
+struct Endpoint {
+    url: String,
+    http: Http,
+    health: Mutex<Health>,
+}
+
+impl Endpoint {
+    fn health(&self) -> Health {
+        self.health.lock().unwrap().clone()
+    }
+
+    fn record_success(&self, latency: Duration) {
+        let mut health = self.health.lock().unwrap();
+        health.failures = 0;
+        health.latency = Some(latency);
+    }
+
+    fn record_failure(&self) {
+        self.health.lock().unwrap().failures += 1;
+    }
+}
+
+/// A JSON-RPC transport over several HTTP endpoints.
+///
+/// Requests go to the healthiest endpoint first. Transport failures mark the
+/// endpoint down and, for idempotent methods, the request is retried on the
+/// next endpoint in line.
+#[derive(Debug)]
+pub struct FailoverClient {
+    endpoints: Vec<Endpoint>,
+}
+
+impl FailoverClient {
+    pub fn new<S: AsRef<str>>(urls: &[S]) -> Result<Self> {
+        let endpoints = urls
+            .iter()
+            .map(|url| {
+                let url = url.as_ref().trim();
+                let http = http(url)?;
+                Ok(Endpoint {
+                    url: url.to_string(),
+                    http,
+                    health: Mutex::new(Health::default()),
+                })
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        if endpoints.is_empty() {
+            return Err(DexError::Input("At least one RPC URL is required".to_string()));
+        }
+
+        Ok(Self { endpoints })
+    }
+
+    /// Ping every endpoint, recording latency and flagging endpoints that lag behind
+    pub async fn health_check(&self) {
+        let results = join_all(self.endpoints.iter().map(|endpoint| async move {
+            let start = Instant::now();
+            let block = endpoint
+                .http
+                .request::<_, U64>("eth_blockNumber", ())
+                .await;
+            match block {
+                Ok(block) => {
+                    endpoint.record_success(start.elapsed());
+                    Some(block.as_u64())
+                }
+                Err(e) => {
+                    warn!("RPC endpoint {} failed health check: {}", endpoint.url, e);
+                    endpoint.record_failure();
+                    None
+                }
+            }
+        }))
+        .await;
+
+        let highest = results.iter().flatten().max().copied().unwrap_or_default();
+        for (endpoint, block) in self.endpoints.iter().zip(results) {
+            let lagging = block.is_some_and(|block| block + MAX_BLOCK_LAG < highest);
+            if lagging {
+                warn!("RPC endpoint {} is behind the chain head", endpoint.url);
+            }
+            endpoint.health.lock().unwrap().lagging = lagging;
+        }
+    }
+
+    fn ranked(&self) -> Vec<&Endpoint> {
+        let mut endpoints: Vec<&Endpoint> = self.endpoints.iter().collect();
+        endpoints.sort_by_key(|endpoint| {
+            let health = endpoint.health();
+            (
+                health.failures,
+                health.lagging,
+                health.latency.unwrap_or(Duration::MAX),
+            )
+        });
+        endpoints
+    }
+}
+
+#[async_trait]
+impl JsonRpcClient for FailoverClient {
+    type Error = HttpClientError;
+
+    async fn request<T, R>(&self, method: &str, params: T) -> Result<R, Self::Error>
+    where
+        T: Debug + Serialize + Send + Sync,
+        R: DeserializeOwned + Send,
+    {
+        let retryable = !NON_IDEMPOTENT_METHODS.contains(&method);
+        let mut last_error = None;
+
+        for endpoint in self.ranked() {
+            let start = Instant::now();
+            let result = endpoint.http.request(method, &params).await;
+            metrics()
+                .rpc_latency
+                .with_label_values(&[method])
+                .observe(start.elapsed().as_secs_f64());
+            match result {
+                Ok(response) => {
+                    endpoint.record_success(start.elapsed());
+                    health::ok(health::RPC);
+                    return Ok(response);
+                }
+                // The node answered; a JSON-RPC error is the same everywhere
+                Err(e @ HttpClientError::JsonRpcError(_)) => {
+                    health::ok(health::RPC);
+                    return Err(e);
+                }
+                Err(e) => {
+                    endpoint.record_failure();
+                    if !retryable {
+                        health::failed(health::RPC, &e);
+                        return Err(e);
+                    }
+                    warn!("{} failed on {}: {}, failing over", method, endpoint.url, e);
+                    metrics().rpc_failovers.inc();
+                    last_error = Some(e);
+                }
+            }
+        }
+
+        let error = last_error.expect("FailoverClient always has at least one endpoint");
+        health::failed(health::RPC, &error);
+        Err(error)
+    }
+}
+
+/// Build a provider from a comma-separated list of RPC URLs, or without one from the
+/// network's endpoints (see [`resolve_rpc_url`])
+pub async fn connect(rpc_url: Option<&str>) -> Result<RpcProvider> {
+    let args = RPC_ARGS.get().cloned().unwrap_or_default();
+    let resolved = resolve_rpc_url(rpc_url)?;
+    let rpc_url = if args.fork {
+        fork_endpoint(&resolved, args.fork_block)?
+    } else {
+        resolved
+    };
+
+    let urls: Vec<&str> = rpc_url.split(',').filter(|url| !url.trim().is_empty()).collect();
+    // Try endpoints in the order `doctor bench` last measured them, fastest first
+    let urls = RpcProfile::load().unwrap_or_default().order(&urls);
+    let client = FailoverClient::new(&urls)?;
+
+    if urls.len() > 1 {
+        client.health_check().await;
+    }
+
+    let client = RetryClient::new(
+        client,
+        args.max_retries,
+        Duration::from_millis(args.initial_backoff_ms),
+        args.max_rps,
+    );
+    let client = if args.no_cache {
+        CacheClient::pass_through(client)
+    } else {
+        CacheClient::new(client, &rpc_url, Duration::from_millis(args.cache_ttl_ms))
+    };
+
+    Ok(Provider::new(client))
+}
+
+/// Parse a private key, or unlock the stored wallet selected with `--account`; needs no
+/// provider, so keys can be used on a machine without network access
+pub fn signing_key(private_key: Option<&str>) -> Result<LocalWallet> {
+    let account = RPC_ARGS.get().and_then(|args| args.account.clone());
+    match (private_key, account) {
+        (Some(private_key), _) => Ok(private_key
+            .parse::<LocalWallet>()
+            .map_err(|e| DexError::Input(format!("Failed to parse private key: {}", e)))?),
+        (None, Some(label)) => Ok(wallets::unlock(&label)?),
+        (None, None) => Err(DexError::Config("Pass --private-key or select a stored wallet with --account".to_string())),
+    }
+}
+
+/// The [`signing_key`] bound to the chain the provider is connected to, or with neither
+/// key nor account given, the `--cold-address` whose transactions can only be prepared.
+/// Fails if the node reports a different chain than `--chain-id`, so nothing gets signed
+/// for the wrong network.
+pub async fn wallet(provider: &RpcProvider, private_key: Option<&str>) -> Result<AccountSigner> {
+    let args = RPC_ARGS.get().cloned().unwrap_or_default();
+    let wallet = match &args.cold_address {
+        Some(address) if private_key.is_none() && args.account.is_none() => {
+            AccountSigner::Cold { address: aliases::resolve(address)?, chain_id: 0 }
+        }
+        _ => signing_key(private_key)?.into(),
+    };
+    let chain_id = provider
+        .get_chainid()
+        .await
+        .map_err(|e| DexError::Rpc(format!("Failed to fetch chain ID: {}", e)))?
+        .as_u64();
+
+    let expected = expected_chain_id();
+    if expected != 0 && chain_id != expected {
+        return Err(DexError::Config(format!(
+            "RPC endpoint is on chain {} but chain {} is configured; refusing to sign (pass --chain-id {} if this is intended)",
+            chain_id,
+            expected,
+            chain_id
+        )));
+    }
+
+    Ok(wallet.with_chain_id(chain_id))
+}
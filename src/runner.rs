@@ -0,0 +1,158 @@
+use crate::bands::{BandedExecutor, PriceBands};
+use crate::bindings::{OrderBookDEX, ORDERBOOKDEX_ABI};
+use crate::bot::{self, Executor, LiveExecutor, PaperExecutor};
+use crate::breaker::CircuitBreaker;
+use crate::compat;
+use crate::error::Result;
+use crate::indexer::{EventStore, DEFAULT_CHUNK_SIZE};
+use crate::marketdata::SharedBook;
+use crate::pairs::PairParams;
+use crate::portfolio;
+use crate::pricefeed::ReferenceExecutor;
+use crate::recorder::RecordingStrategy;
+use crate::reload::ConfigWatcher;
+use crate::risk::{RiskEngine, RiskExecutor, RiskLimits};
+use crate::rpc;
+use crate::session::{SessionExecutor, SessionStore};
+use crate::strategy::{BotConfig, StrategyRegistry};
+use crate::tx::TxArgs;
+use ethers::middleware::SignerMiddleware;
+use ethers::providers::Middleware;
+use ethers::types::Address;
+use std::sync::Arc;
+use std::time::Duration;
+use tracing::{info, warn};
+
+/// Where and how [`run`] trades
+#[derive(Debug, Clone)]
+pub struct RunnerOptions {
+    pub dex: Address,
+    /// Signing key; falls back to `--account` like every other command
+    pub private_key: Option<String>,
+    pub rpc_url: Option<String>,
+    /// Simulate fills against the live book instead of sending transactions
+    pub paper: bool,
+    /// Skip the price bands
+    pub force: bool,
+    pub tx_args: TxArgs,
+    /// Append every strategy input and decision to this event log, for `replay-strategy`
+    pub record: Option<String>,
+    /// Cancel resting orders on shutdown even if the bot config doesn't ask to
+    pub cancel_on_shutdown: bool,
+    /// Trade without first checking that the DEX exposes the expected interface
+    pub skip_compat_check: bool,
+}
+
+/// Run the bot described by the TOML config at `config_path` until SIGINT or SIGTERM.
+///
+/// The strategy is built from `registry`, so crates embedding this one can run their
+/// own strategies through the same execution layer as the `bot` command: session-key
+/// policy, risk limits, price bands, reference feeds, the circuit breaker and config
+/// reloading.
+pub async fn run(config_path: &str, registry: &StrategyRegistry, options: RunnerOptions) -> Result<()> {
+    let config = BotConfig::load(config_path)?;
+    info!(
+        "Starting bot for {:?} / {:?}{}",
+        config.base_token,
+        config.quote_token,
+        if options.paper { " (paper)" } else { "" }
+    );
+
+    let provider = rpc::connect(options.rpc_url.as_deref()).await?;
+    let dex = options.dex;
+    if !options.skip_compat_check {
+        compat::ensure(&provider, dex).await?;
+    }
+
+    let mut strategy = registry.build(&config.strategy)?;
+    if let Some(path) = &options.record {
+        info!("Recording strategy inputs to {}", path);
+        strategy = Box::new(RecordingStrategy::new(strategy, &config.strategy, path)?);
+    }
+    let interval = Duration::from_secs(config.interval_secs);
+    let heartbeat = config.heartbeat_secs.map(Duration::from_secs);
+    let notifications = config.notify.build();
+    let mut watcher = ConfigWatcher::new(config_path, config.clone());
+    let cancel_on_shutdown = options.cancel_on_shutdown || config.cancel_on_shutdown;
+
+    if options.paper {
+        let contract = OrderBookDEX::new(dex, Arc::new(provider));
+        let (_, _, _, _, price_precision) = contract.trading_pairs(config.base_token, config.quote_token).call().await?;
+        let mut breaker = CircuitBreaker::new(config.circuit_breaker.clone(), dex, price_precision)?;
+        let mut executor: Box<dyn Executor> = Box::new(PaperExecutor::new(contract, config.base_token, config.quote_token));
+        if let Some(symbol) = &config.reference_feed {
+            info!("Quoting around the {} price feed", symbol);
+            executor = Box::new(ReferenceExecutor::new(executor, symbol)?);
+        }
+        return bot::run(strategy.as_mut(), executor.as_mut(), interval, &notifications, heartbeat, &mut breaker, Some(&mut watcher), cancel_on_shutdown).await;
+    }
+
+    let wallet = rpc::wallet(&provider, options.private_key.as_deref()).await?;
+    let client = Arc::new(SignerMiddleware::new(provider, wallet));
+    // Nobody is there to confirm each order
+    let tx_args = options.tx_args.unattended();
+    let mut executor = LiveExecutor::new(client.clone(), dex, config.base_token, config.quote_token, tx_args.clone());
+    if let Some(pipeline) = config.pipeline.clone() {
+        if tx_args.private_tx {
+            // The pipeline broadcasts straight to the node, which would bypass the relay
+            warn!("--private-tx is set; ignoring [pipeline] and submitting one transaction at a time");
+        } else {
+            info!("Pipelining up to {} transactions in flight", pipeline.max_in_flight);
+            executor = executor.with_pipeline(pipeline);
+        }
+    }
+    if config.watch_mempool {
+        info!("Watching the mempool for competing orders");
+        executor = executor.with_mempool().await?;
+    }
+    if let Some(shared_book) = &config.shared_book {
+        info!("Reading the book from the shared Redis cache, up to {}s old", shared_book.max_age_secs);
+        executor = executor.with_shared_book(SharedBook::connect(shared_book).await?);
+    }
+
+    // Inventory-aware strategies start from the signer's actual position
+    if let Some(from_block) = config.position_from_block {
+        let mut store = EventStore::load(dex, from_block)?;
+        store.sync(client.inner(), &ORDERBOOKDEX_ABI, DEFAULT_CHUNK_SIZE).await?;
+        let (tracker, _) = portfolio::pair_tracker(&store, client.address(), config.base_token, config.quote_token, 0);
+        info!("Starting from a net position of {} over {} fills", tracker.position, tracker.fills);
+        strategy.on_position(tracker.position);
+    }
+
+    let mut executor: Box<dyn Executor> = Box::new(executor);
+    let contract = OrderBookDEX::new(dex, client.clone());
+
+    // Delegated session keys trade only within their policy
+    if SessionStore::load()?.sessions.contains_key(&client.address()) {
+        info!("{:?} is a session key; enforcing its limits", client.address());
+        let pair = PairParams::fetch(&contract, config.base_token, config.quote_token).await?;
+        executor = Box::new(SessionExecutor::new(executor, client.clone(), pair, tx_args));
+    }
+
+    let limits = RiskLimits::load()?;
+    if !limits.is_empty() {
+        info!("Checking every quote against the risk limits {:?}", limits);
+        let engine = RiskEngine::new(Arc::new(rpc::connect(options.rpc_url.as_deref()).await?), dex, client.address(), limits);
+        executor = Box::new(RiskExecutor::new(executor, engine, config.base_token, config.quote_token));
+    }
+
+    let bands = match config.price_bands.clone() {
+        Some(bands) => bands,
+        None => PriceBands::load()?,
+    };
+    if !options.force && !bands.is_empty() {
+        info!("Refusing quotes outside the price bands {:?}", bands);
+        executor = Box::new(BandedExecutor::new(executor, bands));
+    }
+
+    if let Some(symbol) = &config.reference_feed {
+        info!("Quoting around the {} price feed", symbol);
+        executor = Box::new(ReferenceExecutor::new(executor, symbol)?);
+    }
+
+    // Realized loss is measured in quote units, which takes the pair's price precision
+    let (_, _, _, _, price_precision) = contract.trading_pairs(config.base_token, config.quote_token).call().await?;
+    let mut breaker = CircuitBreaker::new(config.circuit_breaker.clone(), dex, price_precision)?;
+
+    bot::run(strategy.as_mut(), executor.as_mut(), interval, &notifications, heartbeat, &mut breaker, Some(&mut watcher), cancel_on_shutdown).await
+}
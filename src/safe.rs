@@ -0,0 +1,235 @@
+use crate::bindings::GnosisSafe;
+use crate::bot::SignerClient;
+use crate::error::{DexError, Result};
+use crate::fees::Fees;
+use crate::rpc;
+use crate::tx::{self, TxArgs};
+use anyhow::{anyhow, Context};
+use ethers::types::{Address, Bytes, TransactionReceipt, H256, U256};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use tracing::info;
+
+const PROPOSAL_DIR: &str = "config/safe";
+
+/// `Enum.Operation.Call`; admin calls never need DELEGATECALL
+const OPERATION_CALL: u8 = 0;
+
+/// A Safe transaction waiting for owner signatures, shared between owners as a file
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SafeProposal {
+    pub safe: Address,
+    pub to: Address,
+    pub value: U256,
+    pub data: Bytes,
+    pub nonce: U256,
+    pub safe_tx_hash: H256,
+    pub description: String,
+    /// 65-byte ECDSA signatures over `safe_tx_hash`, keyed (and so sorted) by owner
+    pub signatures: BTreeMap<Address, Bytes>,
+}
+
+impl SafeProposal {
+    pub fn path(safe: Address, nonce: U256) -> PathBuf {
+        Path::new(PROPOSAL_DIR).join(format!("{:?}-{}.json", safe, nonce))
+    }
+
+    pub fn load(path: &Path) -> Result<Option<Self>> {
+        if !path.exists() {
+            return Ok(None);
+        }
+        let content = fs::read_to_string(path).with_context(|| format!("Failed to read {}", path.display()))?;
+        Ok(Some(serde_json::from_str(&content)?))
+    }
+
+    pub fn save(&self) -> Result<()> {
+        if rpc::is_fork() {
+            return Ok(());
+        }
+        fs::create_dir_all(PROPOSAL_DIR)?;
+        fs::write(Self::path(self.safe, self.nonce), serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+
+    /// Signatures in the packed, owner-ascending form `execTransaction` expects
+    fn packed_signatures(&self) -> Bytes {
+        self.signatures.values().flat_map(|sig| sig.to_vec()).collect::<Vec<u8>>().into()
+    }
+}
+
+/// Route a call through `safe` instead of sending it from the signer: sign the Safe
+/// transaction for the Safe's current nonce, add the signature to the shared proposal,
+/// and execute once enough owners have signed. Returns the receipt once executed.
+///
+/// Other owners add their signatures by running the same command with the same
+/// `--safe`; the proposal is matched by its Safe transaction hash, so a different call
+/// at the same nonce is refused rather than mixed in.
+pub async fn submit(
+    client: Arc<SignerClient>,
+    safe: Address,
+    to: Address,
+    data: Bytes,
+    description: &str,
+    fees: &Fees,
+    tx_args: &TxArgs,
+) -> Result<Option<TransactionReceipt>> {
+    let contract = GnosisSafe::new(safe, client.clone());
+    let signer = client.address();
+    if !contract.is_owner(signer).call().await? {
+        return Err(DexError::Input(format!("{:?} is not an owner of Safe {:?}", signer, safe)));
+    }
+
+    let nonce = contract.nonce().call().await?;
+    let safe_tx_hash = H256::from(
+        contract
+            .get_transaction_hash(
+                to,
+                U256::zero(),
+                data.clone(),
+                OPERATION_CALL,
+                U256::zero(),
+                U256::zero(),
+                U256::zero(),
+                Address::zero(),
+                Address::zero(),
+                nonce,
+            )
+            .call()
+            .await?,
+    );
+
+    let path = SafeProposal::path(safe, nonce);
+    let mut proposal = match SafeProposal::load(&path)? {
+        Some(existing) if existing.safe_tx_hash != safe_tx_hash => {
+            return Err(DexError::Input(format!(
+                "A different Safe transaction ({}) is already proposed at nonce {} in {}; execute or delete it first",
+                existing.description,
+                nonce,
+                path.display()
+            )));
+        }
+        Some(existing) => existing,
+        None => SafeProposal {
+            safe,
+            to,
+            value: U256::zero(),
+            data: data.clone(),
+            nonce,
+            safe_tx_hash,
+            description: description.to_string(),
+            signatures: BTreeMap::new(),
+        },
+    };
+
+    let threshold = contract.get_threshold().call().await?.as_usize();
+    if tx_args.estimate_only && proposal.signatures.len() + 1 < threshold {
+        println!("Safe transaction {:?} at nonce {}: {}", safe_tx_hash, nonce, description);
+        return Ok(None);
+    }
+
+    // Owners sign the raw Safe transaction hash; v stays 27/28 so the Safe treats it as ECDSA
+    rpc::ensure_writable()?;
+    let signature = client
+        .signer()
+        .sign_hash(safe_tx_hash)
+        .map_err(|e| anyhow!("Failed to sign Safe transaction: {}", e))?;
+    proposal.signatures.insert(signer, signature.to_vec().into());
+
+    if proposal.signatures.len() < threshold {
+        proposal.save()?;
+        println!("Safe Transaction Proposed");
+        println!("=========================");
+        println!("Safe: {:?}", safe);
+        println!("Nonce: {}", nonce);
+        println!("Safe tx hash: {:?}", safe_tx_hash);
+        println!("Action: {}", description);
+        println!("Signatures: {} of {}", proposal.signatures.len(), threshold);
+        println!("Proposal: {}", path.display());
+        println!("Other owners add their signature by running the same command with --safe {:?}", safe);
+        return Ok(None);
+    }
+
+    info!("{} of {} Safe signatures collected, executing...", proposal.signatures.len(), threshold);
+    let method = contract.exec_transaction(
+        to,
+        U256::zero(),
+        data,
+        OPERATION_CALL,
+        U256::zero(),
+        U256::zero(),
+        U256::zero(),
+        Address::zero(),
+        Address::zero(),
+        proposal.packed_signatures(),
+    );
+    let receipt = tx::send(client.as_ref(), method, fees, tx_args).await?;
+
+    if let Some(receipt) = &receipt {
+        // Keep the proposal and its signatures around to retry a reverted execution
+        tx::ensure_success(client.as_ref(), receipt).await?;
+        if path.exists() && !rpc::is_fork() {
+            fs::remove_file(&path)?;
+        }
+        info!("Safe transaction executed successfully!");
+        info!("Transaction hash: {:?}", receipt.transaction_hash);
+    }
+
+    Ok(receipt)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ethers::signers::{LocalWallet, Signer};
+    use ethers::types::Signature;
+
+    const KEYS: [&str; 3] = [
+        "ac0974bec39a17e36ba4a6b4d238ff944bacb478cbed5efcae784d7bf4f2ff80",
+        "59c6995e998f97a5a0044966f0945389dc9e86dae88c7a8412f4603b6b78690d",
+        "5de4111afa1a4b94908f83103eb1f1706367c2e68ca870fc3fb9a804cdab365a",
+    ];
+
+    fn proposal(safe_tx_hash: H256) -> SafeProposal {
+        let mut proposal = SafeProposal {
+            safe: Address::repeat_byte(1),
+            to: Address::repeat_byte(2),
+            value: U256::zero(),
+            data: Bytes::default(),
+            nonce: U256::zero(),
+            safe_tx_hash,
+            description: "pause".to_string(),
+            signatures: BTreeMap::new(),
+        };
+        for key in KEYS {
+            let wallet: LocalWallet = key.parse().unwrap();
+            let signature = wallet.sign_hash(safe_tx_hash).unwrap();
+            proposal.signatures.insert(wallet.address(), signature.to_vec().into());
+        }
+        proposal
+    }
+
+    #[test]
+    fn signatures_are_packed_by_ascending_owner() {
+        let hash = H256::repeat_byte(0xab);
+        let packed = proposal(hash).packed_signatures();
+        assert_eq!(packed.len(), 65 * KEYS.len());
+
+        let signers: Vec<Address> = packed
+            .chunks(65)
+            .map(|chunk| Signature::try_from(chunk).unwrap().recover(hash).unwrap())
+            .collect();
+        let mut owners = signers.clone();
+        owners.sort();
+        assert_eq!(signers, owners);
+    }
+
+    #[test]
+    fn packed_signatures_stay_ecdsa() {
+        // v of 27 or 28 tells the Safe the signature is over the raw hash
+        let packed = proposal(H256::repeat_byte(0xcd)).packed_signatures();
+        assert!(packed.chunks(65).all(|chunk| chunk[64] == 27 || chunk[64] == 28));
+    }
+}
@@ -0,0 +1,270 @@
+use crate::bindings::OrderBookDEX;
+use crate::bot::SignerClient;
+use crate::error::{DexError, Result};
+use crate::events;
+use crate::expiry::unix_secs;
+use crate::limits::{self, Spend};
+use crate::placement::{self, LimitOrder, LimitOrderOptions};
+use crate::session;
+use crate::tx::{self, TxArgs};
+use anyhow::Context;
+use ethers::{
+    providers::Middleware,
+    types::{Address, H256, U256},
+};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::Path;
+use std::sync::Arc;
+use std::time::SystemTime;
+use tracing::{info, warn};
+
+const SCHEDULE_STORE_PATH: &str = "config/schedules.json";
+
+/// Outcome of one scheduled run
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RunLog {
+    /// Unix time in seconds the run was attempted
+    pub at: u64,
+    pub tx_hash: Option<H256>,
+    /// ID of the resting order, for limit orders
+    pub order_id: Option<u64>,
+    pub error: Option<String>,
+}
+
+/// An order placed at a set time, or repeatedly at a fixed interval (DCA)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ScheduledOrder {
+    pub id: u64,
+    pub dex: Address,
+    pub base_token: Address,
+    pub quote_token: Address,
+    pub amount: u64,
+    /// Limit price; `None` places a market order
+    pub price: Option<u64>,
+    pub is_buy: bool,
+    /// Unix time in seconds of the next run
+    pub next_run_at: u64,
+    /// Seconds between runs; `None` runs once
+    pub interval_secs: Option<u64>,
+    pub total_runs: u32,
+    #[serde(default)]
+    pub runs: Vec<RunLog>,
+    #[serde(default)]
+    pub cancelled: bool,
+}
+
+impl ScheduledOrder {
+    pub fn is_finished(&self) -> bool {
+        self.cancelled || self.runs.len() >= self.total_runs as usize
+    }
+
+    pub fn is_due(&self, now: u64) -> bool {
+        !self.is_finished() && now >= self.next_run_at
+    }
+
+    /// Log a run and move to the next slot. Slots missed while nothing was running are
+    /// skipped rather than replayed in a burst.
+    pub fn record(&mut self, run: RunLog, now: u64) {
+        self.runs.push(run);
+        if let Some(interval) = self.interval_secs.filter(|&interval| interval > 0) {
+            self.next_run_at += interval;
+            if self.next_run_at <= now {
+                self.next_run_at += (now - self.next_run_at) / interval * interval + interval;
+            }
+        }
+    }
+}
+
+/// Scheduled orders, persisted so they survive restarts of the runner
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ScheduleStore {
+    #[serde(default)]
+    pub next_id: u64,
+    pub schedules: Vec<ScheduledOrder>,
+}
+
+impl ScheduleStore {
+    pub fn load() -> Result<Self> {
+        if !Path::new(SCHEDULE_STORE_PATH).exists() {
+            return Ok(Self::default());
+        }
+        let content = fs::read_to_string(SCHEDULE_STORE_PATH)
+            .with_context(|| format!("Failed to read {}", SCHEDULE_STORE_PATH))?;
+        Ok(serde_json::from_str(&content)?)
+    }
+
+    pub fn save(&self) -> Result<()> {
+        fs::create_dir_all("config")?;
+        fs::write(SCHEDULE_STORE_PATH, serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+
+    /// Store `schedule` under a fresh ID and return it
+    pub fn add(&mut self, mut schedule: ScheduledOrder) -> u64 {
+        self.next_id += 1;
+        schedule.id = self.next_id;
+        self.schedules.push(schedule);
+        self.next_id
+    }
+
+    pub fn get_mut(&mut self, id: u64) -> Result<&mut ScheduledOrder> {
+        self.schedules
+            .iter_mut()
+            .find(|s| s.id == id)
+            .ok_or_else(|| DexError::Input(format!("No schedule with ID {}", id)))
+    }
+
+    /// Stop a schedule from running again; its run log is kept
+    pub fn cancel(&mut self, id: u64) -> Result<()> {
+        let schedule = self.get_mut(id)?;
+        if schedule.is_finished() {
+            return Err(DexError::Input(format!("Schedule {} has already finished", id)));
+        }
+        schedule.cancelled = true;
+        Ok(())
+    }
+}
+
+/// One pass of `schedule run`: place every scheduled order that has come due, logging each
+/// run to the schedule store. Returns how many runs were attempted.
+pub async fn run_due(client: Arc<SignerClient>, rpc_url: Option<&str>, tx_args: &TxArgs) -> Result<usize> {
+    let now = unix_secs(SystemTime::now());
+    let mut store = ScheduleStore::load()?;
+    let due: Vec<u64> = store.schedules.iter().filter(|s| s.is_due(now)).map(|s| s.id).collect();
+
+    for &id in &due {
+        let schedule = store.get_mut(id)?.clone();
+        info!("Schedule {}: run {} of {}", id, schedule.runs.len() + 1, schedule.total_runs);
+        // Each run is journaled under its own key so a restarted runner doesn't place it twice
+        let run_args = &tx_args.step(format!("{}/{}", id, schedule.runs.len() + 1));
+        let run = match place(client.clone(), rpc_url, &schedule, run_args).await {
+            Ok(run) => run,
+            Err(e) => {
+                warn!("Schedule {} run failed: {}", id, e);
+                RunLog { at: now, tx_hash: None, order_id: None, error: Some(e.to_string()) }
+            }
+        };
+        if tx_args.estimate_only {
+            continue;
+        }
+
+        // Reload so a cancel issued while the order was in flight is kept
+        let mut latest = ScheduleStore::load()?;
+        latest.get_mut(id)?.record(run, unix_secs(SystemTime::now()));
+        latest.save()?;
+        store = latest;
+    }
+    Ok(due.len())
+}
+
+/// Place one run of `schedule` with the checks of PlaceLimitOrder or PlaceMarketOrder
+async fn place(client: Arc<SignerClient>, rpc_url: Option<&str>, schedule: &ScheduledOrder, tx_args: &TxArgs) -> Result<RunLog> {
+    let at = unix_secs(SystemTime::now());
+    let fees = tx_args.fees.resolve(client.inner()).await?;
+    let order = LimitOrder {
+        dex: schedule.dex,
+        base_token: schedule.base_token,
+        quote_token: schedule.quote_token,
+        amount: U256::from(schedule.amount),
+        price: U256::from(schedule.price.unwrap_or_default()),
+        is_buy: schedule.is_buy,
+    };
+
+    let receipt = match schedule.price {
+        Some(_) => placement::place_limit_order(client.clone(), rpc_url, order, &LimitOrderOptions::default(), &fees, tx_args).await?,
+        None => {
+            let notional = placement::check_market_order(client.clone(), rpc_url, &order, tx_args).await?;
+            let contract = OrderBookDEX::new(order.dex, client.clone());
+            let method = contract.place_market_order(order.base_token, order.quote_token, order.amount, order.is_buy);
+            let receipt = tx::send(client.as_ref(), method, &fees, tx_args).await?;
+            if let Some(receipt) = &receipt {
+                tx::ensure_success(client.as_ref(), receipt).await?;
+                limits::record(Spend::OrderNotional, order.quote_token, notional)?;
+                session::record(client.address(), notional)?;
+            }
+            receipt
+        }
+    };
+
+    // Reverts come back as errors and are logged by the caller
+    Ok(match receipt {
+        Some(receipt) => RunLog {
+            at,
+            tx_hash: Some(receipt.transaction_hash),
+            order_id: events::placed_order_id(&receipt).map(|id| id.as_u64()),
+            error: None,
+        },
+        None => RunLog { at, tx_hash: None, order_id: None, error: None },
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn schedule(next_run_at: u64, interval_secs: Option<u64>, total_runs: u32) -> ScheduledOrder {
+        ScheduledOrder {
+            id: 0,
+            dex: Address::repeat_byte(1),
+            base_token: Address::repeat_byte(2),
+            quote_token: Address::repeat_byte(3),
+            amount: 10,
+            price: None,
+            is_buy: true,
+            next_run_at,
+            interval_secs,
+            total_runs,
+            runs: Vec::new(),
+            cancelled: false,
+        }
+    }
+
+    fn run(at: u64) -> RunLog {
+        RunLog { at, tx_hash: None, order_id: None, error: None }
+    }
+
+    #[test]
+    fn a_run_on_time_moves_to_the_next_slot() {
+        let mut dca = schedule(1_000, Some(60), 5);
+        dca.record(run(1_000), 1_001);
+        assert_eq!(dca.next_run_at, 1_060);
+    }
+
+    #[test]
+    fn missed_slots_are_skipped_rather_than_replayed() {
+        let mut dca = schedule(1_000, Some(60), 5);
+        // Down from 1_000 until 1_200: the slots at 1_060, 1_120 and 1_180 are gone
+        dca.record(run(1_200), 1_200);
+        assert_eq!(dca.next_run_at, 1_240);
+        assert!(!dca.is_due(1_239));
+        assert!(dca.is_due(1_240));
+    }
+
+    #[test]
+    fn a_run_landing_on_a_slot_boundary_waits_for_the_next_one() {
+        let mut dca = schedule(1_000, Some(60), 5);
+        dca.record(run(1_120), 1_120);
+        assert_eq!(dca.next_run_at, 1_180);
+    }
+
+    #[test]
+    fn one_off_orders_finish_after_their_run() {
+        let mut once = schedule(1_000, None, 1);
+        assert!(once.is_due(1_000));
+        once.record(run(1_000), 1_000);
+        assert_eq!(once.next_run_at, 1_000);
+        assert!(once.is_finished());
+        assert!(!once.is_due(2_000));
+    }
+
+    #[test]
+    fn cancelled_schedules_stop_and_cannot_be_cancelled_twice() {
+        let mut store = ScheduleStore::default();
+        let id = store.add(schedule(1_000, Some(60), 5));
+        store.cancel(id).unwrap();
+        assert!(!store.get_mut(id).unwrap().is_due(5_000));
+        assert!(store.cancel(id).is_err());
+        assert!(store.get_mut(id + 1).is_err());
+    }
+}
@@ -0,0 +1,280 @@
+use crate::error::{DexError, Result};
+use crate::strategy::{to_f64, BookView, Fill, Intent, Level, OpenOrder, Strategy, StrategyConfig};
+use anyhow::Context;
+use ethers::types::U256;
+use rhai::{CallFnOptions, Dynamic, Engine, Map, Scope, AST, INT};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::time::{Duration, SystemTime};
+use tracing::{info, warn};
+
+/// Parameters of a strategy written as a Rhai script
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ScriptParams {
+    /// Script file; reloaded whenever it changes
+    pub path: String,
+    /// Operations one handler call may run before it is aborted
+    #[serde(default = "default_max_operations")]
+    pub max_operations: u64,
+    /// Deepest function call nesting allowed
+    #[serde(default = "default_max_call_levels")]
+    pub max_call_levels: usize,
+    /// Seconds between `on_timer` calls; the handler is never called when unset
+    #[serde(default)]
+    pub timer_secs: Option<u64>,
+    /// Handed to the script as `this.params`
+    #[serde(default)]
+    pub params: serde_json::Value,
+}
+
+fn default_max_operations() -> u64 {
+    100_000
+}
+
+fn default_max_call_levels() -> usize {
+    16
+}
+
+/// Runs strategy logic from a Rhai script.
+///
+/// The script defines any of `on_book(book, orders)`, `on_fill(fill)` and
+/// `on_timer(orders)`, each returning an array of orders built with `buy(price, amount)`,
+/// `sell(price, amount)` and `cancel(id)`. Handlers keep state in `this`, a map that
+/// starts out as `#{ params: ... }` and survives reloads. Prices and amounts reach the
+/// script as floats; pass amounts too large for a float as decimal strings.
+///
+/// Scripts can't touch files or the network, and every call runs under an operation
+/// budget. A handler that errors or runs out of budget produces no orders.
+pub struct ScriptStrategy {
+    params: ScriptParams,
+    engine: Engine,
+    ast: AST,
+    modified: Option<SystemTime>,
+    state: Dynamic,
+    /// Net base position, from the starting position plus fills since
+    position: f64,
+}
+
+impl ScriptStrategy {
+    pub fn new(params: ScriptParams) -> Result<Self> {
+        let engine = sandboxed_engine(&params);
+        let modified = modified(&params.path);
+        let ast = compile(&engine, &params.path)?;
+        let mut state = Map::new();
+        state.insert("params".into(), rhai::serde::to_dynamic(&params.params).map_err(|e| DexError::Config(format!("Invalid script params: {}", e)))?);
+        Ok(Self { params, engine, ast, modified, state: state.into(), position: 0.0 })
+    }
+
+    /// Recompile the script if the file changed; a script that no longer compiles keeps
+    /// the previous version running
+    fn reload(&mut self) {
+        let modified = modified(&self.params.path);
+        if modified == self.modified {
+            return;
+        }
+        self.modified = modified;
+        match compile(&self.engine, &self.params.path) {
+            Ok(ast) => {
+                info!("Reloaded strategy script {}", self.params.path);
+                self.ast = ast;
+            }
+            Err(e) => warn!("Keeping the previous script: {:#}", e),
+        }
+    }
+
+    fn call(&mut self, handler: &str, args: Vec<Dynamic>) -> Vec<Intent> {
+        if !self.ast.iter_functions().any(|function| function.name == handler) {
+            return Vec::new();
+        }
+        let options = CallFnOptions::new().eval_ast(false).bind_this_ptr(&mut self.state);
+        let result = self
+            .engine
+            .call_fn_with_options::<Dynamic>(options, &mut Scope::new(), &self.ast, handler, args);
+        match result.map_err(|e| DexError::Input(e.to_string())).and_then(intents) {
+            Ok(intents) => intents,
+            Err(e) => {
+                warn!("Script {} failed in {}: {}", self.params.path, handler, e);
+                Vec::new()
+            }
+        }
+    }
+}
+
+impl Strategy for ScriptStrategy {
+    fn name(&self) -> &str {
+        "script"
+    }
+
+    fn on_book_update(&mut self, book: &BookView, open_orders: &[OpenOrder]) -> Vec<Intent> {
+        self.reload();
+        let mut view = Map::new();
+        view.insert("block".into(), book.block.map_or(Dynamic::UNIT, |block| (block as INT).into()));
+        view.insert("bids".into(), levels(&book.bids));
+        view.insert("asks".into(), levels(&book.asks));
+        view.insert("best_bid".into(), optional(book.best_bid().map(to_f64)));
+        view.insert("best_ask".into(), optional(book.best_ask().map(to_f64)));
+        view.insert("mid".into(), optional(book.mid()));
+        view.insert("reference".into(), optional(book.reference));
+        view.insert("position".into(), self.position.into());
+        self.call("on_book", vec![view.into(), orders(open_orders)])
+    }
+
+    fn on_fill(&mut self, fill: &Fill) -> Vec<Intent> {
+        let amount = to_f64(fill.amount);
+        self.position += if fill.is_buy { amount } else { -amount };
+        let mut view = Map::new();
+        view.insert("id".into(), (fill.order_id as INT).into());
+        view.insert("is_buy".into(), fill.is_buy.into());
+        view.insert("amount".into(), amount.into());
+        view.insert("price".into(), to_f64(fill.price).into());
+        view.insert("position".into(), self.position.into());
+        self.call("on_fill", vec![view.into()])
+    }
+
+    fn on_position(&mut self, position: f64) {
+        self.position = position;
+    }
+
+    fn timer_interval(&self) -> Option<Duration> {
+        self.params.timer_secs.map(Duration::from_secs)
+    }
+
+    fn on_timer(&mut self, open_orders: &[OpenOrder]) -> Vec<Intent> {
+        self.reload();
+        self.call("on_timer", vec![orders(open_orders)])
+    }
+
+    fn reconfigure(&mut self, config: &StrategyConfig) -> Result<()> {
+        let StrategyConfig::Script(params) = config else {
+            return Err(DexError::Config(format!("Can't switch from script to {} without a restart", config.kind())));
+        };
+        let mut next = Self::new(params.clone())?;
+        next.state = std::mem::take(&mut self.state);
+        if let Some(mut state) = next.state.write_lock::<Map>() {
+            state.insert("params".into(), rhai::serde::to_dynamic(&params.params).map_err(|e| DexError::Config(format!("Invalid script params: {}", e)))?);
+        }
+        next.position = self.position;
+        *self = next;
+        Ok(())
+    }
+}
+
+/// An engine with no module resolution, output routed to the log and hard limits on
+/// how much work and memory a call can use
+fn sandboxed_engine(params: &ScriptParams) -> Engine {
+    let mut engine = Engine::new();
+    engine.set_module_resolver(rhai::module_resolvers::DummyModuleResolver::new());
+    engine.disable_symbol("eval");
+    engine.set_max_operations(params.max_operations);
+    engine.set_max_call_levels(params.max_call_levels);
+    engine.set_max_expr_depths(64, 32);
+    engine.set_max_string_size(10_000);
+    engine.set_max_array_size(10_000);
+    engine.set_max_map_size(10_000);
+    engine.on_print(|text| info!("[script] {}", text));
+    engine.on_debug(|text, _, position| info!("[script {}] {}", position, text));
+
+    engine.register_fn("buy", |price: Dynamic, amount: Dynamic| place(true, price, amount));
+    engine.register_fn("sell", |price: Dynamic, amount: Dynamic| place(false, price, amount));
+    engine.register_fn("cancel", |id: INT| {
+        let mut order = Map::new();
+        order.insert("cancel".into(), id.into());
+        order
+    });
+    engine
+}
+
+fn place(is_buy: bool, price: Dynamic, amount: Dynamic) -> Map {
+    let mut order = Map::new();
+    order.insert("is_buy".into(), is_buy.into());
+    order.insert("price".into(), price);
+    order.insert("amount".into(), amount);
+    order
+}
+
+fn compile(engine: &Engine, path: &str) -> Result<AST> {
+    let source = fs::read_to_string(path).with_context(|| format!("Failed to read {}", path))?;
+    engine.compile(source).map_err(|e| DexError::Config(format!("{} doesn't compile: {}", path, e)))
+}
+
+fn modified(path: &str) -> Option<SystemTime> {
+    fs::metadata(path).and_then(|metadata| metadata.modified()).ok()
+}
+
+fn optional(value: Option<f64>) -> Dynamic {
+    value.map_or(Dynamic::UNIT, Dynamic::from_float)
+}
+
+fn levels(levels: &[Level]) -> Dynamic {
+    let levels: rhai::Array = levels
+        .iter()
+        .map(|level| {
+            let mut view = Map::new();
+            view.insert("price".into(), to_f64(level.price).into());
+            view.insert("amount".into(), to_f64(level.amount).into());
+            Dynamic::from_map(view)
+        })
+        .collect();
+    levels.into()
+}
+
+fn orders(open_orders: &[OpenOrder]) -> Dynamic {
+    let orders: rhai::Array = open_orders
+        .iter()
+        .map(|order| {
+            let mut view = Map::new();
+            view.insert("id".into(), (order.id as INT).into());
+            view.insert("is_buy".into(), order.is_buy.into());
+            view.insert("price".into(), to_f64(order.price).into());
+            view.insert("amount".into(), to_f64(order.amount).into());
+            Dynamic::from_map(view)
+        })
+        .collect();
+    orders.into()
+}
+
+/// Read the orders a handler returned; `()` means none
+fn intents(result: Dynamic) -> Result<Vec<Intent>> {
+    if result.is_unit() {
+        return Ok(Vec::new());
+    }
+    let orders = result
+        .try_cast::<rhai::Array>()
+        .ok_or_else(|| DexError::Input("handlers must return an array of orders".to_string()))?;
+    orders
+        .into_iter()
+        .map(|order| {
+            let order = order
+                .try_cast::<Map>()
+                .ok_or_else(|| DexError::Input("orders must be built with buy, sell or cancel".to_string()))?;
+            if let Some(id) = order.get("cancel") {
+                let id = id.as_int().map_err(|_| DexError::Input("cancel needs an order id".to_string()))?;
+                let order_id = u64::try_from(id).map_err(|_| DexError::Input(format!("invalid order id {}", id)))?;
+                return Ok(Intent::Cancel { order_id });
+            }
+            let field = |name: &str| order.get(name).cloned().ok_or_else(|| DexError::Input(format!("order without {}", name)));
+            Ok(Intent::Place {
+                price: amount(field("price")?)?,
+                amount: amount(field("amount")?)?,
+                is_buy: field("is_buy")?.as_bool().map_err(|_| DexError::Input("is_buy must be a bool".to_string()))?,
+            })
+        })
+        .collect()
+}
+
+/// A whole, positive number from an integer, a float (rounded down) or a decimal string
+fn amount(value: Dynamic) -> Result<U256> {
+    if let Ok(value) = value.as_int() {
+        return Ok(U256::from(u64::try_from(value).map_err(|_| DexError::Input(format!("negative amount {}", value)))?));
+    }
+    if let Ok(value) = value.as_float() {
+        if !value.is_finite() || value < 0.0 {
+            return Err(DexError::Input(format!("invalid amount {}", value)));
+        }
+        return Ok(U256::from(value.floor() as u128));
+    }
+    if let Some(value) = value.clone().try_cast::<String>() {
+        return U256::from_dec_str(&value).map_err(|e| DexError::Input(format!("invalid amount '{}': {}", value, e)));
+    }
+    Err(DexError::Input(format!("amounts must be numbers or decimal strings, got {}", value.type_name())))
+}
@@ -0,0 +1,291 @@
+use crate::bindings::ERC20;
+use crate::bot::{Executor, SignerClient};
+use crate::error::{DexError, Result};
+use crate::pairs::PairParams;
+use crate::strategy::{BookView, Fill, OpenOrder, PendingActivity};
+use crate::tx::{self, TxArgs};
+use anyhow::{anyhow, Context};
+use async_trait::async_trait;
+use ethers::{
+    providers::Middleware,
+    types::{Address, U256},
+};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+use std::sync::Arc;
+use std::time::{SystemTime, UNIX_EPOCH};
+use tracing::info;
+
+const SESSION_STORE_PATH: &str = "config/sessions.json";
+
+/// What a delegated key may do. Placing and cancelling orders is all a bot needs;
+/// anything that moves funds out is refused.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SessionPolicy {
+    /// Tokens the key may trade; orders on a pair with any other token are refused
+    pub tokens: Vec<Address>,
+    /// Largest quote notional (`amount * price / pricePrecision`) of a single order
+    pub max_order_notional: U256,
+    /// Quote notional the key may place over the whole session. Cancelled orders still
+    /// count, so a runaway place/cancel loop exhausts the session instead of looping forever.
+    pub max_total_notional: U256,
+    #[serde(default)]
+    pub spent_notional: U256,
+    /// Unix time the session ends; `None` until revoked
+    pub expires_at: Option<u64>,
+    /// Wallet whose ERC-20 allowance to the key funds each order's deposit just in time,
+    /// so the key itself holds nothing worth stealing
+    #[serde(default)]
+    pub pull_from: Option<Address>,
+}
+
+/// Something a delegated key is about to sign
+#[derive(Debug, Clone, Copy)]
+pub enum Action {
+    Place { base_token: Address, quote_token: Address, notional: U256 },
+    Cancel,
+    Withdraw,
+    Transfer,
+}
+
+fn now() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or_default()
+}
+
+impl SessionPolicy {
+    pub fn is_expired(&self) -> bool {
+        self.expires_at.is_some_and(|expires_at| now() >= expires_at)
+    }
+
+    /// Refuse `action` unless the policy allows it
+    pub fn authorize(&self, action: Action) -> Result<()> {
+        match action {
+            // Cancelling only ever reduces exposure, so it stays allowed after expiry
+            Action::Cancel => Ok(()),
+            Action::Withdraw => Err(DexError::Input("Session keys may not withdraw from the DEX".to_string())),
+            Action::Transfer => Err(DexError::Input("Session keys may not transfer funds".to_string())),
+            Action::Place { base_token, quote_token, notional } => {
+                if self.is_expired() {
+                    return Err(DexError::Input("Session expired".to_string()));
+                }
+                if let Some(token) = [base_token, quote_token].into_iter().find(|t| !self.tokens.contains(t)) {
+                    return Err(DexError::Input(format!("Session does not allow trading {:?}", token)));
+                }
+                if notional > self.max_order_notional {
+                    return Err(DexError::Input(format!(
+                        "Order notional {} exceeds the session limit of {} per order",
+                        notional,
+                        self.max_order_notional
+                    )));
+                }
+                if self.spent_notional + notional > self.max_total_notional {
+                    return Err(DexError::Input(format!(
+                        "Order notional {} would take the session past its total of {} ({} used)",
+                        notional,
+                        self.max_total_notional,
+                        self.spent_notional
+                    )));
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+/// Session policies keyed by the delegated key's address
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SessionStore {
+    pub sessions: BTreeMap<Address, SessionPolicy>,
+}
+
+impl SessionStore {
+    pub fn load() -> Result<Self> {
+        if !Path::new(SESSION_STORE_PATH).exists() {
+            return Ok(Self::default());
+        }
+        let content = fs::read_to_string(SESSION_STORE_PATH)
+            .with_context(|| format!("Failed to read {}", SESSION_STORE_PATH))?;
+        Ok(serde_json::from_str(&content)?)
+    }
+
+    pub fn save(&self) -> Result<()> {
+        fs::create_dir_all("config")?;
+        fs::write(SESSION_STORE_PATH, serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+
+    /// End the session now. The policy is kept so the key stays restricted.
+    pub fn revoke(&mut self, key: Address) -> Result<()> {
+        let policy = self
+            .sessions
+            .get_mut(&key)
+            .ok_or_else(|| DexError::Input(format!("No session for {:?}", key)))?;
+        policy.expires_at = Some(now());
+        Ok(())
+    }
+}
+
+/// Refuse `action` if `key` is a delegated session key whose policy forbids it.
+/// Keys without a session are unrestricted.
+pub fn check(key: Address, action: Action) -> Result<()> {
+    match SessionStore::load()?.sessions.get(&key) {
+        Some(policy) => policy.authorize(action),
+        None => Ok(()),
+    }
+}
+
+/// Count `notional` toward the session total of `key` once its order is placed. Keys
+/// without a session aren't tracked.
+pub fn record(key: Address, notional: U256) -> Result<()> {
+    let mut store = SessionStore::load()?;
+    if let Some(policy) = store.sessions.get_mut(&key) {
+        policy.spent_notional += notional;
+        store.save()?;
+    }
+    Ok(())
+}
+
+/// Runs a bot's intents through the session policy of its signing key before handing
+/// them to `inner`. The policy is reloaded for every order so a revocation takes effect
+/// on the next intent.
+pub struct SessionExecutor<E> {
+    inner: E,
+    client: Arc<SignerClient>,
+    pair: PairParams,
+    tx_args: TxArgs,
+}
+
+impl<E: Executor> SessionExecutor<E> {
+    pub fn new(inner: E, client: Arc<SignerClient>, pair: PairParams, tx_args: TxArgs) -> Self {
+        Self { inner, client, pair, tx_args }
+    }
+
+    /// Top the key up to `deposit` of `token` from the grantor's allowance
+    async fn pull_deposit(&self, from: Address, token: Address, deposit: U256) -> Result<()> {
+        let key = self.client.address();
+        let contract = ERC20::new(token, self.client.clone());
+        let held = contract.balance_of(key).call().await?;
+        if held >= deposit {
+            return Ok(());
+        }
+
+        let shortfall = deposit - held;
+        info!("Pulling {} of {:?} from {:?} for the order deposit", shortfall, token, from);
+        let fees = self.tx_args.fees.resolve(self.client.inner()).await?;
+        let method = contract.transfer_from(from, key, shortfall);
+        tx::send(self.client.as_ref(), method, &fees, &self.tx_args).await?;
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl<E: Executor> Executor for SessionExecutor<E> {
+    async fn book(&mut self) -> Result<BookView> {
+        self.inner.book().await
+    }
+
+    async fn poll_fills(&mut self) -> Result<Vec<Fill>> {
+        self.inner.poll_fills().await
+    }
+
+    async fn open_orders(&mut self) -> Result<Vec<OpenOrder>> {
+        self.inner.open_orders().await
+    }
+
+    async fn poll_pending(&mut self) -> Result<Vec<PendingActivity>> {
+        self.inner.poll_pending().await
+    }
+
+    async fn place(&mut self, price: U256, amount: U256, is_buy: bool) -> Result<u64> {
+        let key = self.client.address();
+        let notional = self.pair.quote_amount(amount, price);
+        let policy = SessionStore::load()?
+            .sessions
+            .remove(&key)
+            .ok_or_else(|| anyhow!("Session for {:?} no longer exists", key))?;
+        policy.authorize(Action::Place {
+            base_token: self.pair.base_token,
+            quote_token: self.pair.quote_token,
+            notional,
+        })?;
+
+        if let Some(from) = policy.pull_from {
+            let (token, deposit) = if is_buy {
+                (self.pair.quote_token, notional)
+            } else {
+                (self.pair.base_token, amount)
+            };
+            self.pull_deposit(from, token, deposit).await?;
+        }
+
+        let id = self.inner.place(price, amount, is_buy).await?;
+        record(key, notional)?;
+        Ok(id)
+    }
+
+    async fn cancel(&mut self, order_id: u64) -> Result<()> {
+        self.inner.cancel(order_id).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BASE: Address = Address::repeat_byte(1);
+    const QUOTE: Address = Address::repeat_byte(2);
+
+    fn policy(spent: u64, expires_at: Option<u64>) -> SessionPolicy {
+        SessionPolicy {
+            tokens: vec![BASE, QUOTE],
+            max_order_notional: U256::from(100),
+            max_total_notional: U256::from(250),
+            spent_notional: U256::from(spent),
+            expires_at,
+            pull_from: None,
+        }
+    }
+
+    fn place(base_token: Address, notional: u64) -> Action {
+        Action::Place { base_token, quote_token: QUOTE, notional: U256::from(notional) }
+    }
+
+    #[test]
+    fn orders_within_the_policy_are_allowed() {
+        policy(0, None).authorize(place(BASE, 100)).unwrap();
+        policy(150, Some(now() + 60)).authorize(place(BASE, 100)).unwrap();
+    }
+
+    #[test]
+    fn funds_never_leave_through_a_session_key() {
+        assert!(policy(0, None).authorize(Action::Withdraw).is_err());
+        assert!(policy(0, None).authorize(Action::Transfer).is_err());
+    }
+
+    #[test]
+    fn orders_outside_the_policy_are_refused() {
+        let other = Address::repeat_byte(3);
+        assert!(policy(0, None).authorize(place(other, 10)).is_err());
+        assert!(policy(0, None).authorize(place(BASE, 101)).is_err());
+        assert!(policy(200, None).authorize(place(BASE, 51)).is_err());
+    }
+
+    #[test]
+    fn expired_sessions_may_only_cancel() {
+        let expired = policy(0, Some(now() - 1));
+        assert!(expired.authorize(place(BASE, 10)).is_err());
+        expired.authorize(Action::Cancel).unwrap();
+    }
+
+    #[test]
+    fn revoking_ends_the_session_but_keeps_the_policy() {
+        let key = Address::repeat_byte(7);
+        let mut store = SessionStore::default();
+        store.sessions.insert(key, policy(0, None));
+        store.revoke(key).unwrap();
+        assert!(store.sessions[&key].is_expired());
+        assert!(store.revoke(Address::repeat_byte(8)).is_err());
+    }
+}
@@ -0,0 +1,64 @@
+use std::sync::OnceLock;
+use tokio::sync::watch;
+use tracing::warn;
+
+static SHUTDOWN: OnceLock<watch::Sender<bool>> = OnceLock::new();
+
+/// The process-wide shutdown flag, listening for SIGINT and SIGTERM from the first use on
+/// so a signal that arrives mid-iteration is still seen at the next check. A second
+/// signal exits straight away. Must first be used from within the tokio runtime.
+fn sender() -> &'static watch::Sender<bool> {
+    SHUTDOWN.get_or_init(|| {
+        tokio::spawn(async {
+            let name = signal().await;
+            warn!("Received {}, shutting down (send it again to exit immediately)", name);
+            request();
+            let name = signal().await;
+            warn!("Received {} again, exiting immediately", name);
+            std::process::exit(130);
+        });
+        watch::channel(false).0
+    })
+}
+
+/// Wait for SIGINT (Ctrl-C) or, on Unix, SIGTERM, returning the signal's name
+async fn signal() -> &'static str {
+    #[cfg(unix)]
+    {
+        use tokio::signal::unix::{signal, SignalKind};
+        let mut terminate = match signal(SignalKind::terminate()) {
+            Ok(terminate) => terminate,
+            Err(e) => {
+                warn!("Couldn't listen for SIGTERM: {}", e);
+                let _ = tokio::signal::ctrl_c().await;
+                return "SIGINT";
+            }
+        };
+        tokio::select! {
+            _ = tokio::signal::ctrl_c() => "SIGINT",
+            _ = terminate.recv() => "SIGTERM",
+        }
+    }
+    #[cfg(not(unix))]
+    {
+        let _ = tokio::signal::ctrl_c().await;
+        "SIGINT"
+    }
+}
+
+/// Ask everything watching the flag to wind down, as a signal would
+pub fn request() {
+    sender().send_replace(true);
+}
+
+/// Whether shutdown has been requested
+pub fn requested() -> bool {
+    *sender().borrow()
+}
+
+/// Resolve once shutdown has been requested, immediately if it already has
+pub async fn wait() {
+    let mut receiver = sender().subscribe();
+    // Only fails if the sender is dropped, which the static never is
+    let _ = receiver.wait_for(|requested| *requested).await;
+}
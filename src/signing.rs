@@ -0,0 +1,134 @@
+use crate::bindings::OrderBookDEX;
+use crate::error::{DexError, Result};
+use crate::rpc;
+use crate::wallets::AccountSigner;
+use anyhow::{anyhow, Context};
+use ethers::{
+    abi::{encode, Token},
+    providers::Middleware,
+    signers::Signer,
+    types::{Address, Signature, H256, U256},
+    utils::keccak256,
+};
+use serde::{Deserialize, Serialize};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+const DOMAIN_TYPE: &str = "EIP712Domain(string name,string version,uint256 chainId,address verifyingContract)";
+const ORDER_TYPE: &str =
+    "Order(address maker,address baseToken,address quoteToken,uint256 amount,uint256 price,bool isBuy,uint256 nonce,uint256 expiry)";
+
+/// EIP-712 domain of a deployed DEX
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct OrderDomain {
+    pub name: String,
+    pub version: String,
+    pub chain_id: u64,
+    pub verifying_contract: Address,
+}
+
+impl OrderDomain {
+    /// Read the domain from the DEX's EIP-5267 `eip712Domain()`, so signatures are bound
+    /// to exactly the deployment and chain that will settle them
+    pub async fn fetch<M: Middleware + 'static>(contract: &OrderBookDEX<M>) -> Result<Self> {
+        let (_, name, version, chain_id, verifying_contract, _, _) = contract
+            .eip712_domain()
+            .call()
+            .await
+            .with_context(|| format!("{:?} has no EIP-712 domain; redeploy the DEX to sign orders for it", contract.address()))?;
+        Ok(Self {
+            name,
+            version,
+            chain_id: chain_id.low_u64(),
+            verifying_contract,
+        })
+    }
+
+    pub fn separator(&self) -> [u8; 32] {
+        keccak256(encode(&[
+            Token::FixedBytes(keccak256(DOMAIN_TYPE).to_vec()),
+            Token::FixedBytes(keccak256(&self.name).to_vec()),
+            Token::FixedBytes(keccak256(&self.version).to_vec()),
+            Token::Uint(U256::from(self.chain_id)),
+            Token::Address(self.verifying_contract),
+        ]))
+    }
+}
+
+/// An order intent signed off-chain by its maker
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct OffchainOrder {
+    pub maker: Address,
+    pub base_token: Address,
+    pub quote_token: Address,
+    pub amount: u64,
+    pub price: u64,
+    pub is_buy: bool,
+    /// Distinguishes otherwise identical orders from the same maker
+    pub nonce: u64,
+    /// Unix timestamp after which the order must not be settled
+    pub expiry: u64,
+}
+
+impl OffchainOrder {
+    pub fn struct_hash(&self) -> [u8; 32] {
+        keccak256(encode(&[
+            Token::FixedBytes(keccak256(ORDER_TYPE).to_vec()),
+            Token::Address(self.maker),
+            Token::Address(self.base_token),
+            Token::Address(self.quote_token),
+            Token::Uint(U256::from(self.amount)),
+            Token::Uint(U256::from(self.price)),
+            Token::Bool(self.is_buy),
+            Token::Uint(U256::from(self.nonce)),
+            Token::Uint(U256::from(self.expiry)),
+        ]))
+    }
+
+    /// `keccak256("\x19\x01" ‖ domainSeparator ‖ structHash)`, matching the DEX's `hashOrder`
+    pub fn digest(&self, domain: &OrderDomain) -> H256 {
+        let mut message = Vec::with_capacity(66);
+        message.extend_from_slice(&[0x19, 0x01]);
+        message.extend_from_slice(&domain.separator());
+        message.extend_from_slice(&self.struct_hash());
+        H256::from(keccak256(message))
+    }
+
+    pub fn is_expired(&self) -> bool {
+        unix_now() >= self.expiry
+    }
+}
+
+/// An order together with its domain and the maker's signature, as handed to a relayer
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SignedOrder {
+    pub domain: OrderDomain,
+    pub order: OffchainOrder,
+    pub digest: H256,
+    pub signature: Signature,
+}
+
+impl SignedOrder {
+    pub fn sign(wallet: &AccountSigner, domain: OrderDomain, order: OffchainOrder) -> Result<Self> {
+        if order.maker != wallet.address() {
+            return Err(DexError::Input(format!("Order maker {:?} is not the signing wallet {:?}", order.maker, wallet.address())));
+        }
+        rpc::ensure_writable()?;
+        let digest = order.digest(&domain);
+        let signature = wallet
+            .sign_hash(digest)
+            .map_err(|e| anyhow!("Failed to sign order: {}", e))?;
+        Ok(Self {
+            domain,
+            order,
+            digest,
+            signature,
+        })
+    }
+}
+
+pub fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default()
+}
@@ -0,0 +1,121 @@
+use crate::error::{DexError, Result};
+use anyhow::Context;
+use ethers::types::{Address, U256};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Directory snapshots are written to when no path is given
+const SNAPSHOT_DIR: &str = "config/snapshots";
+
+/// One pair's full book as `getOrderBook` returned it at `block`. Orders are kept
+/// individually and in contract order, since matching depends on ID order, not just levels.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BookSnapshot {
+    pub dex: Address,
+    pub base_token: Address,
+    pub quote_token: Address,
+    pub block: u64,
+    pub price_precision: U256,
+    /// `(price, amount)` of each resting buy order
+    pub bids: Vec<(U256, U256)>,
+    /// `(price, amount)` of each resting sell order
+    pub asks: Vec<(U256, U256)>,
+}
+
+/// How one price level changed between two snapshots
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct LevelDiff {
+    pub is_buy: bool,
+    pub price: U256,
+    pub before: U256,
+    pub after: U256,
+    pub orders_before: usize,
+    pub orders_after: usize,
+}
+
+impl LevelDiff {
+    pub fn kind(&self) -> &'static str {
+        if self.before.is_zero() {
+            "added"
+        } else if self.after.is_zero() {
+            "removed"
+        } else if self.after > self.before {
+            "grew"
+        } else if self.after < self.before {
+            "shrank"
+        } else {
+            "reshuffled"
+        }
+    }
+}
+
+impl BookSnapshot {
+    pub fn default_path(&self) -> PathBuf {
+        Path::new(SNAPSHOT_DIR).join(format!("{:?}-{:?}-{}.json", self.base_token, self.quote_token, self.block))
+    }
+
+    pub fn load(path: &Path) -> Result<Self> {
+        let content = fs::read_to_string(path).with_context(|| format!("Failed to read {}", path.display()))?;
+        Ok(serde_json::from_str(&content).with_context(|| format!("{} is not a book snapshot", path.display()))?)
+    }
+
+    /// Write the snapshot as compact JSON
+    pub fn save(&self, path: &Path) -> Result<()> {
+        if let Some(dir) = path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+            fs::create_dir_all(dir)?;
+        }
+        fs::write(path, serde_json::to_string(self)?)?;
+        Ok(())
+    }
+
+    /// Total amount and order count per `(is_buy, price)`
+    pub fn levels(&self) -> BTreeMap<(bool, U256), (U256, usize)> {
+        let mut levels = BTreeMap::new();
+        let sides = self.bids.iter().map(|order| (true, order)).chain(self.asks.iter().map(|order| (false, order)));
+        for (is_buy, (price, amount)) in sides {
+            let level = levels.entry((is_buy, *price)).or_insert((U256::zero(), 0));
+            level.0 += *amount;
+            level.1 += 1;
+        }
+        levels
+    }
+
+    /// Level-by-level changes from `self` to `later`, bids from the best price down then asks
+    /// from the best price up. Levels whose orders were replaced at the same total are reported
+    /// as reshuffled.
+    pub fn diff(&self, later: &BookSnapshot) -> Result<Vec<LevelDiff>> {
+        if (self.dex, self.base_token, self.quote_token) != (later.dex, later.base_token, later.quote_token) {
+            return Err(DexError::Input("Snapshots are of different books".to_string()));
+        }
+
+        let before = self.levels();
+        let after = later.levels();
+        let keys: BTreeSet<_> = before.keys().chain(after.keys()).copied().collect();
+        let empty = (U256::zero(), 0);
+
+        let mut diffs: Vec<LevelDiff> = keys
+            .into_iter()
+            .filter_map(|key| {
+                let (before_amount, orders_before) = *before.get(&key).unwrap_or(&empty);
+                let (after_amount, orders_after) = *after.get(&key).unwrap_or(&empty);
+                let changed = before_amount != after_amount || orders_before != orders_after;
+                changed.then_some(LevelDiff {
+                    is_buy: key.0,
+                    price: key.1,
+                    before: before_amount,
+                    after: after_amount,
+                    orders_before,
+                    orders_after,
+                })
+            })
+            .collect();
+        diffs.sort_by(|a, b| match (a.is_buy, b.is_buy) {
+            (true, true) => b.price.cmp(&a.price),
+            (false, false) => a.price.cmp(&b.price),
+            _ => b.is_buy.cmp(&a.is_buy),
+        });
+        Ok(diffs)
+    }
+}
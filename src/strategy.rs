@@ -0,0 +1,760 @@
+use crate::bands::PriceBands;
+use crate::breaker::BreakerConfig;
+use crate::error::{DexError, Result};
+use crate::marketdata::SharedBookConfig;
+use crate::metrics::metrics;
+use crate::notify::NotifyConfig;
+use crate::script::{ScriptParams, ScriptStrategy};
+use crate::submit::PipelineConfig;
+use anyhow::Context;
+use ethers::types::{Address, H256, U256};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::collections::BTreeMap;
+use std::fs;
+use std::time::Duration;
+use tracing::info;
+
+/// One aggregated price level
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Level {
+    pub price: U256,
+    pub amount: U256,
+}
+
+/// Snapshot of one pair's book handed to strategies
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct BookView {
+    /// Block the snapshot was taken at, when known
+    pub block: Option<u64>,
+    /// Bids, best (highest) first
+    pub bids: Vec<Level>,
+    /// Asks, best (lowest) first
+    pub asks: Vec<Level>,
+    /// External reference price in DEX price units, when the bot has a reference feed
+    #[serde(default)]
+    pub reference: Option<f64>,
+}
+
+impl BookView {
+    /// Aggregate `getOrderBook` output into sorted price levels
+    pub fn from_levels(
+        buy_prices: &[U256],
+        buy_amounts: &[U256],
+        sell_prices: &[U256],
+        sell_amounts: &[U256],
+    ) -> Self {
+        Self {
+            block: None,
+            bids: aggregate(buy_prices, buy_amounts, true),
+            asks: aggregate(sell_prices, sell_amounts, false),
+            reference: None,
+        }
+    }
+
+    pub fn best_bid(&self) -> Option<U256> {
+        self.bids.first().map(|level| level.price)
+    }
+
+    pub fn best_ask(&self) -> Option<U256> {
+        self.asks.first().map(|level| level.price)
+    }
+
+    /// Midpoint of the best bid and ask; `None` unless both sides have liquidity
+    pub fn mid(&self) -> Option<f64> {
+        let bid = self.best_bid()?;
+        let ask = self.best_ask()?;
+        Some((to_f64(bid) + to_f64(ask)) / 2.0)
+    }
+
+    /// The price makers quote around: the reference price when there is one, else the mid
+    pub fn fair_price(&self) -> Option<f64> {
+        self.reference.or_else(|| self.mid())
+    }
+}
+
+fn aggregate(prices: &[U256], amounts: &[U256], descending: bool) -> Vec<Level> {
+    let mut levels: BTreeMap<U256, U256> = BTreeMap::new();
+    for (price, amount) in prices.iter().zip(amounts) {
+        *levels.entry(*price).or_default() += *amount;
+    }
+    let levels = levels.into_iter().map(|(price, amount)| Level { price, amount });
+    if descending {
+        levels.rev().collect()
+    } else {
+        levels.collect()
+    }
+}
+
+/// A strategy's resting order as seen by the strategy
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct OpenOrder {
+    pub id: u64,
+    pub price: U256,
+    /// Unfilled base amount
+    pub amount: U256,
+    pub is_buy: bool,
+}
+
+/// An action a strategy wants taken
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum Intent {
+    Place { price: U256, amount: U256, is_buy: bool },
+    Cancel { order_id: u64 },
+}
+
+/// A (partial) fill of one of the strategy's orders
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Fill {
+    pub order_id: u64,
+    pub is_buy: bool,
+    /// Base amount filled
+    pub amount: U256,
+    pub price: U256,
+}
+
+/// Another trader's DEX transaction, seen in the mempool before it is mined
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PendingActivity {
+    pub tx_hash: H256,
+    pub from: Address,
+    pub base_token: Address,
+    pub quote_token: Address,
+    pub action: PendingAction,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum PendingAction {
+    LimitOrder { price: U256, amount: U256, is_buy: bool },
+    MarketOrder { amount: U256, is_buy: bool },
+    /// Cancellation of a resting order, with that order's details
+    Cancel { order_id: u64, price: U256, amount: U256, is_buy: bool },
+}
+
+impl PendingActivity {
+    /// Whether this transaction would trade against `order` once mined
+    pub fn threatens(&self, order: &OpenOrder) -> bool {
+        match self.action {
+            PendingAction::MarketOrder { is_buy, .. } => is_buy != order.is_buy,
+            PendingAction::LimitOrder { price, is_buy: true, .. } => !order.is_buy && order.price <= price,
+            PendingAction::LimitOrder { price, is_buy: false, .. } => order.is_buy && order.price >= price,
+            PendingAction::Cancel { .. } => false,
+        }
+    }
+}
+
+/// Trading logic shared by the live bot, paper trading and backtests.
+///
+/// Strategies never talk to the chain; they react to book updates and fills with
+/// intents, and the caller decides how those are executed. Strategies from other crates
+/// plug in through a [`StrategyRegistry`].
+pub trait Strategy: Send {
+    fn name(&self) -> &str;
+
+    /// Called with every new book snapshot and the strategy's current resting orders
+    fn on_book_update(&mut self, book: &BookView, open_orders: &[OpenOrder]) -> Vec<Intent>;
+
+    /// Called for every fill before the next book update
+    fn on_fill(&mut self, _fill: &Fill) -> Vec<Intent> {
+        Vec::new()
+    }
+
+    /// Called before the book update with competing transactions pending in the
+    /// mempool, so quotes about to be picked off can be pulled first
+    fn on_pending(&mut self, _pending: &[PendingActivity], _open_orders: &[OpenOrder]) -> Vec<Intent> {
+        Vec::new()
+    }
+
+    /// Called once before the first book update with the trader's net base position in
+    /// the pair, so inventory-aware strategies don't start from flat
+    fn on_position(&mut self, _position: f64) {}
+
+    /// How often live and paper bots call [`on_timer`](Self::on_timer); never when `None`.
+    /// Timers are checked once per book poll, so they fire no more often than that.
+    fn timer_interval(&self) -> Option<Duration> {
+        None
+    }
+
+    /// Called every `timer_interval`, before the book update, with the strategy's resting
+    /// orders, for work driven by time rather than the book (e.g. refreshing stale quotes)
+    fn on_timer(&mut self, _open_orders: &[OpenOrder]) -> Vec<Intent> {
+        Vec::new()
+    }
+
+    /// Take new parameters while running. Resting orders that still fit are kept; the
+    /// rest are replaced on the next book update.
+    fn reconfigure(&mut self, _config: &StrategyConfig) -> Result<()> {
+        Err(DexError::Config(format!("{} can't change parameters while running", self.name())))
+    }
+}
+
+/// Intents that turn `open_orders` into `quotes`, leaving alone resting orders that
+/// already match a quote
+fn reconcile(open_orders: &[OpenOrder], quotes: Vec<Intent>) -> Vec<Intent> {
+    let mut kept = vec![false; open_orders.len()];
+    let mut places = Vec::new();
+    for quote in quotes {
+        if let Intent::Place { price, amount, is_buy } = quote {
+            let existing = open_orders.iter().enumerate().position(|(i, order)| {
+                !kept[i] && order.price == price && order.amount == amount && order.is_buy == is_buy
+            });
+            match existing {
+                Some(i) => kept[i] = true,
+                None => places.push(quote),
+            }
+        }
+    }
+    let mut intents: Vec<Intent> = open_orders
+        .iter()
+        .zip(kept)
+        .filter(|(_, kept)| !kept)
+        .map(|(order, _)| Intent::Cancel { order_id: order.id })
+        .collect();
+    intents.extend(places);
+    intents
+}
+
+/// Parameters of the symmetric market maker
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MakerParams {
+    /// Distance between the innermost bid and ask, in basis points of mid
+    pub spread_bps: f64,
+    /// Base amount per quote
+    pub order_size: u64,
+    /// Quotes per side
+    #[serde(default = "default_levels")]
+    pub levels: usize,
+    /// Extra distance between consecutive levels, in basis points of mid
+    #[serde(default)]
+    pub level_spacing_bps: f64,
+    /// Mid move that triggers a requote, in basis points
+    #[serde(default = "default_requote_bps")]
+    pub requote_threshold_bps: f64,
+    /// Cancel quotes that a pending order in the mempool would take, and requote on
+    /// the iteration after
+    #[serde(default)]
+    pub pull_on_pending: bool,
+    /// Shift both sides away from the current inventory by this many basis points of mid
+    /// per `order_size` of net position: lower while long, higher while short
+    #[serde(default)]
+    pub inventory_skew_bps: f64,
+}
+
+fn default_levels() -> usize {
+    1
+}
+
+fn default_requote_bps() -> f64 {
+    10.0
+}
+
+/// Quotes `levels` bids and asks symmetrically around mid and requotes when mid moves
+#[derive(Debug, Clone)]
+pub struct SymmetricMaker {
+    params: MakerParams,
+    quoted_mid: Option<f64>,
+    /// Quotes were pulled this iteration; skip requoting until the next one
+    pulled: bool,
+    /// Net base position, from the starting position plus fills since
+    inventory: f64,
+    /// Parameters changed; move to the new ladder without replacing orders that still fit
+    reconfigured: bool,
+}
+
+impl SymmetricMaker {
+    pub fn new(params: MakerParams) -> Self {
+        Self {
+            params,
+            quoted_mid: None,
+            pulled: false,
+            inventory: 0.0,
+            reconfigured: false,
+        }
+    }
+
+    /// Mid the ladder is centred on once inventory skew is applied
+    fn reservation_price(&self, mid: f64) -> f64 {
+        let order_size = (self.params.order_size as f64).max(1.0);
+        mid * (1.0 - self.params.inventory_skew_bps * self.inventory / order_size / 10_000.0)
+    }
+
+    fn quotes(&self, mid: f64) -> Vec<Intent> {
+        let mid = self.reservation_price(mid);
+        let size = U256::from(self.params.order_size);
+        let mut intents = Vec::with_capacity(self.params.levels * 2);
+        for level in 0..self.params.levels {
+            let offset = (self.params.spread_bps / 2.0 + level as f64 * self.params.level_spacing_bps) / 10_000.0;
+            let bid = (mid * (1.0 - offset)).floor();
+            let ask = (mid * (1.0 + offset)).ceil();
+            if bid >= 1.0 {
+                intents.push(Intent::Place { price: U256::from(bid as u128), amount: size, is_buy: true });
+            }
+            intents.push(Intent::Place { price: U256::from(ask as u128), amount: size, is_buy: false });
+        }
+        intents
+    }
+}
+
+impl Strategy for SymmetricMaker {
+    fn name(&self) -> &str {
+        "symmetric_maker"
+    }
+
+    fn on_book_update(&mut self, book: &BookView, open_orders: &[OpenOrder]) -> Vec<Intent> {
+        if std::mem::take(&mut self.pulled) {
+            return Vec::new();
+        }
+        let Some(mid) = book.fair_price() else {
+            return Vec::new();
+        };
+
+        let complete = open_orders.len() == self.params.levels * 2;
+        let moved = self.quoted_mid.is_none_or(|quoted| {
+            ((mid - quoted) / quoted).abs() * 10_000.0 > self.params.requote_threshold_bps
+        });
+        if complete && !moved && !self.reconfigured {
+            return Vec::new();
+        }
+
+        self.quoted_mid = Some(mid);
+        if std::mem::take(&mut self.reconfigured) {
+            return reconcile(open_orders, self.quotes(mid));
+        }
+        let mut intents: Vec<Intent> = open_orders
+            .iter()
+            .map(|order| Intent::Cancel { order_id: order.id })
+            .collect();
+        intents.extend(self.quotes(mid));
+        intents
+    }
+
+    fn on_fill(&mut self, fill: &Fill) -> Vec<Intent> {
+        let amount = to_f64(fill.amount);
+        self.inventory += if fill.is_buy { amount } else { -amount };
+        // Refresh the whole ladder on the next book update
+        self.quoted_mid = None;
+        Vec::new()
+    }
+
+    fn on_position(&mut self, position: f64) {
+        self.inventory = position;
+        self.quoted_mid = None;
+    }
+
+    fn on_pending(&mut self, pending: &[PendingActivity], open_orders: &[OpenOrder]) -> Vec<Intent> {
+        if !self.params.pull_on_pending {
+            return Vec::new();
+        }
+        let pulled: Vec<Intent> = open_orders
+            .iter()
+            .filter(|order| pending.iter().any(|activity| activity.threatens(order)))
+            .map(|order| Intent::Cancel { order_id: order.id })
+            .collect();
+        if !pulled.is_empty() {
+            self.quoted_mid = None;
+            self.pulled = true;
+        }
+        pulled
+    }
+
+    fn reconfigure(&mut self, config: &StrategyConfig) -> Result<()> {
+        let StrategyConfig::SymmetricMaker(params) = config else {
+            return Err(DexError::Config(format!("Can't switch from {} to {} without a restart", self.name(), config.kind())));
+        };
+        self.params = params.clone();
+        self.reconfigured = true;
+        Ok(())
+    }
+}
+
+/// Parameters of the inventory-aware market maker. Spreads, volatility and offsets are
+/// in basis points of mid; inventory is counted in multiples of `order_size`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct InventoryMakerParams {
+    /// Base amount per quote
+    pub order_size: u64,
+    /// How strongly inventory pushes the quote center away, and how much volatility
+    /// widens the spread (gamma)
+    #[serde(default = "default_risk_aversion")]
+    pub risk_aversion: f64,
+    /// How fast the chance of a fill falls off with distance from mid (kappa); lower
+    /// values quote wider
+    #[serde(default = "default_order_intensity")]
+    pub order_intensity: f64,
+    /// Iterations ahead the inventory is expected to be held (T - t)
+    #[serde(default = "default_horizon")]
+    pub horizon: f64,
+    /// Half-life of the volatility estimate, in iterations
+    #[serde(default = "default_volatility_halflife")]
+    pub volatility_halflife: f64,
+    #[serde(default = "default_min_spread_bps")]
+    pub min_spread_bps: f64,
+    #[serde(default = "default_max_spread_bps")]
+    pub max_spread_bps: f64,
+    /// Stop quoting the side that would take inventory beyond this many order sizes
+    #[serde(default)]
+    pub max_inventory: Option<f64>,
+    /// Move of the quote center or spread that triggers a requote
+    #[serde(default = "default_requote_bps")]
+    pub requote_threshold_bps: f64,
+}
+
+fn default_risk_aversion() -> f64 {
+    0.1
+}
+
+fn default_order_intensity() -> f64 {
+    0.1
+}
+
+fn default_horizon() -> f64 {
+    1.0
+}
+
+fn default_volatility_halflife() -> f64 {
+    20.0
+}
+
+fn default_min_spread_bps() -> f64 {
+    10.0
+}
+
+fn default_max_spread_bps() -> f64 {
+    500.0
+}
+
+/// Where an [`InventoryMaker`] last quoted
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct QuoteModel {
+    pub mid: f64,
+    /// Reservation price: mid shifted against the current inventory
+    pub center: f64,
+    pub spread_bps: f64,
+    pub volatility_bps: f64,
+}
+
+/// Avellaneda–Stoikov style market maker: centres one bid and one ask on a reservation
+/// price that leans against inventory, with a spread that widens with volatility.
+///
+/// Per iteration, with `q` the inventory in order sizes, `s` the volatility and `T` the
+/// horizon:
+/// - center offset = `q * gamma * s^2 * T` bps below mid (above while short)
+/// - spread = `gamma * s^2 * T + (2 / gamma) * ln(1 + gamma / kappa)` bps, clamped
+#[derive(Debug, Clone)]
+pub struct InventoryMaker {
+    params: InventoryMakerParams,
+    inventory: f64,
+    last_mid: Option<f64>,
+    /// Exponentially weighted variance of mid returns, in bps squared
+    variance: f64,
+    quoted: Option<QuoteModel>,
+    reconfigured: bool,
+}
+
+impl InventoryMaker {
+    pub fn new(params: InventoryMakerParams) -> Self {
+        Self {
+            params,
+            inventory: 0.0,
+            last_mid: None,
+            variance: 0.0,
+            quoted: None,
+            reconfigured: false,
+        }
+    }
+
+    /// Fold the latest mid into the volatility estimate
+    fn observe(&mut self, mid: f64) {
+        if let Some(last) = self.last_mid.filter(|last| *last > 0.0) {
+            let change_bps = (mid - last) / last * 10_000.0;
+            let alpha = 1.0 - 0.5f64.powf(1.0 / self.params.volatility_halflife.max(1.0));
+            self.variance = (1.0 - alpha) * self.variance + alpha * change_bps * change_bps;
+        }
+        self.last_mid = Some(mid);
+    }
+
+    pub fn model(&self, mid: f64) -> QuoteModel {
+        let p = &self.params;
+        let gamma = p.risk_aversion.max(f64::EPSILON);
+        let risk = gamma * self.variance * p.horizon;
+        let q = self.inventory / (p.order_size as f64).max(1.0);
+
+        let spread_bps = (risk + 2.0 / gamma * (1.0 + gamma / p.order_intensity.max(f64::EPSILON)).ln())
+            .clamp(p.min_spread_bps, p.max_spread_bps.max(p.min_spread_bps));
+        QuoteModel {
+            mid,
+            center: mid * (1.0 - q * risk / 10_000.0),
+            spread_bps,
+            volatility_bps: self.variance.sqrt(),
+        }
+    }
+
+    fn quotes(&self, model: &QuoteModel) -> Vec<Intent> {
+        let size = U256::from(self.params.order_size);
+        let q = self.inventory / (self.params.order_size as f64).max(1.0);
+        let (bid_allowed, ask_allowed) = match self.params.max_inventory {
+            Some(max) => (q + 1.0 <= max, q - 1.0 >= -max),
+            None => (true, true),
+        };
+
+        let half = model.spread_bps / 2.0 / 10_000.0;
+        let bid = (model.center * (1.0 - half)).floor();
+        let ask = (model.center * (1.0 + half)).ceil();
+        let mut intents = Vec::with_capacity(2);
+        if bid_allowed && bid >= 1.0 {
+            intents.push(Intent::Place { price: U256::from(bid as u128), amount: size, is_buy: true });
+        }
+        if ask_allowed && ask >= 1.0 {
+            intents.push(Intent::Place { price: U256::from(ask as u128), amount: size, is_buy: false });
+        }
+        intents
+    }
+}
+
+impl Strategy for InventoryMaker {
+    fn name(&self) -> &str {
+        "inventory_maker"
+    }
+
+    fn on_book_update(&mut self, book: &BookView, open_orders: &[OpenOrder]) -> Vec<Intent> {
+        let Some(mid) = book.fair_price() else {
+            return Vec::new();
+        };
+        self.observe(mid);
+        let model = self.model(mid);
+
+        let threshold = self.params.requote_threshold_bps;
+        let stale = self.quoted.is_none_or(|quoted| {
+            ((model.center - quoted.center) / quoted.center).abs() * 10_000.0 > threshold
+                || (model.spread_bps - quoted.spread_bps).abs() > threshold
+        });
+        let intents = self.quotes(&model);
+        if !stale && open_orders.len() == intents.len() && !self.reconfigured {
+            return Vec::new();
+        }
+
+        let metrics = metrics();
+        let name = [self.name()];
+        metrics.bot_mid.with_label_values(&name).set(book.mid().unwrap_or(mid));
+        metrics.bot_quote_center.with_label_values(&name).set(model.center);
+        metrics.bot_quote_spread_bps.with_label_values(&name).set(model.spread_bps);
+        metrics.bot_volatility_bps.with_label_values(&name).set(model.volatility_bps);
+        info!(
+            "Quoting around {:.2} (mid {:.2}, {:+.1} bps), spread {:.1} bps, volatility {:.1} bps, inventory {}",
+            model.center,
+            mid,
+            (model.center - mid) / mid * 10_000.0,
+            model.spread_bps,
+            model.volatility_bps,
+            self.inventory
+        );
+
+        self.quoted = Some(model);
+        if std::mem::take(&mut self.reconfigured) {
+            return reconcile(open_orders, intents);
+        }
+        let mut all: Vec<Intent> = open_orders
+            .iter()
+            .map(|order| Intent::Cancel { order_id: order.id })
+            .collect();
+        all.extend(intents);
+        all
+    }
+
+    fn on_fill(&mut self, fill: &Fill) -> Vec<Intent> {
+        let amount = to_f64(fill.amount);
+        self.inventory += if fill.is_buy { amount } else { -amount };
+        // The center moves with inventory, so requote on the next book update
+        self.quoted = None;
+        Vec::new()
+    }
+
+    fn on_position(&mut self, position: f64) {
+        self.inventory = position;
+        self.quoted = None;
+    }
+
+    fn reconfigure(&mut self, config: &StrategyConfig) -> Result<()> {
+        let StrategyConfig::InventoryMaker(params) = config else {
+            return Err(DexError::Config(format!("Can't switch from {} to {} without a restart", self.name(), config.kind())));
+        };
+        self.params = params.clone();
+        self.reconfigured = true;
+        Ok(())
+    }
+}
+
+/// Strategy selection in a bot config file: `kind` picks a built-in strategy, or one
+/// registered with a [`StrategyRegistry`]
+#[derive(Debug, Clone)]
+pub enum StrategyConfig {
+    SymmetricMaker(MakerParams),
+    InventoryMaker(InventoryMakerParams),
+    Script(ScriptParams),
+    /// Any other kind; `params` is the whole section, `kind` included
+    Custom { kind: String, params: serde_json::Value },
+}
+
+impl StrategyConfig {
+    /// The `kind` written in the config file
+    pub fn kind(&self) -> &str {
+        match self {
+            StrategyConfig::SymmetricMaker(_) => "symmetric_maker",
+            StrategyConfig::InventoryMaker(_) => "inventory_maker",
+            StrategyConfig::Script(_) => "script",
+            StrategyConfig::Custom { kind, .. } => kind,
+        }
+    }
+
+    /// Most orders and base amount the strategy rests on one side of the book; `None`
+    /// for scripts and custom strategies, whose orders aren't known up front
+    pub fn footprint(&self) -> Option<(usize, U256)> {
+        match self {
+            StrategyConfig::SymmetricMaker(params) => {
+                Some((params.levels, U256::from(params.order_size) * U256::from(params.levels)))
+            }
+            StrategyConfig::InventoryMaker(params) => Some((1, U256::from(params.order_size))),
+            StrategyConfig::Script(_) | StrategyConfig::Custom { .. } => None,
+        }
+    }
+}
+
+impl Serialize for StrategyConfig {
+    fn serialize<S: Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        let mut value = match self {
+            StrategyConfig::SymmetricMaker(params) => serde_json::to_value(params),
+            StrategyConfig::InventoryMaker(params) => serde_json::to_value(params),
+            StrategyConfig::Script(params) => serde_json::to_value(params),
+            StrategyConfig::Custom { params, .. } => Ok(params.clone()),
+        }
+        .map_err(serde::ser::Error::custom)?;
+        if let Some(section) = value.as_object_mut() {
+            section.insert("kind".to_string(), self.kind().into());
+        }
+        value.serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for StrategyConfig {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> std::result::Result<Self, D::Error> {
+        let value = serde_json::Value::deserialize(deserializer)?;
+        let kind = value
+            .get("kind")
+            .and_then(|kind| kind.as_str())
+            .ok_or_else(|| serde::de::Error::missing_field("kind"))?
+            .to_string();
+        match kind.as_str() {
+            "symmetric_maker" => serde_json::from_value(value).map(StrategyConfig::SymmetricMaker),
+            "inventory_maker" => serde_json::from_value(value).map(StrategyConfig::InventoryMaker),
+            "script" => serde_json::from_value(value).map(StrategyConfig::Script),
+            _ => Ok(StrategyConfig::Custom { kind, params: value }),
+        }
+        .map_err(serde::de::Error::custom)
+    }
+}
+
+/// Builds a custom strategy from its config section
+pub type StrategyFactory = Box<dyn Fn(&serde_json::Value) -> Result<Box<dyn Strategy>> + Send + Sync>;
+
+/// The strategies a bot can run: the built-in kinds plus any registered by the crate
+/// embedding the runner
+#[derive(Default)]
+pub struct StrategyRegistry {
+    factories: BTreeMap<String, StrategyFactory>,
+}
+
+impl StrategyRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Make `kind` available to `[strategy]` sections. Built-in kinds always build the
+    /// built-in strategy, so registering one of those has no effect.
+    pub fn register<F>(&mut self, kind: &str, factory: F) -> &mut Self
+    where
+        F: Fn(&serde_json::Value) -> Result<Box<dyn Strategy>> + Send + Sync + 'static,
+    {
+        self.factories.insert(kind.to_string(), Box::new(factory));
+        self
+    }
+
+    /// Registered custom kinds
+    pub fn kinds(&self) -> impl Iterator<Item = &str> {
+        self.factories.keys().map(String::as_str)
+    }
+
+    pub fn build(&self, config: &StrategyConfig) -> Result<Box<dyn Strategy>> {
+        match config {
+            StrategyConfig::SymmetricMaker(params) => Ok(Box::new(SymmetricMaker::new(params.clone()))),
+            StrategyConfig::InventoryMaker(params) => Ok(Box::new(InventoryMaker::new(params.clone()))),
+            StrategyConfig::Script(params) => Ok(Box::new(ScriptStrategy::new(params.clone())?)),
+            StrategyConfig::Custom { kind, params } => {
+                let factory = self
+                    .factories
+                    .get(kind)
+                    .ok_or_else(|| DexError::Config(format!("Unknown strategy kind '{}'", kind)))?;
+                Ok(factory(params).with_context(|| format!("Invalid [strategy] for {}", kind))?)
+            }
+        }
+    }
+}
+
+/// Bot config file (TOML), shared by the live bot and backtests
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BotConfig {
+    pub base_token: Address,
+    pub quote_token: Address,
+    /// Seconds between book polls
+    #[serde(default = "default_interval_secs")]
+    pub interval_secs: u64,
+    /// Seconds between heartbeat notifications (none when unset)
+    #[serde(default)]
+    pub heartbeat_secs: Option<u64>,
+    /// Channels for fill confirmations, errors and heartbeats
+    #[serde(default)]
+    pub notify: NotifyConfig,
+    /// Pass other traders' pending DEX transactions to the strategy (live trading only;
+    /// the RPC endpoint must support pending transaction filters)
+    #[serde(default)]
+    pub watch_mempool: bool,
+    /// Concurrent submission for live trading; intents go one receipt at a time when unset
+    #[serde(default)]
+    pub pipeline: Option<PipelineConfig>,
+    /// Hand the strategy the signer's net position in the pair from fills indexed from this
+    /// block on (live trading only); the strategy starts flat when unset
+    #[serde(default)]
+    pub position_from_block: Option<u64>,
+    /// Fat-finger bands for live quotes; the ones set with `price-bands set` when unset
+    #[serde(default)]
+    pub price_bands: Option<PriceBands>,
+    /// Symbol of a `price-feed` to quote around instead of the book mid
+    #[serde(default)]
+    pub reference_feed: Option<String>,
+    /// Anomalies that halt the bot until `resume`
+    #[serde(default)]
+    pub circuit_breaker: BreakerConfig,
+    /// Cancel the bot's resting orders when it stops on SIGINT or SIGTERM
+    #[serde(default)]
+    pub cancel_on_shutdown: bool,
+    /// Read the book a `monad-dexd` publishing to Redis keeps, instead of polling the chain
+    /// (live trading only)
+    #[serde(default)]
+    pub shared_book: Option<SharedBookConfig>,
+    pub strategy: StrategyConfig,
+}
+
+fn default_interval_secs() -> u64 {
+    5
+}
+
+impl BotConfig {
+    pub fn load(path: &str) -> Result<Self> {
+        let content = fs::read_to_string(path).with_context(|| DexError::Config(format!("Failed to read {}", path)))?;
+        Ok(toml::from_str(&content).with_context(|| DexError::Config(format!("Invalid bot config {}", path)))?)
+    }
+}
+
+/// Lossy conversion for price arithmetic
+pub fn to_f64(value: U256) -> f64 {
+    value.to_string().parse().unwrap_or(f64::MAX)
+}
@@ -0,0 +1,199 @@
+use crate::bot::SignerClient;
+use crate::error::{DexError, Result};
+use crate::fees::Fees;
+use crate::rpc;
+use crate::tx::{self, TxArgs};
+use anyhow::anyhow;
+use ethers::{
+    providers::Middleware,
+    types::{transaction::eip2718::TypedTransaction, BlockNumber, TransactionReceipt, H256, U256},
+};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+use tokio::task::JoinHandle;
+use tokio::time::{sleep, timeout};
+use tracing::{info, warn};
+
+/// `[pipeline]` section of a bot config
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PipelineConfig {
+    /// Transactions broadcast but not yet mined; sending waits for a free slot beyond this
+    #[serde(default = "default_max_in_flight")]
+    pub max_in_flight: usize,
+    /// Intents accepted per bot iteration; the rest fail for the strategy to re-issue
+    #[serde(default = "default_max_queue")]
+    pub max_queue: usize,
+    /// Gas limit for every transaction. When unset each one is estimated against the
+    /// latest block, which can fail for a place that relies on an earlier cancel in the
+    /// same batch.
+    #[serde(default)]
+    pub gas_limit: Option<u64>,
+    #[serde(default = "default_receipt_timeout_secs")]
+    pub receipt_timeout_secs: u64,
+    #[serde(default = "default_poll_ms")]
+    pub poll_ms: u64,
+}
+
+fn default_max_in_flight() -> usize {
+    16
+}
+
+fn default_max_queue() -> usize {
+    64
+}
+
+fn default_receipt_timeout_secs() -> u64 {
+    60
+}
+
+fn default_poll_ms() -> u64 {
+    250
+}
+
+/// Signs and broadcasts transactions back to back with locally managed nonces and
+/// tracks their receipts concurrently, instead of waiting for each receipt before the
+/// next send. At most `max_in_flight` transactions are unconfirmed at once.
+pub struct SubmissionPipeline {
+    client: Arc<SignerClient>,
+    config: PipelineConfig,
+    slots: Arc<Semaphore>,
+    /// Next nonce to use; `None` re-reads the pending nonce from the node
+    next_nonce: Option<U256>,
+}
+
+impl SubmissionPipeline {
+    pub fn new(client: Arc<SignerClient>, config: PipelineConfig) -> Self {
+        Self {
+            client,
+            slots: Arc::new(Semaphore::new(config.max_in_flight.max(1))),
+            config,
+            next_nonce: None,
+        }
+    }
+
+    pub fn max_queue(&self) -> usize {
+        self.config.max_queue
+    }
+
+    /// Broadcast `txs` in order with consecutive nonces, then wait for every receipt.
+    /// Results line up with `txs`; a transaction that fails to estimate or broadcast
+    /// doesn't consume a nonce, so later ones never wait behind a gap.
+    pub async fn submit(&mut self, txs: Vec<TypedTransaction>, fees: &Fees, tx_args: &TxArgs) -> Vec<Result<TransactionReceipt>> {
+        if let Err(e) = rpc::ensure_writable() {
+            let message = e.to_string();
+            return txs.iter().map(|_| Err(anyhow!(message.clone()).into())).collect();
+        }
+        let mut nonce = match self.next_nonce {
+            Some(nonce) => nonce,
+            None => match self
+                .client
+                .get_transaction_count(self.client.address(), Some(BlockNumber::Pending.into()))
+                .await
+            {
+                Ok(nonce) => nonce,
+                Err(e) => {
+                    let message = format!("Failed to fetch nonce: {}", e);
+                    return txs.iter().map(|_| Err(anyhow!(message.clone()).into())).collect();
+                }
+            },
+        };
+
+        let mut handles: Vec<Result<JoinHandle<Result<Option<TransactionReceipt>>>>> = Vec::with_capacity(txs.len());
+        for mut tx in txs {
+            // Backpressure: wait for an earlier transaction to be mined before exceeding the limit
+            let permit = match self.slots.clone().acquire_owned().await {
+                Ok(permit) => permit,
+                Err(e) => {
+                    handles.push(Err(anyhow!("Submission pipeline closed: {}", e).into()));
+                    continue;
+                }
+            };
+
+            fees.apply(&mut tx);
+            tx.set_nonce(nonce);
+            let sent: Result<H256> = async {
+                let gas = match self.config.gas_limit {
+                    Some(limit) => U256::from(limit),
+                    None => self.client.estimate_gas(&tx, None).await.map_err(|e| DexError::middleware(&e))?,
+                };
+                tx.set_gas(gas);
+                let pending = self.client.send_transaction(tx, None).await.map_err(|e| DexError::middleware(&e))?;
+                Ok(*pending)
+            }
+            .await;
+
+            match sent {
+                Ok(hash) => {
+                    info!("Transaction sent: {:?} (nonce {})", hash, nonce);
+                    nonce += U256::one();
+                    handles.push(Ok(tokio::spawn(track(
+                        self.client.clone(),
+                        hash,
+                        permit,
+                        Duration::from_secs(self.config.receipt_timeout_secs),
+                        Duration::from_millis(self.config.poll_ms),
+                        tx_args.confirmations,
+                    ))));
+                }
+                Err(e) => handles.push(Err(e)),
+            }
+        }
+        self.next_nonce = Some(nonce);
+
+        let mut results = Vec::with_capacity(handles.len());
+        let mut unmined = false;
+        for handle in handles {
+            results.push(match handle {
+                Ok(handle) => match handle.await {
+                    Ok(Ok(Some(receipt))) => Ok(receipt),
+                    Ok(Ok(None)) => {
+                        unmined = true;
+                        Err(anyhow!("No receipt after {}s", self.config.receipt_timeout_secs).into())
+                    }
+                    Ok(Err(e)) => Err(e),
+                    Err(e) => Err(anyhow!("Receipt task failed: {}", e).into()),
+                },
+                Err(e) => Err(e),
+            });
+        }
+
+        // A transaction that never got mined may leave a gap; take the node's view next time
+        if unmined {
+            warn!("Some transactions were not mined in time; re-reading the nonce before the next batch");
+            self.next_nonce = None;
+        }
+        results
+    }
+}
+
+/// Poll for `hash`'s receipt, holding its in-flight slot until it is mined.
+/// Returns `None` if it isn't mined within `receipt_timeout`.
+async fn track(
+    client: Arc<SignerClient>,
+    hash: H256,
+    _permit: OwnedSemaphorePermit,
+    receipt_timeout: Duration,
+    poll: Duration,
+    confirmations: usize,
+) -> Result<Option<TransactionReceipt>> {
+    let receipt = timeout(receipt_timeout, async {
+        loop {
+            match client.get_transaction_receipt(hash).await {
+                Ok(Some(receipt)) => return receipt,
+                Ok(None) => {}
+                Err(e) => warn!("Receipt poll for {:?} failed: {}", hash, e),
+            }
+            sleep(poll).await;
+        }
+    })
+    .await;
+    let Ok(receipt) = receipt else {
+        return Ok(None);
+    };
+
+    tx::wait_for_confirmations(client.as_ref(), &receipt, confirmations).await?;
+    tx::ensure_success(client.as_ref(), &receipt).await?;
+    Ok(Some(receipt))
+}
@@ -0,0 +1,335 @@
+//! End-to-end test harness: a local chain with two test tokens and the DEX deployed
+//! from the forge artifacts in `out/`, plus funded trader accounts.
+//!
+//! The chain is a fresh anvil node by default. Set `MONAD_TEST_RPC_URL` and
+//! `MONAD_TEST_PRIVATE_KEYS` (comma-separated, deployer first) to run against a Monad
+//! dev node instead.
+
+use crate::bindings::{OrderBookDEX, ERC20};
+use crate::bot::SignerClient;
+use crate::error::Result;
+use crate::events;
+use crate::fees::Fees;
+use crate::pipeline;
+use crate::rpc::{self, RpcProvider};
+use crate::tx::{self, TxArgs};
+use anyhow::{anyhow, Context};
+use ethers::{
+    abi::{Detokenize, Token},
+    contract::ContractCall,
+    middleware::SignerMiddleware,
+    providers::Middleware,
+    signers::{LocalWallet, Signer},
+    types::{Address, TransactionReceipt, U256},
+    utils::{Anvil, AnvilInstance},
+};
+use std::env;
+use std::path::Path;
+use std::process::{Command, Stdio};
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Dev node RPC URL to test against instead of spawning anvil
+pub const RPC_URL_VAR: &str = "MONAD_TEST_RPC_URL";
+
+/// Funded keys on the dev node, deployer first
+pub const PRIVATE_KEYS_VAR: &str = "MONAD_TEST_PRIVATE_KEYS";
+
+/// Whole tokens each test token is created with, all held by the deployer
+const INITIAL_SUPPLY: u64 = 1_000_000;
+
+/// Whole tokens of each test token handed to every trader
+const TRADER_FUNDS: u64 = 10_000;
+
+const ARTIFACTS: &[&str] = &["TestToken", "OrderBookDEX"];
+
+/// Why end-to-end tests can't run in this environment, if they can't
+pub fn unavailable() -> Option<String> {
+    if let Some(contract) = ARTIFACTS
+        .iter()
+        .find(|contract| !Path::new(&format!("out/{0}.sol/{0}.json", contract)).exists())
+    {
+        return Some(format!(
+            "no artifact for {} (run `monad-deploy build`)",
+            contract
+        ));
+    }
+    if env::var(RPC_URL_VAR).is_ok() {
+        return None;
+    }
+    let anvil = Command::new("anvil")
+        .arg("--version")
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status();
+    match anvil {
+        Ok(status) if status.success() => None,
+        _ => Some(format!(
+            "anvil is not installed and {} is not set",
+            RPC_URL_VAR
+        )),
+    }
+}
+
+/// A chain with the DEX and a base/quote token pair deployed
+pub struct TestEnv {
+    /// Keeps the node running; `None` on a dev node
+    _anvil: Option<AnvilInstance>,
+    pub provider: Arc<RpcProvider>,
+    /// Deployed everything and owns the DEX
+    pub deployer: Arc<SignerClient>,
+    /// Accounts holding `TRADER_FUNDS` of both tokens
+    pub traders: Vec<Arc<SignerClient>>,
+    pub dex: OrderBookDEX<SignerClient>,
+    pub base_token: Address,
+    pub quote_token: Address,
+    pub fees: Fees,
+    pub tx_args: TxArgs,
+}
+
+impl TestEnv {
+    /// Start the chain, deploy the contracts and fund the traders. Fails with the reason
+    /// from [`unavailable`] when end-to-end tests can't run here.
+    pub async fn start() -> Result<Self> {
+        if let Some(reason) = unavailable() {
+            return Err(anyhow!("End-to-end tests can't run here: {}", reason).into());
+        }
+        let (anvil, rpc_url, keys) = match env::var(RPC_URL_VAR) {
+            Ok(rpc_url) => {
+                let keys: Vec<String> = env::var(PRIVATE_KEYS_VAR)
+                    .with_context(|| {
+                        format!("{} is set but {} is not", RPC_URL_VAR, PRIVATE_KEYS_VAR)
+                    })?
+                    .split(',')
+                    .map(|key| key.trim().to_string())
+                    .filter(|key| !key.is_empty())
+                    .collect();
+                (None, rpc_url, keys)
+            }
+            Err(_) => {
+                let anvil = Anvil::new().spawn();
+                let keys = anvil
+                    .keys()
+                    .iter()
+                    .map(|key| hex::encode(key.to_bytes()))
+                    .collect();
+                let rpc_url = anvil.endpoint();
+                (Some(anvil), rpc_url, keys)
+            }
+        };
+        if keys.len() < 2 {
+            return Err(anyhow!(
+                "Need a deployer and at least one trader key, got {}",
+                keys.len()
+            ).into());
+        }
+
+        // Local nodes mine instantly, so poll far more often than for a public RPC
+        let poll = Duration::from_millis(50);
+        let provider = Arc::new(rpc::connect(Some(&rpc_url)).await?.interval(poll));
+        let chain_id = provider.get_chainid().await?.as_u64();
+        let mut clients = Vec::with_capacity(keys.len());
+        for key in &keys {
+            let wallet = key
+                .parse::<LocalWallet>()
+                .map_err(|e| anyhow!("Invalid test private key: {}", e))?
+                .with_chain_id(chain_id);
+            let client_provider = rpc::connect(Some(&rpc_url)).await?.interval(poll);
+            clients.push(Arc::new(SignerMiddleware::new(client_provider, wallet.into())));
+        }
+        let deployer = clients[0].clone();
+        let traders = clients[1..].to_vec();
+
+        let tx_args = TxArgs::default().unattended();
+        let fees = tx_args.fees.resolve(provider.as_ref()).await?;
+
+        let base_token = deploy_token(&deployer, "Base Token", "BASE", &fees, &tx_args).await?;
+        let quote_token = deploy_token(&deployer, "Quote Token", "QUOTE", &fees, &tx_args).await?;
+        let (abi, bytecode) = pipeline::load_artifact("OrderBookDEX")?;
+        let (dex, _) =
+            pipeline::deploy_contract(deployer.clone(), abi, bytecode, Vec::new(), &fees, &tx_args)
+                .await?
+                .ok_or_else(|| anyhow!("DEX deployment was not sent"))?;
+
+        let env = Self {
+            _anvil: anvil,
+            provider,
+            dex: OrderBookDEX::new(dex, deployer.clone()),
+            deployer,
+            traders,
+            base_token,
+            quote_token,
+            fees,
+            tx_args,
+        };
+        for trader in &env.traders {
+            for token in [base_token, quote_token] {
+                env.fund(trader.address(), token, env.units(TRADER_FUNDS))
+                    .await?;
+            }
+        }
+        Ok(env)
+    }
+
+    /// `whole` tokens in the test tokens' 18-decimal base units
+    pub fn units(&self, whole: u64) -> U256 {
+        U256::from(whole) * U256::exp10(18)
+    }
+
+    /// The DEX as seen by `client`
+    pub fn dex_as(&self, client: &Arc<SignerClient>) -> OrderBookDEX<SignerClient> {
+        OrderBookDEX::new(self.dex.address(), client.clone())
+    }
+
+    /// ERC-20 `token` as seen by `client`
+    pub fn token_as(&self, token: Address, client: &Arc<SignerClient>) -> ERC20<SignerClient> {
+        ERC20::new(token, client.clone())
+    }
+
+    /// Wallet balance of `token` held by `account`
+    pub async fn balance_of(&self, token: Address, account: Address) -> Result<U256> {
+        Ok(self
+            .token_as(token, &self.deployer)
+            .balance_of(account)
+            .call()
+            .await?)
+    }
+
+    /// Send `call` as `client`, failing if it was dropped instead of mined
+    pub async fn send<D: Detokenize>(
+        &self,
+        client: &Arc<SignerClient>,
+        call: ContractCall<SignerClient, D>,
+    ) -> Result<TransactionReceipt> {
+        tx::send(client.as_ref(), call, &self.fees, &self.tx_args)
+            .await?
+            .ok_or_else(|| anyhow!("Transaction was dropped").into())
+    }
+
+    /// Transfer `amount` of `token` from the deployer to `to`
+    pub async fn fund(&self, to: Address, token: Address, amount: U256) -> Result<()> {
+        self.send(
+            &self.deployer,
+            self.token_as(token, &self.deployer).transfer(to, amount),
+        )
+        .await?;
+        Ok(())
+    }
+
+    /// Approve the DEX to pull `amount` of `token` from `client`
+    pub async fn approve(
+        &self,
+        client: &Arc<SignerClient>,
+        token: Address,
+        amount: U256,
+    ) -> Result<()> {
+        self.send(
+            client,
+            self.token_as(token, client)
+                .approve(self.dex.address(), amount),
+        )
+        .await?;
+        Ok(())
+    }
+
+    /// List the base/quote pair on the DEX
+    pub async fn add_pair(&self, min_order_size: U256, price_precision: U256) -> Result<()> {
+        let call = self.dex.add_trading_pair(
+            self.base_token,
+            self.quote_token,
+            min_order_size,
+            price_precision,
+        );
+        self.send(&self.deployer, call).await?;
+        Ok(())
+    }
+
+    /// Approve the deposit and place a limit order on the base/quote pair, returning its ID
+    pub async fn place_limit_order(
+        &self,
+        client: &Arc<SignerClient>,
+        amount: U256,
+        price: U256,
+        is_buy: bool,
+    ) -> Result<U256> {
+        let receipt = self.place_limit_order_receipt(client, amount, price, is_buy).await?;
+        events::placed_order_id(&receipt)
+            .ok_or_else(|| anyhow!("No OrderPlaced event in {:?}", receipt.transaction_hash).into())
+    }
+
+    /// Like [`Self::place_limit_order`], returning the receipt with any `OrderMatched` logs
+    pub async fn place_limit_order_receipt(
+        &self,
+        client: &Arc<SignerClient>,
+        amount: U256,
+        price: U256,
+        is_buy: bool,
+    ) -> Result<TransactionReceipt> {
+        let (_, _, _, _, price_precision) = self
+            .dex
+            .trading_pairs(self.base_token, self.quote_token)
+            .call()
+            .await?;
+        let (token, deposit) = if is_buy {
+            (self.quote_token, amount * price / price_precision)
+        } else {
+            (self.base_token, amount)
+        };
+        self.approve(client, token, deposit).await?;
+
+        let call = self.dex_as(client).place_limit_order(
+            self.base_token,
+            self.quote_token,
+            amount,
+            price,
+            is_buy,
+        );
+        self.send(client, call).await
+    }
+
+    /// Approve `deposit` of the token paid in (quote for buys, base for sells) and place
+    /// a market order on the base/quote pair
+    pub async fn place_market_order(
+        &self,
+        client: &Arc<SignerClient>,
+        amount: U256,
+        is_buy: bool,
+        deposit: U256,
+    ) -> Result<TransactionReceipt> {
+        let token = if is_buy {
+            self.quote_token
+        } else {
+            self.base_token
+        };
+        self.approve(client, token, deposit).await?;
+
+        let call = self.dex_as(client).place_market_order(
+            self.base_token,
+            self.quote_token,
+            amount,
+            is_buy,
+        );
+        self.send(client, call).await
+    }
+}
+
+async fn deploy_token(
+    deployer: &Arc<SignerClient>,
+    name: &str,
+    symbol: &str,
+    fees: &Fees,
+    tx_args: &TxArgs,
+) -> Result<Address> {
+    let (abi, bytecode) = pipeline::load_artifact("TestToken")?;
+    let args = vec![
+        Token::String(name.to_string()),
+        Token::String(symbol.to_string()),
+        Token::Uint(U256::from(18)),
+        Token::Uint(U256::from(INITIAL_SUPPLY)),
+    ];
+    let (address, _) =
+        pipeline::deploy_contract(deployer.clone(), abi, bytecode, args, fees, tx_args)
+            .await?
+            .ok_or_else(|| anyhow!("{} deployment was not sent", symbol))?;
+    Ok(address)
+}
@@ -0,0 +1,143 @@
+use crate::bindings::ERC20;
+use crate::error::{DexError, Result};
+use anyhow::Context;
+use ethers::{
+    providers::Middleware,
+    types::{Address, U256},
+    utils::format_units,
+};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+use std::sync::Arc;
+
+const TOKEN_REGISTRY_PATH: &str = "config/tokens.json";
+
+/// Symbol and decimals of the chain's native token, which the DEX addresses as 0x0
+const NATIVE_SYMBOL: &str = "MON";
+const NATIVE_DECIMALS: u8 = 18;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TokenInfo {
+    pub address: Address,
+    pub symbol: String,
+    pub name: String,
+    pub decimals: u8,
+}
+
+impl TokenInfo {
+    pub fn native() -> Self {
+        Self {
+            address: Address::zero(),
+            symbol: NATIVE_SYMBOL.to_string(),
+            name: "Monad".to_string(),
+            decimals: NATIVE_DECIMALS,
+        }
+    }
+
+    /// Raw amount in whole-token units, e.g. `1.5 USDC`
+    pub fn format(&self, amount: U256) -> String {
+        match format_units(amount, self.decimals as u32) {
+            Ok(units) => format!("{} {}", trim_decimals(&units), self.symbol),
+            Err(_) => format!("{} (raw) {}", amount, self.symbol),
+        }
+    }
+}
+
+fn trim_decimals(units: &str) -> &str {
+    if units.contains('.') {
+        units.trim_end_matches('0').trim_end_matches('.')
+    } else {
+        units
+    }
+}
+
+/// Local cache of token metadata, filled on first use
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct TokenRegistry {
+    pub tokens: BTreeMap<Address, TokenInfo>,
+}
+
+impl TokenRegistry {
+    pub fn load() -> Result<Self> {
+        if !Path::new(TOKEN_REGISTRY_PATH).exists() {
+            return Ok(Self::default());
+        }
+        let content = fs::read_to_string(TOKEN_REGISTRY_PATH)
+            .with_context(|| format!("Failed to read {}", TOKEN_REGISTRY_PATH))?;
+        Ok(serde_json::from_str(&content)?)
+    }
+
+    pub fn save(&self) -> Result<()> {
+        fs::create_dir_all("config")?;
+        fs::write(TOKEN_REGISTRY_PATH, serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+
+    /// Cached metadata without touching the chain
+    pub fn get(&self, address: Address) -> Option<TokenInfo> {
+        if address.is_zero() {
+            return Some(TokenInfo::native());
+        }
+        self.tokens.get(&address).cloned()
+    }
+
+    /// Cached metadata, fetching and caching it on first use
+    pub async fn resolve<M: Middleware + 'static>(&mut self, client: Arc<M>, address: Address) -> Result<TokenInfo> {
+        if let Some(info) = self.get(address) {
+            return Ok(info);
+        }
+        let info = fetch(client, address).await?;
+        self.tokens.insert(address, info.clone());
+        self.save()?;
+        Ok(info)
+    }
+
+    /// Refetch metadata for `address`, replacing any cached entry
+    pub async fn refresh<M: Middleware + 'static>(&mut self, client: Arc<M>, address: Address) -> Result<TokenInfo> {
+        if address.is_zero() {
+            return Ok(TokenInfo::native());
+        }
+        let info = fetch(client, address).await?;
+        self.tokens.insert(address, info.clone());
+        self.save()?;
+        Ok(info)
+    }
+
+    pub fn remove(&mut self, address: Address) -> Result<TokenInfo> {
+        self.tokens
+            .remove(&address)
+            .ok_or_else(|| DexError::Input(format!("{:?} is not in the token registry", address)))
+    }
+
+    /// Format `amount` of `address` for display, falling back to the raw amount when the
+    /// token's metadata can't be fetched
+    pub async fn display<M: Middleware + 'static>(&mut self, client: Arc<M>, address: Address, amount: U256) -> String {
+        match self.resolve(client, address).await {
+            Ok(info) => info.format(amount),
+            Err(_) => format!("{} tokens", amount),
+        }
+    }
+}
+
+async fn fetch<M: Middleware + 'static>(client: Arc<M>, address: Address) -> Result<TokenInfo> {
+    let token = ERC20::new(address, client);
+    let decimals = token
+        .decimals()
+        .call()
+        .await
+        .with_context(|| format!("{:?} does not look like an ERC-20 token", address))?;
+    // symbol and name are optional in ERC-20
+    let symbol = match token.symbol().call().await {
+        Ok(symbol) => symbol,
+        Err(_) => format!("{:?}", address),
+    };
+    let name = token.name().call().await.unwrap_or_default();
+    Ok(TokenInfo {
+        address,
+        symbol,
+        name,
+        decimals,
+    })
+}
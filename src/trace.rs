@@ -0,0 +1,307 @@
+use crate::bindings::{ERC20_ABI, ORDERBOOKDEX_ABI, WMON_ABI};
+use crate::deployments::DeploymentRegistry;
+use crate::error::{revert_reason, DexError, Result};
+use anyhow::{anyhow, Context};
+use ethers::{
+    abi::Abi,
+    providers::Middleware,
+    types::{
+        Address, CallFrame, GethDebugBuiltInTracerType, GethDebugTracerType, GethDebugTracingOptions, GethTrace,
+        GethTraceFrame, H256,
+    },
+};
+use serde::Serialize;
+use std::fs;
+use tracing::warn;
+
+/// One call on the way from the transaction down to the call that failed
+#[derive(Debug, Clone, Serialize)]
+pub struct FailedCall {
+    /// CALL, DELEGATECALL, STATICCALL or CREATE
+    pub kind: String,
+    pub from: Address,
+    pub to: Option<Address>,
+    /// `Contract.function` when an ABI knows the selector, else the raw selector
+    pub function: String,
+    pub error: Option<String>,
+    pub reason: Option<String>,
+}
+
+/// Where in the Solidity source execution reverted
+#[derive(Debug, Clone, Serialize)]
+pub struct SourceLocation {
+    pub contract: String,
+    pub file: String,
+    pub line: usize,
+    pub function: Option<String>,
+    /// The source line the revert is attributed to
+    pub code: String,
+}
+
+/// What `trace` found out about a failed transaction
+#[derive(Debug, Clone, Serialize)]
+pub struct TraceReport {
+    pub tx_hash: H256,
+    /// From the top-level call down to the one that reverted first
+    pub calls: Vec<FailedCall>,
+    /// Program counter of the REVERT (or other failing opcode) in the last call
+    pub pc: Option<u64>,
+    pub source: Option<SourceLocation>,
+}
+
+/// Trace a mined transaction with `debug_traceTransaction` and follow it down to the
+/// call that reverted first, mapping that revert to a source line when the contract's
+/// artifact (`artifact`, or the one the deployment registry names) is in `out/`.
+pub async fn trace<M: Middleware>(client: &M, tx_hash: H256, artifact: Option<&str>) -> Result<TraceReport> {
+    let options = GethDebugTracingOptions {
+        tracer: Some(GethDebugTracerType::BuiltInTracer(GethDebugBuiltInTracerType::CallTracer)),
+        ..Default::default()
+    };
+    let root = match client.debug_trace_transaction(tx_hash, options).await.map_err(unsupported)? {
+        GethTrace::Known(GethTraceFrame::CallTracer(frame)) => frame,
+        _ => return Err(DexError::Rpc("The node returned no call trace".to_string())),
+    };
+
+    let mut path = vec![&root];
+    while let Some(failed) = path
+        .last()
+        .and_then(|frame| frame.calls.as_ref())
+        .and_then(|calls| calls.iter().rev().find(|call| call.error.is_some()))
+    {
+        path.push(failed);
+    }
+    if root.error.is_none() {
+        return Err(DexError::Input(format!("{:?} didn't fail; nothing to trace", tx_hash)));
+    }
+
+    let registry = DeploymentRegistry::load().unwrap_or_default();
+    let calls: Vec<FailedCall> = path.iter().map(|frame| failed_call(frame, &registry)).collect();
+
+    // The struct logger says which opcode reverted; the call tracer only which call
+    let failing = path.last().expect("path starts at the root");
+    let pc = revert_pc(client, tx_hash, path.len() as u64).await?;
+    let contract = artifact
+        .map(str::to_string)
+        .or_else(|| failing.to.as_ref().and_then(|to| to.as_address()).and_then(|to| contract_name(&registry, *to)));
+    let source = match (pc, contract) {
+        (Some(pc), Some(contract)) => match SourceMap::load(&contract) {
+            Ok(map) => map.and_then(|map| map.locate(pc)),
+            Err(e) => {
+                warn!("No source line for the revert: {:#}", e);
+                None
+            }
+        },
+        _ => None,
+    };
+
+    Ok(TraceReport { tx_hash, calls, pc, source })
+}
+
+fn unsupported(error: impl std::fmt::Display) -> anyhow::Error {
+    DexError::Rpc(format!("debug_traceTransaction failed ({}); the RPC endpoint may not offer the debug namespace", error)).into()
+}
+
+fn failed_call(frame: &CallFrame, registry: &DeploymentRegistry) -> FailedCall {
+    let to = frame.to.as_ref().and_then(|to| to.as_address()).copied();
+    let contract = to.and_then(|to| contract_name(registry, to));
+    let function = function_name(contract.as_deref(), &frame.input)
+        .unwrap_or_else(|| match frame.input.get(..4) {
+            Some(selector) => format!("0x{}", hex::encode(selector)),
+            None => "(no calldata)".to_string(),
+        });
+    FailedCall {
+        kind: frame.typ.clone(),
+        from: frame.from,
+        to,
+        function: match contract {
+            Some(contract) if !function.starts_with("0x") => format!("{}.{}", contract, function),
+            _ => function,
+        },
+        error: frame.error.clone(),
+        reason: frame.output.as_ref().and_then(|output| revert_reason(output)),
+    }
+}
+
+/// Name of the function `input` calls, from the contract's artifact or the ABIs built in
+fn function_name(contract: Option<&str>, input: &[u8]) -> Option<String> {
+    let selector = input.get(..4)?;
+    let find = |abi: &Abi| {
+        abi.functions()
+            .find(|function| function.short_signature() == selector)
+            .map(|function| function.name.clone())
+    };
+    contract
+        .and_then(|contract| artifact_abi(contract).ok())
+        .and_then(|abi| find(&abi))
+        .or_else(|| [&*ORDERBOOKDEX_ABI, &*ERC20_ABI, &*WMON_ABI].into_iter().find_map(find))
+}
+
+/// Contract recorded at `address`, directly or as a proxy's implementation
+fn contract_name(registry: &DeploymentRegistry, address: Address) -> Option<String> {
+    if let Some((_, contract, _)) = registry.find(address) {
+        return Some(contract.to_string());
+    }
+    registry
+        .networks
+        .values()
+        .flat_map(|contracts| contracts.iter())
+        .find(|(_, history)| history.iter().any(|deployment| deployment.implementation == Some(address)))
+        .map(|(contract, _)| contract.clone())
+}
+
+/// The last failing opcode at `depth` (1 for the top-level call), where the first call
+/// to revert stopped
+async fn revert_pc<M: Middleware>(client: &M, tx_hash: H256, depth: u64) -> Result<Option<u64>> {
+    let options = GethDebugTracingOptions {
+        disable_storage: Some(true),
+        disable_stack: Some(true),
+        enable_memory: Some(false),
+        ..Default::default()
+    };
+    let GethTrace::Known(GethTraceFrame::Default(frame)) = client.debug_trace_transaction(tx_hash, options).await.map_err(unsupported)? else {
+        return Ok(None);
+    };
+    Ok(frame
+        .struct_logs
+        .iter()
+        .rev()
+        .find(|log| log.depth == depth && (matches!(log.op.as_str(), "REVERT" | "INVALID") || log.error.is_some()))
+        .map(|log| log.pc))
+}
+
+fn artifact_path(contract: &str) -> String {
+    format!("out/{0}.sol/{0}.json", contract)
+}
+
+fn artifact_abi(contract: &str) -> Result<Abi> {
+    let content = fs::read_to_string(artifact_path(contract))?;
+    let json: serde_json::Value = serde_json::from_str(&content)?;
+    Ok(serde_json::from_value(json["abi"].clone())?)
+}
+
+/// A contract's runtime source map, with what's needed to turn program counters into
+/// source lines
+struct SourceMap {
+    contract: String,
+    /// Instruction index of every byte of the runtime code that starts an instruction
+    instructions: Vec<Option<usize>>,
+    /// (offset, length, file index) per instruction
+    entries: Vec<(usize, usize, i64)>,
+    /// Index of the contract's own source file in the source map
+    file_index: i64,
+    file: String,
+    source: String,
+    /// (offset, length, name) of every function in the file
+    functions: Vec<(usize, usize, String)>,
+}
+
+impl SourceMap {
+    /// The map from the contract's artifact; `None` if it wasn't built with a source map
+    /// and AST (`ast = true` in foundry.toml)
+    fn load(contract: &str) -> Result<Option<Self>> {
+        let path = artifact_path(contract);
+        let content = fs::read_to_string(&path).with_context(|| format!("Failed to read {} (run `monad-deploy build`)", path))?;
+        let json: serde_json::Value = serde_json::from_str(&content)?;
+        let (Some(code), Some(map), Some(ast)) = (
+            json["deployedBytecode"]["object"].as_str(),
+            json["deployedBytecode"]["sourceMap"].as_str(),
+            json.get("ast").filter(|ast| !ast.is_null()),
+        ) else {
+            return Ok(None);
+        };
+        let code = hex::decode(code.trim_start_matches("0x")).with_context(|| format!("Invalid bytecode in {}", path))?;
+        let file = ast["absolutePath"].as_str().ok_or_else(|| anyhow!("{} has no source path in its AST", path))?.to_string();
+        let file_index = ast["src"].as_str().and_then(parse_src).map_or(-1, |(_, _, index)| index);
+        let source = fs::read_to_string(&file).with_context(|| format!("Failed to read {}", file))?;
+
+        let mut functions = Vec::new();
+        collect_functions(ast, &mut functions);
+
+        Ok(Some(Self {
+            contract: contract.to_string(),
+            instructions: instruction_indices(&code),
+            entries: decompress(map),
+            file_index,
+            file,
+            source,
+            functions,
+        }))
+    }
+
+    fn locate(&self, pc: u64) -> Option<SourceLocation> {
+        let instruction = (*self.instructions.get(pc as usize)?)?;
+        let &(offset, length, file_index) = self.entries.get(instruction)?;
+        // Compiler-generated code and code from other files have no line here
+        if file_index != self.file_index || offset > self.source.len() {
+            return None;
+        }
+        let line = self.source[..offset].matches('\n').count() + 1;
+        let code = self.source.lines().nth(line - 1).unwrap_or_default().trim().to_string();
+        let function = self
+            .functions
+            .iter()
+            .filter(|(start, len, _)| *start <= offset && offset + length <= start + len)
+            .min_by_key(|(_, len, _)| *len)
+            .map(|(_, _, name)| name.clone());
+        Some(SourceLocation { contract: self.contract.clone(), file: self.file.clone(), line, function, code })
+    }
+}
+
+/// For every byte offset in `code`, the index of the instruction starting there
+fn instruction_indices(code: &[u8]) -> Vec<Option<usize>> {
+    let mut indices = vec![None; code.len()];
+    let (mut pc, mut index) = (0, 0);
+    while pc < code.len() {
+        indices[pc] = Some(index);
+        let op = code[pc];
+        // PUSH1..PUSH32 carry their operand inline
+        pc += if (0x60..=0x7f).contains(&op) { (op - 0x5f) as usize + 1 } else { 1 };
+        index += 1;
+    }
+    indices
+}
+
+/// Expand solc's compressed source map, where an empty field repeats the one before
+fn decompress(map: &str) -> Vec<(usize, usize, i64)> {
+    let mut entries = Vec::new();
+    let (mut offset, mut length, mut file) = (0usize, 0usize, -1i64);
+    for entry in map.split(';') {
+        let mut fields = entry.split(':');
+        if let Some(value) = fields.next().and_then(|field| field.parse().ok()) {
+            offset = value;
+        }
+        if let Some(value) = fields.next().and_then(|field| field.parse().ok()) {
+            length = value;
+        }
+        if let Some(value) = fields.next().and_then(|field| field.parse().ok()) {
+            file = value;
+        }
+        entries.push((offset, length, file));
+    }
+    entries
+}
+
+/// `offset:length:file` of an AST node
+fn parse_src(src: &str) -> Option<(usize, usize, i64)> {
+    let mut parts = src.split(':');
+    Some((parts.next()?.parse().ok()?, parts.next()?.parse().ok()?, parts.next()?.parse().ok()?))
+}
+
+fn collect_functions(node: &serde_json::Value, functions: &mut Vec<(usize, usize, String)>) {
+    match node {
+        serde_json::Value::Object(fields) => {
+            if matches!(fields.get("nodeType").and_then(|kind| kind.as_str()), Some("FunctionDefinition" | "ModifierDefinition")) {
+                let name = match fields.get("name").and_then(|name| name.as_str()) {
+                    Some("") | None => fields.get("kind").and_then(|kind| kind.as_str()).unwrap_or("function").to_string(),
+                    Some(name) => name.to_string(),
+                };
+                if let Some((offset, length, _)) = fields.get("src").and_then(|src| src.as_str()).and_then(parse_src) {
+                    functions.push((offset, length, name));
+                }
+            }
+            fields.values().for_each(|value| collect_functions(value, functions));
+        }
+        serde_json::Value::Array(values) => values.iter().for_each(|value| collect_functions(value, functions)),
+        _ => {}
+    }
+}
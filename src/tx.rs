@@ -0,0 +1,841 @@
+use crate::aliases::AddressBook;
+use crate::bindings::{ERC20_ABI, ORDERBOOKDEX_ABI};
+use crate::deployments::DeploymentRegistry;
+use crate::error::{DexError, Result};
+use crate::fees::{FeeArgs, Fees};
+use crate::journal::{self, Resumed};
+use crate::offline;
+use crate::price::PriceSource;
+use crate::replace;
+use crate::rpc;
+use clap::Args;
+use ethers::{
+    abi::{Detokenize, Function, Token},
+    contract::ContractCall,
+    providers::{Middleware, PendingTransaction, Provider},
+    types::{transaction::eip2718::TypedTransaction, BlockNumber, NameOrAddress, TransactionReceipt, H256, U256},
+    utils::{format_ether, format_units, hex, keccak256},
+};
+use std::fmt::Display;
+use std::io::{self, BufRead, IsTerminal, Write};
+use std::time::Duration;
+use tokio::time::{sleep, Instant};
+use tracing::{field, info, info_span, warn, Instrument, Span};
+
+/// How often pending transactions are polled for a receipt while auto-replacing
+const RECEIPT_POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+/// Upper bound on automatic fee bumps for a single transaction
+const MAX_REPLACEMENTS: usize = 10;
+
+/// Options shared by every command that sends a transaction
+#[derive(Debug, Clone, Args)]
+pub struct TxArgs {
+    #[command(flatten)]
+    pub fees: FeeArgs,
+
+    /// USD price of the native token for cost reports: a fixed number, a JSON URL
+    /// with a pointer fragment (e.g. https://host/price#/monad/usd), or feed:<SYMBOL>
+    #[arg(long, global = true)]
+    pub usd_price_source: Option<PriceSource>,
+
+    /// Rebroadcast with higher fees if still pending after this long (e.g. 30s, 2m)
+    #[arg(long, value_parser = humantime::parse_duration, global = true)]
+    pub replace_after: Option<Duration>,
+
+    /// Percentage to raise fees by on each replacement
+    #[arg(long, default_value_t = 15, global = true)]
+    pub bump_percent: u64,
+
+    /// Number of confirmations to wait for before a write counts as successful
+    #[arg(long, default_value_t = 1, global = true)]
+    pub confirmations: usize,
+
+    /// Submit transactions through the private relay instead of the public mempool,
+    /// so large orders can't be sandwiched while pending
+    #[arg(long, global = true, requires = "private_relay_url")]
+    pub private_tx: bool,
+
+    /// JSON-RPC endpoint of the private relay; it must accept eth_sendRawTransaction
+    #[arg(long, global = true)]
+    pub private_relay_url: Option<String>,
+
+    /// Broadcast publicly if a privately submitted transaction isn't mined within this long
+    #[arg(long, value_parser = humantime::parse_duration, default_value = "30s", global = true)]
+    pub private_fallback_after: Duration,
+
+    /// Name for this write: running the command again with the same key waits for or
+    /// skips what was already sent under it instead of sending it twice
+    #[arg(long, global = true)]
+    pub idempotency_key: Option<String>,
+
+    /// Send again a transaction that reverted under its idempotency key, instead of
+    /// refusing
+    #[arg(long, global = true)]
+    pub resend_reverted: bool,
+
+    /// Send without first showing a preview of each transaction and asking to confirm it
+    #[arg(short, long, global = true)]
+    pub yes: bool,
+
+    /// Exceed a spending limit set with `limits set`, after typing a confirmation
+    #[arg(long, global = true)]
+    pub override_limit: bool,
+
+    /// Append each transaction, filled in but unsigned, to this file instead of sending
+    /// it; sign it offline with `monad-dex offline sign` (see --cold-address)
+    #[arg(long, global = true)]
+    pub prepare: Option<String>,
+
+    /// Only estimate gas and cost, never broadcast
+    #[arg(skip)]
+    pub estimate_only: bool,
+}
+
+impl Default for TxArgs {
+    fn default() -> Self {
+        Self {
+            fees: FeeArgs::default(),
+            usd_price_source: None,
+            replace_after: None,
+            bump_percent: 15,
+            confirmations: 1,
+            private_tx: false,
+            private_relay_url: None,
+            private_fallback_after: Duration::from_secs(30),
+            idempotency_key: None,
+            resend_reverted: false,
+            yes: false,
+            override_limit: false,
+            prepare: None,
+            estimate_only: false,
+        }
+    }
+}
+
+impl TxArgs {
+    /// Copy of these options that estimates instead of sending
+    pub fn estimate_only(&self) -> Self {
+        Self {
+            estimate_only: true,
+            ..self.clone()
+        }
+    }
+
+    /// Copy of these options that sends without asking, for bots and loops nobody watches
+    pub fn unattended(&self) -> Self {
+        Self {
+            yes: true,
+            ..self.clone()
+        }
+    }
+
+    /// Copy of these options for one `step` of a command that sends several transactions.
+    /// The step is journaled under `<idempotency key>/<step>`, so a rerun resumes each
+    /// step on its own instead of mistaking one step's transaction for another's.
+    pub fn step(&self, step: impl Display) -> Self {
+        Self {
+            idempotency_key: self.idempotency_key.as_ref().map(|key| format!("{}/{}", key, step)),
+            ..self.clone()
+        }
+    }
+
+    /// Whether transactions need confirming before they are sent
+    fn confirms(&self) -> bool {
+        !self.yes && !self.estimate_only && self.prepare.is_none()
+    }
+
+    async fn usd_price(&self) -> Option<f64> {
+        let source = self.usd_price_source.as_ref()?;
+        match source.fetch().await {
+            Ok(price) => Some(price),
+            Err(e) => {
+                warn!("Failed to fetch USD price: {}", e);
+                None
+            }
+        }
+    }
+}
+
+/// Gas and cost figures for a mined transaction
+#[derive(Debug, Clone)]
+pub struct GasReport {
+    pub estimated_gas: U256,
+    pub gas_used: Option<U256>,
+    pub effective_gas_price: Option<U256>,
+}
+
+impl GasReport {
+    pub fn new(estimated_gas: U256, receipt: &TransactionReceipt) -> Self {
+        Self {
+            estimated_gas,
+            gas_used: receipt.gas_used,
+            effective_gas_price: receipt.effective_gas_price,
+        }
+    }
+
+    /// Total fee paid in wei
+    pub fn total_cost(&self) -> Option<U256> {
+        Some(self.gas_used? * self.effective_gas_price?)
+    }
+
+    /// Print the report to stderr, leaving stdout to the command's own output
+    pub fn print(&self, usd_price: Option<f64>) {
+        eprintln!("Gas Report:");
+        eprintln!("  Estimated gas:       {}", self.estimated_gas);
+        match self.gas_used {
+            Some(used) if !self.estimated_gas.is_zero() => eprintln!(
+                "  Gas used:            {} ({:.1}% of estimate)",
+                used,
+                used.as_u128() as f64 * 100.0 / self.estimated_gas.as_u128() as f64
+            ),
+            Some(used) => eprintln!("  Gas used:            {}", used),
+            None => eprintln!("  Gas used:            unknown"),
+        }
+        match self.effective_gas_price {
+            Some(price) => eprintln!("  Effective gas price: {} gwei", format_gwei(price)),
+            None => eprintln!("  Effective gas price: unknown"),
+        }
+        match self.total_cost() {
+            Some(cost) => eprintln!("  Total cost:          {}", format_cost(cost, usd_price)),
+            None => eprintln!("  Total cost:          unknown"),
+        }
+    }
+}
+
+/// Apply fees to a contract call, then either estimate it or send it and wait for the receipt.
+///
+/// Returns `None` when only estimating or when the transaction was dropped from the mempool.
+pub async fn send<M, D>(
+    client: &M,
+    mut call: ContractCall<M, D>,
+    fees: &Fees,
+    args: &TxArgs,
+) -> Result<Option<TransactionReceipt>>
+where
+    M: Middleware + 'static,
+    D: Detokenize,
+{
+    fees.apply(&mut call.tx);
+    if !args.estimate_only {
+        // Checked before estimating: a call already made may no longer succeed
+        if let Some(receipt) = resumed(client, &call.tx, args.idempotency_key.as_deref(), args).await? {
+            return Ok(Some(receipt));
+        }
+    }
+    let estimated_gas = call.estimate_gas().await.map_err(|e| DexError::from(&e))?;
+
+    if args.estimate_only {
+        print_estimate(estimated_gas, fees, args).await;
+        return Ok(None);
+    }
+
+    call.tx.set_gas(estimated_gas);
+    let receipt = journaled(client, call.tx, args, &call.function.name).await?;
+
+    if let Some(receipt) = &receipt {
+        report(estimated_gas, receipt, args).await;
+    }
+
+    Ok(receipt)
+}
+
+/// Send several contract calls back to back with consecutive nonces, then wait for
+/// all of them. A call that fails to estimate or broadcast is reported in its slot
+/// without holding up the rest.
+pub async fn send_batch<M, D>(
+    client: &M,
+    calls: Vec<ContractCall<M, D>>,
+    fees: &Fees,
+    args: &TxArgs,
+) -> Result<Vec<Result<Option<TransactionReceipt>>>>
+where
+    M: Middleware + 'static,
+    D: Detokenize,
+{
+    // Each call is journaled under its own key
+    let calls = calls
+        .into_iter()
+        .enumerate()
+        .map(|(index, call)| (args.step(index).idempotency_key, call))
+        .collect();
+    send_keyed_batch(client, calls, fees, args).await
+}
+
+/// `send_batch` with the idempotency key of every call given, so a resumed batch can
+/// skip or wait for the calls that already went out
+pub async fn send_keyed_batch<M, D>(
+    client: &M,
+    calls: Vec<(Option<String>, ContractCall<M, D>)>,
+    fees: &Fees,
+    args: &TxArgs,
+) -> Result<Vec<Result<Option<TransactionReceipt>>>>
+where
+    M: Middleware + 'static,
+    D: Detokenize,
+{
+    if !args.estimate_only && args.prepare.is_none() {
+        rpc::ensure_writable()?;
+    }
+    // The batch is confirmed as a whole rather than call by call
+    if args.confirms() && !calls.is_empty() {
+        let mut previews = Vec::with_capacity(calls.len());
+        for (_, call) in &calls {
+            let mut tx = call.tx.clone();
+            fees.apply(&mut tx);
+            // A call that won't estimate is previewed without a cost and fails when sent
+            if let Ok(gas) = call.estimate_gas().await {
+                tx.set_gas(gas);
+            }
+            previews.push(tx);
+        }
+        confirm(client, &previews, args).await?;
+    }
+    let args = &args.unattended();
+
+    // The private relay gets one signed transaction at a time, and prepared transactions
+    // are written out one by one
+    if (args.private_tx || args.prepare.is_some()) && !args.estimate_only {
+        let mut receipts = Vec::with_capacity(calls.len());
+        for (key, call) in calls {
+            let args = TxArgs { idempotency_key: key, ..args.clone() };
+            receipts.push(send(client, call, fees, &args).await);
+        }
+        return Ok(receipts);
+    }
+
+    let sender = client
+        .default_sender()
+        .ok_or_else(|| DexError::Config("Batch sending requires a signing client".to_string()))?;
+    let mut nonce = client
+        .get_transaction_count(sender, Some(BlockNumber::Pending.into()))
+        .await
+        .map_err(|e| DexError::middleware(&e))?;
+    let chain_id = client.get_chainid().await.map_err(|e| DexError::middleware(&e))?.as_u64();
+
+    let mut sent: Vec<Result<BatchSlot>> = Vec::with_capacity(calls.len());
+    for (key, mut call) in calls {
+        fees.apply(&mut call.tx);
+        let result: Result<BatchSlot> = async {
+            if !args.estimate_only {
+                if let Some(receipt) = resumed(client, &call.tx, key.as_deref(), args).await? {
+                    return Ok(BatchSlot::Resumed(Box::new(receipt)));
+                }
+            }
+            let estimated_gas = call.estimate_gas().await?;
+            if args.estimate_only {
+                print_estimate(estimated_gas, fees, args).await;
+                return Ok(BatchSlot::Estimated);
+            }
+
+            call.tx.set_gas(estimated_gas);
+            call.tx.set_nonce(nonce);
+            call.tx.set_from(sender);
+            let id = journal::begin(chain_id, key.as_deref(), &call.function.name, &call.tx)?;
+            let pending = match client.send_transaction(call.tx.clone(), None).await {
+                Ok(pending) => pending,
+                Err(e) => {
+                    let e = DexError::middleware(&e);
+                    journal::failed(id, &e);
+                    return Err(e);
+                }
+            };
+            info!("Transaction sent: {:?} (nonce {})", *pending, nonce);
+            journal::sent(id, *pending);
+            Ok(BatchSlot::Sent(id, *pending))
+        }
+        .await;
+
+        if matches!(result, Ok(BatchSlot::Sent(..))) {
+            nonce += U256::one();
+        }
+        sent.push(result);
+    }
+
+    let mut receipts = Vec::with_capacity(sent.len());
+    for result in sent {
+        receipts.push(match result {
+            Ok(BatchSlot::Sent(id, hash)) => {
+                let receipt = PendingTransaction::new(hash, client.provider())
+                    .confirmations(args.confirmations)
+                    .await
+                    .map_err(Into::into);
+                journal::finish(id, &receipt);
+                receipt
+            }
+            Ok(BatchSlot::Resumed(receipt)) => Ok(Some(*receipt)),
+            Ok(BatchSlot::Estimated) => Ok(None),
+            Err(e) => Err(e),
+        });
+    }
+
+    Ok(receipts)
+}
+
+/// What became of one call in `send_batch` before waiting for receipts
+enum BatchSlot {
+    /// Sent as the journal entry with this ID
+    Sent(u64, H256),
+    /// Already sent under its idempotency key
+    Resumed(Box<TransactionReceipt>),
+    Estimated,
+}
+
+/// The receipt of what was already sent under `key`, if anything was
+async fn resumed<M>(
+    client: &M,
+    tx: &TypedTransaction,
+    key: Option<&str>,
+    args: &TxArgs,
+) -> Result<Option<TransactionReceipt>>
+where
+    M: Middleware + 'static,
+{
+    let Some(key) = key else {
+        return Ok(None);
+    };
+    let chain_id = client.get_chainid().await.map_err(|e| DexError::middleware(&e))?.as_u64();
+    match journal::resume(client, chain_id, key, tx, args.confirmations, args.resend_reverted).await? {
+        Resumed::Sent(receipt) => Ok(Some(*receipt)),
+        Resumed::Send => Ok(None),
+    }
+}
+
+/// Name of the function `tx` calls on the DEX or a token, its selector otherwise, or
+/// `transfer` for a plain value transfer
+fn intent_of(tx: &TypedTransaction) -> String {
+    let Some(selector) = tx.data().and_then(|data| data.get(..4)) else {
+        return "transfer".to_string();
+    };
+    known_function(selector).map_or_else(|| format!("0x{}", hex::encode(selector)), |function| function.name.clone())
+}
+
+/// The DEX or token function with this selector
+fn known_function(selector: &[u8]) -> Option<&'static Function> {
+    ORDERBOOKDEX_ABI
+        .functions()
+        .chain(ERC20_ABI.functions())
+        .find(|function| function.short_signature() == selector)
+}
+
+/// Show where `txs` go, what they call and what they may cost, then ask on the terminal
+/// whether to send them. Declining, or having no terminal to ask on, is an error.
+async fn confirm<M>(client: &M, txs: &[TypedTransaction], args: &TxArgs) -> Result<()>
+where
+    M: Middleware + 'static,
+{
+    let chain_id = client.get_chainid().await.map_err(|e| DexError::middleware(&e))?;
+    let usd_price = args.usd_price().await;
+    let book = AddressBook::load().unwrap_or_default();
+    let deployments = DeploymentRegistry::load().unwrap_or_default();
+
+    eprintln!("Transaction Preview");
+    eprintln!("================================");
+    eprintln!("  Network:             {} (chain {})", rpc::network_name(), chain_id);
+    if let Some(from) = txs.first().and_then(|tx| tx.from()) {
+        eprintln!("  From:                {:?}", from);
+    }
+    let mut total_cost = Some(U256::zero());
+    for (index, tx) in txs.iter().enumerate() {
+        if txs.len() > 1 {
+            eprintln!("Transaction {} of {}:", index + 1, txs.len());
+        }
+        let contract = match tx.to() {
+            Some(NameOrAddress::Address(address)) => {
+                let name = book
+                    .name_of(*address)
+                    .or_else(|| deployments.find(*address).map(|(_, contract, _)| contract));
+                match name {
+                    Some(name) => format!("{:?} ({})", address, name),
+                    None => format!("{:?}", address),
+                }
+            }
+            Some(NameOrAddress::Name(name)) => name.clone(),
+            None => "new contract".to_string(),
+        };
+        eprintln!("  Contract:            {}", contract);
+        eprintln!("  Call:                {}", describe_call(tx));
+        eprintln!("  Value:               {} MON", format_ether(tx.value().copied().unwrap_or_default()));
+        match (tx.gas(), tx.gas_price()) {
+            (Some(gas), Some(price)) => {
+                eprintln!("  Estimated gas:       {} at up to {} gwei", gas, format_gwei(price));
+                eprintln!("  Max cost:            {}", format_cost(gas * price, usd_price));
+                total_cost = total_cost.map(|total| total + gas * price);
+            }
+            _ => {
+                eprintln!("  Max cost:            unknown (gas estimate failed)");
+                total_cost = None;
+            }
+        }
+    }
+    if txs.len() > 1 {
+        match total_cost {
+            Some(total) => eprintln!("Total max cost:        {}", format_cost(total, usd_price)),
+            None => eprintln!("Total max cost:        unknown"),
+        }
+    }
+
+    ask(&format!("Send {}?", if txs.len() == 1 { "it" } else { "them" }))
+}
+
+/// Ask `question` on the terminal, with the prompt on stderr. Declining, or having no
+/// terminal to ask on, is an error.
+pub fn ask(question: &str) -> Result<()> {
+    if !io::stdin().is_terminal() {
+        return Err(DexError::Input("No terminal to confirm the transaction on; pass --yes to send without asking".to_string()));
+    }
+    eprint!("{} [y/N] ", question);
+    io::stderr().flush()?;
+    let mut answer = String::new();
+    io::stdin().lock().read_line(&mut answer)?;
+    if !matches!(answer.trim().to_lowercase().as_str(), "y" | "yes") {
+        return Err(DexError::Input("Aborted: transaction not confirmed".to_string()));
+    }
+    Ok(())
+}
+
+/// `name(param: value, ...)` for a call to the DEX or a token, the raw calldata otherwise
+fn describe_call(tx: &TypedTransaction) -> String {
+    let Some(data) = tx.data().filter(|data| !data.is_empty()) else {
+        return "none (plain transfer)".to_string();
+    };
+    if tx.to().is_none() {
+        return format!("deployment ({} bytes of init code)", data.len());
+    }
+    let decoded = data.get(..4).and_then(known_function).and_then(|function| {
+        let tokens = function.decode_input(&data[4..]).ok()?;
+        Some((function, tokens))
+    });
+    match decoded {
+        Some((function, tokens)) => {
+            let args: Vec<String> = function
+                .inputs
+                .iter()
+                .zip(&tokens)
+                .map(|(param, token)| format!("{}: {}", param.name, format_token(token)))
+                .collect();
+            format!("{}({})", function.name, args.join(", "))
+        }
+        None => format!("0x{}", hex::encode(data)),
+    }
+}
+
+fn format_token(token: &Token) -> String {
+    let list = |tokens: &[Token]| tokens.iter().map(format_token).collect::<Vec<_>>().join(", ");
+    match token {
+        Token::Address(address) => format!("{:?}", address),
+        Token::Uint(value) | Token::Int(value) => value.to_string(),
+        Token::Bool(value) => value.to_string(),
+        Token::String(value) => format!("{:?}", value),
+        Token::Bytes(bytes) | Token::FixedBytes(bytes) => format!("0x{}", hex::encode(bytes)),
+        Token::Array(tokens) | Token::FixedArray(tokens) => format!("[{}]", list(tokens)),
+        Token::Tuple(tokens) => format!("({})", list(tokens)),
+    }
+}
+
+/// Send a transaction and wait for it to be mined, rebroadcasting it with bumped
+/// fees at the same nonce whenever it stays pending longer than `--replace-after`.
+///
+/// The transaction is recorded in the transaction journal; with `--idempotency-key`,
+/// one already sent under the key is waited for instead of sent again.
+pub async fn broadcast<M>(
+    client: &M,
+    tx: TypedTransaction,
+    args: &TxArgs,
+) -> Result<Option<TransactionReceipt>>
+where
+    M: Middleware + 'static,
+{
+    if let Some(receipt) = resumed(client, &tx, args.idempotency_key.as_deref(), args).await? {
+        return Ok(Some(receipt));
+    }
+    let intent = intent_of(&tx);
+    journaled(client, tx, args, &intent).await
+}
+
+/// Record `tx` in the transaction journal, then broadcast it.
+///
+/// Runs in a `transaction` span whose `prepare`, `broadcast` and `mined` children time
+/// each phase; signing happens within `broadcast` (or its own `sign` span for private
+/// submission).
+async fn journaled<M>(
+    client: &M,
+    mut tx: TypedTransaction,
+    args: &TxArgs,
+    intent: &str,
+) -> Result<Option<TransactionReceipt>>
+where
+    M: Middleware + 'static,
+{
+    if let Some(path) = &args.prepare {
+        offline::prepare(client, tx, intent, path).await?;
+        return Ok(None);
+    }
+    let span = info_span!(
+        "transaction",
+        intent,
+        journal_id = field::Empty,
+        nonce = field::Empty,
+        tx_hash = field::Empty,
+        block = field::Empty
+    );
+    async move {
+        rpc::ensure_writable()?;
+        // Pin the nonce up front: the journal records it, and every rebroadcast replaces
+        // the same transaction
+        if let (None, Some(sender)) = (tx.from(), client.default_sender()) {
+            tx.set_from(sender);
+        }
+        client
+            .fill_transaction(&mut tx, None)
+            .instrument(info_span!("prepare"))
+            .await
+            .map_err(|e| DexError::middleware(&e))?;
+        if args.confirms() {
+            confirm(client, std::slice::from_ref(&tx), args).await?;
+        }
+        let chain_id = client.get_chainid().await.map_err(|e| DexError::middleware(&e))?.as_u64();
+        let id = journal::begin(chain_id, args.idempotency_key.as_deref(), intent, &tx)?;
+        Span::current().record("journal_id", id);
+        if let Some(nonce) = tx.nonce() {
+            Span::current().record("nonce", nonce.as_u64());
+        }
+
+        let result = broadcast_filled(client, tx, args, id).await;
+        journal::finish(id, &result);
+        if let Ok(Some(receipt)) = &result {
+            Span::current().record("block", receipt.block_number.map_or(0, |block| block.as_u64()));
+        }
+        result
+    }
+    .instrument(span)
+    .await
+}
+
+async fn broadcast_filled<M>(
+    client: &M,
+    mut tx: TypedTransaction,
+    args: &TxArgs,
+    journal_id: u64,
+) -> Result<Option<TransactionReceipt>>
+where
+    M: Middleware + 'static,
+{
+    if args.private_tx {
+        let relay_url = args
+            .private_relay_url
+            .as_deref()
+            .ok_or_else(|| DexError::Input("--private-tx needs --private-relay-url".to_string()))?;
+        return broadcast_private(client, tx, args, relay_url, journal_id).await;
+    }
+
+    let Some(replace_after) = args.replace_after else {
+        let pending = client
+            .send_transaction(tx, None)
+            .instrument(info_span!("broadcast"))
+            .await
+            .map_err(|e| DexError::middleware(&e))?;
+        journal::sent(journal_id, *pending);
+        Span::current().record("tx_hash", field::debug(*pending));
+        return Ok(pending.confirmations(args.confirmations).instrument(info_span!("mined")).await?);
+    };
+
+    let mut hashes = Vec::new();
+    loop {
+        let attempt = hashes.len();
+        match client.send_transaction(tx.clone(), None).instrument(info_span!("broadcast", attempt)).await {
+            Ok(pending) => {
+                info!("Transaction sent: {:?}", *pending);
+                journal::sent(journal_id, *pending);
+                Span::current().record("tx_hash", field::debug(*pending));
+                hashes.push(*pending);
+            }
+            // An earlier broadcast may have been mined while we were bumping
+            Err(e) if !hashes.is_empty() => {
+                warn!("Replacement rejected: {}", e);
+            }
+            Err(e) => return Err(DexError::middleware(&e)),
+        }
+
+        let deadline = Instant::now() + replace_after;
+        let mined = async {
+            while Instant::now() < deadline {
+                for hash in &hashes {
+                    if let Some(receipt) = client.get_transaction_receipt(*hash).await.map_err(|e| DexError::middleware(&e))? {
+                        wait_for_confirmations(client, &receipt, args.confirmations).await?;
+                        return Ok::<_, DexError>(Some(receipt));
+                    }
+                }
+                sleep(RECEIPT_POLL_INTERVAL).await;
+            }
+            Ok(None)
+        }
+        .instrument(info_span!("mined", attempt))
+        .await?;
+        if let Some(receipt) = mined {
+            return Ok(Some(receipt));
+        }
+
+        if hashes.len() > MAX_REPLACEMENTS {
+            return Err(DexError::Rpc(format!(
+                "Transaction still pending after {} replacements: {:?}",
+                MAX_REPLACEMENTS,
+                hashes
+            )));
+        }
+
+        warn!(
+            "Transaction pending for {:?}, bumping fees by {}%",
+            replace_after, args.bump_percent
+        );
+        replace::bump_fees(&mut tx, args.bump_percent);
+    }
+}
+
+/// Sign `tx` locally and hand it to the private relay, keeping it out of the public
+/// mempool. If it isn't mined within `--private-fallback-after`, the same signed
+/// transaction is broadcast publicly; both copies share a nonce, so it executes once.
+async fn broadcast_private<M>(
+    client: &M,
+    mut tx: TypedTransaction,
+    args: &TxArgs,
+    relay_url: &str,
+    journal_id: u64,
+) -> Result<Option<TransactionReceipt>>
+where
+    M: Middleware + 'static,
+{
+    let sender = client
+        .default_sender()
+        .ok_or_else(|| DexError::Config("Private submission requires a signing client".to_string()))?;
+    client.fill_transaction(&mut tx, None).await.map_err(|e| DexError::middleware(&e))?;
+    let signature = client
+        .sign_transaction(&tx, sender)
+        .instrument(info_span!("sign"))
+        .await
+        .map_err(|e| DexError::middleware(&e))?;
+    let raw = tx.rlp_signed(&signature);
+    let hash = H256::from(keccak256(&raw));
+    // Known before anything goes out, so a crash mid-submission still leaves the hash
+    journal::sent(journal_id, hash);
+    Span::current().record("tx_hash", field::debug(hash));
+
+    let relay = Provider::new(rpc::http(relay_url).map_err(|e| DexError::Input(format!("Invalid private relay URL '{}': {}", relay_url, e)))?);
+    let relayed = match relay.send_raw_transaction(raw.clone()).instrument(info_span!("broadcast", relay = true)).await {
+        Ok(_) => {
+            info!("Transaction sent privately: {:?}", hash);
+            true
+        }
+        Err(e) => {
+            warn!("Private relay rejected the transaction, sending publicly: {}", e);
+            false
+        }
+    };
+
+    if relayed {
+        let deadline = Instant::now() + args.private_fallback_after;
+        let mined = async {
+            while Instant::now() < deadline {
+                if let Some(receipt) = client.get_transaction_receipt(hash).await.map_err(|e| DexError::middleware(&e))? {
+                    wait_for_confirmations(client, &receipt, args.confirmations).await?;
+                    return Ok::<_, DexError>(Some(receipt));
+                }
+                sleep(RECEIPT_POLL_INTERVAL).await;
+            }
+            Ok(None)
+        }
+        .instrument(info_span!("mined", relay = true))
+        .await?;
+        if let Some(receipt) = mined {
+            return Ok(Some(receipt));
+        }
+        warn!(
+            "Transaction not mined privately within {:?}, falling back to the public mempool",
+            args.private_fallback_after
+        );
+    }
+
+    match client.send_raw_transaction(raw).instrument(info_span!("broadcast", relay = false)).await {
+        Ok(pending) => {
+            info!("Transaction sent: {:?}", *pending);
+            Ok(pending.confirmations(args.confirmations).instrument(info_span!("mined", relay = false)).await?)
+        }
+        // The relay may have landed it since the last poll
+        Err(e) => match client.get_transaction_receipt(hash).await.map_err(|e| DexError::middleware(&e))? {
+            Some(receipt) => {
+                wait_for_confirmations(client, &receipt, args.confirmations).await?;
+                Ok(Some(receipt))
+            }
+            None => Err(DexError::middleware(&e)),
+        },
+    }
+}
+
+/// Fail with [`DexError::Reverted`], including the decoded reason, if `receipt` reverted
+pub async fn ensure_success<M: Middleware>(client: &M, receipt: &TransactionReceipt) -> Result<()> {
+    if receipt.status.is_some_and(|status| status.as_u64() != 1) {
+        return Err(DexError::reverted(client, receipt).await);
+    }
+    Ok(())
+}
+
+/// Number of blocks including and built on top of the receipt's block
+pub async fn confirmations<M>(client: &M, receipt: &TransactionReceipt) -> Result<u64>
+where
+    M: Middleware + 'static,
+{
+    let Some(mined_in) = receipt.block_number else {
+        return Ok(0);
+    };
+    let latest = client.get_block_number().await.map_err(|e| DexError::middleware(&e))?;
+    Ok(latest.saturating_sub(mined_in).as_u64() + 1)
+}
+
+/// Poll until a mined transaction has at least `required` confirmations
+pub async fn wait_for_confirmations<M>(
+    client: &M,
+    receipt: &TransactionReceipt,
+    required: usize,
+) -> Result<()>
+where
+    M: Middleware + 'static,
+{
+    loop {
+        let confirmed = confirmations(client, receipt).await?;
+        if confirmed >= required as u64 {
+            return Ok(());
+        }
+        info!("Waiting for confirmations: {}/{}", confirmed, required);
+        sleep(RECEIPT_POLL_INTERVAL).await;
+    }
+}
+
+/// Print the cost breakdown for a mined transaction to stderr
+pub async fn report(estimated_gas: U256, receipt: &TransactionReceipt, args: &TxArgs) {
+    GasReport::new(estimated_gas, receipt).print(args.usd_price().await);
+}
+
+/// Print the estimated gas and the worst-case cost at the configured fees
+pub async fn print_estimate(estimated_gas: U256, fees: &Fees, args: &TxArgs) {
+    println!("Gas Estimate:");
+    println!("  Estimated gas:       {}", estimated_gas);
+    if let Some(price) = fees.max_gas_price() {
+        println!("  Max gas price:       {} gwei", format_gwei(price));
+        println!(
+            "  Max cost:            {}",
+            format_cost(estimated_gas * price, args.usd_price().await)
+        );
+    }
+}
+
+fn format_gwei(wei: U256) -> String {
+    format_units(wei, "gwei").unwrap_or_else(|_| format!("{} wei", wei))
+}
+
+fn format_cost(wei: U256, usd_price: Option<f64>) -> String {
+    let native = format_ether(wei);
+    match (usd_price, native.parse::<f64>()) {
+        (Some(price), Ok(amount)) => format!("{} MON (~${:.4})", native, amount * price),
+        _ => format!("{} MON", native),
+    }
+}
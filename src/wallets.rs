@@ -0,0 +1,209 @@
+use crate::error::{DexError, Result};
+use anyhow::{anyhow, Context};
+use async_trait::async_trait;
+use ethers::{
+    signers::{LocalWallet, Signer, WalletError},
+    types::{
+        transaction::{eip2718::TypedTransaction, eip712::Eip712},
+        Address, Signature, H256,
+    },
+};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::env;
+use std::fs;
+use std::io::{self, BufRead, IsTerminal, Write};
+use std::path::Path;
+use thiserror::Error;
+
+const WALLET_INDEX_PATH: &str = "config/wallets.json";
+const KEYSTORE_DIR: &str = "config/keystores";
+
+/// Keystore password used instead of prompting, for bots and scripts
+pub const PASSWORD_VAR: &str = "MONAD_WALLET_PASSWORD";
+
+/// A stored key: its address and the encrypted keystore file holding it
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WalletEntry {
+    pub address: Address,
+    pub keystore: String,
+}
+
+/// Labeled signing keys, each encrypted in a standard JSON keystore under `config/keystores/`
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct WalletStore {
+    pub wallets: BTreeMap<String, WalletEntry>,
+}
+
+impl WalletStore {
+    pub fn load() -> Result<Self> {
+        if !Path::new(WALLET_INDEX_PATH).exists() {
+            return Ok(Self::default());
+        }
+        let content = fs::read_to_string(WALLET_INDEX_PATH)
+            .with_context(|| format!("Failed to read {}", WALLET_INDEX_PATH))?;
+        Ok(serde_json::from_str(&content)?)
+    }
+
+    pub fn save(&self) -> Result<()> {
+        fs::create_dir_all("config")?;
+        fs::write(WALLET_INDEX_PATH, serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+
+    /// Encrypt `private_key` with `password` and store it under `label` (case-insensitive)
+    pub fn add(&mut self, label: &str, private_key: &str, password: &str) -> Result<Address> {
+        let label = label.to_lowercase();
+        if label.is_empty() || !label.chars().all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-') {
+            return Err(DexError::Input(format!("Wallet label '{}' may only contain letters, digits, '_' and '-'", label)));
+        }
+        if self.wallets.contains_key(&label) {
+            return Err(DexError::Input(format!("A wallet labeled '{}' already exists; remove it first", label)));
+        }
+        let wallet = private_key
+            .parse::<LocalWallet>()
+            .map_err(|e| DexError::Input(format!("Failed to parse private key: {}", e)))?;
+        if let Some((other, _)) = self.wallets.iter().find(|(_, entry)| entry.address == wallet.address()) {
+            return Err(DexError::Input(format!("{:?} is already stored as '{}'", wallet.address(), other)));
+        }
+
+        fs::create_dir_all(KEYSTORE_DIR)?;
+        let file = format!("{}.json", label);
+        LocalWallet::encrypt_keystore(
+            KEYSTORE_DIR,
+            &mut rand::thread_rng(),
+            wallet.signer().to_bytes(),
+            password,
+            Some(file.as_str()),
+        )
+        .map_err(|e| anyhow!("Failed to write keystore: {}", e))?;
+
+        let keystore = Path::new(KEYSTORE_DIR).join(file).to_string_lossy().into_owned();
+        self.wallets.insert(label, WalletEntry { address: wallet.address(), keystore });
+        Ok(wallet.address())
+    }
+
+    /// Forget `label` and delete its keystore file
+    pub fn remove(&mut self, label: &str) -> Result<WalletEntry> {
+        let entry = self
+            .wallets
+            .remove(&label.to_lowercase())
+            .ok_or_else(|| DexError::Input(format!("Unknown wallet '{}'", label)))?;
+        if Path::new(&entry.keystore).exists() {
+            fs::remove_file(&entry.keystore)
+                .with_context(|| format!("Failed to delete {}", entry.keystore))?;
+        }
+        Ok(entry)
+    }
+
+    /// Decrypt the key stored under `label`
+    pub fn unlock(&self, label: &str, password: &str) -> Result<LocalWallet> {
+        let entry = self
+            .wallets
+            .get(&label.to_lowercase())
+            .ok_or_else(|| DexError::Input(format!("Unknown wallet '{}' (see `monad-dex wallet list`)", label)))?;
+        LocalWallet::decrypt_keystore(&entry.keystore, password)
+            .map_err(|e| DexError::Input(format!("Failed to unlock wallet '{}': {}", label, e)))
+    }
+}
+
+/// Keystore password from `MONAD_WALLET_PASSWORD`, or asked for on the terminal without
+/// echoing it. Piped input is read as the first line of stdin.
+pub fn password(label: &str) -> Result<String> {
+    if let Ok(password) = env::var(PASSWORD_VAR) {
+        return Ok(password);
+    }
+    let prompt = format!("Password for wallet '{}': ", label);
+    if io::stdin().is_terminal() {
+        return Ok(rpassword::prompt_password(prompt)?);
+    }
+    eprint!("{}", prompt);
+    io::stderr().flush()?;
+    let mut password = String::new();
+    io::stdin().lock().read_line(&mut password)?;
+    Ok(password.trim_end_matches(['\r', '\n']).to_string())
+}
+
+/// Decrypt the stored wallet `label`, asking for its password
+pub fn unlock(label: &str) -> Result<LocalWallet> {
+    WalletStore::load()?.unlock(label, &password(label)?)
+}
+
+/// The account commands act as: a key that signs, or with `--cold-address` an address
+/// whose key is kept offline, for which transactions can only be prepared
+#[derive(Debug, Clone)]
+pub enum AccountSigner {
+    Key(LocalWallet),
+    Cold { address: Address, chain_id: u64 },
+}
+
+#[derive(Debug, Error)]
+pub enum AccountSignerError {
+    #[error(transparent)]
+    Wallet(#[from] WalletError),
+    #[error("The key of {0:?} is offline; prepare the transaction with --prepare and sign it with `monad-dex offline sign`")]
+    Cold(Address),
+}
+
+impl AccountSigner {
+    /// Sign a raw 32-byte hash, as Safe transactions and EIP-712 messages are
+    pub fn sign_hash(&self, hash: H256) -> Result<Signature, AccountSignerError> {
+        match self {
+            AccountSigner::Key(wallet) => Ok(wallet.sign_hash(hash)?),
+            AccountSigner::Cold { address, .. } => Err(AccountSignerError::Cold(*address)),
+        }
+    }
+}
+
+impl From<LocalWallet> for AccountSigner {
+    fn from(wallet: LocalWallet) -> Self {
+        AccountSigner::Key(wallet)
+    }
+}
+
+#[async_trait]
+impl Signer for AccountSigner {
+    type Error = AccountSignerError;
+
+    async fn sign_message<S: Send + Sync + AsRef<[u8]>>(&self, message: S) -> Result<Signature, Self::Error> {
+        match self {
+            AccountSigner::Key(wallet) => Ok(wallet.sign_message(message).await?),
+            AccountSigner::Cold { address, .. } => Err(AccountSignerError::Cold(*address)),
+        }
+    }
+
+    async fn sign_transaction(&self, tx: &TypedTransaction) -> Result<Signature, Self::Error> {
+        match self {
+            AccountSigner::Key(wallet) => Ok(wallet.sign_transaction(tx).await?),
+            AccountSigner::Cold { address, .. } => Err(AccountSignerError::Cold(*address)),
+        }
+    }
+
+    async fn sign_typed_data<T: Eip712 + Send + Sync>(&self, payload: &T) -> Result<Signature, Self::Error> {
+        match self {
+            AccountSigner::Key(wallet) => Ok(wallet.sign_typed_data(payload).await?),
+            AccountSigner::Cold { address, .. } => Err(AccountSignerError::Cold(*address)),
+        }
+    }
+
+    fn address(&self) -> Address {
+        match self {
+            AccountSigner::Key(wallet) => wallet.address(),
+            AccountSigner::Cold { address, .. } => *address,
+        }
+    }
+
+    fn chain_id(&self) -> u64 {
+        match self {
+            AccountSigner::Key(wallet) => wallet.chain_id(),
+            AccountSigner::Cold { chain_id, .. } => *chain_id,
+        }
+    }
+
+    fn with_chain_id<T: Into<u64>>(self, chain_id: T) -> Self {
+        match self {
+            AccountSigner::Key(wallet) => AccountSigner::Key(wallet.with_chain_id(chain_id)),
+            AccountSigner::Cold { address, .. } => AccountSigner::Cold { address, chain_id: chain_id.into() },
+        }
+    }
+}
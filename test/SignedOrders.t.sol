@@ -0,0 +1,204 @@
+// SPDX-License-Identifier: MIT
+pragma solidity ^0.8.20;
+
+import {Test} from "forge-std/Test.sol";
+import {OrderBookDEX} from "../contracts/OrderBookDEX.sol";
+import {TestToken} from "../contracts/TestToken.sol";
+
+contract SignedOrdersTest is Test {
+    OrderBookDEX internal dex;
+    TestToken internal base;
+    TestToken internal quote;
+
+    uint256 internal constant BUYER_KEY = 0xB0B;
+    uint256 internal constant SELLER_KEY = 0xA11CE;
+    uint256 internal constant PRECISION = 1e18;
+    uint256 internal constant PRICE = 2e18;
+
+    address internal buyer;
+    address internal seller;
+
+    function setUp() public {
+        buyer = vm.addr(BUYER_KEY);
+        seller = vm.addr(SELLER_KEY);
+
+        dex = new OrderBookDEX();
+        base = new TestToken("Base", "BASE", 18, 0);
+        quote = new TestToken("Quote", "QUOTE", 18, 0);
+        dex.addTradingPair(address(base), address(quote), 1, PRECISION);
+
+        base.mint(seller, 1_000e18);
+        quote.mint(buyer, 1_000e18);
+        vm.prank(seller);
+        base.approve(address(dex), type(uint256).max);
+        vm.prank(buyer);
+        quote.approve(address(dex), type(uint256).max);
+    }
+
+    function _order(uint256 key, bool isBuy, uint256 amount, uint256 price, uint256 nonce)
+        internal
+        view
+        returns (OrderBookDEX.SignedOrder memory order)
+    {
+        order = OrderBookDEX.SignedOrder({
+            maker: vm.addr(key),
+            baseToken: address(base),
+            quoteToken: address(quote),
+            amount: amount,
+            price: price,
+            isBuy: isBuy,
+            nonce: nonce,
+            expiry: block.timestamp + 1 hours,
+            signature: ""
+        });
+        order.signature = _sign(key, order);
+    }
+
+    function _sign(uint256 key, OrderBookDEX.SignedOrder memory order) internal view returns (bytes memory) {
+        bytes32 digest = dex.hashOrder(
+            order.maker,
+            order.baseToken,
+            order.quoteToken,
+            order.amount,
+            order.price,
+            order.isBuy,
+            order.nonce,
+            order.expiry
+        );
+        (uint8 v, bytes32 r, bytes32 s) = vm.sign(key, digest);
+        return abi.encodePacked(r, s, v);
+    }
+
+    function _settle(OrderBookDEX.SignedOrder memory buy, OrderBookDEX.SignedOrder memory sell, uint256 amount)
+        internal
+    {
+        OrderBookDEX.Settlement[] memory settlements = new OrderBookDEX.Settlement[](1);
+        settlements[0] = OrderBookDEX.Settlement({buy: buy, sell: sell, amount: amount, price: PRICE});
+        dex.settleSignedOrders(settlements);
+    }
+
+    function _hash(OrderBookDEX.SignedOrder memory order) internal view returns (bytes32) {
+        return dex.hashOrder(
+            order.maker,
+            order.baseToken,
+            order.quoteToken,
+            order.amount,
+            order.price,
+            order.isBuy,
+            order.nonce,
+            order.expiry
+        );
+    }
+
+    function test_SettleCreditsBothMakersAndKeepsFee() public {
+        OrderBookDEX.SignedOrder memory buy = _order(BUYER_KEY, true, 10e18, PRICE, 1);
+        OrderBookDEX.SignedOrder memory sell = _order(SELLER_KEY, false, 10e18, PRICE, 1);
+
+        _settle(buy, sell, 10e18);
+
+        uint256 fee = (10e18 * dex.tradingFee()) / dex.FEE_DENOMINATOR();
+        uint256 quoteAmount = (10e18 * PRICE) / PRECISION;
+        assertEq(dex.getUserBalance(buyer, address(base)), 10e18 - fee);
+        assertEq(dex.getUserBalance(seller, address(quote)), quoteAmount);
+        assertEq(base.balanceOf(address(dex)), 10e18);
+        assertEq(quote.balanceOf(address(dex)), quoteAmount);
+        assertEq(quote.balanceOf(buyer), 1_000e18 - quoteAmount);
+        assertEq(base.balanceOf(seller), 1_000e18 - 10e18);
+    }
+
+    function test_FeeFollowsTradingFee() public {
+        dex.setTradingFee(100);
+        _settle(_order(BUYER_KEY, true, 10e18, PRICE, 1), _order(SELLER_KEY, false, 10e18, PRICE, 1), 10e18);
+        assertEq(dex.getUserBalance(buyer, address(base)), 10e18 - 10e18 / 100);
+    }
+
+    function test_PartialFillsUpToOrderAmount() public {
+        OrderBookDEX.SignedOrder memory buy = _order(BUYER_KEY, true, 10e18, PRICE, 1);
+        OrderBookDEX.SignedOrder memory sell = _order(SELLER_KEY, false, 10e18, PRICE, 1);
+
+        _settle(buy, sell, 4e18);
+        assertEq(dex.signedOrderFilled(_hash(buy)), 4e18);
+        assertEq(dex.signedOrderFilled(_hash(sell)), 4e18);
+
+        _settle(buy, sell, 6e18);
+        assertEq(dex.signedOrderFilled(_hash(buy)), 10e18);
+
+        vm.expectRevert("Order overfilled");
+        _settle(buy, sell, 1);
+    }
+
+    function test_ReplayOfFilledOrderReverts() public {
+        OrderBookDEX.SignedOrder memory buy = _order(BUYER_KEY, true, 10e18, PRICE, 1);
+        OrderBookDEX.SignedOrder memory sell = _order(SELLER_KEY, false, 10e18, PRICE, 1);
+        _settle(buy, sell, 10e18);
+
+        vm.expectRevert("Order overfilled");
+        _settle(buy, sell, 10e18);
+    }
+
+    function test_RejectsSignatureFromAnotherKey() public {
+        OrderBookDEX.SignedOrder memory buy = _order(BUYER_KEY, true, 10e18, PRICE, 1);
+        OrderBookDEX.SignedOrder memory sell = _order(SELLER_KEY, false, 10e18, PRICE, 1);
+        // The seller signs an order naming the buyer as maker
+        buy.signature = _sign(SELLER_KEY, buy);
+
+        vm.expectRevert("Invalid signature");
+        _settle(buy, sell, 10e18);
+    }
+
+    function test_RejectsTamperedOrder() public {
+        OrderBookDEX.SignedOrder memory buy = _order(BUYER_KEY, true, 10e18, PRICE, 1);
+        OrderBookDEX.SignedOrder memory sell = _order(SELLER_KEY, false, 10e18, PRICE, 1);
+        buy.amount = 100e18;
+
+        vm.expectRevert("Invalid signature");
+        _settle(buy, sell, 10e18);
+    }
+
+    function test_RejectsExpiredOrder() public {
+        OrderBookDEX.SignedOrder memory buy = _order(BUYER_KEY, true, 10e18, PRICE, 1);
+        OrderBookDEX.SignedOrder memory sell = _order(SELLER_KEY, false, 10e18, PRICE, 1);
+        vm.warp(buy.expiry);
+
+        vm.expectRevert("Order expired");
+        _settle(buy, sell, 10e18);
+    }
+
+    function test_RejectsPriceOutsideLimits() public {
+        OrderBookDEX.SignedOrder memory buy = _order(BUYER_KEY, true, 10e18, PRICE - 1, 1);
+        OrderBookDEX.SignedOrder memory sell = _order(SELLER_KEY, false, 10e18, PRICE, 1);
+
+        vm.expectRevert("Price outside limits");
+        _settle(buy, sell, 10e18);
+    }
+
+    function test_CancelStopsFurtherSettlement() public {
+        OrderBookDEX.SignedOrder memory buy = _order(BUYER_KEY, true, 10e18, PRICE, 1);
+        OrderBookDEX.SignedOrder memory sell = _order(SELLER_KEY, false, 10e18, PRICE, 1);
+        _settle(buy, sell, 4e18);
+
+        vm.prank(buyer);
+        dex.cancelSignedOrder(buy);
+        assertEq(dex.signedOrderFilled(_hash(buy)), 10e18);
+
+        vm.expectRevert("Order overfilled");
+        _settle(buy, sell, 1);
+    }
+
+    function test_OnlyMakerCanCancel() public {
+        OrderBookDEX.SignedOrder memory buy = _order(BUYER_KEY, true, 10e18, PRICE, 1);
+
+        vm.prank(seller);
+        vm.expectRevert("Not order maker");
+        dex.cancelSignedOrder(buy);
+    }
+
+    function test_NewNonceIsIndependentOfCancelledOrder() public {
+        OrderBookDEX.SignedOrder memory buy = _order(BUYER_KEY, true, 10e18, PRICE, 1);
+        vm.prank(buyer);
+        dex.cancelSignedOrder(buy);
+
+        _settle(_order(BUYER_KEY, true, 10e18, PRICE, 2), _order(SELLER_KEY, false, 10e18, PRICE, 1), 10e18);
+        assertEq(dex.getUserBalance(seller, address(quote)), (10e18 * PRICE) / PRECISION);
+    }
+}
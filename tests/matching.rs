@@ -0,0 +1,159 @@
+//! Property tests checking the local matching engine against the contract: random order
+//! flow is replayed on both, and every match and every resting order must agree. Ignored
+//! by default since it needs anvil or a dev node and the built contracts; run it with
+//! `cargo test -- --ignored`.
+
+use anyhow::Result;
+use ethers::{
+    contract::parse_log,
+    types::{TransactionReceipt, U256},
+};
+use monad_app::bindings::OrderMatchedFilter;
+use monad_app::matching::{BookOrder, Match, OrderBook, TRADING_FEE};
+use monad_app::testing::TestEnv;
+use rand::{rngs::StdRng, seq::SliceRandom, Rng, SeedableRng};
+
+/// Operations replayed per seed
+const STEPS: usize = 30;
+
+const SEEDS: &[u64] = &[1, 7, 42];
+
+/// `OrderMatched` events in a receipt, in the shape the local book reports them
+fn matches(receipt: &TransactionReceipt) -> Vec<(u64, u64, U256, U256)> {
+    receipt
+        .logs
+        .iter()
+        .filter_map(|log| parse_log::<OrderMatchedFilter>(log.clone()).ok())
+        .map(|event| {
+            (
+                event.buy_order_id.as_u64(),
+                event.sell_order_id.as_u64(),
+                event.amount,
+                event.price,
+            )
+        })
+        .collect()
+}
+
+fn local_matches(matched: Option<Match>) -> Vec<(u64, u64, U256, U256)> {
+    matched
+        .map(|m| (m.buy_order_id, m.sell_order_id, m.base_amount, m.price))
+        .into_iter()
+        .collect()
+}
+
+/// Active orders on the contract, as `(id, amount, price, is_buy)`
+async fn chain_orders(env: &TestEnv, next_id: u64) -> Result<Vec<(u64, U256, U256, bool)>> {
+    let mut active = Vec::new();
+    for id in 0..next_id {
+        let (_, _, _, _, amount, price, is_buy, is_active, _) =
+            env.dex.orders(U256::from(id)).call().await?;
+        if is_active {
+            active.push((id, amount, price, is_buy));
+        }
+    }
+    Ok(active)
+}
+
+fn local_orders(book: &OrderBook) -> Vec<(u64, U256, U256, bool)> {
+    let mut orders: Vec<_> = book
+        .orders()
+        .iter()
+        .map(|order| (order.id, order.amount, order.price, order.is_buy))
+        .collect();
+    orders.sort_by_key(|order| order.0);
+    orders
+}
+
+/// Replay `STEPS` random limit orders, cancels and market orders from `seed` on a fresh
+/// chain and on the local book, comparing them after every step
+async fn replay(seed: u64) -> Result<()> {
+    let env = TestEnv::start().await?;
+    let price_precision = U256::exp10(18);
+    env.add_pair(env.units(1), price_precision).await?;
+    assert_eq!(
+        env.dex.trading_fee().call().await?,
+        U256::from(TRADING_FEE),
+        "the local book assumes the default trading fee"
+    );
+
+    let traders = &env.traders[..env.traders.len().min(3)];
+    let mut book = OrderBook::new(price_precision);
+    let mut next_id = 0;
+    let mut rng = StdRng::seed_from_u64(seed);
+
+    for step in 0..STEPS {
+        let trader = traders.choose(&mut rng).unwrap();
+        let is_buy = rng.gen_bool(0.5);
+        let amount = env.units(rng.gen_range(1..=20));
+        let roll = rng.gen_range(0..100);
+
+        let own: Vec<BookOrder> = book
+            .orders()
+            .iter()
+            .filter(|order| order.owner == Some(trader.address()))
+            .cloned()
+            .collect();
+        if roll < 15 && !own.is_empty() {
+            let order = own.choose(&mut rng).unwrap();
+            env.send(
+                trader,
+                env.dex_as(trader).cancel_order(U256::from(order.id)),
+            )
+            .await?;
+            book.cancel(order.id);
+        } else if roll < 30 {
+            // Approve what the local book says the order costs; a wrong estimate fails on chain
+            let quote = book.quote_market(amount, is_buy);
+            let deposit = if is_buy { quote.quote_amount } else { amount };
+            let on_chain = env
+                .place_market_order(trader, amount, is_buy, deposit)
+                .await;
+            let local = book.place_market(amount, is_buy);
+            assert_eq!(
+                on_chain.is_ok(),
+                local.is_ok(),
+                "seed {} step {}: market {} of {} went through on chain: {:?}, locally: {:?}",
+                seed,
+                step,
+                if is_buy { "buy" } else { "sell" },
+                amount,
+                on_chain.as_ref().map(|_| ()),
+                local.as_ref().map(|_| ())
+            );
+        } else {
+            // Prices straddle 1.0 so orders cross often
+            let price = U256::from(rng.gen_range(90u64..=110)) * U256::exp10(16);
+            let receipt = env
+                .place_limit_order_receipt(trader, amount, price, is_buy)
+                .await?;
+            let (_, matched) = book.place_limit(Some(trader.address()), amount, price, is_buy);
+            next_id += 1;
+            assert_eq!(
+                matches(&receipt),
+                local_matches(matched),
+                "seed {} step {}: matches differ",
+                seed,
+                step
+            );
+        }
+
+        assert_eq!(
+            chain_orders(&env, next_id).await?,
+            local_orders(&book),
+            "seed {} step {}: resting orders differ",
+            seed,
+            step
+        );
+    }
+    Ok(())
+}
+
+#[tokio::test]
+#[ignore = "end-to-end: needs anvil or MONAD_TEST_RPC_URL and built contracts"]
+async fn local_matching_agrees_with_the_contract() -> Result<()> {
+    for seed in SEEDS {
+        replay(*seed).await?;
+    }
+    Ok(())
+}
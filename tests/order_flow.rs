@@ -0,0 +1,142 @@
+//! End-to-end order flows against a local chain. Ignored by default since they need anvil
+//! or a dev node and the built contracts; run them with `cargo test -- --ignored`.
+
+use anyhow::Result;
+use ethers::types::U256;
+use monad_app::orderbook::{BookPages, PageQuery};
+use monad_app::testing::TestEnv;
+
+/// Start a chain with the base/quote pair listed at 18-decimal price precision
+async fn env_with_pair() -> Result<TestEnv> {
+    let env = TestEnv::start().await?;
+    env.add_pair(env.units(1), U256::exp10(18)).await?;
+    Ok(env)
+}
+
+#[tokio::test]
+#[ignore = "end-to-end: needs anvil or MONAD_TEST_RPC_URL and built contracts"]
+async fn crossing_orders_match_and_charge_the_fee() -> Result<()> {
+    let env = env_with_pair().await?;
+    let (buyer, seller) = (&env.traders[0], &env.traders[1]);
+    let amount = env.units(10);
+    let price = U256::from(2) * U256::exp10(18);
+
+    let buy_id = env.place_limit_order(buyer, amount, price, true).await?;
+    let sell_id = env.place_limit_order(seller, amount, price, false).await?;
+
+    let fee =
+        amount * env.dex.trading_fee().call().await? / env.dex.fee_denominator().call().await?;
+    let bought = env
+        .dex
+        .get_user_balance(buyer.address(), env.base_token)
+        .call()
+        .await?;
+    assert_eq!(bought, amount - fee);
+    let proceeds = env
+        .dex
+        .get_user_balance(seller.address(), env.quote_token)
+        .call()
+        .await?;
+    assert_eq!(proceeds, (amount - fee) * price / U256::exp10(18));
+
+    for id in [buy_id, sell_id] {
+        let (_, _, _, _, remaining, _, _, is_active, _) = env.dex.orders(id).call().await?;
+        assert!(!is_active, "order {} should be filled", id);
+        assert!(remaining.is_zero());
+    }
+    Ok(())
+}
+
+#[tokio::test]
+#[ignore = "end-to-end: needs anvil or MONAD_TEST_RPC_URL and built contracts"]
+async fn cancelling_a_buy_refunds_the_quote_deposit() -> Result<()> {
+    let env = env_with_pair().await?;
+    let buyer = &env.traders[0];
+    let before = env.balance_of(env.quote_token, buyer.address()).await?;
+
+    let order_id = env
+        .place_limit_order(buyer, env.units(5), U256::from(3) * U256::exp10(18), true)
+        .await?;
+    assert_eq!(
+        before - env.balance_of(env.quote_token, buyer.address()).await?,
+        env.units(15)
+    );
+
+    env.send(buyer, env.dex_as(buyer).cancel_order(order_id))
+        .await?;
+    assert_eq!(
+        env.balance_of(env.quote_token, buyer.address()).await?,
+        before
+    );
+    let (_, _, _, _, _, _, _, is_active, _) = env.dex.orders(order_id).call().await?;
+    assert!(!is_active);
+    Ok(())
+}
+
+#[tokio::test]
+#[ignore = "end-to-end: needs anvil or MONAD_TEST_RPC_URL and built contracts"]
+async fn withdraw_moves_matched_funds_to_the_wallet() -> Result<()> {
+    let env = env_with_pair().await?;
+    let (buyer, seller) = (&env.traders[0], &env.traders[1]);
+    let amount = env.units(4);
+    let price = U256::exp10(18);
+
+    env.place_limit_order(buyer, amount, price, true).await?;
+    env.place_limit_order(seller, amount, price, false).await?;
+
+    let dex_balance = env
+        .dex
+        .get_user_balance(buyer.address(), env.base_token)
+        .call()
+        .await?;
+    assert!(!dex_balance.is_zero());
+    let wallet_before = env.balance_of(env.base_token, buyer.address()).await?;
+
+    env.send(
+        buyer,
+        env.dex_as(buyer).withdraw(env.base_token, dex_balance),
+    )
+    .await?;
+    assert_eq!(
+        env.balance_of(env.base_token, buyer.address()).await?,
+        wallet_before + dex_balance
+    );
+    assert!(env
+        .dex
+        .get_user_balance(buyer.address(), env.base_token)
+        .call()
+        .await?
+        .is_zero());
+    Ok(())
+}
+
+#[tokio::test]
+#[ignore = "end-to-end: needs anvil or MONAD_TEST_RPC_URL and built contracts"]
+async fn paged_book_reads_every_order_in_the_price_window() -> Result<()> {
+    let env = env_with_pair().await?;
+    let (buyer, seller) = (&env.traders[0], &env.traders[1]);
+    for price in 1..=3u64 {
+        env.place_limit_order(buyer, env.units(1), U256::from(price) * U256::exp10(18), true)
+            .await?;
+        env.place_limit_order(seller, env.units(1), U256::from(price + 10) * U256::exp10(18), false)
+            .await?;
+    }
+
+    let query = PageQuery {
+        page_size: 1,
+        scan_size: 2,
+        max_price: Some(U256::from(11) * U256::exp10(18)),
+        ..Default::default()
+    };
+    let mut pages = BookPages::new(env.dex.clone(), env.base_token, env.quote_token, query);
+    let mut orders = Vec::new();
+    while let Some(page) = pages.next_page().await? {
+        assert_eq!(page.len(), 1);
+        orders.extend(page);
+    }
+
+    assert_eq!(orders.len(), 4);
+    assert_eq!(orders.iter().filter(|order| order.is_buy()).count(), 3);
+    assert!(orders.windows(2).all(|pair| pair[0].id < pair[1].id));
+    Ok(())
+}